@@ -1,11 +1,13 @@
 mod balance;
 mod nonce;
+mod multisig;
 
 use std::borrow::Cow;
 use std::fmt::{self, Display, Formatter};
 
 pub use balance::{VersionedBalance, BalanceType};
 pub use nonce::VersionedNonce;
+pub use multisig::VersionedMultiSig;
 use serde::{Serialize, Deserialize};
 use crate::crypto::elgamal::{Ciphertext, CompressedCiphertext, DecompressionError, RISTRETTO_COMPRESSED_SIZE};
 
@@ -0,0 +1,78 @@
+use std::fmt::{self, Display, Formatter};
+use serde::{Deserialize, Serialize};
+use crate::{
+    serializer::{
+        Reader,
+        ReaderError,
+        Serializer,
+        Writer
+    },
+    transaction::multisig::MultiSigPayload
+};
+
+// Multisig setup of an account at a topoheight
+// None means the account is not (or no longer) a multisig
+#[derive(Clone, Serialize, Deserialize)]
+pub struct VersionedMultiSig {
+    state: Option<MultiSigPayload>,
+    previous_topoheight: Option<u64>,
+}
+
+impl VersionedMultiSig {
+    pub fn new(state: Option<MultiSigPayload>, previous_topoheight: Option<u64>) -> Self {
+        Self {
+            state,
+            previous_topoheight
+        }
+    }
+
+    pub fn get_state(&self) -> Option<&MultiSigPayload> {
+        self.state.as_ref()
+    }
+
+    pub fn set_state(&mut self, state: Option<MultiSigPayload>) {
+        self.state = state;
+    }
+
+    pub fn take_state(self) -> Option<MultiSigPayload> {
+        self.state
+    }
+
+    pub fn get_previous_topoheight(&self) -> Option<u64> {
+        self.previous_topoheight
+    }
+
+    pub fn set_previous_topoheight(&mut self, previous_topoheight: Option<u64>) {
+        self.previous_topoheight = previous_topoheight;
+    }
+}
+
+impl Serializer for VersionedMultiSig {
+    fn write(&self, writer: &mut Writer) {
+        self.previous_topoheight.write(writer);
+        self.state.write(writer);
+    }
+
+    fn read(reader: &mut Reader) -> Result<Self, ReaderError> {
+        let previous_topoheight = Option::read(reader)?;
+        let state = Option::read(reader)?;
+
+        Ok(Self {
+            state,
+            previous_topoheight
+        })
+    }
+
+    fn size(&self) -> usize {
+        self.previous_topoheight.size() + self.state.size()
+    }
+}
+
+impl Display for VersionedMultiSig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.state {
+            Some(state) => write!(f, "MultiSig[{}/{}, previous: {:?}]", state.threshold, state.participants.len(), self.previous_topoheight),
+            None => write!(f, "MultiSig[none, previous: {:?}]", self.previous_topoheight)
+        }
+    }
+}
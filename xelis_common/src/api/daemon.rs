@@ -13,7 +13,7 @@ use crate::{
     network::Network,
     time::{TimestampMillis, TimestampSeconds}
 };
-use super::RPCTransaction;
+use super::{RPCMultiSigPayload, RPCTransaction};
 
 #[derive(Serialize, Deserialize, PartialEq, Eq)]
 pub enum BlockType {
@@ -110,6 +110,14 @@ pub struct SubmitBlockParams {
     pub block_template: String, // hex: represent the BlockHeader (Block)
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct GenerateBlocksParams<'a> {
+    // Number of blocks to generate
+    pub amount: u64,
+    // Miner address for rewards
+    pub address: Cow<'a, Address>
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct GetBalanceParams<'a> {
     pub address: Cow<'a, Address>,
@@ -167,6 +175,19 @@ pub struct HasNonceResult {
     pub exist: bool
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct GetMultiSigParams<'a> {
+    pub address: Cow<'a, Address>
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GetMultiSigResult {
+    // Topoheight of the last change of the setup
+    pub topoheight: u64,
+    // None if the multisig was removed
+    pub state: Option<RPCMultiSigPayload>
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct GetBalanceResult {
     pub version: VersionedBalance,
@@ -199,6 +220,58 @@ pub struct GetInfoResult {
     pub network: Network
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct GetSupplyParams {
+    // Topoheight at which the supply is requested, top topoheight by default
+    #[serde(default)]
+    pub topoheight: Option<u64>
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GetSupplyResult {
+    pub topoheight: u64,
+    // XELIS emitted through block rewards
+    pub emitted_supply: u64,
+    // XELIS burned through transactions
+    pub burned_supply: u64,
+    // Emitted supply minus the burned supply
+    pub circulating_supply: u64,
+    // Maximum supply of XELIS
+    pub maximum_supply: u64
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GetAssetSupplyParams<'a> {
+    pub asset: Cow<'a, Hash>,
+    #[serde(default)]
+    pub topoheight: Option<u64>
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GetAssetSupplyResult<'a> {
+    pub asset: Cow<'a, Hash>,
+    pub topoheight: u64,
+    // Only XELIS is emitted by the protocol
+    pub emitted_supply: Option<u64>,
+    pub burned_supply: u64
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GetEmissionAtHeightParams {
+    pub height: u64
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GetEmissionAtHeightResult {
+    pub height: u64,
+    // Supply once the block at this height is added
+    pub supply: u64,
+    // Reward of the block at this height
+    pub block_reward: u64,
+    // Heights above the chain are projected using one block per height
+    pub projected: bool
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct SubmitTransactionParams {
     pub data: String // should be in hex format
@@ -327,6 +400,13 @@ pub struct GetAccountHistoryParams {
     pub maximum_topoheight: Option<u64>
 }
 
+// Used by light clients to retrieve only their own transactions of a block
+#[derive(Serialize, Deserialize)]
+pub struct GetAccountTransactionsAtTopoHeightParams<'a> {
+    pub address: Cow<'a, Address>,
+    pub topoheight: u64
+}
+
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")] 
 pub enum AccountHistoryType {
@@ -406,6 +486,73 @@ pub struct SizeOnDiskResult {
     pub size_formatted: String
 }
 
+#[derive(Serialize, Deserialize, Default)]
+pub struct GetFilteredMempoolParams<'a> {
+    pub skip: Option<usize>,
+    pub maximum: Option<usize>,
+    // Only the transactions sent by this address
+    pub sender: Option<Cow<'a, Address>>,
+    // Only the transactions transferring or burning this asset
+    pub asset: Option<Cow<'a, Hash>>,
+    // Only returns the summary of the transactions matching the filters
+    #[serde(default)]
+    pub summary: bool
+}
+
+// State of the mempool after a block was added
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MempoolHistoryEntry {
+    pub timestamp: TimestampSeconds,
+    pub height: u64,
+    pub count: usize,
+    pub total_size: usize,
+    pub total_fees: u64
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct GetMempoolStatsParams {
+    // How many blocks from the top topoheight are used to compute the median fees
+    pub blocks: Option<u64>
+}
+
+// Mempool TXs paying at least this fee per KB, and less than the next bucket
+#[derive(Serialize, Deserialize)]
+pub struct FeeHistogramBucket {
+    pub min_fee_per_kb: u64,
+    pub count: usize,
+    pub total_size: usize
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GetMempoolStatsResult {
+    // Mempool state for each of the last blocks added, from the oldest
+    pub history: Vec<MempoolHistoryEntry>,
+    // Current mempool TXs grouped by fee per KB
+    pub fee_histogram: Vec<FeeHistogramBucket>,
+    // Blocks used to compute the median fees
+    pub blocks: u64,
+    // TXs included in these blocks
+    pub transactions: usize,
+    // None if no TX was included in these blocks
+    pub median_fee: Option<u64>,
+    pub median_fee_per_kb: Option<u64>
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GetFilteredMempoolResult<'a> {
+    // Count of transactions matching the filters
+    pub count: usize,
+    // Sum of their fees
+    pub total_fees: u64,
+    // Sum of their sizes in bytes
+    pub total_size: usize,
+    // Requested page of transactions, ordered by first seen
+    // Not present in summary mode
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub transactions: Option<Vec<TransactionResponse<'a>>>
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct GetMempoolCacheParams<'a> {
     pub address: Cow<'a, Address>
@@ -423,6 +570,24 @@ pub struct GetMempoolCacheResult {
     balances: HashMap<Hash, CiphertextCache>
 }
 
+impl GetMempoolCacheResult {
+    pub fn get_min(&self) -> u64 {
+        self.min
+    }
+
+    pub fn get_max(&self) -> u64 {
+        self.max
+    }
+
+    pub fn get_txs(&self) -> &Vec<Hash> {
+        &self.txs
+    }
+
+    pub fn get_balances(&self) -> &HashMap<Hash, CiphertextCache> {
+        &self.balances
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum NotifyEvent {
@@ -438,9 +603,18 @@ pub enum NotifyEvent {
     // When stable height has changed (different than the previous one)
     // it contains StableHeightChangedEvent struct as value
     StableHeightChanged,
+    // When already ordered blocks got reordered or orphaned by a new block
+    // it contains ChainReorganizedEvent struct as value
+    ChainReorganized,
     // When a transaction that was executed in a block is not reintroduced in mempool
     // It contains TransactionOrphanedEvent as value
     TransactionOrphaned,
+    // When a mempool transaction got deleted because it conflicts with a transaction executed in a block
+    // It contains TransactionConflictedEvent as value
+    TransactionConflicted,
+    // When a transaction got deleted from mempool because it was not included in a block in time
+    // It contains TransactionExpiredEvent as value
+    TransactionExpired,
     // When a new transaction is added in mempool
     // it contains TransactionAddedInMempoolEvent struct as value
     TransactionAddedInMempool,
@@ -492,6 +666,23 @@ pub struct BlockOrphanedEvent<'a> {
     pub old_topoheight: u64
 }
 
+// Value of NotifyEvent::ChainReorganized
+#[derive(Serialize, Deserialize)]
+pub struct ChainReorganizedEvent<'a> {
+    // Block that triggered the reorg
+    pub block_hash: Cow<'a, Hash>,
+    // First topoheight reverted, everything executed from it was executed again
+    pub from_topoheight: u64,
+    // Topoheight of the chain before the reorg
+    pub previous_topoheight: u64,
+    // Topoheight of the chain after the reorg
+    pub new_topoheight: u64,
+    // Blocks that are not ordered anymore
+    pub orphaned_blocks: Vec<Cow<'a, Hash>>,
+    // Transactions that are not executed anymore
+    pub orphaned_transactions: Vec<Cow<'a, Hash>>
+}
+
 // Value of NotifyEvent::StableHeightChanged
 #[derive(Serialize, Deserialize)]
 pub struct StableHeightChangedEvent {
@@ -504,6 +695,27 @@ pub type TransactionAddedInMempoolEvent = TransactionResponse<'static>;
 // Value of NotifyEvent::TransactionOrphaned
 pub type TransactionOrphanedEvent = TransactionResponse<'static>;
 
+// Value of NotifyEvent::TransactionExpired
+pub type TransactionExpiredEvent = TransactionResponse<'static>;
+
+// Why a mempool transaction lost against the transactions executed in a block
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransactionConflictReason {
+    // Its nonce has been used by another transaction of the same source
+    Nonce,
+    // It is not valid anymore against the new balances of its source
+    // or a previous transaction of the same source was invalid
+    Balance
+}
+
+// Value of NotifyEvent::TransactionConflicted
+#[derive(Serialize, Deserialize)]
+pub struct TransactionConflictedEvent<'a> {
+    pub reason: TransactionConflictReason,
+    #[serde(flatten)]
+    pub transaction: TransactionResponse<'a>
+}
+
 // Value of NotifyEvent::TransactionExecuted
 #[derive(Serialize, Deserialize)]
 pub struct TransactionExecutedEvent<'a> {
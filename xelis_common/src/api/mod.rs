@@ -15,8 +15,10 @@ use crate::{
         Hash,
         Signature
     },
+    network::Network,
     transaction::{
         aead::AEADCipher,
+        multisig::{MultiSig, MultiSigPayload},
         BurnPayload,
         Reference,
         SourceCommitment,
@@ -76,17 +78,43 @@ impl<'a> From<RPCTransferPayload<'a>> for TransferPayload {
 pub enum RPCTransactionType<'a> {
     Transfers(Vec<RPCTransferPayload<'a>>),
     Burn(Cow<'a, BurnPayload>),
+    MultiSig(RPCMultiSigPayload),
+}
+
+// Multisig setup with the participants displayed as addresses
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RPCMultiSigPayload {
+    pub threshold: u8,
+    pub participants: Vec<Address>
+}
+
+impl RPCMultiSigPayload {
+    pub fn from_payload(payload: &MultiSigPayload, network: Network) -> Self {
+        Self {
+            threshold: payload.threshold,
+            participants: payload.participants.iter().map(|key| key.as_address(network)).collect()
+        }
+    }
+}
+
+impl From<RPCMultiSigPayload> for MultiSigPayload {
+    fn from(payload: RPCMultiSigPayload) -> Self {
+        MultiSigPayload::new(
+            payload.threshold,
+            payload.participants.into_iter().map(|address| address.to_public_key()).collect()
+        )
+    }
 }
 
 impl<'a> RPCTransactionType<'a> {
-    pub fn from_type(data: &'a TransactionType, mainnet: bool) -> Self {
+    pub fn from_type(data: &'a TransactionType, network: Network) -> Self {
         match data {
             TransactionType::Transfers(transfers) => {
                 let mut rpc_transfers = Vec::new();
                 for transfer in transfers {
                     rpc_transfers.push(RPCTransferPayload {
                         asset: Cow::Borrowed(transfer.get_asset()),
-                        destination: transfer.get_destination().as_address(mainnet),
+                        destination: transfer.get_destination().as_address(network),
                         extra_data: Cow::Borrowed(transfer.get_extra_data()),
                         commitment: Cow::Borrowed(transfer.get_commitment()),
                         sender_handle: Cow::Borrowed(transfer.get_sender_handle()),
@@ -96,7 +124,8 @@ impl<'a> RPCTransactionType<'a> {
                 }
                 Self::Transfers(rpc_transfers)
             },
-            TransactionType::Burn(burn) => Self::Burn(Cow::Borrowed(burn))
+            TransactionType::Burn(burn) => Self::Burn(Cow::Borrowed(burn)),
+            TransactionType::MultiSig(payload) => Self::MultiSig(RPCMultiSigPayload::from_payload(payload, network))
         }
     }
 }
@@ -107,7 +136,8 @@ impl From<RPCTransactionType<'_>> for TransactionType {
             RPCTransactionType::Transfers(transfers) => {
                 TransactionType::Transfers(transfers.into_iter().map(|transfer| transfer.into()).collect::<Vec<TransferPayload>>())
             },
-            RPCTransactionType::Burn(burn) => TransactionType::Burn(burn.into_owned())
+            RPCTransactionType::Burn(burn) => TransactionType::Burn(burn.into_owned()),
+            RPCTransactionType::MultiSig(payload) => TransactionType::MultiSig(payload.into())
         }
     }
 }
@@ -136,22 +166,26 @@ pub struct RPCTransaction<'a> {
     pub range_proof: Cow<'a, RangeProof>,
     /// Reference at which block the transaction was built
     pub reference: Cow<'a, Reference>,
+    /// Signatures of the multisig participants
+    #[serde(default)]
+    pub multisig: Option<Cow<'a, MultiSig>>,
     /// Signature of the transaction
     pub signature: Cow<'a, Signature>,
 }
 
 impl<'a> RPCTransaction<'a> {
-    pub fn from_tx(tx: &'a Transaction, hash: &'a Hash, mainnet: bool) -> Self {
+    pub fn from_tx(tx: &'a Transaction, hash: &'a Hash, network: Network) -> Self {
         Self {
             hash: Cow::Borrowed(hash),
             version: tx.get_version(),
-            source: tx.get_source().as_address(mainnet),
-            data: RPCTransactionType::from_type(tx.get_data(), mainnet),
+            source: tx.get_source().as_address(network),
+            data: RPCTransactionType::from_type(tx.get_data(), network),
             fee: tx.get_fee(),
             nonce: tx.get_nonce(),
             source_commitments: Cow::Borrowed(tx.get_source_commitments()),
             range_proof: Cow::Borrowed(tx.get_range_proof()),
             reference: Cow::Borrowed(tx.get_reference()),
+            multisig: tx.get_multisig().as_ref().map(Cow::Borrowed),
             signature: Cow::Borrowed(tx.get_signature()),
         }
     }
@@ -160,6 +194,7 @@ impl<'a> RPCTransaction<'a> {
 impl<'a> From<RPCTransaction<'a>> for Transaction {
     fn from(tx: RPCTransaction<'a>) -> Self {
         Transaction::new(
+            tx.version,
             tx.source.to_public_key(),
             tx.data.into(),
             tx.fee,
@@ -167,6 +202,7 @@ impl<'a> From<RPCTransaction<'a>> for Transaction {
             tx.source_commitments.into_owned(),
             tx.range_proof.into_owned(),
             tx.reference.into_owned(),
+            tx.multisig.map(Cow::into_owned),
             tx.signature.into_owned()
         )
     }
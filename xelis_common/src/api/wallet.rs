@@ -1,13 +1,13 @@
 use std::borrow::Cow;
 use serde::{Deserialize, Serialize};
 use crate::{
-    crypto::{Address, Hash},
+    crypto::{Address, Hash, Signature},
     transaction::{
         builder::{FeeBuilder, TransactionTypeBuilder},
         Transaction
     }
 };
-use super::{DataHash, DataElement, DataValue, query::Query};
+use super::{daemon::TransactionConflictReason, DataHash, DataElement, DataValue, query::Query};
 
 #[derive(Serialize, Deserialize)]
 pub struct BuildTransactionParams {
@@ -18,7 +18,21 @@ pub struct BuildTransactionParams {
     pub broadcast: bool,
     // Returns the TX in HEX format also
     #[serde(default = "default_false_value")]
-    pub tx_as_hex: bool
+    pub tx_as_hex: bool,
+    // Override the nonce to use, to replace a stuck transaction for example
+    // Only the next nonce or the one of the only pending transaction are accepted
+    #[serde(default)]
+    pub nonce: Option<u64>
+}
+
+// Same as BuildTransactionParams but always broadcasted
+#[derive(Serialize, Deserialize)]
+pub struct SendTransactionParams {
+    #[serde(flatten)]
+    pub tx_type: TransactionTypeBuilder,
+    pub fee: Option<FeeBuilder>,
+    #[serde(default)]
+    pub nonce: Option<u64>
 }
 
 #[derive(Serialize, Deserialize)]
@@ -108,12 +122,84 @@ pub struct GetTransactionParams {
     pub hash: Hash
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct VerifyDataParams {
+    // Data which was signed
+    pub data: DataElement,
+    pub signature: Signature,
+    // Address of the signer
+    pub address: Address
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct BalanceChanged {
     pub asset: Hash,
     pub balance: u64
 }
 
+// Why a transaction sent by the wallet will never be executed
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransactionFailedReason {
+    // It was executed in a block reverted by a reorg and couldn't be added back to the mempool
+    Orphaned,
+    // Its nonce has been used by another transaction of the wallet
+    Nonce,
+    // It is not valid anymore against the new balances of the wallet
+    Balance
+}
+
+impl From<TransactionConflictReason> for TransactionFailedReason {
+    fn from(reason: TransactionConflictReason) -> Self {
+        match reason {
+            TransactionConflictReason::Nonce => Self::Nonce,
+            TransactionConflictReason::Balance => Self::Balance
+        }
+    }
+}
+
+// A transaction sent by the wallet that got deleted from the mempool without being executed
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TransactionFailed {
+    pub hash: Hash,
+    pub nonce: u64,
+    pub reason: TransactionFailedReason
+}
+
+// Block scanned by the wallet during a sync
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BlockScanned {
+    pub block_hash: Hash,
+    pub topoheight: u64
+}
+
+// A transfer received in a confirmed transaction
+// A transaction with several transfers for us gives one IncomingTransfer per transfer
+#[derive(Serialize, Deserialize, Clone)]
+pub struct IncomingTransfer {
+    pub hash: Hash,
+    pub topoheight: u64,
+    pub from: Address,
+    pub asset: Hash,
+    pub amount: u64,
+    pub extra_data: Option<DataElement>,
+    // Index of the subaddress that received it, None for the main address
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subaddress: Option<u32>
+}
+
+// Progress of the blocks scan done by the wallet during a sync
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SyncProgress {
+    // Topoheights scanned until now
+    pub current: u64,
+    // Topoheights to scan in total
+    pub target: u64,
+    // Average blocks fetched per second since the start
+    pub blocks_per_second: f64,
+    // Estimated remaining time in seconds
+    pub eta: Option<u64>
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct GetValueFromKeyParams {
     pub tree: String,
@@ -173,10 +259,24 @@ pub enum NotifyEvent {
     // When a rescan happened on the wallet
     // Contains a topoheight as value to indicate until which topoheight transactions got deleted
     Rescan,
+    // When the wallet is scanning blocks
+    // Contains a SyncProgress as value
+    SyncProgress,
+    // When a block has been scanned by the wallet
+    // Contains a BlockScanned as value
+    NewBlockScanned,
+    // When a transfer is received in a new transaction
+    // Contains an IncomingTransfer as value
+    IncomingTransfer,
+    // When a transaction sent by the wallet will never be executed
+    // Contains a TransactionFailed as value
+    TransactionFailed,
     // When network state changed
     Online,
     // Same here
     Offline,
+    // When the connection with the daemon is lost without being requested
+    Disconnected,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -198,7 +298,10 @@ pub struct TransferIn {
     // Plaintext amount
     pub amount: u64,
     // extra data
-    pub extra_data: Option<DataElement>
+    pub extra_data: Option<DataElement>,
+    // Index of the subaddress that received it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subaddress: Option<u32>
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -17,7 +17,7 @@ use crate::{
     time::TimestampMillis
 };
 use xelis_hash::Error as XelisHashError;
-use super::EXTRA_NONCE_SIZE;
+use super::{EXTRA_NONCE_SIZE, LATEST_BLOCK_VERSION};
 
 // Serialize the extra nonce in a hexadecimal string
 pub fn serialize_extra_nonce<S: serde::Serializer>(extra_nonce: &[u8; EXTRA_NONCE_SIZE], s: S) -> Result<S::Ok, S::Error> {
@@ -80,6 +80,10 @@ impl BlockHeader {
         self.miner = key;
     }
 
+    pub fn set_nonce(&mut self, nonce: u64) {
+        self.nonce = nonce;
+    }
+
     pub fn set_extra_nonce(&mut self, values: [u8; EXTRA_NONCE_SIZE]) {
         self.extra_nonce = values;
     }
@@ -208,9 +212,9 @@ impl Serializer for BlockHeader {
 
     fn read(reader: &mut Reader) -> Result<BlockHeader, ReaderError> {
         let version = reader.read_u8()?;
-        // At this moment we only support version 0, so we check it here directly
-        if version != 0 {
-            debug!("Expected version 0 got version {version}");
+        // Version expected at its height is checked by the daemon
+        if version > LATEST_BLOCK_VERSION {
+            debug!("Expected version up to {LATEST_BLOCK_VERSION} got version {version}");
             return Err(ReaderError::InvalidValue)
         }
 
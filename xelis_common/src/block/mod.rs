@@ -9,6 +9,8 @@ pub use miner::BlockMiner;
 use crate::crypto::{Hash, HASH_SIZE};
 
 pub const EXTRA_NONCE_SIZE: usize = 32;
+// Highest block version known, activated by the hard forks of the daemon
+pub const LATEST_BLOCK_VERSION: u8 = 1;
 pub const HEADER_WORK_SIZE: usize = 73;
 pub const BLOCK_WORK_SIZE: usize = 112; // 32 + 8 + 8 + 32 + 32 = 112
 
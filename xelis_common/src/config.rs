@@ -25,6 +25,8 @@ pub const MAXIMUM_SUPPLY: u64 = 18_400_000 * COIN_VALUE;
 pub const PREFIX_ADDRESS: &str = "xel";
 // testnet prefix address
 pub const TESTNET_PREFIX_ADDRESS: &str = "xet";
+// devnet prefix address
+pub const DEVNET_PREFIX_ADDRESS: &str = "xed";
 
 // Max transaction size in bytes
 pub const MAX_TRANSACTION_SIZE: usize = 1024 * 1024; // 1 MB
@@ -6,7 +6,8 @@ use std::{
 use crate::{
     api::{DataElement, ValueType, DataValue},
     serializer::{Serializer, Writer, Reader, ReaderError},
-    config::{PREFIX_ADDRESS, TESTNET_PREFIX_ADDRESS},
+    config::{DEVNET_PREFIX_ADDRESS, PREFIX_ADDRESS, TESTNET_PREFIX_ADDRESS},
+    network::Network,
     transaction::EXTRA_DATA_LIMIT_SIZE
 };
 use super::{
@@ -17,6 +18,7 @@ use core::fmt;
 use log::debug;
 use serde::de::Error as SerdeError;
 use anyhow::Error;
+use thiserror::Error;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum AddressType {
@@ -28,15 +30,15 @@ pub enum AddressType {
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Address {
-    mainnet: bool,
+    network: Network,
     addr_type: AddressType,
     key: PublicKey
 }
 
 impl Address {
-    pub fn new(mainnet: bool, addr_type: AddressType, key: PublicKey) -> Self {
+    pub fn new(network: Network, addr_type: AddressType, key: PublicKey) -> Self {
         Self {
-            mainnet,
+            network,
             addr_type,
             key
         }
@@ -77,7 +79,7 @@ impl Address {
     pub fn extract_data(self) -> (Option<DataElement>, Self) {
         match self.addr_type {
             AddressType::Data(data) => {
-                (Some(data), Self::new(self.mainnet, AddressType::Normal, self.key))
+                (Some(data), Self::new(self.network, AddressType::Normal, self.key))
             },
             AddressType::Normal => (None, self)
         }
@@ -93,11 +95,16 @@ impl Address {
 
     // Check if the address is a mainnet address
     pub fn is_mainnet(&self) -> bool {
-        self.mainnet
+        self.network.is_mainnet()
+    }
+
+    // Network encoded in the address prefix
+    pub fn get_network(&self) -> Network {
+        self.network
     }
 
     // Compress the address to a byte array
-    // We don't use Serializer trait to avoid storing mainnet bool
+    // We don't use Serializer trait to avoid storing the network
     fn compress(&self) -> Vec<u8> {
         let mut writer = Writer::new();
         self.key.write(&mut writer);
@@ -106,13 +113,12 @@ impl Address {
     }
 
     // Read the address from a byte array
-    // Hrp validity isn't checked here, it should be done before calling this function
-    fn decompress(bytes: &[u8], hrp: &str) -> Result<Self, ReaderError> {
+    // The network is retrieved from the hrp before calling this function
+    fn decompress(bytes: &[u8], network: Network) -> Result<Self, ReaderError> {
         let mut reader = Reader::new(bytes);
-        let mainnet = hrp == PREFIX_ADDRESS;
         let key = PublicKey::read(&mut reader)?;
         let addr_type = AddressType::read(&mut reader)?;
-        Ok(Self::new(mainnet, addr_type, key))
+        Ok(Self::new(network, addr_type, key))
     }
 
     // Search for a data value in the address
@@ -126,13 +132,7 @@ impl Address {
     // Returns the address as a string (human readable format)
     pub fn as_string(&self) -> Result<String, Bech32Error> {
         let bits = convert_bits(&self.compress(), 8, 5, true)?;
-        let hrp = if self.is_mainnet() {
-            PREFIX_ADDRESS
-        } else {
-            TESTNET_PREFIX_ADDRESS
-        };
-
-        let result = encode(hrp.to_owned(), &bits)?;
+        let result = encode(self.network.address_prefix().to_owned(), &bits)?;
         Ok(result)
     }
 
@@ -140,27 +140,36 @@ impl Address {
     pub fn from_string(address: &String) -> Result<Self, Error> {
         let (hrp, decoded) = decode(address)?;
         // check that hrp is valid one
-        if hrp != PREFIX_ADDRESS && hrp != TESTNET_PREFIX_ADDRESS {
-            return Err(Bech32Error::InvalidPrefix(hrp, format!("{} or {}", PREFIX_ADDRESS, TESTNET_PREFIX_ADDRESS)).into())
-        }
+        let network = match Network::from_address_prefix(&hrp) {
+            Some(network) => network,
+            None => return Err(Bech32Error::InvalidPrefix(hrp, format!("{}, {} or {}", PREFIX_ADDRESS, TESTNET_PREFIX_ADDRESS, DEVNET_PREFIX_ADDRESS)).into())
+        };
 
         let bits = convert_bits(&decoded, 5, 8, false)?;
-        let addr = Address::decompress(&bits, hrp.as_str())?;
-
-        // now check that the hrp decoded is the one for the network state
-        if (addr.is_mainnet() && hrp != PREFIX_ADDRESS) || (!addr.is_mainnet() && hrp != TESTNET_PREFIX_ADDRESS) {
-            let expected = if addr.is_mainnet() {
-                PREFIX_ADDRESS
-            } else {
-                TESTNET_PREFIX_ADDRESS
-            };
-            return Err(Bech32Error::InvalidPrefix(hrp, expected.to_owned()).into())
+        let addr = Address::decompress(&bits, network)?;
+
+        Ok(addr)
+    }
+
+    // Parse an address from a string and verify that it is for the expected network
+    pub fn from_string_for(address: &String, network: Network) -> Result<Self, Error> {
+        let addr = Self::from_string(address)?;
+        if addr.network != network {
+            return Err(InvalidAddressNetwork { expected: network, found: addr.network }.into())
         }
 
         Ok(addr)
     }
 }
 
+// Address prefix is not the one of the network expected
+#[derive(Error, Debug)]
+#[error("Address is for {found} but {expected} is expected, its prefix must be '{}'", expected.address_prefix())]
+pub struct InvalidAddressNetwork {
+    pub expected: Network,
+    pub found: Network
+}
+
 impl FromStr for Address {
     type Err = Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
@@ -244,16 +253,34 @@ impl Display for Address {
 
 #[cfg(test)]
 mod tests {
-    use crate::crypto::KeyPair;
+    use crate::{crypto::KeyPair, network::Network};
 
     use super::{Address, AddressType};
 
     #[test]
     fn test_serde() {
         let (pub_key, _) = KeyPair::new().split();
-        let addr = Address::new(false, AddressType::Normal, pub_key.compress());
+        let addr = Address::new(Network::Testnet, AddressType::Normal, pub_key.compress());
         let v = addr.to_string();
         let addr2: Address = Address::from_string(&v).unwrap();
         assert_eq!(addr, addr2);
     }
+
+    #[test]
+    fn test_network_prefix() {
+        let (pub_key, _) = KeyPair::new().split();
+        for network in [Network::Mainnet, Network::Testnet, Network::Dev] {
+            let addr = Address::new(network, AddressType::Normal, pub_key.compress());
+            let v = addr.to_string();
+            assert!(v.starts_with(network.address_prefix()));
+
+            let addr2 = Address::from_string(&v).unwrap();
+            assert_eq!(addr2.get_network(), network);
+            assert!(Address::from_string_for(&v, network).is_ok());
+        }
+
+        let addr = Address::new(Network::Dev, AddressType::Normal, pub_key.compress()).to_string();
+        assert!(Address::from_string_for(&addr, Network::Mainnet).is_err());
+        assert!(Address::from_string_for(&addr, Network::Testnet).is_err());
+    }
 }
\ No newline at end of file
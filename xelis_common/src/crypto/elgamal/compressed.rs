@@ -1,7 +1,7 @@
 use curve25519_dalek::{ristretto::CompressedRistretto, Scalar};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
-use crate::{api::DataElement, crypto::{Address, AddressType}, serializer::{Reader, ReaderError, Serializer, Writer}};
+use crate::{api::DataElement, crypto::{Address, AddressType}, network::Network, serializer::{Reader, ReaderError, Serializer, Writer}};
 use super::{Ciphertext, DecryptHandle, PedersenCommitment, PublicKey};
 
 // Compressed point size in bytes
@@ -160,18 +160,18 @@ impl CompressedPublicKey {
     }
 
     // Clone the key to convert it to an address
-    pub fn as_address(&self, mainnet: bool) -> Address {
-        self.clone().to_address(mainnet)
+    pub fn as_address(&self, network: Network) -> Address {
+        self.clone().to_address(network)
     }
 
     // Convert it to an address
-    pub fn to_address(self, mainnet: bool) -> Address {
-        Address::new(mainnet, AddressType::Normal, self)
+    pub fn to_address(self, network: Network) -> Address {
+        Address::new(network, AddressType::Normal, self)
     }
 
     // Convert it to an address with data integrated
-    pub fn to_address_with(self, mainnet: bool, data: DataElement) -> Address {
-        Address::new(mainnet, AddressType::Data(data), self)
+    pub fn to_address_with(self, network: Network, data: DataElement) -> Address {
+        Address::new(network, AddressType::Data(data), self)
     }
 }
 
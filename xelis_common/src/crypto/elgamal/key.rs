@@ -4,11 +4,13 @@ use curve25519_dalek::{
     Scalar
 };
 use rand::rngs::OsRng;
+use sha3::{Digest, Sha3_512};
 use zeroize::Zeroize;
 use crate::{
     api::DataElement,
     config::MAXIMUM_SUPPLY,
     crypto::{Address, AddressType},
+    network::Network,
     serializer::{
         Reader,
         ReaderError,
@@ -85,13 +87,13 @@ impl PublicKey {
     }
 
     // Convert the public key to an address
-    pub fn to_address(&self, mainnet: bool) -> Address {
-        Address::new(mainnet, AddressType::Normal, self.compress())
+    pub fn to_address(&self, network: Network) -> Address {
+        Address::new(network, AddressType::Normal, self.compress())
     }
 
     // Convert the public key to an address with data integrated
-    pub fn to_address_with(&self, mainnet: bool, data: DataElement) -> Address {
-        Address::new(mainnet, AddressType::Data(data), self.compress())
+    pub fn to_address_with(&self, network: Network, data: DataElement) -> Address {
+        Address::new(network, AddressType::Data(data), self.compress())
     }
 }
 
@@ -109,6 +111,30 @@ impl PrivateKey {
         &self.0
     }
 
+    // Derive deterministically a child private key at the requested index
+    // This allows to have several accounts recoverable from the same seed
+    pub fn derive_child(&self, index: u32) -> Self {
+        self.derive(b"xelis-account", index)
+    }
+
+    // Derive deterministically the private key of a receive subaddress
+    // A different domain is used so a subaddress never collides with an account
+    pub fn derive_subaddress(&self, index: u32) -> Self {
+        self.derive(b"xelis-subaddress", index)
+    }
+
+    fn derive(&self, domain: &[u8], index: u32) -> Self {
+        let mut hasher = Sha3_512::default();
+        hasher.update(domain);
+        hasher.update(self.0.as_bytes());
+        hasher.update(index.to_be_bytes());
+        let hash = hasher.finalize();
+
+        let mut bytes = [0u8; 64];
+        bytes.copy_from_slice(hash.as_ref());
+        Self(Scalar::from_bytes_mod_order_wide(&bytes))
+    }
+
     // Decrypt a Ciphertext to a point
     pub fn decrypt_to_point(&self, ciphertext: &Ciphertext) -> RistrettoPoint {
         let commitment = ciphertext.commitment().as_point();
@@ -190,6 +216,13 @@ impl KeyPair {
     }
 }
 
+// Wipe the private key from memory once it is not used anymore
+impl Drop for PrivateKey {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
 impl Serializer for PrivateKey {
     fn write(&self, writer: &mut Writer) {
         self.0.write(writer);
@@ -231,6 +264,28 @@ mod tests {
         assert_eq!(decrypted, amount * &G);
     }
 
+    #[test]
+    fn test_derive_child() {
+        let keypair = KeyPair::new();
+        let private_key = keypair.get_private_key();
+
+        let child = private_key.derive_child(1);
+        assert_eq!(child.as_scalar(), private_key.derive_child(1).as_scalar());
+        assert_ne!(child.as_scalar(), private_key.derive_child(2).as_scalar());
+        assert_ne!(child.as_scalar(), private_key.as_scalar());
+    }
+
+    #[test]
+    fn test_derive_subaddress() {
+        let keypair = KeyPair::new();
+        let private_key = keypair.get_private_key();
+
+        let subaddress = private_key.derive_subaddress(1);
+        assert_eq!(subaddress.as_scalar(), private_key.derive_subaddress(1).as_scalar());
+        assert_ne!(subaddress.as_scalar(), private_key.derive_subaddress(2).as_scalar());
+        assert_ne!(subaddress.as_scalar(), private_key.derive_child(1).as_scalar());
+    }
+
     #[test]
     fn test_identity() {
         let keypair = KeyPair::new();
@@ -0,0 +1,92 @@
+use bip39::{Language, Mnemonic};
+use curve25519_dalek::Scalar;
+use rand::{rngs::OsRng, RngCore};
+use sha3::{Digest, Sha3_512};
+use thiserror::Error;
+use zeroize::Zeroize;
+use super::PrivateKey;
+
+// Words count of the BIP39 mnemonics used by the wallets (256 bits of entropy)
+pub const MNEMONIC_WORDS_COUNT: usize = 24;
+
+// Bytes of entropy encoded in a mnemonic
+const ENTROPY_SIZE: usize = 32;
+
+#[derive(Error, Debug)]
+pub enum MnemonicError {
+    #[error("Invalid mnemonic words count, expected {} but got {}", MNEMONIC_WORDS_COUNT, _0)]
+    InvalidWordsCount(usize),
+    #[error("Invalid mnemonic: {}", _0)]
+    Invalid(#[from] bip39::Error)
+}
+
+// Generate a new random BIP39 mnemonic using the english words list
+pub fn generate_mnemonic() -> String {
+    let mut entropy = [0u8; ENTROPY_SIZE];
+    OsRng.fill_bytes(&mut entropy);
+    let mnemonic = Mnemonic::from_entropy_in(Language::English, &entropy)
+        .expect("entropy has a valid size");
+    entropy.zeroize();
+
+    mnemonic.to_string()
+}
+
+// Verify the words and the checksum of a BIP39 mnemonic
+pub fn parse_mnemonic(phrase: &str) -> Result<Mnemonic, MnemonicError> {
+    let words: Vec<&str> = phrase.split_whitespace().collect();
+    if words.len() != MNEMONIC_WORDS_COUNT {
+        return Err(MnemonicError::InvalidWordsCount(words.len()))
+    }
+
+    Ok(Mnemonic::parse_in_normalized(Language::English, &words.join(" ").to_lowercase())?)
+}
+
+// Derive the private key of a BIP39 mnemonic
+// The 64 bytes BIP39 seed (PBKDF2 of the mnemonic and the passphrase)
+// is hashed with a domain separator and reduced to a scalar
+pub fn mnemonic_to_private_key(phrase: &str, passphrase: &str) -> Result<PrivateKey, MnemonicError> {
+    let mnemonic = parse_mnemonic(phrase)?;
+    let mut seed = mnemonic.to_seed_normalized(passphrase);
+
+    let mut hasher = Sha3_512::default();
+    hasher.update(b"xelis-bip39");
+    hasher.update(&seed);
+    seed.zeroize();
+
+    let mut bytes = [0u8; 64];
+    bytes.copy_from_slice(hasher.finalize().as_ref());
+    let scalar = Scalar::from_bytes_mod_order_wide(&bytes);
+    bytes.zeroize();
+
+    Ok(PrivateKey::from_scalar(scalar))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_and_derive() {
+        let phrase = generate_mnemonic();
+        assert_eq!(phrase.split_whitespace().count(), MNEMONIC_WORDS_COUNT);
+
+        let key = mnemonic_to_private_key(&phrase, "").unwrap();
+        let same = mnemonic_to_private_key(&phrase.to_uppercase(), "").unwrap();
+        assert_eq!(key.as_scalar(), same.as_scalar());
+
+        // The passphrase gives another key
+        let other = mnemonic_to_private_key(&phrase, "xelis").unwrap();
+        assert_ne!(key.as_scalar(), other.as_scalar());
+    }
+
+    #[test]
+    fn test_invalid_checksum() {
+        // Last word of "abandon ... art" is the checksum, changing it must be rejected
+        let valid = format!("{} art", ["abandon"; 23].join(" "));
+        assert!(parse_mnemonic(&valid).is_ok());
+
+        let invalid = format!("{} abandon", ["abandon"; 23].join(" "));
+        assert!(matches!(parse_mnemonic(&invalid), Err(MnemonicError::Invalid(_))));
+        assert!(matches!(parse_mnemonic("abandon art"), Err(MnemonicError::InvalidWordsCount(2))));
+    }
+}
@@ -1,14 +1,17 @@
 mod hash;
 mod address;
 mod transcript;
+mod uri;
 
 pub mod elgamal;
 pub mod proofs;
 pub mod bech32;
+pub mod mnemonic;
 
 pub use hash::*;
 pub use address::*;
 pub use transcript::*;
+pub use uri::*;
 
 pub type PublicKey = elgamal::CompressedPublicKey;
 pub use elgamal::{PrivateKey, KeyPair, Signature, SIGNATURE_SIZE};
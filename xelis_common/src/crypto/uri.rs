@@ -0,0 +1,273 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    str::FromStr
+};
+use thiserror::Error;
+use crate::{serializer::Serializer, utils::from_coin};
+use super::{Address, Hash};
+
+// Scheme used by payment URIs: xelis:<address>?amount=&asset=&message=
+pub const URI_SCHEME: &str = "xelis";
+
+#[derive(Error, Debug)]
+pub enum PaymentURIError {
+    #[error("Invalid scheme, expected '{}'", URI_SCHEME)]
+    InvalidScheme,
+    #[error("Invalid address: {}", _0)]
+    InvalidAddress(String),
+    #[error("Invalid amount: {}", _0)]
+    InvalidAmount(String),
+    #[error("Invalid asset: {}", _0)]
+    InvalidAsset(String),
+    #[error("Invalid percent encoding")]
+    InvalidEncoding,
+    #[error("Unknown parameter: {}", _0)]
+    UnknownParameter(String),
+    #[error("Duplicated parameter: {}", _0)]
+    DuplicatedParameter(String)
+}
+
+// Payment request that can be shared to receive funds
+// Amount is kept in human readable format as its atomic value
+// depends on the decimals of the asset requested
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaymentURI {
+    address: Address,
+    amount: Option<String>,
+    asset: Option<Hash>,
+    message: Option<String>
+}
+
+impl PaymentURI {
+    pub fn new(address: Address) -> Self {
+        Self {
+            address,
+            amount: None,
+            asset: None,
+            message: None
+        }
+    }
+
+    // Set the amount requested, it must be a valid decimal number
+    pub fn with_amount(mut self, amount: String) -> Result<Self, PaymentURIError> {
+        if !is_valid_amount(&amount) {
+            return Err(PaymentURIError::InvalidAmount(amount))
+        }
+
+        self.amount = Some(amount);
+        Ok(self)
+    }
+
+    pub fn with_asset(mut self, asset: Hash) -> Self {
+        self.asset = Some(asset);
+        self
+    }
+
+    pub fn with_message(mut self, message: String) -> Self {
+        self.message = Some(message);
+        self
+    }
+
+    pub fn get_address(&self) -> &Address {
+        &self.address
+    }
+
+    // Amount requested in human readable format
+    pub fn get_amount(&self) -> Option<&String> {
+        self.amount.as_ref()
+    }
+
+    // Amount requested in atomic units using the decimals of the asset
+    pub fn get_atomic_amount(&self, decimals: u8) -> Result<Option<u64>, PaymentURIError> {
+        match &self.amount {
+            Some(amount) => from_coin(amount.as_str(), decimals)
+                .map(Some)
+                .ok_or_else(|| PaymentURIError::InvalidAmount(amount.clone())),
+            None => Ok(None)
+        }
+    }
+
+    pub fn get_asset(&self) -> Option<&Hash> {
+        self.asset.as_ref()
+    }
+
+    pub fn get_message(&self) -> Option<&String> {
+        self.message.as_ref()
+    }
+
+    // Check if the value looks like a payment URI
+    pub fn is_uri(value: &str) -> bool {
+        value.strip_prefix(URI_SCHEME).map(|v| v.starts_with(':')).unwrap_or(false)
+    }
+}
+
+// A valid amount only contains digits and at most one dot
+fn is_valid_amount(value: &str) -> bool {
+    let mut split = value.split('.');
+    let left = split.next().unwrap_or("");
+    let right = split.next();
+    if split.next().is_some() || left.is_empty() {
+        return false
+    }
+
+    left.chars().all(|c| c.is_ascii_digit()) && right.map(|r| !r.is_empty() && r.chars().all(|c| c.is_ascii_digit())).unwrap_or(true)
+}
+
+// Percent-encode all characters that are not unreserved
+fn encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte))
+        }
+    }
+    encoded
+}
+
+fn decode(value: &str) -> Result<String, PaymentURIError> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = value.get(i + 1..i + 3).ok_or(PaymentURIError::InvalidEncoding)?;
+                let byte = u8::from_str_radix(hex, 16).map_err(|_| PaymentURIError::InvalidEncoding)?;
+                decoded.push(byte);
+                i += 3;
+            },
+            b'+' => {
+                decoded.push(b' ');
+                i += 1;
+            },
+            byte => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8(decoded).map_err(|_| PaymentURIError::InvalidEncoding)
+}
+
+impl FromStr for PaymentURI {
+    type Err = PaymentURIError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.strip_prefix(URI_SCHEME)
+            .and_then(|v| v.strip_prefix(':'))
+            .ok_or(PaymentURIError::InvalidScheme)?;
+
+        let (address, query) = match value.split_once('?') {
+            Some((address, query)) => (address, Some(query)),
+            None => (value, None)
+        };
+
+        let address = Address::from_str(address)
+            .map_err(|e| PaymentURIError::InvalidAddress(e.to_string()))?;
+        let mut uri = PaymentURI::new(address);
+
+        for param in query.into_iter().flat_map(|q| q.split('&')).filter(|p| !p.is_empty()) {
+            let (key, value) = param.split_once('=').unwrap_or((param, ""));
+            let value = decode(value)?;
+            match key {
+                "amount" => {
+                    if uri.amount.is_some() {
+                        return Err(PaymentURIError::DuplicatedParameter(key.to_owned()))
+                    }
+                    uri = uri.with_amount(value)?;
+                },
+                "asset" => {
+                    if uri.asset.is_some() {
+                        return Err(PaymentURIError::DuplicatedParameter(key.to_owned()))
+                    }
+                    let asset = Hash::from_hex(value.clone()).map_err(|_| PaymentURIError::InvalidAsset(value))?;
+                    uri.asset = Some(asset);
+                },
+                "message" => {
+                    if uri.message.is_some() {
+                        return Err(PaymentURIError::DuplicatedParameter(key.to_owned()))
+                    }
+                    uri.message = Some(value);
+                },
+                _ => return Err(PaymentURIError::UnknownParameter(key.to_owned()))
+            }
+        }
+
+        Ok(uri)
+    }
+}
+
+impl Display for PaymentURI {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", URI_SCHEME, self.address)?;
+
+        let mut params = Vec::new();
+        if let Some(amount) = &self.amount {
+            params.push(format!("amount={}", amount));
+        }
+
+        if let Some(asset) = &self.asset {
+            params.push(format!("asset={}", asset));
+        }
+
+        if let Some(message) = &self.message {
+            params.push(format!("message={}", encode(message)));
+        }
+
+        if !params.is_empty() {
+            write!(f, "?{}", params.join("&"))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{config::XELIS_ASSET, crypto::{AddressType, KeyPair}, network::Network};
+    use super::*;
+
+    fn random_address() -> Address {
+        let (pub_key, _) = KeyPair::new().split();
+        Address::new(Network::Testnet, AddressType::Normal, pub_key.compress())
+    }
+
+    #[test]
+    fn test_address_only() {
+        let address = random_address();
+        let uri = PaymentURI::new(address.clone());
+        let value = uri.to_string();
+        assert_eq!(value, format!("xelis:{}", address));
+
+        let parsed: PaymentURI = value.parse().unwrap();
+        assert_eq!(parsed, uri);
+    }
+
+    #[test]
+    fn test_full_uri() {
+        let uri = PaymentURI::new(random_address())
+            .with_amount("1.5".to_owned()).unwrap()
+            .with_asset(XELIS_ASSET)
+            .with_message("Coffee & cake?".to_owned());
+
+        let value = uri.to_string();
+        assert!(value.contains("message=Coffee%20%26%20cake%3F"));
+
+        let parsed: PaymentURI = value.parse().unwrap();
+        assert_eq!(parsed, uri);
+        assert_eq!(parsed.get_atomic_amount(8).unwrap(), Some(150_000_000));
+    }
+
+    #[test]
+    fn test_invalid_uri() {
+        let address = random_address();
+        assert!(PaymentURI::from_str(&address.to_string()).is_err());
+        assert!(PaymentURI::from_str(&format!("xelis:{}?amount=1.2.3", address)).is_err());
+        assert!(PaymentURI::from_str(&format!("xelis:{}?amount=abc", address)).is_err());
+        assert!(PaymentURI::from_str(&format!("xelis:{}?foo=bar", address)).is_err());
+        assert!(PaymentURI::from_str(&format!("xelis:{}?amount=1&amount=2", address)).is_err());
+        assert!(PaymentURI::from_str(&format!("xelis:{}?message=%zz", address)).is_err());
+    }
+}
@@ -5,9 +5,13 @@ use tokio_tungstenite::tungstenite::Error as TungsteniteError;
 
 mod http;
 mod websocket;
+mod tls;
+mod proxy;
 
 pub use http::JsonRPCClient;
-pub use websocket::{WebSocketJsonRPCClientImpl, WebSocketJsonRPCClient, EventReceiver};
+pub use tls::TlsConfig;
+pub use proxy::ProxyConfig;
+pub use websocket::{WebSocketJsonRPCClientImpl, WebSocketJsonRPCClient, EventReceiver, DEFAULT_AUTO_RECONNECT};
 
 const JSON_RPC_VERSION: &str = "2.0";
 
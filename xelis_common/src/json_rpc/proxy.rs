@@ -0,0 +1,71 @@
+use anyhow::{anyhow, Context, Result};
+use log::debug;
+use tokio::net::TcpStream;
+use tokio_socks::tcp::Socks5Stream;
+use tokio_tungstenite::tungstenite::http::Uri;
+
+// SOCKS5 proxy used to reach the remote server (e.g. Tor)
+#[derive(Debug, Clone)]
+pub struct ProxyConfig {
+    // host:port of the proxy
+    address: String,
+    // Optional credentials, Tor uses them to isolate the circuits
+    credentials: Option<(String, String)>
+}
+
+impl ProxyConfig {
+    // Parse a proxy address in the format socks5://[user:password@]host:port
+    // socks5h:// is accepted too as the host name is always resolved by the proxy
+    pub fn parse(value: &str) -> Result<Self> {
+        let value = value.trim();
+        let rest = value.strip_prefix("socks5://")
+            .or_else(|| value.strip_prefix("socks5h://"))
+            .ok_or_else(|| anyhow!("Only socks5:// proxies are supported, got '{}'", value))?;
+
+        let rest = rest.trim_end_matches('/');
+        let (credentials, address) = match rest.rsplit_once('@') {
+            Some((credentials, address)) => {
+                let (user, password) = credentials.split_once(':').unwrap_or((credentials, ""));
+                (Some((user.to_owned(), password.to_owned())), address)
+            },
+            None => (None, rest)
+        };
+
+        if address.is_empty() || !address.contains(':') {
+            return Err(anyhow!("Proxy address must be in the format socks5://host:port"))
+        }
+
+        Ok(Self {
+            address: address.to_owned(),
+            credentials
+        })
+    }
+
+    pub fn get_address(&self) -> &str {
+        &self.address
+    }
+
+    // Open a TCP stream to the target URL through the proxy
+    // The host name is sent as is so the DNS resolution is done by the proxy
+    pub async fn connect(&self, target: &str) -> Result<TcpStream> {
+        let uri: Uri = target.parse().with_context(|| format!("Invalid target address '{}'", target))?;
+        let host = uri.host().ok_or_else(|| anyhow!("No host in target address '{}'", target))?;
+        // IPv6 hosts are written between brackets in URLs
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        let port = match uri.port_u16() {
+            Some(port) => port,
+            None => match uri.scheme_str() {
+                Some("wss") | Some("https") => 443,
+                _ => 80
+            }
+        };
+
+        debug!("Connecting to {}:{} through proxy {}", host, port, self.address);
+        let stream = match self.credentials.as_ref() {
+            Some((user, password)) => Socks5Stream::connect_with_password(self.address.as_str(), (host, port), user, password).await,
+            None => Socks5Stream::connect(self.address.as_str(), (host, port)).await
+        }.with_context(|| format!("Couldn't connect to {}:{} through proxy {}", host, port, self.address))?;
+
+        Ok(stream.into_inner())
+    }
+}
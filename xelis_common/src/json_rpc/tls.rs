@@ -0,0 +1,113 @@
+use std::{fs::File, io::BufReader, sync::Arc};
+use anyhow::{anyhow, Context, Result};
+use log::{debug, warn};
+use rustls::{
+    client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+    crypto::{ring::default_provider, verify_tls12_signature, verify_tls13_signature, WebPkiSupportedAlgorithms},
+    pki_types::{CertificateDer, ServerName, UnixTime},
+    ClientConfig,
+    DigitallySignedStruct,
+    Error as TlsError,
+    RootCertStore,
+    SignatureScheme
+};
+use sha2::{Digest, Sha256};
+
+// TLS options used for secure (https / wss) connections
+#[derive(Debug, Clone, Default)]
+pub struct TlsConfig {
+    // PEM file containing additional certificate authorities to trust
+    pub ca_file: Option<String>,
+    // Hex SHA-256 fingerprints of the accepted server certificates
+    // If set, only these certificates are accepted, even if self-signed
+    pub pinned_certificates: Vec<String>
+}
+
+impl TlsConfig {
+    // Nothing to customize, the default connector can be used
+    pub fn is_default(&self) -> bool {
+        self.ca_file.is_none() && self.pinned_certificates.is_empty()
+    }
+
+    // Build the rustls configuration using the system roots and the custom options
+    pub fn build_client_config(&self) -> Result<Arc<ClientConfig>> {
+        let mut roots = RootCertStore::empty();
+        match rustls_native_certs::load_native_certs() {
+            Ok(certs) => {
+                let (added, ignored) = roots.add_parsable_certificates(certs);
+                debug!("Loaded {} native root certificates ({} ignored)", added, ignored);
+            },
+            Err(e) => warn!("Couldn't load native root certificates: {}", e)
+        };
+
+        if let Some(path) = self.ca_file.as_ref() {
+            let file = File::open(path).with_context(|| format!("Couldn't open CA file {}", path))?;
+            let mut reader = BufReader::new(file);
+            for cert in rustls_pemfile::certs(&mut reader) {
+                let cert = cert.with_context(|| format!("Invalid certificate in CA file {}", path))?;
+                roots.add(cert).context("Invalid CA certificate")?;
+            }
+        }
+
+        let builder = ClientConfig::builder();
+        let config = if self.pinned_certificates.is_empty() {
+            builder.with_root_certificates(roots).with_no_client_auth()
+        } else {
+            let verifier = PinnedCertificateVerifier::new(&self.pinned_certificates)?;
+            builder.dangerous()
+                .with_custom_certificate_verifier(Arc::new(verifier))
+                .with_no_client_auth()
+        };
+
+        Ok(Arc::new(config))
+    }
+}
+
+// Accept only the server certificates matching a pinned fingerprint
+#[derive(Debug)]
+struct PinnedCertificateVerifier {
+    fingerprints: Vec<[u8; 32]>,
+    algorithms: WebPkiSupportedAlgorithms
+}
+
+impl PinnedCertificateVerifier {
+    fn new(pins: &[String]) -> Result<Self> {
+        let mut fingerprints = Vec::with_capacity(pins.len());
+        for pin in pins {
+            // Accept the usual "AB:CD:..." format
+            let bytes = hex::decode(pin.replace(':', "")).with_context(|| format!("Invalid certificate fingerprint {}", pin))?;
+            let fingerprint: [u8; 32] = bytes.try_into()
+                .map_err(|_| anyhow!("Certificate fingerprint {} must be a SHA-256 hash", pin))?;
+            fingerprints.push(fingerprint);
+        }
+
+        Ok(Self {
+            fingerprints,
+            algorithms: default_provider().signature_verification_algorithms
+        })
+    }
+}
+
+impl ServerCertVerifier for PinnedCertificateVerifier {
+    fn verify_server_cert(&self, end_entity: &CertificateDer<'_>, _: &[CertificateDer<'_>], _: &ServerName<'_>, _: &[u8], _: UnixTime) -> Result<ServerCertVerified, TlsError> {
+        let fingerprint: [u8; 32] = Sha256::digest(end_entity.as_ref()).into();
+        if self.fingerprints.contains(&fingerprint) {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            Err(TlsError::General(format!("Server certificate {} is not pinned", hex::encode(fingerprint))))
+        }
+    }
+
+    fn verify_tls12_signature(&self, message: &[u8], cert: &CertificateDer<'_>, dss: &DigitallySignedStruct) -> Result<HandshakeSignatureValid, TlsError> {
+        verify_tls12_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn verify_tls13_signature(&self, message: &[u8], cert: &CertificateDer<'_>, dss: &DigitallySignedStruct) -> Result<HandshakeSignatureValid, TlsError> {
+        verify_tls13_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.algorithms.supported_schemes()
+    }
+}
+
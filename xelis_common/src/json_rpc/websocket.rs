@@ -14,12 +14,12 @@ use futures_util::{StreamExt, stream::{SplitSink, SplitStream}, SinkExt};
 use serde::{de::DeserializeOwned, Serialize};
 use serde_json::{Value, json};
 use tokio::{net::TcpStream, sync::{broadcast, oneshot, Mutex}, task::JoinHandle, time::sleep};
-use tokio_tungstenite::{WebSocketStream, MaybeTlsStream, connect_async, tungstenite::Message};
+use tokio_tungstenite::{WebSocketStream, MaybeTlsStream, Connector, connect_async_tls_with_config, client_async_tls_with_config, tungstenite::Message};
 use log::{debug, error, trace, warn};
 
 use crate::api::SubscribeParams;
 
-use super::{JSON_RPC_VERSION, JsonRPCError, JsonRPCResponse, JsonRPCResult};
+use super::{JSON_RPC_VERSION, JsonRPCError, JsonRPCResponse, JsonRPCResult, ProxyConfig, TlsConfig};
 
 // EventReceiver allows to get the event value parsed directly
 pub struct EventReceiver<T: DeserializeOwned> {
@@ -75,6 +75,10 @@ pub struct WebSocketJsonRPCClientImpl<E: Serialize + Hash + Eq + Send + Sync + C
     events_to_id: Mutex<HashMap<E, usize>>,
     // websocket server address
     target: String,
+    // TLS connector used for wss connections, None to use the default one
+    connector: Option<Connector>,
+    // SOCKS5 proxy used to reach the server, None for a direct connection
+    proxy: Option<ProxyConfig>,
     // auto reconnect duration
     auto_reconnect: Mutex<Option<Duration>>,
     // is the client online
@@ -88,10 +92,18 @@ pub struct WebSocketJsonRPCClientImpl<E: Serialize + Hash + Eq + Send + Sync + C
 }
 
 pub const DEFAULT_AUTO_RECONNECT: Duration = Duration::from_secs(5);
+// The delay between two reconnection attempts is doubled after each failure up to this value
+pub const MAX_AUTO_RECONNECT: Duration = Duration::from_secs(5 * 60);
 
 impl<E: Serialize + Hash + Eq + Send + Sync + Clone + 'static> WebSocketJsonRPCClientImpl<E> {
-    async fn connect_to(target: &String) -> Result<WebSocketStream<MaybeTlsStream<TcpStream>>, JsonRPCError> {
-        let (ws, response) = connect_async(target).await?;
+    async fn connect_to(target: &String, connector: Option<Connector>, proxy: Option<&ProxyConfig>) -> Result<WebSocketStream<MaybeTlsStream<TcpStream>>, JsonRPCError> {
+        let (ws, response) = match proxy {
+            Some(proxy) => {
+                let stream = proxy.connect(target).await?;
+                client_async_tls_with_config(target, stream, None, connector).await?
+            },
+            None => connect_async_tls_with_config(target, None, false, connector).await?
+        };
         let status = response.status();
         if status.is_server_error() || status.is_client_error() {
             return Err(JsonRPCError::ConnectionError(status.to_string()));
@@ -100,7 +112,13 @@ impl<E: Serialize + Hash + Eq + Send + Sync + Clone + 'static> WebSocketJsonRPCC
         Ok(ws)
     }
 
-    pub async fn new(mut target: String) -> Result<WebSocketJsonRPCClient<E>, JsonRPCError> {
+    pub async fn new(target: String) -> Result<WebSocketJsonRPCClient<E>, JsonRPCError> {
+        Self::new_with_options(target, None, None).await
+    }
+
+    // Create a client using custom TLS options (CA, pinned certificates) for wss connections
+    // and/or a SOCKS5 proxy to reach the server
+    pub async fn new_with_options(mut target: String, tls: Option<TlsConfig>, proxy: Option<ProxyConfig>) -> Result<WebSocketJsonRPCClient<E>, JsonRPCError> {
         if target.starts_with("https://") {
             target.replace_range(..8, "wss://");
         }
@@ -111,7 +129,12 @@ impl<E: Serialize + Hash + Eq + Send + Sync + Clone + 'static> WebSocketJsonRPCC
             target.insert_str(0, "ws://");
         }
 
-        let ws = Self::connect_to(&target).await?;
+        let connector = match tls.filter(|tls| !tls.is_default()) {
+            Some(tls) => Some(Connector::Rustls(tls.build_client_config()?)),
+            None => None
+        };
+
+        let ws = Self::connect_to(&target, connector.clone(), proxy.as_ref()).await?;
         
         let (write, read) = ws.split();
         let client = Arc::new(WebSocketJsonRPCClientImpl {
@@ -121,6 +144,8 @@ impl<E: Serialize + Hash + Eq + Send + Sync + Clone + 'static> WebSocketJsonRPCC
             handler_by_id: Mutex::new(HashMap::new()),
             events_to_id: Mutex::new(HashMap::new()),
             target,
+            connector,
+            proxy,
             auto_reconnect: Mutex::new(Some(DEFAULT_AUTO_RECONNECT)),
             online: AtomicBool::new(true),
             offline_channel: Mutex::new(None),
@@ -204,6 +229,11 @@ impl<E: Serialize + Hash + Eq + Send + Sync + Clone + 'static> WebSocketJsonRPCC
         self.online.load(Ordering::SeqCst)
     }
 
+    // Websocket server address used by the client
+    pub fn get_target(&self) -> &String {
+        &self.target
+    }
+
     // resubscribe to all events because of a reconnection
     async fn resubscribe_events(&self) -> Result<(), JsonRPCError> {
         let events = {
@@ -262,7 +292,7 @@ impl<E: Serialize + Hash + Eq + Send + Sync + Clone + 'static> WebSocketJsonRPCC
         }
 
         {
-            let ws = Self::connect_to(&self.target).await?;
+            let ws = Self::connect_to(&self.target, self.connector.clone(), self.proxy.as_ref()).await?;
             let (write, read) = ws.split();
             {
                 let mut lock = self.ws.lock().await;
@@ -292,24 +322,25 @@ impl<E: Serialize + Hash + Eq + Send + Sync + Clone + 'static> WebSocketJsonRPCC
         self.notify_connection_channel(&self.offline_channel).await;
 
         // Check if we should reconnect
-        let mut reconnect = {
+        let mut delay = {
             let reconnect = self.auto_reconnect.lock().await;
-            reconnect.clone()
+            reconnect.clone()?
         };
-        
+
         // Try to reconnect to the server
-        while let Some(duration) = reconnect.as_ref() {
-            sleep(*duration).await;
-            debug!("Trying to reconnect to the server...");
+        loop {
+            sleep(delay).await;
+            // Auto reconnect may have been disabled while waiting
+            if !self.should_auto_reconnect().await {
+                return None
+            }
 
-            let ws = match Self::connect_to(&self.target).await {
+            debug!("Trying to reconnect to the server...");
+            let ws = match Self::connect_to(&self.target, self.connector.clone(), self.proxy.as_ref()).await {
                 Ok(ws) => ws,
                 Err(e) => {
-                    debug!("Error while reconnecting to the server: {:?}", e);
-                    reconnect = {
-                        let reconnect = self.auto_reconnect.lock().await;
-                        reconnect.clone()
-                    };
+                    delay = (delay * 2).min(MAX_AUTO_RECONNECT);
+                    debug!("Error while reconnecting to the server: {:?}, retrying in {:?}", e, delay);
                     continue;
                 }
             };
@@ -339,8 +370,18 @@ impl<E: Serialize + Hash + Eq + Send + Sync + Clone + 'static> WebSocketJsonRPCC
 
             return Some(read)
         }
+    }
 
-        None
+    // Reconnect after losing the connection, pending requests are dropped
+    // If we can't reconnect, events are cleared to notify their listeners
+    async fn reconnect_after_connection_lost(self: &Arc<Self>) -> Option<SplitStream<WebSocketStream<MaybeTlsStream<TcpStream>>>> {
+        self.clear_requests().await;
+        let read = self.try_reconnect().await;
+        if read.is_none() {
+            self.clear_events().await;
+        }
+
+        read
     }
 
     // Clear all pending requests to notifier the caller that the connection is lost
@@ -371,13 +412,9 @@ impl<E: Serialize + Hash + Eq + Send + Sync + Clone + 'static> WebSocketJsonRPCC
                 Err(e) => {
                     // Try to reconnect to the server
                     debug!("Error while reading from the websocket: {:?}", e);
-                    self.clear_requests().await;
-                    if let Some(new_read) = self.try_reconnect().await {
-                        read = new_read;
-                    }
-                    else {
-                        self.clear_events().await;
-                        return Err(JsonRPCError::ConnectionError(e.to_string()));
+                    match self.reconnect_after_connection_lost().await {
+                        Some(new_read) => read = new_read,
+                        None => return Err(JsonRPCError::ConnectionError(e.to_string()))
                     }
                     continue;
                 }
@@ -413,7 +450,12 @@ impl<E: Serialize + Hash + Eq + Send + Sync + Clone + 'static> WebSocketJsonRPCC
                     }
                 },
                 Message::Close(_) => {
-                    break;
+                    // Server closed the connection (restart, timeout...), try to reconnect to it
+                    debug!("WebSocket connection closed by the server");
+                    match self.reconnect_after_connection_lost().await {
+                        Some(new_read) => read = new_read,
+                        None => break
+                    }
                 },
                 _ => {}
             }
@@ -1,7 +1,10 @@
 use std::{fmt::{Display, Formatter, self}, str::FromStr};
 use serde::{Serialize, Deserialize};
 
-use crate::serializer::{Serializer, Reader, ReaderError, Writer};
+use crate::{
+    config::{DEVNET_PREFIX_ADDRESS, PREFIX_ADDRESS, TESTNET_PREFIX_ADDRESS},
+    serializer::{Serializer, Reader, ReaderError, Writer}
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
@@ -18,6 +21,25 @@ impl Network {
             _ => false
         }
     }
+
+    // Human readable part used in the addresses of this network
+    pub fn address_prefix(&self) -> &'static str {
+        match &self {
+            Self::Mainnet => PREFIX_ADDRESS,
+            Self::Testnet => TESTNET_PREFIX_ADDRESS,
+            Self::Dev => DEVNET_PREFIX_ADDRESS
+        }
+    }
+
+    // Find the network using this address prefix
+    pub fn from_address_prefix(prefix: &str) -> Option<Self> {
+        Some(match prefix {
+            PREFIX_ADDRESS => Self::Mainnet,
+            TESTNET_PREFIX_ADDRESS => Self::Testnet,
+            DEVNET_PREFIX_ADDRESS => Self::Dev,
+            _ => return None
+        })
+    }
 }
 
 impl Display for Network {
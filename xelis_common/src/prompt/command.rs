@@ -5,7 +5,7 @@ use crate::{config::VERSION, async_handler, context::Context};
 use super::{argument::*, ShareablePrompt, LogLevel};
 use anyhow::Error;
 use thiserror::Error;
-use log::{info, warn, error};
+use log::{info, warn, error, Level};
 
 #[derive(Error, Debug)]
 pub enum CommandError {
@@ -52,6 +52,10 @@ pub struct Command {
     description: String,
     required_args: Vec<Arg>,
     optional_args: Vec<Arg>,
+    // Boolean options set using --name anywhere after the command name
+    flags: Vec<String>,
+    // Optional values set using --name <value> or --name=<value> anywhere after the command name
+    options: Vec<Arg>,
     callback: CommandHandler
 }
 
@@ -62,6 +66,8 @@ impl Command {
             description: description.to_owned(),
             required_args: Vec::new(),
             optional_args: Vec::new(),
+            flags: Vec::new(),
+            options: Vec::new(),
             callback
         }
     }
@@ -72,6 +78,8 @@ impl Command {
             description: description.to_owned(),
             required_args: Vec::new(),
             optional_args,
+            flags: Vec::new(),
+            options: Vec::new(),
             callback
         }
     }
@@ -82,6 +90,8 @@ impl Command {
             description: description.to_owned(),
             required_args,
             optional_args: Vec::new(),
+            flags: Vec::new(),
+            options: Vec::new(),
             callback
         }
     }
@@ -92,10 +102,22 @@ impl Command {
             description: description.to_owned(),
             required_args,
             optional_args,
+            flags: Vec::new(),
+            options: Vec::new(),
             callback
         }
     }
 
+    pub fn with_flags(mut self, flags: Vec<&str>) -> Self {
+        self.flags = flags.into_iter().map(|flag| flag.to_owned()).collect();
+        self
+    }
+
+    pub fn with_options(mut self, options: Vec<Arg>) -> Self {
+        self.options = options;
+        self
+    }
+
     pub async fn execute(&self, manager: &CommandManager, values: ArgumentManager) -> Result<(), CommandError> {
         match &self.callback {
             CommandHandler::Sync(handler) => {
@@ -123,6 +145,14 @@ impl Command {
         &self.optional_args
     }
 
+    pub fn get_flags(&self) -> &Vec<String> {
+        &self.flags
+    }
+
+    pub fn get_options(&self) -> &Vec<Arg> {
+        &self.options
+    }
+
     pub fn get_usage(&self) -> String {
         let required_args: Vec<String> = self.get_required_args()
             .iter()
@@ -134,16 +164,37 @@ impl Command {
             .map(|arg| format!("[{}]", arg.get_name()))
             .collect();
 
-        format!("{} {}{}", self.get_name(), required_args.join(" "), optional_args.join(" "))
+        let flags: Vec<String> = self.get_flags()
+            .iter()
+            .map(|flag| format!(" [--{}]", flag))
+            .collect();
+
+        let options: Vec<String> = self.get_options()
+            .iter()
+            .map(|option| format!(" [--{} <{}>]", option.get_name(), option.get_name()))
+            .collect();
+
+        format!("{} {}{}{}{}", self.get_name(), required_args.join(" "), optional_args.join(" "), options.join(""), flags.join(""))
     }
 }
 
+// Message sent by a command while the output is captured
+#[derive(Debug, Clone)]
+pub struct CapturedMessage {
+    pub level: Level,
+    pub message: String
+}
+
 // We use Mutex from std instead of tokio so we can use it in sync code too
 pub struct CommandManager {
     commands: Mutex<Vec<Rc<Command>>>,
     context: Mutex<Context>,
     prompt: ShareablePrompt,
-    running_since: Instant
+    running_since: Instant,
+    // Messages sent by the commands, only kept when the capture is enabled
+    captured_output: Mutex<Option<Vec<CapturedMessage>>>,
+    // Called before executing each command
+    before_command: Mutex<Option<Rc<dyn Fn(&CommandManager)>>>
 }
 
 impl CommandManager {
@@ -152,7 +203,9 @@ impl CommandManager {
             commands: Mutex::new(Vec::new()),
             context: Mutex::new(context),
             prompt,
-            running_since: Instant::now()
+            running_since: Instant::now(),
+            captured_output: Mutex::new(None),
+            before_command: Mutex::new(None)
         }
     }
 
@@ -210,11 +263,56 @@ impl CommandManager {
     pub async fn handle_command(&self, value: String) -> Result<(), CommandError> {
         let mut command_split = value.split_whitespace();
         let command_name = command_split.next().ok_or(CommandError::ExpectedCommandName)?;
+        self.handle_command_with_args(command_name, command_split).await
+    }
+
+    // Execute a command with its arguments already split, an argument can contain spaces
+    pub async fn handle_command_with_args<'a, I: IntoIterator<Item = &'a str>>(&self, command_name: &str, args: I) -> Result<(), CommandError> {
+        if command_name.is_empty() {
+            return Err(CommandError::ExpectedCommandName)
+        }
+
+        let mut command_split = args.into_iter();
         let command = {
             let commands = self.commands.lock()?;
             commands.iter().find(|command| *command.get_name() == *command_name).cloned().ok_or(CommandError::CommandNotFound)?
         };
         let mut arguments: HashMap<String, ArgValue> = HashMap::new();
+
+        // Flags and options can be placed anywhere, the other values are read in order
+        let mut values = Vec::new();
+        while let Some(value) = command_split.next() {
+            let name = match value.strip_prefix("--") {
+                Some(name) => name,
+                None => {
+                    values.push(value);
+                    continue;
+                }
+            };
+
+            if command.get_flags().iter().any(|f| f == name) {
+                arguments.insert(name.to_owned(), ArgValue::Bool(true));
+                continue;
+            }
+
+            // --name=value or --name value
+            let (name, inline) = match name.split_once('=') {
+                Some((name, value)) => (name, Some(value)),
+                None => (name, None)
+            };
+            match command.get_options().iter().find(|option| option.get_name() == name) {
+                Some(option) => {
+                    let option_value = match inline {
+                        Some(value) => value,
+                        None => command_split.next().ok_or_else(|| CommandError::ExpectedRequiredArg(name.to_owned()))?
+                    };
+                    arguments.insert(option.get_name().clone(), option.get_type().to_value(option_value)?);
+                },
+                None => values.push(value)
+            }
+        }
+        let mut command_split = values.into_iter();
+
         for arg in command.get_required_args() {
             let arg_value = command_split.next().ok_or_else(|| CommandError::ExpectedRequiredArg(arg.get_name().to_owned()))?;
             arguments.insert(arg.get_name().clone(), arg.get_type().to_value(arg_value)?);
@@ -229,13 +327,38 @@ impl CommandManager {
             }
         }
 
-        if command_split.next().is_some() {
-            return Err(CommandError::TooManyArguments);
+        // The last argument, if it's an array, also accepts its values separated by spaces
+        let mut remaining = command_split.peekable();
+        if remaining.peek().is_some() {
+            let last_arg = command.get_optional_args().last().or_else(|| command.get_required_args().last());
+            let values = last_arg.and_then(|arg| arguments.get_mut(arg.get_name()).map(|value| (arg, value)));
+            match values {
+                Some((arg, ArgValue::Array(values))) => {
+                    for value in remaining {
+                        match arg.get_type().to_value(value)? {
+                            ArgValue::Array(more) => values.extend(more),
+                            value => values.push(value)
+                        }
+                    }
+                },
+                _ => return Err(CommandError::TooManyArguments)
+            }
+        }
+
+        let hook = self.before_command.lock()?.clone();
+        if let Some(hook) = hook {
+            hook(self);
         }
 
         command.execute(self, ArgumentManager::new(arguments)).await
     }
 
+    // Set a function called before executing each command
+    pub fn set_before_command<F: Fn(&CommandManager) + 'static>(&self, hook: F) -> Result<(), CommandError> {
+        *self.before_command.lock()? = Some(Rc::new(hook));
+        Ok(())
+    }
+
     pub fn display_commands(&self) -> Result<(), CommandError> {
         let commands = self.commands.lock()?;
         self.message("Available commands:");
@@ -247,14 +370,39 @@ impl CommandManager {
 
     pub fn message<D: Display>(&self, message: D) {
         info!("{}", message);
+        self.capture(Level::Info, message);
     }
 
     pub fn warn<D: Display>(&self, message: D) {
         warn!("{}", message);
+        self.capture(Level::Warn, message);
     }
 
     pub fn error<D: Display>(&self, message: D) {
         error!("{}", message);
+        self.capture(Level::Error, message);
+    }
+
+    // Keep the messages sent by the commands until they are taken
+    // This allows to return the output of a command to a script
+    pub fn set_capture_output(&self, enabled: bool) -> Result<(), CommandError> {
+        let mut captured = self.captured_output.lock()?;
+        *captured = if enabled { Some(Vec::new()) } else { None };
+        Ok(())
+    }
+
+    // Take the messages captured since the last call
+    pub fn take_captured_output(&self) -> Result<Vec<CapturedMessage>, CommandError> {
+        let mut captured = self.captured_output.lock()?;
+        Ok(captured.as_mut().map(std::mem::take).unwrap_or_default())
+    }
+
+    fn capture<D: Display>(&self, level: Level, message: D) {
+        if let Ok(mut captured) = self.captured_output.lock() {
+            if let Some(messages) = captured.as_mut() {
+                messages.push(CapturedMessage { level, message: message.to_string() });
+            }
+        }
     }
 
     pub fn running_since(&self) -> Duration {
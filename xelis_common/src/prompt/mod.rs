@@ -112,6 +112,8 @@ pub enum PromptError {
     AlreadyRunning,
     #[error("Prompt is not running")]
     NotRunning,
+    #[error("No input can be read in headless mode")]
+    Headless,
     #[error("No command manager found")]
     NoCommandManager,
     #[error("Error while parsing: {}", _0)]
@@ -139,14 +141,16 @@ struct State {
     prompt_sender: Mutex<Option<oneshot::Sender<String>>>,
     has_exited: AtomicBool,
     ascii_escape_regex: Regex,
-    interactive: bool
+    interactive: bool,
+    // stdout is reserved to the caller, nothing is displayed and no input is read
+    headless: bool
 }
 
 impl State {
-    fn new() -> Self {
+    fn new(headless: bool) -> Self {
         // enable the raw mode for terminal
         // so we can read each event/action
-        let interactive = !crossterminal::enable_raw_mode().is_err();
+        let interactive = !headless && !crossterminal::enable_raw_mode().is_err();
         if interactive {
             warn!("Non-interactive mode enabled");
         }
@@ -161,7 +165,8 @@ impl State {
             prompt_sender: Mutex::new(None),
             has_exited: AtomicBool::new(false),
             ascii_escape_regex: Regex::new("\x1B\\[[0-9;]*[A-Za-z]").unwrap(),
-            interactive
+            interactive,
+            headless
         }
     }
 
@@ -189,6 +194,10 @@ impl State {
         self.interactive
     }
 
+    pub fn is_headless(&self) -> bool {
+        self.headless
+    }
+
     fn ioloop(self: &Arc<Self>, sender: UnboundedSender<String>) -> Result<(), PromptError> {
         debug!("ioloop started");
 
@@ -359,6 +368,10 @@ impl State {
     }
 
     fn show_with_prompt_and_input(&self, prompt: &String, input: &String) -> Result<(), PromptError> {
+        if self.headless {
+            return Ok(())
+        }
+
         let current_count = self.count_lines(&format!("\r{}{}", prompt, input));
         let previous_count = self.previous_prompt_line.swap(current_count, Ordering::SeqCst);
 
@@ -434,14 +447,25 @@ type AsyncF<'a, T1, T2, R> = Box<dyn Fn(&'a T1, T2) -> LocalBoxFuture<'a, R> + '
 
 impl Prompt {
     pub fn new(level: LogLevel, dir_path: &String, filename_log: &String, disable_file_logging: bool) -> Result<ShareablePrompt, PromptError> {
+        Self::with_mode(level, dir_path, filename_log, disable_file_logging, false)
+    }
+
+    // Prompt used to run commands from a script
+    // Logs are only written in the log file to keep stdout for the commands results,
+    // no input can be read and confirmations are accepted automatically
+    pub fn new_headless(level: LogLevel, dir_path: &String, filename_log: &String, disable_file_logging: bool) -> Result<ShareablePrompt, PromptError> {
+        Self::with_mode(level, dir_path, filename_log, disable_file_logging, true)
+    }
+
+    fn with_mode(level: LogLevel, dir_path: &String, filename_log: &String, disable_file_logging: bool, headless: bool) -> Result<ShareablePrompt, PromptError> {
         let (read_input_sender, read_input_receiver) = mpsc::channel(1);
         let prompt = Self {
-            state: Arc::new(State::new()),
+            state: Arc::new(State::new(headless)),
             input_receiver: Mutex::new(None),
             read_input_receiver: AsyncMutex::new(read_input_receiver),
             read_input_sender,
         };
-        prompt.setup_logger(level, dir_path, filename_log, disable_file_logging)?;
+        prompt.setup_logger(level, dir_path, filename_log, disable_file_logging, !headless)?;
 
         if prompt.state.is_interactive() {
             let (input_sender, input_receiver) = mpsc::unbounded_channel::<String>();
@@ -562,6 +586,11 @@ impl Prompt {
         Ok(())
     }
 
+    // is the prompt used without terminal, see new_headless
+    pub fn is_headless(&self) -> bool {
+        self.state.is_headless()
+    }
+
     // get the current prompt displayed
     pub fn get_prompt(&self) -> Result<Option<String>, PromptError> {
         let prompt = self.state.prompt.lock()?;
@@ -587,6 +616,19 @@ impl Prompt {
     }
 
     pub async fn ask_confirmation(&self) -> Result<bool, PromptError> {
+        if self.state.is_headless() {
+            return Ok(true)
+        }
+
+        self.ask_interactive_confirmation().await
+    }
+
+    // Same as ask_confirmation but it is never skipped, a headless prompt returns an error
+    pub async fn ask_interactive_confirmation(&self) -> Result<bool, PromptError> {
+        if self.state.is_headless() {
+            return Err(PromptError::Headless)
+        }
+
         let res = self.read_valid_str_value(
             colorize_str(Color::Green, "Confirm ? (Y/N): "),
             vec!["y", "n"]
@@ -614,6 +656,10 @@ impl Prompt {
 
     // read a message from the user and apply the input mask if necessary
     pub async fn read_input(&self, prompt: String, apply_mask: bool) -> Result<String, PromptError> {
+        if self.state.is_headless() {
+            return Err(PromptError::Headless)
+        }
+
         // This is also used as a sempahore to have only one call at a time
         let mut canceler = self.read_input_receiver.lock().await;
 
@@ -682,7 +728,7 @@ impl Prompt {
     }
 
     // configure fern and print prompt message after each new output
-    fn setup_logger(&self, level: LogLevel, dir_path: &String, filename_log: &String, disable_file_logging: bool) -> Result<(), fern::InitError> {
+    fn setup_logger(&self, level: LogLevel, dir_path: &String, filename_log: &String, disable_file_logging: bool, log_to_stdout: bool) -> Result<(), fern::InitError> {
         let colors = ColoredLevelConfig::new()
             .debug(Color::Green)
             .info(Color::Cyan)
@@ -715,7 +761,11 @@ impl Prompt {
             .chain(std::io::stdout())
             .level(level.into());
 
-        let mut base = base.chain(stdout_log);
+        let mut base = if log_to_stdout {
+            base.chain(stdout_log)
+        } else {
+            base
+        };
         if !disable_file_logging {
             let logs_path = Path::new(dir_path);
             if !logs_path.exists() {
@@ -35,6 +35,9 @@ pub struct CipherFormatError;
 /// We never use a key twice, then. We can reuse the same nonce everytime.
 const NONCE: &[u8; 12] = b"xelis-crypto";
 
+/// Bytes added to the encrypted data by the Poly1305 authentication tag.
+pub const TAG_SIZE: usize = 16;
+
 /// This is the encrypted data, which is the result of the encryption process.
 /// It is a simple wrapper around a vector of bytes.
 /// This doesn't contain the nonce, which is always the same.
@@ -5,6 +5,7 @@
 use bulletproofs::RangeProof;
 use curve25519_dalek::Scalar;
 use serde::{Deserialize, Serialize};
+use indexmap::IndexSet;
 use std::{
     collections::HashSet,
     iter,
@@ -33,18 +34,22 @@ use crate::{
             PC_GENS,
             BULLET_PROOF_SIZE,
         },
+        hash,
         Address,
+        AddressType,
         Hash,
         ProtocolTranscript,
         HASH_SIZE,
         SIGNATURE_SIZE
     },
+    network::Network,
     serializer::{Reader, ReaderError, Serializer, Writer},
     utils::calculate_tx_fee
 };
 use thiserror::Error;
 use super::{
-    aead::{derive_aead_key_from_opening, PlaintextData},
+    aead::{derive_aead_key_from_opening, PlaintextData, TAG_SIZE},
+    multisig::{MultiSig, MultiSigPayload, SignatureId, MAX_MULTISIG_PARTICIPANTS},
     BurnPayload,
     Reference,
     Role,
@@ -53,7 +58,8 @@ use super::{
     TransactionType,
     TransferPayload,
     EXTRA_DATA_LIMIT_SIZE,
-    MAX_TRANSFER_COUNT
+    MAX_TRANSFER_COUNT,
+    MULTISIG_TX_VERSION
 };
 
 #[derive(Error, Debug, Clone)]
@@ -70,10 +76,12 @@ pub enum GenerationError<T> {
     ExtraDataTooLarge,
     #[error("Encrypted extra data is too large")]
     EncryptedExtraDataTooLarge,
-    #[error("Address is not on the same network as us")]
-    InvalidNetwork,
+    #[error("Address is for {} but the transaction is built for {}", _0, _1)]
+    InvalidNetwork(Network, Network),
     #[error("Extra data was provied with an integrated address")]
     ExtraDataAndIntegratedAddress,
+    #[error("Invalid multisig setup")]
+    InvalidMultiSigSetup,
     #[error("Proof generation error: {0}")]
     Proof(#[from] ProofGenerationError),
 }
@@ -109,7 +117,7 @@ pub trait FeeHelper {
 pub trait AccountState: FeeHelper {
 
     /// Used to verify if the address is on the same chain
-    fn is_mainnet(&self) -> bool;
+    fn get_network(&self) -> Network;
 
     /// Get the balance from the source
     fn get_account_balance(&self, asset: &Hash) -> Result<u64, Self::Error>;
@@ -135,7 +143,15 @@ pub trait AccountState: FeeHelper {
 pub enum TransactionTypeBuilder {
     Transfers(Vec<TransferBuilder>),
     // We can use the same as final transaction
-    Burn(BurnPayload)
+    Burn(BurnPayload),
+    MultiSig(MultiSigBuilder)
+}
+
+// Setup the source account as a multisig, participants are addresses to verify their network
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MultiSigBuilder {
+    pub threshold: u8,
+    pub participants: Vec<Address>
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -144,6 +160,8 @@ pub struct TransferBuilder {
     pub amount: u64,
     pub destination: Address,
     // we can put whatever we want up to EXTRA_DATA_LIMIT_SIZE bytes
+    // It is encrypted with a key derived from the amount opening,
+    // so only the sender and the receiver can read it
     pub extra_data: Option<DataElement>,
 }
 
@@ -152,7 +170,10 @@ pub struct TransactionBuilder {
     version: u8,
     source: CompressedPublicKey,
     data: TransactionTypeBuilder,
-    fee_builder: FeeBuilder
+    fee_builder: FeeBuilder,
+    // Signatures expected from the multisig participants, used to estimate the size
+    #[serde(default)]
+    required_signatures: u8
 }
 
 // Internal struct for build
@@ -191,7 +212,8 @@ impl TransactionTypeBuilder {
             }
             TransactionTypeBuilder::Burn(payload) => {
                 consumed.insert(payload.asset.clone());
-            }
+            },
+            TransactionTypeBuilder::MultiSig(_) => {}
         }
 
         consumed
@@ -206,7 +228,7 @@ impl TransactionTypeBuilder {
                     used_keys.insert(transfer.destination.get_public_key().clone());
                 }
             }
-            TransactionTypeBuilder::Burn(_) => {}
+            TransactionTypeBuilder::Burn(_) | TransactionTypeBuilder::MultiSig(_) => {}
         }
 
         used_keys
@@ -215,7 +237,9 @@ impl TransactionTypeBuilder {
 
 // Used to build the final transaction
 // by signing it
-struct TransactionSigner {
+// With a multisig account, the participants signatures are collected before
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TransactionSigner {
     version: u8,
     source: CompressedPublicKey,
     data: TransactionType,
@@ -224,9 +248,70 @@ struct TransactionSigner {
     source_commitments: Vec<SourceCommitment>,
     reference: Reference,
     range_proof: RangeProof,
+    multisig: Option<MultiSig>,
 }
 
 impl TransactionSigner {
+    pub fn get_source(&self) -> &CompressedPublicKey {
+        &self.source
+    }
+
+    pub fn get_data(&self) -> &TransactionType {
+        &self.data
+    }
+
+    pub fn get_fee(&self) -> u64 {
+        self.fee
+    }
+
+    pub fn get_nonce(&self) -> u64 {
+        self.nonce
+    }
+
+    pub fn get_multisig(&self) -> &Option<MultiSig> {
+        &self.multisig
+    }
+
+    // Write everything except the multisig and the signature
+    fn write_without_multisig(&self, writer: &mut Writer) {
+        self.version.write(writer);
+        self.source.write(writer);
+        self.data.write(writer);
+        self.fee.write(writer);
+        self.nonce.write(writer);
+
+        writer.write_u8(self.source_commitments.len() as u8);
+        for commitment in &self.source_commitments {
+            commitment.write(writer);
+        }
+
+        self.range_proof.write(writer);
+        self.reference.write(writer);
+    }
+
+    // Hash to be signed by each multisig participant
+    // This is the same as `Transaction::get_multisig_hash`
+    pub fn get_multisig_hash(&self) -> Hash {
+        let mut writer = Writer::new();
+        self.write_without_multisig(&mut writer);
+        hash(&writer.bytes())
+    }
+
+    // Sign the transaction as the participant `id` of the multisig setup
+    pub fn sign_multisig(&self, keypair: &KeyPair, id: u8) -> SignatureId {
+        let signature = keypair.sign(self.get_multisig_hash().as_bytes());
+        SignatureId {
+            id,
+            signature
+        }
+    }
+
+    // Add the signature of a participant
+    // Its validity is checked against the setup during the verification
+    pub fn add_multisig_signature(&mut self, signature: SignatureId) {
+        self.multisig.get_or_insert_with(MultiSig::new).add_signature(signature);
+    }
+
     pub fn sign(self, keypair: &KeyPair) -> Transaction {
         let bytes = self.to_bytes();
         let signature = keypair.sign(&bytes);
@@ -240,6 +325,7 @@ impl TransactionSigner {
             source_commitments: self.source_commitments,
             range_proof: self.range_proof,
             reference: self.reference,
+            multisig: self.multisig,
             signature,
         }
     }
@@ -252,12 +338,19 @@ impl TransactionBuilder {
             source,
             data,
             fee_builder,
+            required_signatures: 0
         }
     }
 
-    /// Estimate by hand the bytes size of a final TX
-    // Returns bytes size and transfers count
-    fn estimate_size(&self) -> usize {
+    // Set how many participants will sign the transaction of a multisig account
+    pub fn with_required_signatures(mut self, count: u8) -> Self {
+        self.required_signatures = count;
+        self
+    }
+
+    /// Estimate by hand the bytes size of the final TX, before building it
+    /// This allows to check it against MAX_TRANSACTION_SIZE and to display the fees up front
+    pub fn estimate_size(&self) -> usize {
         let assets_used = self.data.used_assets().len();
         // Version byte
         let mut size = 1
@@ -280,6 +373,15 @@ impl TransactionBuilder {
         + SIGNATURE_SIZE
         ;
 
+        if self.version >= MULTISIG_TX_VERSION {
+            // Multisig option byte
+            size += 1;
+            if self.required_signatures > 0 {
+                // Signatures count byte, then id and signature for each participant
+                size += 1 + self.required_signatures as usize * (1 + SIGNATURE_SIZE);
+            }
+        }
+
         let transfers_count = match &self.data {
             TransactionTypeBuilder::Transfers(transfers) => {
                 // Transfers count byte
@@ -294,9 +396,18 @@ impl TransactionBuilder {
                     // Extra data byte flag
                     + 1;
 
-                    if let Some(extra_data) = &transfer.extra_data {
-                        // 2 represents u16 length
-                        size += 2 + extra_data.size();
+                    // Integrated addresses data is sent as extra data
+                    let extra_data_size = match &transfer.extra_data {
+                        Some(extra_data) => Some(extra_data.size()),
+                        None => match transfer.destination.get_type() {
+                            AddressType::Data(data) => Some(data.size()),
+                            AddressType::Normal => None
+                        }
+                    };
+
+                    if let Some(extra_data_size) = extra_data_size {
+                        // 2 represents u16 length, extra data is encrypted with its authentication tag
+                        size += 2 + extra_data_size + TAG_SIZE;
                     }
                 }
                 transfers.len()
@@ -305,6 +416,11 @@ impl TransactionBuilder {
                 // Payload size
                 size += payload.size();
                 0
+            },
+            TransactionTypeBuilder::MultiSig(multisig) => {
+                // Threshold byte, participants count byte and their keys
+                size += 1 + 1 + multisig.participants.len() * RISTRETTO_COMPRESSED_SIZE;
+                0
             }
         };
 
@@ -370,7 +486,8 @@ impl TransactionBuilder {
                 if *asset == payload.asset {
                     ct -= Scalar::from(payload.amount)
                 }
-            }
+            },
+            TransactionTypeBuilder::MultiSig(_) => {}
         }
 
         ct
@@ -397,17 +514,29 @@ impl TransactionBuilder {
                 if *asset == payload.asset {
                     cost += payload.amount
                 }
-            }
+            },
+            TransactionTypeBuilder::MultiSig(_) => {}
         }
 
         cost
     }
 
     pub fn build<B: AccountState>(
-        mut self,
+        self,
         state: &mut B,
         source_keypair: &KeyPair,
     ) -> Result<Transaction, GenerationError<B::Error>> {
+        let signer = self.build_unsigned(state, source_keypair)?;
+        Ok(signer.sign(source_keypair))
+    }
+
+    // Build the transaction with all its proofs but without its final signature
+    // This lets the participants of a multisig account sign it before
+    pub fn build_unsigned<B: AccountState>(
+        mut self,
+        state: &mut B,
+        source_keypair: &KeyPair,
+    ) -> Result<TransactionSigner, GenerationError<B::Error>> {
         // Compute the fees
         let fee = self.estimate_fees(state)?;
 
@@ -435,8 +564,8 @@ impl TransactionBuilder {
                     return Err(GenerationError::SenderIsReceiver);
                 }
 
-                if state.is_mainnet() != transfer.destination.is_mainnet() {
-                    return Err(GenerationError::InvalidNetwork);
+                if state.get_network() != transfer.destination.get_network() {
+                    return Err(GenerationError::InvalidNetwork(transfer.destination.get_network(), state.get_network()));
                 }
 
                 // Either extra data provided or an integrated address, not both
@@ -628,7 +757,26 @@ impl TransactionBuilder {
 
         let data = match self.data {
             TransactionTypeBuilder::Transfers(_) => TransactionType::Transfers(transfers),
-            TransactionTypeBuilder::Burn(payload) => TransactionType::Burn(payload)
+            TransactionTypeBuilder::Burn(payload) => TransactionType::Burn(payload),
+            TransactionTypeBuilder::MultiSig(multisig) => {
+                let mut participants = IndexSet::with_capacity(multisig.participants.len());
+                for participant in multisig.participants {
+                    if participant.get_network() != state.get_network() {
+                        return Err(GenerationError::InvalidNetwork(participant.get_network(), state.get_network()));
+                    }
+
+                    // Same participant can't be set twice
+                    if !participants.insert(participant.to_public_key()) {
+                        return Err(GenerationError::InvalidMultiSigSetup);
+                    }
+                }
+
+                let payload = MultiSigPayload::new(multisig.threshold, participants);
+                if self.version < MULTISIG_TX_VERSION || payload.participants.len() > MAX_MULTISIG_PARTICIPANTS || !payload.is_valid(&self.source) {
+                    return Err(GenerationError::InvalidMultiSigSetup);
+                }
+                TransactionType::MultiSig(payload)
+            }
         };
 
         // 3. Create the RangeProof
@@ -643,7 +791,7 @@ impl TransactionBuilder {
         )
         .map_err(ProofGenerationError::from)?;
 
-        let transaction = TransactionSigner {
+        Ok(TransactionSigner {
             version: self.version,
             source: self.source,
             data,
@@ -652,27 +800,17 @@ impl TransactionBuilder {
             source_commitments,
             reference,
             range_proof,
-        }.sign(source_keypair);
-
-        Ok(transaction)
+            multisig: None,
+        })
     }
 }
 
 impl Serializer for TransactionSigner {
     fn write(&self, writer: &mut Writer) {
-        self.version.write(writer);
-        self.source.write(writer);
-        self.data.write(writer);
-        self.fee.write(writer);
-        self.nonce.write(writer);
-
-        writer.write_u8(self.source_commitments.len() as u8);
-        for commitment in &self.source_commitments {
-            commitment.write(writer);
+        self.write_without_multisig(writer);
+        if self.version >= MULTISIG_TX_VERSION {
+            self.multisig.write(writer);
         }
-
-        self.range_proof.write(writer);
-        self.reference.write(writer);
     }
 
     // Should never be called
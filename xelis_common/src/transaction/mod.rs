@@ -2,20 +2,23 @@ use crate::{
     crypto::{
         elgamal::{CompressedCiphertext, CompressedCommitment, CompressedHandle, CompressedPublicKey},
         proofs::{CiphertextValidityProof, CommitmentEqProof},
+        hash,
         Hash,
         Hashable,
         Signature,
+        SIGNATURE_SIZE,
     },
     serializer::{Reader, ReaderError, Serializer, Writer}
 };
 use bulletproofs::RangeProof;
 use log::debug;
 use serde::{Deserialize, Serialize};
-use self::aead::AEADCipher;
+use self::{aead::AEADCipher, multisig::{MultiSig, MultiSigPayload}};
 
 pub mod builder;
 pub mod verify;
 pub mod aead;
+pub mod multisig;
 
 #[cfg(test)]
 mod tests;
@@ -23,6 +26,8 @@ mod tests;
 // Maximum total size of payload across all transfers per transaction
 pub const EXTRA_DATA_LIMIT_SIZE: usize = 1024;
 pub const MAX_TRANSFER_COUNT: usize = 255;
+// Version from which the multisig signatures are serialized in the transaction
+pub const MULTISIG_TX_VERSION: u8 = 1;
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Reference {
@@ -75,6 +80,7 @@ pub struct BurnPayload {
 pub enum TransactionType {
     Transfers(Vec<TransferPayload>),
     Burn(BurnPayload),
+    MultiSig(MultiSigPayload),
 }
 
 // Transaction to be sent over the network
@@ -97,6 +103,9 @@ pub struct Transaction {
     range_proof: RangeProof,
     /// At which block the TX is built
     reference: Reference,
+    /// Signatures of the participants if the source account is a multisig
+    /// Only present from MULTISIG_TX_VERSION
+    multisig: Option<MultiSig>,
     /// The signature of the source key
     signature: Signature,
 }
@@ -166,9 +175,9 @@ impl TransferPayload {
 }
 
 impl Transaction {
-    pub fn new(source: CompressedPublicKey, data: TransactionType, fee: u64, nonce: u64, source_commitments: Vec<SourceCommitment>, range_proof: RangeProof, reference: Reference, signature: Signature) -> Self {
+    pub fn new(version: u8, source: CompressedPublicKey, data: TransactionType, fee: u64, nonce: u64, source_commitments: Vec<SourceCommitment>, range_proof: RangeProof, reference: Reference, multisig: Option<MultiSig>, signature: Signature) -> Self {
         Transaction {
-            version: 0,
+            version,
             source,
             data,
             fee,
@@ -176,6 +185,7 @@ impl Transaction {
             source_commitments,
             range_proof,
             reference,
+            multisig,
             signature
         }
     }
@@ -225,6 +235,23 @@ impl Transaction {
         &self.reference
     }
 
+    // Get the signatures of the multisig participants if any
+    pub fn get_multisig(&self) -> &Option<MultiSig> {
+        &self.multisig
+    }
+
+    // Get the hash signed by the multisig participants
+    // It covers the whole transaction except the multisig and the source signature
+    pub fn get_multisig_hash(&self) -> Hash {
+        let bytes = self.to_bytes();
+        let multisig_size = if self.version >= MULTISIG_TX_VERSION {
+            self.multisig.size()
+        } else {
+            0
+        };
+        hash(&bytes[..bytes.len() - SIGNATURE_SIZE - multisig_size])
+    }
+
     pub fn consume(self) -> (CompressedPublicKey, TransactionType) {
         (self.source, self.data)
     }
@@ -332,6 +359,10 @@ impl Serializer for TransactionType {
                 for tx in txs {
                     tx.write(writer);
                 }
+            },
+            TransactionType::MultiSig(payload) => {
+                writer.write_u8(2);
+                payload.write(writer);
             }
         };
     }
@@ -354,6 +385,7 @@ impl Serializer for TransactionType {
                 }
                 TransactionType::Transfers(txs)
             },
+            2 => TransactionType::MultiSig(MultiSigPayload::read(reader)?),
             _ => {
                 return Err(ReaderError::InvalidValue)
             }
@@ -371,6 +403,9 @@ impl Serializer for TransactionType {
                     size += tx.size();
                 }
                 size
+            },
+            TransactionType::MultiSig(payload) => {
+                1 + payload.size()
             }
         }
     }
@@ -391,14 +426,17 @@ impl Serializer for Transaction {
 
         self.range_proof.write(writer);
         self.reference.write(writer);
+        if self.version >= MULTISIG_TX_VERSION {
+            self.multisig.write(writer);
+        }
         self.signature.write(writer);
     }
 
     fn read(reader: &mut Reader) -> Result<Transaction, ReaderError> {
         let version = reader.read_u8()?;
-        // At this moment we only support version 0, so we check it here directly
-        if version != 0 {
-            debug!("Expected version 0 got version {version}");
+        // At this moment we only support version 0 and 1, so we check it here directly
+        if version > MULTISIG_TX_VERSION {
+            debug!("Expected version 0 or 1 got version {version}");
             return Err(ReaderError::InvalidValue)
         }
 
@@ -419,6 +457,11 @@ impl Serializer for Transaction {
 
         let range_proof = RangeProof::read(reader)?;
         let reference = Reference::read(reader)?;
+        let multisig = if version >= MULTISIG_TX_VERSION {
+            Option::read(reader)?
+        } else {
+            None
+        };
         let signature = Signature::read(reader)?;
 
         Ok(Transaction {
@@ -430,6 +473,7 @@ impl Serializer for Transaction {
             source_commitments,
             range_proof,
             reference,
+            multisig,
             signature,
         })
     }
@@ -446,6 +490,7 @@ impl Serializer for Transaction {
         + self.source_commitments.iter().map(|c| c.size()).sum::<usize>()
         + self.range_proof.size()
         + self.reference.size()
+        + if self.version >= MULTISIG_TX_VERSION { self.multisig.size() } else { 0 }
         + self.signature.size()
     }
 }
@@ -0,0 +1,166 @@
+use indexmap::IndexSet;
+use serde::{Deserialize, Serialize};
+use crate::{
+    crypto::{elgamal::CompressedPublicKey, Signature},
+    serializer::{Reader, ReaderError, Serializer, Writer}
+};
+
+// Maximum participants allowed in a multisig setup
+// Each participant is referenced by its index as a byte
+pub const MAX_MULTISIG_PARTICIPANTS: usize = 255;
+
+// Configure the account of the source as a M-of-N multisig
+// Once applied, every transaction of this account requires at least
+// `threshold` signatures from the participants in addition to its own one
+// A threshold of 0 without participants removes the multisig
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct MultiSigPayload {
+    pub threshold: u8,
+    pub participants: IndexSet<CompressedPublicKey>
+}
+
+impl MultiSigPayload {
+    pub fn new(threshold: u8, participants: IndexSet<CompressedPublicKey>) -> Self {
+        Self {
+            threshold,
+            participants
+        }
+    }
+
+    // Is this payload removing the multisig of the account
+    pub fn is_delete(&self) -> bool {
+        self.threshold == 0 && self.participants.is_empty()
+    }
+
+    // Verify that the setup is coherent for the account
+    pub fn is_valid(&self, source: &CompressedPublicKey) -> bool {
+        if self.is_delete() {
+            return true
+        }
+
+        self.threshold != 0
+            && self.participants.len() <= MAX_MULTISIG_PARTICIPANTS
+            && self.threshold as usize <= self.participants.len()
+            // Owner is always signing the transaction, it can't be a participant
+            && !self.participants.contains(source)
+    }
+}
+
+impl Serializer for MultiSigPayload {
+    fn write(&self, writer: &mut Writer) {
+        writer.write_u8(self.threshold);
+        writer.write_u8(self.participants.len() as u8);
+        for participant in &self.participants {
+            participant.write(writer);
+        }
+    }
+
+    fn read(reader: &mut Reader) -> Result<MultiSigPayload, ReaderError> {
+        let threshold = reader.read_u8()?;
+        let count = reader.read_u8()?;
+        let mut participants = IndexSet::with_capacity(count as usize);
+        for _ in 0..count {
+            if !participants.insert(CompressedPublicKey::read(reader)?) {
+                return Err(ReaderError::InvalidValue)
+            }
+        }
+
+        Ok(MultiSigPayload {
+            threshold,
+            participants
+        })
+    }
+
+    fn size(&self) -> usize {
+        1 + 1 + self.participants.iter().map(|p| p.size()).sum::<usize>()
+    }
+}
+
+// Signature of a participant referenced by its index in the multisig setup
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SignatureId {
+    pub id: u8,
+    pub signature: Signature
+}
+
+impl Serializer for SignatureId {
+    fn write(&self, writer: &mut Writer) {
+        writer.write_u8(self.id);
+        self.signature.write(writer);
+    }
+
+    fn read(reader: &mut Reader) -> Result<SignatureId, ReaderError> {
+        let id = reader.read_u8()?;
+        let signature = Signature::read(reader)?;
+        Ok(SignatureId {
+            id,
+            signature
+        })
+    }
+
+    fn size(&self) -> usize {
+        1 + self.signature.size()
+    }
+}
+
+// Signatures collected from the participants for a transaction
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct MultiSig {
+    signatures: Vec<SignatureId>
+}
+
+impl MultiSig {
+    pub fn new() -> Self {
+        Self {
+            signatures: Vec::new()
+        }
+    }
+
+    // Add the signature of a participant, replacing its previous one if any
+    pub fn add_signature(&mut self, signature: SignatureId) {
+        self.signatures.retain(|s| s.id != signature.id);
+        self.signatures.push(signature);
+    }
+
+    pub fn get_signatures(&self) -> &Vec<SignatureId> {
+        &self.signatures
+    }
+
+    pub fn len(&self) -> usize {
+        self.signatures.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.signatures.is_empty()
+    }
+}
+
+impl Serializer for MultiSig {
+    fn write(&self, writer: &mut Writer) {
+        writer.write_u8(self.signatures.len() as u8);
+        for signature in &self.signatures {
+            signature.write(writer);
+        }
+    }
+
+    fn read(reader: &mut Reader) -> Result<MultiSig, ReaderError> {
+        let count = reader.read_u8()?;
+        let mut signatures: Vec<SignatureId> = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let signature = SignatureId::read(reader)?;
+            // Same participant can't be counted twice
+            if signatures.iter().any(|s| s.id == signature.id) {
+                return Err(ReaderError::InvalidValue)
+            }
+            signatures.push(signature);
+        }
+
+        Ok(MultiSig {
+            signatures
+        })
+    }
+
+    fn size(&self) -> usize {
+        1 + self.signatures.iter().map(|s| s.size()).sum::<usize>()
+    }
+}
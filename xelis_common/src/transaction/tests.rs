@@ -11,8 +11,9 @@ use crate::{
         KeyPair,
         PublicKey
     },
+    network::Network,
     serializer::Serializer,
-    transaction::{TransactionType, MAX_TRANSFER_COUNT}
+    transaction::{TransactionType, MAX_TRANSFER_COUNT, MULTISIG_TX_VERSION}
 };
 use super::{
     aead::{
@@ -24,11 +25,13 @@ use super::{
         AccountState,
         FeeBuilder,
         FeeHelper,
+        MultiSigBuilder,
         TransactionBuilder,
         TransactionTypeBuilder,
         TransferBuilder
     },
-    verify::BlockchainVerificationState,
+    multisig::MultiSigPayload,
+    verify::{BlockchainVerificationState, VerificationError},
     BurnPayload,
     Reference,
     Role,
@@ -42,6 +45,7 @@ struct AccountChainState {
 
 struct ChainState {
     accounts: HashMap<PublicKey, AccountChainState>,
+    multisig: HashMap<PublicKey, MultiSigPayload>,
 }
 
 #[derive(Clone)]
@@ -75,7 +79,7 @@ impl Account {
     }
 
     fn address(&self) -> Address {
-        self.keypair.get_public_key().to_address(false)
+        self.keypair.get_public_key().to_address(Network::Testnet)
     }
 }
 
@@ -180,6 +184,7 @@ async fn test_tx_verify() {
 
     let mut state = ChainState {
         accounts: HashMap::new(),
+        multisig: HashMap::new(),
     };
 
     // Create the chain state
@@ -208,6 +213,83 @@ async fn test_tx_verify() {
     tx.verify(&mut state).await.unwrap();
 }
 
+#[test]
+fn test_tx_verify_stateless() {
+    let mut alice = Account::new();
+    let bob = Account::new();
+
+    alice.set_balance(XELIS_ASSET, 100 * COIN_VALUE);
+
+    let mut tx = create_tx_for(alice, bob.address(), 50, None);
+    assert!(tx.verify_stateless::<()>().is_ok());
+
+    // Any change must invalidate the signature
+    tx.fee += 1;
+    assert!(matches!(tx.verify_stateless::<()>(), Err(VerificationError::InvalidSignature)));
+}
+
+#[tokio::test]
+async fn test_multi_asset_tx_verify() {
+    let mut alice = Account::new();
+    let mut bob = Account::new();
+    let asset = Hash::new([1u8; 32]);
+
+    alice.set_balance(XELIS_ASSET, 100 * COIN_VALUE);
+    alice.set_balance(asset.clone(), 100 * COIN_VALUE);
+    bob.set_balance(XELIS_ASSET, 0);
+    bob.set_balance(asset.clone(), 0);
+
+    let tx = {
+        let mut state = AccountStateImpl {
+            balances: alice.balances.clone(),
+            nonce: alice.nonce,
+            reference: Reference {
+                topoheight: 0,
+                hash: Hash::zero(),
+            },
+        };
+
+        let data = TransactionTypeBuilder::Transfers(vec![
+            TransferBuilder {
+                amount: 10,
+                destination: bob.address(),
+                asset: XELIS_ASSET,
+                extra_data: None,
+            },
+            TransferBuilder {
+                amount: 5,
+                destination: bob.address(),
+                asset: asset.clone(),
+                extra_data: None,
+            }
+        ]);
+        let builder = TransactionBuilder::new(0, alice.keypair.get_public_key().compress(), data, FeeBuilder::Multiplier(1f64));
+        builder.build(&mut state, &alice.keypair).unwrap()
+    };
+
+    // One nonce and one fee for both assets
+    assert_eq!(tx.get_nonce(), alice.nonce);
+    assert_eq!(tx.get_source_commitments().len(), 2);
+
+    let mut state = ChainState {
+        accounts: HashMap::new(),
+        multisig: HashMap::new(),
+    };
+
+    for account in [&alice, &bob] {
+        let mut balances = HashMap::new();
+        for (asset, balance) in &account.balances {
+            balances.insert(asset.clone(), balance.ciphertext.clone().take_ciphertext().unwrap());
+        }
+        state.accounts.insert(account.keypair.get_public_key().compress(), AccountChainState {
+            balances,
+            nonce: alice.nonce,
+        });
+    }
+
+    tx.verify(&mut state).await.unwrap();
+}
+
 #[tokio::test]
 async fn test_burn_tx_verify() {
     let mut alice = Account::new();
@@ -236,6 +318,7 @@ async fn test_burn_tx_verify() {
 
     let mut state = ChainState {
         accounts: HashMap::new(),
+        multisig: HashMap::new(),
     };
 
     // Create the chain state
@@ -300,6 +383,7 @@ async fn test_max_transfers() {
     // Create the chain state
     let mut state = ChainState {
         accounts: HashMap::new(),
+        multisig: HashMap::new(),
     };
 
     // Alice
@@ -329,6 +413,137 @@ async fn test_max_transfers() {
     assert!(tx.verify(&mut state).await.is_ok());
 }
 
+#[test]
+fn test_estimate_size() {
+    let mut alice = Account::new();
+    let bob = Account::new();
+
+    alice.set_balance(XELIS_ASSET, 100 * COIN_VALUE);
+
+    for extra_data in [None, Some(DataElement::Value(DataValue::String("Hello, World!".to_owned())))] {
+        let mut state = AccountStateImpl {
+            balances: alice.balances.clone(),
+            nonce: alice.nonce,
+            reference: Reference {
+                topoheight: 0,
+                hash: Hash::zero(),
+            },
+        };
+
+        let data = TransactionTypeBuilder::Transfers(vec![TransferBuilder {
+            amount: 1,
+            destination: bob.address(),
+            asset: XELIS_ASSET,
+            extra_data,
+        }]);
+
+        let builder = TransactionBuilder::new(0, alice.keypair.get_public_key().compress(), data, FeeBuilder::Multiplier(1f64));
+        let estimated_size = builder.estimate_size();
+        let tx = builder.build(&mut state, &alice.keypair).unwrap();
+
+        assert_eq!(estimated_size, tx.size());
+    }
+}
+
+#[tokio::test]
+async fn test_multisig_tx_verify() {
+    let mut alice = Account::new();
+    let mut bob = Account::new();
+    let charlie = Account::new();
+    let dave = Account::new();
+
+    alice.set_balance(XELIS_ASSET, 100 * COIN_VALUE);
+    bob.set_balance(XELIS_ASSET, 0);
+
+    let alice_key = alice.keypair.get_public_key().compress();
+    let mut account_state = AccountStateImpl {
+        balances: alice.balances.clone(),
+        nonce: alice.nonce,
+        reference: Reference {
+            topoheight: 0,
+            hash: Hash::zero(),
+        },
+    };
+
+    // 1 of 2 multisig with charlie and dave as participants
+    let setup_tx = {
+        let data = TransactionTypeBuilder::MultiSig(MultiSigBuilder {
+            threshold: 1,
+            participants: vec![charlie.address(), dave.address()],
+        });
+        let builder = TransactionBuilder::new(MULTISIG_TX_VERSION, alice_key.clone(), data, FeeBuilder::Multiplier(1f64));
+        let estimated_size = builder.estimate_size();
+        let tx = builder.build(&mut account_state, &alice.keypair).unwrap();
+        assert_eq!(estimated_size, tx.size());
+        tx
+    };
+
+    let mut state = ChainState {
+        accounts: HashMap::new(),
+        multisig: HashMap::new(),
+    };
+
+    for account in [&alice, &bob] {
+        let mut balances = HashMap::new();
+        for (asset, balance) in &account.balances {
+            balances.insert(asset.clone(), balance.ciphertext.clone().take_ciphertext().unwrap());
+        }
+        state.accounts.insert(account.keypair.get_public_key().compress(), AccountChainState {
+            balances,
+            nonce: account.nonce,
+        });
+    }
+
+    setup_tx.verify(&mut state).await.unwrap();
+    // Verifying it doesn't change the setup
+    assert!(state.multisig.get(&alice_key).is_none());
+
+    // Applied once the transaction is executed
+    let setup = match setup_tx.get_data() {
+        TransactionType::MultiSig(payload) => payload.clone(),
+        _ => unreachable!()
+    };
+    state.set_multisig_state(&alice_key, Some(&setup)).await.unwrap();
+    assert_eq!(state.multisig.get(&alice_key).map(|setup| setup.threshold), Some(1));
+
+    // Transfer from the multisig account
+    let signer = {
+        let data = TransactionTypeBuilder::Transfers(vec![TransferBuilder {
+            amount: 1,
+            destination: bob.address(),
+            asset: XELIS_ASSET,
+            extra_data: None,
+        }]);
+        let builder = TransactionBuilder::new(MULTISIG_TX_VERSION, alice_key.clone(), data, FeeBuilder::Multiplier(1f64))
+            .with_required_signatures(1);
+        builder.build_unsigned(&mut account_state, &alice.keypair).unwrap()
+    };
+
+    // Without the signature of a participant, it must be rejected
+    let tx = signer.clone().sign(&alice.keypair);
+    assert!(matches!(tx.verify(&mut state).await, Err(VerificationError::MultiSigRequired)));
+    state.accounts.get_mut(&alice_key).unwrap().nonce = 1;
+
+    // Signed by bob who is not a participant of the setup
+    let mut invalid = signer.clone();
+    invalid.add_multisig_signature(invalid.sign_multisig(&bob.keypair, 0));
+    let tx = invalid.sign(&alice.keypair);
+    assert!(matches!(tx.verify(&mut state).await, Err(VerificationError::InvalidSignature)));
+    state.accounts.get_mut(&alice_key).unwrap().nonce = 1;
+
+    // Signed by dave
+    let mut signer = signer;
+    signer.add_multisig_signature(signer.sign_multisig(&dave.keypair, 1));
+    let tx = signer.sign(&alice.keypair);
+    assert_eq!(tx.get_multisig_hash(), {
+        let mut unsigned = tx.clone();
+        unsigned.multisig = None;
+        unsigned.get_multisig_hash()
+    });
+
+    tx.verify(&mut state).await.unwrap();
+}
+
 #[async_trait]
 impl<'a> BlockchainVerificationState<'a, ()> for ChainState {
 
@@ -385,6 +600,27 @@ impl<'a> BlockchainVerificationState<'a, ()> for ChainState {
     ) -> Result<(), ()> {
         self.accounts.get_mut(account).map(|account| account.nonce = new_nonce).ok_or(())
     }
+
+    /// Get the multisig setup of an account
+    async fn get_multisig_state<'b>(
+        &'b mut self,
+        account: &'a PublicKey
+    ) -> Result<Option<&'b MultiSigPayload>, ()> {
+        Ok(self.multisig.get(account))
+    }
+
+    /// Apply a new multisig setup to an account
+    async fn set_multisig_state(
+        &mut self,
+        account: &'a PublicKey,
+        setup: Option<&MultiSigPayload>
+    ) -> Result<(), ()> {
+        match setup {
+            Some(setup) => self.multisig.insert(account.clone(), setup.clone()),
+            None => self.multisig.remove(account)
+        };
+        Ok(())
+    }
 }
 
 impl FeeHelper for AccountStateImpl {
@@ -396,8 +632,8 @@ impl FeeHelper for AccountStateImpl {
 }
 
 impl AccountState for AccountStateImpl {
-    fn is_mainnet(&self) -> bool {
-        false
+    fn get_network(&self) -> Network {
+        Network::Testnet
     }
 
     fn get_account_balance(&self, asset: &Hash) -> Result<u64, Self::Error> {
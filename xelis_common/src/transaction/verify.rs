@@ -2,8 +2,8 @@ use bulletproofs::RangeProof;
 use curve25519_dalek::{ristretto::CompressedRistretto, traits::Identity, RistrettoPoint, Scalar};
 use log::{debug, trace};
 use merlin::Transcript;
-use crate::{config::XELIS_ASSET, crypto::{elgamal::{Ciphertext, CompressedPublicKey, DecompressionError, DecryptHandle, PedersenCommitment}, proofs::{BatchCollector, ProofVerificationError, BP_GENS, BULLET_PROOF_SIZE, PC_GENS}, Hash, ProtocolTranscript, SIGNATURE_SIZE}, serializer::Serializer, transaction::{EXTRA_DATA_LIMIT_SIZE, MAX_TRANSFER_COUNT}};
-use super::{Reference, Role, Transaction, TransactionType, TransferPayload};
+use crate::{config::XELIS_ASSET, crypto::{elgamal::{Ciphertext, CompressedPublicKey, DecompressionError, DecryptHandle, PedersenCommitment}, proofs::{BatchCollector, ProofVerificationError, BP_GENS, BULLET_PROOF_SIZE, PC_GENS}, Hash, ProtocolTranscript, SIGNATURE_SIZE}, serializer::Serializer, transaction::{EXTRA_DATA_LIMIT_SIZE, MAX_TRANSFER_COUNT, MULTISIG_TX_VERSION}};
+use super::{multisig::MultiSigPayload, Reference, Role, Transaction, TransactionType, TransferPayload};
 use thiserror::Error;
 use std::iter;
 use async_trait::async_trait;
@@ -59,6 +59,19 @@ pub trait BlockchainVerificationState<'a, E> {
         account: &'a CompressedPublicKey,
         new_nonce: u64
     ) -> Result<(), E>;
+
+    /// Get the multisig setup of an account, None if it's not a multisig
+    async fn get_multisig_state<'b>(
+        &'b mut self,
+        account: &'a CompressedPublicKey
+    ) -> Result<Option<&'b MultiSigPayload>, E>;
+
+    /// Apply a new multisig setup to an account, None removes it
+    async fn set_multisig_state(
+        &mut self,
+        account: &'a CompressedPublicKey,
+        setup: Option<&MultiSigPayload>
+    ) -> Result<(), E>;
 }
 
 #[derive(Error, Debug, Clone)]
@@ -71,6 +84,16 @@ pub enum VerificationError<T> {
     SenderIsReceiver,
     #[error("Invalid signature")]
     InvalidSignature,
+    #[error("Invalid multisig setup")]
+    InvalidMultiSigSetup,
+    #[error("Multisig signatures are required for this account")]
+    MultiSigRequired,
+    #[error("Account is not a multisig")]
+    MultiSigNotConfigured,
+    #[error("Not enough multisig signatures: {0} provided, {1} required")]
+    MultiSigThreshold(usize, u8),
+    #[error("Invalid multisig participant {0}")]
+    MultiSigParticipant(u8),
     #[error("Proof verification error: {0}")]
     Proof(#[from] ProofVerificationError),
 }
@@ -127,7 +150,9 @@ impl Transaction {
                 if *asset == payload.asset {
                     output += Scalar::from(payload.amount)
                 }
-            }
+            },
+            // Only the fees are paid
+            TransactionType::MultiSig(_) => {}
         }
 
         Ok(output)
@@ -181,40 +206,70 @@ impl Transaction {
                 .iter()
                 .all(|transfer| has_commitment_for_asset(&transfer.asset)),
             TransactionType::Burn(payload) => has_commitment_for_asset(&payload.asset),
+            TransactionType::MultiSig(_) => true,
         }
     }
 
-    // internal, does not verify the range proof
-    // returns (transcript, commitments for range proof)
-    async fn pre_verify<'a, E, B: BlockchainVerificationState<'a, E>>(
-        &'a self,
-        state: &mut B,
-        sigma_batch_collector: &mut BatchCollector,
-    ) -> Result<(Transcript, Vec<(RistrettoPoint, CompressedRistretto)>), VerificationError<E>>
-    {
-        trace!("Pre-verifying transaction");
-        state.pre_verify_tx(&self).await
-            .map_err(VerificationError::State)?;
+    // Verify that enough participants of the multisig setup signed the transaction
+    fn verify_multisig<E>(&self, setup: Option<&MultiSigPayload>) -> Result<(), VerificationError<E>> {
+        match (setup, &self.multisig) {
+            (Some(setup), Some(multisig)) => {
+                if multisig.len() < setup.threshold as usize {
+                    debug!("not enough multisig signatures");
+                    return Err(VerificationError::MultiSigThreshold(multisig.len(), setup.threshold));
+                }
 
-        // First, check the nonce
-        let account_nonce = state.get_account_nonce(&self.source).await
-            .map_err(VerificationError::State)?;
+                let hash = self.get_multisig_hash();
+                for signature in multisig.get_signatures() {
+                    let participant = setup.participants.get_index(signature.id as usize)
+                        .ok_or(VerificationError::MultiSigParticipant(signature.id))?
+                        .decompress()
+                        .map_err(|err| VerificationError::Proof(err.into()))?;
 
-        if account_nonce != self.nonce {
-            return Err(VerificationError::InvalidNonce);
+                    if !signature.signature.verify(hash.as_bytes(), &participant) {
+                        debug!("multisig signature of participant {} is invalid", signature.id);
+                        return Err(VerificationError::InvalidSignature);
+                    }
+                }
+
+                Ok(())
+            },
+            (Some(_), None) => Err(VerificationError::MultiSigRequired),
+            (None, Some(_)) => Err(VerificationError::MultiSigNotConfigured),
+            (None, None) => Ok(())
         }
+    }
 
-        // Nonce is valid, update it for next transactions if any
-        state
-            .update_account_nonce(&self.source, self.nonce + 1).await
-            .map_err(VerificationError::State)?;
+    // Multisig setup to apply to the source account if the transaction changes it
+    fn get_new_multisig_setup(&self) -> Option<Option<&MultiSigPayload>> {
+        match &self.data {
+            TransactionType::MultiSig(payload) => Some(if payload.is_delete() { None } else { Some(payload) }),
+            _ => None
+        }
+    }
 
+    // Verify the format and the signature of the transaction
+    // These checks don't depend on the chain state and can be done in parallel
+    pub fn verify_stateless<E>(&self) -> Result<(), VerificationError<E>> {
         if !self.verify_commitment_assets() {
             debug!("Invalid commitment assets");
             return Err(VerificationError::Proof(ProofVerificationError::Format));
         }
 
-        let transfers_decompressed = if let TransactionType::Transfers(transfers) = &self.data {
+        if let TransactionType::MultiSig(payload) = &self.data {
+            // Signatures of the participants can't be included before this version
+            if self.version < MULTISIG_TX_VERSION {
+                debug!("multisig setup requires version {}", MULTISIG_TX_VERSION);
+                return Err(VerificationError::Proof(ProofVerificationError::Format));
+            }
+
+            if !payload.is_valid(&self.source) {
+                debug!("invalid multisig setup");
+                return Err(VerificationError::InvalidMultiSigSetup);
+            }
+        }
+
+        if let TransactionType::Transfers(transfers) = &self.data {
             if transfers.len() > MAX_TRANSFER_COUNT || transfers.is_empty() {
                 debug!("incorrect transfers size: {}", transfers.len());
                 return Err(VerificationError::Proof(ProofVerificationError::Format));
@@ -237,7 +292,53 @@ impl Transaction {
                 debug!("extra data size is too large");
                 return Err(VerificationError::Proof(ProofVerificationError::Format));
             }
+        }
+
+        let owner = self
+            .source
+            .decompress()
+            .map_err(|err| VerificationError::Proof(err.into()))?;
+
+        let bytes = self.to_bytes();
+        if !self.signature.verify(&bytes[..bytes.len() - SIGNATURE_SIZE], &owner) {
+            debug!("transaction signature is invalid");
+            return Err(VerificationError::InvalidSignature);
+        }
+
+        Ok(())
+    }
+
+    // internal, does not verify the range proof
+    // returns (transcript, commitments for range proof)
+    async fn pre_verify<'a, E, B: BlockchainVerificationState<'a, E>>(
+        &'a self,
+        state: &mut B,
+        sigma_batch_collector: &mut BatchCollector,
+        stateless_verified: bool,
+    ) -> Result<(Transcript, Vec<(RistrettoPoint, CompressedRistretto)>), VerificationError<E>>
+    {
+        trace!("Pre-verifying transaction");
+        state.pre_verify_tx(&self).await
+            .map_err(VerificationError::State)?;
+
+        // First, check the nonce
+        let account_nonce = state.get_account_nonce(&self.source).await
+            .map_err(VerificationError::State)?;
+
+        if account_nonce != self.nonce {
+            return Err(VerificationError::InvalidNonce);
+        }
+
+        // Nonce is valid, update it for next transactions if any
+        state
+            .update_account_nonce(&self.source, self.nonce + 1).await
+            .map_err(VerificationError::State)?;
 
+        if !stateless_verified {
+            self.verify_stateless()?;
+        }
+
+        let transfers_decompressed = if let TransactionType::Transfers(transfers) = &self.data {
             transfers
                 .iter()
                 .map(DecompressedTransferCt::decompress)
@@ -261,12 +362,11 @@ impl Transaction {
 
         let mut transcript = Self::prepare_transcript(self.version, &self.source, self.fee, self.nonce);
 
-        // 0. Verify Signature
-        let bytes = self.to_bytes();
-        if !self.signature.verify(&bytes[..bytes.len() - SIGNATURE_SIZE], &owner) {
-            debug!("transaction signature is invalid");
-            return Err(VerificationError::InvalidSignature);
-        }
+        // 0. Verify the signatures of the multisig participants
+        let setup = state.get_multisig_state(&self.source).await
+            .map_err(VerificationError::State)?;
+        // A new setup is only applied with the transaction, once all its proofs are verified
+        self.verify_multisig(setup)?;
 
         // 1. Verify CommitmentEqProofs
         trace!("verifying commitments eq proofs");
@@ -410,12 +510,29 @@ impl Transaction {
     pub async fn verify_batch<'a, T: AsRef<Transaction>, E, B: BlockchainVerificationState<'a, E>>(
         txs: &'a [T],
         state: &mut B,
+    ) -> Result<(), VerificationError<E>> {
+        Self::verify_batch_internal(txs, state, false).await
+    }
+
+    /// Verify a batch of transactions already checked using `verify_stateless`.
+    /// Only the checks depending on `state` and the proofs are done.
+    pub async fn verify_batch_stateless_verified<'a, T: AsRef<Transaction>, E, B: BlockchainVerificationState<'a, E>>(
+        txs: &'a [T],
+        state: &mut B,
+    ) -> Result<(), VerificationError<E>> {
+        Self::verify_batch_internal(txs, state, true).await
+    }
+
+    async fn verify_batch_internal<'a, T: AsRef<Transaction>, E, B: BlockchainVerificationState<'a, E>>(
+        txs: &'a [T],
+        state: &mut B,
+        stateless_verified: bool,
     ) -> Result<(), VerificationError<E>> {
         trace!("Verifying batch of {} transactions", txs.len());
         let mut sigma_batch_collector = BatchCollector::default();
         let mut prepared = Vec::with_capacity(txs.len());
         for tx in txs {
-            let (transcript, commitments) = tx.as_ref().pre_verify(state, &mut sigma_batch_collector).await?;
+            let (transcript, commitments) = tx.as_ref().pre_verify(state, &mut sigma_batch_collector, stateless_verified).await?;
             prepared.push((transcript, commitments));
         }
 
@@ -444,7 +561,7 @@ impl Transaction {
         state: &mut B,
     ) -> Result<(), VerificationError<E>> {
         let mut sigma_batch_collector = BatchCollector::default();
-        let (mut transcript, commitments) = self.pre_verify(state, &mut sigma_batch_collector).await?;
+        let (mut transcript, commitments) = self.pre_verify(state, &mut sigma_batch_collector, false).await?;
 
         trace!("Verifying sigma proofs");
         sigma_batch_collector
@@ -523,6 +640,10 @@ impl Transaction {
                 *current_bal += receiver_ct;
             }
         }
+
+        if let Some(setup) = self.get_new_multisig_setup() {
+            state.set_multisig_state(&self.source, setup).await?;
+        }
     
         Ok(())
     }
@@ -640,6 +761,11 @@ impl Transaction {
             }
         }
 
+        if let Some(setup) = self.get_new_multisig_setup() {
+            state.set_multisig_state(&self.source, setup).await
+                .map_err(VerificationError::State)?;
+        }
+
         Ok(())
     }
 }
\ No newline at end of file
@@ -9,7 +9,9 @@ use xelis_common::{
     difficulty::Difficulty,
     network::Network,
     time::TimestampSeconds,
+    transaction::MULTISIG_TX_VERSION
 };
+use crate::core::hard_fork::{is_valid_hard_forks_schedule, HardFork};
 
 // In case of potential forks, have a unique network id to not connect to others compatible chains
 pub const NETWORK_ID_SIZE: usize = 16;
@@ -32,10 +34,12 @@ pub const BLOCK_TIME_MILLIS: u64 = 15 * MILLIS_PER_SECOND; // 15s block time
 // This is to prevent spamming the network with low difficulty blocks
 // This is active only on mainnet mode
 pub const MAINNET_MINIMUM_DIFFICULTY: Difficulty = Difficulty::from_u64(BLOCK_TIME_MILLIS * 20);
-// Testnet & Devnet minimum difficulty
+// Testnet minimum difficulty
 pub const OTHER_MINIMUM_DIFFICULTY: Difficulty = Difficulty::from_u64(BLOCK_TIME_MILLIS);
 // This is also used as testnet and devnet minimum difficulty
 pub const GENESIS_BLOCK_DIFFICULTY: Difficulty = Difficulty::from_u64(1);
+// Devnet difficulty is fixed so blocks can be generated instantly
+pub const DEV_DIFFICULTY: Difficulty = GENESIS_BLOCK_DIFFICULTY;
 // 1024 * 1024 + (256 * 1024) bytes = 1.25 MB maximum size per block with txs
 pub const MAX_BLOCK_SIZE: usize = (1024 * 1024) + (256 * 1024);
 // 2 seconds maximum in future (prevent any attack on reducing difficulty but keep margin for unsynced devices)
@@ -47,6 +51,8 @@ pub const PRUNE_SAFETY_LIMIT: u64 = STABLE_LIMIT * 10;
 
 // BlockDAG rules
 pub const STABLE_LIMIT: u64 = 8; // in how many height we consider the block stable
+// Minimum reorg depth that can be configured, below it side blocks would be rejected
+pub const MIN_REORG_DEPTH: u64 = 2;
 
 // Emission rules
 // 15%, 10%, 5% per block going to dev address
@@ -87,6 +93,20 @@ pub const EMISSION_SPEED_FACTOR: u64 = 20;
 // (testnet/mainnet format is converted lazily later)
 pub const DEV_ADDRESS: &str = "xel:vs3mfyywt0fjys0rgslue7mm4wr23xdgejsjk0ld7f2kxng4d4nqqnkdufz";
 
+// Mempool rules
+// maximum TXs kept while waiting on the previous nonces of their source
+pub const FUTURE_TXS_POOL_SIZE: usize = 1024;
+// maximum TXs of a same source kept in the future pool
+pub const FUTURE_TXS_MAX_PER_SOURCE: usize = 8;
+// TXs with a nonce above the next expected one + N are rejected
+pub const FUTURE_TX_MAX_NONCE_GAP: u64 = 16;
+// TXs are deleted from mempool if not included in a block after N seconds by default
+pub const MEMPOOL_DEFAULT_TX_EXPIRATION: u64 = 3 * 60 * 60;
+// TXs submitted through our RPC are rebroadcasted during N seconds maximum
+pub const LOCAL_TX_REBROADCAST_EXPIRATION: u64 = 60 * 60;
+// Mempool state is saved for the last N blocks added
+pub const MEMPOOL_HISTORY_SIZE: usize = 240;
+
 // Chain sync config
 // minimum X seconds between each chain sync request per peer
 pub const CHAIN_SYNC_DELAY: u64 = 5;
@@ -104,6 +124,8 @@ pub const CHAIN_SYNC_DEFAULT_RESPONSE_BLOCKS: usize = 4096;
 pub const CHAIN_SYNC_RESPONSE_MAX_BLOCKS: usize = 16384;
 // send last 10 heights
 pub const CHAIN_SYNC_TOP_BLOCKS: usize = 10;
+// maximum peers used to download the blocks bodies in parallel during chain sync
+pub const CHAIN_SYNC_BODIES_MAX_PEERS: usize = 8;
 
 // P2p rules
 // time between each ping
@@ -118,6 +140,12 @@ pub const P2P_DEFAULT_MAX_PEERS: usize = 32;
 pub const P2P_EXTEND_PEERLIST_DELAY: u64 = 60;
 // Peer wait on error accept new p2p connections in seconds
 pub const P2P_PEER_WAIT_ON_ERROR: u64 = 15;
+// maximum blocks kept in the orphan pool while waiting on their parents
+pub const P2P_ORPHAN_POOL_MAX_BLOCKS: usize = 256;
+// orphans above our height + N are discarded, the chain sync will retrieve them
+pub const P2P_ORPHAN_MAX_DISTANCE: u64 = STABLE_LIMIT;
+// time in seconds between each rebroadcast of the TXs submitted through our RPC
+pub const P2P_TX_REBROADCAST_DELAY: u64 = 60;
 
 // Peer rules
 // number of seconds to reset the counter
@@ -182,6 +210,86 @@ pub fn get_genesis_block_hash(network: &Network) -> &'static Hash {
     }
 }
 
+// Checkpoints embedded for both networks as (topoheight, block hash)
+// Blocks proven to lead to the latest checkpoint are not fully verified during the sync
+// Genesis block is always a checkpoint
+const MAINNET_CHECKPOINTS: [(u64, Hash); 1] = [
+    (0, MAINNET_GENESIS_BLOCK_HASH)
+];
+const TESTNET_CHECKPOINTS: [(u64, Hash); 1] = [
+    (0, TESTNET_GENESIS_BLOCK_HASH)
+];
+
+// Checkpoints based on network selected
+pub fn get_checkpoints(network: &Network) -> &'static [(u64, Hash)] {
+    match network {
+        Network::Mainnet => &MAINNET_CHECKPOINTS,
+        Network::Testnet => &TESTNET_CHECKPOINTS,
+        Network::Dev => &[]
+    }
+}
+
+// Hard forks scheduled for each network
+// Each entry is activated starting its height and must be sorted by height
+// Genesis block is always built with the first one
+const MAINNET_HARD_FORKS: [HardFork; 2] = [
+    HardFork {
+        height: 0,
+        version: 0,
+        max_tx_version: 0,
+        changelog: "Initial version"
+    },
+    HardFork {
+        height: 1_000_000,
+        version: 1,
+        max_tx_version: MULTISIG_TX_VERSION,
+        changelog: "MultiSig accounts"
+    }
+];
+const TESTNET_HARD_FORKS: [HardFork; 2] = [
+    HardFork {
+        height: 0,
+        version: 0,
+        max_tx_version: 0,
+        changelog: "Initial version"
+    },
+    HardFork {
+        height: 500_000,
+        version: 1,
+        max_tx_version: MULTISIG_TX_VERSION,
+        changelog: "MultiSig accounts"
+    }
+];
+// Dev network activates everything directly after the genesis block
+const DEV_HARD_FORKS: [HardFork; 2] = [
+    HardFork {
+        height: 0,
+        version: 0,
+        max_tx_version: 0,
+        changelog: "Initial version"
+    },
+    HardFork {
+        height: 1,
+        version: 1,
+        max_tx_version: MULTISIG_TX_VERSION,
+        changelog: "MultiSig accounts"
+    }
+];
+
+// Reject any invalid schedule at compile time
+const _: () = assert!(is_valid_hard_forks_schedule(&MAINNET_HARD_FORKS));
+const _: () = assert!(is_valid_hard_forks_schedule(&TESTNET_HARD_FORKS));
+const _: () = assert!(is_valid_hard_forks_schedule(&DEV_HARD_FORKS));
+
+// Hard forks based on the network selected
+pub const fn get_hard_forks(network: &Network) -> &'static [HardFork] {
+    match network {
+        Network::Mainnet => &MAINNET_HARD_FORKS,
+        Network::Testnet => &TESTNET_HARD_FORKS,
+        Network::Dev => &DEV_HARD_FORKS,
+    }
+}
+
 // Mainnet seed nodes
 const MAINNET_SEED_NODES: [&str; 5] = [
     // France
@@ -217,6 +325,7 @@ pub const fn get_seed_nodes(network: &Network) -> &[&str] {
 pub const fn get_minimum_difficulty(network: &Network) -> Difficulty {
     match network {
         Network::Mainnet => MAINNET_MINIMUM_DIFFICULTY,
+        Network::Dev => DEV_DIFFICULTY,
         _ => OTHER_MINIMUM_DIFFICULTY,
     }
 }
\ No newline at end of file
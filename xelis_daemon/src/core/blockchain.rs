@@ -7,9 +7,12 @@ use xelis_common::{
         daemon::{
             BlockOrderedEvent,
             BlockOrphanedEvent,
+            ChainReorganizedEvent,
             BlockType,
+            MempoolHistoryEntry,
             NotifyEvent,
             StableHeightChangedEvent,
+            TransactionConflictedEvent,
             TransactionExecutedEvent,
             TransactionResponse
         },
@@ -23,7 +26,6 @@ use xelis_common::{
     },
     config::{
         COIN_DECIMALS,
-        MAXIMUM_SUPPLY,
         MAX_TRANSACTION_SIZE,
         TIPS_LIMIT,
         XELIS_ASSET
@@ -37,11 +39,12 @@ use xelis_common::{
     difficulty::{check_difficulty, CumulativeDifficulty, Difficulty},
     immutable::Immutable,
     network::Network,
-    serializer::Serializer,
+    serializer::{Reader, Serializer, Writer},
     time::{
         get_current_time_in_millis,
         get_current_time_in_seconds,
-        TimestampMillis
+        TimestampMillis,
+        TimestampSeconds
     },
     transaction::{verify::BlockchainVerificationState, Transaction, TransactionType},
     utils::{calculate_tx_fee, format_xelis},
@@ -52,18 +55,23 @@ use crate::{
         get_genesis_block_hash, get_hex_genesis_block, get_minimum_difficulty,
         BLOCK_TIME_MILLIS, CHAIN_SYNC_RESPONSE_MAX_BLOCKS, CHAIN_SYNC_RESPONSE_MIN_BLOCKS,
         DEFAULT_CACHE_SIZE, DEFAULT_P2P_BIND_ADDRESS, DEFAULT_RPC_BIND_ADDRESS, DEV_FEES,
-        DEV_PUBLIC_KEY, EMISSION_SPEED_FACTOR, GENESIS_BLOCK_DIFFICULTY, MAX_BLOCK_SIZE,
-        MILLIS_PER_SECOND, P2P_DEFAULT_MAX_PEERS, SIDE_BLOCK_REWARD_MAX_BLOCKS, PRUNE_SAFETY_LIMIT,
+        DEV_PUBLIC_KEY, FUTURE_TXS_MAX_PER_SOURCE, FUTURE_TXS_POOL_SIZE, FUTURE_TX_MAX_NONCE_GAP, GENESIS_BLOCK_DIFFICULTY, LOCAL_TX_REBROADCAST_EXPIRATION, MAX_BLOCK_SIZE, MEMPOOL_DEFAULT_TX_EXPIRATION, MEMPOOL_HISTORY_SIZE,
+        MIN_REORG_DEPTH, P2P_DEFAULT_MAX_PEERS, SIDE_BLOCK_REWARD_MAX_BLOCKS, PRUNE_SAFETY_LIMIT,
         SIDE_BLOCK_REWARD_PERCENT, SIDE_BLOCK_REWARD_MIN_PERCENT, STABLE_LIMIT, TIMESTAMP_IN_FUTURE_LIMIT
     },
     core::{
         blockdag,
+        checkpoint::{load_checkpoints, Checkpoint},
+        snapshot::{SnapshotHeader, SnapshotReader, SnapshotWriter},
         difficulty,
+        emission::get_block_reward,
         error::BlockchainError,
+        future_pool::FutureTxPool,
+        hard_fork,
         mempool::Mempool,
         nonce_checker::NonceChecker,
         simulator::Simulator,
-        storage::{DagOrderProvider, DifficultyProvider, Storage},
+        storage::{DagOrderProvider, DifficultyProvider, Storage, StorageBackend},
         tx_selector::{TxSelector, TxSelectorEntry},
         state::{ChainState, ApplicableChainState},
     },
@@ -85,15 +93,21 @@ use std::{
         HashSet,
         VecDeque
     },
+    fs,
+    io,
     net::SocketAddr,
     num::NonZeroUsize,
     sync::{
-        atomic::{AtomicU64, Ordering},
+        atomic::{AtomicBool, AtomicU64, Ordering},
         Arc
     },
+    thread,
     time::Instant
 };
-use tokio::sync::{Mutex, RwLock};
+use tokio::{
+    sync::{Mutex, RwLock},
+    task
+};
 use log::{info, error, debug, warn, trace};
 use rand::Rng;
 
@@ -101,7 +115,9 @@ use super::storage::{
     BlocksAtHeightProvider,
     ClientProtocolProvider,
     PrunedTopoheightProvider,
-    AccountProvider
+    AccountProvider,
+    SnapshotProvider,
+    BurnedSupplyProvider
 };
 
 #[derive(Debug, clap::Args)]
@@ -134,6 +150,10 @@ pub struct Config {
     /// Set LRUCache size (0 = disabled).
     #[clap(long, default_value_t = DEFAULT_CACHE_SIZE)]
     pub cache_size: usize,
+    /// Database engine used to store the chain (sled, or rocksdb when built with the rocksdb feature).
+    /// Each backend has its own directory, use a snapshot to migrate the chain.
+    #[clap(long, default_value_t = StorageBackend::Sled)]
+    pub storage_backend: StorageBackend,
     /// Disable GetWork Server (WebSocket for miners).
     #[clap(long)]
     pub disable_getwork_server: bool,
@@ -148,6 +168,13 @@ pub struct Config {
     /// before the top.
     #[clap(long)]
     pub auto_prune_keep_n_blocks: Option<u64>,
+    /// Enable the pruning mode and delete the transactions
+    /// of the blocks older than N blocks before the top.
+    /// 
+    /// Block headers and the chain state are kept,
+    /// so the node can still validate new blocks while using less disk space.
+    #[clap(long)]
+    pub prune: Option<u64>,
     /// Allow fast sync mode.
     /// 
     /// Sync a bootstrapped chain if your local copy is outdated.
@@ -164,6 +191,35 @@ pub struct Config {
     /// It is not enabled by default because it will requests several blocks before validating each previous.
     #[clap(long)]
     pub allow_boost_sync: bool,
+    /// Load additional checkpoints from a JSON file.
+    /// 
+    /// The file must be signed by the developer key
+    /// unless `--allow-unsigned-checkpoints` is set.
+    #[clap(long)]
+    pub checkpoints_file: Option<String>,
+    /// Accept a checkpoints file that is not signed by the developer key.
+    /// 
+    /// Use it only with checkpoints that you trust.
+    #[clap(long)]
+    pub allow_unsigned_checkpoints: bool,
+    /// Disable the checkpoints sync.
+    /// 
+    /// All blocks are fully verified, even those leading to the latest checkpoint.
+    /// Checkpoints are still enforced.
+    #[clap(long)]
+    pub disable_checkpoints_sync: bool,
+    /// Maximum depth in blocks of a chain reorganization.
+    /// 
+    /// Blocks that would reorganize the chain deeper are rejected before any change.
+    /// It must be between 2 and the stable limit (default).
+    #[clap(long)]
+    pub max_reorg_depth: Option<u64>,
+    /// Threads count used to verify the TXs signatures of a block.
+    /// 
+    /// By default, it uses all the available threads.
+    /// Set it to 1 to verify them on the current thread only.
+    #[clap(long)]
+    pub txs_verification_threads: Option<usize>,
     /// Configure the maximum chain response size.
     /// 
     /// This is useful for low devices who want to reduce resources usage
@@ -182,7 +238,36 @@ pub struct Config {
     /// 
     /// This is useful for seed nodes under heavy load or for nodes that don't want to connect to others.
     #[clap(long, default_value = "false")]
-    pub disable_outgoing_connections: bool
+    pub disable_outgoing_connections: bool,
+    /// Disable the mempool persistence.
+    /// 
+    /// By default, pending TXs are saved on disk at shutdown
+    /// and verified again to be added back in the mempool at startup.
+    #[clap(long)]
+    pub disable_mempool_persistence: bool,
+    /// Enable the `validate_transaction` RPC method.
+    /// 
+    /// It fully verifies the TX proofs without any fee paid,
+    /// so it is disabled by default to not be abused on a public node.
+    #[clap(long)]
+    pub enable_tx_validation_rpc: bool,
+    /// Minimum fee per byte required for a TX to be accepted in our mempool and relayed.
+    /// 
+    /// This is a policy of the node only, TXs included in blocks are not affected.
+    #[clap(long, default_value_t = 0)]
+    pub min_relay_fee_per_byte: u64,
+    /// Minimum amount burned by a burn TX to be accepted in our mempool and relayed.
+    /// 
+    /// Transfers amounts are encrypted, so dust transfers can't be detected by the node.
+    /// This is a policy of the node only, TXs included in blocks are not affected.
+    #[clap(long, default_value_t = 0)]
+    pub min_burn_amount: u64,
+    /// Delete the TXs from mempool not included in a block after N seconds.
+    /// 
+    /// Next TXs of the same account are deleted too as they depend on it.
+    /// Set it to 0 to keep them until they are included or invalid.
+    #[clap(long, default_value_t = MEMPOOL_DEFAULT_TX_EXPIRATION)]
+    pub mempool_tx_expiration: u64
 }
 
 pub struct Blockchain<S: Storage> {
@@ -218,7 +303,34 @@ pub struct Blockchain<S: Storage> {
     // using base hash, current tip hash and base height, this cache is used to store the DAG order
     full_order_cache: Mutex<LruCache<(Hash, Hash, u64), IndexSet<Hash>>>,
     // auto prune mode if enabled, will delete all blocks every N and keep only N top blocks (topoheight based)
-    auto_prune_keep_n_blocks: Option<u64>
+    auto_prune_keep_n_blocks: Option<u64>,
+    // prune mode if enabled, will delete the transactions of the blocks older than the N top blocks
+    prune_bodies_keep_n_blocks: Option<u64>,
+    // blocks hashes expected at some topoheights, sorted by topoheight
+    checkpoints: Vec<Checkpoint>,
+    // skip the PoW and TXs proofs verification of the blocks leading to the latest checkpoint
+    checkpoints_sync: AtomicBool,
+    // blocks not yet in chain proven to be ancestors of the latest checkpoint by their headers
+    checkpoint_ancestors: Mutex<HashSet<Hash>>,
+    // maximum depth in height of a reorg, blocks going deeper are rejected
+    max_reorg_depth: u64,
+    // threads count used to verify the TXs of a block before applying them
+    txs_verification_threads: usize,
+    // file where the mempool is saved at shutdown, None if persistence is disabled
+    mempool_file: Option<String>,
+    // TXs waiting on the previous nonces of their source before being added in the mempool
+    future_txs: Mutex<FutureTxPool<Arc<Transaction>>>,
+    // TXs submitted through our RPC with the time they were submitted
+    // They are rebroadcasted until they are included in a block
+    local_txs: Mutex<HashMap<Hash, TimestampSeconds>>,
+    // is the validate_transaction RPC method allowed
+    tx_validation_rpc: bool,
+    // minimum fee per byte and burned amount for a TX to be accepted in mempool, on top of the consensus rules
+    relay_policy: RelayPolicy,
+    // seconds after which a TX not included is deleted from mempool, 0 if disabled
+    mempool_tx_expiration: u64,
+    // mempool state after each of the last blocks added
+    mempool_history: Mutex<VecDeque<MempoolHistoryEntry>>
 }
 
 impl<S: Storage> Blockchain<S> {
@@ -237,6 +349,13 @@ impl<S: Storage> Blockchain<S> {
                 }
             }
 
+            if let Some(keep_only) = config.prune {
+                if keep_only < PRUNE_SAFETY_LIMIT {
+                    error!("Prune mode should keep at least {} blocks", PRUNE_SAFETY_LIMIT);
+                    return Err(BlockchainError::PruneMode.into())
+                }
+            }
+
             if let Some(size) = config.max_chain_response_size {
                 if size < CHAIN_SYNC_RESPONSE_MIN_BLOCKS || size > CHAIN_SYNC_RESPONSE_MAX_BLOCKS {
                     error!("Max chain response size should be in inclusive range of [{}-{}]", CHAIN_SYNC_RESPONSE_MIN_BLOCKS, CHAIN_SYNC_RESPONSE_MAX_BLOCKS);
@@ -244,12 +363,39 @@ impl<S: Storage> Blockchain<S> {
                 }
             }
 
+            if let Some(depth) = config.max_reorg_depth {
+                if depth < MIN_REORG_DEPTH || depth > STABLE_LIMIT {
+                    error!("Max reorg depth should be in inclusive range of [{}-{}]", MIN_REORG_DEPTH, STABLE_LIMIT);
+                    return Err(BlockchainError::ConfigMaxReorgDepth.into())
+                }
+            }
+
+            if config.txs_verification_threads == Some(0) {
+                error!("TXs verification threads count should be at least 1");
+                return Err(BlockchainError::ConfigTxsVerificationThreads.into())
+            }
+
             if config.allow_boost_sync && config.allow_fast_sync {
                 error!("Boost sync and fast sync can't be enabled at the same time!");
                 return Err(BlockchainError::ConfigSyncMode.into())
             }
         }
 
+        let checkpoints = load_checkpoints(&network, config.checkpoints_file.as_ref(), config.allow_unsigned_checkpoints)?;
+        debug!("{} checkpoints loaded", checkpoints.len());
+
+        let txs_verification_threads = match config.txs_verification_threads {
+            Some(threads) => threads,
+            None => thread::available_parallelism().map(NonZeroUsize::get).unwrap_or(1)
+        };
+        debug!("{} threads used for TXs verification", txs_verification_threads);
+
+        let mempool_file = if config.disable_mempool_persistence {
+            None
+        } else {
+            Some(format!("{}mempool-{}.bin", config.dir_path.as_deref().unwrap_or_default(), network.to_string().to_lowercase()))
+        };
+
         let on_disk = storage.has_blocks().await;
         let (height, topoheight) = if on_disk {
             info!("Reading last metadata available...");
@@ -275,7 +421,23 @@ impl<S: Storage> Blockchain<S> {
             tip_base_cache: Mutex::new(LruCache::new(NonZeroUsize::new(1024).unwrap())),
             tip_work_score_cache: Mutex::new(LruCache::new(NonZeroUsize::new(1024).unwrap())),
             full_order_cache: Mutex::new(LruCache::new(NonZeroUsize::new(1024).unwrap())),
-            auto_prune_keep_n_blocks: config.auto_prune_keep_n_blocks
+            auto_prune_keep_n_blocks: config.auto_prune_keep_n_blocks,
+            prune_bodies_keep_n_blocks: config.prune,
+            checkpoints,
+            checkpoints_sync: AtomicBool::new(!config.disable_checkpoints_sync),
+            checkpoint_ancestors: Mutex::new(HashSet::new()),
+            max_reorg_depth: config.max_reorg_depth.unwrap_or(STABLE_LIMIT),
+            txs_verification_threads,
+            mempool_file,
+            future_txs: Mutex::new(FutureTxPool::new(NonZeroUsize::new(FUTURE_TXS_POOL_SIZE).unwrap())),
+            local_txs: Mutex::new(HashMap::new()),
+            tx_validation_rpc: config.enable_tx_validation_rpc,
+            relay_policy: RelayPolicy {
+                min_fee_per_byte: config.min_relay_fee_per_byte,
+                min_burn_amount: config.min_burn_amount
+            },
+            mempool_tx_expiration: config.mempool_tx_expiration,
+            mempool_history: Mutex::new(VecDeque::with_capacity(MEMPOOL_HISTORY_SIZE))
         };
 
         // include genesis block
@@ -289,6 +451,14 @@ impl<S: Storage> Blockchain<S> {
             blockchain.set_difficulty(difficulty).await;
         }
 
+        // Index the burned supply of a chain synced before it was tracked by asset
+        {
+            let mut storage = blockchain.get_storage().write().await;
+            if storage.get_burned_supply_indexed_from()?.is_none() {
+                blockchain.rebuild_burned_supply(&mut *storage).await?;
+            }
+        }
+
         // now compute the stable height
         {
             debug!("Retrieving tips for computing current stable height");
@@ -299,6 +469,17 @@ impl<S: Storage> Blockchain<S> {
             // Search the stable topoheight
             let stable_topoheight = storage.get_topo_height_for_hash(&stable_hash).await?;
             blockchain.stable_topoheight.store(stable_topoheight, Ordering::SeqCst);
+
+            // Verify that our stable chain is the one from the checkpoints
+            let pruned_topoheight = storage.get_pruned_topoheight().await?.unwrap_or(0);
+            blockchain.verify_checkpoints(&*storage, pruned_topoheight, stable_topoheight).await?;
+        }
+
+        // restore the TXs pending before the last shutdown
+        if let Some(path) = blockchain.mempool_file.as_ref() {
+            if let Err(e) = blockchain.load_mempool_from_disk(path).await {
+                warn!("Error while loading mempool from disk: {}", e);
+            }
         }
 
         let arc = Arc::new(blockchain);
@@ -364,6 +545,11 @@ impl<S: Storage> Blockchain<S> {
         self.simulator.is_some()
     }
 
+    // Is the validate_transaction RPC method allowed
+    pub fn is_tx_validation_rpc_enabled(&self) -> bool {
+        self.tx_validation_rpc
+    }
+
     // Stop all blockchain modules
     // Each module is stopped in its own context
     // So no deadlock occurs in case they are linked
@@ -383,6 +569,13 @@ impl<S: Storage> Blockchain<S> {
             }
         }
 
+        if let Some(path) = self.mempool_file.as_ref() {
+            match self.save_mempool_to_disk(path).await {
+                Ok(count) => info!("{} TXs from mempool saved on disk", count),
+                Err(e) => error!("Error while saving mempool on disk: {}", e)
+            }
+        }
+
         {
             let mut storage = self.storage.write().await;
             if let Err(e) = storage.stop().await {
@@ -393,6 +586,61 @@ impl<S: Storage> Blockchain<S> {
         info!("All modules are now stopped!");
     }
 
+    // Write the mempool TXs on disk to not lose them on restart
+    // TXs of each account are written ordered by nonce to be added back in the same order,
+    // followed by the TXs waiting in the future pool
+    async fn save_mempool_to_disk(&self, path: &str) -> Result<usize, BlockchainError> {
+        trace!("save mempool to disk");
+        let mempool = self.mempool.read().await;
+        let future_txs = self.future_txs.lock().await;
+        let mut txs = Vec::with_capacity(mempool.size() + future_txs.len());
+        for cache in mempool.get_caches().values() {
+            for hash in cache.get_txs() {
+                txs.push(mempool.view_tx(hash)?.as_ref());
+            }
+        }
+        txs.extend(future_txs.iter().map(|tx| tx.as_ref()));
+
+        // Written next to it then renamed, so a crash while writing doesn't corrupt the previous file
+        let tmp_path = format!("{}.tmp", path);
+        fs::write(&tmp_path, write_saved_txs(txs.iter().copied()))?;
+        fs::rename(&tmp_path, path)?;
+
+        Ok(txs.len())
+    }
+
+    // Load the TXs saved at the last shutdown
+    // Each TX is verified again against the current chain state, invalid ones are dropped
+    async fn load_mempool_from_disk(&self, path: &str) -> Result<(), BlockchainError> {
+        trace!("load mempool from disk");
+        let bytes = match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e.into())
+        };
+
+        let (txs, skipped) = read_saved_txs(&bytes);
+        if skipped > 0 {
+            warn!("{} TXs from saved mempool couldn't be read", skipped);
+        }
+
+        let count = txs.len();
+        let mut added = 0;
+        for tx in txs {
+            let hash = tx.hash();
+            match self.add_tx_to_mempool_with_hash(tx, hash.clone(), false).await {
+                Ok(()) => added += 1,
+                Err(e) => debug!("TX {} from saved mempool is not valid anymore: {}", hash, e)
+            }
+        }
+        info!("{} TXs restored in mempool, {} dropped", added, count - added);
+
+        // The file is written again at next shutdown
+        fs::remove_file(path)?;
+
+        Ok(())
+    }
+
     // Reload the storage and update all cache values
     // Clear the mempool also in case of not being up-to-date
     pub async fn reload_from_disk(&self) -> Result<(), BlockchainError> {
@@ -421,10 +669,140 @@ impl<S: Storage> Blockchain<S> {
         let mut mempool = self.mempool.write().await;
         debug!("Clearing mempool");
         mempool.clear();
+        self.future_txs.lock().await.clear();
 
         Ok(())
     }
 
+    // Export the whole chain (blocks and state) in a compressed snapshot file
+    // Storage is locked during the export to have a consistent snapshot
+    pub async fn export_snapshot(&self, path: &str) -> Result<(SnapshotHeader, Hash), BlockchainError> {
+        let storage = self.storage.read().await;
+        let header = SnapshotHeader {
+            network: self.network,
+            topoheight: storage.get_top_topoheight()?,
+            height: storage.get_top_height()?,
+            tips: storage.get_tips().await?
+        };
+
+        info!("Exporting snapshot at topoheight {} to {}", header.topoheight, path);
+        let mut writer = SnapshotWriter::create(path, &header)?;
+        storage.export_snapshot(&mut writer).await?;
+        let checksum = writer.finish()?;
+        info!("Snapshot exported with checksum {}", checksum);
+
+        Ok((header, checksum))
+    }
+
+    // Import a snapshot to bootstrap a new node
+    // The snapshot is read only once into a temporary storage,
+    // which replaces the current one only once its checksum and its chain are verified
+    pub async fn import_snapshot(&self, path: &str) -> Result<SnapshotHeader, BlockchainError> {
+        if self.get_topo_height() != 0 {
+            return Err(BlockchainError::SnapshotChainNotEmpty)
+        }
+
+        let mut reader = SnapshotReader::open(path)?;
+        let header = reader.get_header().clone();
+        if header.network != self.network {
+            return Err(BlockchainError::InvalidSnapshot(format!("snapshot is for {} network", header.network)))
+        }
+
+        {
+            let mut storage = self.storage.write().await;
+            info!("Importing snapshot {} at topoheight {}", path, header.topoheight);
+            // Checksum is verified once the last record is read
+            let imported = storage.import_snapshot(&mut reader).await?;
+            if let Err(e) = self.verify_imported_snapshot(&imported, &header).await {
+                imported.discard()?;
+                return Err(e)
+            }
+
+            storage.replace_with(imported)?;
+        }
+
+        self.reload_from_disk().await?;
+
+        info!("Snapshot imported, chain is now at topoheight {}", header.topoheight);
+        Ok(header)
+    }
+
+    // Verify the chain of an imported snapshot against its header, our genesis block and our checkpoints
+    async fn verify_imported_snapshot(&self, storage: &S, header: &SnapshotHeader) -> Result<(), BlockchainError> {
+        if storage.get_network()? != self.network {
+            return Err(BlockchainError::InvalidSnapshot("imported chain is on another network".to_owned()))
+        }
+
+        let topoheight = storage.get_top_topoheight()?;
+        if topoheight != header.topoheight || storage.get_top_height()? != header.height {
+            return Err(BlockchainError::InvalidSnapshot("imported chain height doesn't match".to_owned()))
+        }
+
+        let tips = storage.get_tips().await?;
+        if tips != header.tips {
+            return Err(BlockchainError::InvalidSnapshot("imported chain tips don't match".to_owned()))
+        }
+
+        for hash in tips.iter() {
+            if !storage.has_block_with_hash(hash).await? {
+                return Err(BlockchainError::InvalidSnapshot(format!("tip {} is missing", hash)))
+            }
+        }
+
+        if self.network != Network::Dev {
+            if storage.get_hash_at_topo_height(0).await? != *get_genesis_block_hash(&self.network) {
+                return Err(BlockchainError::InvalidSnapshot("imported chain has another genesis block".to_owned()))
+            }
+        }
+
+        let pruned_topoheight = storage.get_pruned_topoheight().await?.unwrap_or(0);
+        for checkpoint in self.checkpoints.iter().filter(|c| c.topoheight >= pruned_topoheight && c.topoheight <= topoheight) {
+            let hash = storage.get_hash_at_topo_height(checkpoint.topoheight).await?;
+            if hash != checkpoint.hash {
+                return Err(BlockchainError::InvalidSnapshot(format!("block {} at topoheight {} doesn't match the checkpoint {}", hash, checkpoint.topoheight, checkpoint.hash)))
+            }
+        }
+
+        Ok(())
+    }
+
+    // Rebuild the burned supply from the burns executed in the chain
+    // Entries were keyed by topoheight before and the burns made before it was tracked are missing
+    async fn rebuild_burned_supply(&self, storage: &mut S) -> Result<(), BlockchainError> {
+        info!("Indexing burned supply...");
+        storage.clear_burned_supply()?;
+
+        // Transactions are not available anymore in pruned blocks
+        let start = storage.get_pruned_bodies_topoheight().await?
+            .max(storage.get_pruned_topoheight().await?)
+            .unwrap_or(0);
+        if start > 0 {
+            warn!("Burned supply can't be indexed below pruned topoheight {}", start);
+        }
+
+        let topoheight = self.get_topo_height();
+        for topo in start..=topoheight {
+            let hash = storage.get_hash_at_topo_height(topo).await?;
+            let block = storage.get_block_by_hash(&hash).await?;
+            let mut burned_supply: HashMap<&Hash, u64> = HashMap::new();
+            for (tx, tx_hash) in block.get_transactions().iter().zip(block.get_txs_hashes()) {
+                if let TransactionType::Burn(payload) = tx.get_data() {
+                    if storage.is_tx_executed_in_block(tx_hash, &hash)? {
+                        *burned_supply.entry(&payload.asset).or_insert(0) += payload.amount;
+                    }
+                }
+            }
+
+            for (asset, amount) in burned_supply {
+                storage.add_burned_supply_at_topoheight(asset, topo, amount).await?;
+            }
+        }
+
+        storage.set_burned_supply_indexed_from(start)?;
+        info!("Burned supply indexed from topoheight {} until {}", start, topoheight);
+        Ok(())
+    }
+
     // function to include the genesis block and register the public dev key.
     async fn create_genesis_block(&self) -> Result<(), BlockchainError> {
         let mut storage = self.storage.write().await;
@@ -557,6 +935,52 @@ impl<S: Storage> Blockchain<S> {
         }
     }
 
+    // Delete the transactions of all blocks below the topoheight in param
+    // Block headers are kept to validate the chain, and the balances are not touched
+    // Returns the new pruned bodies topoheight
+    pub async fn prune_bodies_until_topoheight_for_storage(&self, topoheight: u64, storage: &mut S) -> Result<u64, BlockchainError> {
+        // Blocks that may be rewinded must stay complete
+        let topoheight = topoheight.min(self.get_stable_topoheight());
+        let current_topoheight = self.get_topo_height();
+        if topoheight >= current_topoheight || current_topoheight - topoheight < PRUNE_SAFETY_LIMIT {
+            return Err(BlockchainError::PruneHeightTooHigh)
+        }
+
+        // Fully pruned blocks are already deleted, and the genesis block is kept
+        let start = storage.get_pruned_bodies_topoheight().await?
+            .max(storage.get_pruned_topoheight().await?)
+            .unwrap_or(1);
+
+        if topoheight <= start {
+            debug!("Bodies are already pruned until topoheight {}", start);
+            return Ok(start)
+        }
+
+        for topo in start..topoheight {
+            let hash = storage.get_hash_at_topo_height(topo).await?;
+            let header = storage.get_block_header_by_hash(&hash).await?;
+            'txs: for tx_hash in header.get_transactions() {
+                if !storage.has_transaction(tx_hash).await? {
+                    continue;
+                }
+
+                // The same TX can be included in several blocks, keep it if one of them is still complete
+                for block in storage.get_blocks_for_tx(tx_hash)? {
+                    if !storage.is_block_topological_ordered(&block).await || storage.get_topo_height_for_hash(&block).await? >= topoheight {
+                        trace!("TX {} is also included in block {}, keeping it", tx_hash, block);
+                        continue 'txs;
+                    }
+                }
+
+                trace!("Pruning TX {} of block {} at topoheight {}", tx_hash, hash, topo);
+                storage.delete_transaction_body(tx_hash).await?;
+            }
+        }
+
+        storage.set_pruned_bodies_topoheight(topoheight).await?;
+        Ok(topoheight)
+    }
+
     // determine the topoheight of the nearest sync block until limit topoheight
     pub async fn locate_nearest_sync_block_for_topoheight<P>(&self, provider: &P, mut topoheight: u64, current_height: u64) -> Result<u64, BlockchainError>
     where
@@ -1143,8 +1567,8 @@ impl<S: Storage> Blockchain<S> {
         }
 
         let height = blockdag::calculate_height_at_tips(provider, tips.clone().into_iter()).await?;
-        // Simulator is enabled, don't calculate difficulty
-        if height <= 1 || self.is_simulator_enabled() {
+        // Simulator is enabled or we are on dev network, don't calculate difficulty
+        if height <= 1 || self.is_simulator_enabled() || self.network == Network::Dev {
             return Ok((get_minimum_difficulty(self.get_network()), difficulty::P))
         }
 
@@ -1197,6 +1621,81 @@ impl<S: Storage> Blockchain<S> {
         }
     }
 
+    // Check if this block can skip the full verification
+    // Only blocks whose hash is linked to the latest checkpoint by the headers chain are trusted
+    async fn is_checkpoint_ancestor(&self, hash: &Hash) -> bool {
+        self.checkpoints_sync.load(Ordering::SeqCst) && self.checkpoint_ancestors.lock().await.contains(hash)
+    }
+
+    // Returns the latest checkpoint if its ancestors must be proven before syncing the blocks
+    pub async fn get_unproven_checkpoint(&self) -> Result<Option<&Checkpoint>, BlockchainError> {
+        if !self.checkpoints_sync.load(Ordering::SeqCst) {
+            return Ok(None)
+        }
+
+        match self.checkpoints.last() {
+            Some(checkpoint) if !self.has_block(&checkpoint.hash).await? => Ok(Some(checkpoint)),
+            _ => Ok(None)
+        }
+    }
+
+    // Set the blocks proven to lead to the latest checkpoint, replacing those of a previous sync
+    // Caller must have verified that each header hash is linked to the checkpoint through the tips
+    pub async fn set_checkpoint_ancestors(&self, ancestors: HashSet<Hash>) {
+        debug!("{} blocks are proven to lead to the latest checkpoint", ancestors.len());
+        *self.checkpoint_ancestors.lock().await = ancestors;
+    }
+
+    // Returns the checkpoints used by the chain
+    pub fn get_checkpoints(&self) -> &Vec<Checkpoint> {
+        &self.checkpoints
+    }
+
+    // Verify the format and the signature of each TX
+    // TXs are split in chunks, each one being verified on a blocking thread
+    // so the async runtime is not stalled while waiting on them
+    async fn verify_txs_stateless(&self, txs: &[&Immutable<Transaction>]) -> Result<(), BlockchainError> {
+        if self.txs_verification_threads <= 1 || txs.len() <= 1 {
+            for tx in txs {
+                tx.verify_stateless::<BlockchainError>()?;
+            }
+            return Ok(())
+        }
+
+        let chunk_size = txs.len().div_ceil(self.txs_verification_threads);
+        trace!("verifying {} TXs in chunks of {}", txs.len(), chunk_size);
+        let handles: Vec<_> = txs.chunks(chunk_size)
+            .map(|chunk| {
+                let chunk: Vec<Arc<Transaction>> = chunk.iter().map(|tx| (*tx).clone().to_arc()).collect();
+                task::spawn_blocking(move || {
+                    chunk.iter().try_for_each(|tx| tx.verify_stateless::<BlockchainError>())
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            handle.await.map_err(|_| BlockchainError::Unknown)??;
+        }
+
+        Ok(())
+    }
+
+    // Verify that the blocks ordered in the topoheight range (inclusive) match the checkpoints
+    // In case of mismatch, the checkpoints sync is disabled to fully verify the next blocks
+    async fn verify_checkpoints<P: DagOrderProvider>(&self, provider: &P, from: u64, to: u64) -> Result<(), BlockchainError> {
+        for checkpoint in self.checkpoints.iter().filter(|c| c.topoheight >= from && c.topoheight <= to) {
+            let hash = provider.get_hash_at_topo_height(checkpoint.topoheight).await?;
+            if hash != checkpoint.hash {
+                error!("Block {} at topoheight {} doesn't match the checkpoint {}, the chain must be resynced", hash, checkpoint.topoheight, checkpoint.hash);
+                self.checkpoints_sync.store(false, Ordering::SeqCst);
+                return Err(BlockchainError::CheckpointMismatch(checkpoint.topoheight, checkpoint.hash.clone(), hash))
+            }
+            debug!("Checkpoint at topoheight {} is valid", checkpoint.topoheight);
+        }
+
+        Ok(())
+    }
+
     // Returns the P2p module used for blockchain if enabled
     pub fn get_p2p(&self) -> &RwLock<Option<Arc<P2pServer<S>>>> {
         &self.p2p
@@ -1217,56 +1716,244 @@ impl<S: Storage> Blockchain<S> {
         &self.mempool
     }
 
+    // Get the mempool state after each of the last blocks added, from the oldest
+    pub fn get_mempool_history(&self) -> &Mutex<VecDeque<MempoolHistoryEntry>> {
+        &self.mempool_history
+    }
+
     // Add a tx to the mempool, its hash will be computed
     pub async fn add_tx_to_mempool(&self, tx: Transaction, broadcast: bool) -> Result<(), BlockchainError> {
         let hash = tx.hash();
         self.add_tx_to_mempool_with_hash(tx, hash, broadcast).await
     }
 
+    // Add a tx submitted through our RPC to the mempool
+    // It is tracked to be rebroadcasted until it is included in a block
+    pub async fn submit_local_tx(&self, tx: Transaction) -> Result<(), BlockchainError> {
+        let hash = tx.hash();
+        self.add_tx_to_mempool_with_hash(tx, hash.clone(), true).await?;
+
+        // Nothing to rebroadcast without the p2p server
+        if self.p2p.read().await.is_some() {
+            self.local_txs.lock().await.insert(hash, get_current_time_in_seconds());
+        }
+
+        Ok(())
+    }
+
+    // Get the local TXs that are still waiting in mempool
+    // Included, dropped and expired ones are not tracked anymore
+    // TXs waiting in the future pool are kept but not returned
+    pub async fn get_local_txs_to_rebroadcast(&self) -> Vec<Hash> {
+        let now = get_current_time_in_seconds();
+        let mempool = self.mempool.read().await;
+        let future_txs = self.future_txs.lock().await;
+        let mut local_txs = self.local_txs.lock().await;
+
+        let mut txs = Vec::new();
+        local_txs.retain(|hash, submitted_at| {
+            if now >= *submitted_at + LOCAL_TX_REBROADCAST_EXPIRATION {
+                debug!("Local TX {} has expired, stop rebroadcasting it", hash);
+                return false
+            }
+
+            if mempool.contains_tx(hash) {
+                txs.push(hash.clone());
+                true
+            } else {
+                future_txs.contains(hash)
+            }
+        });
+
+        txs
+    }
+
     // Add a tx to the mempool with the given hash, it is not computed and the TX is transformed into an Arc
     pub async fn add_tx_to_mempool_with_hash<'a>(&'a self, tx: Transaction, hash: Hash, broadcast: bool) -> Result<(), BlockchainError> {
         let storage = self.storage.read().await;
         self.add_tx_to_mempool_with_storage_and_hash(&*storage, Arc::new(tx), hash, broadcast).await
     }
 
+    // Checks done before verifying a TX for the mempool: size, relay policy, duplicates and nonce range of the pending TXs of its owner
+    // Returns the hash of the TX in mempool replaced by this one
+    fn pre_verify_tx_for_mempool(&self, storage: &S, mempool: &Mempool, tx: &Transaction, hash: &Hash, tx_size: usize, allow_replacement: bool) -> Result<Option<Arc<Hash>>, BlockchainError> {
+        if tx_size > MAX_TRANSACTION_SIZE {
+            return Err(BlockchainError::TxTooBig(tx_size, MAX_TRANSACTION_SIZE))
+        }
+
+        self.relay_policy.verify(tx, tx_size)?;
+
+        if mempool.contains_tx(hash) {
+            return Err(BlockchainError::TxAlreadyInMempool(hash.clone()))
+        }
+
+        // check that the TX is not already in blockchain
+        if storage.is_tx_executed_in_a_block(hash)? {
+            return Err(BlockchainError::TxAlreadyInBlockchain(hash.clone()))
+        }
+
+        // get the highest nonce available
+        // if presents, it means we have at least one tx from this owner in mempool
+        if let Some(cache) = mempool.get_cache_for(tx.get_source()) {
+            if let Some(hash) = cache.has_tx_with_same_nonce(tx.get_nonce()) {
+                // A TX with the same nonce is already in mempool
+                // We accept to replace it if the new one has a higher fee and it's the only pending TX of its owner,
+                // otherwise the TXs built on top of it would become invalid
+                let replaceable = allow_replacement
+                    && cache.get_txs().len() == 1
+                    && mempool.get_sorted_tx(hash)?.get_fee() < tx.get_fee();
+
+                if !replaceable {
+                    return Err(BlockchainError::TxNonceAlreadyUsed(tx.get_nonce(), hash.as_ref().clone()))
+                }
+
+                return Ok(Some(hash.clone()))
+            }
+
+            // check that the nonce is in the range
+            if !(tx.get_nonce() <= cache.get_max() + 1 && tx.get_nonce() >= cache.get_min()) {
+                debug!("TX {} nonce is not in the range of the pending TXs for this owner, received: {}, expected between {} and {}", hash, tx.get_nonce(), cache.get_min(), cache.get_max());
+                return Err(BlockchainError::InvalidTxNonceMempoolCache(tx.get_nonce(), cache.get_min(), cache.get_max()))
+            }
+        }
+
+        Ok(None)
+    }
+
+    // Verify a TX like it would be before being added to the mempool, without adding nor broadcasting it
+    // This allows a client to know why a TX would be rejected before sending it
+    pub async fn simulate_tx(&self, tx: &Transaction) -> Result<(), BlockchainError> {
+        let hash = tx.hash();
+        let storage = self.storage.read().await;
+        let mempool = self.mempool.read().await;
+        self.pre_verify_tx_for_mempool(&*storage, &mempool, tx, &hash, tx.size(), false)?;
+
+        let current_topoheight = self.get_topo_height();
+        let block_version = self.get_version_at_height(self.get_height() + 1);
+        mempool.verify_tx(&*storage, current_topoheight, block_version, tx).await
+    }
+
     // Add a tx to the mempool with the given hash, it will verify the TX and check that it is not already in mempool or in blockchain
     // and its validity (nonce, balance, etc...)
+    // A TX with a nonce ahead of the next one expected is kept in the future pool until the gap is filled
     pub async fn add_tx_to_mempool_with_storage_and_hash<'a>(&'a self, storage: &S, tx: Arc<Transaction>, hash: Hash, broadcast: bool) -> Result<(), BlockchainError> {
+        if self.try_add_future_tx(storage, &tx, &hash).await? {
+            return Ok(())
+        }
+
+        let source = tx.get_source().clone();
+        let next_nonce = tx.get_nonce() + 1;
+        self.add_tx_to_mempool_internal(storage, tx, hash, broadcast).await?;
+
+        // This TX may be the one missing for the TXs waiting in the future pool
+        self.promote_future_txs(storage, &source, next_nonce).await;
+
+        Ok(())
+    }
+
+    // Next nonce expected for the source, based on its pending TXs or on the chain
+    async fn get_next_nonce_for(&self, storage: &S, key: &PublicKey) -> Result<u64, BlockchainError> {
+        if let Some(cache) = self.mempool.read().await.get_cache_for(key) {
+            return Ok(cache.get_next_nonce())
+        }
+
+        let nonce = storage.get_nonce_at_maximum_topoheight(key, self.get_topo_height()).await?
+            .map(|(_, version)| version.get_nonce())
+            .unwrap_or(0);
+        Ok(nonce)
+    }
+
+    // Keep the TX in the future pool if its nonce is ahead of the next one expected
+    // Returns true if the TX has been added to the future pool
+    async fn try_add_future_tx(&self, storage: &S, tx: &Arc<Transaction>, hash: &Hash) -> Result<bool, BlockchainError> {
+        let nonce = tx.get_nonce();
+        let source = tx.get_source();
+        let expected = self.get_next_nonce_for(storage, source).await?;
+        // A nonce too far is rejected by the mempool verification
+        if nonce <= expected || nonce > expected + FUTURE_TX_MAX_NONCE_GAP {
+            return Ok(false)
+        }
+
         let tx_size = tx.size();
         if tx_size > MAX_TRANSACTION_SIZE {
             return Err(BlockchainError::TxTooBig(tx_size, MAX_TRANSACTION_SIZE))
         }
 
-        {
-            let mut mempool = self.mempool.write().await;
-    
-            if mempool.contains_tx(&hash) {
-                return Err(BlockchainError::TxAlreadyInMempool(hash))
-            }
-    
-            // check that the TX is not already in blockchain
-            if storage.is_tx_executed_in_a_block(&hash)? {
-                return Err(BlockchainError::TxAlreadyInBlockchain(hash))
+        self.relay_policy.verify(tx, tx_size)?;
+
+        if self.future_txs.lock().await.contains(hash) {
+            return Err(BlockchainError::TxAlreadyInMempool(hash.clone()))
+        }
+
+        // The previous nonces can only be filled by an existing account
+        let has_pending_txs = self.mempool.read().await.get_cache_for(source).is_some();
+        if !has_pending_txs && !storage.is_account_registered(source).await? {
+            return Err(BlockchainError::AddressNotRegistered(source.as_address(self.network)))
+        }
+
+        // The balances can't be verified yet, the TX is fully verified once promoted
+        tx.verify_stateless::<BlockchainError>()?;
+
+        let mut future_txs = self.future_txs.lock().await;
+        match future_txs.get_with_nonce(source, nonce) {
+            // Same rule as in mempool, a waiting TX is only replaced by one paying more
+            Some(previous) => if previous.get_fee() >= tx.get_fee() {
+                return Err(BlockchainError::FeesToLowToOverride(previous.get_fee().saturating_add(1), tx.get_fee()))
+            },
+            None => if future_txs.count_for(source) >= FUTURE_TXS_MAX_PER_SOURCE {
+                return Err(BlockchainError::TooManyFutureTxs(source.as_address(self.network), FUTURE_TXS_MAX_PER_SOURCE))
             }
+        }
 
-            let current_topoheight = self.get_topo_height();
-            // get the highest nonce available
-            // if presents, it means we have at least one tx from this owner in mempool
-            if let Some(cache) = mempool.get_cache_for(tx.get_source()) {
-                // we accept to delete a tx from mempool if the new one has a higher fee
-                if let Some(hash) = cache.has_tx_with_same_nonce(tx.get_nonce()) {
-                    // A TX with the same nonce is already in mempool
-                    return Err(BlockchainError::TxNonceAlreadyUsed(tx.get_nonce(), hash.as_ref().clone()))
-                }
+        future_txs.insert(hash.clone(), source.clone(), nonce, Arc::clone(tx));
+        debug!("TX {} with nonce {} is waiting on nonce {} of {} ({} TXs in future pool)", hash, nonce, expected, source.as_address(self.network), future_txs.len());
 
-                // check that the nonce is in the range
-                if !(tx.get_nonce() <= cache.get_max() + 1 && tx.get_nonce() >= cache.get_min()) {
-                    debug!("TX {} nonce is not in the range of the pending TXs for this owner, received: {}, expected between {} and {}", hash, tx.get_nonce(), cache.get_min(), cache.get_max());
-                    return Err(BlockchainError::InvalidTxNonceMempoolCache(tx.get_nonce(), cache.get_min(), cache.get_max()))
-                }
+        Ok(true)
+    }
+
+    // Add in the mempool the TXs of the source waiting in the future pool, starting at this nonce
+    async fn promote_future_txs(&self, storage: &S, source: &PublicKey, mut nonce: u64) {
+        loop {
+            let next = self.future_txs.lock().await.take_next(source, nonce);
+            let Some((hash, tx)) = next else {
+                break;
+            };
+
+            debug!("Promoting TX {} with nonce {} from the future pool", hash, nonce);
+            if let Err(e) = self.add_tx_to_mempool_internal(storage, tx, hash.clone(), true).await {
+                debug!("Error while promoting TX {} from the future pool: {}", hash, e);
+                break;
             }
+            nonce += 1;
+        }
+    }
+
+    // Verify and add a TX in the mempool, then broadcast it if requested
+    async fn add_tx_to_mempool_internal(&self, storage: &S, tx: Arc<Transaction>, hash: Hash, broadcast: bool) -> Result<(), BlockchainError> {
+        let tx_size = tx.size();
+        {
+            let mut mempool = self.mempool.write().await;
+            let replaced = self.pre_verify_tx_for_mempool(storage, &mempool, &tx, &hash, tx_size, true)?;
 
-            mempool.add_tx(storage, current_topoheight, hash.clone(), tx.clone(), tx_size).await?;
+            let current_topoheight = self.get_topo_height();
+            let block_version = self.get_version_at_height(self.get_height() + 1);
+            match replaced {
+                Some(replaced) => {
+                    // Keep the previous TX to restore it if the new one is invalid
+                    let (previous, previous_size) = {
+                        let sorted_tx = mempool.get_sorted_tx(&replaced)?;
+                        (sorted_tx.get_tx().clone(), sorted_tx.get_size())
+                    };
+
+                    mempool.remove_tx(&replaced)?;
+                    if let Err(e) = mempool.add_tx(storage, current_topoheight, block_version, hash.clone(), tx.clone(), tx_size).await {
+                        mempool.add_tx(storage, current_topoheight, block_version, replaced.as_ref().clone(), previous, previous_size).await?;
+                        return Err(e)
+                    }
+                    info!("TX {} has been replaced by {} using the same nonce {}", replaced, hash, tx.get_nonce());
+                },
+                None => mempool.add_tx(storage, current_topoheight, block_version, hash.clone(), tx.clone(), tx_size).await?
+            };
         }
 
         if broadcast {
@@ -1285,7 +1972,7 @@ impl<S: Storage> Blockchain<S> {
                 }
 
                 if rpc.is_event_tracked(&NotifyEvent::TransactionAddedInMempool).await {
-                    let data = RPCTransaction::from_tx(&tx, &hash, storage.is_mainnet());
+                    let data = RPCTransaction::from_tx(&tx, &hash, storage.network());
                     let data: TransactionResponse<'_> = TransactionResponse {
                         blocks: None,
                         executed_in_block: None,
@@ -1304,9 +1991,9 @@ impl<S: Storage> Blockchain<S> {
         Ok(())
     }
 
-    // this will be used in future for hard fork versions
-    pub fn get_version_at_height(&self, _height: u64) -> u8 {
-        0
+    // Block version expected at this height based on the hard forks schedule
+    pub fn get_version_at_height(&self, height: u64) -> u8 {
+        hard_fork::get_version_at_height(&self.network, height)
     }
 
     // Get a block template for the new block work (mining)
@@ -1443,16 +2130,13 @@ impl<S: Storage> Blockchain<S> {
         // data used to verify txs
         let topoheight = self.get_topo_height();
         trace!("build chain state for block template");
-        let mut chain_state = ChainState::new(storage, topoheight);
+        let mut chain_state = ChainState::new(storage, topoheight, block.get_version());
 
         let mut failed_sources = HashSet::new();
-        while let Some(TxSelectorEntry { size, hash, tx }) = tx_selector.next() {
-            if block_size + total_txs_size + size >= MAX_BLOCK_SIZE {
-                break;
-            }
-
+        // A smaller TX from another sender may still fit in the block when the best one doesn't
+        while let Some(TxSelectorEntry { size, hash, tx }) = tx_selector.next_fitting(MAX_BLOCK_SIZE.saturating_sub(block_size + total_txs_size + HASH_SIZE)) {
             // Check if the TX is valid for this potential block
-            trace!("Checking TX {} with nonce {}, {}", hash, tx.get_nonce(), tx.get_source().as_address(self.network.is_mainnet()));
+            trace!("Checking TX {} with nonce {}, {}", hash, tx.get_nonce(), tx.get_source().as_address(self.network));
             let source = tx.get_source();
             if failed_sources.contains(&source) {
                 debug!("Skipping TX {} because its source has failed before", hash);
@@ -1460,7 +2144,7 @@ impl<S: Storage> Blockchain<S> {
             }
 
             if let Err(e) = tx.verify(&mut chain_state).await {
-                warn!("TX {} ({}) is not valid for mining: {}", hash, source.as_address(self.network.is_mainnet()), e);
+                warn!("TX {} ({}) is not valid for mining: {}", hash, source.as_address(self.network), e);
                 failed_sources.insert(source);
             } else {
                 trace!("Selected {} (nonce: {}, fees: {}) for mining", hash, tx.get_nonce(), format_xelis(tx.get_fee()));
@@ -1504,7 +2188,32 @@ impl<S: Storage> Blockchain<S> {
     }
 
     // Add a new block in chain using the requested storage
+    // Every change made in the storage is reverted if the block can't be fully added,
+    // so a failing reorg leaves the chain as it was before
     pub async fn add_new_block_for_storage(&self, storage: &mut S, block: Block, broadcast: bool, mining: bool) -> Result<(), BlockchainError> {
+        let topoheight = self.get_topo_height();
+        let height = self.get_height();
+        let stable_height = self.get_stable_height();
+        let stable_topoheight = self.get_stable_topoheight();
+
+        storage.start_commit_point()?;
+        let res = self.add_new_block_internal(storage, block, broadcast, mining).await;
+        if let Err(e) = &res {
+            // Commit point is ended once all the changes of the block are written
+            if storage.has_commit_point() {
+                debug!("Reverting the changes made by the block: {}", e);
+                storage.end_commit_point(false).await?;
+                self.topoheight.store(topoheight, Ordering::SeqCst);
+                self.height.store(height, Ordering::SeqCst);
+                self.stable_height.store(stable_height, Ordering::SeqCst);
+                self.stable_topoheight.store(stable_topoheight, Ordering::SeqCst);
+            }
+        }
+
+        res
+    }
+
+    async fn add_new_block_internal(&self, storage: &mut S, block: Block, broadcast: bool, mining: bool) -> Result<(), BlockchainError> {
         let start = Instant::now();
 
         // Verify that the block is on the correct version
@@ -1598,6 +2307,12 @@ impl<S: Storage> Blockchain<S> {
                 debug!("{} with hash {} have deviated too much, maximum allowed is {} (current height: {}, distance: {})", block, block_hash, STABLE_LIMIT, current_height, distance);
                 return Err(BlockchainError::BlockDeviation)
             }
+
+            // Reject it before any change if it would revert too many blocks
+            if distance <= current_height && current_height - distance >= self.max_reorg_depth {
+                debug!("{} with hash {} would reorganize the chain too deep (current height: {}, distance: {})", block, block_hash, current_height, distance);
+                return Err(BlockchainError::ReorgTooDeep(block_hash, current_height - distance, self.max_reorg_depth))
+            }
         }
 
         if tips_count > 1 {
@@ -1613,13 +2328,22 @@ impl<S: Storage> Blockchain<S> {
             }
         }
 
+        let mut current_topoheight = self.get_topo_height();
+        // blocks leading to the latest checkpoint are trusted, only their structure is verified
+        let skip_verification = self.is_checkpoint_ancestor(&block_hash).await;
+
         // verify PoW and get difficulty for this block based on tips
-        let pow_hash = block.get_pow_hash()?;
-        debug!("POW hash: {}", pow_hash);
-        let (difficulty, p) = self.verify_proof_of_work(storage, &pow_hash, block.get_tips().iter()).await?;
-        debug!("PoW is valid for difficulty {}", difficulty);
+        let (difficulty, p) = if skip_verification {
+            debug!("Skipping PoW verification for block {}, ancestor of latest checkpoint", block_hash);
+            self.get_difficulty_at_tips(storage, block.get_tips().iter()).await?
+        } else {
+            let pow_hash = block.get_pow_hash()?;
+            debug!("POW hash: {}", pow_hash);
+            let (difficulty, p) = self.verify_proof_of_work(storage, &pow_hash, block.get_tips().iter()).await?;
+            debug!("PoW is valid for difficulty {}", difficulty);
+            (difficulty, p)
+        };
 
-        let mut current_topoheight = self.get_topo_height();
         // Transaction verification
         // Here we are going to verify all TXs in the block
         // For this, we must select TXs that are not doing collisions with other TXs in block
@@ -1635,7 +2359,7 @@ impl<S: Storage> Blockchain<S> {
             }
 
             trace!("verifying {} TXs in block {}", txs_len, block_hash);
-            let mut chain_state = ChainState::new(storage, current_topoheight);
+            let mut chain_state = ChainState::new(storage, current_topoheight, block.get_version());
             // Cache to retrieve only one time all TXs hashes until stable height
             let mut all_parents_txs: Option<HashSet<Hash>> = None;
             let mut batch = Vec::with_capacity(block.get_txs_count());
@@ -1698,9 +2422,15 @@ impl<S: Storage> Blockchain<S> {
                 batch.push(tx);
             }
 
-            trace!("proof verifications of {} TXs in block {}", batch.len(), block_hash);
-            // Verify all valid transactions in one batch
-            Transaction::verify_batch(batch.as_slice(), &mut chain_state).await?;
+            if skip_verification {
+                debug!("Skipping proof verifications of {} TXs in block {}, ancestor of latest checkpoint", batch.len(), block_hash);
+            } else {
+                trace!("proof verifications of {} TXs in block {}", batch.len(), block_hash);
+                // Signatures don't depend on the chain state, verify them in parallel first
+                self.verify_txs_stateless(batch.as_slice()).await?;
+                // Verify all valid transactions in one batch
+                Transaction::verify_batch_stateless_verified(batch.as_slice(), &mut chain_state).await?;
+            }
         }
 
         // Save transactions & block
@@ -1709,6 +2439,9 @@ impl<S: Storage> Blockchain<S> {
         debug!("Saving block {} on disk", block_hash);
         // Add block to chain
         storage.save_block(block.clone(), &txs, difficulty, p, block_hash.clone()).await?;
+        if skip_verification {
+            self.checkpoint_ancestors.lock().await.remove(&block_hash);
+        }
 
         // Compute cumulative difficulty for block
         let cumulative_difficulty = {
@@ -1752,6 +2485,9 @@ impl<S: Storage> Blockchain<S> {
         let mut events: HashMap<NotifyEvent, Vec<Value>> = HashMap::new();
         // Track all orphaned tranasctions
         let mut orphaned_transactions = HashSet::new();
+        // Track the reorg if already ordered blocks are reverted
+        let mut reorg_from_topoheight = None;
+        let mut orphaned_blocks = Vec::new();
 
         // order the DAG (up to TOP_HEIGHT - STABLE_LIMIT)
         let mut highest_topo = 0;
@@ -1784,6 +2520,9 @@ impl<S: Storage> Blockchain<S> {
                     }
 
                     debug!("Cleaning transactions executions at topo height {} (block {})", topoheight, hash_at_topo);
+                    if reorg_from_topoheight.is_none() {
+                        reorg_from_topoheight = Some(topoheight);
+                    }
 
                     let block = storage.get_block_header_by_hash(&hash_at_topo).await?;
 
@@ -1798,6 +2537,10 @@ impl<S: Storage> Blockchain<S> {
                         events.entry(NotifyEvent::BlockOrphaned).or_insert_with(Vec::new).push(value);
                     }
 
+                    if is_orphaned {
+                        orphaned_blocks.push(hash_at_topo.clone());
+                    }
+
                     // mark txs as unexecuted if it was executed in this block
                     for tx_hash in block.get_txs_hashes() {
                         if storage.is_tx_executed_in_block(tx_hash, &hash_at_topo)? {
@@ -1813,7 +2556,9 @@ impl<S: Storage> Blockchain<S> {
                     // Delete changes made by this block
                     storage.delete_versioned_balances_at_topoheight(topoheight).await?;
                     storage.delete_versioned_nonces_at_topoheight(topoheight).await?;
+                    storage.delete_versioned_multisigs_at_topoheight(topoheight).await?;
                     storage.delete_registrations_at_topoheight(topoheight).await?;
+                    storage.delete_burned_supply_at_topoheight(topoheight).await?;
 
                     topoheight += 1;
                 }
@@ -1882,9 +2627,11 @@ impl<S: Storage> Blockchain<S> {
 
                 // All fees from the transactions executed in this block
                 let mut total_fees = 0;
+                // Amounts burned per asset in this block
+                let mut burned_supply: HashMap<&Hash, u64> = HashMap::new();
                 // Chain State used for the verification
                 trace!("building chain state to execute TXs in block {}", block_hash);
-                let mut chain_state = ApplicableChainState::new(storage, highest_topo);
+                let mut chain_state = ApplicableChainState::new(storage, highest_topo, block.get_version());
 
                 // compute rewards & execute txs
                 for (tx, tx_hash) in block.get_transactions().iter().zip(block.get_txs_hashes()) { // execute all txs
@@ -1915,7 +2662,7 @@ impl<S: Storage> Blockchain<S> {
 
                         // Calculate the new nonce
                         // This has to be done in case of side blocks where TX B would be before TX A
-                        let next_nonce = nonce_checker.get_new_nonce(tx.get_source(), self.network.is_mainnet())?;
+                        let next_nonce = nonce_checker.get_new_nonce(tx.get_source(), self.network)?;
                         chain_state.as_mut().update_account_nonce(tx.get_source(), next_nonce).await?;
 
                         // mark tx as executed
@@ -1936,6 +2683,10 @@ impl<S: Storage> Blockchain<S> {
                             events.entry(NotifyEvent::TransactionExecuted).or_insert_with(Vec::new).push(value);
                         }
 
+                        if let TransactionType::Burn(payload) = tx.get_data() {
+                            *burned_supply.entry(&payload.asset).or_insert(0) += payload.amount;
+                        }
+
                         // Increase total tx fees for miner
                         total_fees += tx.get_fee();
                     }
@@ -1956,6 +2707,10 @@ impl<S: Storage> Blockchain<S> {
                 // apply changes from Chain State
                 chain_state.apply_changes().await?;
 
+                for (asset, amount) in burned_supply {
+                    storage.add_burned_supply_at_topoheight(asset, highest_topo, amount).await?;
+                }
+
                 if should_track_events.contains(&NotifyEvent::BlockOrdered) {
                     let value = json!(BlockOrderedEvent {
                         block_hash: Cow::Borrowed(&hash),
@@ -2014,17 +2769,32 @@ impl<S: Storage> Blockchain<S> {
             }
         }
 
-        // auto prune mode
-        if extended {
-            if let Some(keep_only) = self.auto_prune_keep_n_blocks {
-                // check that the topoheight is greater than the safety limit
-                // and that we can prune the chain using the config while respecting the safety limit
-                if current_topoheight % keep_only == 0 && current_topoheight - keep_only > 0 {
-                    info!("Auto pruning chain until topoheight {} (keep only {} blocks)", current_topoheight - keep_only, keep_only);
-                    if let Err(e) = self.prune_until_topoheight_for_storage(current_topoheight - keep_only, storage).await {
-                        warn!("Error while trying to auto prune chain: {}", e);
+        // Notify the reorg, wallets and indexers must invalidate everything above its topoheight
+        if let Some(from_topoheight) = reorg_from_topoheight {
+            let depth = current_topoheight - from_topoheight + 1;
+            if orphaned_blocks.is_empty() {
+                debug!("Chain reordered {} blocks from topoheight {} with block {}", depth, from_topoheight, block_hash);
+            } else {
+                info!("Chain reorganized {} blocks from topoheight {} with block {}, {} blocks orphaned", depth, from_topoheight, block_hash, orphaned_blocks.len());
+            }
+
+            if should_track_events.contains(&NotifyEvent::ChainReorganized) {
+                let mut txs = Vec::new();
+                for tx_hash in orphaned_transactions.iter() {
+                    if !storage.is_tx_executed_in_a_block(tx_hash)? {
+                        txs.push(Cow::Borrowed(tx_hash));
                     }
                 }
+
+                let value = json!(ChainReorganizedEvent {
+                    block_hash: Cow::Borrowed(&block_hash),
+                    from_topoheight,
+                    previous_topoheight: current_topoheight,
+                    new_topoheight: highest_topo,
+                    orphaned_blocks: orphaned_blocks.iter().map(Cow::Borrowed).collect(),
+                    orphaned_transactions: txs
+                });
+                events.entry(NotifyEvent::ChainReorganized).or_insert_with(Vec::new).push(value);
             }
         }
 
@@ -2054,10 +2824,16 @@ impl<S: Storage> Blockchain<S> {
                 }
             }
 
-            // Update caches
-            self.stable_height.store(stable_height, Ordering::SeqCst);
             // Search the topoheight of the stable block
             let stable_topoheight = storage.get_topo_height_for_hash(&stable_hash).await?;
+            // Blocks that became stable must match the checkpoints
+            let previous_stable_topoheight = self.get_stable_topoheight();
+            if stable_topoheight > previous_stable_topoheight {
+                self.verify_checkpoints(&*storage, previous_stable_topoheight + 1, stable_topoheight).await?;
+            }
+
+            // Update caches
+            self.stable_height.store(stable_height, Ordering::SeqCst);
             self.stable_topoheight.store(stable_topoheight, Ordering::SeqCst);
 
             trace!("update difficulty in cache");
@@ -2065,23 +2841,52 @@ impl<S: Storage> Blockchain<S> {
             self.set_difficulty(difficulty).await;
         }
 
-        // Check if the event is tracked
+        // All the changes of the block are written
+        storage.end_commit_point(true).await?;
+
+        // auto prune mode
+        // done once the block is written to not record all the pruned entries
+        if extended {
+            if let Some(keep_only) = self.auto_prune_keep_n_blocks {
+                // check that the topoheight is greater than the safety limit
+                // and that we can prune the chain using the config while respecting the safety limit
+                if current_topoheight % keep_only == 0 && current_topoheight - keep_only > 0 {
+                    info!("Auto pruning chain until topoheight {} (keep only {} blocks)", current_topoheight - keep_only, keep_only);
+                    if let Err(e) = self.prune_until_topoheight_for_storage(current_topoheight - keep_only, storage).await {
+                        warn!("Error while trying to auto prune chain: {}", e);
+                    }
+                }
+            }
+
+            // prune mode, only the transactions of the oldest blocks are deleted
+            if let Some(keep_only) = self.prune_bodies_keep_n_blocks {
+                if current_topoheight > keep_only {
+                    if let Err(e) = self.prune_bodies_until_topoheight_for_storage(current_topoheight - keep_only, storage).await {
+                        debug!("Error while trying to prune blocks bodies: {}", e);
+                    }
+                }
+            }
+        }
+
+        // Check if the events are tracked
         let orphan_event_tracked = should_track_events.contains(&NotifyEvent::TransactionOrphaned);
+        let conflict_event_tracked = should_track_events.contains(&NotifyEvent::TransactionConflicted);
 
         // Clean mempool from old txs
         let mempool_deleted_txs = {
             debug!("Locking mempool write mode");
             let mut mempool = self.mempool.write().await;
             debug!("mempool write mode ok");
-            mempool.clean_up(&*storage, highest_topo).await
+            mempool.clean_up(&*storage, highest_topo, self.get_version_at_height(current_height + 1)).await
         };
 
-        if orphan_event_tracked {
-            for (tx_hash, sorted_tx) in mempool_deleted_txs {
+        if orphan_event_tracked || conflict_event_tracked {
+            for (tx_hash, sorted_tx, reason) in mempool_deleted_txs {
                 // Delete it from our orphaned transactions list
                 // This save some performances as it will not try to add it back and
                 // consume resources for verifying the ZK Proof if we already know the answer
-                if orphaned_transactions.remove(&tx_hash) {
+                let orphaned = orphaned_transactions.remove(&tx_hash);
+                if orphaned {
                     trace!("Transaction {} was marked as orphaned, but got deleted from mempool. Prevent adding it back", tx_hash);
                 }
                 // Verify that the TX was not executed in a block
@@ -2089,7 +2894,7 @@ impl<S: Storage> Blockchain<S> {
                     continue;
                 }
 
-                let data = RPCTransaction::from_tx(&sorted_tx.get_tx(), &tx_hash, storage.is_mainnet());
+                let data = RPCTransaction::from_tx(&sorted_tx.get_tx(), &tx_hash, storage.network());
                 let data = TransactionResponse {
                     blocks: None,
                     executed_in_block: None,
@@ -2097,7 +2902,42 @@ impl<S: Storage> Blockchain<S> {
                     first_seen: Some(sorted_tx.get_first_seen()),
                     data,
                 };
-                events.entry(NotifyEvent::TransactionOrphaned).or_insert_with(Vec::new).push(json!(data));
+
+                // A TX that was never executed lost against the TXs of the new chain
+                if orphaned {
+                    if orphan_event_tracked {
+                        events.entry(NotifyEvent::TransactionOrphaned).or_insert_with(Vec::new).push(json!(data));
+                    }
+                } else if conflict_event_tracked {
+                    debug!("TX {} is in conflict with the chain: {:?}", tx_hash, reason);
+                    let event = TransactionConflictedEvent {
+                        reason,
+                        transaction: data
+                    };
+                    events.entry(NotifyEvent::TransactionConflicted).or_insert_with(Vec::new).push(json!(event));
+                }
+            }
+        }
+
+        // Delete the TXs that stayed too long in mempool
+        if self.mempool_tx_expiration > 0 {
+            let expired_txs = self.mempool.write().await.remove_expired_txs(self.mempool_tx_expiration);
+            if !expired_txs.is_empty() {
+                debug!("{} TXs have expired from mempool", expired_txs.len());
+            }
+
+            if should_track_events.contains(&NotifyEvent::TransactionExpired) {
+                for (tx_hash, sorted_tx) in expired_txs {
+                    let data = RPCTransaction::from_tx(&sorted_tx.get_tx(), &tx_hash, storage.network());
+                    let data = TransactionResponse {
+                        blocks: None,
+                        executed_in_block: None,
+                        in_mempool: false,
+                        first_seen: Some(sorted_tx.get_first_seen()),
+                        data,
+                    };
+                    events.entry(NotifyEvent::TransactionExpired).or_insert_with(Vec::new).push(json!(data));
+                }
             }
         }
 
@@ -2124,7 +2964,7 @@ impl<S: Storage> Blockchain<S> {
                     if let Err(e) = self.add_tx_to_mempool_with_storage_and_hash(&storage, tx.clone(), tx_hash.clone(), false).await {
                         debug!("Error while adding back orphaned tx: {}, broadcasting event", e);
                         // We couldn't add it back to mempool, let's notify this event
-                        let data = RPCTransaction::from_tx(&tx, &tx_hash, storage.is_mainnet());
+                        let data = RPCTransaction::from_tx(&tx, &tx_hash, storage.network());
                         let data = TransactionResponse {
                             blocks: None,
                             executed_in_block: None,
@@ -2138,6 +2978,36 @@ impl<S: Storage> Blockchain<S> {
             }
         }
 
+        // Nonces may have been updated by this block, promote the TXs that are now expected
+        let sources = self.future_txs.lock().await.get_sources();
+        for source in sources {
+            match self.get_next_nonce_for(&storage, &source).await {
+                Ok(nonce) => self.promote_future_txs(&storage, &source, nonce).await,
+                Err(e) => debug!("Error while retrieving next nonce of {} for future pool: {}", source.as_address(self.network), e)
+            }
+        }
+
+        // Save the mempool state for the stats
+        {
+            let entry = {
+                let mempool = self.mempool.read().await;
+                let txs = mempool.get_txs().values();
+                MempoolHistoryEntry {
+                    timestamp: get_current_time_in_seconds(),
+                    height: current_height,
+                    count: mempool.size(),
+                    total_size: txs.clone().map(|sorted_tx| sorted_tx.get_size()).sum(),
+                    total_fees: txs.fold(0u64, |total, sorted_tx| total.saturating_add(sorted_tx.get_fee()))
+                }
+            };
+
+            let mut history = self.mempool_history.lock().await;
+            if history.len() >= MEMPOOL_HISTORY_SIZE {
+                history.pop_front();
+            }
+            history.push_back(entry);
+        }
+
         info!("Processed block {} at height {} in {:?} with {} txs (DAG: {})", block_hash, block.get_height(), start.elapsed(), block.get_txs_count(), block_is_ordered);
 
         // Broadcast to p2p nodes
@@ -2146,7 +3016,7 @@ impl<S: Storage> Blockchain<S> {
             if let Some(p2p) = self.p2p.read().await.as_ref() {
                 trace!("P2p locked, broadcasting in new task");
                 let p2p = p2p.clone();
-                let pruned_topoheight = storage.get_pruned_topoheight().await?;
+                let pruned_topoheight = storage.get_shared_pruned_topoheight().await?;
                 let block = block.clone();
                 let block_hash = block_hash.clone();
                 tokio::spawn(async move {
@@ -2460,6 +3330,70 @@ pub async fn estimate_required_tx_fees<P: AccountProvider>(provider: &P, current
     Ok(calculate_tx_fee(tx.size(), output_count, new_addresses))
 }
 
+// Relay policy of this node, on top of the consensus rules
+// TXs in blocks are not verified against it
+struct RelayPolicy {
+    // minimum fee per byte of the TX size
+    min_fee_per_byte: u64,
+    // minimum amount burned by a burn TX, transfers amounts are encrypted and can't be checked
+    min_burn_amount: u64
+}
+
+impl RelayPolicy {
+    fn verify(&self, tx: &Transaction, tx_size: usize) -> Result<(), BlockchainError> {
+        let required_fee = self.min_fee_per_byte.saturating_mul(tx_size as u64);
+        if tx.get_fee() < required_fee {
+            return Err(BlockchainError::RelayFeeTooLow(required_fee, tx.get_fee()))
+        }
+
+        if let TransactionType::Burn(payload) = tx.get_data() {
+            if payload.amount < self.min_burn_amount {
+                return Err(BlockchainError::BurnAmountTooLow(payload.amount, self.min_burn_amount))
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Serialize the TXs saved from the mempool
+// Each TX is prefixed by its size so an invalid one can be skipped when reading them back
+fn write_saved_txs<'a, I: Iterator<Item = &'a Transaction>>(txs: I) -> Vec<u8> {
+    let mut writer = Writer::new();
+    for tx in txs {
+        let bytes = tx.to_bytes();
+        writer.write_u32(&(bytes.len() as u32));
+        writer.write_bytes(&bytes);
+    }
+
+    writer.bytes()
+}
+
+// Read the TXs saved by `write_saved_txs`
+// Returns the TXs read and the count of entries skipped because they are invalid or truncated
+fn read_saved_txs(bytes: &[u8]) -> (Vec<Transaction>, usize) {
+    let mut reader = Reader::new(bytes);
+    let mut txs = Vec::new();
+    let mut skipped = 0;
+    while reader.size() > 0 {
+        let entry = match reader.read_u32().and_then(|size| reader.read_bytes_ref(size as usize)) {
+            Ok(entry) => entry,
+            Err(_) => {
+                // The end of the file is lost, nothing else can be read
+                skipped += 1;
+                break;
+            }
+        };
+
+        match Transaction::from_bytes(entry) {
+            Ok(tx) => txs.push(tx),
+            Err(_) => skipped += 1
+        }
+    }
+
+    (txs, skipped)
+}
+
 // Get the block reward for a side block based on how many side blocks exists at same height
 pub fn side_block_reward_percentage(side_blocks: u64) -> u64 {
     let mut side_block_percent = SIDE_BLOCK_REWARD_PERCENT;
@@ -2476,18 +3410,6 @@ pub fn side_block_reward_percentage(side_blocks: u64) -> u64 {
     side_block_percent
 }
 
-// Calculate the block reward based on the current supply
-pub fn get_block_reward(supply: u64) -> u64 {
-    // Prevent any overflow
-    if supply >= MAXIMUM_SUPPLY {
-        // Max supply reached, do we want to generate small fixed amount of coins? 
-        return 0
-    }
-
-    let base_reward = (MAXIMUM_SUPPLY - supply) >> EMISSION_SPEED_FACTOR;
-    base_reward * BLOCK_TIME_MILLIS / MILLIS_PER_SECOND / 180
-}
-
 // Returns the fee percentage for a block at a given height
 pub fn get_block_dev_fee(height: u64) -> u64 {
     for threshold in DEV_FEES.iter() {
@@ -2515,6 +3437,8 @@ pub fn get_block_dev_fee(height: u64) -> u64 {
 
 #[cfg(test)]
 mod tests {
+    use xelis_common::{config::COIN_VALUE, crypto::KeyPair};
+    use crate::core::test_utils::create_burn_tx;
     use super::*;
 
     #[test]
@@ -2524,4 +3448,64 @@ mod tests {
         assert_eq!(side_block_reward_percentage(2), SIDE_BLOCK_REWARD_PERCENT / 4);
         assert_eq!(side_block_reward_percentage(3), SIDE_BLOCK_REWARD_MIN_PERCENT);
     }
-}
\ No newline at end of file
+    #[test]
+    fn test_saved_txs_round_trip() {
+        let keypair = KeyPair::new();
+        let txs: Vec<Transaction> = (0..3).map(|nonce| create_burn_tx(&keypair, 1000 + nonce, nonce)).collect();
+
+        let bytes = write_saved_txs(txs.iter());
+        let (read, skipped) = read_saved_txs(&bytes);
+        assert_eq!(skipped, 0);
+        assert_eq!(read.iter().map(Transaction::hash).collect::<Vec<_>>(), txs.iter().map(Transaction::hash).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_saved_txs_skip_invalid_entries() {
+        let keypair = KeyPair::new();
+        let first = create_burn_tx(&keypair, 1000, 0);
+        let last = create_burn_tx(&keypair, 1000, 1);
+
+        // A corrupted entry in the middle doesn't prevent reading the next ones
+        let mut bytes = write_saved_txs([&first].into_iter());
+        bytes.extend_from_slice(&4u32.to_be_bytes());
+        bytes.extend_from_slice(&[0xFF; 4]);
+        bytes.extend(write_saved_txs([&last].into_iter()));
+
+        let (read, skipped) = read_saved_txs(&bytes);
+        assert_eq!(skipped, 1);
+        assert_eq!(read.iter().map(Transaction::hash).collect::<Vec<_>>(), vec![first.hash(), last.hash()]);
+
+        // A truncated file keeps the TXs fully written
+        let bytes = write_saved_txs([&first, &last].into_iter());
+        let (read, skipped) = read_saved_txs(&bytes[..bytes.len() - 1]);
+        assert_eq!(skipped, 1);
+        assert_eq!(read.len(), 1);
+        assert_eq!(read[0].hash(), first.hash());
+
+        assert_eq!(read_saved_txs(&[]).0.len(), 0);
+    }
+    #[test]
+    fn test_relay_policy_fee_per_byte() {
+        let tx = create_burn_tx(&KeyPair::new(), 1000, 0);
+        let policy = |min_fee_per_byte| RelayPolicy { min_fee_per_byte, min_burn_amount: 0 };
+
+        // Disabled by default
+        assert!(policy(0).verify(&tx, 500).is_ok());
+        // Exactly the fee required
+        assert!(policy(2).verify(&tx, 500).is_ok());
+        assert!(matches!(policy(2).verify(&tx, 501), Err(BlockchainError::RelayFeeTooLow(1002, 1000))));
+        assert!(matches!(policy(3).verify(&tx, 500), Err(BlockchainError::RelayFeeTooLow(1500, 1000))));
+        // No overflow on huge policies
+        assert!(matches!(policy(u64::MAX).verify(&tx, 2), Err(BlockchainError::RelayFeeTooLow(u64::MAX, 1000))));
+    }
+
+    #[test]
+    fn test_relay_policy_burn_amount() {
+        // The TX burns one coin
+        let tx = create_burn_tx(&KeyPair::new(), 1000, 0);
+        let policy = |min_burn_amount| RelayPolicy { min_fee_per_byte: 0, min_burn_amount };
+
+        assert!(policy(COIN_VALUE).verify(&tx, 500).is_ok());
+        assert!(matches!(policy(COIN_VALUE + 1).verify(&tx, 500), Err(BlockchainError::BurnAmountTooLow(COIN_VALUE, _))));
+    }
+}
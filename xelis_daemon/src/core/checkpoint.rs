@@ -0,0 +1,108 @@
+use std::fs;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use xelis_common::{
+    crypto::{Hash, PublicKey, Signature},
+    network::Network,
+    serializer::{Reader, ReaderError, Serializer, Writer}
+};
+use crate::config::{get_checkpoints, DEV_PUBLIC_KEY};
+use super::error::BlockchainError;
+
+// Block hash expected at a topoheight
+// Every block ordered below the latest checkpoint is trusted
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Checkpoint {
+    pub topoheight: u64,
+    pub hash: Hash
+}
+
+impl Checkpoint {
+    pub const fn new(topoheight: u64, hash: Hash) -> Self {
+        Self {
+            topoheight,
+            hash
+        }
+    }
+}
+
+impl Serializer for Checkpoint {
+    fn read(reader: &mut Reader) -> Result<Self, ReaderError> {
+        Ok(Self {
+            topoheight: reader.read_u64()?,
+            hash: reader.read_hash()?
+        })
+    }
+
+    fn write(&self, writer: &mut Writer) {
+        writer.write_u64(&self.topoheight);
+        writer.write_hash(&self.hash);
+    }
+}
+
+// Checkpoints file that can be provided to the daemon
+// The signature is done by the developer key on the network and all the checkpoints
+#[derive(Serialize, Deserialize)]
+pub struct CheckpointsFile {
+    pub network: Network,
+    pub checkpoints: Vec<Checkpoint>,
+    pub signature: Option<Signature>
+}
+
+impl CheckpointsFile {
+    // Bytes signed by the developer key
+    pub fn get_signing_bytes(&self) -> Vec<u8> {
+        let mut writer = Writer::new();
+        self.network.write(&mut writer);
+        writer.write_u16(self.checkpoints.len() as u16);
+        for checkpoint in &self.checkpoints {
+            checkpoint.write(&mut writer);
+        }
+        writer.bytes()
+    }
+
+    // Verify that the file is signed by the expected key
+    pub fn is_signed_by(&self, key: &PublicKey) -> bool {
+        match &self.signature {
+            Some(signature) => signature.verify(&self.get_signing_bytes(), key),
+            None => false
+        }
+    }
+}
+
+// Load the checkpoints to use for the network
+// Embedded checkpoints are always included, a file can extend them
+pub fn load_checkpoints(network: &Network, path: Option<&String>, allow_unsigned: bool) -> Result<Vec<Checkpoint>, BlockchainError> {
+    let mut checkpoints: Vec<Checkpoint> = get_checkpoints(network).iter()
+        .map(|(topoheight, hash)| Checkpoint::new(*topoheight, hash.clone()))
+        .collect();
+
+    if let Some(path) = path {
+        info!("Loading checkpoints from {}", path);
+        let content = fs::read_to_string(path).map_err(|e| BlockchainError::InvalidCheckpointsFile(e.to_string()))?;
+        let file: CheckpointsFile = serde_json::from_str(&content).map_err(|e| BlockchainError::InvalidCheckpointsFile(e.to_string()))?;
+        if file.network != *network {
+            return Err(BlockchainError::InvalidCheckpointsFile(format!("checkpoints are for {} network", file.network)))
+        }
+
+        if !file.is_signed_by(&DEV_PUBLIC_KEY) {
+            if !allow_unsigned {
+                return Err(BlockchainError::InvalidCheckpointsFile("invalid or missing signature".to_owned()))
+            }
+            warn!("Checkpoints file {} is not signed, make sure you trust it", path);
+        }
+
+        for checkpoint in file.checkpoints {
+            match checkpoints.iter().find(|c| c.topoheight == checkpoint.topoheight) {
+                Some(c) if c.hash != checkpoint.hash => {
+                    return Err(BlockchainError::InvalidCheckpointsFile(format!("checkpoint at topoheight {} conflicts with an embedded one", checkpoint.topoheight)))
+                },
+                Some(_) => {},
+                None => checkpoints.push(checkpoint)
+            }
+        }
+    }
+
+    checkpoints.sort_by_key(|c| c.topoheight);
+    Ok(checkpoints)
+}
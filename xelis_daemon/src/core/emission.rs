@@ -0,0 +1,45 @@
+use xelis_common::config::MAXIMUM_SUPPLY;
+use crate::config::{BLOCK_TIME_MILLIS, EMISSION_SPEED_FACTOR, MILLIS_PER_SECOND};
+
+// Calculate the block reward based on the current supply
+pub fn get_block_reward(supply: u64) -> u64 {
+    // Prevent any overflow
+    if supply >= MAXIMUM_SUPPLY {
+        // Max supply reached, do we want to generate small fixed amount of coins? 
+        return 0
+    }
+
+    let base_reward = (MAXIMUM_SUPPLY - supply) >> EMISSION_SPEED_FACTOR;
+    base_reward * BLOCK_TIME_MILLIS / MILLIS_PER_SECOND / 180
+}
+
+// Project the supply after N blocks starting from the supply given
+// It expects one block per height, side blocks rewards are not included
+pub fn get_projected_supply(mut supply: u64, blocks: u64) -> u64 {
+    for _ in 0..blocks {
+        let reward = get_block_reward(supply);
+        if reward == 0 {
+            break;
+        }
+        supply += reward;
+    }
+
+    supply
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_projected_supply() {
+        assert_eq!(get_projected_supply(0, 0), 0);
+        assert_eq!(get_projected_supply(0, 1), get_block_reward(0));
+
+        let supply = get_projected_supply(0, 2);
+        assert_eq!(supply, get_block_reward(0) + get_block_reward(get_block_reward(0)));
+        assert_eq!(get_projected_supply(supply, 10), get_projected_supply(0, 12));
+
+        assert_eq!(get_projected_supply(MAXIMUM_SUPPLY, 10), MAXIMUM_SUPPLY);
+    }
+}
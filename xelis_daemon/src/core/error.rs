@@ -11,6 +11,7 @@ use xelis_common::{
         XelisHashError
     },
     difficulty::DifficultyError,
+    network::Network,
     prompt::PromptError,
     serializer::ReaderError,
     time::TimestampMillis,
@@ -88,8 +89,14 @@ pub enum BlockchainError {
     DuplicateRegistration(Address), // address
     #[error("Invalid Tx fee, expected at least {}, got {}", _0, _1)]
     InvalidTxFee(u64, u64),
+    #[error("Tx fee is below the minimum relay fee of this node, expected at least {}, got {}", _0, _1)]
+    RelayFeeTooLow(u64, u64),
+    #[error("Tx burned amount {} is below the minimum of this node ({})", _0, _1)]
+    BurnAmountTooLow(u64, u64),
     #[error("Fees are lower for this TX than the overrided TX, expected at least {}, got {}", _0, _1)]
     FeesToLowToOverride(u64, u64),
+    #[error("Too many TXs of {} are waiting on previous nonces, maximum is {}", _0, _1)]
+    TooManyFutureTxs(Address, usize),
     #[error("No account found for {}", _0)]
     AccountNotFound(Address),
     #[error("Address {} is not registered", _0)]
@@ -118,6 +125,8 @@ pub enum BlockchainError {
     InvalidTransactionExtraDataTooBig(usize, usize),
     #[error("Invalid network state")]
     InvalidNetwork,
+    #[error("Address is for {} but the node is running on {}", _0, _1)]
+    InvalidAddressNetwork(Network, Network),
     #[error("Error while retrieving block by hash: {} not found", _0)]
     BlockNotFound(Hash),
     #[error("Error while retrieving block by height: {} not found", _0)]
@@ -164,6 +173,9 @@ pub enum BlockchainError {
     UnexpectedTransactionVariant,
     #[error("Unexpected error on database: {}", _0)]
     DatabaseError(#[from] sled::Error),
+    #[cfg(feature = "rocksdb")]
+    #[error("Unexpected error on RocksDB: {}", _0)]
+    RocksDBError(#[from] rocksdb::Error),
     #[error("Unsupported operation")]
     UnsupportedOperation,
     #[error("Data not found on disk: {}", _0)]
@@ -172,6 +184,10 @@ pub enum BlockchainError {
     ConfigMaxChainResponseSize,
     #[error("Invalid config sync mode")]
     ConfigSyncMode,
+    #[error("Invalid paramater: max reorg depth isn't in range")]
+    ConfigMaxReorgDepth,
+    #[error("Invalid paramater: TXs verification threads count must be at least 1")]
+    ConfigTxsVerificationThreads,
     #[error("Expected at least one tips")]
     ExpectedTips,
     #[error("Block {0} has invalid tips count: {1}")]
@@ -190,6 +206,8 @@ pub enum BlockchainError {
     InvalidReachability,
     #[error("Block has too much deviated")]
     BlockDeviation,
+    #[error("Block {} would reorganize the chain on {} blocks, maximum allowed is {}", _0, _1, _2)]
+    ReorgTooDeep(Hash, u64, u64),
     #[error("Invalid genesis block hash")]
     InvalidGenesisHash,
     #[error("Invalid tx {} nonce (got {} expected {}) for {}", _0, _1, _2, _3)]
@@ -224,6 +242,20 @@ pub enum BlockchainError {
     PruneLowerThanLastPruned,
     #[error("Auto prune mode is misconfigured")]
     AutoPruneMode,
+    #[error("Prune mode is misconfigured")]
+    PruneMode,
+    #[error("Invalid checkpoints file: {}", _0)]
+    InvalidCheckpointsFile(String),
+    #[error("Block at topoheight {} doesn't match the checkpoint, expected {} got {}", _0, _1, _2)]
+    CheckpointMismatch(u64, Hash, Hash),
+    #[error("Invalid snapshot: {}", _0)]
+    InvalidSnapshot(String),
+    #[error("Invalid snapshot checksum")]
+    SnapshotChecksumMismatch,
+    #[error("A snapshot can only be imported on an empty chain")]
+    SnapshotChainNotEmpty,
+    #[error("A commit point is already started in the storage")]
+    CommitPointAlreadyStarted,
     #[error(transparent)]
     TryFromSliceError(#[from] std::array::TryFromSliceError),
     #[error("Invalid ciphertext")]
@@ -242,6 +274,18 @@ pub enum BlockchainError {
     SenderIsReceiver,
     #[error("Invalid transaction proof: {}", _0)]
     TransactionProof(ProofVerificationError),
+    #[error("Invalid multisig setup")]
+    InvalidMultiSigSetup,
+    #[error("Multisig signatures are required for this account")]
+    MultiSigRequired,
+    #[error("Account is not a multisig")]
+    MultiSigNotConfigured,
+    #[error("Not enough multisig signatures: {} provided, {} required", _0, _1)]
+    MultiSigThreshold(usize, u8),
+    #[error("Invalid multisig participant {}", _0)]
+    MultiSigParticipant(u8),
+    #[error("A multisig setup Tx {} is pending for this account", _0)]
+    MultiSigSetupPending(Hash),
     #[error("Error while generating pow hash")]
     POWHashError(#[from] XelisHashError),
 }
@@ -258,6 +302,11 @@ impl From<VerificationError<BlockchainError>> for BlockchainError {
             VerificationError::InvalidNonce => BlockchainError::InvalidNonce,
             VerificationError::SenderIsReceiver => BlockchainError::NoSenderOutput,
             VerificationError::InvalidSignature => BlockchainError::InvalidTransactionSignature,
+            VerificationError::InvalidMultiSigSetup => BlockchainError::InvalidMultiSigSetup,
+            VerificationError::MultiSigRequired => BlockchainError::MultiSigRequired,
+            VerificationError::MultiSigNotConfigured => BlockchainError::MultiSigNotConfigured,
+            VerificationError::MultiSigThreshold(provided, required) => BlockchainError::MultiSigThreshold(provided, required),
+            VerificationError::MultiSigParticipant(id) => BlockchainError::MultiSigParticipant(id),
             VerificationError::State(s) => s,
             VerificationError::Proof(proof) => BlockchainError::TransactionProof(proof)
         }
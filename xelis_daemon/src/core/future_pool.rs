@@ -0,0 +1,216 @@
+use std::{
+    collections::{hash_map::Entry, BTreeMap, HashMap},
+    num::NonZeroUsize
+};
+use lru::LruCache;
+use xelis_common::crypto::{Hash, PublicKey};
+
+// TX waiting in the pool with the source and nonce it was sent with
+struct FutureTx<T> {
+    source: PublicKey,
+    nonce: u64,
+    tx: T
+}
+
+// Bounded pool of TXs received with a nonce ahead of the next one expected for their source
+// They are promoted to the mempool once the missing nonces are filled
+// The oldest TXs are evicted once it is full
+pub struct FutureTxPool<T> {
+    txs: LruCache<Hash, FutureTx<T>>,
+    // source -> nonce -> TX waiting with this nonce
+    nonces: HashMap<PublicKey, BTreeMap<u64, Hash>>
+}
+
+impl<T> FutureTxPool<T> {
+    pub fn new(capacity: NonZeroUsize) -> Self {
+        Self {
+            txs: LruCache::new(capacity),
+            nonces: HashMap::new()
+        }
+    }
+
+    pub fn contains(&self, hash: &Hash) -> bool {
+        self.txs.contains(hash)
+    }
+
+    // Get the TX of the source waiting with this nonce
+    pub fn get_with_nonce(&self, source: &PublicKey, nonce: u64) -> Option<&T> {
+        let hash = self.nonces.get(source)?.get(&nonce)?;
+        self.txs.peek(hash).map(|future_tx| &future_tx.tx)
+    }
+
+    // Count of TXs of the source waiting
+    pub fn count_for(&self, source: &PublicKey) -> usize {
+        self.nonces.get(source).map_or(0, BTreeMap::len)
+    }
+
+    // Add a TX waiting on the previous nonces of its source
+    // A TX already waiting with the same nonce is replaced
+    pub fn insert(&mut self, hash: Hash, source: PublicKey, nonce: u64, tx: T) {
+        let nonces = self.nonces.entry(source.clone()).or_insert_with(BTreeMap::new);
+        if let Some(previous) = nonces.insert(nonce, hash.clone()) {
+            self.txs.pop(&previous);
+        }
+
+        if let Some((_, evicted)) = self.txs.push(hash, FutureTx { source, nonce, tx }) {
+            self.unlink(&evicted.source, evicted.nonce);
+        }
+    }
+
+    // Remove the nonce from the ones waiting for this source
+    fn unlink(&mut self, source: &PublicKey, nonce: u64) {
+        if let Entry::Occupied(mut entry) = self.nonces.entry(source.clone()) {
+            entry.get_mut().remove(&nonce);
+            if entry.get().is_empty() {
+                entry.remove();
+            }
+        }
+    }
+
+    // Remove and returns the TX of the source waiting on this nonce
+    // TXs with a lower nonce can't be included anymore and are dropped
+    pub fn take_next(&mut self, source: &PublicKey, nonce: u64) -> Option<(Hash, T)> {
+        let Entry::Occupied(mut entry) = self.nonces.entry(source.clone()) else {
+            return None
+        };
+
+        let mut next = None;
+        while let Some((tx_nonce, hash)) = entry.get_mut().pop_first() {
+            if tx_nonce > nonce {
+                entry.get_mut().insert(tx_nonce, hash);
+                break;
+            }
+
+            if let Some(future_tx) = self.txs.pop(&hash) {
+                if tx_nonce == nonce {
+                    next = Some((hash, future_tx.tx));
+                    break;
+                }
+            }
+        }
+
+        if entry.get().is_empty() {
+            entry.remove();
+        }
+
+        next
+    }
+
+    // Get all the sources having TXs waiting
+    pub fn get_sources(&self) -> Vec<PublicKey> {
+        self.nonces.keys().cloned().collect()
+    }
+
+    // Iterate over all the TXs waiting
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.txs.iter().map(|(_, future_tx)| &future_tx.tx)
+    }
+
+    pub fn len(&self) -> usize {
+        self.txs.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.txs.clear();
+        self.nonces.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use xelis_common::crypto::KeyPair;
+    use super::*;
+
+    fn new_pool(capacity: usize) -> FutureTxPool<u64> {
+        FutureTxPool::new(NonZeroUsize::new(capacity).unwrap())
+    }
+
+    fn new_source() -> PublicKey {
+        KeyPair::new().get_public_key().compress()
+    }
+
+    // TXs are identified by their hash, the value stored is the nonce to check what is returned
+    fn insert(pool: &mut FutureTxPool<u64>, source: &PublicKey, nonce: u64, id: u8) -> Hash {
+        let hash = Hash::new([id; 32]);
+        pool.insert(hash.clone(), source.clone(), nonce, nonce);
+        hash
+    }
+
+    #[test]
+    fn test_insert_and_take_next() {
+        let mut pool = new_pool(8);
+        let source = new_source();
+        let first = insert(&mut pool, &source, 2, 1);
+        let second = insert(&mut pool, &source, 3, 2);
+
+        assert_eq!(pool.len(), 2);
+        assert_eq!(pool.count_for(&source), 2);
+        assert_eq!(pool.get_with_nonce(&source, 3), Some(&3));
+        let mut waiting: Vec<u64> = pool.iter().copied().collect();
+        waiting.sort();
+        assert_eq!(waiting, vec![2, 3]);
+
+        // Nonce 1 is still missing
+        assert!(pool.take_next(&source, 1).is_none());
+        assert_eq!(pool.len(), 2);
+
+        assert_eq!(pool.take_next(&source, 2), Some((first, 2)));
+        assert_eq!(pool.take_next(&source, 3), Some((second, 3)));
+        assert_eq!(pool.len(), 0);
+        assert!(pool.get_sources().is_empty());
+    }
+
+    #[test]
+    fn test_take_next_drops_lower_nonces() {
+        let mut pool = new_pool(8);
+        let source = new_source();
+        let outdated = insert(&mut pool, &source, 2, 1);
+        let next = insert(&mut pool, &source, 4, 2);
+
+        // Nonce 2 was filled by another TX, only the nonce 4 can still be included
+        assert_eq!(pool.take_next(&source, 4), Some((next, 4)));
+        assert!(!pool.contains(&outdated));
+        assert_eq!(pool.count_for(&source), 0);
+    }
+
+    #[test]
+    fn test_replace_same_nonce() {
+        let mut pool = new_pool(8);
+        let source = new_source();
+        let previous = insert(&mut pool, &source, 2, 1);
+        let replacement = insert(&mut pool, &source, 2, 2);
+
+        assert!(!pool.contains(&previous));
+        assert!(pool.contains(&replacement));
+        assert_eq!(pool.len(), 1);
+        assert_eq!(pool.count_for(&source), 1);
+    }
+
+    #[test]
+    fn test_eviction_unlinks_oldest() {
+        let mut pool = new_pool(2);
+        let first_source = new_source();
+        let second_source = new_source();
+        let oldest = insert(&mut pool, &first_source, 2, 1);
+        insert(&mut pool, &second_source, 2, 2);
+        insert(&mut pool, &second_source, 3, 3);
+
+        assert!(!pool.contains(&oldest));
+        assert_eq!(pool.len(), 2);
+        assert_eq!(pool.count_for(&first_source), 0);
+        assert!(pool.take_next(&first_source, 2).is_none());
+        assert_eq!(pool.get_sources(), vec![second_source]);
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut pool = new_pool(8);
+        let source = new_source();
+        insert(&mut pool, &source, 2, 1);
+        pool.clear();
+
+        assert_eq!(pool.len(), 0);
+        assert!(pool.get_with_nonce(&source, 2).is_none());
+        assert!(pool.get_sources().is_empty());
+    }
+}
@@ -0,0 +1,119 @@
+use serde::Serialize;
+use xelis_common::network::Network;
+use crate::config::get_hard_forks;
+
+// Consensus upgrade activated starting a specific height
+// Every block from this height must use its version
+#[derive(Serialize, Debug, Clone, Copy)]
+pub struct HardFork {
+    // Height at which the hard fork is activated
+    pub height: u64,
+    // Block version required
+    pub version: u8,
+    // Highest transaction version accepted in blocks of this version
+    pub max_tx_version: u8,
+    // Short description of the rules changed
+    pub changelog: &'static str
+}
+
+// Check that a schedule starts at genesis and that each hard fork
+// is activated after the previous one with a higher block version
+// Transactions versions accepted can't be lowered by a hard fork
+pub const fn is_valid_hard_forks_schedule(hard_forks: &[HardFork]) -> bool {
+    if hard_forks.is_empty() || hard_forks[0].height != 0 {
+        return false
+    }
+
+    let mut i = 1;
+    while i < hard_forks.len() {
+        let (previous, current) = (&hard_forks[i - 1], &hard_forks[i]);
+        if previous.height >= current.height || previous.version >= current.version || previous.max_tx_version > current.max_tx_version {
+            return false
+        }
+        i += 1;
+    }
+
+    true
+}
+
+// Get the hard fork active at the given height
+pub fn get_hard_fork_at_height(network: &Network, height: u64) -> &'static HardFork {
+    let hard_forks = get_hard_forks(network);
+    hard_forks.iter()
+        .rev()
+        .find(|hard_fork| hard_fork.height <= height)
+        .unwrap_or(&hard_forks[0])
+}
+
+// Get the hard fork that introduced this block version
+pub fn get_hard_fork_for_version(network: &Network, version: u8) -> Option<&'static HardFork> {
+    get_hard_forks(network).iter().find(|hard_fork| hard_fork.version == version)
+}
+
+// Block version expected at this height
+// Rules changes are gated on the block version instead of the height
+pub fn get_version_at_height(network: &Network, height: u64) -> u8 {
+    get_hard_fork_at_height(network, height).version
+}
+
+// Check if a transaction version can be included in a block of this version
+pub fn is_tx_version_allowed_in_block_version(network: &Network, block_version: u8, tx_version: u8) -> bool {
+    get_hard_fork_for_version(network, block_version)
+        .is_some_and(|hard_fork| tx_version <= hard_fork.max_tx_version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NETWORKS: [Network; 3] = [Network::Mainnet, Network::Testnet, Network::Dev];
+
+    const fn hard_fork(height: u64, version: u8, max_tx_version: u8) -> HardFork {
+        HardFork {
+            height,
+            version,
+            max_tx_version,
+            changelog: ""
+        }
+    }
+
+    #[test]
+    fn test_hard_forks_schedule() {
+        for network in NETWORKS {
+            assert!(is_valid_hard_forks_schedule(get_hard_forks(&network)));
+        }
+    }
+
+    #[test]
+    fn test_valid_hard_forks_schedule() {
+        assert!(is_valid_hard_forks_schedule(&[hard_fork(0, 0, 0)]));
+        assert!(is_valid_hard_forks_schedule(&[hard_fork(0, 0, 0), hard_fork(10, 1, 1)]));
+        assert!(is_valid_hard_forks_schedule(&[hard_fork(0, 0, 0), hard_fork(10, 1, 0), hard_fork(20, 3, 2)]));
+    }
+
+    #[test]
+    fn test_invalid_hard_forks_schedule() {
+        // Empty or not starting at genesis
+        assert!(!is_valid_hard_forks_schedule(&[]));
+        assert!(!is_valid_hard_forks_schedule(&[hard_fork(1, 0, 0)]));
+        // Same or decreasing height
+        assert!(!is_valid_hard_forks_schedule(&[hard_fork(0, 0, 0), hard_fork(0, 1, 1)]));
+        assert!(!is_valid_hard_forks_schedule(&[hard_fork(0, 0, 0), hard_fork(20, 1, 1), hard_fork(10, 2, 1)]));
+        // Same or decreasing version
+        assert!(!is_valid_hard_forks_schedule(&[hard_fork(0, 0, 0), hard_fork(10, 0, 1)]));
+        assert!(!is_valid_hard_forks_schedule(&[hard_fork(0, 1, 0), hard_fork(10, 2, 1), hard_fork(20, 1, 1)]));
+        // Decreasing transaction version
+        assert!(!is_valid_hard_forks_schedule(&[hard_fork(0, 0, 1), hard_fork(10, 1, 0)]));
+    }
+
+    #[test]
+    fn test_version_at_height() {
+        for network in NETWORKS {
+            for hard_fork in get_hard_forks(&network) {
+                assert_eq!(get_version_at_height(&network, hard_fork.height), hard_fork.version);
+                assert!(is_tx_version_allowed_in_block_version(&network, hard_fork.version, hard_fork.max_tx_version));
+                assert!(!is_tx_version_allowed_in_block_version(&network, hard_fork.version, hard_fork.max_tx_version + 1));
+            }
+        }
+    }
+}
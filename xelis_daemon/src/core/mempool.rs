@@ -12,6 +12,7 @@ use serde::{Serialize, Deserialize};
 use indexmap::IndexSet;
 use log::{trace, debug, warn};
 use xelis_common::{
+    api::daemon::TransactionConflictReason,
     time::{TimestampSeconds, get_current_time_in_seconds},
     crypto::elgamal::Ciphertext,
     network::Network,
@@ -51,7 +52,7 @@ pub struct AccountCache {
 
 pub struct Mempool {
     // Used for log purpose
-    mainnet: bool,
+    network: Network,
     // store all txs waiting to be included in a block
     txs: HashMap<Arc<Hash>, SortedTx>,
     // store all sender's nonce for faster finding
@@ -62,19 +63,26 @@ impl Mempool {
     // Create a new empty mempool
     pub fn new(network: Network) -> Self {
         Mempool {
-            mainnet: network.is_mainnet(),
+            network,
             txs: HashMap::new(),
             caches: HashMap::new()
         }
     }
 
     // All checks are made in Blockchain before calling this function
-    pub async fn add_tx<S: Storage>(&mut self, storage: &S, topoheight: u64, hash: Hash, tx: Arc<Transaction>, size: usize) -> Result<(), BlockchainError> {
-        let mut state = MempoolState::new(&self, storage, topoheight);
+    // Verify a TX against the current mempool state without adding it
+    pub async fn verify_tx<S: Storage>(&self, storage: &S, topoheight: u64, block_version: u8, tx: &Transaction) -> Result<(), BlockchainError> {
+        let mut state = MempoolState::new(&self, storage, topoheight, block_version);
+        tx.verify(&mut state).await?;
+        Ok(())
+    }
+
+    pub async fn add_tx<S: Storage>(&mut self, storage: &S, topoheight: u64, block_version: u8, hash: Hash, tx: Arc<Transaction>, size: usize) -> Result<(), BlockchainError> {
+        let mut state = MempoolState::new(&self, storage, topoheight, block_version);
         tx.verify(&mut state).await?;
 
         let balances = state.get_sender_balances(tx.get_source())
-            .ok_or_else(|| BlockchainError::AccountNotFound(tx.get_source().as_address(storage.is_mainnet())))?
+            .ok_or_else(|| BlockchainError::AccountNotFound(tx.get_source().as_address(storage.network())))?
             .iter().map(|(asset, ciphertext)| (Hash::clone(*asset), ciphertext.clone())).collect();
 
         let hash = Arc::new(hash);
@@ -83,7 +91,7 @@ impl Mempool {
         let mut must_update = true;
         if let Some(cache) = self.caches.get_mut(tx.get_source()) {
             // delete the TX if its in the range of already tracked nonces
-            trace!("Cache found for owner {} with nonce range {}-{}, nonce = {}", tx.get_source().as_address(self.mainnet), cache.get_min(), cache.get_max(), nonce);
+            trace!("Cache found for owner {} with nonce range {}-{}, nonce = {}", tx.get_source().as_address(self.network), cache.get_min(), cache.get_max(), nonce);
 
             // Support the case where the nonce is already used in cache
             // If a user want to cancel its TX, he can just resend a TX with same nonce and higher fee
@@ -187,17 +195,50 @@ impl Mempool {
                 }
             }
         } else {
-            warn!("No cache found for owner {} while deleting TX {}", tx.get_tx().get_source().as_address(self.mainnet), hash);
+            warn!("No cache found for owner {} while deleting TX {}", tx.get_tx().get_source().as_address(self.network), hash);
         }
 
         if delete {
-            trace!("Removing empty nonce cache for owner {}", key.as_address(self.mainnet));
+            trace!("Removing empty nonce cache for owner {}", key.as_address(self.network));
             self.caches.remove(key);
         }
 
         Ok(())
     }
 
+    // Delete the TXs of the accounts whose lowest nonce TX is in mempool for too long
+    // TXs with a higher nonce of the same account depend on it and are deleted too
+    // So the cache is deleted entirely and the nonce is free to be used again
+    pub fn remove_expired_txs(&mut self, expiration: TimestampSeconds) -> Vec<(Arc<Hash>, SortedTx)> {
+        trace!("Removing expired TXs from mempool");
+        let now = get_current_time_in_seconds();
+        let network = self.network;
+        let txs = &mut self.txs;
+
+        let mut expired_transactions = Vec::new();
+        self.caches.retain(|key, cache| {
+            let first_seen = cache.txs.iter()
+                .filter_map(|hash| txs.get(hash))
+                .min_by_key(|sorted_tx| sorted_tx.get_tx().get_nonce())
+                .map(|sorted_tx| sorted_tx.get_first_seen());
+
+            match first_seen {
+                Some(first_seen) if first_seen.saturating_add(expiration) > now => true,
+                _ => {
+                    debug!("Deleting {} expired TXs of {}", cache.txs.len(), key.as_address(network));
+                    for hash in cache.txs.drain(..) {
+                        if let Some(sorted_tx) = txs.remove(&hash) {
+                            expired_transactions.push((hash, sorted_tx));
+                        }
+                    }
+                    false
+                }
+            }
+        });
+
+        expired_transactions
+    }
+
     // Get the nonce cache for all keys
     pub fn get_caches(&self) -> &HashMap<PublicKey, AccountCache> {
         &self.caches
@@ -267,29 +308,30 @@ impl Mempool {
     // Because of DAG reorg, we can't only check updated keys from new block,
     // as a block could be orphaned and the nonce order would change
     // So we need to check all keys from mempool and compare it from storage
-    pub async fn clean_up<S: Storage>(&mut self, storage: &S, topoheight: u64) -> Vec<(Arc<Hash>, SortedTx)> {
+    // Deleted TXs are returned with the reason of their conflict with the chain
+    pub async fn clean_up<S: Storage>(&mut self, storage: &S, topoheight: u64, block_version: u8) -> Vec<(Arc<Hash>, SortedTx, TransactionConflictReason)> {
         trace!("Cleaning up mempool...");
 
         // All deleted sorted txs with their hashes
-        let mut deleted_transactions: Vec<(Arc<Hash>, SortedTx)> = Vec::new();
+        let mut deleted_transactions: Vec<(Arc<Hash>, SortedTx, TransactionConflictReason)> = Vec::new();
 
         let mut cache = HashMap::new();
         // Swap the nonces_cache with cache, so we iterate over cache and reinject it in nonces_cache
         std::mem::swap(&mut cache, &mut self.caches);
 
         for (key, mut cache) in cache {
-            trace!("Cleaning up mempool for owner {}", key.as_address(self.mainnet));
+            trace!("Cleaning up mempool for owner {}", key.as_address(self.network));
             let nonce = match storage.get_last_nonce(&key).await {
                 Ok((_, version)) => version.get_nonce(),
                 Err(e) => {
                     // We get an error while retrieving the last nonce for this key,
                     // that means the key is not in storage anymore, so we can delete safely
                     // we just have to skip this iteration so it's not getting re-injected
-                    warn!("Error while getting nonce for owner {}, he maybe has no nonce anymore, skipping: {}", key.as_address(self.mainnet), e);
+                    warn!("Error while getting nonce for owner {}, he maybe has no nonce anymore, skipping: {}", key.as_address(self.network), e);
                     continue;
                 }
             };
-            trace!("Owner {} has nonce {}, cache min: {}, max: {}", key.as_address(self.mainnet), nonce, cache.get_min(), cache.get_max());
+            trace!("Owner {} has nonce {}, cache min: {}, max: {}", key.as_address(self.network), nonce, cache.get_min(), cache.get_max());
 
             let mut delete_cache = false;
             // Check if the minimum nonce is higher than the new nonce, that means
@@ -297,7 +339,7 @@ impl Mempool {
             // or, check and delete txs if the nonce is lower than the new nonce
             // otherwise the cache is still up to date
             if cache.get_min() > nonce {
-                trace!("All TXs for key {} are orphaned, deleting them", key.as_address(self.mainnet));
+                trace!("All TXs for key {} are orphaned, deleting them", key.as_address(self.network));
                 // We can delete all these TXs as they got automatically orphaned
                 // Because of the suite being broked
                 for hash in cache.txs.iter() {
@@ -307,7 +349,7 @@ impl Mempool {
                 }
                 delete_cache = true;
             } else if cache.get_min() < nonce {
-                trace!("Deleting TXs for owner {} with nonce < {}", key.as_address(self.mainnet), nonce);
+                trace!("Deleting TXs for owner {} with nonce < {}", key.as_address(self.network), nonce);
                 // txs hashes to delete
                 let mut hashes: HashSet<Arc<Hash>> = HashSet::with_capacity(cache.txs.len());
 
@@ -358,13 +400,16 @@ impl Mempool {
                     cache.max = max;
                 }
 
+                // TXs deleted because they are not valid anymore
+                let mut invalid_hashes: IndexSet<Arc<Hash>> = IndexSet::new();
+
                 // delete the nonce cache if no txs are left
                 delete_cache = cache.txs.is_empty();
                 // Cache is not empty yet, but we deleted some TXs from it, balances may be out-dated, verify TXs left
                 // TODO: there may be a way to optimize this even more, by checking if deleted TXs are those who got mined
                 // Which mean, expected balances are still up to date with chain state
                 if !delete_cache && !hashes.is_empty() {
-                    let mut state = MempoolState::new(&self, storage, topoheight);
+                    let mut state = MempoolState::new(&self, storage, topoheight, block_version);
                     let mut txs = Vec::with_capacity(cache.txs.len());
                     for tx_hash in &cache.txs {
                         if let Some(sorted_tx) = self.txs.get(tx_hash) {
@@ -383,7 +428,7 @@ impl Mempool {
                     // NOTE: this can be revert easily in case we are deleting valid TXs also,
                     // But will be slower during high traffic
                     if let Err(e) = Transaction::verify_batch(txs.as_slice(), &mut state).await {
-                        warn!("Error while verifying TXs for sender {}: {}", key.as_address(self.mainnet), e);
+                        warn!("Error while verifying TXs for sender {}: {}", key.as_address(self.network), e);
                         // We may have only one TX invalid, but because they are all linked to each others we delete the whole cache
                         delete_cache = true;
                     } else {
@@ -396,17 +441,17 @@ impl Mempool {
 
                 if delete_cache {
                     // We empty the cache, so we can delete all txs
-                    let mut local_cache = IndexSet::new();
-                    mem::swap(&mut local_cache, &mut cache.txs);
-
-                    hashes.extend(local_cache);
+                    mem::swap(&mut invalid_hashes, &mut cache.txs);
                 }
 
                 // now delete all necessary txs
-                for hash in hashes {
-                    debug!("Deleting TX {} for owner {}", hash, key.as_address(self.mainnet));
+                // TXs below the new nonce lost against the TXs executed with the same nonce
+                let hashes = hashes.into_iter().map(|hash| (hash, TransactionConflictReason::Nonce))
+                    .chain(invalid_hashes.into_iter().map(|hash| (hash, TransactionConflictReason::Balance)));
+                for (hash, reason) in hashes {
+                    debug!("Deleting TX {} for owner {} ({:?} conflict)", hash, key.as_address(self.network), reason);
                     if let Some(sorted_tx) = self.txs.remove(&hash) {
-                        deleted_transactions.push((hash, sorted_tx));
+                        deleted_transactions.push((hash, sorted_tx, reason));
                     } else {
                         // This should never happen, but better to put a warning here
                         // in case of a lurking bug
@@ -416,7 +461,7 @@ impl Mempool {
             }
 
             if !delete_cache {
-                debug!("Re-injecting nonce cache for owner {}", key.as_address(self.mainnet));
+                debug!("Re-injecting nonce cache for owner {}", key.as_address(self.network));
                 self.caches.insert(key, cache);
             }
         }
@@ -8,4 +8,12 @@ pub mod simulator;
 pub mod nonce_checker;
 pub mod tx_selector;
 pub mod state;
-pub mod merkle;
\ No newline at end of file
+pub mod merkle;
+pub mod checkpoint;
+pub mod snapshot;
+pub mod emission;
+pub mod hard_fork;
+pub mod future_pool;
+
+#[cfg(test)]
+pub mod test_utils;
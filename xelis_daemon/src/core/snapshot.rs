@@ -0,0 +1,202 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write}
+};
+use blake3::Hasher;
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use xelis_common::{
+    crypto::{Hash, HASH_SIZE},
+    network::Network,
+    serializer::{Reader, ReaderError, Serializer, Writer}
+};
+use super::{error::BlockchainError, storage::Tips};
+
+// Magic bytes at the start of every snapshot
+const SNAPSHOT_MAGIC: &[u8; 8] = b"XELISSNP";
+// Version of the snapshot format
+const SNAPSHOT_VERSION: u8 = 0;
+// Maximum size of a key or a value in a snapshot
+const SNAPSHOT_MAX_RECORD_SIZE: usize = 16 * 1024 * 1024;
+
+// Tags of the records
+const TAG_END: u8 = 0;
+const TAG_TREE: u8 = 1;
+const TAG_ENTRY: u8 = 2;
+
+// Chain metadata written at the start of a snapshot
+// Used to verify the chain once imported
+#[derive(Debug, Clone)]
+pub struct SnapshotHeader {
+    pub network: Network,
+    pub topoheight: u64,
+    pub height: u64,
+    pub tips: Tips
+}
+
+impl Serializer for SnapshotHeader {
+    fn read(reader: &mut Reader) -> Result<Self, ReaderError> {
+        Ok(Self {
+            network: Network::read(reader)?,
+            topoheight: reader.read_u64()?,
+            height: reader.read_u64()?,
+            tips: Tips::read(reader)?
+        })
+    }
+
+    fn write(&self, writer: &mut Writer) {
+        self.network.write(writer);
+        writer.write_u64(&self.topoheight);
+        writer.write_u64(&self.height);
+        self.tips.write(writer);
+    }
+}
+
+// Record read from a snapshot
+pub enum SnapshotRecord {
+    // Following entries are for this tree
+    Tree(Vec<u8>),
+    // Key and value of an entry
+    Entry(Vec<u8>, Vec<u8>)
+}
+
+// Write a gzip compressed snapshot
+// Format: magic | version | header | records | end tag | checksum
+// The checksum is the blake3 hash of everything before it
+pub struct SnapshotWriter {
+    encoder: GzEncoder<BufWriter<File>>,
+    hasher: Hasher
+}
+
+impl SnapshotWriter {
+    pub fn create(path: &str, header: &SnapshotHeader) -> Result<Self, BlockchainError> {
+        let file = File::create(path)?;
+        let mut writer = Self {
+            encoder: GzEncoder::new(BufWriter::new(file), Compression::default()),
+            hasher: Hasher::new()
+        };
+
+        writer.write_all(SNAPSHOT_MAGIC)?;
+        writer.write_all(&[SNAPSHOT_VERSION])?;
+        writer.write_bytes(&header.to_bytes())?;
+        Ok(writer)
+    }
+
+    fn write_all(&mut self, bytes: &[u8]) -> Result<(), BlockchainError> {
+        self.hasher.update(bytes);
+        self.encoder.write_all(bytes)?;
+        Ok(())
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), BlockchainError> {
+        self.write_all(&(bytes.len() as u32).to_be_bytes())?;
+        self.write_all(bytes)
+    }
+
+    // Start the entries of a tree
+    pub fn write_tree(&mut self, name: &[u8]) -> Result<(), BlockchainError> {
+        self.write_all(&[TAG_TREE])?;
+        self.write_bytes(name)
+    }
+
+    pub fn write_entry(&mut self, key: &[u8], value: &[u8]) -> Result<(), BlockchainError> {
+        self.write_all(&[TAG_ENTRY])?;
+        self.write_bytes(key)?;
+        self.write_bytes(value)
+    }
+
+    // Write the end tag and the checksum, then flush the file
+    pub fn finish(mut self) -> Result<Hash, BlockchainError> {
+        self.write_all(&[TAG_END])?;
+        let checksum = Hash::new(*self.hasher.finalize().as_bytes());
+        self.encoder.write_all(checksum.as_bytes())?;
+        self.encoder.finish()?.flush()?;
+        Ok(checksum)
+    }
+}
+
+// Read a snapshot created by the SnapshotWriter
+pub struct SnapshotReader {
+    decoder: GzDecoder<BufReader<File>>,
+    hasher: Hasher,
+    header: SnapshotHeader
+}
+
+impl SnapshotReader {
+    pub fn open(path: &str) -> Result<Self, BlockchainError> {
+        let file = File::open(path)?;
+        let mut reader = Self {
+            decoder: GzDecoder::new(BufReader::new(file)),
+            hasher: Hasher::new(),
+            header: SnapshotHeader {
+                network: Network::Mainnet,
+                topoheight: 0,
+                height: 0,
+                tips: Tips::new()
+            }
+        };
+
+        let mut magic = [0u8; SNAPSHOT_MAGIC.len()];
+        reader.read_exact(&mut magic)?;
+        if magic != *SNAPSHOT_MAGIC {
+            return Err(BlockchainError::InvalidSnapshot("not a snapshot file".to_owned()))
+        }
+
+        let mut version = [0u8; 1];
+        reader.read_exact(&mut version)?;
+        if version[0] != SNAPSHOT_VERSION {
+            return Err(BlockchainError::InvalidSnapshot(format!("unsupported version {}", version[0])))
+        }
+
+        let bytes = reader.read_bytes()?;
+        reader.header = SnapshotHeader::from_bytes(&bytes)?;
+        Ok(reader)
+    }
+
+    pub fn get_header(&self) -> &SnapshotHeader {
+        &self.header
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), BlockchainError> {
+        self.decoder.read_exact(buf)?;
+        self.hasher.update(buf);
+        Ok(())
+    }
+
+    fn read_bytes(&mut self) -> Result<Vec<u8>, BlockchainError> {
+        let mut len = [0u8; 4];
+        self.read_exact(&mut len)?;
+        let len = u32::from_be_bytes(len) as usize;
+        if len > SNAPSHOT_MAX_RECORD_SIZE {
+            return Err(BlockchainError::InvalidSnapshot(format!("record of {} bytes is too big", len)))
+        }
+
+        let mut bytes = vec![0u8; len];
+        self.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    // Read the next record
+    // Returns None once the end is reached and the checksum is valid
+    pub fn next_record(&mut self) -> Result<Option<SnapshotRecord>, BlockchainError> {
+        let mut tag = [0u8; 1];
+        self.read_exact(&mut tag)?;
+        match tag[0] {
+            TAG_TREE => Ok(Some(SnapshotRecord::Tree(self.read_bytes()?))),
+            TAG_ENTRY => {
+                let key = self.read_bytes()?;
+                let value = self.read_bytes()?;
+                Ok(Some(SnapshotRecord::Entry(key, value)))
+            },
+            TAG_END => {
+                let expected = *self.hasher.finalize().as_bytes();
+                let mut checksum = [0u8; HASH_SIZE];
+                self.decoder.read_exact(&mut checksum)?;
+                if checksum != expected {
+                    return Err(BlockchainError::SnapshotChecksumMismatch)
+                }
+                Ok(None)
+            },
+            tag => Err(BlockchainError::InvalidSnapshot(format!("unknown record tag {}", tag)))
+        }
+    }
+}
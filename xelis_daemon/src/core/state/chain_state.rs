@@ -6,6 +6,7 @@ use xelis_common::{
         BalanceType,
         CiphertextCache,
         VersionedBalance,
+        VersionedMultiSig,
         VersionedNonce
     },
     config::XELIS_ASSET,
@@ -16,6 +17,7 @@ use xelis_common::{
         PublicKey
     },
     transaction::{
+        multisig::MultiSigPayload,
         verify::BlockchainVerificationState,
         Reference,
         Transaction
@@ -25,6 +27,7 @@ use xelis_common::{
 use crate::core::{
     blockchain,
     error::BlockchainError,
+    hard_fork,
     storage::Storage
 };
 
@@ -136,8 +139,19 @@ pub struct ChainState<'a, S: Storage> {
     // Sender accounts
     // This is used to verify ZK Proofs and store/update nonces
     accounts: HashMap<&'a PublicKey, Account<'a>>,
+    // Multisig setups of the accounts read or updated
+    multisig: HashMap<&'a PublicKey, MultiSigEntry>,
     // Current topoheight of the snapshot
-    topoheight: u64
+    topoheight: u64,
+    // Version of the block in which the TXs are verified
+    block_version: u8
+}
+
+// Multisig setup of an account loaded in the chain state
+struct MultiSigEntry {
+    version: VersionedMultiSig,
+    // Only updated setups are written to the storage
+    updated: bool
 }
 
 // Chain State that can be applied to the mutable storage
@@ -172,9 +186,9 @@ impl<'a, S: Storage> AsMut<ChainState<'a, S>> for ApplicableChainState<'a, S> {
 }
 
 impl<'a, S: Storage> ApplicableChainState<'a, S> {
-    pub fn new(storage: &'a mut S, topoheight: u64) -> Self {
+    pub fn new(storage: &'a mut S, topoheight: u64, block_version: u8) -> Self {
         Self {
-            inner: ChainState::with(StorageReference::Mutable(storage), topoheight)
+            inner: ChainState::with(StorageReference::Mutable(storage), topoheight, block_version)
         }
     }
 
@@ -189,7 +203,7 @@ impl<'a, S: Storage> ApplicableChainState<'a, S> {
     pub async fn apply_changes(mut self) -> Result<(), BlockchainError> {
         // Apply changes for sender accounts
         for (key, account) in &mut self.inner.accounts {
-            trace!("Saving {} for {} at topoheight {}", account.nonce, key.as_address(self.inner.storage.is_mainnet()), self.inner.topoheight);
+            trace!("Saving {} for {} at topoheight {}", account.nonce, key.as_address(self.inner.storage.network()), self.inner.topoheight);
             self.inner.storage.set_last_nonce_to(key, self.inner.topoheight, &account.nonce).await?;
 
             let balances = self.inner.receiver_balances.entry(&key).or_insert_with(HashMap::new);
@@ -199,12 +213,12 @@ impl<'a, S: Storage> ApplicableChainState<'a, S> {
             // Example: Alice sends 100 to Bob, Bob sends 100 to Charlie
             // But Bob built its ZK Proof with the balance before Alice's transaction
             for (asset, echange) in account.assets.drain() {
-                trace!("{} {} updated for {} at topoheight {}", echange.version, asset, key.as_address(self.inner.storage.is_mainnet()), self.inner.topoheight);
+                trace!("{} {} updated for {} at topoheight {}", echange.version, asset, key.as_address(self.inner.storage.network()), self.inner.topoheight);
                 let Echange { version, output_sum, output_balance_used, new_version, .. } = echange;
                 trace!("sender output sum: {:?}", output_sum.compress());
                 match balances.entry(asset) {
                     Entry::Occupied(mut o) => {
-                        trace!("{} already has a balance for {} at topoheight {}", key.as_address(self.inner.storage.is_mainnet()), asset, self.inner.topoheight);
+                        trace!("{} already has a balance for {} at topoheight {}", key.as_address(self.inner.storage.network()), asset, self.inner.topoheight);
                         // We got incoming funds while spending some
                         // We need to split the version in two
                         // Output balance is the balance after outputs spent without incoming funds
@@ -241,7 +255,7 @@ impl<'a, S: Storage> ApplicableChainState<'a, S> {
                         *final_balance -= output_sum;
                     },
                     Entry::Vacant(e) => {
-                        trace!("{} has no balance for {} at topoheight {}", key.as_address(self.inner.storage.is_mainnet()), asset, self.inner.topoheight);
+                        trace!("{} has no balance for {} at topoheight {}", key.as_address(self.inner.storage.network()), asset, self.inner.topoheight);
                         // We have no incoming update for this key
                         // Select the right final version
                         // For that, we must check if we used the output balance and/or if we are not on the last version 
@@ -250,7 +264,7 @@ impl<'a, S: Storage> ApplicableChainState<'a, S> {
                             // This is necessary to build the final balance
                             let mut version = self.inner.storage.get_new_versioned_balance(key, asset, self.inner.topoheight).await?;
                             // Substract the output sum
-                            trace!("{} has no balance for {} at topoheight {}, substract output sum", key.as_address(self.inner.storage.is_mainnet()), asset, self.inner.topoheight);
+                            trace!("{} has no balance for {} at topoheight {}, substract output sum", key.as_address(self.inner.storage.network()), asset, self.inner.topoheight);
                             *version.get_mut_balance().computable()? -= output_sum;
                             version
                         } else {
@@ -267,17 +281,25 @@ impl<'a, S: Storage> ApplicableChainState<'a, S> {
             }
         }
 
+        // Apply the multisig setups changed at topoheight
+        for (key, entry) in self.inner.multisig {
+            if entry.updated {
+                trace!("Saving multisig {} for {} at topoheight {}", entry.version, key.as_address(self.inner.storage.network()), self.inner.topoheight);
+                self.inner.storage.set_last_multisig_to(key, self.inner.topoheight, &entry.version).await?;
+            }
+        }
+
         // Apply all balances changes at topoheight
         // We injected the sender balances in the receiver balances previously
         for (account, balances) in self.inner.receiver_balances {
             for (asset, version) in balances {
-                trace!("Saving versioned balance {} for {} at topoheight {}", version, account.as_address(self.inner.storage.is_mainnet()), self.inner.topoheight);
+                trace!("Saving versioned balance {} for {} at topoheight {}", version, account.as_address(self.inner.storage.network()), self.inner.topoheight);
                 self.inner.storage.set_last_balance_to(account, asset, self.inner.topoheight, &version).await?;
             }
 
             // If the account has no nonce set, set it to 0
             if !self.inner.accounts.contains_key(account) && !self.inner.storage.has_nonce(account).await? {
-                debug!("{} has now a balance but without any nonce registered, set default (0) nonce", account.as_address(self.inner.storage.is_mainnet()));
+                debug!("{} has now a balance but without any nonce registered, set default (0) nonce", account.as_address(self.inner.storage.network()));
                 self.inner.storage.set_last_nonce_to(account, self.inner.topoheight, &VersionedNonce::new(0, None)).await?;
             }
 
@@ -292,17 +314,19 @@ impl<'a, S: Storage> ApplicableChainState<'a, S> {
 }
 
 impl<'a, S: Storage> ChainState<'a, S> {
-    fn with(storage: StorageReference<'a, S>, topoheight: u64) -> Self {
+    fn with(storage: StorageReference<'a, S>, topoheight: u64, block_version: u8) -> Self {
         Self {
             storage,
             receiver_balances: HashMap::new(),
             accounts: HashMap::new(),
-            topoheight
+            multisig: HashMap::new(),
+            topoheight,
+            block_version
         }
     }
 
-    pub fn new(storage: &'a S, topoheight: u64) -> Self {
-        Self::with(StorageReference::Immutable(storage), topoheight)
+    pub fn new(storage: &'a S, topoheight: u64, block_version: u8) -> Self {
+        Self::with(StorageReference::Immutable(storage), topoheight, block_version)
     }
 
     // Get the storage used by the chain state
@@ -326,7 +350,7 @@ impl<'a, S: Storage> ChainState<'a, S> {
     async fn create_sender_account(key: &PublicKey, storage: &S, topoheight: u64) -> Result<Account<'a>, BlockchainError> {
         let (topo, mut version) = storage
             .get_nonce_at_maximum_topoheight(key, topoheight).await?
-            .ok_or_else(|| BlockchainError::AccountNotFound(key.as_address(storage.is_mainnet())))?;
+            .ok_or_else(|| BlockchainError::AccountNotFound(key.as_address(storage.network())))?;
         version.set_previous_topoheight(Some(topo));
 
         Ok(Account {
@@ -351,7 +375,7 @@ impl<'a, S: Storage> ChainState<'a, S> {
     // This is used for TX outputs verification
     // This depends on the transaction and can be final balance or output balance
     async fn internal_get_sender_verification_balance<'b>(&'b mut self, key: &'a PublicKey, asset: &'a Hash, reference: &Reference) -> Result<&'b mut CiphertextCache, BlockchainError> {
-        trace!("getting sender verification balance for {} at topoheight {}, reference: {}", key.as_address(self.storage.is_mainnet()), self.topoheight, reference.topoheight);
+        trace!("getting sender verification balance for {} at topoheight {}, reference: {}", key.as_address(self.storage.network()), self.topoheight, reference.topoheight);
         match self.accounts.entry(key) {
             Entry::Occupied(o) => {
                 let account = o.into_mut();
@@ -381,7 +405,7 @@ impl<'a, S: Storage> ChainState<'a, S> {
         trace!("update sender echange: {:?}", new_ct.compress());
         let change = self.accounts.get_mut(key)
             .and_then(|a| a.assets.get_mut(asset))
-            .ok_or_else(|| BlockchainError::NoTxSender(key.as_address(self.storage.is_mainnet())))?;
+            .ok_or_else(|| BlockchainError::NoTxSender(key.as_address(self.storage.network())))?;
 
         // Increase the total output
         change.add_output_to_sum(new_ct);
@@ -405,7 +429,7 @@ impl<'a, S: Storage> ChainState<'a, S> {
     // Only sender accounts should be used here
     // For each TX, we must update the nonce by one
     async fn internal_update_account_nonce(&mut self, account: &'a PublicKey, new_nonce: u64) -> Result<(), BlockchainError> {
-        trace!("Updating nonce for {} to {} at topoheight {}", account.as_address(self.storage.is_mainnet()), new_nonce, self.topoheight);
+        trace!("Updating nonce for {} to {} at topoheight {}", account.as_address(self.storage.network()), new_nonce, self.topoheight);
         match self.accounts.entry(account) {
             Entry::Occupied(mut o) => {
                 let account = o.get_mut();
@@ -423,9 +447,27 @@ impl<'a, S: Storage> ChainState<'a, S> {
         Ok(())
     }
 
+    // Get the multisig setup of an account, loaded from the storage if not in cache
+    async fn internal_get_multisig_entry<'b>(&'b mut self, key: &'a PublicKey) -> Result<&'b mut MultiSigEntry, BlockchainError> {
+        match self.multisig.entry(key) {
+            Entry::Occupied(o) => Ok(o.into_mut()),
+            Entry::Vacant(e) => {
+                let version = match self.storage.get_multisig_at_maximum_topoheight(key, self.topoheight).await? {
+                    Some((topo, version)) => VersionedMultiSig::new(version.take_state(), Some(topo)),
+                    None => VersionedMultiSig::new(None, None)
+                };
+
+                Ok(e.insert(MultiSigEntry {
+                    version,
+                    updated: false
+                }))
+            }
+        }
+    }
+
     // Reward a miner for the block mined
     pub async fn reward_miner(&mut self, miner: &'a PublicKey, reward: u64) -> Result<(), BlockchainError> {
-        debug!("Rewarding miner {} with {} XEL at topoheight {}", miner.as_address(self.storage.is_mainnet()), reward, self.topoheight);
+        debug!("Rewarding miner {} with {} XEL at topoheight {}", miner.as_address(self.storage.network()), reward, self.topoheight);
         let miner_balance = self.internal_get_receiver_balance(miner, &XELIS_ASSET).await?;
         *miner_balance += reward;
 
@@ -442,8 +484,8 @@ impl<'a, S: Storage> BlockchainVerificationState<'a, BlockchainError> for ChainS
         tx: &Transaction,
     ) -> Result<(), BlockchainError> {
         // Check the version
-        if tx.get_version() != 0 {
-            debug!("Invalid version for tx {}: {}", tx.hash(), tx.get_version());
+        if !hard_fork::is_tx_version_allowed_in_block_version(&self.get_storage().network(), self.block_version, tx.get_version()) {
+            debug!("Invalid version for tx {}: {} in block version {}", tx.hash(), tx.get_version(), self.block_version);
             return Err(BlockchainError::InvalidTxVersion);
         }
 
@@ -516,4 +558,25 @@ impl<'a, S: Storage> BlockchainVerificationState<'a, BlockchainError> for ChainS
     ) -> Result<(), BlockchainError> {
         self.internal_update_account_nonce(account, new_nonce).await
     }
+
+    /// Get the multisig setup of an account
+    async fn get_multisig_state<'b>(
+        &'b mut self,
+        account: &'a PublicKey
+    ) -> Result<Option<&'b MultiSigPayload>, BlockchainError> {
+        let entry = self.internal_get_multisig_entry(account).await?;
+        Ok(entry.version.get_state())
+    }
+
+    /// Apply a new multisig setup to an account
+    async fn set_multisig_state(
+        &mut self,
+        account: &'a PublicKey,
+        setup: Option<&MultiSigPayload>
+    ) -> Result<(), BlockchainError> {
+        let entry = self.internal_get_multisig_entry(account).await?;
+        entry.version.set_state(setup.cloned());
+        entry.updated = true;
+        Ok(())
+    }
 } 
\ No newline at end of file
@@ -8,15 +8,18 @@ use xelis_common::{
         PublicKey
     },
     transaction::{
+        multisig::MultiSigPayload,
         verify::BlockchainVerificationState,
         Reference,
-        Transaction
+        Transaction,
+        TransactionType
     },
     utils::format_xelis
 };
 use crate::core::{
     blockchain,
     error::BlockchainError,
+    hard_fork,
     mempool::Mempool,
     storage::Storage
 };
@@ -41,18 +44,24 @@ pub struct MempoolState<'a, S: Storage> {
     // Sender accounts
     // This is used to verify ZK Proofs and store/update nonces
     accounts: HashMap<&'a PublicKey, Account<'a>>,
+    // Multisig setups of the accounts
+    multisig: HashMap<&'a PublicKey, Option<MultiSigPayload>>,
     // The current topoheight of the chain
     topoheight: u64,
+    // Version of the next block, used to verify the TX version
+    block_version: u8,
 }
 
 impl<'a, S: Storage> MempoolState<'a, S> {
-    pub fn new(mempool: &'a Mempool, storage: &'a S, topoheight: u64) -> Self {
+    pub fn new(mempool: &'a Mempool, storage: &'a S, topoheight: u64, block_version: u8) -> Self {
         Self {
             mempool,
             storage,
             receiver_balances: HashMap::new(),
             accounts: HashMap::new(),
+            multisig: HashMap::new(),
             topoheight,
+            block_version,
         }
     }
 
@@ -172,6 +181,18 @@ impl<'a, S: Storage> MempoolState<'a, S> {
         }
         Ok(())
     }
+
+    // Retrieve the multisig setup of an account from our cache or from the storage
+    async fn internal_get_multisig_state<'b>(&'b mut self, key: &'a PublicKey) -> Result<&'b mut Option<MultiSigPayload>, BlockchainError> {
+        match self.multisig.entry(key) {
+            Entry::Occupied(o) => Ok(o.into_mut()),
+            Entry::Vacant(e) => {
+                let state = self.storage.get_multisig_at_maximum_topoheight(key, self.topoheight).await?
+                    .and_then(|(_, version)| version.take_state());
+                Ok(e.insert(state))
+            }
+        }
+    }
 }
 
 #[async_trait]
@@ -182,11 +203,23 @@ impl<'a, S: Storage> BlockchainVerificationState<'a, BlockchainError> for Mempoo
         tx: &Transaction,
     ) -> Result<(), BlockchainError> {
         // Check the version
-        if tx.get_version() != 0 {
-            debug!("Invalid version: {}", tx.get_version());
+        if !hard_fork::is_tx_version_allowed_in_block_version(&self.storage.network(), self.block_version, tx.get_version()) {
+            debug!("Invalid version: {} in block version {}", tx.get_version(), self.block_version);
             return Err(BlockchainError::InvalidTxVersion);
         }
 
+        // A pending multisig setup changes the signatures required by the next transactions
+        // Reject them until the setup is included in a block
+        if let Some(cache) = self.mempool.get_cache_for(tx.get_source()) {
+            for hash in cache.get_txs() {
+                let pending = self.mempool.view_tx(hash)?;
+                if matches!(pending.get_data(), TransactionType::MultiSig(_)) && pending.get_nonce() < tx.get_nonce() {
+                    debug!("Multisig setup {} is pending for the source", hash);
+                    return Err(BlockchainError::MultiSigSetupPending(hash.as_ref().clone()));
+                }
+            }
+        }
+
         let required_fees = blockchain::estimate_required_tx_fees(self.storage, self.topoheight, tx).await?;
         if required_fees > tx.get_fee() {
             debug!("Invalid fees: {} required, {} provided", format_xelis(required_fees), format_xelis(tx.get_fee()));
@@ -255,4 +288,24 @@ impl<'a, S: Storage> BlockchainVerificationState<'a, BlockchainError> for Mempoo
     ) -> Result<(), BlockchainError> {
         self.internal_update_account_nonce(account, new_nonce).await
     }
+
+    /// Get the multisig setup of an account
+    async fn get_multisig_state<'b>(
+        &'b mut self,
+        account: &'a PublicKey
+    ) -> Result<Option<&'b MultiSigPayload>, BlockchainError> {
+        let state = self.internal_get_multisig_state(account).await?;
+        Ok(state.as_ref())
+    }
+
+    /// Apply a new multisig setup to an account
+    async fn set_multisig_state(
+        &mut self,
+        account: &'a PublicKey,
+        setup: Option<&MultiSigPayload>
+    ) -> Result<(), BlockchainError> {
+        let state = self.internal_get_multisig_state(account).await?;
+        *state = setup.cloned();
+        Ok(())
+    }
 }
\ No newline at end of file
@@ -15,7 +15,7 @@ use super::{error::BlockchainError, storage::Storage};
 // - is it a new version created
 // - Versioned Balance to use for verification
 pub (super) async fn search_versioned_balance_for_reference<S: Storage>(storage: &S, key: &PublicKey, asset: &Hash, current_topoheight: u64, reference: &Reference) -> Result<(bool, bool, VersionedBalance), BlockchainError> {
-    trace!("search versioned balance for {} at topoheight {}, reference: {}", key.as_address(storage.is_mainnet()), current_topoheight, reference.topoheight);
+    trace!("search versioned balance for {} at topoheight {}, reference: {}", key.as_address(storage.network()), current_topoheight, reference.topoheight);
     // Scenario A
     // TX A has reference topo 1000
     // We are at block topo 1001
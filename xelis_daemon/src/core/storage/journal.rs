@@ -0,0 +1,61 @@
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use crate::core::error::BlockchainError;
+
+// Key changed while a commit point is active, with the tree it belongs to
+// The previous value is None if the key didn't exist
+pub(super) type JournalEntry<T> = (T, Vec<u8>, Option<Vec<u8>>);
+
+// Records the previous values of the keys changed since the commit point
+// so all the changes made by a block can be reverted if it can't be fully added
+// It is shared by all the trees of a storage
+pub(super) struct Journal<T> {
+    entries: Arc<Mutex<Option<Vec<JournalEntry<T>>>>>
+}
+
+impl<T> Clone for Journal<T> {
+    fn clone(&self) -> Self {
+        Self {
+            entries: Arc::clone(&self.entries)
+        }
+    }
+}
+
+impl<T: Clone> Journal<T> {
+    pub(super) fn new() -> Self {
+        Self {
+            entries: Arc::new(Mutex::new(None))
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Option<Vec<JournalEntry<T>>>> {
+        // Entries are only pushed, they are still valid if a thread panicked
+        self.entries.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    // Start recording the changes
+    pub(super) fn start(&self) -> Result<(), BlockchainError> {
+        let mut entries = self.lock();
+        if entries.is_some() {
+            return Err(BlockchainError::CommitPointAlreadyStarted)
+        }
+
+        *entries = Some(Vec::new());
+        Ok(())
+    }
+
+    pub(super) fn is_recording(&self) -> bool {
+        self.lock().is_some()
+    }
+
+    // Record the previous value of a key, only if the changes are recorded
+    pub(super) fn record(&self, tree: &T, key: &[u8], previous: Option<Vec<u8>>) {
+        if let Some(entries) = self.lock().as_mut() {
+            entries.push((tree.clone(), key.to_vec(), previous));
+        }
+    }
+
+    // Stop recording and returns the changes recorded since the start, oldest first
+    pub(super) fn stop(&self) -> Option<Vec<JournalEntry<T>>> {
+        self.lock().take()
+    }
+}
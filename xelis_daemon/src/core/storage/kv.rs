@@ -0,0 +1,1480 @@
+use anyhow::Context;
+use async_trait::async_trait;
+use indexmap::IndexSet;
+use crate::{
+    config::PRUNE_SAFETY_LIMIT,
+    core::error::{BlockchainError, DiskContext}
+};
+use xelis_common::{
+    account::{VersionedBalance, VersionedNonce},
+    block::{Block, BlockHeader},
+    crypto::{Hash, PublicKey},
+    difficulty::{CumulativeDifficulty, Difficulty},
+    immutable::Immutable,
+    network::Network,
+    serializer::{Reader, Serializer},
+    transaction::Transaction
+};
+use std::{
+    collections::HashSet,
+    hash::Hash as StdHash,
+    sync::{Arc, atomic::{AtomicU64, Ordering}},
+    num::NonZeroUsize
+};
+use tokio::sync::Mutex;
+use lru::LruCache;
+use log::{debug, trace, warn, info};
+
+use super::{
+    journal::Journal,
+    BalanceProvider,
+    BlocksAtHeightProvider,
+    DagOrderProvider,
+    DifficultyProvider,
+    NonceProvider,
+    MultiSigProvider,
+    PrunedTopoheightProvider,
+    ClientProtocolProvider,
+    TransactionProvider,
+    BlockProvider,
+    Storage,
+    Tips
+};
+
+// Constant keys used for extra Tree
+pub(super) const TIPS: &[u8; 4] = b"TIPS";
+pub(super) const TOP_TOPO_HEIGHT: &[u8; 4] = b"TOPO";
+pub(super) const TOP_HEIGHT: &[u8; 4] = b"TOPH";
+pub(super) const NETWORK: &[u8] = b"NET";
+pub(super) const PRUNED_TOPOHEIGHT: &[u8; 4] = b"PRUN";
+pub(super) const PRUNED_BODIES_TOPOHEIGHT: &[u8; 4] = b"PRBD";
+// Counters (prevent to perform a O(n))
+pub(super) const ACCOUNTS_COUNT: &[u8; 4] = b"CACC";
+pub(super) const TXS_COUNT: &[u8; 4] = b"CTXS";
+pub(super) const ASSETS_COUNT: &[u8; 4] = b"CAST";
+pub(super) const BLOCKS_COUNT: &[u8; 4] = b"CBLK";
+// Topoheight from which the burned supply tree contains all the burns, set once it is indexed by asset
+pub(super) const BURNED_SUPPLY_INDEXED_FROM: &[u8; 4] = b"BRNF";
+
+// Trees used by the storage
+// Both backends use the same names so a snapshot can be imported in any of them
+pub(super) const TREES: [&str; 24] = [
+    "transactions",
+    "txs_executed",
+    "blocks",
+    "blocks_at_height",
+    "extra",
+    "topo_at_hash",
+    "hash_at_topo",
+    "cumulative_difficulty",
+    "difficulty_covariance",
+    "assets",
+    "nonces",
+    "rewards",
+    "supply",
+    "difficulty",
+    "tx_blocks",
+    "versioned_nonces",
+    "balances",
+    "versioned_balances",
+    "merkle_hashes",
+    "registrations",
+    "registrations_prefixed",
+    "multisig",
+    "versioned_multisig",
+    "burned_supply"
+];
+
+// key/value pair read from a tree
+pub type Entry = (Box<[u8]>, Box<[u8]>);
+
+// Entries are read in the order of their keys
+// Iterators may not be Send, collect them before any await
+pub type EntryIter<'a> = Box<dyn Iterator<Item = Result<Entry, BlockchainError>> + 'a>;
+
+// Key/value tree of a backend, the only operations the storage needs from it
+pub trait KVTree: Send + Sync {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, BlockchainError>;
+
+    fn contains_key(&self, key: &[u8]) -> Result<bool, BlockchainError>;
+
+    fn insert(&self, key: &[u8], value: &[u8]) -> Result<(), BlockchainError>;
+
+    fn remove(&self, key: &[u8]) -> Result<(), BlockchainError>;
+
+    fn iter(&self) -> EntryIter<'_>;
+
+    // Iterate over the entries with a key greater or equal to the one given
+    fn iter_from(&self, key: &[u8]) -> EntryIter<'_>;
+
+    // Iterate in reverse order over the entries with a key lower or equal to the one given
+    fn iter_rev_from(&self, key: &[u8]) -> EntryIter<'_>;
+}
+
+// Database engine on which the storage is built
+pub trait KVBackend: Sized + Send + Sync + 'static {
+    type Tree: KVTree;
+
+    // Name of the engine displayed in the logs
+    const NAME: &'static str;
+
+    // Prefix of the database directory, followed by the network
+    const DIR_PREFIX: &'static str;
+
+    // Open the database in this directory, all the TREES must be available after it
+    fn open(path: &str) -> Result<Self, BlockchainError>;
+
+    fn open_tree(&self, name: &'static str) -> Result<Self::Tree, BlockchainError>;
+
+    // Write all the trees on disk
+    fn flush(&self) -> Result<(), BlockchainError>;
+
+    // Current size on disk in bytes
+    fn size_on_disk(&self) -> Result<u64, BlockchainError>;
+}
+
+// Tree of a backend recording the previous values of the keys it changes while a commit point is active
+// Reads are done directly on the backend tree
+pub(super) struct Tree<T> {
+    tree: T,
+    name: &'static str,
+    // Changes recorded since the commit point, shared by all the trees
+    journal: Journal<&'static str>
+}
+
+impl<T: KVTree> Tree<T> {
+    fn new(tree: T, name: &'static str, journal: &Journal<&'static str>) -> Self {
+        Self {
+            tree,
+            name,
+            journal: journal.clone()
+        }
+    }
+
+    pub(super) fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub(super) fn get<K: AsRef<[u8]>>(&self, key: K) -> Result<Option<Vec<u8>>, BlockchainError> {
+        self.tree.get(key.as_ref())
+    }
+
+    pub(super) fn contains_key<K: AsRef<[u8]>>(&self, key: K) -> Result<bool, BlockchainError> {
+        self.tree.contains_key(key.as_ref())
+    }
+
+    // Keep the previous value of the key if a commit point is active
+    fn record(&self, key: &[u8]) -> Result<(), BlockchainError> {
+        if self.journal.is_recording() {
+            let previous = self.get(key)?;
+            self.journal.record(&self.name, key, previous);
+        }
+        Ok(())
+    }
+
+    pub(super) fn insert<K: AsRef<[u8]>, V: AsRef<[u8]>>(&self, key: K, value: V) -> Result<(), BlockchainError> {
+        self.record(key.as_ref())?;
+        self.tree.insert(key.as_ref(), value.as_ref())
+    }
+
+    // Same as insert but returns the previous value
+    // Only use it when needed as it reads the key before writing it
+    pub(super) fn replace<K: AsRef<[u8]>, V: AsRef<[u8]>>(&self, key: K, value: V) -> Result<Option<Vec<u8>>, BlockchainError> {
+        let previous = self.get(&key)?;
+        self.insert(key, value)?;
+        Ok(previous)
+    }
+
+    pub(super) fn remove<K: AsRef<[u8]>>(&self, key: K) -> Result<(), BlockchainError> {
+        self.record(key.as_ref())?;
+        self.tree.remove(key.as_ref())
+    }
+
+    // Same as remove but returns the deleted value
+    pub(super) fn take<K: AsRef<[u8]>>(&self, key: K) -> Result<Option<Vec<u8>>, BlockchainError> {
+        let previous = self.get(&key)?;
+        if previous.is_some() {
+            self.remove(key)?;
+        }
+        Ok(previous)
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        !matches!(self.tree.iter().next(), Some(Ok(_)))
+    }
+
+    pub(super) fn iter(&self) -> EntryIter<'_> {
+        self.tree.iter()
+    }
+
+    pub(super) fn keys(&self) -> impl Iterator<Item = Result<Box<[u8]>, BlockchainError>> + '_ {
+        self.iter().map(|res| res.map(|(key, _)| key))
+    }
+
+    // Iterate over the entries whose key starts with the prefix
+    pub(super) fn scan_prefix<'a>(&'a self, prefix: &'a [u8]) -> impl Iterator<Item = Result<Entry, BlockchainError>> + 'a {
+        self.tree.iter_from(prefix)
+            .take_while(move |res| match res {
+                Ok((key, _)) => key.starts_with(prefix),
+                Err(_) => true
+            })
+    }
+
+    pub(super) fn scan_prefix_keys<'a>(&'a self, prefix: &'a [u8]) -> impl Iterator<Item = Result<Box<[u8]>, BlockchainError>> + 'a {
+        self.scan_prefix(prefix).map(|res| res.map(|(key, _)| key))
+    }
+
+    // Iterate over the entries with a key greater or equal to the one given
+    pub(super) fn iter_from(&self, key: &[u8]) -> EntryIter<'_> {
+        self.tree.iter_from(key)
+    }
+
+    // Iterate in reverse order over the entries with a key lower or equal to the one given
+    pub(super) fn iter_rev_from(&self, key: &[u8]) -> EntryIter<'_> {
+        self.tree.iter_rev_from(key)
+    }
+}
+
+// Storage of the chain, implemented once for all the backends
+pub struct KVStorage<B: KVBackend> {
+    // Network used by the storage
+    network: Network,
+    // Directory of the database
+    path: String,
+    // Size of the caches, kept to reopen the storage
+    cache_size: Option<usize>,
+    // All trees used to store data
+    // all txs stored on disk
+    pub(super) transactions: Tree<B::Tree>,
+    // all txs executed in block
+    pub(super) txs_executed: Tree<B::Tree>,
+    // all blocks on disk
+    pub(super) blocks: Tree<B::Tree>,
+    // all blocks height at specific height
+    pub(super) blocks_at_height: Tree<B::Tree>,
+    // all extra data saved on disk
+    pub(super) extra: Tree<B::Tree>,
+    // topo at hash on disk
+    pub(super) topo_by_hash: Tree<B::Tree>,
+    // hash at topo height on disk
+    pub(super) hash_at_topo: Tree<B::Tree>,
+    // cumulative difficulty for each block hash on disk
+    pub(super) cumulative_difficulty: Tree<B::Tree>,
+    // Difficulty estimated covariance (P)
+    pub(super) difficulty_covariance: Tree<B::Tree>,
+    // keep tracks of all available assets on network
+    pub(super) assets: Tree<B::Tree>,
+    // account nonces to prevent TX replay attack
+    pub(super) nonces: Tree<B::Tree>,
+    // block reward for each block topoheight
+    pub(super) rewards: Tree<B::Tree>,
+    // supply for each block topoheight
+    pub(super) supply: Tree<B::Tree>,
+    // difficulty for each block hash
+    pub(super) difficulty: Tree<B::Tree>,
+    // tree to store all blocks hashes where a tx was included in
+    pub(super) tx_blocks: Tree<B::Tree>,
+    // Tree that store all versioned nonces using hashed keys
+    pub(super) versioned_nonces: Tree<B::Tree>,
+    // Tree that store all balances with prefixed keys
+    pub(super) balances: Tree<B::Tree>,
+    // Tree that store all versioned balances using hashed keys
+    pub(super) versioned_balances: Tree<B::Tree>,
+    // Tree that store all merkle hashes for each topoheight
+    pub(super) merkle_hashes: Tree<B::Tree>,
+    // Account registrations topoheight
+    pub(super) registrations: Tree<B::Tree>,
+    // Account registrations prefixed by their topoheight for easier deletion
+    pub(super) registrations_prefixed: Tree<B::Tree>,
+    // Last topoheight of the multisig setup for each account
+    pub(super) multisig: Tree<B::Tree>,
+    // Tree that store all versioned multisig setups
+    // They are not pruned as they are rarely changed
+    pub(super) versioned_multisig: Tree<B::Tree>,
+    // Total burned of each asset at the topoheights where it changed
+    pub(super) burned_supply: Tree<B::Tree>,
+    // opened database
+    db: B,
+    // Changes recorded since the commit point
+    journal: Journal<&'static str>,
+
+    // all available caches
+    // Transaction cache
+    pub(super) transactions_cache: Option<Mutex<LruCache<Hash, Arc<Transaction>>>>,
+    // Block header cache
+    pub(super) blocks_cache: Option<Mutex<LruCache<Hash, Arc<BlockHeader>>>>,
+    // Blocks Tips cache
+    pub(super) past_blocks_cache: Option<Mutex<LruCache<Hash, Arc<IndexSet<Hash>>>>>,
+    // Topoheight by hash cache
+    pub(super) topo_by_hash_cache: Option<Mutex<LruCache<Hash, u64>>>,
+    // Hash by topoheight cache
+    pub(super) hash_at_topo_cache: Option<Mutex<LruCache<u64, Hash>>>,
+    // Cumulative difficulty cache
+    pub(super) cumulative_difficulty_cache: Option<Mutex<LruCache<Hash, CumulativeDifficulty>>>,
+    // Assets cache
+    pub(super) assets_cache: Option<Mutex<LruCache<Hash, ()>>>,
+    // Tips cache: current chain Tips
+    tips_cache: Tips,
+    // Pruned topoheight cache
+    pub(super) pruned_topoheight: Option<u64>,
+    // Topoheight under which the transactions of the blocks are deleted, their headers are kept
+    pub(super) pruned_bodies_topoheight: Option<u64>,
+
+    // Atomic counters
+    // Count of assets
+    pub(super) assets_count: AtomicU64,
+    // Count of accounts
+    pub(super) accounts_count: AtomicU64,
+    // Count of transactions
+    pub(super) transactions_count: AtomicU64,
+    // Count of blocks
+    pub(super) blocks_count: AtomicU64
+}
+
+macro_rules! init_cache {
+    ($cache_size: expr) => {{
+        if let Some(size) = &$cache_size {
+            Some(Mutex::new(LruCache::new(NonZeroUsize::new(*size).unwrap())))
+        } else {
+            None
+        }
+    }};
+}
+
+impl<B: KVBackend> KVStorage<B> {
+    pub fn new(dir_path: String, cache_size: Option<usize>, network: Network) -> Result<Self, BlockchainError> {
+        Self::open(format!("{}{}{}", dir_path, B::DIR_PREFIX, network.to_string().to_lowercase()), cache_size, network)
+    }
+
+    // Open the storage in this directory
+    pub(super) fn open(path: String, cache_size: Option<usize>, network: Network) -> Result<Self, BlockchainError> {
+        let db = B::open(&path)?;
+        let journal = Journal::new();
+        let open_tree = |name: &'static str| -> Result<Tree<B::Tree>, BlockchainError> {
+            Ok(Tree::new(db.open_tree(name)?, name, &journal))
+        };
+
+        let mut storage = Self {
+            network,
+            path,
+            cache_size,
+            transactions: open_tree("transactions")?,
+            txs_executed: open_tree("txs_executed")?,
+            blocks: open_tree("blocks")?,
+            blocks_at_height: open_tree("blocks_at_height")?,
+            extra: open_tree("extra")?,
+            topo_by_hash: open_tree("topo_at_hash")?,
+            hash_at_topo: open_tree("hash_at_topo")?,
+            cumulative_difficulty: open_tree("cumulative_difficulty")?,
+            difficulty_covariance: open_tree("difficulty_covariance")?,
+            assets: open_tree("assets")?,
+            nonces: open_tree("nonces")?,
+            rewards: open_tree("rewards")?,
+            supply: open_tree("supply")?,
+            difficulty: open_tree("difficulty")?,
+            tx_blocks: open_tree("tx_blocks")?,
+            versioned_nonces: open_tree("versioned_nonces")?,
+            balances: open_tree("balances")?,
+            versioned_balances: open_tree("versioned_balances")?,
+            merkle_hashes: open_tree("merkle_hashes")?,
+            registrations: open_tree("registrations")?,
+            registrations_prefixed: open_tree("registrations_prefixed")?,
+            multisig: open_tree("multisig")?,
+            versioned_multisig: open_tree("versioned_multisig")?,
+            burned_supply: open_tree("burned_supply")?,
+            db,
+            journal,
+            transactions_cache: init_cache!(cache_size),
+            blocks_cache: init_cache!(cache_size),
+            past_blocks_cache: init_cache!(cache_size),
+            topo_by_hash_cache: init_cache!(cache_size),
+            hash_at_topo_cache: init_cache!(cache_size),
+            cumulative_difficulty_cache: init_cache!(cache_size),
+            assets_cache: init_cache!(cache_size),
+            tips_cache: HashSet::new(),
+            pruned_topoheight: None,
+            pruned_bodies_topoheight: None,
+            assets_count: AtomicU64::new(0),
+            accounts_count: AtomicU64::new(0),
+            transactions_count: AtomicU64::new(0),
+            blocks_count: AtomicU64::new(0)
+        };
+
+        // Verify that we are opening a DB on same network
+        // This prevent any corruption made by user
+        if storage.has_network()? {
+            let storage_network = storage.get_network()?;
+            if storage_network != network {
+                return Err(BlockchainError::InvalidNetwork);
+            }
+        } else {
+            storage.set_network(&network)?;
+        }
+
+        storage.load_metadata_from_disk();
+
+        Ok(storage)
+    }
+
+    pub fn network(&self) -> Network {
+        self.network
+    }
+
+    // Directory of the database
+    pub(super) fn get_path(&self) -> &str {
+        &self.path
+    }
+
+    // Size of the caches used by the storage
+    pub(super) fn get_cache_size(&self) -> Option<usize> {
+        self.cache_size
+    }
+
+    // All the trees of the storage, in the same order as TREES
+    pub(super) fn trees(&self) -> [&Tree<B::Tree>; 24] {
+        [
+            &self.transactions,
+            &self.txs_executed,
+            &self.blocks,
+            &self.blocks_at_height,
+            &self.extra,
+            &self.topo_by_hash,
+            &self.hash_at_topo,
+            &self.cumulative_difficulty,
+            &self.difficulty_covariance,
+            &self.assets,
+            &self.nonces,
+            &self.rewards,
+            &self.supply,
+            &self.difficulty,
+            &self.tx_blocks,
+            &self.versioned_nonces,
+            &self.balances,
+            &self.versioned_balances,
+            &self.merkle_hashes,
+            &self.registrations,
+            &self.registrations_prefixed,
+            &self.multisig,
+            &self.versioned_multisig,
+            &self.burned_supply
+        ]
+    }
+
+    // Load the tips, pruned topoheights and counters from disk
+    // Missing values are reset to their defaults
+    pub(super) fn load_metadata_from_disk(&mut self) {
+        self.tips_cache = match self.load_from_disk::<Tips>(&self.extra, TIPS) {
+            Ok(tips) => {
+                debug!("Found tips: {}", tips.len());
+                tips
+            },
+            Err(_) => HashSet::new()
+        };
+
+        self.pruned_topoheight = self.load_from_disk::<u64>(&self.extra, PRUNED_TOPOHEIGHT).ok();
+        if let Some(pruned_topoheight) = self.pruned_topoheight {
+            debug!("Found pruned topoheight: {}", pruned_topoheight);
+        }
+
+        self.pruned_bodies_topoheight = self.load_from_disk::<u64>(&self.extra, PRUNED_BODIES_TOPOHEIGHT).ok();
+        if let Some(pruned_bodies_topoheight) = self.pruned_bodies_topoheight {
+            debug!("Found pruned bodies topoheight: {}", pruned_bodies_topoheight);
+        }
+
+        let assets_count = self.load_from_disk::<u64>(&self.extra, ASSETS_COUNT).unwrap_or(0);
+        debug!("Found assets count: {}", assets_count);
+        self.assets_count.store(assets_count, Ordering::SeqCst);
+
+        let txs_count = self.load_from_disk::<u64>(&self.extra, TXS_COUNT).unwrap_or(0);
+        debug!("Found txs count: {}", txs_count);
+        self.transactions_count.store(txs_count, Ordering::SeqCst);
+
+        let blocks_count = self.load_from_disk::<u64>(&self.extra, BLOCKS_COUNT).unwrap_or(0);
+        debug!("Found blocks count: {}", blocks_count);
+        self.blocks_count.store(blocks_count, Ordering::SeqCst);
+
+        let accounts_count = self.load_from_disk::<u64>(&self.extra, ACCOUNTS_COUNT).unwrap_or(0);
+        debug!("Found accounts count: {}", accounts_count);
+        self.accounts_count.store(accounts_count, Ordering::SeqCst);
+    }
+
+    pub(super) fn load_optional_from_disk<T: Serializer>(&self, tree: &Tree<B::Tree>, key: &[u8]) -> Result<Option<T>, BlockchainError> {
+        match tree.get(key)? {
+            Some(bytes) => {
+                let mut reader = Reader::new(&bytes);
+                let value = T::read(&mut reader)?;
+                Ok(Some(value))
+            },
+            None => Ok(None)
+        }
+    }
+
+    pub(super) fn load_from_disk<T: Serializer>(&self, tree: &Tree<B::Tree>, key: &[u8]) -> Result<T, BlockchainError> {
+        match tree.get(key)? {
+            Some(bytes) => {
+                let mut reader = Reader::new(&bytes);
+                let value = T::read(&mut reader)?;
+                Ok(value)
+            },
+            None => Err(BlockchainError::NotFoundOnDisk(DiskContext::LoadData))
+        }
+    }
+
+    pub(super) async fn get_cacheable_arc_data<K: Eq + StdHash + Serializer + Clone, V: Serializer>(&self, tree: &Tree<B::Tree>, cache: &Option<Mutex<LruCache<K, Arc<V>>>>, key: &K) -> Result<Arc<V>, BlockchainError> {
+        let value = if let Some(cache) = cache {
+            let mut cache = cache.lock().await;
+            if let Some(value) = cache.get(key) {
+                return Ok(Arc::clone(&value));
+            }
+
+            let value = Arc::new(self.load_from_disk(tree, &key.to_bytes())?);
+            cache.put(key.clone(), Arc::clone(&value));
+            value
+        } else {
+            Arc::new(self.load_from_disk(tree, &key.to_bytes())?)
+        };
+
+        Ok(value)
+    }
+
+    pub(super) async fn get_cacheable_data<K: Eq + StdHash + Serializer + Clone, V: Serializer + Clone>(&self, tree: &Tree<B::Tree>, cache: &Option<Mutex<LruCache<K, V>>>, key: &K) -> Result<V, BlockchainError> {
+        let value = if let Some(cache) = cache {
+            let mut cache = cache.lock().await;
+            if let Some(value) = cache.get(key) {
+                return Ok(value.clone());
+            }
+
+            let value: V = self.load_from_disk(tree, &key.to_bytes())?;
+            cache.put(key.clone(), value.clone());
+            value
+        } else {
+            self.load_from_disk(tree, &key.to_bytes())?
+        };
+
+        Ok(value)
+    }
+
+    pub(super) async fn delete_cacheable_data<K: Eq + StdHash + Serializer + Clone, V: Serializer>(&self, tree: &Tree<B::Tree>, cache: &Option<Mutex<LruCache<K, V>>>, key: &K) -> Result<V, BlockchainError> {
+        let bytes = match tree.take(key.to_bytes())? {
+            Some(data) => data,
+            None => return Err(BlockchainError::NotFoundOnDisk(DiskContext::DeleteData))
+        };
+
+        if let Some(cache) = cache {
+            let mut cache = cache.lock().await;
+            if let Some(value) = cache.pop(key) {
+                return Ok(value);
+            }
+        }
+
+        let mut reader = Reader::new(&bytes);
+        let value = V::read(&mut reader)?;
+        Ok(value)
+    }
+
+    pub(super) async fn delete_data<K: Eq + StdHash + Serializer + Clone, V: Serializer>(&self, tree: &Tree<B::Tree>, cache: &Option<Mutex<LruCache<K, Arc<V>>>>, key: &K) -> Result<Arc<V>, BlockchainError> {
+        let bytes = match tree.take(key.to_bytes())? {
+            Some(data) => data,
+            None => return Err(BlockchainError::NotFoundOnDisk(DiskContext::DeleteData))
+        };
+
+        if let Some(cache) = cache {
+            let mut cache = cache.lock().await;
+            if let Some(value) = cache.pop(key) {
+                return Ok(value);
+            }
+        }
+
+        let mut reader = Reader::new(&bytes);
+        let value = V::read(&mut reader)?;
+        Ok(Arc::new(value))
+    }
+
+    pub(super) async fn contains_data<K: Eq + StdHash + Serializer + Clone, V>(&self, tree: &Tree<B::Tree>, cache: &Option<Mutex<LruCache<K, V>>>, key: &K) -> Result<bool, BlockchainError> {
+        if let Some(cache) = cache {
+            let cache = cache.lock().await;
+            return Ok(cache.contains(key) || tree.contains_key(&key.to_bytes())?)
+        }
+
+        tree.contains_key(&key.to_bytes())
+    }
+
+    // Write all the trees on disk
+    pub(super) fn flush(&self) -> Result<(), BlockchainError> {
+        self.db.flush()
+    }
+
+    // Update the assets count and store it on disk
+    pub(super) fn store_assets_count(&self, count: u64) -> Result<(), BlockchainError> {
+        self.assets_count.store(count, Ordering::SeqCst);
+        self.extra.insert(ASSETS_COUNT, &count.to_be_bytes())?;
+        Ok(())
+    }
+
+    // Versioned keys are prefixed by their topoheight
+    fn delete_versioned_tree_above_topoheight(&self, tree: &Tree<B::Tree>, topoheight: u64) -> Result<(), BlockchainError> {
+        trace!("delete versioned {} above topoheight {}", tree.name(), topoheight);
+        for el in tree.keys() {
+            let key = el?;
+            let topo = u64::from_bytes(&key[0..8])?;
+            if topo > topoheight {
+                tree.remove(&key)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn delete_versioned_tree_below_topoheight(&self, tree: &Tree<B::Tree>, topoheight: u64) -> Result<(), BlockchainError> {
+        trace!("delete versioned {} below topoheight {}", tree.name(), topoheight);
+        for el in tree.keys() {
+            let key = el?;
+            let topo = u64::from_bytes(&key[0..8])?;
+            if topo < topoheight {
+                tree.remove(&key)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<B: KVBackend> Storage for KVStorage<B> {
+    fn network(&self) -> Network {
+        self.network
+    }
+
+    async fn clear_caches(&mut self) -> Result<(), BlockchainError> {
+        if let Some(cache) = self.transactions_cache.as_ref() {
+            let mut cache = cache.lock().await;
+            cache.clear();
+        }
+
+        if let Some(cache) = self.blocks_cache.as_ref() {
+            let mut cache = cache.lock().await;
+            cache.clear();
+        }
+
+        if let Some(cache) = self.past_blocks_cache.as_ref() {
+            let mut cache = cache.lock().await;
+            cache.clear();
+        }
+
+        if let Some(cache) = self.topo_by_hash_cache.as_ref() {
+            let mut cache = cache.lock().await;
+            cache.clear();
+        }
+
+        if let Some(cache) = self.hash_at_topo_cache.as_ref() {
+            let mut cache = cache.lock().await;
+            cache.clear();
+        }
+
+        if let Some(cache) = self.cumulative_difficulty_cache.as_ref() {
+            let mut cache = cache.lock().await;
+            cache.clear();
+        }
+
+        if let Some(cache) = self.assets_cache.as_ref() {
+            let mut cache = cache.lock().await;
+            cache.clear();
+        }
+
+        Ok(())
+    }
+
+    // Delete the whole block using its topoheight
+    async fn delete_block_at_topoheight(&mut self, topoheight: u64) -> Result<(Hash, Arc<BlockHeader>, Vec<(Hash, Arc<Transaction>)>), BlockchainError> {
+        trace!("Delete block at topoheight {topoheight}");
+
+        // delete topoheight<->hash pointers
+        let hash = self.delete_cacheable_data(&self.hash_at_topo, &self.hash_at_topo_cache, &topoheight).await?;
+        trace!("Hash is {hash} at topo {topoheight}");
+
+        self.delete_cacheable_data::<Hash, u64>(&self.topo_by_hash, &self.topo_by_hash_cache, &hash).await?;
+
+        trace!("deleting block header {}", hash);
+        let block = self.delete_data(&self.blocks, &self.blocks_cache, &hash).await?;
+        trace!("block header deleted successfully");
+
+        trace!("Deleting supply and block reward");
+        let supply: u64 = self.delete_cacheable_data(&self.supply, &None, &topoheight).await?;
+        trace!("Supply was {}", supply);
+
+        let reward: u64 = self.delete_cacheable_data(&self.rewards, &None, &topoheight).await?;
+        trace!("Reward for block {} was: {}", hash, reward);
+
+        trace!("Deleting difficulty");
+        let _: Difficulty = self.delete_cacheable_data(&self.difficulty, &None, &hash).await?;
+
+        trace!("Deleting cumulative difficulty");
+        let cumulative_difficulty: CumulativeDifficulty = self.delete_cacheable_data(&self.cumulative_difficulty, &self.cumulative_difficulty_cache, &hash).await?;
+        trace!("Cumulative difficulty deleted: {}", cumulative_difficulty);
+
+        let mut txs = Vec::new();
+        for tx_hash in block.get_transactions() {
+            if self.has_tx_blocks(tx_hash)? {
+                let mut blocks: Tips = self.delete_cacheable_data(&self.tx_blocks, &None, tx_hash).await?;
+                let blocks_len =  blocks.len();
+                blocks.remove(&hash);
+                self.set_blocks_for_tx(tx_hash, &blocks)?;
+                trace!("Tx was included in {}, blocks left: {}", blocks_len, blocks.into_iter().map(|b| b.to_string()).collect::<Vec<String>>().join(", "));
+            }
+
+            if self.is_tx_executed_in_a_block(tx_hash)? {
+                trace!("Tx {} was executed, deleting", tx_hash);
+                self.remove_tx_executed(&tx_hash)?;
+            }
+
+            // We have to check first as we may have already deleted it because of client protocol
+            // which allow multiple time the same txs in differents blocks
+            if self.contains_data(&self.transactions, &self.transactions_cache, tx_hash).await? {
+                trace!("Deleting TX {} in block {}", tx_hash, hash);
+                let tx: Arc<Transaction> = self.delete_data(&self.transactions, &self.transactions_cache, tx_hash).await?;
+                txs.push((tx_hash.clone(), tx));
+            }
+        }
+
+        // remove the block hash from the set, and delete the set if empty
+        if self.has_blocks_at_height(block.get_height()).await? {
+            self.remove_block_hash_at_height(&hash, block.get_height()).await?;
+        }
+
+        // Delete cache of past blocks
+        if let Some(cache) = &self.past_blocks_cache {
+            let mut cache = cache.lock().await;
+            cache.pop(&hash);
+        }
+
+        Ok((hash, block, txs))
+    }
+
+    async fn delete_versioned_balances_at_topoheight(&mut self, topoheight: u64) -> Result<(), BlockchainError> {
+        trace!("delete versioned balances at topoheight {}", topoheight);
+        let prefix = topoheight.to_be_bytes();
+        // Collect them first as the tree is read again for each entry
+        let entries = self.versioned_balances.scan_prefix(&prefix).collect::<Result<Vec<_>, _>>()?;
+        for (key, value) in entries {
+            // Delete this version from DB
+            self.versioned_balances.remove(&key)?;
+
+            // Deserialize keys part
+            let asset = Hash::from_bytes(&key[40..72])?;
+            let key = PublicKey::from_bytes(&key[8..40])?;
+
+            let last_topoheight = self.get_last_topoheight_for_balance(&key, &asset).await?;
+            if last_topoheight >= topoheight {
+                // Deserialize value, it is needed to get the previous topoheight
+                let versioned_balance = VersionedBalance::from_bytes(&value)?;
+
+                // Now records changes, for each balances
+                let db_key = self.get_balance_key_for(&key, &asset);
+                if let Some(previous_topoheight) = versioned_balance.get_previous_topoheight() {
+                    self.balances.insert(&db_key, &previous_topoheight.to_be_bytes())?;
+                } else {
+                    // if there is no previous topoheight, it means that this is the first version
+                    // so we can delete the balance
+                    self.balances.remove(&db_key)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn delete_versioned_nonces_at_topoheight(&mut self, topoheight: u64) -> Result<(), BlockchainError> {
+        trace!("delete versioned nonces at topoheight {}", topoheight);
+        let prefix = topoheight.to_be_bytes();
+        // Collect them first as the pointers are updated for each entry
+        let entries = self.versioned_nonces.scan_prefix(&prefix).collect::<Result<Vec<_>, _>>()?;
+        for (key, value) in entries {
+            // Delete this version from DB
+            self.versioned_nonces.remove(&key)?;
+
+            // Deserialize keys part
+            let key = PublicKey::from_bytes(&key[8..40])?;
+
+            // Because of chain reorg, it may have been already deleted
+            if let Ok(last_topoheight) = self.get_last_topoheight_for_nonce(&key).await {
+                if last_topoheight >= topoheight {
+                    // Deserialize value, it is needed to get the previous topoheight
+                    let version = VersionedNonce::from_bytes(&value)?;
+                    // Now records changes
+                    if let Some(previous_topoheight) = version.get_previous_topoheight() {
+                        self.set_last_topoheight_for_nonce(&key, previous_topoheight).await?;
+                    } else {
+                        // if there is no previous topoheight, it means that this is the first version
+                        // so we can delete the balance
+                        self.delete_last_topoheight_for_nonce(&key).await?;
+                    }
+                }
+            }
+        }
+
+        trace!("delete versioned nonces at topoheight {} done!", topoheight);
+        Ok(())
+    }
+
+    async fn delete_versioned_balances_above_topoheight(&mut self, topoheight: u64) -> Result<(), BlockchainError> {
+        trace!("delete versioned balances above topoheight {}!", topoheight);
+        self.delete_versioned_tree_above_topoheight(&self.versioned_balances, topoheight)
+    }
+
+    async fn delete_versioned_nonces_above_topoheight(&mut self, topoheight: u64) -> Result<(), BlockchainError> {
+        trace!("delete versioned nonces above topoheight {}", topoheight);
+        self.delete_versioned_tree_above_topoheight(&self.versioned_nonces, topoheight)
+    }
+
+    async fn delete_registrations_above_topoheight(&mut self, topoheight: u64) -> Result<(), BlockchainError> {
+        trace!("delete registrations above topoheight {}", topoheight);
+        for el in self.registrations_prefixed.keys() {
+            let key = el?;
+            let topo = u64::from_bytes(&key[0..8])?;
+            if topo > topoheight {
+                self.registrations_prefixed.remove(&key)?;
+                let pkey = &key[8..40];
+                self.registrations.remove(pkey)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn delete_registrations_below_topoheight(&mut self, topoheight: u64) -> Result<(), BlockchainError> {
+        trace!("delete registrations below topoheight {}", topoheight);
+        let mut buf = [0u8; 40];
+        for el in self.registrations.iter() {
+            let (key, value) = el?;
+            let topo = u64::from_bytes(&value[0..8])?;
+            if topo < topoheight {
+                buf[0..8].copy_from_slice(&value);
+                buf[8..40].copy_from_slice(&key);
+
+                self.registrations_prefixed.remove(&buf)?;
+                self.registrations.remove(&key)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn delete_versioned_balances_below_topoheight(&mut self, topoheight: u64) -> Result<(), BlockchainError> {
+        trace!("delete versioned balances below topoheight {}!", topoheight);
+        self.delete_versioned_tree_below_topoheight(&self.versioned_balances, topoheight)
+    }
+
+    async fn delete_versioned_nonces_below_topoheight(&mut self, topoheight: u64) -> Result<(), BlockchainError> {
+        trace!("delete versioned nonces below topoheight {}", topoheight);
+        self.delete_versioned_tree_below_topoheight(&self.versioned_nonces, topoheight)
+    }
+
+    async fn create_snapshot_balances_at_topoheight(&mut self, topoheight: u64) -> Result<(), BlockchainError> {
+        // tree where PublicKey are stored with the highest balance topoheight in it
+        let entries = self.balances.iter().collect::<Result<Vec<_>, _>>()?;
+        for (key_bytes, value) in entries {
+            let key = PublicKey::from_bytes(&key_bytes[0..32])?;
+            let asset = Hash::from_bytes(&key_bytes[32..64])?;
+            let highest_balance_topoheight = u64::from_bytes(&value)?;
+
+            // retrieve the highest versioned balance
+            let mut versioned_balance = self.get_balance_at_exact_topoheight(&key, &asset, highest_balance_topoheight).await?;
+
+            // if the highest topoheight for this account is less than the snapshot topoheight
+            // update it to the topoheight
+            // otherwise, delete the previous topoheight in VersionedBalance which is under topoheight
+            if highest_balance_topoheight < topoheight {
+                // save the new highest topoheight
+                self.balances.insert(&key_bytes, &topoheight.to_be_bytes())?;
+                // remove the previous topoheight
+                versioned_balance.set_previous_topoheight(None);
+
+                // save it
+                let key = self.get_versioned_balance_key(&key, &asset, topoheight);
+                self.versioned_balances.insert(key, versioned_balance.to_bytes())?;
+            } else {
+                // find the first VersionedBalance which is under topoheight
+                while let Some(previous_topoheight) = versioned_balance.get_previous_topoheight() {
+                    if previous_topoheight < topoheight {
+                        versioned_balance.set_previous_topoheight(None);
+                        // save it
+                        let key = self.get_versioned_balance_key(&key, &asset, topoheight);
+                        self.versioned_balances.insert(key, versioned_balance.to_bytes())?;
+                        break;
+                    }
+
+                    // keep searching
+                    versioned_balance = self.get_balance_at_exact_topoheight(&key, &asset, previous_topoheight).await?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn create_snapshot_nonces_at_topoheight(&mut self, topoheight: u64) -> Result<(), BlockchainError> {
+        // tree where PublicKey are stored with the highest nonce topoheight in it
+        let entries = self.nonces.iter().collect::<Result<Vec<_>, _>>()?;
+        for (key_bytes, value) in entries {
+            let key = PublicKey::from_bytes(&key_bytes)?;
+            let highest_topoheight = u64::from_bytes(&value)?;
+
+            // retrieve the highest versioned nonce
+            let mut versioned_nonce = self.get_nonce_at_exact_topoheight(&key, highest_topoheight).await?;
+
+            // if the highest topoheight for this account is less than the snapshot topoheight
+            // update it to the topoheight
+            // otherwise, delete the previous topoheight in VersionedNonce which is under topoheight
+            if highest_topoheight < topoheight {
+                // save the new highest topoheight
+                self.nonces.insert(&key_bytes, &topoheight.to_be_bytes())?;
+                // remove the previous topoheight
+                versioned_nonce.set_previous_topoheight(None);
+
+                // save it
+                let key = self.get_versioned_nonce_key(&key, topoheight);
+                self.versioned_nonces.insert(key, versioned_nonce.to_bytes())?;
+            } else {
+                // find the first VersionedNonce which is under topoheight
+                while let Some(previous_topoheight) = versioned_nonce.get_previous_topoheight() {
+                    if previous_topoheight < topoheight {
+                        versioned_nonce.set_previous_topoheight(None);
+                        // save it
+                        let key = self.get_versioned_nonce_key(&key, topoheight);
+                        self.versioned_nonces.insert(key, versioned_nonce.to_bytes())?;
+                        break;
+                    }
+
+                    // keep searching
+                    versioned_nonce = self.get_nonce_at_exact_topoheight(&key, previous_topoheight).await?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn create_snapshot_registrations_at_topoheight(&mut self, topoheight: u64) -> Result<(), BlockchainError> {
+        // tree where PublicKey are stored with the registration topoheight in it
+        let mut buf = [0u8; 40];
+        for el in self.registrations.iter() {
+            let (key, value) = el?;
+            let registration_topo = u64::from_bytes(&value)?;
+
+            // if the registration topoheight for this account is less than the snapshot topoheight
+            // update it to the topoheight
+            if registration_topo < topoheight {
+                // Delete the prefixed registration
+                buf[0..8].copy_from_slice(&value);
+                buf[8..40].copy_from_slice(&key);
+                self.registrations_prefixed.remove(&buf)?;
+
+                // save the new registration topoheight
+                self.registrations.insert(&key, &topoheight.to_be_bytes())?;
+
+                // Overwrite with the new topoheight
+                buf[0..8].copy_from_slice(&topoheight.to_be_bytes());
+                self.registrations_prefixed.insert(&buf, b"")?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_network(&self) -> Result<Network, BlockchainError> {
+        trace!("get network");
+        self.load_from_disk(&self.extra, NETWORK)
+    }
+
+    fn set_network(&mut self, network: &Network) -> Result<(), BlockchainError> {
+        trace!("set network to {}", network);
+        self.extra.insert(NETWORK, network.to_bytes())?;
+        Ok(())
+    }
+
+    fn has_network(&self) -> Result<bool, BlockchainError> {
+        trace!("has network");
+        self.extra.contains_key(NETWORK)
+    }
+
+    async fn pop_blocks(&mut self, mut height: u64, mut topoheight: u64, count: u64, stable_topo_height: u64) -> Result<(u64, u64, Vec<(Hash, Arc<Transaction>)>), BlockchainError> {
+        trace!("pop blocks from height: {}, topoheight: {}, count: {}", height, topoheight, count);
+        if topoheight < count as u64 { // also prevent removing genesis block
+            return Err(BlockchainError::NotEnoughBlocks);
+        }
+
+        // search the lowest topo height available based on count + 1
+        // (last lowest topo height accepted)
+        let mut lowest_topo = topoheight - count;
+        trace!("Lowest topoheight for rewind: {}", lowest_topo);
+
+        let pruned_topoheight = self.get_pruned_topoheight().await?.unwrap_or(0);
+        if pruned_topoheight != 0 {
+            let safety_pruned_topoheight = pruned_topoheight + PRUNE_SAFETY_LIMIT;
+            if lowest_topo <= safety_pruned_topoheight && stable_topo_height != 0 {
+                warn!("Pruned topoheight is {}, lowest topoheight is {}, rewind only until {}", pruned_topoheight, lowest_topo, safety_pruned_topoheight);
+                lowest_topo = safety_pruned_topoheight;
+            }
+        }
+
+        // Transactions of these blocks are deleted, they can't be rewinded
+        if let Some(pruned_bodies_topoheight) = self.pruned_bodies_topoheight {
+            if lowest_topo < pruned_bodies_topoheight && stable_topo_height != 0 {
+                warn!("Blocks bodies are pruned until topoheight {}, lowest topoheight is {}, rewind only until it", pruned_bodies_topoheight, lowest_topo);
+                lowest_topo = pruned_bodies_topoheight;
+            }
+        }
+
+        // new TIPS for chain
+        let mut tips = self.get_tips().await?;
+
+        // Delete all orphaned blocks tips
+        for tip in tips.clone() {
+            if !self.is_block_topological_ordered(&tip).await {
+                debug!("Tip {} is not ordered, removing", tip);
+                tips.remove(&tip);
+            }
+        }
+
+        // all txs to be rewinded
+        let mut txs = Vec::new();
+        let mut done = 0;
+        'main: loop {
+            // stop rewinding if its genesis block or if we reached the lowest topo
+            if topoheight <= lowest_topo || topoheight <= stable_topo_height || height == 0 { // prevent removing genesis block
+                trace!("Done: {done}, count: {count}, height: {height}, topoheight: {topoheight}, lowest topo: {lowest_topo}, stable topo: {stable_topo_height}");
+                break 'main;
+            }
+
+            // Delete the hash at topoheight
+            let (hash, block, block_txs) = self.delete_block_at_topoheight(topoheight).await?;
+            trace!("Block {} at topoheight {} deleted", hash, topoheight);
+            txs.extend(block_txs);
+
+            // generate new tips
+            trace!("Removing {} from {} tips", hash, tips.len());
+            tips.remove(&hash);
+
+            for hash in block.get_tips() {
+                trace!("Adding {} to {} tips", hash, tips.len());
+                tips.insert(hash.clone());
+            }
+
+            if topoheight <= pruned_topoheight {
+                warn!("Pruned topoheight is reached, this is not healthy, starting from 0");
+                topoheight = 0;
+                height = 0;
+
+                tips.clear();
+                tips.insert(self.get_hash_at_topo_height(0).await?);
+
+                self.extra.remove(PRUNED_TOPOHEIGHT)?;
+                self.pruned_topoheight = None;
+
+                break 'main;
+            }
+
+            topoheight -= 1;
+            // height of old block become new height
+            if block.get_height() < height {
+                height = block.get_height();
+            }
+            done += 1;
+        }
+
+        debug!("Blocks processed {}, new topoheight: {}, new height: {}, tips: {}", done, topoheight, height, tips.len());
+
+        trace!("Cleaning assets");
+
+        // All deleted assets
+        let mut deleted_assets = HashSet::new();
+
+        // clean all assets
+        for el in self.assets.iter() {
+            let (key, value) = el.context("error on asset iterator")?;
+            let asset = Hash::from_bytes(&key)?;
+            trace!("verifying asset registered: {}", asset);
+
+            let registration_topoheight = u64::from_bytes(&value)?;
+            if registration_topoheight > topoheight {
+                trace!("Asset {} was registered at topoheight {}, deleting", asset, registration_topoheight);
+                // Delete it from registered assets
+                self.assets.remove(&key).context(format!("Error while deleting asset {asset} from registered assets"))?;
+
+                deleted_assets.insert(asset);
+            }
+        }
+
+        trace!("Cleaning nonces");
+        // now let's process nonces versions
+        // we set the new highest topoheight to the highest found under the new topoheight
+        let entries = self.nonces.iter().collect::<Result<Vec<_>, _>>()?;
+        for (key, value) in entries {
+            let highest_topoheight = u64::from_bytes(&value)?;
+            if highest_topoheight < pruned_topoheight {
+                warn!("wrong nonce topoheight stored, highest topoheight is {}, pruned topoheight is {}", highest_topoheight, pruned_topoheight);
+                self.nonces.remove(&key)?;
+                continue;
+            }
+
+            if highest_topoheight > topoheight {
+                // the key was read from the tree, so it is always present
+                self.nonces.remove(&key)?;
+                self.store_accounts_count(self.count_accounts().await? - 1)?;
+
+                // find the first version which is under topoheight
+                let pkey = PublicKey::from_bytes(&key)?;
+                let mut version = self.get_nonce_at_exact_topoheight(&pkey, highest_topoheight).await
+                    .context(format!("Error while retrieving nonce at exact topoheight {highest_topoheight}"))?;
+
+                while let Some(previous_topoheight) = version.get_previous_topoheight() {
+                    if previous_topoheight < topoheight {
+                        // we find the new highest version which is under new topoheight
+                        trace!("New highest version nonce for {} is at topoheight {}", pkey.as_address(self.network()), previous_topoheight);
+                        if self.nonces.replace(&key, &previous_topoheight.to_be_bytes())?.is_none() {
+                            self.store_accounts_count(self.count_accounts().await? + 1)?;
+                        }
+                        break;
+                    }
+
+                    // keep searching
+                    version = self.get_nonce_at_exact_topoheight(&pkey, previous_topoheight).await
+                        .context(format!("Error while searching nonce at exact topoheight"))?;
+                }
+            } else {
+                // nothing to do as its under the rewinded topoheight
+            }
+        }
+
+        trace!("Cleaning multisig setups");
+        // same for the multisig setups
+        let entries = self.multisig.iter().collect::<Result<Vec<_>, _>>()?;
+        for (key, value) in entries {
+            let highest_topoheight = u64::from_bytes(&value)?;
+            if highest_topoheight > topoheight {
+                self.multisig.remove(&key)?;
+
+                let pkey = PublicKey::from_bytes(&key)?;
+                let mut version = self.get_multisig_at_exact_topoheight(&pkey, highest_topoheight).await
+                    .context(format!("Error while retrieving multisig at exact topoheight {highest_topoheight}"))?;
+
+                while let Some(previous_topoheight) = version.get_previous_topoheight() {
+                    if previous_topoheight < topoheight {
+                        trace!("New highest version multisig for {} is at topoheight {}", pkey.as_address(self.network()), previous_topoheight);
+                        self.multisig.insert(&key, &previous_topoheight.to_be_bytes())?;
+                        break;
+                    }
+
+                    version = self.get_multisig_at_exact_topoheight(&pkey, previous_topoheight).await
+                        .context(format!("Error while searching multisig at exact topoheight"))?;
+                }
+            }
+        }
+
+        trace!("Cleaning balances");
+        // do balances too
+        let entries = self.balances.iter().collect::<Result<Vec<_>, _>>()?;
+        for (key, value) in entries {
+            let asset = Hash::from_bytes(&key[32..64])?;
+            let mut delete = true;
+
+            // if the asset is not deleted, we can process it
+            if !deleted_assets.contains(&asset) {
+                let highest_topoheight = u64::from_bytes(&value)?;
+                if highest_topoheight > topoheight && highest_topoheight >= pruned_topoheight {
+                    // find the first version which is under topoheight
+                    let pkey = PublicKey::from_bytes(&key[0..32])?;
+
+                    let mut version = self.get_balance_at_exact_topoheight(&pkey, &asset, highest_topoheight).await
+                        .context(format!("Error while retrieving balance at exact topoheight {highest_topoheight}"))?;
+
+                    while let Some(previous_topoheight) = version.get_previous_topoheight() {
+                        if previous_topoheight < topoheight {
+                            // we find the new highest version which is under new topoheight
+                            trace!("New highest version balance for {} is at topoheight {} with asset {}", pkey.as_address(self.network()), previous_topoheight, asset);
+                            self.balances.insert(&key, &previous_topoheight.to_be_bytes())?;
+                            delete = false;
+                            break;
+                        }
+
+                        // keep searching
+                        version = self.get_balance_at_exact_topoheight(&pkey, &asset, previous_topoheight).await?;
+                    }
+                }
+            }
+
+            if delete {
+                self.balances.remove(&key)?;
+            }
+        }
+
+        warn!("Blocks rewinded: {}, new topoheight: {}, new height: {}", done, topoheight, height);
+
+        trace!("Cleaning versioned balances and nonces");
+
+        // now delete all versioned balances and nonces above the new topoheight
+        self.delete_versioned_balances_above_topoheight(topoheight).await?;
+        self.delete_versioned_nonces_above_topoheight(topoheight).await?;
+        self.delete_versioned_tree_above_topoheight(&self.versioned_multisig, topoheight)?;
+        self.delete_burned_supply_above_topoheight(topoheight)?;
+        // Delete also registrations
+        self.delete_registrations_above_topoheight(topoheight).await?;
+
+        trace!("Cleaning caches");
+        // Clear all caches to not have old data after rewind
+        self.clear_caches().await?;
+
+        trace!("Storing new pointers");
+        // store the new tips and topo topoheight
+        self.store_tips(&tips)?;
+        self.set_top_topoheight(topoheight)?;
+        self.set_top_height(height)?;
+
+        // Reduce the count of blocks stored
+        let count = self.count_blocks().await? - done;
+        self.extra.insert(BLOCKS_COUNT, &count.to_be_bytes())?;
+
+        Ok((height, topoheight, txs))
+    }
+
+    async fn get_top_block_hash(&self) -> Result<Hash, BlockchainError> {
+        trace!("get top block hash");
+        self.get_hash_at_topo_height(self.get_top_topoheight()?).await
+    }
+
+    fn get_top_topoheight(&self) -> Result<u64, BlockchainError> {
+        trace!("get top topoheight");
+        self.load_from_disk(&self.extra, TOP_TOPO_HEIGHT)
+    }
+
+    fn set_top_topoheight(&mut self, topoheight: u64) -> Result<(), BlockchainError> {
+        trace!("set new top topoheight at {}", topoheight);
+        self.extra.insert(TOP_TOPO_HEIGHT, &topoheight.to_be_bytes())?;
+        Ok(())
+    }
+
+    fn get_top_height(&self) -> Result<u64, BlockchainError> {
+        trace!("get top height");
+        self.load_from_disk(&self.extra, TOP_HEIGHT)
+    }
+
+    fn set_top_height(&mut self, height: u64) -> Result<(), BlockchainError> {
+        trace!("set new top height at {}", height);
+        self.extra.insert(TOP_HEIGHT, &height.to_be_bytes())?;
+        Ok(())
+    }
+
+    async fn get_top_block_header(&self) -> Result<(Arc<BlockHeader>, Hash), BlockchainError> {
+        trace!("get top block header");
+        let hash = self.get_top_block_hash().await?;
+        Ok((self.get_block_header_by_hash(&hash).await?, hash))
+    }
+
+    async fn get_top_block(&self) -> Result<Block, BlockchainError> {
+        trace!("get top block");
+        let (block, _) = self.get_top_block_header().await?;
+        let mut transactions = Vec::new();
+        for tx in block.get_transactions() {
+            let transaction = self.get_transaction(tx).await?;
+            transactions.push(Immutable::Arc(transaction));
+        }
+
+        let block = Block::new(Immutable::Arc(block), transactions);
+        Ok(block)
+    }
+
+    async fn get_tips(&self) -> Result<Tips, BlockchainError> {
+        trace!("get tips");
+        Ok(self.tips_cache.clone())
+    }
+
+    fn store_tips(&mut self, tips: &Tips) -> Result<(), BlockchainError> {
+        trace!("Saving {} Tips", tips.len());
+        self.extra.insert(TIPS, tips.to_bytes())?;
+        self.tips_cache = tips.clone();
+        Ok(())
+    }
+
+    // Returns the current size on disk in bytes
+    async fn get_size_on_disk(&self) -> Result<u64, BlockchainError> {
+        self.db.size_on_disk()
+    }
+
+    fn start_commit_point(&mut self) -> Result<(), BlockchainError> {
+        trace!("start commit point");
+        self.journal.start()
+    }
+
+    fn has_commit_point(&self) -> bool {
+        self.journal.is_recording()
+    }
+
+    async fn end_commit_point(&mut self, apply: bool) -> Result<(), BlockchainError> {
+        trace!("end commit point, apply: {}", apply);
+        let Some(entries) = self.journal.stop() else {
+            return Ok(())
+        };
+
+        if !apply && !entries.is_empty() {
+            debug!("Reverting {} changes made since the commit point", entries.len());
+            // Newest changes first so each key gets back its value from the start
+            for (name, key, previous) in entries.into_iter().rev() {
+                let Some(tree) = self.trees().into_iter().find(|tree| tree.name() == name) else {
+                    continue;
+                };
+
+                match previous {
+                    Some(value) => tree.insert(key, value)?,
+                    None => tree.remove(key)?
+                }
+            }
+
+            self.clear_caches().await?;
+            self.load_metadata_from_disk();
+        }
+
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<(), BlockchainError> {
+        info!("Flushing {} database", B::NAME);
+        self.flush()?;
+        info!("{} database flushed", B::NAME);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process, sync::atomic::AtomicUsize};
+    #[cfg(feature = "rocksdb")]
+    use super::super::rocksdb::RocksBackend;
+    use super::{super::sled::SledBackend, *};
+
+    // New directory for each storage opened by the tests
+    fn temp_path(backend: &str) -> String {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let id = COUNTER.fetch_add(1, Ordering::SeqCst);
+        env::temp_dir()
+            .join(format!("xelis-storage-{}-{}-{}", backend.to_lowercase(), process::id(), id))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    fn key(bytes: &[u8]) -> Box<[u8]> {
+        Box::from(bytes)
+    }
+
+    async fn test_open_write_reopen<B: KVBackend>() {
+        let path = temp_path(B::NAME);
+        let tips: Tips = [Hash::new([1; 32]), Hash::new([2; 32])].into_iter().collect();
+
+        let mut storage = KVStorage::<B>::open(path.clone(), Some(16), Network::Dev).unwrap();
+        assert!(storage.get_top_topoheight().is_err());
+        storage.set_top_topoheight(42).unwrap();
+        storage.set_top_height(40).unwrap();
+        storage.store_tips(&tips).unwrap();
+        storage.stop().await.unwrap();
+        drop(storage);
+
+        let storage = KVStorage::<B>::open(path.clone(), None, Network::Dev).unwrap();
+        assert_eq!(storage.get_top_topoheight().unwrap(), 42);
+        assert_eq!(storage.get_top_height().unwrap(), 40);
+        assert_eq!(storage.get_tips().await.unwrap(), tips);
+        drop(storage);
+
+        // The network is stored on the first open
+        assert!(matches!(KVStorage::<B>::open(path.clone(), None, Network::Mainnet), Err(BlockchainError::InvalidNetwork)));
+
+        fs::remove_dir_all(path).unwrap();
+    }
+
+    fn test_tree_iterators<B: KVBackend>() {
+        let path = temp_path(B::NAME);
+        let storage = KVStorage::<B>::open(path.clone(), None, Network::Dev).unwrap();
+        let tree = &storage.burned_supply;
+        assert!(tree.is_empty());
+
+        for (k, v) in [([1u8, 1], 1u8), ([1, 3], 2), ([2, 0], 3)] {
+            tree.insert(k, [v]).unwrap();
+        }
+        assert_eq!(tree.get([1u8, 3]).unwrap(), Some(vec![2]));
+        assert!(!tree.contains_key([1u8, 2]).unwrap());
+
+        let next = |res: Option<Result<Entry, BlockchainError>>| res.map(|res| res.unwrap().0);
+        assert_eq!(next(tree.iter_from(&[1, 2]).next()), Some(key(&[1, 3])));
+        assert_eq!(next(tree.iter_from(&[1, 3]).next()), Some(key(&[1, 3])));
+        assert_eq!(next(tree.iter_from(&[2, 1]).next()), None);
+        assert_eq!(next(tree.iter_rev_from(&[1, 2]).next()), Some(key(&[1, 1])));
+        assert_eq!(next(tree.iter_rev_from(&[1, 3]).next()), Some(key(&[1, 3])));
+        assert_eq!(next(tree.iter_rev_from(&[0]).next()), None);
+
+        let keys = tree.scan_prefix_keys(&[1]).collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(keys, vec![key(&[1, 1]), key(&[1, 3])]);
+
+        assert_eq!(tree.replace([2u8, 0], [4u8]).unwrap(), Some(vec![3]));
+        assert_eq!(tree.take([2u8, 0]).unwrap(), Some(vec![4]));
+        assert_eq!(tree.take([2u8, 0]).unwrap(), None);
+
+        drop(storage);
+        fs::remove_dir_all(path).unwrap();
+    }
+
+    async fn test_commit_point_revert<B: KVBackend>() {
+        let path = temp_path(B::NAME);
+        let mut storage = KVStorage::<B>::open(path.clone(), None, Network::Dev).unwrap();
+        storage.set_top_topoheight(1).unwrap();
+        storage.extra.insert(b"KEEP", [1u8]).unwrap();
+
+        storage.start_commit_point().unwrap();
+        storage.set_top_topoheight(2).unwrap();
+        storage.set_top_topoheight(3).unwrap();
+        storage.extra.insert(b"TEMP", [2u8]).unwrap();
+        storage.extra.remove(b"KEEP").unwrap();
+        storage.end_commit_point(false).await.unwrap();
+
+        assert_eq!(storage.get_top_topoheight().unwrap(), 1);
+        assert!(!storage.extra.contains_key(b"TEMP").unwrap());
+        assert_eq!(storage.extra.get(b"KEEP").unwrap(), Some(vec![1]));
+
+        // Applied changes are kept
+        storage.start_commit_point().unwrap();
+        storage.set_top_topoheight(4).unwrap();
+        storage.end_commit_point(true).await.unwrap();
+        assert_eq!(storage.get_top_topoheight().unwrap(), 4);
+
+        drop(storage);
+        fs::remove_dir_all(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_sled_open_write_reopen() {
+        test_open_write_reopen::<SledBackend>().await;
+    }
+
+    #[cfg(feature = "rocksdb")]
+    #[tokio::test]
+    async fn test_rocksdb_open_write_reopen() {
+        test_open_write_reopen::<RocksBackend>().await;
+    }
+
+    #[test]
+    fn test_sled_tree_iterators() {
+        test_tree_iterators::<SledBackend>();
+    }
+
+    #[cfg(feature = "rocksdb")]
+    #[test]
+    fn test_rocksdb_tree_iterators() {
+        test_tree_iterators::<RocksBackend>();
+    }
+
+    #[tokio::test]
+    async fn test_sled_commit_point_revert() {
+        test_commit_point_revert::<SledBackend>().await;
+    }
+
+    #[cfg(feature = "rocksdb")]
+    #[tokio::test]
+    async fn test_rocksdb_commit_point_revert() {
+        test_commit_point_revert::<RocksBackend>().await;
+    }
+}
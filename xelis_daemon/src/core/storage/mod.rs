@@ -1,12 +1,23 @@
 mod providers;
+mod journal;
+mod kv;
 mod sled;
+#[cfg(feature = "rocksdb")]
+mod rocksdb;
 
 pub use self::{
     sled::SledStorage,
     providers::*,
 };
-
-use std::{collections::HashSet, sync::Arc};
+#[cfg(feature = "rocksdb")]
+pub use self::rocksdb::RocksStorage;
+
+use std::{
+    collections::HashSet,
+    fmt::{Display, Formatter},
+    str::FromStr,
+    sync::Arc
+};
 use async_trait::async_trait;
 use xelis_common::{
     block::{Block, BlockHeader},
@@ -19,10 +30,46 @@ use crate::core::error::BlockchainError;
 // Represents the tips of the chain or of a block
 pub type Tips = HashSet<Hash>;
 
+// Database engine used by the daemon
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum StorageBackend {
+    Sled,
+    // Better write throughput and compaction on large chains
+    // Only available when built with the rocksdb feature
+    #[cfg(feature = "rocksdb")]
+    RocksDB
+}
+
+impl FromStr for StorageBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "sled" => Self::Sled,
+            #[cfg(feature = "rocksdb")]
+            "rocksdb" => Self::RocksDB,
+            #[cfg(not(feature = "rocksdb"))]
+            "rocksdb" => return Err("RocksDB storage backend is not available, the daemon must be built with the rocksdb feature".into()),
+            _ => return Err("Invalid storage backend".into())
+        })
+    }
+}
+
+impl Display for StorageBackend {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let str = match &self {
+            Self::Sled => "sled",
+            #[cfg(feature = "rocksdb")]
+            Self::RocksDB => "rocksdb",
+        };
+        write!(f, "{}", str)
+    }
+}
+
 #[async_trait]
-pub trait Storage: DagOrderProvider + PrunedTopoheightProvider + NonceProvider + AccountProvider + ClientProtocolProvider + BlockDagProvider + MerkleHashProvider + Sync + Send + 'static {
-    // Is the chain running on mainnet
-    fn is_mainnet(&self) -> bool;
+pub trait Storage: DagOrderProvider + PrunedTopoheightProvider + NonceProvider + AccountProvider + ClientProtocolProvider + BlockDagProvider + MerkleHashProvider + MultiSigProvider + SnapshotProvider + BurnedSupplyProvider + Sync + Send + 'static {
+    // Network the chain is running on, used to display the addresses
+    fn network(&self) -> Network;
 
     // Clear caches if exists
     async fn clear_caches(&mut self) -> Result<(), BlockchainError>;
@@ -107,6 +154,15 @@ pub trait Storage: DagOrderProvider + PrunedTopoheightProvider + NonceProvider +
     // Get the size of the chain on disk in bytes
     async fn get_size_on_disk(&self) -> Result<u64, BlockchainError>;
 
+    // Start recording the changes made to the storage so they can be reverted
+    fn start_commit_point(&mut self) -> Result<(), BlockchainError>;
+
+    // Check if the changes made to the storage are being recorded
+    fn has_commit_point(&self) -> bool;
+
+    // Stop recording the changes, they are all reverted if apply is false
+    async fn end_commit_point(&mut self, apply: bool) -> Result<(), BlockchainError>;
+
     // Stop the storage and wait for it to finish
     async fn stop(&mut self) -> Result<(), BlockchainError>;
 }
\ No newline at end of file
@@ -1,6 +1,6 @@
 use async_trait::async_trait;
 use xelis_common::{crypto::PublicKey, serializer::Serializer};
-use crate::core::{error::BlockchainError, storage::SledStorage};
+use crate::core::{error::BlockchainError, storage::kv::{KVBackend, KVStorage}};
 
 #[async_trait]
 pub trait AccountProvider {
@@ -31,19 +31,18 @@ fn prefixed_db_key_no_u64(topoheight: &[u8], key: &PublicKey) -> [u8; 40] {
     buf
 }
 
-
 #[async_trait]
-impl AccountProvider for SledStorage {
+impl<B: KVBackend> AccountProvider for KVStorage<B> {
     async fn get_account_registration_topoheight(&self, key: &PublicKey) -> Result<u64, BlockchainError> {
         self.load_from_disk(&self.registrations, key.as_bytes())
     }
 
     async fn set_account_registration_topoheight(&mut self, key: &PublicKey, topoheight: u64) -> Result<(), BlockchainError> {
-        if let Some(old) = self.registrations.insert(key.as_bytes(), topoheight.to_bytes())? {
+        if let Some(old) = self.registrations.replace(key.as_bytes(), topoheight.to_bytes())? {
             self.registrations_prefixed.remove(&prefixed_db_key_no_u64(&old, key))?;
         }
 
-        self.registrations_prefixed.insert(prefixed_db_key(topoheight, key), &[])?;
+        self.registrations_prefixed.insert(prefixed_db_key(topoheight, key), b"")?;
 
         Ok(())
     }
@@ -51,7 +50,7 @@ impl AccountProvider for SledStorage {
     async fn is_account_registered(&self, key: &PublicKey) -> Result<bool, BlockchainError> {
         let value = self.load_optional_from_disk::<u64>(&self.registrations, key.as_bytes())?;
         if let Some(topo) = value {
-            return Ok(self.registrations_prefixed.contains_key(prefixed_db_key(topo, key))?)
+            return self.registrations_prefixed.contains_key(prefixed_db_key(topo, key))
         }
 
         Ok(false)
@@ -67,7 +66,8 @@ impl AccountProvider for SledStorage {
     }
 
     async fn delete_registrations_at_topoheight(&mut self, topoheight: u64) -> Result<(), BlockchainError> {
-        for el in self.registrations_prefixed.scan_prefix(topoheight.to_bytes()).keys() {
+        let prefix = topoheight.to_bytes();
+        for el in self.registrations_prefixed.scan_prefix_keys(&prefix) {
             let k = el?;
             self.registrations_prefixed.remove(&k)?;
             let key = &k[8..40];
@@ -76,4 +76,4 @@ impl AccountProvider for SledStorage {
 
         Ok(())
     }
-}
\ No newline at end of file
+}
@@ -9,7 +9,7 @@ use xelis_common::{
 };
 use crate::core::{
     error::BlockchainError,
-    storage::SledStorage,
+    storage::kv::{KVBackend, KVStorage},
 };
 
 #[async_trait]
@@ -44,7 +44,7 @@ pub trait AssetProvider {
 }
 
 #[async_trait]
-impl AssetProvider for SledStorage {
+impl<B: KVBackend> AssetProvider for KVStorage<B> {
     async fn has_asset(&self, asset: &Hash) -> Result<bool, BlockchainError> {
         trace!("asset exist {}", asset);
         self.contains_data(&self.assets, &self.assets_cache, asset).await
@@ -59,7 +59,7 @@ impl AssetProvider for SledStorage {
     async fn get_assets(&self) -> Result<Vec<Hash>, BlockchainError> {
         trace!("get assets");
 
-        self.assets.iter().keys().map(|res| {
+        self.assets.keys().map(|res| {
             let key = res?;
             Ok(Hash::new(key[0..HASH_SIZE].try_into()?))
         }).collect()
@@ -90,7 +90,7 @@ impl AssetProvider for SledStorage {
 
     async fn get_chunked_assets(&self, maximum: usize, skip: usize) -> Result<IndexSet<Hash>, BlockchainError> {
         let mut assets = IndexSet::with_capacity(maximum);
-        for el in self.assets.iter().keys().skip(skip).take(maximum) {
+        for el in self.assets.keys().skip(skip).take(maximum) {
             let key = el?;
             let asset = Hash::from_bytes(&key)?;
             assets.insert(asset);
@@ -100,7 +100,7 @@ impl AssetProvider for SledStorage {
 
     // Returns all assets that the key has
     async fn get_assets_for(&self, key: &PublicKey) -> Result<Vec<Hash>, BlockchainError> {
-        self.balances.scan_prefix(key.as_bytes()).keys().map(|res| {
+        self.balances.scan_prefix_keys(key.as_bytes()).map(|res| {
             let key = res?;
             // Keys are stored like this: [public key (32 bytes)][asset hash (32 bytes)]
             // See Self::get_balance_key_for
@@ -127,4 +127,4 @@ impl AssetProvider for SledStorage {
         }
         Ok(())
     }
-}
\ No newline at end of file
+}
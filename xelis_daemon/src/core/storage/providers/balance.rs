@@ -9,7 +9,7 @@ use xelis_common::{
     serializer::Serializer
 };
 
-use crate::core::{error::BlockchainError, storage::SledStorage};
+use crate::core::{error::BlockchainError, storage::kv::{KVBackend, KVStorage}};
 use super::AssetProvider;
 
 #[async_trait]
@@ -64,11 +64,11 @@ pub trait BalanceProvider: AssetProvider {
     fn delete_last_topoheight_for_balance(&mut self, key: &PublicKey, asset: &Hash) -> Result<(), BlockchainError>;
 }
 
-impl SledStorage {
+impl<B: KVBackend> KVStorage<B> {
     // Generate a key including the key and its asset
     // It is used to store/retrieve the highest topoheight version available
     pub fn get_balance_key_for(&self, key: &PublicKey, asset: &Hash) -> [u8; 64] {
-        trace!("get balance {} key for {}", asset, key.as_address(self.is_mainnet()));
+        trace!("get balance {} key for {}", asset, key.as_address(self.network()));
         let mut bytes = [0; 64];
         bytes[0..32].copy_from_slice(key.as_bytes());
         bytes[32..64].copy_from_slice(asset.as_bytes());
@@ -77,7 +77,7 @@ impl SledStorage {
 
     // Versioned key is a 72 bytes key with topoheight, key, assets bytes
     pub fn get_versioned_balance_key(&self, key: &PublicKey, asset: &Hash, topoheight: u64) -> [u8; 72] {
-        trace!("get versioned balance {} key at {} for {}", asset, topoheight, key.as_address(self.is_mainnet()));
+        trace!("get versioned balance {} key at {} for {}", asset, topoheight, key.as_address(self.network()));
         let mut bytes = [0; 72];
         bytes[0..8].copy_from_slice(&topoheight.to_be_bytes());
         bytes[8..40].copy_from_slice(key.as_bytes());
@@ -88,16 +88,16 @@ impl SledStorage {
 
     async fn has_balance_internal(&self, key: &[u8; 64]) -> Result<bool, BlockchainError> {
         trace!("has balance internal");
-        Ok(self.balances.contains_key(key)?)
+        self.balances.contains_key(key)
     }
 
 }
 
 #[async_trait]
-impl BalanceProvider for SledStorage {
+impl<B: KVBackend> BalanceProvider for KVStorage<B> {
     // Check if a balance exists for asset and key
     async fn has_balance_for(&self, key: &PublicKey, asset: &Hash) -> Result<bool, BlockchainError> {
-        trace!("has balance {} for {}", asset, key.as_address(self.is_mainnet()));
+        trace!("has balance {} for {}", asset, key.as_address(self.network()));
         if !self.has_asset(asset).await? {
             return Err(BlockchainError::AssetNotFound(asset.clone()))
         }
@@ -107,7 +107,7 @@ impl BalanceProvider for SledStorage {
 
     // returns the highest topoheight where a balance changes happened
     async fn get_last_topoheight_for_balance(&self, key: &PublicKey, asset: &Hash) -> Result<u64, BlockchainError> {
-        trace!("get last topoheight for balance {} for {}", asset, key.as_address(self.is_mainnet()));
+        trace!("get last topoheight for balance {} for {}", asset, key.as_address(self.network()));
         let key = self.get_balance_key_for(key, asset);
         if !self.has_balance_internal(&key).await? {
             return Ok(0)
@@ -118,7 +118,7 @@ impl BalanceProvider for SledStorage {
 
     // set in storage the new top topoheight (the most up-to-date versioned balance)
     fn set_last_topoheight_for_balance(&mut self, key: &PublicKey, asset: &Hash, topoheight: u64) -> Result<(), BlockchainError> {
-        trace!("set last topoheight to {} for balance {} for {}", topoheight, asset, key.as_address(self.is_mainnet()));
+        trace!("set last topoheight to {} for balance {} for {}", topoheight, asset, key.as_address(self.network()));
         let key = self.get_balance_key_for(key, asset);
         self.balances.insert(&key, &topoheight.to_be_bytes())?;
         Ok(())
@@ -127,7 +127,7 @@ impl BalanceProvider for SledStorage {
     // get the balance at a specific topoheight
     // if there is no balance change at this topoheight just return an error
     async fn has_balance_at_exact_topoheight(&self, key: &PublicKey, asset: &Hash, topoheight: u64) -> Result<bool, BlockchainError> {
-        trace!("has balance {} for {} at exact topoheight {}", asset, key.as_address(self.is_mainnet()), topoheight);
+        trace!("has balance {} for {} at exact topoheight {}", asset, key.as_address(self.network()), topoheight);
         // check first that this address has balance, if no returns
         if !self.has_balance_for(key, asset).await? {
             return Ok(false)
@@ -140,22 +140,22 @@ impl BalanceProvider for SledStorage {
     // get the balance at a specific topoheight
     // if there is no balance change at this topoheight just return an error
     async fn get_balance_at_exact_topoheight(&self, key: &PublicKey, asset: &Hash, topoheight: u64) -> Result<VersionedBalance, BlockchainError> {
-        trace!("get balance {} for {} at exact topoheight {}", asset, key.as_address(self.is_mainnet()), topoheight);
+        trace!("get balance {} for {} at exact topoheight {}", asset, key.as_address(self.network()), topoheight);
         // check first that this address has balance, if no returns
         if !self.has_balance_at_exact_topoheight(key, asset, topoheight).await? {
-            trace!("No balance {} found for {} at exact topoheight {}", asset, key.as_address(self.is_mainnet()), topoheight);
-            return Err(BlockchainError::NoBalanceChanges(key.as_address(self.is_mainnet()), topoheight, asset.clone()))
+            trace!("No balance {} found for {} at exact topoheight {}", asset, key.as_address(self.network()), topoheight);
+            return Err(BlockchainError::NoBalanceChanges(key.as_address(self.network()), topoheight, asset.clone()))
         }
 
         let disk_key = self.get_versioned_balance_key(key, asset, topoheight);
-        self.get_cacheable_data(&self.versioned_balances, &None, &disk_key).await.map_err(|_| BlockchainError::NoBalanceChanges(key.as_address(self.is_mainnet()), topoheight, asset.clone()))
+        self.get_cacheable_data(&self.versioned_balances, &None, &disk_key).await.map_err(|_| BlockchainError::NoBalanceChanges(key.as_address(self.network()), topoheight, asset.clone()))
     }
 
     // delete the last topoheight registered for this key
     // it can happens when rewinding chain and we don't have any changes (no transaction in/out) for this key
     // because all versioned balances got deleted
     fn delete_last_topoheight_for_balance(&mut self, key: &PublicKey, asset: &Hash) -> Result<(), BlockchainError> {
-        trace!("delete last topoheight balance {} for {}", asset, key.as_address(self.is_mainnet()));
+        trace!("delete last topoheight balance {} for {}", asset, key.as_address(self.network()));
         let key = self.get_balance_key_for(key, asset);
         self.balances.remove(&key)?;
         Ok(())
@@ -166,10 +166,10 @@ impl BalanceProvider for SledStorage {
     // returns None if the key has no balances for this asset
     // Maximum topoheight is inclusive
     async fn get_balance_at_maximum_topoheight(&self, key: &PublicKey, asset: &Hash, topoheight: u64) -> Result<Option<(u64, VersionedBalance)>, BlockchainError> {
-        trace!("get balance {} for {} at maximum topoheight {}", asset, key.as_address(self.is_mainnet()), topoheight);
+        trace!("get balance {} for {} at maximum topoheight {}", asset, key.as_address(self.network()), topoheight);
         // check first that this address has balance for this asset, if no returns None
         if !self.has_balance_for(key, asset).await? {
-            trace!("No balance {} found for {} at maximum topoheight {}", asset, key.as_address(self.is_mainnet()), topoheight);
+            trace!("No balance {} found for {} at maximum topoheight {}", asset, key.as_address(self.network()), topoheight);
             return Ok(None)
         }
 
@@ -180,7 +180,7 @@ impl BalanceProvider for SledStorage {
         }
 
         let (topo, mut version) = self.get_last_balance(key, asset).await?;
-        trace!("Last version balance {} for {} is at topoheight {}", asset, key.as_address(self.is_mainnet()), topo);
+        trace!("Last version balance {} for {} is at topoheight {}", asset, key.as_address(self.network()), topo);
         // if it's the latest and its under the maximum topoheight
         if topo <= topoheight {
             trace!("Last version balance (valid) found at {} (maximum topoheight = {})", topo, topoheight);
@@ -210,16 +210,16 @@ impl BalanceProvider for SledStorage {
 
     // delete versioned balances for this topoheight
     async fn delete_balance_at_topoheight(&mut self, key: &PublicKey, asset: &Hash, topoheight: u64) -> Result<VersionedBalance, BlockchainError> {
-        trace!("delete balance {} for {} at topoheight {}", asset, key.as_address(self.is_mainnet()), topoheight);
+        trace!("delete balance {} for {} at topoheight {}", asset, key.as_address(self.network()), topoheight);
         let disk_key = self.get_versioned_balance_key(key, asset, topoheight);
-        self.delete_cacheable_data(&self.versioned_balances, &None, &disk_key).await.map_err(|_| BlockchainError::NoBalanceChanges(key.as_address(self.is_mainnet()), topoheight, asset.clone()))
+        self.delete_cacheable_data(&self.versioned_balances, &None, &disk_key).await.map_err(|_| BlockchainError::NoBalanceChanges(key.as_address(self.network()), topoheight, asset.clone()))
     }
 
     // returns a new versioned balance with already-set previous topoheight
     // Topoheight is the new topoheight for the versioned balance,
     // We create a new versioned balance by taking the previous version and setting it as previous topoheight
     async fn get_new_versioned_balance(&self, key: &PublicKey, asset: &Hash, topoheight: u64) -> Result<VersionedBalance, BlockchainError> {
-        trace!("get new versioned balance {} for {} at {}", asset, key.as_address(self.is_mainnet()), topoheight);
+        trace!("get new versioned balance {} for {} at {}", asset, key.as_address(self.network()), topoheight);
 
         let version = match self.get_balance_at_maximum_topoheight(key, asset, topoheight).await? {
             Some((topo, mut version)) => {
@@ -236,7 +236,7 @@ impl BalanceProvider for SledStorage {
     }
 
     async fn get_output_balance_at_maximum_topoheight(&self, key: &PublicKey, asset: &Hash, topoheight: u64) -> Result<Option<(u64, VersionedBalance)>, BlockchainError> {
-        trace!("get output balance {} for {} at maximum topoheight {}", asset, key.as_address(self.is_mainnet()), topoheight);
+        trace!("get output balance {} for {} at maximum topoheight {}", asset, key.as_address(self.network()), topoheight);
         if let Some((topo, version)) = self.get_balance_at_maximum_topoheight(key, asset, topoheight).await? {
             if version.contains_output() {
                 return Ok(Some((topo, version)))
@@ -259,7 +259,7 @@ impl BalanceProvider for SledStorage {
 
     // save a new versioned balance in storage and update the pointer
     async fn set_last_balance_to(&mut self, key: &PublicKey, asset: &Hash, topoheight: u64, version: &VersionedBalance) -> Result<(), BlockchainError> {
-        trace!("set balance {} for {} to topoheight {}", asset, key.as_address(self.is_mainnet()), topoheight);
+        trace!("set balance {} for {} to topoheight {}", asset, key.as_address(self.network()), topoheight);
         self.set_balance_at_topoheight(asset, topoheight, key, &version).await?;
         self.set_last_topoheight_for_balance(key, asset, topoheight)?;
         Ok(())
@@ -267,10 +267,10 @@ impl BalanceProvider for SledStorage {
 
     // get the last version of balance and returns topoheight
     async fn get_last_balance(&self, key: &PublicKey, asset: &Hash) -> Result<(u64, VersionedBalance), BlockchainError> {
-        trace!("get last balance {} for {}", asset, key.as_address(self.is_mainnet()));
+        trace!("get last balance {} for {}", asset, key.as_address(self.network()));
         if !self.has_balance_for(key, asset).await? {
-            trace!("No balance {} found for {}", asset, key.as_address(self.is_mainnet()));
-            return Err(BlockchainError::NoBalance(key.as_address(self.is_mainnet())))
+            trace!("No balance {} found for {}", asset, key.as_address(self.network()));
+            return Err(BlockchainError::NoBalance(key.as_address(self.network())))
         }
 
         let topoheight = self.get_cacheable_data(&self.balances, &None, &self.get_balance_key_for(key, asset)).await?;
@@ -295,9 +295,9 @@ impl BalanceProvider for SledStorage {
 
     // save the asset balance at specific topoheight
     async fn set_balance_at_topoheight(&mut self, asset: &Hash, topoheight: u64, key: &PublicKey, balance: &VersionedBalance) -> Result<(), BlockchainError> {
-        trace!("set balance {} at topoheight {} for {}", asset, topoheight, key.as_address(self.is_mainnet()));
+        trace!("set balance {} at topoheight {} for {}", asset, topoheight, key.as_address(self.network()));
         let key = self.get_versioned_balance_key(key, asset, topoheight);
         self.versioned_balances.insert(key, balance.to_bytes())?;
         Ok(())
     }
-}
\ No newline at end of file
+}
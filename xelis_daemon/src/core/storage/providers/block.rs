@@ -11,7 +11,7 @@ use xelis_common::{
     transaction::Transaction,
     varuint::VarUint
 };
-use crate::core::{error::BlockchainError, storage::{sled::BLOCKS_COUNT, SledStorage}};
+use crate::core::{error::BlockchainError, storage::kv::{KVBackend, KVStorage, BLOCKS_COUNT}};
 use super::{BlocksAtHeightProvider, DifficultyProvider, TransactionProvider};
 
 #[async_trait]
@@ -32,7 +32,7 @@ pub trait BlockProvider: TransactionProvider + DifficultyProvider + BlocksAtHeig
     async fn save_block(&mut self, block: Arc<BlockHeader>, txs: &Vec<Immutable<Transaction>>, difficulty: Difficulty, p: VarUint, hash: Hash) -> Result<(), BlockchainError>;
 }
 
-impl SledStorage {
+impl<B: KVBackend> KVStorage<B> {
     // Update the blocks count and store it on disk
     fn store_blocks_count(&self, count: u64) -> Result<(), BlockchainError> {
         self.blocks_count.store(count, Ordering::SeqCst);
@@ -42,7 +42,7 @@ impl SledStorage {
 }
 
 #[async_trait]
-impl BlockProvider for SledStorage {
+impl<B: KVBackend> BlockProvider for KVStorage<B> {
     async fn has_blocks(&self) -> bool {
         trace!("has blocks");
         !self.blocks.is_empty()
@@ -76,7 +76,7 @@ impl BlockProvider for SledStorage {
         }
 
         // Store block header and increase blocks count if it's a new block
-        if self.blocks.insert(hash.as_bytes(), block.to_bytes())?.is_none() {
+        if self.blocks.replace(hash.as_bytes(), block.to_bytes())?.is_none() {
             self.store_blocks_count(self.count_blocks().await? + 1)?;
         }
 
@@ -107,4 +107,4 @@ impl BlockProvider for SledStorage {
         let block = Block::new(Immutable::Arc(block), transactions);
         Ok(block)
     }
-}
\ No newline at end of file
+}
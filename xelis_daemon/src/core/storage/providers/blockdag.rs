@@ -7,7 +7,7 @@ use xelis_common::{
     crypto::Hash
 };
 
-use crate::core::{error::BlockchainError, storage::SledStorage};
+use crate::core::{error::BlockchainError, storage::kv::{KVBackend, KVStorage}};
 
 use super::{BlockProvider, DagOrderProvider, DifficultyProvider};
 
@@ -30,7 +30,7 @@ pub trait BlockDagProvider: DagOrderProvider + BlockProvider {
 }
 
 #[async_trait]
-impl BlockDagProvider for SledStorage {
+impl<B: KVBackend> BlockDagProvider for KVStorage<B> {
     async fn get_block_header_at_topoheight(&self, topoheight: u64) -> Result<(Hash, Arc<BlockHeader>), BlockchainError> {
         trace!("get block at topoheight: {}", topoheight);
         let hash = self.get_hash_at_topo_height(topoheight).await?;
@@ -40,7 +40,7 @@ impl BlockDagProvider for SledStorage {
 
     fn get_block_reward_at_topo_height(&self, topoheight: u64) -> Result<u64, BlockchainError> {
         trace!("get block reward at topo height {}", topoheight);
-        Ok(self.load_from_disk(&self.rewards, &topoheight.to_be_bytes())?)
+        self.load_from_disk(&self.rewards, &topoheight.to_be_bytes())
     }
 
     async fn get_supply_at_topo_height(&self, topoheight: u64) -> Result<u64, BlockchainError> {
@@ -59,4 +59,4 @@ impl BlockDagProvider for SledStorage {
         self.supply.insert(topoheight.to_be_bytes(), &supply.to_be_bytes())?;
         Ok(())
     }
-}
\ No newline at end of file
+}
@@ -6,7 +6,7 @@ use xelis_common::{
 };
 use crate::core::{
     error::BlockchainError,
-    storage::{SledStorage, Tips},
+    storage::{kv::{KVBackend, KVStorage}, Tips},
 };
 
 #[async_trait]
@@ -28,10 +28,10 @@ pub trait BlocksAtHeightProvider {
 }
 
 #[async_trait]
-impl BlocksAtHeightProvider for SledStorage {
+impl<B: KVBackend> BlocksAtHeightProvider for KVStorage<B> {
     async fn has_blocks_at_height(&self, height: u64) -> Result<bool, BlockchainError> {
-        trace!("get blocks at height {}", height);
-        Ok(self.blocks_at_height.contains_key(&height.to_be_bytes())?)
+        trace!("has blocks at height {}", height);
+        self.blocks_at_height.contains_key(&height.to_be_bytes())
     }
 
     async fn get_blocks_at_height(&self, height: u64) -> Result<Tips, BlockchainError> {
@@ -74,4 +74,4 @@ impl BlocksAtHeightProvider for SledStorage {
 
         Ok(())
     }
-}
\ No newline at end of file
+}
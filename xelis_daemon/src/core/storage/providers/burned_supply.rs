@@ -0,0 +1,155 @@
+use async_trait::async_trait;
+use log::trace;
+use xelis_common::{
+    crypto::{Hash, HASH_SIZE},
+    serializer::Serializer
+};
+use crate::core::{
+    error::BlockchainError,
+    storage::kv::{KVBackend, KVStorage, BURNED_SUPPLY_INDEXED_FROM},
+};
+
+// Burned supply is stored for each asset at the topoheights where some of it was burned
+// Each entry is the total burned until this topoheight (inclusive) so it can be reverted during a reorg
+#[async_trait]
+pub trait BurnedSupplyProvider {
+    // Get the total burned of an asset under or equal topoheight requested
+    async fn get_burned_supply_at_maximum_topoheight(&self, asset: &Hash, topoheight: u64) -> Result<u64, BlockchainError>;
+
+    // Add an amount burned in the block at topoheight
+    async fn add_burned_supply_at_topoheight(&mut self, asset: &Hash, topoheight: u64, amount: u64) -> Result<(), BlockchainError>;
+
+    // Delete all burned supplies changed at topoheight
+    async fn delete_burned_supply_at_topoheight(&mut self, topoheight: u64) -> Result<(), BlockchainError>;
+
+    // Get the topoheight from which all the burns are included in the burned supply
+    // None if the burned supply is not indexed by asset yet
+    // Above 0 when the burns below were pruned or not synced, so the totals are incomplete
+    fn get_burned_supply_indexed_from(&self) -> Result<Option<u64>, BlockchainError>;
+
+    // Mark the burned supply as indexed with all the burns from this topoheight
+    fn set_burned_supply_indexed_from(&mut self, topoheight: u64) -> Result<(), BlockchainError>;
+
+    // Delete all the burned supplies stored
+    fn clear_burned_supply(&mut self) -> Result<(), BlockchainError>;
+}
+
+// Key is a 40 bytes key with the asset as first bytes and the topoheight as last bytes
+// So all the entries of an asset are sorted by topoheight
+fn get_burned_supply_key(asset: &Hash, topoheight: u64) -> [u8; 40] {
+    let mut bytes = [0; 40];
+    bytes[0..HASH_SIZE].copy_from_slice(asset.as_bytes());
+    bytes[HASH_SIZE..40].copy_from_slice(&topoheight.to_be_bytes());
+
+    bytes
+}
+
+impl<B: KVBackend> KVStorage<B> {
+    // Get the first asset with a burned supply stored after the one given
+    // It seeks over all the entries of the previous asset
+    fn get_next_burned_asset(&self, previous: Option<&Hash>) -> Result<Option<Hash>, BlockchainError> {
+        let entry = match previous {
+            Some(asset) => {
+                let last_key = get_burned_supply_key(asset, u64::MAX);
+                // The seek is inclusive, skip the last entry of the previous asset if it exists
+                self.burned_supply.iter_from(&last_key)
+                    .find(|res| !matches!(res, Ok((key, _)) if key.starts_with(asset.as_bytes())))
+            },
+            None => self.burned_supply.iter().next()
+        };
+
+        match entry {
+            Some(el) => {
+                let (key, _) = el?;
+                Ok(Some(Hash::from_bytes(&key[0..HASH_SIZE])?))
+            },
+            None => Ok(None)
+        }
+    }
+
+    // Delete all burned supplies changed above topoheight
+    pub(crate) fn delete_burned_supply_above_topoheight(&self, topoheight: u64) -> Result<(), BlockchainError> {
+        trace!("delete burned supply above topoheight {}", topoheight);
+        let mut next = self.get_next_burned_asset(None)?;
+        while let Some(asset) = next {
+            let start = get_burned_supply_key(&asset, topoheight);
+            for el in self.burned_supply.iter_from(&start) {
+                let (key, _) = el?;
+                if !key.starts_with(asset.as_bytes()) {
+                    break;
+                }
+
+                if *key != start {
+                    self.burned_supply.remove(&key)?;
+                }
+            }
+
+            next = self.get_next_burned_asset(Some(&asset))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<B: KVBackend> BurnedSupplyProvider for KVStorage<B> {
+    async fn get_burned_supply_at_maximum_topoheight(&self, asset: &Hash, topoheight: u64) -> Result<u64, BlockchainError> {
+        trace!("get burned supply of {} at maximum topoheight {}", asset, topoheight);
+        let max_key = get_burned_supply_key(asset, topoheight);
+        // Last entry of the asset under or equal to the topoheight
+        match self.burned_supply.iter_rev_from(&max_key).next() {
+            Some(el) => {
+                let (key, value) = el?;
+                if key.starts_with(asset.as_bytes()) {
+                    Ok(u64::from_bytes(&value)?)
+                } else {
+                    Ok(0)
+                }
+            },
+            None => Ok(0)
+        }
+    }
+
+    async fn add_burned_supply_at_topoheight(&mut self, asset: &Hash, topoheight: u64, amount: u64) -> Result<(), BlockchainError> {
+        trace!("add burned supply {} of {} at topoheight {}", amount, asset, topoheight);
+        let burned = self.get_burned_supply_at_maximum_topoheight(asset, topoheight).await?;
+        let key = get_burned_supply_key(asset, topoheight);
+        self.burned_supply.insert(&key, &(burned + amount).to_be_bytes())?;
+        Ok(())
+    }
+
+    async fn delete_burned_supply_at_topoheight(&mut self, topoheight: u64) -> Result<(), BlockchainError> {
+        trace!("delete burned supply at topoheight {}", topoheight);
+        let mut next = self.get_next_burned_asset(None)?;
+        while let Some(asset) = next {
+            let key = get_burned_supply_key(&asset, topoheight);
+            if self.burned_supply.contains_key(&key)? {
+                self.burned_supply.remove(&key)?;
+            }
+
+            next = self.get_next_burned_asset(Some(&asset))?;
+        }
+
+        Ok(())
+    }
+
+    fn get_burned_supply_indexed_from(&self) -> Result<Option<u64>, BlockchainError> {
+        trace!("get burned supply indexed from");
+        self.load_optional_from_disk(&self.extra, BURNED_SUPPLY_INDEXED_FROM)
+    }
+
+    fn set_burned_supply_indexed_from(&mut self, topoheight: u64) -> Result<(), BlockchainError> {
+        trace!("set burned supply indexed from {}", topoheight);
+        self.extra.insert(BURNED_SUPPLY_INDEXED_FROM, topoheight.to_bytes())
+    }
+
+    fn clear_burned_supply(&mut self) -> Result<(), BlockchainError> {
+        trace!("clear burned supply");
+        for el in self.burned_supply.keys() {
+            let key = el?;
+            self.burned_supply.remove(&key)?;
+        }
+
+        Ok(())
+    }
+}
@@ -11,7 +11,7 @@ use xelis_common::{
 use crate::core::{
     error::BlockchainError,
     storage::{
-        SledStorage,
+        kv::{KVBackend, KVStorage},
         Tips
     }
 };
@@ -53,7 +53,7 @@ pub trait ClientProtocolProvider {
 }
 
 #[async_trait]
-impl ClientProtocolProvider for SledStorage {
+impl<B: KVBackend> ClientProtocolProvider for KVStorage<B> {
     fn get_block_executor_for_tx(&self, tx: &Hash) -> Result<Hash, BlockchainError> {
         trace!("get block executer for tx {}", tx);
         self.load_from_disk(&self.txs_executed, tx.as_bytes())
@@ -73,7 +73,7 @@ impl ClientProtocolProvider for SledStorage {
 
     fn is_tx_executed_in_a_block(&self, tx: &Hash) -> Result<bool, BlockchainError> {
         trace!("is tx {} executed in a block", tx);
-        Ok(self.txs_executed.contains_key(tx.as_bytes())?)
+        self.txs_executed.contains_key(tx.as_bytes())
     }
 
     fn is_tx_executed_in_block(&self, tx: &Hash, block: &Hash) -> Result<bool, BlockchainError> {
@@ -88,8 +88,7 @@ impl ClientProtocolProvider for SledStorage {
 
     fn has_tx_blocks(&self, hash: &Hash) -> Result<bool, BlockchainError> {
         trace!("has tx blocks {}", hash);
-        let contains = self.tx_blocks.contains_key(hash.as_bytes())?;
-        Ok(contains)
+        self.tx_blocks.contains_key(hash.as_bytes())
     }
 
     fn has_block_linked_to_tx(&self, tx: &Hash, block: &Hash) -> Result<bool, BlockchainError> {
@@ -139,4 +138,4 @@ impl ClientProtocolProvider for SledStorage {
         self.tx_blocks.insert(tx.as_bytes(), blocks.to_bytes())?;
         Ok(())
     }
-}
\ No newline at end of file
+}
@@ -6,7 +6,7 @@ use xelis_common::{
 };
 use crate::core::{
     error::BlockchainError,
-    storage::SledStorage,
+    storage::kv::{KVBackend, KVStorage},
 };
 
 // This trait is used for find_tip_work_score to provide topoheight of each blocks
@@ -19,7 +19,7 @@ pub trait DagOrderProvider {
 }
 
 #[async_trait]
-impl DagOrderProvider for SledStorage {
+impl<B: KVBackend> DagOrderProvider for KVStorage<B> {
     async fn set_topo_height_for_block(&mut self, hash: &Hash, topoheight: u64) -> Result<(), BlockchainError> {
         trace!("set topo height for {} at {}", hash, topoheight);
         self.topo_by_hash.insert(hash.as_bytes(), topoheight.to_bytes())?;
@@ -80,4 +80,4 @@ impl DagOrderProvider for SledStorage {
 
         Ok(hash)
     }
-}
\ No newline at end of file
+}
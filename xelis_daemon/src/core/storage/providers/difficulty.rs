@@ -16,7 +16,7 @@ use xelis_common::{
 };
 use crate::core::{
     error::BlockchainError,
-    storage::SledStorage,
+    storage::kv::{KVBackend, KVStorage},
 };
 
 // this trait is useful for P2p to check itself the validty of a chain
@@ -51,7 +51,7 @@ pub trait DifficultyProvider {
 }
 
 #[async_trait]
-impl DifficultyProvider for SledStorage {
+impl<B: KVBackend> DifficultyProvider for KVStorage<B> {
     // TODO optimize all these functions to read only what is necessary
     async fn get_height_for_block_hash(&self, hash: &Hash) -> Result<u64, BlockchainError> {
         trace!("get height for block hash {}", hash);
@@ -117,4 +117,4 @@ impl DifficultyProvider for SledStorage {
         self.difficulty_covariance.insert(hash.as_bytes(), p.to_bytes())?;
         Ok(())
     }
-}
\ No newline at end of file
+}
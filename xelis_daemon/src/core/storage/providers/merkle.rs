@@ -1,7 +1,7 @@
 use async_trait::async_trait;
 use log::trace;
 use xelis_common::{crypto::Hash, serializer::Serializer};
-use crate::core::{error::BlockchainError, storage::SledStorage};
+use crate::core::{error::BlockchainError, storage::kv::{KVBackend, KVStorage}};
 
 // Merkle Hash provider allow to give a Hash at a specific topoheight
 // The merkle hash only contains account balances
@@ -18,7 +18,7 @@ pub trait MerkleHashProvider {
 }
 
 #[async_trait]
-impl MerkleHashProvider for SledStorage {
+impl<B: KVBackend> MerkleHashProvider for KVStorage<B> {
     async fn get_balances_merkle_hash_at_topoheight(&self, topoheight: u64) -> Result<Hash, BlockchainError> {
         trace!("get merkle hash at topoheight {}", topoheight);
         self.load_from_disk(&self.merkle_hashes, &topoheight.to_bytes())
@@ -29,4 +29,4 @@ impl MerkleHashProvider for SledStorage {
         self.merkle_hashes.insert(&topoheight.to_bytes(), merkle_proof.as_bytes())?;
         Ok(())
     }
-}
\ No newline at end of file
+}
@@ -11,6 +11,9 @@ mod block;
 mod blockdag;
 mod merkle;
 mod account;
+mod multisig;
+mod snapshot;
+mod burned_supply;
 
 pub use asset::AssetProvider;
 pub use blocks_at_height::BlocksAtHeightProvider;
@@ -24,4 +27,7 @@ pub use transaction::TransactionProvider;
 pub use block::BlockProvider;
 pub use blockdag::BlockDagProvider;
 pub use merkle::MerkleHashProvider;
-pub use account::AccountProvider;
\ No newline at end of file
+pub use account::AccountProvider;
+pub use multisig::MultiSigProvider;
+pub use snapshot::SnapshotProvider;
+pub use burned_supply::BurnedSupplyProvider;
\ No newline at end of file
@@ -0,0 +1,127 @@
+use async_trait::async_trait;
+use log::{error, trace};
+use xelis_common::{
+    account::VersionedMultiSig,
+    crypto::PublicKey,
+    serializer::Serializer
+};
+use crate::core::{
+    error::BlockchainError,
+    storage::kv::{KVBackend, KVStorage},
+};
+
+// Multisig setups are versioned by topoheight like the nonces
+// so they can be reverted during a reorg
+#[async_trait]
+pub trait MultiSigProvider {
+    // Check if the account has a multisig setup version
+    async fn has_multisig(&self, key: &PublicKey) -> Result<bool, BlockchainError>;
+
+    // Get the last topoheight at which the multisig setup of the account changed
+    async fn get_last_topoheight_for_multisig(&self, key: &PublicKey) -> Result<u64, BlockchainError>;
+
+    // Get the multisig setup at a specific topoheight
+    async fn get_multisig_at_exact_topoheight(&self, key: &PublicKey, topoheight: u64) -> Result<VersionedMultiSig, BlockchainError>;
+
+    // Get the multisig setup under or equal topoheight requested for an account
+    async fn get_multisig_at_maximum_topoheight(&self, key: &PublicKey, topoheight: u64) -> Result<Option<(u64, VersionedMultiSig)>, BlockchainError>;
+
+    // Set the multisig setup at topoheight and update the pointer to it
+    async fn set_last_multisig_to(&mut self, key: &PublicKey, topoheight: u64, version: &VersionedMultiSig) -> Result<(), BlockchainError>;
+
+    // Delete all multisig setups changed at topoheight and restore the previous pointers
+    async fn delete_versioned_multisigs_at_topoheight(&mut self, topoheight: u64) -> Result<(), BlockchainError>;
+}
+
+impl<B: KVBackend> KVStorage<B> {
+    // Versioned key is a 40 bytes key with topoheight as first bytes and the key as last bytes
+    pub fn get_versioned_multisig_key(&self, key: &PublicKey, topoheight: u64) -> [u8; 40] {
+        let mut bytes = [0; 40];
+        bytes[0..8].copy_from_slice(&topoheight.to_be_bytes());
+        bytes[8..40].copy_from_slice(key.as_bytes());
+
+        bytes
+    }
+}
+
+#[async_trait]
+impl<B: KVBackend> MultiSigProvider for KVStorage<B> {
+    async fn has_multisig(&self, key: &PublicKey) -> Result<bool, BlockchainError> {
+        trace!("has multisig {}", key.as_address(self.network()));
+        self.multisig.contains_key(key.as_bytes())
+    }
+
+    async fn get_last_topoheight_for_multisig(&self, key: &PublicKey) -> Result<u64, BlockchainError> {
+        trace!("get last topoheight for multisig {}", key.as_address(self.network()));
+        self.load_from_disk(&self.multisig, key.as_bytes())
+    }
+
+    async fn get_multisig_at_exact_topoheight(&self, key: &PublicKey, topoheight: u64) -> Result<VersionedMultiSig, BlockchainError> {
+        trace!("get multisig at topoheight {} for {}", topoheight, key.as_address(self.network()));
+        let key = self.get_versioned_multisig_key(key, topoheight);
+        self.load_from_disk(&self.versioned_multisig, &key)
+    }
+
+    // topoheight is inclusive bounds
+    async fn get_multisig_at_maximum_topoheight(&self, key: &PublicKey, topoheight: u64) -> Result<Option<(u64, VersionedMultiSig)>, BlockchainError> {
+        trace!("get multisig at maximum topoheight {} for {}", topoheight, key.as_address(self.network()));
+        if !self.has_multisig(key).await? {
+            return Ok(None)
+        }
+
+        let mut topo = self.get_last_topoheight_for_multisig(key).await?;
+        let mut version = self.get_multisig_at_exact_topoheight(key, topo).await?;
+        loop {
+            if topo <= topoheight {
+                return Ok(Some((topo, version)))
+            }
+
+            match version.get_previous_topoheight() {
+                Some(previous) => {
+                    if previous > topo {
+                        error!("FATAL ERROR: Previous topoheight ({}) should not be higher than current version ({})!", previous, topo);
+                        return Err(BlockchainError::Unknown)
+                    }
+                    topo = previous;
+                    version = self.get_multisig_at_exact_topoheight(key, previous).await?;
+                },
+                None => return Ok(None)
+            }
+        }
+    }
+
+    async fn set_last_multisig_to(&mut self, key: &PublicKey, topoheight: u64, version: &VersionedMultiSig) -> Result<(), BlockchainError> {
+        trace!("set last multisig {} for {} at topoheight {}", version, key.as_address(self.network()), topoheight);
+        let disk_key = self.get_versioned_multisig_key(key, topoheight);
+        self.versioned_multisig.insert(&disk_key, version.to_bytes())?;
+        self.multisig.insert(key.as_bytes(), &topoheight.to_be_bytes())?;
+        Ok(())
+    }
+
+    async fn delete_versioned_multisigs_at_topoheight(&mut self, topoheight: u64) -> Result<(), BlockchainError> {
+        trace!("delete versioned multisigs at topoheight {}", topoheight);
+        let prefix = topoheight.to_be_bytes();
+        // Collect them first as the pointers are read for each entry
+        let entries = self.versioned_multisig.scan_prefix(&prefix).collect::<Result<Vec<_>, _>>()?;
+        for (key, value) in entries {
+            self.versioned_multisig.remove(&key)?;
+
+            let key = PublicKey::from_bytes(&key[8..40])?;
+            if let Ok(last_topoheight) = self.get_last_topoheight_for_multisig(&key).await {
+                if last_topoheight >= topoheight {
+                    let version = VersionedMultiSig::from_bytes(&value)?;
+                    match version.get_previous_topoheight() {
+                        Some(previous_topoheight) => {
+                            self.multisig.insert(key.as_bytes(), &previous_topoheight.to_be_bytes())?;
+                        },
+                        None => {
+                            self.multisig.remove(key.as_bytes())?;
+                        }
+                    };
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
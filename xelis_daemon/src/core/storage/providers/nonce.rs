@@ -10,7 +10,7 @@ use xelis_common::{
 };
 use crate::core::{
     error::BlockchainError,
-    storage::{sled::ACCOUNTS_COUNT, SledStorage},
+    storage::kv::{KVBackend, KVStorage, ACCOUNTS_COUNT},
 };
 
 use super::{AssetProvider, BalanceProvider};
@@ -61,7 +61,7 @@ pub trait NonceProvider: BalanceProvider {
     async fn set_nonce_at_topoheight(&mut self, key: &PublicKey, topoheight: u64, version: &VersionedNonce) -> Result<(), BlockchainError>;
 }
 
-impl SledStorage {
+impl<B: KVBackend> KVStorage<B> {
     // Update the accounts count and store it on disk
     pub fn store_accounts_count(&mut self, count: u64) -> Result<(), BlockchainError> {
         self.accounts_count.store(count, Ordering::SeqCst);
@@ -71,7 +71,7 @@ impl SledStorage {
 
     // Versioned key is a 40 bytes key with topoheight as first bytes and the key as last bytes
     pub fn get_versioned_nonce_key(&self, key: &PublicKey, topoheight: u64) -> [u8; 40] {
-        trace!("get versioned balance key at {} for {}", topoheight, key.as_address(self.is_mainnet()));
+        trace!("get versioned nonce key at {} for {}", topoheight, key.as_address(self.network()));
         let mut bytes = [0; 40];
         bytes[0..8].copy_from_slice(&topoheight.to_be_bytes());
         bytes[8..40].copy_from_slice(key.as_bytes());
@@ -81,40 +81,39 @@ impl SledStorage {
 }
 
 #[async_trait]
-impl NonceProvider for SledStorage {
+impl<B: KVBackend> NonceProvider for KVStorage<B> {
     async fn count_accounts(&self) -> Result<u64, BlockchainError> {
         trace!("count accounts");
         Ok(self.accounts_count.load(Ordering::SeqCst))
     }
 
     async fn set_last_nonce_to(&mut self, key: &PublicKey, topoheight: u64, version: &VersionedNonce) -> Result<(), BlockchainError> {
-        trace!("set last nonce {} for {} at topoheight {}", version.get_nonce(), key.as_address(self.is_mainnet()), topoheight);
+        trace!("set last nonce {} for {} at topoheight {}", version.get_nonce(), key.as_address(self.network()), topoheight);
         self.set_nonce_at_topoheight(key, topoheight, version).await?;
         self.set_last_topoheight_for_nonce(key, topoheight).await?;
         Ok(())
     }
 
     async fn delete_last_topoheight_for_nonce(&mut self, key: &PublicKey) -> Result<(), BlockchainError> {
-        trace!("delete last topoheight for nonce {}", key.as_address(self.is_mainnet()));
-        if self.nonces.remove(key.as_bytes())?.is_some() {
+        trace!("delete last topoheight for nonce {}", key.as_address(self.network()));
+        if self.nonces.take(key.as_bytes())?.is_some() {
             self.store_accounts_count(self.count_accounts().await? - 1)?;
         }
         Ok(())
     }
 
     async fn get_last_topoheight_for_nonce(&self, key: &PublicKey) -> Result<u64, BlockchainError> {
-        trace!("get last topoheight for nonce {}", key.as_address(self.is_mainnet()));
+        trace!("get last topoheight for nonce {}", key.as_address(self.network()));
         self.load_from_disk(&self.nonces, key.as_bytes())
     }
 
     async fn has_nonce(&self, key: &PublicKey) -> Result<bool, BlockchainError> {
-        trace!("has nonce {}", key.as_address(self.is_mainnet()));
-        let contains = self.nonces.contains_key(key.as_bytes())?;
-        Ok(contains)
+        trace!("has nonce {}", key.as_address(self.network()));
+        self.nonces.contains_key(key.as_bytes())
     }
 
     async fn has_nonce_at_exact_topoheight(&self, key: &PublicKey, topoheight: u64) -> Result<bool, BlockchainError> {
-        trace!("has nonce {} at topoheight {}", key.as_address(self.is_mainnet()), topoheight);
+        trace!("has nonce {} at topoheight {}", key.as_address(self.network()), topoheight);
         let key = self.get_versioned_nonce_key(key, topoheight);
         self.contains_data::<_, ()>(&self.versioned_nonces, &None, &key).await
     }
@@ -125,8 +124,9 @@ impl NonceProvider for SledStorage {
 
         let mut keys: IndexSet<PublicKey> = IndexSet::new();
         let mut skip_count = 0;
-        for el in self.nonces.iter().keys() {
-            let key = el?;
+        // Collect them first as each key is checked asynchronously
+        let nonces = self.nonces.keys().collect::<Result<Vec<_>, _>>()?;
+        for key in nonces {
             let pkey = PublicKey::from_bytes(&key)?;
 
             // check that we have a nonce before the maximum topoheight
@@ -147,9 +147,9 @@ impl NonceProvider for SledStorage {
     }
 
     async fn get_last_nonce(&self, key: &PublicKey) -> Result<(u64, VersionedNonce), BlockchainError> {
-        trace!("get last nonce {}", key.as_address(self.is_mainnet()));
+        trace!("get last nonce {}", key.as_address(self.network()));
         if !self.has_nonce(key).await? {
-            return Err(BlockchainError::NoNonce(key.as_address(self.is_mainnet())))
+            return Err(BlockchainError::NoNonce(key.as_address(self.network())))
         }
 
         let topoheight = self.load_from_disk(&self.nonces, key.as_bytes())?;
@@ -157,7 +157,7 @@ impl NonceProvider for SledStorage {
     }
 
     async fn get_nonce_at_exact_topoheight(&self, key: &PublicKey, topoheight: u64) -> Result<VersionedNonce, BlockchainError> {
-        trace!("get nonce at topoheight {} for {}", topoheight, key.as_address(self.is_mainnet()));
+        trace!("get nonce at topoheight {} for {}", topoheight, key.as_address(self.network()));
 
         let key = self.get_versioned_nonce_key(key, topoheight);
         self.load_from_disk(&self.versioned_nonces, &key)
@@ -165,14 +165,14 @@ impl NonceProvider for SledStorage {
 
     // topoheight is inclusive bounds
     async fn get_nonce_at_maximum_topoheight(&self, key: &PublicKey, topoheight: u64) -> Result<Option<(u64, VersionedNonce)>, BlockchainError> {
-        trace!("get nonce at maximum topoheight {} for {}", topoheight, key.as_address(self.is_mainnet()));
+        trace!("get nonce at maximum topoheight {} for {}", topoheight, key.as_address(self.network()));
         // check first that this address has nonce, if no returns None
         if !self.has_nonce(key).await? {
             return Ok(None)
         }
 
         let (topo, mut version) = self.get_last_nonce(key).await?;
-        trace!("Last version of nonce for {} is at topoheight {}", key.as_address(self.is_mainnet()), topo);
+        trace!("Last version of nonce for {} is at topoheight {}", key.as_address(self.network()), topo);
         // if it's the latest and its under the maximum topoheight
         if topo <= topoheight {
             trace!("Last version nonce (valid) found at {} (maximum topoheight = {})", topo, topoheight);
@@ -201,7 +201,7 @@ impl NonceProvider for SledStorage {
     }
 
     async fn has_key_updated_in_range(&self, key: &PublicKey, minimum_topoheight: u64, maximum_topoheight: u64) -> Result<bool, BlockchainError> {
-        trace!("has key {} updated in range min topoheight {} and max topoheight {}", key.as_address(self.is_mainnet()), minimum_topoheight, maximum_topoheight);
+        trace!("has key {} updated in range min topoheight {} and max topoheight {}", key.as_address(self.network()), minimum_topoheight, maximum_topoheight);
         // check first that this address has nonce, if no returns None
         if !self.has_nonce(key).await? {
             return Ok(false)
@@ -209,7 +209,7 @@ impl NonceProvider for SledStorage {
 
         // fast path check the latest nonce
         let (topo, mut version) = self.get_last_nonce(key).await?;
-        trace!("Last version of nonce for {} is at topoheight {}", key.as_address(self.is_mainnet()), topo);
+        trace!("Last version of nonce for {} is at topoheight {}", key.as_address(self.network()), topo);
 
         // if it's the latest and its under the maximum topoheight and above minimum topoheight
         if topo >= minimum_topoheight && topo <= maximum_topoheight {
@@ -278,18 +278,18 @@ impl NonceProvider for SledStorage {
     }
 
     async fn set_nonce_at_topoheight(&mut self, key: &PublicKey, topoheight: u64, version: &VersionedNonce) -> Result<(), BlockchainError> {
-        trace!("set nonce to {} for {} at topo {}", version.get_nonce(), key.as_address(self.is_mainnet()), topoheight);
+        trace!("set nonce to {} for {} at topo {}", version.get_nonce(), key.as_address(self.network()), topoheight);
         let disk_key = self.get_versioned_nonce_key(key, topoheight);
         self.versioned_nonces.insert(&disk_key, version.to_bytes())?;
         Ok(())
     }
 
     async fn set_last_topoheight_for_nonce(&mut self, key: &PublicKey, topoheight: u64) -> Result<(), BlockchainError> {
-        trace!("set last topoheight for nonce {} to {}", key.as_address(self.is_mainnet()), topoheight);
-        if self.nonces.insert(&key.as_bytes(), &topoheight.to_be_bytes())?.is_none() {
+        trace!("set last topoheight for nonce {} to {}", key.as_address(self.network()), topoheight);
+        if self.nonces.replace(key.as_bytes(), &topoheight.to_be_bytes())?.is_none() {
             self.store_accounts_count(self.count_accounts().await? + 1)?;
         }
 
         Ok(())
     }
-}
\ No newline at end of file
+}
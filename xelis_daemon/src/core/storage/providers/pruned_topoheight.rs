@@ -1,7 +1,7 @@
 use async_trait::async_trait;
 use crate::core::{
     error::BlockchainError,
-    storage::{sled::PRUNED_TOPOHEIGHT, SledStorage},
+    storage::kv::{KVBackend, KVStorage, PRUNED_BODIES_TOPOHEIGHT, PRUNED_TOPOHEIGHT},
 };
 
 // This trait is used for pruning
@@ -12,10 +12,23 @@ pub trait PrunedTopoheightProvider {
 
     // set the pruned topoheight on disk
     async fn set_pruned_topoheight(&mut self, pruned_topoheight: u64) -> Result<(), BlockchainError>;
+
+    // get the topoheight under which only the block headers are kept
+    async fn get_pruned_bodies_topoheight(&self) -> Result<Option<u64>, BlockchainError>;
+
+    // set the pruned bodies topoheight on disk
+    async fn set_pruned_bodies_topoheight(&mut self, topoheight: u64) -> Result<(), BlockchainError>;
+
+    // get the pruned topoheight shared with peers, no full block can be served under it
+    async fn get_shared_pruned_topoheight(&self) -> Result<Option<u64>, BlockchainError> {
+        let pruned_topoheight = self.get_pruned_topoheight().await?;
+        let pruned_bodies_topoheight = self.get_pruned_bodies_topoheight().await?;
+        Ok(pruned_topoheight.max(pruned_bodies_topoheight))
+    }
 }
 
 #[async_trait]
-impl PrunedTopoheightProvider for SledStorage {
+impl<B: KVBackend> PrunedTopoheightProvider for KVStorage<B> {
     async fn set_pruned_topoheight(&mut self, pruned_topoheight: u64) -> Result<(), BlockchainError> {
         self.pruned_topoheight = Some(pruned_topoheight);
         self.extra.insert(PRUNED_TOPOHEIGHT, &pruned_topoheight.to_be_bytes())?;
@@ -25,4 +38,14 @@ impl PrunedTopoheightProvider for SledStorage {
     async fn get_pruned_topoheight(&self) -> Result<Option<u64>, BlockchainError> {
         Ok(self.pruned_topoheight)
     }
+
+    async fn set_pruned_bodies_topoheight(&mut self, topoheight: u64) -> Result<(), BlockchainError> {
+        self.pruned_bodies_topoheight = Some(topoheight);
+        self.extra.insert(PRUNED_BODIES_TOPOHEIGHT, &topoheight.to_be_bytes())?;
+        Ok(())
+    }
+
+    async fn get_pruned_bodies_topoheight(&self) -> Result<Option<u64>, BlockchainError> {
+        Ok(self.pruned_bodies_topoheight)
+    }
 }
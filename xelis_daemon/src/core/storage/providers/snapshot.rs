@@ -0,0 +1,143 @@
+use std::{fs, path::Path};
+use async_trait::async_trait;
+use log::{debug, trace, warn};
+use crate::core::{
+    error::BlockchainError,
+    snapshot::{SnapshotReader, SnapshotRecord, SnapshotWriter},
+    storage::{kv::{KVBackend, KVStorage, Tree}, Storage}
+};
+
+// Suffix of the directory where a snapshot is imported
+const IMPORT_DIR_SUFFIX: &str = ".import";
+// Suffix of the directory where the replaced storage is moved during the swap
+const BACKUP_DIR_SUFFIX: &str = ".backup";
+
+// This trait is used to export and import the whole storage
+#[async_trait]
+pub trait SnapshotProvider: Sized {
+    // Write every entry of the storage in the snapshot
+    async fn export_snapshot(&self, writer: &mut SnapshotWriter) -> Result<(), BlockchainError>;
+
+    // Import the snapshot in a new storage located next to this one
+    // The current storage is not modified
+    async fn import_snapshot(&self, reader: &mut SnapshotReader) -> Result<Self, BlockchainError>;
+
+    // Replace the current storage by an imported one
+    // Its directory is moved in place of ours
+    fn replace_with(&mut self, imported: Self) -> Result<(), BlockchainError>;
+
+    // Delete an imported storage that won't be used
+    fn discard(self) -> Result<(), BlockchainError>;
+}
+
+fn remove_dir_if_exists(path: &str) -> Result<(), BlockchainError> {
+    if Path::new(path).exists() {
+        fs::remove_dir_all(path)?;
+    }
+    Ok(())
+}
+
+// Move the imported directory in place of the live one
+// The live one is restored if the move fails, otherwise it's kept as backup until the swap is done
+fn swap_directories(live: &str, imported: &str) -> Result<String, BlockchainError> {
+    let backup = format!("{}{}", live, BACKUP_DIR_SUFFIX);
+    remove_dir_if_exists(&backup)?;
+    fs::rename(live, &backup)?;
+    if let Err(e) = fs::rename(imported, live) {
+        fs::rename(&backup, live)?;
+        return Err(e.into())
+    }
+
+    Ok(backup)
+}
+
+impl<B: KVBackend> KVStorage<B> {
+    // Write all the records of the snapshot in the storage
+    async fn write_snapshot(&mut self, reader: &mut SnapshotReader) -> Result<(), BlockchainError> {
+        {
+            // None until the first tree record, Some(None) for a tree unknown by this storage
+            let mut tree: Option<Option<&Tree<B::Tree>>> = None;
+            while let Some(record) = reader.next_record()? {
+                match record {
+                    SnapshotRecord::Tree(name) => {
+                        let found = self.trees().into_iter().find(|tree| tree.name().as_bytes() == name.as_slice());
+                        if found.is_some() {
+                            debug!("Importing tree {}", String::from_utf8_lossy(&name));
+                        } else {
+                            warn!("Skipping unknown tree {} from snapshot", String::from_utf8_lossy(&name));
+                        }
+                        tree = Some(found);
+                    },
+                    SnapshotRecord::Entry(key, value) => {
+                        let tree = tree.ok_or_else(|| BlockchainError::InvalidSnapshot("entry without tree".to_owned()))?;
+                        if let Some(tree) = tree {
+                            tree.insert(key, value)?;
+                        }
+                    }
+                }
+            }
+        }
+
+        self.flush()?;
+        self.clear_caches().await?;
+        self.load_metadata_from_disk();
+
+        Ok(())
+    }
+}
+
+// Trees have the same names in all the backends
+// so a snapshot can be imported in any of them
+#[async_trait]
+impl<B: KVBackend> SnapshotProvider for KVStorage<B> {
+    async fn export_snapshot(&self, writer: &mut SnapshotWriter) -> Result<(), BlockchainError> {
+        trace!("export snapshot");
+        for tree in self.trees() {
+            debug!("Exporting tree {}", tree.name());
+            writer.write_tree(tree.name().as_bytes())?;
+            for res in tree.iter() {
+                let (key, value) = res?;
+                writer.write_entry(&key, &value)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn import_snapshot(&self, reader: &mut SnapshotReader) -> Result<Self, BlockchainError> {
+        trace!("import snapshot");
+        let path = format!("{}{}", self.get_path(), IMPORT_DIR_SUFFIX);
+        // Remove what is left from a previous import
+        remove_dir_if_exists(&path)?;
+
+        let mut storage = Self::open(path, None, self.network())?;
+        if let Err(e) = storage.write_snapshot(reader).await {
+            storage.discard()?;
+            return Err(e)
+        }
+
+        Ok(storage)
+    }
+
+    fn replace_with(&mut self, imported: Self) -> Result<(), BlockchainError> {
+        trace!("replace with imported storage");
+        let path = self.get_path().to_owned();
+        let imported_path = imported.get_path().to_owned();
+        imported.flush()?;
+        drop(imported);
+
+        self.flush()?;
+        let backup = swap_directories(&path, &imported_path)?;
+        *self = Self::open(path, self.get_cache_size(), self.network())?;
+        fs::remove_dir_all(backup)?;
+
+        Ok(())
+    }
+
+    fn discard(self) -> Result<(), BlockchainError> {
+        trace!("discard storage");
+        let path = self.get_path().to_owned();
+        drop(self);
+        remove_dir_if_exists(&path)
+    }
+}
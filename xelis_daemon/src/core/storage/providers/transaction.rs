@@ -11,8 +11,7 @@ use crate::core::{
         DiskContext
     },
     storage::{
-        sled::TXS_COUNT,
-        SledStorage
+        kv::{KVBackend, KVStorage, TXS_COUNT}
     }
 };
 
@@ -32,9 +31,12 @@ pub trait TransactionProvider {
 
     // Delete a transaction from the storage using its hash
     async fn delete_transaction(&mut self, hash: &Hash) -> Result<Arc<Transaction>, BlockchainError>;
+
+    // Delete only the content of a transaction, the blocks including it are still known
+    async fn delete_transaction_body(&mut self, hash: &Hash) -> Result<(), BlockchainError>;
 }
 
-impl SledStorage {
+impl<B: KVBackend> KVStorage<B> {
     // Update the txs count and store it on disk
     pub(super) fn store_transactions_count(&self, count: u64) -> Result<(), BlockchainError> {
         self.transactions_count.store(count, Ordering::SeqCst);
@@ -44,7 +46,7 @@ impl SledStorage {
 }
 
 #[async_trait]
-impl TransactionProvider for SledStorage {
+impl<B: KVBackend> TransactionProvider for KVStorage<B> {
     async fn get_transaction(&self, hash: &Hash) -> Result<Arc<Transaction>, BlockchainError> {
         trace!("get transaction for hash {}", hash);
         self.get_cacheable_arc_data(&self.transactions, &self.transactions_cache, hash).await
@@ -70,4 +72,10 @@ impl TransactionProvider for SledStorage {
         self.delete_cacheable_data::<Hash, HashSet<Hash>>(&self.tx_blocks, &None, hash).await?;
         self.delete_data(&self.transactions, &self.transactions_cache, hash).await
     }
-}
\ No newline at end of file
+
+    async fn delete_transaction_body(&mut self, hash: &Hash) -> Result<(), BlockchainError> {
+        trace!("delete transaction body {}", hash);
+        let _: Arc<Transaction> = self.delete_data(&self.transactions, &self.transactions_cache, hash).await?;
+        Ok(())
+    }
+}
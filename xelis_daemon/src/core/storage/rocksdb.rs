@@ -0,0 +1,112 @@
+use std::{sync::Arc, thread};
+use rocksdb::{ColumnFamily, DBCompressionType, Direction, IteratorMode, Options, DB};
+use crate::core::error::BlockchainError;
+use super::kv::{EntryIter, KVBackend, KVStorage, KVTree, TREES};
+
+pub type RocksStorage = KVStorage<RocksBackend>;
+
+// Handle on a column family, one is opened for each tree of the storage
+pub struct Column {
+    db: Arc<DB>,
+    name: &'static str
+}
+
+impl Column {
+    fn handle(&self) -> &ColumnFamily {
+        // All the column families are created when opening the DB
+        self.db.cf_handle(self.name).expect("column family not opened")
+    }
+
+    fn iter_mode(&self, mode: IteratorMode) -> EntryIter<'_> {
+        Box::new(self.db.iterator_cf(self.handle(), mode).map(|res| res.map_err(BlockchainError::from)))
+    }
+}
+
+impl KVTree for Column {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, BlockchainError> {
+        Ok(self.db.get_cf(self.handle(), key)?)
+    }
+
+    fn contains_key(&self, key: &[u8]) -> Result<bool, BlockchainError> {
+        Ok(self.db.get_pinned_cf(self.handle(), key)?.is_some())
+    }
+
+    fn insert(&self, key: &[u8], value: &[u8]) -> Result<(), BlockchainError> {
+        self.db.put_cf(self.handle(), key, value)?;
+        Ok(())
+    }
+
+    fn remove(&self, key: &[u8]) -> Result<(), BlockchainError> {
+        self.db.delete_cf(self.handle(), key)?;
+        Ok(())
+    }
+
+    fn iter(&self) -> EntryIter<'_> {
+        self.iter_mode(IteratorMode::Start)
+    }
+
+    fn iter_from(&self, key: &[u8]) -> EntryIter<'_> {
+        self.iter_mode(IteratorMode::From(key, Direction::Forward))
+    }
+
+    fn iter_rev_from(&self, key: &[u8]) -> EntryIter<'_> {
+        self.iter_mode(IteratorMode::From(key, Direction::Reverse))
+    }
+}
+
+pub struct RocksBackend {
+    db: Arc<DB>
+}
+
+impl RocksBackend {
+    fn handles(&self) -> impl Iterator<Item = &ColumnFamily> + '_ {
+        TREES.iter().map(|name| self.db.cf_handle(name).expect("column family not opened"))
+    }
+}
+
+impl KVBackend for RocksBackend {
+    type Tree = Column;
+
+    const NAME: &'static str = "RocksDB";
+
+    const DIR_PREFIX: &'static str = "rocksdb-";
+
+    fn open(path: &str) -> Result<Self, BlockchainError> {
+        let mut options = Options::default();
+        options.create_if_missing(true);
+        options.create_missing_column_families(true);
+        // Use all the cores for the flushes and compactions
+        let threads = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+        options.increase_parallelism(threads as i32);
+        options.set_compression_type(DBCompressionType::Lz4);
+        // Keep the levels size dynamic to limit the space amplification on large chains
+        options.set_level_compaction_dynamic_level_bytes(true);
+
+        Ok(Self {
+            db: Arc::new(DB::open_cf(&options, path, TREES)?)
+        })
+    }
+
+    fn open_tree(&self, name: &'static str) -> Result<Column, BlockchainError> {
+        Ok(Column {
+            db: Arc::clone(&self.db),
+            name
+        })
+    }
+
+    fn flush(&self) -> Result<(), BlockchainError> {
+        for handle in self.handles() {
+            self.db.flush_cf(handle)?;
+        }
+        Ok(())
+    }
+
+    // Size of the SST files of all the column families
+    fn size_on_disk(&self) -> Result<u64, BlockchainError> {
+        let mut size = 0;
+        for handle in self.handles() {
+            size += self.db.property_int_value_cf(handle, "rocksdb.total-sst-files-size")?.unwrap_or(0);
+        }
+        Ok(size)
+    }
+}
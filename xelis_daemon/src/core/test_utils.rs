@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+use xelis_common::{
+    account::CiphertextCache,
+    config::{COIN_VALUE, XELIS_ASSET},
+    crypto::{elgamal::Ciphertext, Hash, KeyPair, PublicKey},
+    network::Network,
+    transaction::{
+        builder::{AccountState, FeeBuilder, FeeHelper, TransactionBuilder, TransactionTypeBuilder},
+        BurnPayload,
+        Reference,
+        Transaction
+    }
+};
+
+// Balances of an account known in plaintext to build its TXs
+struct TestAccountState {
+    balances: HashMap<Hash, (u64, CiphertextCache)>,
+    nonce: u64
+}
+
+impl FeeHelper for TestAccountState {
+    type Error = ();
+
+    fn account_exists(&self, _: &PublicKey) -> Result<bool, Self::Error> {
+        Ok(true)
+    }
+}
+
+impl AccountState for TestAccountState {
+    fn get_network(&self) -> Network {
+        Network::Dev
+    }
+
+    fn get_account_balance(&self, asset: &Hash) -> Result<u64, Self::Error> {
+        self.balances.get(asset).map(|(balance, _)| *balance).ok_or(())
+    }
+
+    fn get_reference(&self) -> Reference {
+        Reference {
+            topoheight: 0,
+            hash: Hash::zero()
+        }
+    }
+
+    fn get_account_ciphertext(&self, asset: &Hash) -> Result<CiphertextCache, Self::Error> {
+        self.balances.get(asset).map(|(_, ciphertext)| ciphertext.clone()).ok_or(())
+    }
+
+    fn update_account_balance(&mut self, asset: &Hash, new_balance: u64, ciphertext: Ciphertext) -> Result<(), Self::Error> {
+        self.balances.insert(asset.clone(), (new_balance, CiphertextCache::Decompressed(ciphertext)));
+        Ok(())
+    }
+
+    fn get_nonce(&self) -> Result<u64, Self::Error> {
+        Ok(self.nonce)
+    }
+
+    fn update_nonce(&mut self, new_nonce: u64) -> Result<(), Self::Error> {
+        self.nonce = new_nonce;
+        Ok(())
+    }
+}
+
+// Build a burn TX of the account with the fee and nonce given
+pub fn create_burn_tx(keypair: &KeyPair, fee: u64, nonce: u64) -> Transaction {
+    let balance = 100 * COIN_VALUE;
+    let mut state = TestAccountState {
+        balances: HashMap::from([(XELIS_ASSET, (balance, CiphertextCache::Decompressed(keypair.get_public_key().encrypt(balance))))]),
+        nonce
+    };
+
+    let data = TransactionTypeBuilder::Burn(BurnPayload {
+        asset: XELIS_ASSET,
+        amount: COIN_VALUE
+    });
+    let builder = TransactionBuilder::new(0, keypair.get_public_key().compress(), data, FeeBuilder::Value(fee));
+    builder.build(&mut state, keypair).unwrap()
+}
@@ -34,27 +34,44 @@ impl PartialEq for TxSelectorEntry<'_> {
 
 impl Eq for TxSelectorEntry<'_> {}
 
+impl TxSelectorEntry<'_> {
+    // Compare the fee rate (fee per byte) of two entries
+    // Cross multiplication is used to not lose precision
+    fn cmp_fee_rate(&self, other: &Self) -> Ordering {
+        let rate = self.tx.get_fee() as u128 * other.size.max(1) as u128;
+        let other_rate = other.tx.get_fee() as u128 * self.size.max(1) as u128;
+        rate.cmp(&other_rate)
+            // Smallest TX first to fill the block at same fee rate
+            .then_with(|| other.size.cmp(&self.size))
+    }
+}
+
 // this struct is used to store transactions in a queue
-// and to order them by fees
+// and to order them by fee rate
 // Each Transactions is for a specific sender
 #[derive(PartialEq, Eq)]
 struct Transactions<'a>(VecDeque<TxSelectorEntry<'a>>);
 
 impl PartialOrd for Transactions<'_> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        self.0.front().map(|e| e.tx.get_fee()).partial_cmp(&other.0.front().map(|e| e.tx.get_fee()))
+        Some(self.cmp(other))
     }
 }
 
+// Only the next TX of a sender can be selected because of the nonces ordering
+// So the group is ordered by the fee rate of its first TX
 impl Ord for Transactions<'_> {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.0.front().map(|e| e.tx.get_fee()).cmp(&other.0.front().map(|e| e.tx.get_fee()))
+        match (self.0.front(), other.0.front()) {
+            (Some(a), Some(b)) => a.cmp_fee_rate(b),
+            (a, b) => a.is_some().cmp(&b.is_some())
+        }
     }
 }
 
 // TX selector is used to select transactions from the mempool
 // It create sub groups of transactions by sender and order them by nonces
-// It joins all sub groups in a queue that is ordered by fees
+// It joins all sub groups in a queue that is ordered by fee rate
 pub struct TxSelector<'a> {
     queue: BinaryHeap<Transactions<'a>>
 }
@@ -110,18 +127,102 @@ impl<'a> TxSelector<'a> {
         Self::grouped(iter)
     }
 
-    // Get the next transaction with the highest fee
+    // Get the next transaction with the highest fee rate
     pub fn next(&mut self) -> Option<TxSelectorEntry<'a>> {
-        // get the group with the highest fee
-        let mut group = self.queue.pop()?;
-        // get the entry with the highest fee from this group
-        let entry = group.0.pop_front()?;
-
-        // if its not empty, push it back to the queue
-        if !group.0.is_empty() {
-            self.queue.push(group);
+        self.next_fitting(usize::MAX)
+    }
+
+    // Get the next transaction with the highest fee rate whose size is at most `max_size`
+    // A sender whose next TX is too big is dropped with all its TXs, they can't be included without it
+    pub fn next_fitting(&mut self, max_size: usize) -> Option<TxSelectorEntry<'a>> {
+        loop {
+            // get the group with the highest fee rate
+            let mut group = self.queue.pop()?;
+            // get the entry with the lowest nonce from this group
+            let entry = group.0.pop_front()?;
+            if entry.size > max_size {
+                continue;
+            }
+
+            // if its not empty, push it back to the queue
+            if !group.0.is_empty() {
+                self.queue.push(group);
+            }
+
+            return Some(entry)
         }
+    }
+}
 
-        Some(entry)
+#[cfg(test)]
+mod tests {
+    use xelis_common::crypto::{Hashable, KeyPair};
+    use crate::core::test_utils::create_burn_tx;
+    use super::*;
+
+    // TX with the size used by the selector, as (size, hash, tx)
+    fn new_entry(keypair: &KeyPair, fee: u64, nonce: u64, size: usize) -> (usize, Arc<Hash>, Arc<Transaction>) {
+        let tx = create_burn_tx(keypair, fee, nonce);
+        (size, Arc::new(tx.hash()), Arc::new(tx))
+    }
+
+    fn new_selector(entries: &[(usize, Arc<Hash>, Arc<Transaction>)]) -> TxSelector<'_> {
+        TxSelector::new(entries.iter().map(|(size, hash, tx)| (*size, hash, tx)))
+    }
+
+    fn hash_of(entry: &(usize, Arc<Hash>, Arc<Transaction>)) -> &Hash {
+        &entry.1
+    }
+
+    #[test]
+    fn test_fee_rate_ordering_across_senders() {
+        let entries = vec![
+            // 10 per byte
+            new_entry(&KeyPair::new(), 1000, 0, 100),
+            // 30 per byte
+            new_entry(&KeyPair::new(), 3000, 0, 100),
+            // 40 per byte, smaller fee but also smaller TX
+            new_entry(&KeyPair::new(), 2000, 0, 50)
+        ];
+
+        let mut selector = new_selector(&entries);
+        let order: Vec<&Hash> = std::iter::from_fn(|| selector.next()).map(|entry| entry.hash.as_ref()).collect();
+        assert_eq!(order, vec![hash_of(&entries[2]), hash_of(&entries[1]), hash_of(&entries[0])]);
+    }
+
+    #[test]
+    fn test_nonce_order_within_sender() {
+        let alice = KeyPair::new();
+        let entries = vec![
+            // Given out of order, the higher fee of the next nonce doesn't make it selected first
+            new_entry(&alice, 10000, 1, 100),
+            new_entry(&alice, 100, 0, 100),
+            new_entry(&KeyPair::new(), 5000, 0, 100)
+        ];
+
+        let mut selector = new_selector(&entries);
+        let order: Vec<&Hash> = std::iter::from_fn(|| selector.next()).map(|entry| entry.hash.as_ref()).collect();
+        assert_eq!(order, vec![hash_of(&entries[2]), hash_of(&entries[1]), hash_of(&entries[0])]);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_skip_tx_not_fitting() {
+        let alice = KeyPair::new();
+        let entries = vec![
+            // Best fee rate but too big, its next TX is dropped with it
+            new_entry(&alice, 10000, 0, 500),
+            new_entry(&alice, 10000, 1, 50),
+            new_entry(&KeyPair::new(), 1000, 0, 100)
+        ];
+
+        let mut selector = new_selector(&entries);
+        let entry = selector.next_fitting(200).unwrap();
+        assert_eq!(entry.hash.as_ref(), hash_of(&entries[2]));
+        assert!(selector.next_fitting(200).is_none());
+
+        // Everything fits
+        let mut selector = new_selector(&entries);
+        let order: Vec<&Hash> = std::iter::from_fn(|| selector.next_fitting(500)).map(|entry| entry.hash.as_ref()).collect();
+        assert_eq!(order, vec![hash_of(&entries[0]), hash_of(&entries[1]), hash_of(&entries[2])]);
+    }
+}
@@ -52,11 +52,12 @@ use crate::{
     core::{
         blockchain::{
             Config,
-            Blockchain,
-            get_block_reward
+            Blockchain
         },
+        emission::get_block_reward,
         storage::{
             Storage,
+            StorageBackend,
             SledStorage
         }
     },
@@ -65,6 +66,8 @@ use crate::{
         MILLIS_PER_SECOND
     }
 };
+#[cfg(feature = "rocksdb")]
+use crate::core::storage::RocksStorage;
 use core::blockdag;
 use std::{
     fs::File,
@@ -131,19 +134,31 @@ async fn main() -> Result<()> {
         }
     }
 
-    let storage = {
-        let use_cache = if blockchain_config.cache_size > 0 {
-            Some(blockchain_config.cache_size)
-        } else {
-            None
-        };
-
-        let dir_path = blockchain_config.dir_path.clone().unwrap_or_default();
-        SledStorage::new(dir_path, use_cache, config.network)?
+    let use_cache = if blockchain_config.cache_size > 0 {
+        Some(blockchain_config.cache_size)
+    } else {
+        None
     };
 
-    let blockchain = Blockchain::new(blockchain_config, config.network, storage).await?;
-    if let Err(e) = run_prompt(prompt, blockchain.clone(), config.network).await {
+    let dir_path = blockchain_config.dir_path.clone().unwrap_or_default();
+    info!("Using {} storage backend", blockchain_config.storage_backend);
+    match blockchain_config.storage_backend {
+        StorageBackend::Sled => {
+            let storage = SledStorage::new(dir_path, use_cache, config.network)?;
+            start_chain(prompt, blockchain_config, config.network, storage).await
+        },
+        #[cfg(feature = "rocksdb")]
+        StorageBackend::RocksDB => {
+            let storage = RocksStorage::new(dir_path, use_cache, config.network)?;
+            start_chain(prompt, blockchain_config, config.network, storage).await
+        }
+    }
+}
+
+// Start the blockchain with the selected storage and run the prompt until exit
+async fn start_chain<S: Storage>(prompt: ShareablePrompt, config: Config, network: Network, storage: S) -> Result<()> {
+    let blockchain = Blockchain::new(config, network, storage).await?;
+    if let Err(e) = run_prompt(prompt, blockchain.clone(), network).await {
         error!("Error while running prompt: {}", e);
     }
 
@@ -180,6 +195,8 @@ async fn run_prompt<S: Storage>(prompt: ShareablePrompt, blockchain: Arc<Blockch
     command_manager.add_command(Command::new("clear_p2p_peerlist", "Clear P2P peerlist", CommandHandler::Async(async_handler!(clear_p2p_peerlist::<S>))))?;
     command_manager.add_command(Command::with_optional_arguments("difficulty_dataset", "Create a dataset for difficulty from chain", vec![Arg::new("output", ArgType::String)], CommandHandler::Async(async_handler!(difficulty_dataset::<S>))))?;
     command_manager.add_command(Command::with_optional_arguments("mine_block", "Mine a block on testnet", vec![Arg::new("count", ArgType::Number)], CommandHandler::Async(async_handler!(mine_block::<S>))))?;
+    command_manager.add_command(Command::with_optional_arguments("export_snapshot", "Export the chain in a snapshot file", vec![Arg::new("output", ArgType::String)], CommandHandler::Async(async_handler!(export_snapshot::<S>))))?;
+    command_manager.add_command(Command::with_required_arguments("import_snapshot", "Bootstrap the chain from a snapshot file", vec![Arg::new("path", ArgType::String)], CommandHandler::Async(async_handler!(import_snapshot::<S>))))?;
     command_manager.add_command(Command::new("p2p_outgoing_connections", "Accept/refuse to connect to outgoing nodes", CommandHandler::Async(async_handler!(p2p_outgoing_connections::<S>))))?;
 
 
@@ -439,7 +456,8 @@ async fn show_balance<S: Storage>(manager: &CommandManager, mut arguments: Argum
         prompt::colorize_str(Color::Green, "Address: "),
         false
     ).await.context("Error while reading address")?;
-    let address = Address::from_string(&str_address).context("Invalid address")?;
+    let network = *manager.get_context().lock()?.get::<Arc<Blockchain<S>>>()?.get_network();
+    let address = Address::from_string_for(&str_address, network).context("Invalid address")?;
 
     // Read asset
     let asset = prompt.read_hash(
@@ -590,6 +608,7 @@ async fn status<S: Storage>(manager: &CommandManager, _: ArgumentManager) -> Res
     let blocks_count = storage.count_blocks().await.context("Error while counting blocks")?;
     let assets = storage.count_assets().await.context("Error while counting assets")?;
     let pruned_topoheight = storage.get_pruned_topoheight().await.context("Error while retrieving pruned topoheight")?;
+    let pruned_bodies_topoheight = storage.get_pruned_bodies_topoheight().await.context("Error while retrieving pruned bodies topoheight")?;
 
     manager.message(format!("Height: {}", height));
     manager.message(format!("Stable Height: {}", stableheight));
@@ -614,6 +633,10 @@ async fn status<S: Storage>(manager: &CommandManager, _: ArgumentManager) -> Res
         manager.message("Chain is in full mode");
     }
 
+    if let Some(pruned_bodies_topoheight) = pruned_bodies_topoheight {
+        manager.message(format!("Transactions are pruned until topoheight {}, only block headers are kept", pruned_bodies_topoheight));
+    }
+
     let elapsed_seconds = manager.running_since().as_secs();
     let elapsed = format_duration(Duration::from_secs(elapsed_seconds)).to_string();
     manager.message(format!("Uptime: {}", elapsed));
@@ -829,6 +852,42 @@ async fn mine_block<S: Storage>(manager: &CommandManager, mut arguments: Argumen
     Ok(())
 }
 
+async fn export_snapshot<S: Storage>(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let output_path = if arguments.has_argument("output") {
+        arguments.get_value("output")?.to_string_value()?
+    } else {
+        "snapshot.xsnp".to_string()
+    };
+
+    let context = manager.get_context().lock()?;
+    let blockchain: &Arc<Blockchain<S>> = context.get()?;
+
+    manager.message(format!("Exporting snapshot to {}...", output_path));
+    let (header, checksum) = blockchain.export_snapshot(&output_path).await.context("Error while exporting snapshot")?;
+    manager.message(format!("Snapshot at topoheight {} written to {}", header.topoheight, output_path));
+    manager.message(format!("Checksum: {}", checksum));
+
+    Ok(())
+}
+
+async fn import_snapshot<S: Storage>(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let path = arguments.get_value("path")?.to_string_value()?;
+    let context = manager.get_context().lock()?;
+    let blockchain: &Arc<Blockchain<S>> = context.get()?;
+
+    let prompt = manager.get_prompt();
+    manager.message("Importing a snapshot replaces the whole chain, only use a snapshot from a trusted source. Continue?");
+    if !prompt.ask_confirmation().await.context("Error while asking confirmation")? {
+        return Ok(())
+    }
+
+    manager.message(format!("Importing snapshot {}...", path));
+    let header = blockchain.import_snapshot(&path).await.context("Error while importing snapshot")?;
+    manager.message(format!("Snapshot imported, chain is at topoheight {} with {} tips", header.topoheight, header.tips.len()));
+
+    Ok(())
+}
+
 async fn p2p_outgoing_connections<S: Storage>(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
     let context = manager.get_context().lock()?;
     let blockchain: &Arc<Blockchain<S>> = context.get()?;
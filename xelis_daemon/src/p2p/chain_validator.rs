@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{collections::HashSet, sync::Arc};
 use async_trait::async_trait;
 use indexmap::{IndexMap, IndexSet};
 use xelis_common::{
@@ -146,6 +146,26 @@ impl<'a, S: Storage> ChainValidator<'a, S> {
         Ok(())
     }
 
+    // Retrieve the blocks of the chain validator leading to this block, including it
+    // Empty if the block is not part of the validated headers
+    pub fn get_ancestors_of(&self, hash: &Hash) -> HashSet<Hash> {
+        let mut ancestors = HashSet::new();
+        if !self.blocks.contains_key(hash) {
+            return ancestors
+        }
+
+        let mut stack = vec![hash];
+        while let Some(hash) = stack.pop() {
+            if let Some(data) = self.blocks.get(hash) {
+                if ancestors.insert(hash.clone()) {
+                    stack.extend(data.header.get_tips().iter());
+                }
+            }
+        }
+
+        ancestors
+    }
+
     // Retrieve all blocks from the chain validator
     pub fn get_blocks(self) -> impl Iterator<Item = (Hash, Arc<BlockHeader>)> {
         self.blocks.into_iter().map(|(hash, data)| (hash, data.header))
@@ -309,6 +329,15 @@ impl<S: Storage> PrunedTopoheightProvider for ChainValidator<'_, S> {
     async fn set_pruned_topoheight(&mut self, _: u64) -> Result<(), BlockchainError> {
         Err(BlockchainError::UnsupportedOperation)
     }
+
+    async fn get_pruned_bodies_topoheight(&self) -> Result<Option<u64>, BlockchainError> {
+        let storage = self.blockchain.get_storage().read().await;
+        storage.get_pruned_bodies_topoheight().await
+    }
+
+    async fn set_pruned_bodies_topoheight(&mut self, _: u64) -> Result<(), BlockchainError> {
+        Err(BlockchainError::UnsupportedOperation)
+    }
 }
 
 #[async_trait]
@@ -6,13 +6,14 @@ pub mod peer_list;
 pub mod chain_validator;
 mod tracker;
 mod encryption;
+mod orphan_pool;
 
 pub use encryption::EncryptionKey;
 
 use indexmap::IndexSet;
 use lru::LruCache;
 use xelis_common::{
-    account::VersionedNonce,
+    account::{VersionedMultiSig, VersionedNonce},
     api::daemon::{
         Direction,
         NotifyEvent,
@@ -24,6 +25,7 @@ use xelis_common::{
     difficulty::CumulativeDifficulty,
     immutable::Immutable,
     serializer::Serializer,
+    transaction::Transaction,
     time::{
         get_current_time_in_millis,
         get_current_time_in_seconds,
@@ -36,7 +38,8 @@ use crate::{
         CHAIN_SYNC_DEFAULT_RESPONSE_BLOCKS, CHAIN_SYNC_DELAY, CHAIN_SYNC_REQUEST_EXPONENTIAL_INDEX_START,
         CHAIN_SYNC_REQUEST_MAX_BLOCKS, CHAIN_SYNC_RESPONSE_MIN_BLOCKS, CHAIN_SYNC_TOP_BLOCKS, PEER_MAX_PACKET_SIZE,
         MILLIS_PER_SECOND, NETWORK_ID, P2P_EXTEND_PEERLIST_DELAY, P2P_PING_DELAY, P2P_PING_PEER_LIST_DELAY, P2P_PING_PEER_LIST_LIMIT,
-        PEER_FAIL_LIMIT, PEER_TIMEOUT_INIT_CONNECTION, PRUNE_SAFETY_LIMIT, STABLE_LIMIT, P2P_PEER_WAIT_ON_ERROR
+        PEER_FAIL_LIMIT, PEER_TIMEOUT_INIT_CONNECTION, PRUNE_SAFETY_LIMIT, STABLE_LIMIT, P2P_PEER_WAIT_ON_ERROR,
+        P2P_ORPHAN_POOL_MAX_BLOCKS, P2P_ORPHAN_MAX_DISTANCE, CHAIN_SYNC_BODIES_MAX_PEERS, P2P_TX_REBROADCAST_DELAY
     },
     core::{
         blockchain::Blockchain,
@@ -75,6 +78,7 @@ use self::{
     },
     peer::Peer,
     tracker::{ObjectTracker, SharedObjectTracker},
+    orphan_pool::{OrphanBlock, OrphanPool},
     peer_list::{SharedPeerList, PeerList},
     connection::{State, Connection},
     error::P2pError
@@ -101,7 +105,7 @@ use std::{
         Arc,
         atomic::{AtomicBool, Ordering}
     },
-    collections::{hash_map::Entry, HashSet},
+    collections::{hash_map::Entry, HashMap, HashSet, VecDeque},
     convert::TryInto,
     net::{IpAddr, SocketAddr},
     time::Duration,
@@ -141,6 +145,8 @@ pub struct P2pServer<S: Storage> {
     blocks_propagation_queue: Mutex<LruCache<Hash, ()>>,
     // Sender for the blocks processing task to have a ordered queue
     blocks_processor: Sender<(Arc<Peer>, BlockHeader, Hash)>,
+    // Blocks propagated before their parents, waiting for them
+    orphan_pool: Mutex<OrphanPool<Arc<Peer>>>,
     // allow fast syncing (only balances / assets / Smart Contracts changes)
     // without syncing the history
     allow_fast_sync_mode: bool,
@@ -199,6 +205,7 @@ impl<S: Storage> P2pServer<S> {
             is_running: AtomicBool::new(true),
             blocks_propagation_queue: Mutex::new(LruCache::new(NonZeroUsize::new(STABLE_LIMIT as usize * TIPS_LIMIT).unwrap())),
             blocks_processor,
+            orphan_pool: Mutex::new(OrphanPool::new(P2P_ORPHAN_POOL_MAX_BLOCKS)),
             allow_fast_sync_mode,
             allow_boost_sync_mode,
             max_chain_response_size: max_chain_response_size.unwrap_or(CHAIN_SYNC_DEFAULT_RESPONSE_BLOCKS),
@@ -321,6 +328,9 @@ impl<S: Storage> P2pServer<S> {
         tokio::spawn(Arc::clone(&self).event_loop(event_receiver));
 
 
+        // start the task rebroadcasting our local TXs
+        tokio::spawn(Arc::clone(&self).tx_rebroadcast_loop());
+
         // start another task for peerlist loop
         if use_peerlist {
             tokio::spawn(Arc::clone(&self).peerlist_loop());
@@ -471,7 +481,7 @@ impl<S: Storage> P2pServer<S> {
         let storage = self.blockchain.get_storage().read().await;
         let (block, top_hash) = storage.get_top_block_header().await?;
         let topoheight = self.blockchain.get_topo_height();
-        let pruned_topoheight = storage.get_pruned_topoheight().await?;
+        let pruned_topoheight = storage.get_shared_pruned_topoheight().await?;
         let cumulative_difficulty = storage.get_cumulative_difficulty_for_block_hash(&top_hash).await.unwrap_or_else(|_| CumulativeDifficulty::zero());
         let genesis_block = get_genesis_block_hash(self.blockchain.get_network());
         let handshake = Handshake::new(Cow::Owned(VERSION.to_owned()), *self.blockchain.get_network(), Cow::Borrowed(self.get_tag()), Cow::Borrowed(&NETWORK_ID), self.get_peer_id(), self.bind_address.port(), get_current_time_in_seconds(), topoheight, block.get_height(), pruned_topoheight, Cow::Borrowed(&top_hash), Cow::Borrowed(genesis_block), Cow::Borrowed(&cumulative_difficulty), self.sharable);
@@ -579,7 +589,7 @@ impl<S: Storage> P2pServer<S> {
     // if a peer is given, we will check and update the peers list
     async fn build_generic_ping_packet_with_storage(&self, storage: &S) -> Ping<'_> {
         let (cumulative_difficulty, block_top_hash, pruned_topoheight) = {
-            let pruned_topoheight = match storage.get_pruned_topoheight().await {
+            let pruned_topoheight = match storage.get_shared_pruned_topoheight().await {
                 Ok(pruned_topoheight) => pruned_topoheight,
                 Err(e) => {
                     error!("Couldn't get the pruned topoheight from storage for generic ping packet: {}", e);
@@ -894,6 +904,24 @@ impl<S: Storage> P2pServer<S> {
         }
     }
 
+    // rebroadcast the TXs submitted through our RPC until they are included in a block
+    // so they are not lost if we were not connected to enough peers when submitted
+    async fn tx_rebroadcast_loop(self: Arc<Self>) {
+        debug!("Starting TX rebroadcast task...");
+        loop {
+            sleep(Duration::from_secs(P2P_TX_REBROADCAST_DELAY)).await;
+
+            let txs = self.blockchain.get_local_txs_to_rebroadcast().await;
+            if !txs.is_empty() {
+                debug!("Rebroadcasting {} local TXs", txs.len());
+            }
+
+            for hash in txs {
+                self.propagate_tx_hash(hash, true).await;
+            }
+        }
+    }
+
     // try to extend our peerlist each time its possible by searching in known peerlist from disk
     async fn peerlist_loop(self: Arc<Self>) {
         debug!("Starting peerlist task...");
@@ -946,60 +974,171 @@ impl<S: Storage> P2pServer<S> {
     // Task for all blocks propagation
     async fn blocks_processing_task(self: Arc<Self>, mut receiver: Receiver<(Arc<Peer>, BlockHeader, Hash)>) {
         debug!("Starting blocks processing task");
-        while let Some((peer, header, block_hash)) = receiver.recv().await {
-            let mut response_blockers: Vec<ResponseBlocker> = Vec::new();
-            for hash in header.get_txs_hashes() {
-                let contains = { // we don't lock one time because we may wait on p2p response
-                    // Check in ObjectTracker
-                    if let Some(response_blocker) = self.object_tracker.get_response_blocker_for_requested_object(hash).await {
-                        trace!("{} is already requested, waiting on response blocker for block {}", hash, block_hash);
-                        response_blockers.push(response_blocker);
-                        true
-                    } else {
-                        self.blockchain.has_tx(hash).await.unwrap_or(false)
-                    }
-                };
-
-                if !contains { // retrieve one by one to prevent acquiring the lock for nothing
-                    debug!("Requesting TX {} to {} for block {}", hash, peer, block_hash);
-                    if let Err(e) = self.object_tracker.request_object_from_peer(Arc::clone(&peer), ObjectRequest::Transaction(hash.clone()), false).await {
-                            error!("Error while requesting TX {} to {} for block {}: {}", hash, peer, block_hash, e);
-                            peer.increment_fail_count();
-                            continue;
-                    }
-
-                    if let Some(response_blocker) = self.object_tracker.get_response_blocker_for_requested_object(hash).await {
-                        response_blockers.push(response_blocker);
+        while let Some(next) = receiver.recv().await {
+            // Orphans waiting on a block added are processed right after it
+            let mut queue = VecDeque::new();
+            queue.push_back(next);
+            while let Some((peer, header, block_hash)) = queue.pop_front() {
+                if self.process_propagated_block(&peer, header, &block_hash).await {
+                    let mut orphan_pool = self.orphan_pool.lock().await;
+                    for (hash, orphan) in orphan_pool.take_children(&block_hash) {
+                        debug!("Orphan block {} is not waiting on {} anymore", hash, block_hash);
+                        queue.push_back((orphan.peer, orphan.header, hash));
                     }
                 }
             }
+        }
 
-            // Wait on all already requested txs
-            for mut blocker in response_blockers {
-                if let Err(e) = blocker.recv().await {
-                    // It's mostly a closed channel error, so we can ignore it
-                    debug!("Error while waiting on response blocker: {}", e);
+        debug!("Blocks processing task ended");
+    }
+
+    // Request the TXs and add the propagated block to the chain
+    // If its tips are not in our chain yet, it is kept in the orphan pool
+    // Returns true if the block was added
+    async fn process_propagated_block(self: &Arc<Self>, peer: &Arc<Peer>, header: BlockHeader, block_hash: &Hash) -> bool {
+        let missing_tips = {
+            let storage = self.blockchain.get_storage().read().await;
+            let mut missing_tips = Vec::new();
+            for tip in header.get_tips() {
+                if !storage.has_block_with_hash(tip).await.unwrap_or(false) {
+                    missing_tips.push(tip.clone());
                 }
             }
+            missing_tips
+        };
 
-            // add immediately the block to chain as we are synced with
-            let block = match self.blockchain.build_block_from_header(Immutable::Owned(header)).await {
-                Ok(block) => block,
-                Err(e) => {
-                    error!("Error while building block {} from peer {}: {}", block_hash, peer, e);
-                    peer.increment_fail_count();
-                    continue;
+        if !missing_tips.is_empty() {
+            self.add_orphan_block(peer, header, block_hash, missing_tips).await;
+            return false;
+        }
+
+        let mut response_blockers: Vec<ResponseBlocker> = Vec::new();
+        for hash in header.get_txs_hashes() {
+            let contains = { // we don't lock one time because we may wait on p2p response
+                // Check in ObjectTracker
+                if let Some(response_blocker) = self.object_tracker.get_response_blocker_for_requested_object(hash).await {
+                    trace!("{} is already requested, waiting on response blocker for block {}", hash, block_hash);
+                    response_blockers.push(response_blocker);
+                    true
+                } else {
+                    self.blockchain.has_tx(hash).await.unwrap_or(false)
                 }
             };
 
-            debug!("Adding received block {} from {} to chain", block_hash, peer);
-            if let Err(e) = self.blockchain.add_new_block(block, true, false).await {
-                error!("Error while adding new block from {}: {}", peer, e);
+            if !contains { // retrieve one by one to prevent acquiring the lock for nothing
+                debug!("Requesting TX {} to {} for block {}", hash, peer, block_hash);
+                if let Err(e) = self.object_tracker.request_object_from_peer(Arc::clone(peer), ObjectRequest::Transaction(hash.clone()), false).await {
+                        error!("Error while requesting TX {} to {} for block {}: {}", hash, peer, block_hash, e);
+                        peer.increment_fail_count();
+                        continue;
+                }
+
+                if let Some(response_blocker) = self.object_tracker.get_response_blocker_for_requested_object(hash).await {
+                    response_blockers.push(response_blocker);
+                }
+            }
+        }
+
+        // Wait on all already requested txs
+        for mut blocker in response_blockers {
+            if let Err(e) = blocker.recv().await {
+                // It's mostly a closed channel error, so we can ignore it
+                debug!("Error while waiting on response blocker: {}", e);
+            }
+        }
+
+        // add immediately the block to chain as we are synced with
+        let block = match self.blockchain.build_block_from_header(Immutable::Owned(header)).await {
+            Ok(block) => block,
+            Err(e) => {
+                error!("Error while building block {} from peer {}: {}", block_hash, peer, e);
                 peer.increment_fail_count();
+                return false;
             }
+        };
+
+        debug!("Adding received block {} from {} to chain", block_hash, peer);
+        if let Err(e) = self.blockchain.add_new_block(block, true, false).await {
+            error!("Error while adding new block from {}: {}", peer, e);
+            peer.increment_fail_count();
+            return false;
         }
 
-        debug!("Blocks processing task ended");
+        true
+    }
+
+    // Send back to the blocks processor the orphans waiting on a block added by the chain sync
+    async fn process_orphans_of(&self, hash: &Hash) {
+        let orphans = {
+            let mut orphan_pool = self.orphan_pool.lock().await;
+            orphan_pool.take_children(hash)
+        };
+
+        for (orphan_hash, orphan) in orphans {
+            debug!("Orphan block {} is not waiting on {} anymore", orphan_hash, hash);
+            if let Err(e) = self.blocks_processor.send((orphan.peer, orphan.header, orphan_hash)).await {
+                error!("Error while sending orphan block to blocks processor task: {}", e);
+            }
+        }
+    }
+
+    // Keep a block in the orphan pool and request its missing tips to the peer
+    // Blocks too far ahead of our chain are discarded, the chain sync will retrieve them
+    async fn add_orphan_block(self: &Arc<Self>, peer: &Arc<Peer>, header: BlockHeader, block_hash: &Hash, missing_tips: Vec<Hash>) {
+        let height = self.blockchain.get_height();
+        if header.get_height() > height + P2P_ORPHAN_MAX_DISTANCE {
+            debug!("Orphan block {} at height {} is too far from our height {}, discarding it", block_hash, header.get_height(), height);
+            return;
+        }
+
+        let mut orphan_pool = self.orphan_pool.lock().await;
+        // Request only the tips that aren't already awaited by another orphan
+        let to_request: Vec<Hash> = missing_tips.iter()
+            .filter(|tip| !orphan_pool.is_waiting_on(tip) && !orphan_pool.contains(tip))
+            .cloned()
+            .collect();
+
+        debug!("Adding block {} to orphan pool, waiting on {} tips", block_hash, missing_tips.len());
+        orphan_pool.insert(block_hash.clone(), OrphanBlock { peer: Arc::clone(peer), header }, missing_tips);
+
+        for tip in to_request {
+            let zelf = Arc::clone(self);
+            let peer = Arc::clone(peer);
+            tokio::spawn(async move {
+                debug!("Requesting missing block {} to {}", tip, peer);
+                let header = match peer.request_blocking_object(ObjectRequest::BlockHeader(tip.clone())).await {
+                    Ok(OwnedObjectResponse::BlockHeader(header, _)) => header,
+                    Ok(response) => {
+                        warn!("{} sent us an invalid response for missing block {}: {}", peer, tip, response.get_hash());
+                        peer.increment_fail_count();
+                        return;
+                    },
+                    Err(e) => {
+                        debug!("Error while requesting missing block {} to {}: {}", tip, peer, e);
+                        return;
+                    }
+                };
+
+                if header.hash() != tip {
+                    warn!("{} sent us block {} instead of {}", peer, header.hash(), tip);
+                    peer.increment_fail_count();
+                    return;
+                }
+
+                {
+                    let mut blocks_propagation_queue = zelf.blocks_propagation_queue.lock().await;
+                    if blocks_propagation_queue.contains(&tip) {
+                        debug!("Missing block {} is already in processing", tip);
+                        return;
+                    }
+                    blocks_propagation_queue.put(tip.clone(), ());
+                }
+
+                if let Err(e) = zelf.blocks_processor.send((peer, header, tip)).await {
+                    error!("Error while sending missing block to blocks processor task: {}", e);
+                }
+            });
+        }
     }
 
     // this function handle the logic to send all packets to the peer
@@ -1761,27 +1900,7 @@ impl<S: Storage> P2pServer<S> {
                 }
 
                 // request all blocks header and verify basic chain structure
-                // Starting topoheight must be the next topoheight after common block
-                // Blocks in chain response must be ordered by topoheight otherwise it will give incorrect results 
-                let mut chain_validator = ChainValidator::new(&self.blockchain, common_topoheight + 1);
-                for hash in blocks {
-                    trace!("Request block header for chain validator: {}", hash);
-
-                    // check if we already have the block to not request it
-                    if self.blockchain.has_block(&hash).await? {
-                        trace!("We already have block {}, skipping", hash);
-                        continue;
-                    }
-
-                    let response = peer.request_blocking_object(ObjectRequest::BlockHeader(hash)).await?;
-                    if let OwnedObjectResponse::BlockHeader(header, hash) = response {
-                        trace!("Received {} with hash {}", header, hash);
-                        chain_validator.insert_block(hash, header).await?;
-                    } else {
-                        error!("{} sent us an invalid object response", peer);
-                        return Err(P2pError::ExpectedBlock.into())
-                    }
-                }
+                let chain_validator = self.request_and_validate_headers(peer, common_topoheight, blocks).await?;
 
                 // Verify that it has a higher cumulative difficulty than us
                 // Otherwise we don't switch to his chain
@@ -1795,49 +1914,16 @@ impl<S: Storage> P2pServer<S> {
                 self.blockchain.rewind_chain(pop_count, false).await?;
 
                 // now retrieve all txs from all blocks header and add block in chain
-                for (hash, header) in chain_validator.get_blocks() {
-                    trace!("Processing block {} from chain validator", hash);
-                    // we don't already have this block, lets retrieve its txs and add in our chain
-                    if !self.blockchain.has_block(&hash).await? {
-                        let mut transactions = Vec::new(); // don't pre allocate
-                        for tx_hash in header.get_txs_hashes() {
-                            // check first on disk in case it was already fetch by a previous block
-                            // it can happens as TXs can be integrated in multiple blocks and executed only one time
-                            // check if we find it
-                            if let Some(tx) = self.blockchain.get_tx(tx_hash).await.ok() {
-                                trace!("Found the transaction {} on disk", tx_hash);
-                                transactions.push(Immutable::Arc(tx));
-                            } else { // otherwise, ask it from peer
-                                let response = peer.request_blocking_object(ObjectRequest::Transaction(tx_hash.clone())).await?;
-                                if let OwnedObjectResponse::Transaction(tx, _) = response {
-                                    trace!("Received transaction {} at block {} from {}", tx_hash, hash, peer);
-                                    transactions.push(Immutable::Owned(tx));
-                                } else {
-                                    error!("{} sent us an invalid block response", peer);
-                                    return Err(P2pError::ExpectedTransaction.into())
-                                }
-                            }
-                        }
-
-                        // Assemble back the block and add it to the chain
-                        let block = Block::new(Immutable::Arc(header), transactions);
-                        self.blockchain.add_new_block(block, false, false).await?; // don't broadcast block because it's syncing
-                    }
-                }
+                self.add_validated_blocks(peer, chain_validator).await?;
             }
-        } else {
+        } else if self.allow_boost_sync() {
             // no rewind are needed, process normally
             // it will first add blocks to sync, and then all alt-tips blocks if any (top blocks)
             let mut total_requested: usize = 0;
             let mut final_blocker = None;
-            // If boost sync is allowed, we can request all blocks in parallel,
+            // Boost sync is allowed, we can request all blocks in parallel,
             // Create a new group in Object Tracker to be notified of a failure
-            let (group_id, mut notifier) = if self.allow_boost_sync() {
-                let (group_id, notifier) = self.object_tracker.get_group_manager().next_group_id().await;
-                (Some(group_id), Some(notifier))
-            } else {
-                (None, None)
-            };
+            let (group_id, mut notifier) = self.object_tracker.get_group_manager().next_group_id().await;
 
             // Peekable is here to help to know if we are at the last element
             // so we create only one channel for the last blocker
@@ -1845,30 +1931,15 @@ impl<S: Storage> P2pServer<S> {
             while let Some(hash) = blocks_iter.next() {
                 if !self.blockchain.has_block(&hash).await? {
                     trace!("Block {} is not found, asking it to {} (index = {})", hash, peer.get_outgoing_address(), total_requested);
-                    // if it's allowed by the user, request all blocks in parallel
-                    if self.allow_boost_sync() {
-                        if let Some(notifier) = &mut notifier {
-                            // Check if we don't have any message pending in the channel
-                            if notifier.try_recv().is_ok() {
-                                debug!("An error has occured in batch while requesting chain in boost mode");
-                                return Err(P2pError::BoostSyncModeFailed.into());
-                            }
-                        }
+                    // Check if we don't have any message pending in the channel
+                    if notifier.try_recv().is_ok() {
+                        debug!("An error has occured in batch while requesting chain in boost mode");
+                        return Err(P2pError::BoostSyncModeFailed.into());
+                    }
 
-                        let is_last = blocks_iter.peek().is_none();
-                        if let Some(blocker) = self.object_tracker.request_object_from_peer_with(Arc::clone(peer), ObjectRequest::Block(hash.clone()), group_id, is_last, is_last).await? {
-                            final_blocker = Some(blocker);
-                        }
-                    } else {
-                        // Otherwise, request them one by one and wait for the response
-                        let response = peer.request_blocking_object(ObjectRequest::Block(hash)).await?;
-                        if let OwnedObjectResponse::Block(block, hash) = response {
-                            trace!("Received block {} at height {} from {}", hash, block.get_height(), peer);
-                            self.blockchain.add_new_block(block, false, false).await?;
-                        } else {
-                            error!("{} sent us an invalid block response", peer);
-                            return Err(P2pError::ExpectedBlock.into())
-                        }
+                    let is_last = blocks_iter.peek().is_none();
+                    if let Some(blocker) = self.object_tracker.request_object_from_peer_with(Arc::clone(peer), ObjectRequest::Block(hash.clone()), Some(group_id), is_last, is_last).await? {
+                        final_blocker = Some(blocker);
                     }
                     total_requested += 1;
                 } else {
@@ -1876,7 +1947,7 @@ impl<S: Storage> P2pServer<S> {
                 }
             }
 
-            if let (Some(mut notifier), Some(mut blocker)) = (notifier, final_blocker) {
+            if let Some(mut blocker) = final_blocker {
                 debug!("Waiting for final blocker to finish...");
                 select! {
                     res = &mut notifier => {
@@ -1887,11 +1958,7 @@ impl<S: Storage> P2pServer<S> {
                     res = blocker.recv() => match res {
                         Ok(()) => {
                             debug!("Final blocker finished");
-                            if let Some(group_id) = group_id {
-                                self.object_tracker.get_group_manager().unregister_group(group_id).await;
-                            } else {
-                                warn!("Group ID is None while it should not be");
-                            }
+                            self.object_tracker.get_group_manager().unregister_group(group_id).await;
                         },
                         Err(e) => {
                             error!("Error while waiting for final blocker: {}", e);
@@ -1901,6 +1968,12 @@ impl<S: Storage> P2pServer<S> {
                 }
             }
             info!("we've synced {} on {} blocks and {} top blocks from {}", total_requested, blocks_len, top_len, peer);
+        } else {
+            // no rewind are needed, sync using headers first
+            // all headers are validated before downloading the blocks bodies from several peers
+            let chain_validator = self.request_and_validate_headers(peer, common_topoheight, blocks).await?;
+            let total_requested = self.add_validated_blocks(peer, chain_validator).await?;
+            info!("we've synced {} on {} blocks and {} top blocks from {}", total_requested, blocks_len, top_len, peer);
         }
 
         let peer_topoheight = peer.get_topoheight();
@@ -1919,6 +1992,171 @@ impl<S: Storage> P2pServer<S> {
         Ok(())
     }
 
+    // Request the headers of the blocks we don't have and verify the chain structure and their PoW
+    // Blocks must be ordered by topoheight, starting after the common point
+    async fn request_and_validate_headers(&self, peer: &Arc<Peer>, common_topoheight: u64, blocks: IndexSet<Hash>) -> Result<ChainValidator<'_, S>, BlockchainError> {
+        // Starting topoheight must be the next topoheight after common block
+        let mut chain_validator = ChainValidator::new(&self.blockchain, common_topoheight + 1);
+        for hash in blocks {
+            trace!("Request block header for chain validator: {}", hash);
+
+            // check if we already have the block to not request it
+            if self.blockchain.has_block(&hash).await? {
+                trace!("We already have block {}, skipping", hash);
+                continue;
+            }
+
+            let response = peer.request_blocking_object(ObjectRequest::BlockHeader(hash)).await?;
+            if let OwnedObjectResponse::BlockHeader(header, hash) = response {
+                trace!("Received {} with hash {}", header, hash);
+                chain_validator.insert_block(hash, header).await?;
+            } else {
+                error!("{} sent us an invalid object response", peer);
+                return Err(P2pError::ExpectedBlock.into())
+            }
+        }
+
+        // The headers are linked by their hashes computed locally,
+        // so the blocks found from the latest checkpoint are its ancestors
+        if let Some(checkpoint) = self.blockchain.get_unproven_checkpoint().await? {
+            let ancestors = chain_validator.get_ancestors_of(&checkpoint.hash);
+            if ancestors.is_empty() {
+                debug!("Checkpoint {} is not part of the headers sent by {}, blocks are fully verified", checkpoint.hash, peer);
+            }
+            self.blockchain.set_checkpoint_ancestors(ancestors).await;
+        }
+
+        Ok(chain_validator)
+    }
+
+    // Download the bodies of the validated blocks and add them to the chain in order
+    // Returns the count of blocks added
+    async fn add_validated_blocks(&self, peer: &Arc<Peer>, chain_validator: ChainValidator<'_, S>) -> Result<usize, BlockchainError> {
+        let headers: Vec<(Hash, Arc<BlockHeader>)> = chain_validator.get_blocks().collect();
+        let txs = self.request_blocks_bodies(peer, &headers).await?;
+
+        let mut count = 0;
+        for (hash, header) in headers {
+            trace!("Processing block {} from chain validator", hash);
+            // it may have been received by propagation in the meantime
+            if self.blockchain.has_block(&hash).await? {
+                continue;
+            }
+
+            let transactions = header.get_txs_hashes()
+                .iter()
+                .map(|tx_hash| txs.get(tx_hash).cloned().map(Immutable::Arc).ok_or(P2pError::ExpectedTransaction))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            // Assemble back the block and add it to the chain
+            let block = Block::new(Immutable::Arc(header), transactions);
+            self.blockchain.add_new_block(block, false, false).await?; // don't broadcast block because it's syncing
+            self.process_orphans_of(&hash).await;
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
+    // Retrieve all the TXs of the blocks headers
+    // TXs not already known are split between the syncing peer and others peers having these blocks
+    // and requested in parallel. Those that couldn't be retrieved are requested again to the syncing peer
+    async fn request_blocks_bodies(&self, peer: &Arc<Peer>, headers: &[(Hash, Arc<BlockHeader>)]) -> Result<HashMap<Hash, Arc<Transaction>>, BlockchainError> {
+        let mut txs = HashMap::new();
+        let mut missing = IndexSet::new();
+        for (_, header) in headers {
+            for tx_hash in header.get_txs_hashes() {
+                if txs.contains_key(tx_hash) || missing.contains(tx_hash) {
+                    continue;
+                }
+
+                // check first on disk in case it was already fetch by a previous block
+                // it can happens as TXs can be integrated in multiple blocks and executed only one time
+                if let Ok(tx) = self.blockchain.get_tx(tx_hash).await {
+                    trace!("Found the transaction {} on disk", tx_hash);
+                    txs.insert(tx_hash.clone(), tx);
+                } else {
+                    missing.insert(tx_hash.clone());
+                }
+            }
+        }
+
+        if missing.is_empty() {
+            return Ok(txs)
+        }
+
+        let max_height = headers.iter().map(|(_, header)| header.get_height()).max().unwrap_or(0);
+        let peers = self.get_peers_for_bodies(peer, max_height).await;
+        let missing: Vec<Hash> = missing.into_iter().collect();
+        let chunk_size = missing.len().div_ceil(peers.len());
+        debug!("Requesting {} TXs to {} peers", missing.len(), peers.len());
+
+        let mut handles = Vec::with_capacity(peers.len());
+        for (peer, chunk) in peers.into_iter().zip(missing.chunks(chunk_size)) {
+            let chunk = chunk.to_vec();
+            handles.push(tokio::spawn(async move {
+                let mut received = Vec::with_capacity(chunk.len());
+                let mut iter = chunk.into_iter();
+                while let Some(tx_hash) = iter.next() {
+                    match peer.request_blocking_object(ObjectRequest::Transaction(tx_hash.clone())).await {
+                        Ok(OwnedObjectResponse::Transaction(tx, hash)) => received.push((hash, tx)),
+                        res => {
+                            if let Err(e) = res {
+                                debug!("Error while requesting TX {} to {}: {}", tx_hash, peer, e);
+                            } else {
+                                peer.increment_fail_count();
+                            }
+                            // stop using this peer, its remaining TXs are requested to the syncing peer
+                            let mut failed = vec![tx_hash];
+                            failed.extend(iter);
+                            return (received, failed)
+                        }
+                    }
+                }
+                (received, Vec::new())
+            }));
+        }
+
+        let mut failed = Vec::new();
+        for handle in handles {
+            let (received, mut remaining) = handle.await.map_err(|e| {
+                error!("Error while waiting on TXs download task: {}", e);
+                BlockchainError::Unknown
+            })?;
+
+            txs.extend(received.into_iter().map(|(hash, tx)| (hash, Arc::new(tx))));
+            failed.append(&mut remaining);
+        }
+
+        for tx_hash in failed {
+            let response = peer.request_blocking_object(ObjectRequest::Transaction(tx_hash.clone())).await?;
+            if let OwnedObjectResponse::Transaction(tx, hash) = response {
+                trace!("Received transaction {} from {}", hash, peer);
+                txs.insert(hash, Arc::new(tx));
+            } else {
+                error!("{} sent us an invalid transaction response", peer);
+                return Err(P2pError::ExpectedTransaction.into())
+            }
+        }
+
+        Ok(txs)
+    }
+
+    // Select the peers used to download the blocks bodies
+    // The syncing peer is always selected, others must be at least at the height of the blocks
+    async fn get_peers_for_bodies(&self, peer: &Arc<Peer>, height: u64) -> Vec<Arc<Peer>> {
+        let mut peers = vec![Arc::clone(peer)];
+        let peer_list = self.peer_list.read().await;
+        peers.extend(
+            peer_list.get_peers()
+                .values()
+                .filter(|p| p.get_id() != peer.get_id() && p.get_height() >= height)
+                .take(CHAIN_SYNC_BODIES_MAX_PEERS - 1)
+                .cloned()
+        );
+        peers
+    }
+
     // determine if we are connected to a priority node and that this node is equal / greater to our chain
     async fn is_connected_to_a_synced_priority_node(&self) -> bool {
         let topoheight = self.blockchain.get_topo_height();
@@ -2007,7 +2245,12 @@ impl<S: Storage> P2pServer<S> {
     // This is used so we don't overload the network during spam or high transactions count
     // We simply share its hash to nodes and others nodes can check if they have it already or not
     pub async fn broadcast_tx_hash(&self, tx: Hash) {
-        debug!("Broadcasting tx hash {}", tx);
+        self.propagate_tx_hash(tx, false).await
+    }
+
+    // Send the TX hash to the peers not having it in their cache
+    async fn propagate_tx_hash(&self, tx: Hash, rebroadcast: bool) {
+        debug!("Broadcasting tx hash {} (rebroadcast: {})", tx, rebroadcast);
         let ping = self.build_generic_ping_packet().await;
         trace!("Ping packet has been generated for tx broadcast");
         let current_topoheight = ping.get_topoheight();
@@ -2026,7 +2269,8 @@ impl<S: Storage> P2pServer<S> {
                 trace!("Peer {} is not too far from us, checking cache for tx hash {}", peer, tx);
                 let mut txs_cache = peer.get_txs_cache().lock().await;
                 // check that we didn't already send this tx to this peer or that he don't already have it
-                if !txs_cache.contains(&tx) {
+                // A rebroadcast is sent to every peer as we don't know which ones lost it
+                if rebroadcast || !txs_cache.contains(&tx) {
                     trace!("Broadcasting tx hash {} to {}", tx, peer);
                     if let Err(e) = peer.send_bytes(bytes.clone()).await {
                         error!("Error while broadcasting tx hash {} to {}: {}", tx, peer, e);
@@ -2166,6 +2410,14 @@ impl<S: Storage> P2pServer<S> {
                 }
                 StepResponse::Nonces(nonces)
             },
+            StepRequest::MultiSig(topoheight, keys) => {
+                let mut setups = Vec::with_capacity(keys.len());
+                for key in keys.iter() {
+                    let setup = storage.get_multisig_at_maximum_topoheight(key, topoheight).await?.and_then(|(_, v)| v.take_state());
+                    setups.push(setup);
+                }
+                StepResponse::MultiSig(setups)
+            },
             StepRequest::Keys(min, max, page) => {
                 if min > max {
                     warn!("Invalid range for assets");
@@ -2239,7 +2491,7 @@ impl<S: Storage> P2pServer<S> {
 
     // first, retrieve chain info of selected peer
     // We retrieve all assets through pagination,
-    // then we fetch all keys with its nonces and its balances (also through pagination)
+    // then we fetch all keys with its nonces, its multisig setups and its balances (also through pagination)
     // and for the last step, retrieve last STABLE TOPOHEIGHT - PRUNE_SAFETY_LIMIT blocks
     // reload blockchain cache from disk, and we're ready to sync the rest of the chain
     // NOTE: it could be even faster without retrieving each TXs, but we do it in case user don't enable pruning
@@ -2337,12 +2589,42 @@ impl<S: Storage> P2pServer<S> {
                         let mut storage = self.blockchain.get_storage().write().await;
                         // save all nonces
                         for (key, nonce) in keys.iter().zip(nonces) {
-                            debug!("Saving nonce {} for {}", nonce, key.as_address(self.blockchain.get_network().is_mainnet()));
+                            debug!("Saving nonce {} for {}", nonce, key.as_address(*self.blockchain.get_network()));
                             storage.set_last_nonce_to(key, stable_topoheight, &VersionedNonce::new(nonce, None)).await?;
                             storage.set_account_registration_topoheight(key, stable_topoheight).await?;
                         }
                     }
 
+                    debug!("Requesting multisig setups for keys");
+                    let StepResponse::MultiSig(setups) = peer.request_boostrap_chain(StepRequest::MultiSig(stable_topoheight, Cow::Borrowed(&keys))).await? else {
+                        // shouldn't happen
+                        error!("Received an invalid StepResponse (how ?) while fetching multisig setups");
+                        return Err(P2pError::InvalidPacket.into())
+                    };
+
+                    if setups.len() != keys.len() {
+                        error!("Received {} multisig setups for {} keys", setups.len(), keys.len());
+                        return Err(P2pError::InvalidPacket.into())
+                    }
+
+                    {
+                        let mut storage = self.blockchain.get_storage().write().await;
+                        for (key, setup) in keys.iter().zip(setups) {
+                            if matches!(&setup, Some(setup) if !setup.is_valid(key)) {
+                                error!("Received an invalid multisig setup for {}", key.as_address(*self.blockchain.get_network()));
+                                return Err(P2pError::InvalidPacket.into())
+                            }
+
+                            // An account without setup only needs a version if we had one before the common point
+                            if setup.is_none() && !storage.has_multisig(key).await? {
+                                continue;
+                            }
+
+                            debug!("Saving multisig setup for {}", key.as_address(*self.blockchain.get_network()));
+                            storage.set_last_multisig_to(key, stable_topoheight, &VersionedMultiSig::new(setup, None)).await?;
+                        }
+                    }
+
                     let mut page = 0;
                     loop {
                         // Retrieve chunked assets
@@ -2370,7 +2652,7 @@ impl<S: Storage> P2pServer<S> {
                             for (key, balance) in keys.iter().zip(balances) {
                                 // check that the account have balance for this asset
                                 if let Some((balance, output_balance, balance_type)) = balance {
-                                    debug!("Saving balance {:?} for key {} at topoheight {}", balance, key.as_address(self.blockchain.get_network().is_mainnet()), stable_topoheight);
+                                    debug!("Saving balance {:?} for key {} at topoheight {}", balance, key.as_address(*self.blockchain.get_network()), stable_topoheight);
                                     let mut versioned_balance = storage.get_new_versioned_balance(key, &asset, stable_topoheight).await?;
                                     versioned_balance.set_balance(balance);
                                     versioned_balance.set_output_balance(output_balance);
@@ -2460,6 +2742,9 @@ impl<S: Storage> P2pServer<S> {
                     storage.delete_registrations_below_topoheight(lowest_topoheight).await?;
 
                     storage.set_pruned_topoheight(lowest_topoheight).await?;
+                    // The burns of the chain bootstrapped are not known
+                    storage.clear_burned_supply()?;
+                    storage.set_burned_supply_indexed_from(top_topoheight + 1)?;
                     storage.set_top_topoheight(top_topoheight)?;
                     storage.set_top_height(top_height)?;
                     storage.store_tips(&HashSet::from([top_block_hash.take().expect("Expected top block hash for fast sync")]))?;
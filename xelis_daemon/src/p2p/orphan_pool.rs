@@ -0,0 +1,179 @@
+use std::{
+    collections::{hash_map::Entry, HashMap, HashSet},
+    num::NonZeroUsize
+};
+use lru::LruCache;
+use xelis_common::{block::BlockHeader, crypto::Hash};
+
+// Block propagated before its parents, waiting to be processed
+pub struct OrphanBlock<P> {
+    // Peer that sent us the block, used to request its missing parents
+    pub peer: P,
+    pub header: BlockHeader
+}
+
+// Bounded pool of blocks whose tips are not in our chain yet
+// The oldest orphans are evicted once it is full
+pub struct OrphanPool<P> {
+    blocks: LruCache<Hash, OrphanBlock<P>>,
+    // missing parent hash -> orphans waiting on it
+    waiting: HashMap<Hash, HashSet<Hash>>
+}
+
+impl<P> OrphanPool<P> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            blocks: LruCache::new(NonZeroUsize::new(capacity).expect("orphan pool capacity must be non zero")),
+            waiting: HashMap::new()
+        }
+    }
+
+    pub fn contains(&self, hash: &Hash) -> bool {
+        self.blocks.contains(hash)
+    }
+
+    // Check if an orphan is already waiting on this parent
+    // so it is requested only one time
+    pub fn is_waiting_on(&self, hash: &Hash) -> bool {
+        self.waiting.contains_key(hash)
+    }
+
+    // Add an orphan waiting on its missing tips
+    pub fn insert(&mut self, hash: Hash, orphan: OrphanBlock<P>, missing_tips: Vec<Hash>) {
+        for tip in missing_tips {
+            self.waiting.entry(tip).or_insert_with(HashSet::new).insert(hash.clone());
+        }
+
+        // The same block sent again only replaces its entry
+        if let Some((evicted_hash, evicted)) = self.blocks.push(hash.clone(), orphan) {
+            if evicted_hash != hash {
+                self.unlink(&evicted_hash, &evicted.header);
+            }
+        }
+    }
+
+    // Remove the orphan from the waiting lists of its tips
+    fn unlink(&mut self, hash: &Hash, header: &BlockHeader) {
+        for tip in header.get_tips() {
+            if let Entry::Occupied(mut entry) = self.waiting.entry(tip.clone()) {
+                entry.get_mut().remove(hash);
+                if entry.get().is_empty() {
+                    entry.remove();
+                }
+            }
+        }
+    }
+
+    // Remove and returns the orphans that were waiting on this block
+    // They may still wait on others tips and be added back
+    pub fn take_children(&mut self, hash: &Hash) -> Vec<(Hash, OrphanBlock<P>)> {
+        let Some(children) = self.waiting.remove(hash) else {
+            return Vec::new()
+        };
+
+        let mut orphans = Vec::with_capacity(children.len());
+        for child in children {
+            if let Some(orphan) = self.blocks.pop(&child) {
+                self.unlink(&child, &orphan.header);
+                orphans.push((child, orphan));
+            }
+        }
+
+        orphans
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indexmap::IndexSet;
+    use xelis_common::{
+        block::EXTRA_NONCE_SIZE,
+        crypto::{Hashable, KeyPair}
+    };
+    use super::*;
+
+    fn new_orphan(height: u64, tips: &[Hash]) -> (Hash, OrphanBlock<()>) {
+        let miner = KeyPair::new().get_public_key().compress();
+        let header = BlockHeader::new(0, height, 0, tips.iter().cloned().collect(), [0u8; EXTRA_NONCE_SIZE], miner, IndexSet::new());
+        (header.hash(), OrphanBlock { peer: (), header })
+    }
+
+    #[test]
+    fn test_insert_and_take_children() {
+        let mut pool = OrphanPool::new(8);
+        let parent = Hash::new([1u8; 32]);
+        let (hash, orphan) = new_orphan(1, &[parent.clone()]);
+        pool.insert(hash.clone(), orphan, vec![parent.clone()]);
+
+        assert!(pool.contains(&hash));
+        assert!(pool.is_waiting_on(&parent));
+
+        let children = pool.take_children(&parent);
+        assert_eq!(children.len(), 1);
+        assert_eq!(children[0].0, hash);
+        assert!(!pool.contains(&hash));
+        assert!(!pool.is_waiting_on(&parent));
+        assert!(pool.take_children(&parent).is_empty());
+    }
+
+    #[test]
+    fn test_take_children_unlinks_other_tips() {
+        let mut pool = OrphanPool::new(8);
+        let first = Hash::new([1u8; 32]);
+        let second = Hash::new([2u8; 32]);
+        let (hash, orphan) = new_orphan(1, &[first.clone(), second.clone()]);
+        pool.insert(hash.clone(), orphan, vec![first.clone(), second.clone()]);
+
+        // Taken from its first tip, it must not stay awaited on the second one
+        assert_eq!(pool.take_children(&first).len(), 1);
+        assert!(!pool.is_waiting_on(&second));
+        assert!(pool.take_children(&second).is_empty());
+    }
+
+    #[test]
+    fn test_shared_parent() {
+        let mut pool = OrphanPool::new(8);
+        let parent = Hash::new([1u8; 32]);
+        let other = Hash::new([2u8; 32]);
+        let (a, orphan_a) = new_orphan(1, &[parent.clone()]);
+        let (b, orphan_b) = new_orphan(1, &[parent.clone(), other.clone()]);
+        pool.insert(a.clone(), orphan_a, vec![parent.clone()]);
+        pool.insert(b.clone(), orphan_b, vec![parent.clone(), other.clone()]);
+
+        let children: HashSet<Hash> = pool.take_children(&parent).into_iter().map(|(hash, _)| hash).collect();
+        assert_eq!(children, HashSet::from([a, b]));
+        assert!(!pool.is_waiting_on(&other));
+    }
+
+    #[test]
+    fn test_eviction_unlinks_oldest() {
+        let mut pool = OrphanPool::new(1);
+        let first_parent = Hash::new([1u8; 32]);
+        let second_parent = Hash::new([2u8; 32]);
+        let (first, orphan) = new_orphan(1, &[first_parent.clone()]);
+        pool.insert(first.clone(), orphan, vec![first_parent.clone()]);
+
+        let (second, orphan) = new_orphan(1, &[second_parent.clone()]);
+        pool.insert(second.clone(), orphan, vec![second_parent.clone()]);
+
+        assert!(!pool.contains(&first));
+        assert!(!pool.is_waiting_on(&first_parent));
+        assert!(pool.contains(&second));
+        assert!(pool.is_waiting_on(&second_parent));
+    }
+
+    #[test]
+    fn test_insert_same_block_again() {
+        let mut pool = OrphanPool::new(1);
+        let parent = Hash::new([1u8; 32]);
+        let (hash, orphan) = new_orphan(1, &[parent.clone()]);
+        pool.insert(hash.clone(), orphan, vec![parent.clone()]);
+
+        let (_, orphan) = new_orphan(1, &[parent.clone()]);
+        pool.insert(hash.clone(), orphan, vec![parent.clone()]);
+
+        assert!(pool.contains(&hash));
+        assert!(pool.is_waiting_on(&parent));
+        assert_eq!(pool.take_children(&parent).len(), 1);
+    }
+}
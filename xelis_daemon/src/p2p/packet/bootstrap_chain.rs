@@ -20,6 +20,7 @@ use xelis_common::{
         Serializer,
         Writer
     },
+    transaction::multisig::MultiSigPayload,
     varuint::VarUint
 };
 use super::chain::{BlockId, CommonPoint};
@@ -30,6 +31,7 @@ use crate::config::CHAIN_SYNC_REQUEST_MAX_BLOCKS;
 // based on the size of the chain, you can have pagination or not.
 // With the set of assets, you can retrieve all registered keys for it and then its balances
 // Nonces need to be retrieve only one time because its common for all assets.
+// Multisig setups are retrieved with the nonces for the same keys.
 // The protocol is based on
 // how many items we can answer per request
 
@@ -110,6 +112,7 @@ pub enum StepKind {
     Keys,
     Balances,
     Nonces,
+    MultiSig,
     BlocksMetadata
 }
 
@@ -120,7 +123,8 @@ impl StepKind {
             Self::Assets => Self::Keys,
             Self::Keys => Self::Balances,
             Self::Balances => Self::Nonces,
-            Self::Nonces => Self::BlocksMetadata,
+            Self::Nonces => Self::MultiSig,
+            Self::MultiSig => Self::BlocksMetadata,
             Self::BlocksMetadata => return None
         })
     }
@@ -138,6 +142,8 @@ pub enum StepRequest<'a> {
     Balances(u64, Cow<'a, Hash>, Cow<'a, IndexSet<PublicKey>>),
     // Max topoheight, Accounts
     Nonces(u64, Cow<'a, IndexSet<PublicKey>>),
+    // Max topoheight, Accounts
+    MultiSig(u64, Cow<'a, IndexSet<PublicKey>>),
     // Request blocks metadata starting topoheight
     BlocksMetadata(u64)
 }
@@ -150,6 +156,7 @@ impl<'a> StepRequest<'a> {
             Self::Keys(_, _, _) => StepKind::Keys,
             Self::Balances(_, _, _) => StepKind::Balances,
             Self::Nonces(_, _) => StepKind::Nonces,
+            Self::MultiSig(_, _) => StepKind::MultiSig,
             Self::BlocksMetadata(_) => StepKind::BlocksMetadata
         }
     }
@@ -161,6 +168,7 @@ impl<'a> StepRequest<'a> {
             Self::Keys(_, topo, _) => topo,
             Self::Balances(topo, _, _) => topo,
             Self::Nonces(topo, _) => topo,
+            Self::MultiSig(topo, _) => topo,
             Self::BlocksMetadata(topo) => topo
         })
     }
@@ -233,6 +241,11 @@ impl Serializer for StepRequest<'_> {
             5 => {
                 Self::BlocksMetadata(reader.read_u64()?)
             },
+            6 => {
+                let topoheight = reader.read_u64()?;
+                let keys = Cow::<'_, IndexSet<PublicKey>>::read(reader)?;
+                Self::MultiSig(topoheight, keys)
+            },
             id => {
                 debug!("Received invalid value for StepResponse: {}", id);
                 return Err(ReaderError::InvalidValue)
@@ -276,6 +289,11 @@ impl Serializer for StepRequest<'_> {
                 writer.write_u8(5);
                 writer.write_u64(topoheight);
             },
+            Self::MultiSig(topoheight, keys) => {
+                writer.write_u8(6);
+                writer.write_u64(topoheight);
+                keys.write(writer);
+            },
         };
     }
 
@@ -286,6 +304,7 @@ impl Serializer for StepRequest<'_> {
             Self::Keys(min, max, page) => min.size() + max.size() + page.size(),
             Self::Balances(topoheight, asset, accounts) => topoheight.size() + asset.size() + accounts.size(),
             Self::Nonces(topoheight, nonces) => topoheight.size() + nonces.size(),
+            Self::MultiSig(topoheight, keys) => topoheight.size() + keys.size(),
             Self::BlocksMetadata(topoheight) => topoheight.size()
         };
         // 1 for the id
@@ -306,6 +325,8 @@ pub enum StepResponse {
     Balances(Vec<Option<(CiphertextCache, Option<CiphertextCache>, BalanceType)>>),
     // Nonces for requested accounts
     Nonces(Vec<u64>),
+    // Multisig setups for requested accounts, None if the account is not a multisig
+    MultiSig(Vec<Option<MultiSigPayload>>),
     // top blocks metadata
     BlocksMetadata(IndexSet<BlockMetadata>),
 }
@@ -318,6 +339,7 @@ impl StepResponse {
             Self::Keys(_, _) => StepKind::Keys,
             Self::Balances(_) => StepKind::Balances,
             Self::Nonces(_) => StepKind::Nonces,
+            Self::MultiSig(_) => StepKind::MultiSig,
             Self::BlocksMetadata(_) => StepKind::BlocksMetadata
         }
     }
@@ -365,6 +387,9 @@ impl Serializer for StepResponse {
             5 => {
                 Self::BlocksMetadata(IndexSet::read(reader)?)
             },
+            6 => {
+                Self::MultiSig(Vec::read(reader)?)
+            },
             id => {
                 debug!("Received invalid value for StepResponse: {}", id);
                 return Err(ReaderError::InvalidValue)
@@ -402,6 +427,10 @@ impl Serializer for StepResponse {
             Self::BlocksMetadata(blocks) => {
                 writer.write_u8(5);
                 blocks.write(writer);
+            },
+            Self::MultiSig(setups) => {
+                writer.write_u8(6);
+                setups.write(writer);
             }
         };
     }
@@ -425,6 +454,9 @@ impl Serializer for StepResponse {
             },
             Self::BlocksMetadata(blocks) => {
                 blocks.size()
+            },
+            Self::MultiSig(setups) => {
+                setups.size()
             }
         };
         // 1 for the id
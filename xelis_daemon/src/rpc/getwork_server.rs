@@ -48,6 +48,7 @@ use xelis_common::{
         SubmitBlockParams
     },
     serializer::Serializer,
+    network::Network,
     block::{
         BlockHeader,
         BlockMiner
@@ -86,7 +87,7 @@ impl TMessage for Response {
 
 pub struct Miner {
     // Used to display correctly its address
-    mainnet: bool,
+    network: Network,
     // timestamp of first connection
     first_seen: TimestampMillis,
     // public key of account (address)
@@ -100,9 +101,9 @@ pub struct Miner {
 }
 
 impl Miner {
-    pub fn new(mainnet: bool, key: PublicKey, name: String) -> Self {
+    pub fn new(network: Network, key: PublicKey, name: String) -> Self {
         Self {
-            mainnet,
+            network,
             first_seen: get_current_time_in_millis(),
             key,
             name,
@@ -130,7 +131,7 @@ impl Miner {
 
 impl Display for Miner {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Miner[address={}, name={}, accepted={}, rejected={}]", self.key.as_address(self.mainnet), self.name, self.blocks_accepted, self.blocks_rejected)
+        write!(f, "Miner[address={}, name={}, accepted={}, rejected={}]", self.key.as_address(self.network), self.name, self.blocks_accepted, self.blocks_rejected)
     }
 }
 
@@ -285,7 +286,7 @@ impl<S: Storage> GetWorkServer<S> {
         trace!("add miner");
         {
             let mut miners = self.miners.lock().await;
-            let miner = Miner::new(self.blockchain.get_network().is_mainnet(), key.clone(), worker);
+            let miner = Miner::new(*self.blockchain.get_network(), key.clone(), worker);
             debug!("Adding new miner to GetWork server: {}", miner);
             miners.insert(addr.clone(), miner);
         }
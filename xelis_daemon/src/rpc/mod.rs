@@ -204,8 +204,8 @@ async fn getwork_endpoint<S: Storage>(server: Data<DaemonRpcServer<S>>, request:
             }
 
             let network = server.get_rpc_handler().get_data().get_network();
-            if address.is_mainnet() != network.is_mainnet() {
-                return Ok(HttpResponse::BadRequest().body(format!("Address is not in same network state, should be in {} mode", network.to_string().to_lowercase())))
+            if address.get_network() != *network {
+                return Ok(HttpResponse::BadRequest().body(format!("Address is for {} but the node is running on {}, its prefix must be '{}'", address.get_network(), network, network.address_prefix())))
             }
 
             let key = address.to_public_key();
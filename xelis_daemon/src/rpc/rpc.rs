@@ -1,15 +1,17 @@
 use crate::{
     config::{
+        get_hard_forks,
         BLOCK_TIME_MILLIS,
         DEV_FEES,
-        DEV_PUBLIC_KEY
+        DEV_PUBLIC_KEY,
+        MILLIS_PER_SECOND
     },
     core::{
         blockchain::{
             get_block_dev_fee,
-            get_block_reward,
             Blockchain
         },
+        emission::{get_block_reward, get_projected_supply},
         error::BlockchainError,
         mempool::Mempool,
         storage::Storage
@@ -26,6 +28,7 @@ use xelis_common::{
             BlockType,
             GetAccountAssetsParams,
             GetAccountHistoryParams,
+            GetAccountTransactionsAtTopoHeightParams,
             GetAccountsParams,
             GetAssetParams,
             GetAssetsParams,
@@ -56,12 +59,27 @@ use xelis_common::{
             PeerEntry,
             SizeOnDiskResult,
             SubmitBlockParams,
+            GenerateBlocksParams,
+            GetSupplyParams,
+            GetSupplyResult,
+            GetAssetSupplyParams,
+            GetAssetSupplyResult,
+            GetEmissionAtHeightParams,
+            GetEmissionAtHeightResult,
             SubmitTransactionParams,
             TransactionResponse,
+            GetFilteredMempoolParams,
+            GetFilteredMempoolResult,
+            GetMempoolStatsParams,
+            GetMempoolStatsResult,
+            FeeHistogramBucket,
             GetMempoolCacheParams,
             IsAccountRegisteredParams,
             GetAccountRegistrationParams,
+            GetMultiSigParams,
+            GetMultiSigResult,
         },
+        RPCMultiSigPayload,
         RPCTransaction,
         RPCTransactionType as RPCTransactionType
     },
@@ -71,18 +89,20 @@ use xelis_common::{
         BlockHeader
     },
     config::{
+        FEE_PER_KB,
         MAXIMUM_SUPPLY,
         MAX_TRANSACTION_SIZE,
         VERSION,
         XELIS_ASSET
     },
     context::Context,
-    crypto::Hash,
+    crypto::{Address, Hash, Hashable},
     difficulty::{
         CumulativeDifficulty,
         Difficulty
     },
     immutable::Immutable,
+    network::Network,
     rpc_server::{
         parse_params,
         RPCHandler
@@ -97,7 +117,8 @@ use xelis_common::{
 use anyhow::Context as AnyContext;
 use human_bytes::human_bytes;
 use serde_json::{json, Value};
-use std::{sync::Arc, borrow::Cow};
+use std::{sync::Arc, borrow::Cow, collections::HashSet};
+use tokio::task;
 use log::{info, debug, trace};
 
 // Get the block type using the block hash and the blockchain current state
@@ -149,20 +170,21 @@ pub async fn get_block_response<S: Storage>(blockchain: &Blockchain<S>, storage:
         }
     }
 
-    let mainnet = blockchain.get_network().is_mainnet();
+    let network = *blockchain.get_network();
     let header = block.get_header();
     let transactions = block.get_transactions()
         .iter().zip(block.get_txs_hashes()).map(|(tx, hash)| {
             RPCTransaction {
                 hash: Cow::Borrowed(hash),
                 version: tx.get_version(),
-                source: tx.get_source().as_address(mainnet),
-                data: RPCTransactionType::from_type(tx.get_data(), mainnet),
+                source: tx.get_source().as_address(network),
+                data: RPCTransactionType::from_type(tx.get_data(), network),
                 fee: tx.get_fee(),
                 nonce: tx.get_nonce(),
                 source_commitments: Cow::Borrowed(tx.get_source_commitments()),
                 range_proof: Cow::Borrowed(tx.get_range_proof()),
                 reference: Cow::Borrowed(tx.get_reference()),
+                multisig: tx.get_multisig().as_ref().map(Cow::Borrowed),
                 signature: Cow::Borrowed(tx.get_signature()),
             }
         }).collect::<Vec<RPCTransaction<'_>>>();
@@ -182,7 +204,7 @@ pub async fn get_block_response<S: Storage>(blockchain: &Blockchain<S>, storage:
         nonce: header.get_nonce(),
         height: header.get_height(),
         version: header.get_version(),
-        miner: Cow::Owned(header.get_miner().as_address(mainnet)),
+        miner: Cow::Owned(header.get_miner().as_address(network)),
         tips: Cow::Borrowed(header.get_tips()),
         txs_hashes: Cow::Borrowed(header.get_txs_hashes()),
         transactions
@@ -209,7 +231,7 @@ pub async fn get_block_response_for_hash<S: Storage>(blockchain: &Blockchain<S>,
             total_size_in_bytes += storage.get_transaction_size(tx_hash).await.context(format!("Error while retrieving transaction {hash} size"))?;
         }
 
-        let mainnet = blockchain.get_network().is_mainnet();
+        let network = *blockchain.get_network();
 
         json!(RPCBlockResponse {
             hash: Cow::Borrowed(hash),
@@ -226,7 +248,7 @@ pub async fn get_block_response_for_hash<S: Storage>(blockchain: &Blockchain<S>,
             nonce: header.get_nonce(),
             height: header.get_height(),
             version: header.get_version(),
-            miner: Cow::Owned(header.get_miner().as_address(mainnet)),
+            miner: Cow::Owned(header.get_miner().as_address(network)),
             tips: Cow::Borrowed(header.get_tips()),
             txs_hashes: Cow::Borrowed(header.get_txs_hashes()),
             transactions: Vec::with_capacity(0),
@@ -237,16 +259,21 @@ pub async fn get_block_response_for_hash<S: Storage>(blockchain: &Blockchain<S>,
 }
 
 // Transaction response based on data in chain/mempool and from parameters
-pub async fn get_transaction_response<S: Storage>(storage: &S, tx: &Arc<Transaction>, hash: &Hash, in_mempool: bool, first_seen: Option<TimestampSeconds>) -> Result<Value, InternalRpcError> {
+pub async fn build_transaction_response<'a, S: Storage>(storage: &S, tx: &'a Arc<Transaction>, hash: &'a Hash, in_mempool: bool, first_seen: Option<TimestampSeconds>) -> Result<TransactionResponse<'a>, InternalRpcError> {
     let blocks = if storage.has_tx_blocks(hash).context("Error while checking if tx in included in blocks")? {
         Some(storage.get_blocks_for_tx(hash).context("Error while retrieving in which blocks its included")?)
     } else {
         None
     };
 
-    let data = RPCTransaction::from_tx(tx, hash, storage.is_mainnet());
+    let data = RPCTransaction::from_tx(tx, hash, storage.network());
     let executed_in_block = storage.get_block_executor_for_tx(hash).ok();
-    Ok(json!(TransactionResponse { blocks, executed_in_block, data, in_mempool, first_seen }))
+    Ok(TransactionResponse { blocks, executed_in_block, data, in_mempool, first_seen })
+}
+
+// Same as above but serialized
+pub async fn get_transaction_response<S: Storage>(storage: &S, tx: &Arc<Transaction>, hash: &Hash, in_mempool: bool, first_seen: Option<TimestampSeconds>) -> Result<Value, InternalRpcError> {
+    Ok(json!(build_transaction_response(storage, tx, hash, in_mempool, first_seen).await?))
 }
 
 // first check on disk, then check in mempool
@@ -282,6 +309,15 @@ pub async fn get_peer_entry(peer: &Peer) -> PeerEntry {
     }
 }
 
+// Verify that the address is for the network of this node
+fn verify_address_network(address: &Address, network: &Network) -> Result<(), InternalRpcError> {
+    if address.get_network() != *network {
+        return Err(InternalRpcError::AnyError(BlockchainError::InvalidAddressNetwork(address.get_network(), *network).into()))
+    }
+
+    Ok(())
+}
+
 // This function is used to register all the RPC methods
 pub fn register_methods<S: Storage>(handler: &mut RPCHandler<Arc<Blockchain<S>>>) {
     info!("Registering RPC methods...");
@@ -295,35 +331,45 @@ pub fn register_methods<S: Storage>(handler: &mut RPCHandler<Arc<Blockchain<S>>>
     handler.register_method("get_block_by_hash", async_handler!(get_block_by_hash::<S>));
     handler.register_method("get_top_block", async_handler!(get_top_block::<S>));
     handler.register_method("submit_block", async_handler!(submit_block::<S>));
+    handler.register_method("generate_blocks", async_handler!(generate_blocks::<S>));
     handler.register_method("get_balance", async_handler!(get_balance::<S>));
     handler.register_method("has_balance", async_handler!(has_balance::<S>));
     handler.register_method("get_balance_at_topoheight", async_handler!(get_balance_at_topoheight::<S>));
     handler.register_method("get_info", async_handler!(get_info::<S>));
+    handler.register_method("get_supply", async_handler!(get_supply::<S>));
+    handler.register_method("get_asset_supply", async_handler!(get_asset_supply::<S>));
+    handler.register_method("get_emission_at_height", async_handler!(get_emission_at_height::<S>));
     handler.register_method("get_nonce", async_handler!(get_nonce::<S>));
     handler.register_method("has_nonce", async_handler!(has_nonce::<S>));
     handler.register_method("get_nonce_at_topoheight", async_handler!(get_nonce_at_topoheight::<S>));
+    handler.register_method("get_multisig", async_handler!(get_multisig::<S>));
     handler.register_method("get_asset", async_handler!(get_asset::<S>));
     handler.register_method("get_assets", async_handler!(get_assets::<S>));
     handler.register_method("count_assets", async_handler!(count_assets::<S>));
     handler.register_method("count_accounts", async_handler!(count_accounts::<S>));
     handler.register_method("count_transactions", async_handler!(count_transactions::<S>));
     handler.register_method("submit_transaction", async_handler!(submit_transaction::<S>));
+    handler.register_method("validate_transaction", async_handler!(validate_transaction::<S>));
     handler.register_method("get_transaction", async_handler!(get_transaction::<S>));
     handler.register_method("p2p_status", async_handler!(p2p_status::<S>));
     handler.register_method("get_peers", async_handler!(get_peers::<S>));
     handler.register_method("get_mempool", async_handler!(get_mempool::<S>));
+    handler.register_method("get_filtered_mempool", async_handler!(get_filtered_mempool::<S>));
+    handler.register_method("get_mempool_stats", async_handler!(get_mempool_stats::<S>));
     handler.register_method("get_tips", async_handler!(get_tips::<S>));
     handler.register_method("get_dag_order", async_handler!(get_dag_order::<S>));
     handler.register_method("get_blocks_range_by_topoheight", async_handler!(get_blocks_range_by_topoheight::<S>));
     handler.register_method("get_blocks_range_by_height", async_handler!(get_blocks_range_by_height::<S>));
     handler.register_method("get_transactions", async_handler!(get_transactions::<S>));
     handler.register_method("get_account_history", async_handler!(get_account_history::<S>));
+    handler.register_method("get_account_transactions_at_topoheight", async_handler!(get_account_transactions_at_topoheight::<S>));
     handler.register_method("get_account_assets", async_handler!(get_account_assets::<S>));
     handler.register_method("get_accounts", async_handler!(get_accounts::<S>));
     handler.register_method("is_account_registered", async_handler!(is_account_registered::<S>));
     handler.register_method("get_account_registration_topoheight", async_handler!(get_account_registration_topoheight::<S>));
     handler.register_method("is_tx_executed_in_block", async_handler!(is_tx_executed_in_block::<S>));
     handler.register_method("get_dev_fee_thresholds", async_handler!(get_dev_fee_thresholds::<S>));
+    handler.register_method("get_hard_forks", async_handler!(get_hard_forks_schedule::<S>));
     handler.register_method("get_size_on_disk", async_handler!(get_size_on_disk::<S>));
     handler.register_method("get_mempool_cache", async_handler!(get_mempool_cache::<S>));
 }
@@ -389,9 +435,7 @@ async fn get_block_template<S: Storage>(context: Context, body: Value) -> Result
     }
 
     let blockchain: &Arc<Blockchain<S>> = context.get()?;
-    if params.address.is_mainnet() != blockchain.get_network().is_mainnet() {
-        return Err(InternalRpcError::AnyError(BlockchainError::InvalidNetwork.into()))
-    }
+    verify_address_network(&params.address, blockchain.get_network())?;
 
     let storage = blockchain.get_storage().read().await;
     let block = blockchain.get_block_template_for_storage(&storage, params.address.into_owned().to_public_key()).await.context("Error while retrieving block template")?;
@@ -410,12 +454,43 @@ async fn submit_block<S: Storage>(context: Context, body: Value) -> Result<Value
     Ok(json!(true))
 }
 
+// Maximum blocks generated per request
+const MAX_GENERATE_BLOCKS: u64 = 1000;
+
+// Mine instantly the requested blocks
+// This is only available on dev network as its difficulty is trivial
+async fn generate_blocks<S: Storage>(context: Context, body: Value) -> Result<Value, InternalRpcError> {
+    let params: GenerateBlocksParams = parse_params(body)?;
+    if !params.address.is_normal() {
+        return Err(InternalRpcError::AnyError(ApiError::ExpectedNormalAddress.into()))
+    }
+
+    let blockchain: &Arc<Blockchain<S>> = context.get()?;
+    if *blockchain.get_network() != Network::Dev {
+        return Err(InternalRpcError::CustomStr("Blocks can only be generated on dev network"))
+    }
+    verify_address_network(&params.address, blockchain.get_network())?;
+
+    if params.amount == 0 || params.amount > MAX_GENERATE_BLOCKS {
+        return Err(InternalRpcError::InvalidRequest).context(format!("Invalid amount of blocks requested, received {} but maximum is {}", params.amount, MAX_GENERATE_BLOCKS))?
+    }
+
+    let key = params.address.into_owned().to_public_key();
+    let mut hashes = Vec::with_capacity(params.amount as usize);
+    for _ in 0..params.amount {
+        let block = blockchain.mine_block(&key).await.context("Error while generating block")?;
+        let hash = block.hash();
+        blockchain.add_new_block(block, true, true).await.context("Error while adding generated block to chain")?;
+        hashes.push(hash);
+    }
+
+    Ok(json!(hashes))
+}
+
 async fn get_balance<S: Storage>(context: Context, body: Value) -> Result<Value, InternalRpcError> {
     let params: GetBalanceParams = parse_params(body)?;
     let blockchain: &Arc<Blockchain<S>> = context.get()?;
-    if params.address.is_mainnet() != blockchain.get_network().is_mainnet() {
-        return Err(InternalRpcError::AnyError(BlockchainError::InvalidNetwork.into()))
-    }
+    verify_address_network(&params.address, blockchain.get_network())?;
 
     let storage = blockchain.get_storage().read().await;
     let (topoheight, version) = storage.get_last_balance(params.address.get_public_key(), &params.asset).await.context("Error while retrieving last balance")?;
@@ -428,9 +503,7 @@ async fn get_balance<S: Storage>(context: Context, body: Value) -> Result<Value,
 async fn has_balance<S: Storage>(context: Context, body: Value) -> Result<Value, InternalRpcError> {
     let params: HasBalanceParams = parse_params(body)?;
     let blockchain: &Arc<Blockchain<S>> = context.get()?;
-    if params.address.is_mainnet() != blockchain.get_network().is_mainnet() {
-        return Err(InternalRpcError::AnyError(BlockchainError::InvalidNetwork.into()))
-    }
+    verify_address_network(&params.address, blockchain.get_network())?;
 
     let key = params.address.get_public_key();
     let storage = blockchain.get_storage().read().await;
@@ -492,9 +565,7 @@ async fn get_balance_at_topoheight<S: Storage>(context: Context, body: Value) ->
         return Err(InternalRpcError::UnexpectedParams).context("Topoheight cannot be greater than current chain topoheight")?
     }
 
-    if params.address.is_mainnet() != blockchain.get_network().is_mainnet() {
-        return Err(InternalRpcError::AnyError(BlockchainError::InvalidNetwork.into()))
-    }
+    verify_address_network(&params.address, blockchain.get_network())?;
 
     let storage = blockchain.get_storage().read().await;
     let balance = storage.get_balance_at_exact_topoheight(params.address.get_public_key(), &params.asset, params.topoheight).await.context("Error while retrieving balance at exact topo height")?;
@@ -504,9 +575,7 @@ async fn get_balance_at_topoheight<S: Storage>(context: Context, body: Value) ->
 async fn has_nonce<S: Storage>(context: Context, body: Value) -> Result<Value, InternalRpcError> {
     let params: HasNonceParams = parse_params(body)?;
     let blockchain: &Arc<Blockchain<S>> = context.get()?;
-    if params.address.is_mainnet() != blockchain.get_network().is_mainnet() {
-        return Err(InternalRpcError::AnyError(BlockchainError::InvalidNetwork.into()))
-    }
+    verify_address_network(&params.address, blockchain.get_network())?;
 
     let storage = blockchain.get_storage().read().await;
     let exist = if let Some(topoheight) = params.topoheight {
@@ -518,12 +587,132 @@ async fn has_nonce<S: Storage>(context: Context, body: Value) -> Result<Value, I
     Ok(json!(HasNonceResult { exist }))
 }
 
+// Verify that the topoheight requested is in the available range of the chain
+async fn get_available_topoheight<S: Storage>(blockchain: &Blockchain<S>, storage: &S, topoheight: Option<u64>) -> Result<u64, InternalRpcError> {
+    let current_topoheight = blockchain.get_topo_height();
+    let Some(topoheight) = topoheight else {
+        return Ok(current_topoheight)
+    };
+
+    if topoheight > current_topoheight {
+        return Err(InternalRpcError::CustomStr("Topoheight is higher than current topoheight"))
+    }
+
+    let pruned_topoheight = storage.get_pruned_topoheight().await.context("Error while retrieving pruned topoheight")?.unwrap_or(0);
+    if topoheight < pruned_topoheight {
+        return Err(InternalRpcError::CustomStr("Topoheight is lower than pruned topoheight"))
+    }
+
+    Ok(topoheight)
+}
+
+// Get the burned supply of an asset, only if all the burns of the chain are known by this node
+async fn get_burned_supply<S: Storage>(storage: &S, asset: &Hash, topoheight: u64) -> Result<u64, InternalRpcError> {
+    match storage.get_burned_supply_indexed_from().context("Error while retrieving burned supply index")? {
+        Some(0) => Ok(storage.get_burned_supply_at_maximum_topoheight(asset, topoheight).await.context("Error while retrieving burned supply")?),
+        Some(from) => Err(InternalRpcError::Custom(format!("Burned supply is unavailable, the burns below topoheight {} were pruned or not synced by this node", from))),
+        None => Err(InternalRpcError::CustomStr("Burned supply is unavailable, it is not indexed yet"))
+    }
+}
+
+async fn get_supply<S: Storage>(context: Context, body: Value) -> Result<Value, InternalRpcError> {
+    let params: GetSupplyParams = parse_params(body)?;
+    let blockchain: &Arc<Blockchain<S>> = context.get()?;
+    let storage = blockchain.get_storage().read().await;
+    let topoheight = get_available_topoheight(blockchain, &storage, params.topoheight).await?;
+
+    let emitted_supply = storage.get_supply_at_topo_height(topoheight).await.context("Error while retrieving supply at topo height")?;
+    let burned_supply = get_burned_supply(&*storage, &XELIS_ASSET, topoheight).await?;
+
+    Ok(json!(GetSupplyResult {
+        topoheight,
+        emitted_supply,
+        burned_supply,
+        circulating_supply: emitted_supply.saturating_sub(burned_supply),
+        maximum_supply: MAXIMUM_SUPPLY
+    }))
+}
+
+async fn get_asset_supply<S: Storage>(context: Context, body: Value) -> Result<Value, InternalRpcError> {
+    let params: GetAssetSupplyParams = parse_params(body)?;
+    let blockchain: &Arc<Blockchain<S>> = context.get()?;
+    let storage = blockchain.get_storage().read().await;
+    if !storage.has_asset(&params.asset).await.context("Error while checking if asset exists")? {
+        return Err(InternalRpcError::CustomStr("Asset is not registered"))
+    }
+
+    let topoheight = get_available_topoheight(blockchain, &storage, params.topoheight).await?;
+    let emitted_supply = if *params.asset == XELIS_ASSET {
+        Some(storage.get_supply_at_topo_height(topoheight).await.context("Error while retrieving supply at topo height")?)
+    } else {
+        None
+    };
+    let burned_supply = get_burned_supply(&*storage, &params.asset, topoheight).await?;
+
+    Ok(json!(GetAssetSupplyResult {
+        asset: params.asset,
+        topoheight,
+        emitted_supply,
+        burned_supply
+    }))
+}
+
+// Maximum blocks projected above the chain height, around one year of blocks
+const MAX_PROJECTED_BLOCKS: u64 = 365 * 24 * 60 * 60 * MILLIS_PER_SECOND / BLOCK_TIME_MILLIS;
+
+async fn get_emission_at_height<S: Storage>(context: Context, body: Value) -> Result<Value, InternalRpcError> {
+    let params: GetEmissionAtHeightParams = parse_params(body)?;
+    let blockchain: &Arc<Blockchain<S>> = context.get()?;
+    let storage = blockchain.get_storage().read().await;
+    let height = blockchain.get_height();
+
+    if params.height > height {
+        let blocks = params.height - height;
+        if blocks > MAX_PROJECTED_BLOCKS {
+            return Err(InternalRpcError::InvalidRequest).context(format!("Height requested is too far from chain height, maximum is {}", height + MAX_PROJECTED_BLOCKS))?
+        }
+
+        // Project from the latest supply, the block at requested height is the last one
+        let supply = storage.get_supply_at_topo_height(blockchain.get_topo_height()).await.context("Error while retrieving supply at topo height")?;
+        // Don't keep the storage locked while projecting
+        drop(storage);
+        let past_supply = task::spawn_blocking(move || get_projected_supply(supply, blocks - 1)).await
+            .context("Error while projecting supply")?;
+        let block_reward = get_block_reward(past_supply);
+
+        return Ok(json!(GetEmissionAtHeightResult {
+            height: params.height,
+            supply: past_supply + block_reward,
+            block_reward,
+            projected: true
+        }))
+    }
+
+    // Use the last block ordered at this height
+    let mut topoheight = None;
+    for hash in storage.get_blocks_at_height(params.height).await.context("Error while retrieving blocks at height")? {
+        if storage.is_block_topological_ordered(&hash).await {
+            let topo = storage.get_topo_height_for_hash(&hash).await.context("Error while retrieving topoheight for block")?;
+            topoheight = Some(topoheight.map_or(topo, |current: u64| current.max(topo)));
+        }
+    }
+
+    let topoheight = topoheight.ok_or(InternalRpcError::CustomStr("No block ordered at this height"))?;
+    let supply = storage.get_supply_at_topo_height(topoheight).await.context("Error while retrieving supply at topo height")?;
+    let block_reward = storage.get_block_reward_at_topo_height(topoheight).context("Error while retrieving block reward")?;
+
+    Ok(json!(GetEmissionAtHeightResult {
+        height: params.height,
+        supply,
+        block_reward,
+        projected: false
+    }))
+}
+
 async fn get_nonce<S: Storage>(context: Context, body: Value) -> Result<Value, InternalRpcError> {
     let params: GetNonceParams = parse_params(body)?;
     let blockchain: &Arc<Blockchain<S>> = context.get()?;
-    if params.address.is_mainnet() != blockchain.get_network().is_mainnet() {
-        return Err(InternalRpcError::AnyError(BlockchainError::InvalidNetwork.into()))
-    }
+    verify_address_network(&params.address, blockchain.get_network())?;
 
     let storage = blockchain.get_storage().read().await;
     let (topoheight, version) = storage.get_last_nonce(params.address.get_public_key()).await
@@ -540,15 +729,29 @@ async fn get_nonce_at_topoheight<S: Storage>(context: Context, body: Value) -> R
         return Err(InternalRpcError::UnexpectedParams).context("Topoheight cannot be greater than current chain topoheight")?
     }
 
-    if params.address.is_mainnet() != blockchain.get_network().is_mainnet() {
-        return Err(InternalRpcError::AnyError(BlockchainError::InvalidNetwork.into()))
-    }
+    verify_address_network(&params.address, blockchain.get_network())?;
 
     let storage = blockchain.get_storage().read().await;
     let nonce = storage.get_nonce_at_exact_topoheight(params.address.get_public_key(), params.topoheight).await.context("Error while retrieving nonce at exact topo height")?;
     Ok(json!(nonce))
 }
 
+async fn get_multisig<S: Storage>(context: Context, body: Value) -> Result<Value, InternalRpcError> {
+    let params: GetMultiSigParams = parse_params(body)?;
+    let blockchain: &Arc<Blockchain<S>> = context.get()?;
+    verify_address_network(&params.address, blockchain.get_network())?;
+
+    let storage = blockchain.get_storage().read().await;
+    let result = storage.get_multisig_at_maximum_topoheight(params.address.get_public_key(), blockchain.get_topo_height()).await
+        .context("Error while retrieving multisig for account")?
+        .map(|(topoheight, version)| {
+            let state = version.get_state().map(|payload| RPCMultiSigPayload::from_payload(payload, storage.network()));
+            GetMultiSigResult { topoheight, state }
+        });
+
+    Ok(json!(result))
+}
+
 async fn get_asset<S: Storage>(context: Context, body: Value) -> Result<Value, InternalRpcError> {
     let params: GetAssetParams = parse_params(body)?;
     let blockchain: &Arc<Blockchain<S>> = context.get()?;
@@ -619,7 +822,26 @@ async fn submit_transaction<S: Storage>(context: Context, body: Value) -> Result
 
     let transaction = Transaction::from_hex(params.data)?;
     let blockchain: &Arc<Blockchain<S>> = context.get()?;
-    blockchain.add_tx_to_mempool(transaction, true).await.map_err(|e| InternalRpcError::AnyError(e.into()))?;
+    blockchain.submit_local_tx(transaction).await.map_err(|e| InternalRpcError::AnyError(e.into()))?;
+    Ok(json!(true))
+}
+
+// Verify a transaction like submit_transaction but without adding it to the mempool nor broadcasting it
+// It is disabled by default as the proofs are verified for free
+async fn validate_transaction<S: Storage>(context: Context, body: Value) -> Result<Value, InternalRpcError> {
+    let blockchain: &Arc<Blockchain<S>> = context.get()?;
+    if !blockchain.is_tx_validation_rpc_enabled() {
+        return Err(InternalRpcError::CustomStr("Transaction validation is disabled on this node, start it with --enable-tx-validation-rpc"))
+    }
+
+    let params: SubmitTransactionParams = parse_params(body)?;
+    // x2 because of hex encoding
+    if params.data.len() > MAX_TRANSACTION_SIZE * 2 {
+        return Err(InternalRpcError::InvalidRequest).context(format!("Transaction size cannot be greater than {}", human_bytes(MAX_TRANSACTION_SIZE as f64)))?
+    }
+
+    let transaction = Transaction::from_hex(params.data)?;
+    blockchain.simulate_tx(&transaction).await.map_err(|e| InternalRpcError::AnyError(e.into()))?;
     Ok(json!(true))
 }
 
@@ -690,6 +912,17 @@ async fn get_peers<S: Storage>(context: Context, body: Value) -> Result<Value, I
     }
 }
 
+const MAX_MEMPOOL_TXS: usize = 100;
+
+// Check if the TX transfers or burns the asset
+fn is_tx_using_asset(tx: &Transaction, asset: &Hash) -> bool {
+    match tx.get_data() {
+        TransactionType::Transfers(transfers) => transfers.iter().any(|transfer| transfer.get_asset() == asset),
+        TransactionType::Burn(payload) => payload.asset == *asset,
+        TransactionType::MultiSig(_) => false
+    }
+}
+
 async fn get_mempool<S: Storage>(context: Context, body: Value) -> Result<Value, InternalRpcError> {
     if body != Value::Null {
         return Err(InternalRpcError::UnexpectedParams)
@@ -705,6 +938,167 @@ async fn get_mempool<S: Storage>(context: Context, body: Value) -> Result<Value,
     Ok(json!(transactions))
 }
 
+async fn get_filtered_mempool<S: Storage>(context: Context, body: Value) -> Result<Value, InternalRpcError> {
+    // No params is allowed to fetch the first page
+    let params: GetFilteredMempoolParams = if body == Value::Null {
+        GetFilteredMempoolParams::default()
+    } else {
+        parse_params(body)?
+    };
+
+    let maximum = if let Some(maximum) = params.maximum {
+        if maximum > MAX_MEMPOOL_TXS {
+            return Err(InternalRpcError::InvalidRequest).context(format!("Maximum txs requested cannot be greater than {}", MAX_MEMPOOL_TXS))?
+        }
+        maximum
+    } else {
+        MAX_MEMPOOL_TXS
+    };
+    let skip = params.skip.unwrap_or(0);
+
+    let blockchain: &Arc<Blockchain<S>> = context.get()?;
+    if let Some(sender) = params.sender.as_ref() {
+        if !sender.is_normal() {
+            return Err(InternalRpcError::AnyError(ApiError::ExpectedNormalAddress.into()))
+        }
+        verify_address_network(sender, blockchain.get_network())?;
+    }
+
+    let storage = blockchain.get_storage().read().await;
+    let mempool = blockchain.get_mempool().read().await;
+
+    // Use the cache of the sender to not iterate over the whole mempool
+    let mut entries = Vec::new();
+    match params.sender.as_ref() {
+        Some(sender) => if let Some(cache) = mempool.get_cache_for(sender.get_public_key()) {
+            for hash in cache.get_txs() {
+                entries.push((hash, mempool.get_sorted_tx(hash).context("Error while retrieving TX from mempool")?));
+            }
+        },
+        None => entries.extend(mempool.get_txs().iter())
+    };
+
+    if let Some(asset) = params.asset.as_ref() {
+        entries.retain(|(_, sorted_tx)| is_tx_using_asset(sorted_tx.get_tx(), asset));
+    }
+
+    let count = entries.len();
+    let total_fees = entries.iter().fold(0u64, |total, (_, sorted_tx)| total.saturating_add(sorted_tx.get_fee()));
+    let total_size: usize = entries.iter().map(|(_, sorted_tx)| sorted_tx.get_size()).sum();
+
+    let transactions = if params.summary {
+        None
+    } else {
+        // Sort them to have stable pages
+        entries.sort_by(|(a_hash, a), (b_hash, b)| a.get_first_seen().cmp(&b.get_first_seen()).then_with(|| a_hash.cmp(b_hash)));
+
+        let mut transactions = Vec::with_capacity(maximum.min(count));
+        for (hash, sorted_tx) in entries.into_iter().skip(skip).take(maximum) {
+            transactions.push(build_transaction_response(&*storage, sorted_tx.get_tx(), hash, true, Some(sorted_tx.get_first_seen())).await?);
+        }
+        Some(transactions)
+    };
+
+    Ok(json!(GetFilteredMempoolResult {
+        count,
+        total_fees,
+        total_size,
+        transactions
+    }))
+}
+
+const MAX_FEE_STATS_BLOCKS: u64 = 100;
+const DEFAULT_FEE_STATS_BLOCKS: u64 = 20;
+// Lower bounds of the fee histogram buckets, in multiples of FEE_PER_KB
+const FEE_HISTOGRAM_BUCKETS: [u64; 9] = [0, 1, 2, 3, 5, 10, 20, 50, 100];
+
+// Fee paid per KB, used to compare TXs of different sizes
+fn get_fee_per_kb(fee: u64, size: usize) -> u64 {
+    u64::try_from(fee as u128 * 1024 / size.max(1) as u128).unwrap_or(u64::MAX)
+}
+
+// Returns the median of the values, sorting them
+fn get_median(values: &mut [u64]) -> Option<u64> {
+    if values.is_empty() {
+        return None
+    }
+
+    values.sort_unstable();
+    let middle = values.len() / 2;
+    Some(if values.len() % 2 == 0 {
+        ((values[middle - 1] as u128 + values[middle] as u128) / 2) as u64
+    } else {
+        values[middle]
+    })
+}
+
+// Data needed by wallets to estimate the fees to pay
+async fn get_mempool_stats<S: Storage>(context: Context, body: Value) -> Result<Value, InternalRpcError> {
+    let params: GetMempoolStatsParams = if body == Value::Null {
+        GetMempoolStatsParams::default()
+    } else {
+        parse_params(body)?
+    };
+
+    let blocks = params.blocks.unwrap_or(DEFAULT_FEE_STATS_BLOCKS);
+    if blocks == 0 || blocks > MAX_FEE_STATS_BLOCKS {
+        return Err(InternalRpcError::InvalidRequest).context(format!("Blocks requested must be between 1 and {}", MAX_FEE_STATS_BLOCKS))?
+    }
+
+    let blockchain: &Arc<Blockchain<S>> = context.get()?;
+    let history = blockchain.get_mempool_history().lock().await.iter().cloned().collect();
+
+    let mut fee_histogram: Vec<FeeHistogramBucket> = FEE_HISTOGRAM_BUCKETS.iter().map(|multiplier| FeeHistogramBucket {
+        min_fee_per_kb: multiplier * FEE_PER_KB,
+        count: 0,
+        total_size: 0
+    }).collect();
+    {
+        let mempool = blockchain.get_mempool().read().await;
+        for sorted_tx in mempool.get_txs().values() {
+            let fee_per_kb = get_fee_per_kb(sorted_tx.get_fee(), sorted_tx.get_size());
+            // First bucket starts at 0, so one is always found
+            if let Some(bucket) = fee_histogram.iter_mut().rev().find(|bucket| bucket.min_fee_per_kb <= fee_per_kb) {
+                bucket.count += 1;
+                bucket.total_size += sorted_tx.get_size();
+            }
+        }
+    }
+
+    let storage = blockchain.get_storage().read().await;
+    let top_topoheight = storage.get_top_topoheight().context("Error while retrieving top topoheight")?;
+    let pruned_topoheight = storage.get_pruned_topoheight().await.context("Error while retrieving pruned topoheight")?.unwrap_or(0);
+    let start_topoheight = top_topoheight.saturating_sub(blocks - 1).max(pruned_topoheight);
+
+    // A TX can be included in several blocks, count it only one time
+    let mut included = HashSet::new();
+    let mut fees = Vec::new();
+    let mut fees_per_kb = Vec::new();
+    for topoheight in start_topoheight..=top_topoheight {
+        let hash = storage.get_hash_at_topo_height(topoheight).await.context("Error while retrieving hash at topo height")?;
+        let block = storage.get_block_header_by_hash(&hash).await.context("Error while retrieving block header")?;
+        for tx_hash in block.get_txs_hashes() {
+            if !included.insert(tx_hash.clone()) {
+                continue;
+            }
+
+            let tx = storage.get_transaction(tx_hash).await.context("Error while retrieving transaction")?;
+            let size = storage.get_transaction_size(tx_hash).await.context("Error while retrieving transaction size")?;
+            fees.push(tx.get_fee());
+            fees_per_kb.push(get_fee_per_kb(tx.get_fee(), size));
+        }
+    }
+
+    Ok(json!(GetMempoolStatsResult {
+        history,
+        fee_histogram,
+        blocks: top_topoheight - start_topoheight + 1,
+        transactions: included.len(),
+        median_fee: get_median(&mut fees),
+        median_fee_per_kb: get_median(&mut fees_per_kb)
+    }))
+}
+
 async fn get_blocks_at_height<S: Storage>(context: Context, body: Value) -> Result<Value, InternalRpcError> {
     let params: GetBlocksAtHeightParams = parse_params(body)?;
     let blockchain: &Arc<Blockchain<S>> = context.get()?;
@@ -850,9 +1244,7 @@ const MAX_HISTORY: usize = 20;
 async fn get_account_history<S: Storage>(context: Context, body: Value) -> Result<Value, InternalRpcError> {
     let params: GetAccountHistoryParams = parse_params(body)?;
     let blockchain: &Arc<Blockchain<S>> = context.get()?;
-    if params.address.is_mainnet() != blockchain.get_network().is_mainnet() {
-        return Err(InternalRpcError::AnyError(BlockchainError::InvalidNetwork.into()))
-    }
+    verify_address_network(&params.address, blockchain.get_network())?;
 
     let key = params.address.get_public_key();
     let minimum_topoheight = params.minimum_topoheight.unwrap_or(0);
@@ -923,7 +1315,7 @@ async fn get_account_history<S: Storage>(context: Context, body: Value) -> Resul
                                         topoheight: topo,
                                         hash: tx_hash.clone(),
                                         history_type: AccountHistoryType::Incoming {
-                                            from: tx.get_source().as_address(blockchain.get_network().is_mainnet())
+                                            from: tx.get_source().as_address(*blockchain.get_network())
                                         },
                                         block_timestamp: block_header.get_timestamp()
                                     });
@@ -934,7 +1326,7 @@ async fn get_account_history<S: Storage>(context: Context, body: Value) -> Resul
                                         topoheight: topo,
                                         hash: tx_hash.clone(),
                                         history_type: AccountHistoryType::Outgoing {
-                                            to: transfer.get_destination().as_address(blockchain.get_network().is_mainnet())
+                                            to: transfer.get_destination().as_address(*blockchain.get_network())
                                         },
                                         block_timestamp: block_header.get_timestamp()
                                     });
@@ -953,7 +1345,9 @@ async fn get_account_history<S: Storage>(context: Context, body: Value) -> Resul
                                 });
                             }
                         }
-                    }
+                    },
+                    // No asset is moved, only the fees are paid
+                    TransactionType::MultiSig(_) => {}
                 }
             }
 
@@ -976,12 +1370,38 @@ async fn get_account_history<S: Storage>(context: Context, body: Value) -> Resul
     Ok(json!(history))
 }
 
+// Only the transactions sent or received by the account are returned
+// Light clients verify them against the block header instead of downloading the full block
+async fn get_account_transactions_at_topoheight<S: Storage>(context: Context, body: Value) -> Result<Value, InternalRpcError> {
+    let params: GetAccountTransactionsAtTopoHeightParams = parse_params(body)?;
+    let blockchain: &Arc<Blockchain<S>> = context.get()?;
+    let network = *blockchain.get_network();
+    verify_address_network(&params.address, &network)?;
+
+    let key = params.address.get_public_key();
+    let storage = blockchain.get_storage().read().await;
+    let (hash, block_header) = storage.get_block_header_at_topoheight(params.topoheight).await.context(format!("Error while retrieving block header at topo height {}", params.topoheight))?;
+
+    let mut transactions = Vec::new();
+    for tx_hash in block_header.get_transactions() {
+        let tx = storage.get_transaction(tx_hash).await.context(format!("Error while retrieving transaction {tx_hash} from block {hash}"))?;
+        let involved = *tx.get_source() == *key || match tx.get_data() {
+            TransactionType::Transfers(transfers) => transfers.iter().any(|transfer| *transfer.get_destination() == *key),
+            TransactionType::Burn(_) | TransactionType::MultiSig(_) => false
+        };
+
+        if involved {
+            transactions.push(json!(RPCTransaction::from_tx(&tx, tx_hash, network)));
+        }
+    }
+
+    Ok(json!(transactions))
+}
+
 async fn get_account_assets<S: Storage>(context: Context, body: Value) -> Result<Value, InternalRpcError> {
     let params: GetAccountAssetsParams = parse_params(body)?;
     let blockchain: &Arc<Blockchain<S>> = context.get()?;
-    if params.address.is_mainnet() != blockchain.get_network().is_mainnet() {
-        return Err(InternalRpcError::AnyError(BlockchainError::InvalidNetwork.into()))
-    }
+    verify_address_network(&params.address, blockchain.get_network())?;
 
     let key = params.address.get_public_key();
     let storage = blockchain.get_storage().read().await;
@@ -1027,10 +1447,10 @@ async fn get_accounts<S: Storage>(context: Context, body: Value) -> Result<Value
     };
 
     let storage = blockchain.get_storage().read().await;
-    let mainnet = storage.is_mainnet();
+    let network = storage.network();
     let accounts = storage.get_partial_keys(maximum, skip, minimum_topoheight, maximum_topoheight).await
         .context("Error while retrieving accounts")?
-        .into_iter().map(|key| key.to_address(mainnet)).collect::<Vec<_>>();
+        .into_iter().map(|key| key.to_address(network)).collect::<Vec<_>>();
 
     Ok(json!(accounts))
 }
@@ -1079,6 +1499,16 @@ async fn get_dev_fee_thresholds<S: Storage>(_: Context, body: Value) -> Result<V
     Ok(json!(DEV_FEES))
 }
 
+// Get the hard forks scheduled for the network used
+async fn get_hard_forks_schedule<S: Storage>(context: Context, body: Value) -> Result<Value, InternalRpcError> {
+    if body != Value::Null {
+        return Err(InternalRpcError::UnexpectedParams)
+    }
+
+    let blockchain: &Arc<Blockchain<S>> = context.get()?;
+    Ok(json!(get_hard_forks(blockchain.get_network())))
+}
+
 // Get size on disk of the chain database
 async fn get_size_on_disk<S: Storage>(context: Context, body: Value) -> Result<Value, InternalRpcError> {
     if body != Value::Null {
@@ -1103,13 +1533,35 @@ async fn get_mempool_cache<S: Storage>(context: Context, body: Value) -> Result<
     }
     
     let blockchain: &Arc<Blockchain<S>> = context.get()?;
-    if params.address.is_mainnet() != blockchain.get_network().is_mainnet() {
-        return Err(InternalRpcError::AnyError(BlockchainError::InvalidNetwork.into()))
-    }
+    verify_address_network(&params.address, blockchain.get_network())?;
 
     let mempool = blockchain.get_mempool().read().await;
     let cache = mempool.get_cache_for(params.address.get_public_key())
         .context("Account not found while retrieving mempool cache")?;
 
     Ok(json!(cache))
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_fee_per_kb() {
+        assert_eq!(get_fee_per_kb(1000, 1024), 1000);
+        assert_eq!(get_fee_per_kb(1000, 512), 2000);
+        assert_eq!(get_fee_per_kb(1000, 2048), 500);
+        // Empty size is handled as one byte
+        assert_eq!(get_fee_per_kb(1, 0), 1024);
+        assert_eq!(get_fee_per_kb(u64::MAX, 1), u64::MAX);
+    }
+
+    #[test]
+    fn test_get_median() {
+        assert_eq!(get_median(&mut []), None);
+        assert_eq!(get_median(&mut [5]), Some(5));
+        assert_eq!(get_median(&mut [9, 1, 5]), Some(5));
+        assert_eq!(get_median(&mut [4, 1, 3, 2]), Some(2));
+        assert_eq!(get_median(&mut [u64::MAX, u64::MAX]), Some(u64::MAX));
+    }
+}
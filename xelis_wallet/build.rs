@@ -0,0 +1,11 @@
+// This file is executed before the build and generates the gRPC service
+// from its protobuf definition when the grpc feature is enabled.
+// protoc must be installed to build with this feature.
+
+fn main() {
+    #[cfg(feature = "grpc")]
+    {
+        println!("cargo:rerun-if-changed=proto/wallet.proto");
+        tonic_build::compile_protos("proto/wallet.proto").expect("Failed to compile the wallet protobuf");
+    }
+}
@@ -0,0 +1,185 @@
+use std::{net::SocketAddr, pin::Pin, sync::Arc};
+use futures_util::{stream, Stream};
+use log::info;
+use tonic::{transport::Server, Request, Response, Status};
+use xelis_common::{
+    api::wallet::{self as api, BuildTransactionParams, EntryType},
+    config::XELIS_ASSET,
+    crypto::{Address, Hash, Hashable},
+    serializer::Serializer,
+    transaction::builder::{FeeBuilder, TransactionTypeBuilder, TransferBuilder}
+};
+use crate::wallet::Wallet;
+use super::{
+    rpc::create_transaction,
+    rpc_server::{AuthorizedToken, RPCTokenHandler}
+};
+use self::proto::{
+    transaction_entry::Entry,
+    wallet_service_server::{WalletService, WalletServiceServer},
+    Burn,
+    Coinbase,
+    GetBalanceRequest,
+    GetBalanceResponse,
+    GetHistoryRequest,
+    Incoming,
+    Outgoing,
+    TransactionEntry,
+    TransferIn,
+    TransferOut,
+    TransferRequest,
+    TransferResponse
+};
+
+mod proto {
+    tonic::include_proto!("xelis.wallet");
+}
+
+// Serve the gRPC wallet service until the task is aborted
+pub async fn serve(wallet: Arc<Wallet>, bind_address: SocketAddr) -> Result<(), tonic::transport::Error> {
+    info!("Starting gRPC Server on {}", bind_address);
+    Server::builder()
+        .add_service(WalletServiceServer::new(GrpcWallet { wallet }))
+        .serve(bind_address)
+        .await
+}
+
+struct GrpcWallet {
+    wallet: Arc<Wallet>
+}
+
+impl GrpcWallet {
+    // Every call requires an API token allowing the equivalent RPC method
+    async fn authorize<T>(&self, request: &Request<T>, method: &str) -> Result<AuthorizedToken, Status> {
+        let secret = request.metadata().get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or_else(|| Status::unauthenticated("Missing API token"))?;
+
+        let (name, scope) = self.wallet.get_token_scope(secret).await
+            .map_err(|e| Status::internal(e.to_string()))?
+            .ok_or_else(|| Status::unauthenticated("API token is invalid"))?;
+
+        if !scope.allows(method) {
+            return Err(Status::permission_denied(format!("Method '{}' is not allowed for this API token", method)))
+        }
+
+        Ok(AuthorizedToken { name, scope })
+    }
+}
+
+fn parse_asset(asset: Option<String>) -> Result<Hash, Status> {
+    match asset {
+        Some(asset) if !asset.is_empty() => Hash::from_hex(asset).map_err(|_| Status::invalid_argument("Invalid asset hash")),
+        _ => Ok(XELIS_ASSET)
+    }
+}
+
+impl From<api::TransactionEntry> for TransactionEntry {
+    fn from(value: api::TransactionEntry) -> Self {
+        let entry = match value.entry {
+            EntryType::Coinbase { reward } => Entry::Coinbase(Coinbase { reward }),
+            EntryType::Burn { asset, amount } => Entry::Burn(Burn { asset: asset.to_hex(), amount }),
+            EntryType::Incoming { from, transfers } => Entry::Incoming(Incoming {
+                from: from.to_string(),
+                transfers: transfers.into_iter()
+                    .map(|transfer| TransferIn { asset: transfer.asset.to_hex(), amount: transfer.amount })
+                    .collect()
+            }),
+            EntryType::Outgoing { transfers, fee, nonce } => Entry::Outgoing(Outgoing {
+                transfers: transfers.into_iter()
+                    .map(|transfer| TransferOut { destination: transfer.destination.to_string(), asset: transfer.asset.to_hex(), amount: transfer.amount })
+                    .collect(),
+                fee,
+                nonce
+            })
+        };
+
+        Self {
+            hash: value.hash.to_hex(),
+            topoheight: value.topoheight,
+            entry: Some(entry)
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl WalletService for GrpcWallet {
+    type GetHistoryStream = Pin<Box<dyn Stream<Item = Result<TransactionEntry, Status>> + Send>>;
+
+    async fn get_balance(&self, request: Request<GetBalanceRequest>) -> Result<Response<GetBalanceResponse>, Status> {
+        self.authorize(&request, "get_balance").await?;
+        let asset = parse_asset(request.into_inner().asset)?;
+
+        let balance = self.wallet.get_balance(&asset).await
+            .map_err(|e| Status::internal(e.to_string()))?;
+        let storage = self.wallet.get_storage().read().await;
+        let decimals = storage.get_asset_decimals(&asset)
+            .map_err(|_| Status::not_found("Asset is not tracked by the wallet"))?;
+
+        Ok(Response::new(GetBalanceResponse {
+            balance,
+            decimals: decimals as u32
+        }))
+    }
+
+    async fn get_history(&self, request: Request<GetHistoryRequest>) -> Result<Response<Self::GetHistoryStream>, Status> {
+        self.authorize(&request, "list_transactions").await?;
+        let params = request.into_inner();
+
+        let network = *self.wallet.get_network();
+        let storage = self.wallet.get_storage().read().await;
+        let transactions = storage.get_filtered_transactions(
+            None,
+            params.min_topoheight,
+            params.max_topoheight,
+            params.accept_incoming.unwrap_or(true),
+            params.accept_outgoing.unwrap_or(true),
+            params.accept_coinbase.unwrap_or(true),
+            params.accept_burn.unwrap_or(true),
+            None
+        ).map_err(|e| Status::internal(e.to_string()))?;
+
+        let entries = transactions.into_iter()
+            .map(|tx| Ok(TransactionEntry::from(tx.serializable(network))))
+            .collect::<Vec<_>>();
+
+        Ok(Response::new(Box::pin(stream::iter(entries))))
+    }
+
+    async fn transfer(&self, request: Request<TransferRequest>) -> Result<Response<TransferResponse>, Status> {
+        let token = self.authorize(&request, "send").await?;
+        let params = request.into_inner();
+
+        let network = *self.wallet.get_network();
+        let mut transfers = Vec::with_capacity(params.transfers.len());
+        for transfer in params.transfers {
+            let destination = Address::from_string(&transfer.destination)
+                .map_err(|_| Status::invalid_argument(format!("Invalid address '{}'", transfer.destination)))?;
+            if destination.get_network() != network {
+                return Err(Status::invalid_argument(format!("Address '{}' is not on the same network as the wallet", transfer.destination)))
+            }
+
+            transfers.push(TransferBuilder {
+                asset: parse_asset(transfer.asset)?,
+                amount: transfer.amount,
+                destination,
+                extra_data: None
+            });
+        }
+
+        let tx = create_transaction(&self.wallet, Some(&token), BuildTransactionParams {
+            tx_type: TransactionTypeBuilder::Transfers(transfers),
+            fee: params.fee_multiplier.map(FeeBuilder::Multiplier),
+            broadcast: true,
+            tx_as_hex: false,
+            nonce: params.nonce
+        }).await.map_err(|e| Status::aborted(e.to_string()))?;
+
+        Ok(Response::new(TransferResponse {
+            hash: tx.hash().to_hex(),
+            fee: tx.get_fee(),
+            nonce: tx.get_nonce()
+        }))
+    }
+}
@@ -1,15 +1,19 @@
+mod rest;
 mod rpc;
 mod rpc_server;
 mod xswd;
+#[cfg(feature = "grpc")]
+pub mod grpc;
 
 use serde::ser::Serialize;
 use xelis_common::{api::wallet::NotifyEvent, rpc_server::WebSocketServerHandler};
 
 pub use self::{
-    rpc_server::{WalletRpcServer, WalletRpcServerShared, AuthConfig},
+    rpc_server::{WalletRpcServer, WalletRpcServerShared, AuthConfig, AuthorizedToken, RPCTokenHandler},
     xswd::{
         XSWD,
         AppStateShared,
+        Permission,
         PermissionResult,
         PermissionRequest,
         XSWDPermissionHandler,
@@ -20,7 +24,7 @@ pub use self::{
 
 pub enum APIServer<W>
 where
-    W: Clone + Send + Sync + XSWDPermissionHandler + XSWDNodeMethodHandler + 'static
+    W: Clone + Send + Sync + XSWDPermissionHandler + XSWDNodeMethodHandler + RPCTokenHandler + 'static
 {
     RPCServer(WalletRpcServerShared<W>),
     XSWD(XSWD<W>)
@@ -28,7 +32,7 @@ where
 
 impl<W> APIServer<W>
 where
-    W: Clone + Send + Sync + XSWDPermissionHandler + XSWDNodeMethodHandler + 'static
+    W: Clone + Send + Sync + XSWDPermissionHandler + XSWDNodeMethodHandler + RPCTokenHandler + 'static
 {
     pub async fn notify_event<V: Serialize>(&self, event: &NotifyEvent, value: &V) {
         let json = serde_json::to_value(value).unwrap();
@@ -0,0 +1,82 @@
+use actix_web::{
+    web::{self, Data, Path, Query},
+    HttpRequest,
+    HttpResponse,
+    Responder
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use xelis_common::rpc_server::{RpcRequest, JSON_RPC_VERSION};
+use super::rpc_server::{RPCTokenHandler, WalletRpcServer};
+
+// Small REST surface over the JSON-RPC methods
+// Each route is converted to a RPC request and executed by the same handler,
+// so validation, API token scopes and results are identical to the JSON-RPC API
+
+// Query parameters accepted by GET /history
+// They are the same as the list_transactions params
+#[derive(Serialize, Deserialize)]
+pub struct HistoryQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min_topoheight: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_topoheight: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    accept_incoming: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    accept_outgoing: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    accept_coinbase: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    accept_burn: Option<bool>
+}
+
+// Execute a RPC method and map its response to a REST one
+// The result is returned as is, errors are returned with a 400 status code
+async fn execute<W>(server: &WalletRpcServer<W>, http_request: &HttpRequest, method: &str, params: Value) -> HttpResponse
+where
+    W: Clone + Send + Sync + RPCTokenHandler + 'static
+{
+    let request = RpcRequest {
+        jsonrpc: JSON_RPC_VERSION.to_owned(),
+        id: None,
+        method: method.to_owned(),
+        params: Some(params)
+    };
+
+    match server.execute_request(http_request, request).await {
+        Ok(mut response) => HttpResponse::Ok().json(response["result"].take()),
+        Err(e) => HttpResponse::BadRequest().json(e.to_json()["error"].take())
+    }
+}
+
+// GET /balance/{asset}
+pub async fn get_balance<W>(server: Data<WalletRpcServer<W>>, http_request: HttpRequest, asset: Path<String>) -> impl Responder
+where
+    W: Clone + Send + Sync + RPCTokenHandler + 'static
+{
+    execute(server.as_ref(), &http_request, "get_balance", json!({ "asset": asset.into_inner() })).await
+}
+
+// POST /transfer, body is the same as the send RPC method params
+pub async fn transfer<W>(server: Data<WalletRpcServer<W>>, http_request: HttpRequest, body: web::Bytes) -> impl Responder
+where
+    W: Clone + Send + Sync + RPCTokenHandler + 'static
+{
+    let params: Value = match serde_json::from_slice(&body) {
+        Ok(params) => params,
+        Err(e) => return HttpResponse::BadRequest().json(json!({ "message": format!("Invalid JSON body: {}", e) }))
+    };
+
+    execute(server.as_ref(), &http_request, "send", params).await
+}
+
+// GET /history
+pub async fn get_history<W>(server: Data<WalletRpcServer<W>>, http_request: HttpRequest, query: Query<HistoryQuery>) -> impl Responder
+where
+    W: Clone + Send + Sync + RPCTokenHandler + 'static
+{
+    execute(server.as_ref(), &http_request, "list_transactions", json!(query.into_inner())).await
+}
@@ -16,11 +16,13 @@ use xelis_common::{
             ListTransactionsParams,
             QueryDBParams,
             RescanParams,
+            SendTransactionParams,
             SplitAddressParams,
             SplitAddressResult,
             StoreParams,
             TransactionResponse,
             SetOnlineModeParams,
+            VerifyDataParams,
         },
         DataElement,
         DataHash
@@ -36,14 +38,20 @@ use xelis_common::{
         RPCHandler
     },
     serializer::Serializer,
-    transaction::builder::FeeBuilder
+    transaction::{
+        builder::{FeeBuilder, TransactionTypeBuilder},
+        Transaction
+    }
 };
 use serde_json::{Value, json};
-use crate::wallet::{
-    Wallet,
-    WalletError
+use crate::{
+    api_token::TokenScope,
+    wallet::{
+        Wallet,
+        WalletError
+    }
 };
-use super::xswd::XSWDWebSocketHandler;
+use super::{rpc_server::AuthorizedToken, xswd::XSWDWebSocketHandler};
 use log::info;
 
 // Register all RPC methods
@@ -62,11 +70,15 @@ pub fn register_methods(handler: &mut RPCHandler<Arc<Wallet>>) {
     handler.register_method("get_asset_precision", async_handler!(get_asset_precision));
     handler.register_method("get_transaction", async_handler!(get_transaction));
     handler.register_method("build_transaction", async_handler!(build_transaction));
+    handler.register_method("send", async_handler!(send));
     handler.register_method("list_transactions", async_handler!(list_transactions));
+    // Alias of list_transactions
+    handler.register_method("get_history", async_handler!(list_transactions));
     handler.register_method("is_online", async_handler!(is_online));
     handler.register_method("set_online_mode", async_handler!(set_online_mode));
     handler.register_method("set_offline_mode", async_handler!(set_offline_mode));
     handler.register_method("sign_data", async_handler!(sign_data));
+    handler.register_method("verify_data", async_handler!(verify_data));
     handler.register_method("estimate_fees", async_handler!(estimate_fees));
 
     // These functions allow to have an encrypted DB directly in the wallet storage
@@ -166,11 +178,10 @@ async fn get_balance(context: Context, body: Value) -> Result<Value, InternalRpc
     let params: GetBalanceParams = parse_params(body)?;
     let asset = params.asset.unwrap_or(XELIS_ASSET);
     let wallet: &Arc<Wallet> = context.get()?;
-    let storage = wallet.get_storage().read().await;
 
     // If the asset is not found, it will returns 0
     // Use has_balance below to check if the wallet has a balance for a specific asset
-    let balance = storage.get_plaintext_balance_for(&asset).await.unwrap_or(0);
+    let balance = wallet.get_balance(&asset).await.context("Error while retrieving balance")?;
     Ok(json!(balance))
 }
 
@@ -216,13 +227,46 @@ async fn get_transaction(context: Context, body: Value) -> Result<Value, Interna
     let storage = wallet.get_storage().read().await;
     let transaction = storage.get_transaction(&params.hash)?;
 
-    Ok(json!(transaction.serializable(wallet.get_network().is_mainnet())))
+    Ok(json!(transaction.serializable(*wallet.get_network())))
 }
 
 // Build a transaction and broadcast it if requested
 async fn build_transaction(context: Context, body: Value) -> Result<Value, InternalRpcError> {
     let params: BuildTransactionParams = parse_params(body)?;
     let wallet: &Arc<Wallet> = context.get()?;
+    let tx_as_hex = params.tx_as_hex;
+    let tx = create_transaction(wallet, context.get_optional(), params).await?;
+    Ok(transaction_response(tx, tx_as_hex))
+}
+
+// Build and broadcast a transaction in one call
+async fn send(context: Context, body: Value) -> Result<Value, InternalRpcError> {
+    let params: SendTransactionParams = parse_params(body)?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    let tx = create_transaction(wallet, context.get_optional(), BuildTransactionParams {
+        tx_type: params.tx_type,
+        fee: params.fee,
+        broadcast: true,
+        tx_as_hex: false,
+        nonce: params.nonce
+    }).await?;
+    Ok(transaction_response(tx, false))
+}
+
+// Amount of XELIS sent or burned, None if another asset is used
+fn get_xelis_amount(tx_type: &TransactionTypeBuilder) -> Option<u64> {
+    match tx_type {
+        TransactionTypeBuilder::Transfers(transfers) => transfers.iter()
+            .map(|transfer| (transfer.asset == XELIS_ASSET).then_some(transfer.amount))
+            .try_fold(0u64, |total, amount| total.checked_add(amount?)),
+        TransactionTypeBuilder::Burn(payload) => (payload.asset == XELIS_ASSET).then_some(payload.amount),
+        // Only the fee is paid, limited to admin tokens
+        TransactionTypeBuilder::MultiSig(_) => Some(0)
+    }
+}
+
+// The API token used, if any, limits the amount that can be spent
+pub(super) async fn create_transaction(wallet: &Arc<Wallet>, token: Option<&AuthorizedToken>, params: BuildTransactionParams) -> Result<Transaction, InternalRpcError> {
     // request ask to broadcast the TX but wallet is not connected to any daemon
     if !wallet.is_online().await && params.broadcast {
         return Err(WalletError::NotOnlineMode).context("Cannot broadcast TX")?
@@ -232,18 +276,68 @@ async fn build_transaction(context: Context, body: Value) -> Result<Value, Inter
         return Err(InternalRpcError::CustomStr("Invalid params, should either be broadcasted, or returned in hex format"))
     }
 
+    let mut storage = wallet.get_storage().write().await;
+    // Same as the CLI: a nonce can only re-use a pending one or be the next one
+    if let Some(nonce) = params.nonce {
+        let current = storage.get_nonce().unwrap_or(0);
+        if nonce > current {
+            return Err(InternalRpcError::Custom(format!("Nonce cannot be higher than the next nonce {}", current)))
+        }
+    }
+
+    // Load the token from storage to have its latest spending
+    let mut limited_token = match token {
+        Some(token) => {
+            let api_token = storage.get_api_token(&token.name)?;
+            // Changing the multisig setup gives away the control of the account
+            if matches!(params.tx_type, TransactionTypeBuilder::MultiSig(_)) && api_token.get_scope() != TokenScope::Admin {
+                return Err(InternalRpcError::AnyError(WalletError::ApiTokenMultiSigNotAllowed(token.name.clone()).into()))
+            }
+
+            match api_token.get_spend_limit() {
+                Some(_) => {
+                    let amount = get_xelis_amount(&params.tx_type)
+                        .ok_or_else(|| InternalRpcError::AnyError(WalletError::ApiTokenAssetNotAllowed(token.name.clone()).into()))?;
+                    Some((token, api_token, amount))
+                },
+                None => None
+            }
+        },
+        None => None
+    };
+
     // create the TX
-    let tx = wallet.create_transaction(params.tx_type, params.fee.unwrap_or(FeeBuilder::Multiplier(1f64))).await
+    let (mut state, tx) = wallet.create_transaction_with_nonce(&mut storage, params.tx_type, params.fee.unwrap_or(FeeBuilder::Multiplier(1f64)), params.nonce).await
         .context("Error while creating transaction")?;
 
+    // Fees are included in the spending
+    if let Some((token, api_token, amount)) = limited_token.as_mut() {
+        *amount = amount.saturating_add(tx.get_fee());
+        if !api_token.can_spend(*amount) {
+            return Err(InternalRpcError::AnyError(WalletError::ApiTokenSpendLimitExceeded(token.name.clone()).into()))
+        }
+    }
+
     // if requested, broadcast the TX ourself
+    // changes are applied only if the daemon accepted it
     if params.broadcast {
-        wallet.submit_transaction(&tx).await.context("Couldn't broadcast transaction")?;
+        wallet.submit_transaction_with_state(&mut storage, state, &tx).await.context("Couldn't broadcast transaction")?;
+    } else {
+        state.apply_changes(&mut storage).await.context("Error while applying changes")?;
+    }
+
+    if let Some((token, mut api_token, amount)) = limited_token {
+        api_token.add_spent(amount);
+        storage.set_api_token(&token.name, &api_token)?;
     }
 
-    // returns the created TX and its hash
-    Ok(json!(TransactionResponse {
-        tx_as_hex: if params.tx_as_hex {
+    Ok(tx)
+}
+
+// returns the created TX and its hash
+fn transaction_response(tx: Transaction, tx_as_hex: bool) -> Value {
+    json!(TransactionResponse {
+        tx_as_hex: if tx_as_hex {
             Some(hex::encode(tx.to_bytes()))
         } else {
             None
@@ -252,7 +346,7 @@ async fn build_transaction(context: Context, body: Value) -> Result<Value, Inter
             hash: Cow::Owned(tx.hash()),
             data: Cow::Owned(tx)
         }
-    }))
+    })
 }
 
 // Estimate fees for a transaction
@@ -277,10 +371,10 @@ async fn list_transactions(context: Context, body: Value) -> Result<Value, Inter
     let storage = wallet.get_storage().read().await;
     let opt_key = params.address.map(|addr| addr.to_public_key());
     
-    let mainnet = wallet.get_network().is_mainnet();
+    let network = *wallet.get_network();
     let txs = storage.get_filtered_transactions(opt_key.as_ref(), params.min_topoheight, params.max_topoheight, params.accept_incoming, params.accept_outgoing, params.accept_coinbase, params.accept_burn, params.query.as_ref())?
         .into_iter()
-        .map(|tx| tx.serializable(mainnet))
+        .map(|tx| tx.serializable(network))
         .collect::<Vec<_>>();
 
     Ok(json!(txs))
@@ -332,10 +426,19 @@ async fn sign_data(context: Context, body: Value) -> Result<Value, InternalRpcEr
     let params: DataElement = parse_params(body)?;
 
     let wallet: &Arc<Wallet> = context.get()?;
-    let signature = wallet.sign_data(&params.to_bytes());
+    let signature = wallet.sign_data(&params.to_bytes()).context("Error while signing data")?;
     Ok(json!(signature))
 }
 
+// Verify a signature of any data converted in bytes format against an address
+async fn verify_data(context: Context, body: Value) -> Result<Value, InternalRpcError> {
+    let params: VerifyDataParams = parse_params(body)?;
+
+    let wallet: &Arc<Wallet> = context.get()?;
+    let valid = wallet.verify_data(&params.data.to_bytes(), &params.signature, params.address.get_public_key());
+    Ok(json!(valid))
+}
+
 // In EncryptedStorage, custom trees are already prefixed
 async fn get_tree_name(context: &Context, tree: String) -> Result<String, InternalRpcError> {
     // If the API is not used through XSWD, we don't need to prefix the tree name with the app id
@@ -1,36 +1,58 @@
 use std::sync::Arc;
 
-use actix_web_httpauth::{middleware::HttpAuthentication, extractors::basic::BasicAuth};
+use actix_web_httpauth::{middleware::HttpAuthentication, extractors::{basic::BasicAuth, bearer::BearerAuth}};
 use anyhow::Result;
+use async_trait::async_trait;
 use log::{info, warn};
+use serde_json::Value;
 use tokio::sync::Mutex;
-use xelis_common::{config, rpc_server::{RPCHandler, RPCServerHandler, json_rpc, websocket, websocket::{EventWebSocketHandler, WebSocketServerShared, WebSocketServer}, WebSocketServerHandler}, api::wallet::NotifyEvent};
-use actix_web::{get, HttpResponse, Responder, HttpServer, web::{Data, self}, App, dev::{ServerHandle, ServiceRequest}, Error, error::{ErrorUnauthorized, ErrorBadGateway, ErrorBadRequest}};
+use xelis_common::{config, context::Context, rpc_server::{InternalRpcError, RPCHandler, RPCServerHandler, RpcRequest, RpcResponseError, websocket, websocket::{EventWebSocketHandler, WebSocketServerShared, WebSocketServer}, WebSocketServerHandler}, api::wallet::NotifyEvent};
+use crate::api_token::TokenScope;
+use super::rest;
+use actix_web::{get, HttpMessage, HttpRequest, HttpResponse, Responder, HttpServer, web::{Data, Payload, self}, App, dev::{ServerHandle, ServiceRequest}, Error, error::{ErrorForbidden, ErrorUnauthorized, ErrorBadGateway, ErrorBadRequest}};
 
 pub type WalletRpcServerShared<W> = Arc<WalletRpcServer<W>>;
 
+#[derive(Clone)]
 pub struct AuthConfig {
     pub username: String,
     pub password: String
 }
 
+// Used to authenticate the clients using an API token
+#[async_trait]
+pub trait RPCTokenHandler {
+    // Returns the name and scope of the token matching the secret
+    async fn get_token_scope(&self, secret: &str) -> Result<Option<(String, TokenScope)>, anyhow::Error>;
+}
+
+// Token used by the client for the current request
+// It is stored in the request context so methods can apply its restrictions
+#[derive(Debug, Clone)]
+pub struct AuthorizedToken {
+    pub name: String,
+    pub scope: TokenScope
+}
+
 pub struct WalletRpcServer<W>
 where
-    W: Clone + Send + Sync + 'static
+    W: Clone + Send + Sync + RPCTokenHandler + 'static
 {
     handle: Mutex<Option<ServerHandle>>,
     websocket: WebSocketServerShared<EventWebSocketHandler<W, NotifyEvent>>,
+    bind_address: String,
     auth_config: Option<AuthConfig>
 }
 
 impl<W> WalletRpcServer<W>
 where
-    W: Clone + Send + Sync + 'static
+    W: Clone + Send + Sync + RPCTokenHandler + 'static
 {
     pub async fn new(bind_address: String, rpc_handler: RPCHandler<W>, auth_config: Option<AuthConfig>) -> Result<WalletRpcServerShared<W>> {
         let server = Arc::new(Self {
             handle: Mutex::new(None),
             websocket: WebSocketServer::new(EventWebSocketHandler::new(rpc_handler)),
+            bind_address: bind_address.clone(),
             auth_config
         });
 
@@ -38,14 +60,18 @@ where
             let clone = Arc::clone(&server);
             let http_server = HttpServer::new(move || {
                 let server = Arc::clone(&clone);
-                let auth = HttpAuthentication::basic(auth::<W>);
+                let auth = HttpAuthentication::with_fn(auth::<W>);
                 App::new()
                     .app_data(Data::from(server))
                     .wrap(auth)
                     // WebSocket support
-                    .route("/json_rpc", web::get().to(websocket::<EventWebSocketHandler<W, NotifyEvent>, Self>))
+                    .route("/json_rpc", web::get().to(websocket_endpoint::<W>))
                     // HTTP support
-                    .route("/json_rpc", web::post().to(json_rpc::<W, WalletRpcServer<W>>))
+                    .route("/json_rpc", web::post().to(json_rpc::<W>))
+                    // REST support
+                    .route("/balance/{asset}", web::get().to(rest::get_balance::<W>))
+                    .route("/transfer", web::post().to(rest::transfer::<W>))
+                    .route("/history", web::get().to(rest::get_history::<W>))
                     .service(index)
             })
             .disable_signals()
@@ -64,6 +90,14 @@ where
         Ok(server)
     }
 
+    pub fn get_bind_address(&self) -> &str {
+        &self.bind_address
+    }
+
+    pub fn get_auth_config(&self) -> Option<&AuthConfig> {
+        self.auth_config.as_ref()
+    }
+
     async fn authenticate(&self, credentials: BasicAuth) -> Result<(), Error> {
         if let Some(config) = &self.auth_config {
             let user = credentials.user_id();
@@ -77,6 +111,32 @@ where
         Ok(())
     }
 
+    async fn authenticate_token(&self, credentials: BearerAuth) -> Result<AuthorizedToken, Error> {
+        let data = self.get_rpc_handler().get_data();
+        match data.get_token_scope(credentials.token()).await {
+            Ok(Some((name, scope))) => Ok(AuthorizedToken { name, scope }),
+            Ok(None) => Err(ErrorUnauthorized("API token is invalid")),
+            Err(e) => {
+                warn!("Error while verifying API token: {}", e);
+                Err(ErrorBadGateway("Couldn't verify API token"))
+            }
+        }
+    }
+
+    // Execute a RPC request with the restrictions of the token used by the client
+    pub async fn execute_request(&self, http_request: &HttpRequest, request: RpcRequest) -> Result<Value, RpcResponseError> {
+        let mut context = Context::default();
+        let token = http_request.extensions().get::<AuthorizedToken>().cloned();
+        if let Some(token) = token {
+            if !token.scope.allows(&request.method) {
+                return Err(RpcResponseError::new(request.id, InternalRpcError::Custom(format!("Method '{}' is not allowed for this API token", request.method))))
+            }
+            context.store(token);
+        }
+
+        self.get_rpc_handler().execute_method(context, request).await
+    }
+
     pub async fn stop(&self) {
         info!("Stopping RPC Server...");
         let mut handle = self.handle.lock().await;
@@ -91,7 +151,7 @@ where
 
 impl<W> WebSocketServerHandler<EventWebSocketHandler<W, NotifyEvent>> for WalletRpcServer<W>
 where
-    W: Clone + Send + Sync + 'static
+    W: Clone + Send + Sync + RPCTokenHandler + 'static
 {
     fn get_websocket(&self) -> &WebSocketServerShared<EventWebSocketHandler<W, NotifyEvent>> {
         &self.websocket
@@ -100,27 +160,67 @@ where
 
 impl<W> RPCServerHandler<W> for WalletRpcServer<W>
 where
-    W: Clone + Send + Sync + 'static
+    W: Clone + Send + Sync + RPCTokenHandler + 'static
 {
     fn get_rpc_handler(&self) -> &RPCHandler<W> {
         &self.get_websocket().get_handler().get_rpc_handler()
     }
 }
 
-async fn auth<W>(request: ServiceRequest, credentials: BasicAuth) -> Result<ServiceRequest, (Error, ServiceRequest)>
+// Clients can authenticate using the username/password or an API token
+async fn auth<W>(request: ServiceRequest, credentials: (Option<BearerAuth>, Option<BasicAuth>)) -> Result<ServiceRequest, (Error, ServiceRequest)>
 where
-    W: Clone + Send + Sync + 'static
+    W: Clone + Send + Sync + RPCTokenHandler + 'static
 {
     let data: Option<&Data<WalletRpcServer<W>>> = request.app_data();
-    match data {
-        Some(server) => match server.authenticate(credentials).await {
-            Ok(_) => Ok(request),
-            Err(e) => Err((e, request))
+    let server = match data {
+        Some(server) => server.clone(),
+        None => return Err((ErrorBadGateway("RPC Server was not found"), request))
+    };
+
+    let res = match credentials {
+        (Some(bearer), _) => match server.authenticate_token(bearer).await {
+            Ok(token) => {
+                request.extensions_mut().insert(token);
+                Ok(())
+            },
+            Err(e) => Err(e)
         },
-        None => Err((ErrorBadGateway("RPC Server was not found"), request))
+        (None, Some(basic)) => server.authenticate(basic).await,
+        (None, None) if server.auth_config.is_none() => Ok(()),
+        (None, None) => Err(ErrorUnauthorized("Missing credentials"))
+    };
+
+    match res {
+        Ok(_) => Ok(request),
+        Err(e) => Err((e, request))
     }
 }
 
+// JSON RPC handler endpoint
+async fn json_rpc<W>(server: Data<WalletRpcServer<W>>, http_request: HttpRequest, body: web::Bytes) -> Result<impl Responder, RpcResponseError>
+where
+    W: Clone + Send + Sync + RPCTokenHandler + 'static
+{
+    let request = server.get_rpc_handler().parse_request(&body)?;
+    let result = server.execute_request(&http_request, request).await?;
+    Ok(HttpResponse::Ok().json(result))
+}
+
+// WebSocket JSON RPC handler endpoint
+// Scopes can't be applied on each WebSocket message, only admin tokens are accepted
+async fn websocket_endpoint<W>(server: Data<WalletRpcServer<W>>, request: HttpRequest, body: Payload) -> Result<impl Responder, Error>
+where
+    W: Clone + Send + Sync + RPCTokenHandler + 'static
+{
+    let scope = request.extensions().get::<AuthorizedToken>().map(|token| token.scope);
+    if scope.map_or(false, |scope| scope != TokenScope::Admin) {
+        return Err(ErrorForbidden("Only admin API tokens can use the WebSocket"))
+    }
+
+    websocket::<EventWebSocketHandler<W, NotifyEvent>, WalletRpcServer<W>>(server, request, body).await
+}
+
 #[get("/")]
 async fn index() -> impl Responder {
     HttpResponse::Ok().body(format!("Hello, world!\nRunning on: {}", config::VERSION))
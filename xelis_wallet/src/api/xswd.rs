@@ -384,6 +384,29 @@ where
         &self.applications
     }
 
+    // Find the state of a connected application using its ID
+    pub async fn get_application_by_id(&self, id: &String) -> Option<AppStateShared> {
+        let applications = self.applications.read().await;
+        applications.values().find(|app| app.id == *id).cloned()
+    }
+
+    // Disconnect an application and drop all its permissions
+    pub async fn disconnect_application(&self, id: &String) -> bool {
+        let session = {
+            let applications = self.applications.read().await;
+            applications.iter().find(|(_, app)| app.id == *id).map(|(session, _)| session.clone())
+        };
+
+        match session {
+            Some(session) => {
+                // on_close will clean the application state and its listeners
+                session.get_server().delete_session(&session, None).await;
+                true
+            },
+            None => false
+        }
+    }
+
     // get a HashSet of all events tracked
     pub async fn get_tracked_events(&self) -> HashSet<NotifyEvent> {
         let sessions = self.listeners.lock().await;
@@ -442,7 +465,7 @@ where
                         Ok(())
                     },
                     PermissionResult::AlwaysDeny => {
-                        permissions.insert(request.method.clone(), Permission::AcceptAlways);
+                        permissions.insert(request.method.clone(), Permission::DenyAlways);
                         Err(RpcResponseError::new(request.id, PERMISSION_DENIED_ERROR))
                     }   
                 }
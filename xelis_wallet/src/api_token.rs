@@ -0,0 +1,177 @@
+use chacha20poly1305::aead::OsRng;
+use rand::RngCore;
+use xelis_common::{
+    crypto::{hash, Hash},
+    serializer::{Reader, ReaderError, Serializer, Writer}
+};
+
+// Size in bytes of a generated token secret
+const TOKEN_SECRET_SIZE: usize = 32;
+
+// Methods that only read the wallet state
+const READ_ONLY_METHODS: [&str; 20] = [
+    "get_version",
+    "get_network",
+    "get_nonce",
+    "get_topoheight",
+    "get_address",
+    "split_address",
+    "get_balance",
+    "has_balance",
+    "get_tracked_assets",
+    "get_asset_precision",
+    "get_transaction",
+    "list_transactions",
+    "get_history",
+    "is_online",
+    "estimate_fees",
+    "verify_data",
+    "get_matching_keys",
+    "get_value_from_key",
+    "has_key",
+    "query_db"
+];
+
+// Methods that create transactions, their spending is limited by the token
+const TRANSFER_METHODS: [&str; 2] = [
+    "build_transaction",
+    "send"
+];
+
+// Rights given to a client using an API token
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenScope {
+    // Only methods reading the wallet state
+    ReadOnly,
+    // Read-only methods and transfers, up to the token spend limit
+    Transfer,
+    // Every method, like the username/password authentication
+    Admin
+}
+
+impl TokenScope {
+    pub fn from_str(value: &str) -> Option<Self> {
+        Some(match value {
+            "read_only" => Self::ReadOnly,
+            "transfer" => Self::Transfer,
+            "admin" => Self::Admin,
+            _ => return None
+        })
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ReadOnly => "read_only",
+            Self::Transfer => "transfer",
+            Self::Admin => "admin"
+        }
+    }
+
+    pub fn get_id(&self) -> u8 {
+        match self {
+            Self::ReadOnly => 0,
+            Self::Transfer => 1,
+            Self::Admin => 2
+        }
+    }
+
+    pub fn from_id(id: u8) -> Option<Self> {
+        Some(match id {
+            0 => Self::ReadOnly,
+            1 => Self::Transfer,
+            2 => Self::Admin,
+            _ => return None
+        })
+    }
+
+    // Check if a RPC method can be called with this scope
+    pub fn allows(&self, method: &str) -> bool {
+        match self {
+            Self::ReadOnly => READ_ONLY_METHODS.contains(&method),
+            Self::Transfer => READ_ONLY_METHODS.contains(&method) || TRANSFER_METHODS.contains(&method),
+            Self::Admin => true
+        }
+    }
+}
+
+// Token created by the user to give a limited access to the wallet API
+// Only the hash of the secret is stored
+#[derive(Debug, Clone)]
+pub struct ApiToken {
+    secret_hash: Hash,
+    scope: TokenScope,
+    // Maximum amount of XELIS, fees included, that can be spent with this token
+    spend_limit: Option<u64>,
+    // Amount of XELIS already spent with this token
+    spent: u64
+}
+
+impl ApiToken {
+    // Create a new token, the secret returned must be given to the client
+    pub fn generate(scope: TokenScope, spend_limit: Option<u64>) -> (Self, String) {
+        let mut bytes = [0u8; TOKEN_SECRET_SIZE];
+        OsRng.fill_bytes(&mut bytes);
+        let secret = hex::encode(bytes);
+
+        let token = Self {
+            secret_hash: hash(secret.as_bytes()),
+            scope,
+            spend_limit,
+            spent: 0
+        };
+
+        (token, secret)
+    }
+
+    // Check if the secret sent by a client is the one of this token
+    pub fn matches(&self, secret: &str) -> bool {
+        hash(secret.as_bytes()) == self.secret_hash
+    }
+
+    pub fn get_scope(&self) -> TokenScope {
+        self.scope
+    }
+
+    pub fn get_spend_limit(&self) -> Option<u64> {
+        self.spend_limit
+    }
+
+    pub fn get_spent(&self) -> u64 {
+        self.spent
+    }
+
+    // Check if the amount can be spent without going above the spend limit
+    pub fn can_spend(&self, amount: u64) -> bool {
+        match self.spend_limit {
+            Some(limit) => self.spent.checked_add(amount).map_or(false, |total| total <= limit),
+            None => true
+        }
+    }
+
+    pub fn add_spent(&mut self, amount: u64) {
+        self.spent = self.spent.saturating_add(amount);
+    }
+}
+
+impl Serializer for ApiToken {
+    fn read(reader: &mut Reader) -> Result<Self, ReaderError> {
+        let secret_hash = reader.read_hash()?;
+        let scope = TokenScope::from_id(reader.read_u8()?).ok_or(ReaderError::InvalidValue)?;
+        let spend_limit = Option::read(reader)?;
+        let spent = reader.read_u64()?;
+
+        Ok(Self {
+            secret_hash,
+            scope,
+            spend_limit,
+            spent
+        })
+    }
+
+    fn write(&self, writer: &mut Writer) {
+        writer.write_hash(&self.secret_hash);
+        writer.write_u8(self.scope.get_id());
+        self.spend_limit.write(writer);
+        writer.write_u64(&self.spent);
+    }
+}
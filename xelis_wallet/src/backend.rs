@@ -0,0 +1,504 @@
+use std::{
+    path::Path,
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        Arc
+    }
+};
+use anyhow::{anyhow, Result};
+use log::{debug, error};
+use sled::transaction::{TransactionError, Transactional};
+
+#[cfg(feature = "sqlite")]
+use {
+    std::sync::Mutex,
+    rusqlite::{params, Connection, OptionalExtension}
+};
+
+// File created in the wallet directory when SQLite is used
+pub const SQLITE_FILENAME: &str = "wallet.sqlite";
+
+// Key/value backends available to store a wallet
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum StorageBackendKind {
+    Sled,
+    Sqlite
+}
+
+// Backend used when creating a new wallet
+// Existing wallets are opened with the backend they were created with
+static DEFAULT_BACKEND: AtomicU8 = AtomicU8::new(0);
+
+pub fn set_default_backend(kind: StorageBackendKind) {
+    DEFAULT_BACKEND.store(kind as u8, Ordering::SeqCst);
+}
+
+pub fn get_default_backend() -> StorageBackendKind {
+    match DEFAULT_BACKEND.load(Ordering::SeqCst) {
+        1 => StorageBackendKind::Sqlite,
+        _ => StorageBackendKind::Sled
+    }
+}
+
+// Open the backend of a wallet directory, detecting the one used if it already exists
+pub fn open_backend(path: &str) -> Result<Arc<dyn StorageBackend>> {
+    let kind = if Path::new(path).join(SQLITE_FILENAME).is_file() {
+        StorageBackendKind::Sqlite
+    } else if Path::new(path).is_dir() {
+        StorageBackendKind::Sled
+    } else {
+        get_default_backend()
+    };
+
+    debug!("Opening {:?} storage backend at {}", kind, path);
+    match kind {
+        StorageBackendKind::Sled => Ok(Arc::new(SledBackend::open(path)?)),
+        #[cfg(feature = "sqlite")]
+        StorageBackendKind::Sqlite => Ok(Arc::new(SqliteBackend::open(path)?)),
+        #[cfg(not(feature = "sqlite"))]
+        StorageBackendKind::Sqlite => Err(anyhow!("SQLite storage backend is not enabled, rebuild with the 'sqlite' feature"))
+    }
+}
+
+pub type Entry = (Vec<u8>, Vec<u8>);
+
+// A named key/value collection, keys are ordered by bytes
+pub trait TreeBackend: Send + Sync {
+    fn name(&self) -> &[u8];
+
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>>;
+
+    fn insert(&self, key: &[u8], value: &[u8]) -> Result<()>;
+
+    fn remove(&self, key: &[u8]) -> Result<()>;
+
+    fn contains_key(&self, key: &[u8]) -> Result<bool>;
+
+    fn iter(&self) -> Iter;
+
+    fn clear(&self) -> Result<()>;
+
+    fn len(&self) -> Result<usize>;
+}
+
+// Database containing all the trees of a wallet
+pub trait StorageBackend: Send + Sync {
+    // Tree used to store the public (not encrypted) data
+    fn default_tree(&self) -> Result<Tree>;
+
+    fn open_tree(&self, name: &[u8]) -> Result<Tree>;
+
+    // Names of all trees created, including the default one
+    fn tree_names(&self) -> Result<Vec<Vec<u8>>>;
+
+    // Apply all operations of the batch atomically
+    fn apply_batch(&self, batch: Batch) -> Result<()>;
+
+    fn flush(&self) -> Result<()>;
+
+    // Reclaim the space left by deleted entries if the backend supports it
+    fn compact(&self) -> Result<()>;
+
+    // Size in bytes used on disk
+    fn size_on_disk(&self) -> Result<u64>;
+}
+
+// Shareable handle on a tree from any backend
+#[derive(Clone)]
+pub struct Tree(Arc<dyn TreeBackend>);
+
+impl Tree {
+    pub fn name(&self) -> &[u8] {
+        self.0.name()
+    }
+
+    pub fn get<K: AsRef<[u8]>>(&self, key: K) -> Result<Option<Vec<u8>>> {
+        self.0.get(key.as_ref())
+    }
+
+    pub fn insert<K: AsRef<[u8]>, V: AsRef<[u8]>>(&self, key: K, value: V) -> Result<()> {
+        self.0.insert(key.as_ref(), value.as_ref())
+    }
+
+    pub fn remove<K: AsRef<[u8]>>(&self, key: K) -> Result<()> {
+        self.0.remove(key.as_ref())
+    }
+
+    pub fn contains_key<K: AsRef<[u8]>>(&self, key: K) -> Result<bool> {
+        self.0.contains_key(key.as_ref())
+    }
+
+    pub fn iter(&self) -> Iter {
+        self.0.iter()
+    }
+
+    pub fn clear(&self) -> Result<()> {
+        self.0.clear()
+    }
+
+    // Number of entries, 0 if it can't be read
+    pub fn len(&self) -> usize {
+        match self.0.len() {
+            Ok(len) => len,
+            Err(e) => {
+                error!("Error while counting entries of tree: {}", e);
+                0
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+// Iterator over the entries of a tree ordered by key
+pub struct Iter(Box<dyn DoubleEndedIterator<Item = Result<Entry>> + Send>);
+
+impl Iter {
+    pub fn keys(self) -> impl DoubleEndedIterator<Item = Result<Vec<u8>>> {
+        self.map(|res| res.map(|(key, _)| key))
+    }
+
+    pub fn values(self) -> impl DoubleEndedIterator<Item = Result<Vec<u8>>> {
+        self.map(|res| res.map(|(_, value)| value))
+    }
+}
+
+impl Iterator for Iter {
+    type Item = Result<Entry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+impl DoubleEndedIterator for Iter {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back()
+    }
+}
+
+pub enum BatchOperation {
+    Insert(Vec<u8>, Vec<u8>),
+    Remove(Vec<u8>)
+}
+
+// Operations to apply atomically on several trees
+#[derive(Default)]
+pub struct Batch {
+    // tree name -> operations
+    operations: Vec<(Vec<u8>, Vec<BatchOperation>)>
+}
+
+impl Batch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn get_operations(&mut self, tree: &Tree) -> &mut Vec<BatchOperation> {
+        let index = match self.operations.iter().position(|(name, _)| name.as_slice() == tree.name()) {
+            Some(index) => index,
+            None => {
+                self.operations.push((tree.name().to_vec(), Vec::new()));
+                self.operations.len() - 1
+            }
+        };
+
+        &mut self.operations[index].1
+    }
+
+    pub fn insert<K: AsRef<[u8]>, V: AsRef<[u8]>>(&mut self, tree: &Tree, key: K, value: V) {
+        self.get_operations(tree).push(BatchOperation::Insert(key.as_ref().to_vec(), value.as_ref().to_vec()));
+    }
+
+    pub fn remove<K: AsRef<[u8]>>(&mut self, tree: &Tree, key: K) {
+        self.get_operations(tree).push(BatchOperation::Remove(key.as_ref().to_vec()));
+    }
+}
+
+// Default backend using sled, each tree is a sled tree
+pub struct SledBackend {
+    db: sled::Db
+}
+
+impl SledBackend {
+    pub fn open(path: &str) -> Result<Self> {
+        Ok(Self {
+            db: sled::open(path)?
+        })
+    }
+}
+
+struct SledTree {
+    name: Vec<u8>,
+    tree: sled::Tree
+}
+
+impl SledTree {
+    fn new(tree: sled::Tree) -> Tree {
+        Tree(Arc::new(Self {
+            name: tree.name().to_vec(),
+            tree
+        }))
+    }
+}
+
+impl TreeBackend for SledTree {
+    fn name(&self) -> &[u8] {
+        &self.name
+    }
+
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        Ok(self.tree.get(key)?.map(|v| v.to_vec()))
+    }
+
+    fn insert(&self, key: &[u8], value: &[u8]) -> Result<()> {
+        self.tree.insert(key, value)?;
+        Ok(())
+    }
+
+    fn remove(&self, key: &[u8]) -> Result<()> {
+        self.tree.remove(key)?;
+        Ok(())
+    }
+
+    fn contains_key(&self, key: &[u8]) -> Result<bool> {
+        Ok(self.tree.contains_key(key)?)
+    }
+
+    fn iter(&self) -> Iter {
+        Iter(Box::new(self.tree.iter().map(|res| {
+            let (key, value) = res?;
+            Ok((key.to_vec(), value.to_vec()))
+        })))
+    }
+
+    fn clear(&self) -> Result<()> {
+        self.tree.clear()?;
+        Ok(())
+    }
+
+    fn len(&self) -> Result<usize> {
+        Ok(self.tree.len())
+    }
+}
+
+impl StorageBackend for SledBackend {
+    fn default_tree(&self) -> Result<Tree> {
+        Ok(SledTree::new((*self.db).clone()))
+    }
+
+    fn open_tree(&self, name: &[u8]) -> Result<Tree> {
+        Ok(SledTree::new(self.db.open_tree(name)?))
+    }
+
+    fn tree_names(&self) -> Result<Vec<Vec<u8>>> {
+        Ok(self.db.tree_names().into_iter().map(|name| name.to_vec()).collect())
+    }
+
+    fn apply_batch(&self, batch: Batch) -> Result<()> {
+        let mut trees = Vec::with_capacity(batch.operations.len());
+        for (name, _) in &batch.operations {
+            trees.push(self.db.open_tree(name)?);
+        }
+
+        trees.as_slice().transaction(|views| {
+            for (view, (_, operations)) in views.iter().zip(batch.operations.iter()) {
+                for operation in operations {
+                    match operation {
+                        BatchOperation::Insert(key, value) => {
+                            view.insert(key.as_slice(), value.as_slice())?;
+                        },
+                        BatchOperation::Remove(key) => {
+                            view.remove(key.as_slice())?;
+                        }
+                    }
+                }
+            }
+            Ok(())
+        }).map_err(|e: TransactionError<()>| anyhow!("Error while applying batch: {:?}", e))
+    }
+
+    fn flush(&self) -> Result<()> {
+        self.db.flush()?;
+        Ok(())
+    }
+
+    // sled has no manual compaction, it rewrites the fragmented segments in the background
+    // This only flushes the pending writes, the space is reclaimed later by sled
+    fn compact(&self) -> Result<()> {
+        self.flush()
+    }
+
+    fn size_on_disk(&self) -> Result<u64> {
+        Ok(self.db.size_on_disk()?)
+    }
+}
+
+// SQLite backend, all trees are stored in the same table
+// This allows to inspect the (encrypted) wallet data using standard tools
+#[cfg(feature = "sqlite")]
+pub struct SqliteBackend {
+    connection: Arc<Mutex<Connection>>
+}
+
+#[cfg(feature = "sqlite")]
+const SQLITE_DEFAULT_TREE: &[u8] = b"__default";
+
+#[cfg(feature = "sqlite")]
+impl SqliteBackend {
+    pub fn open(path: &str) -> Result<Self> {
+        std::fs::create_dir_all(path)?;
+        let connection = Connection::open(Path::new(path).join(SQLITE_FILENAME))?;
+        connection.execute_batch(
+            "PRAGMA journal_mode = WAL;
+            PRAGMA synchronous = FULL;
+            CREATE TABLE IF NOT EXISTS trees (name BLOB PRIMARY KEY NOT NULL);
+            CREATE TABLE IF NOT EXISTS entries (
+                tree BLOB NOT NULL,
+                key BLOB NOT NULL,
+                value BLOB NOT NULL,
+                PRIMARY KEY (tree, key)
+            ) WITHOUT ROWID;"
+        )?;
+
+        Ok(Self {
+            connection: Arc::new(Mutex::new(connection))
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+struct SqliteTree {
+    name: Vec<u8>,
+    connection: Arc<Mutex<Connection>>
+}
+
+#[cfg(feature = "sqlite")]
+impl SqliteTree {
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, Connection>> {
+        self.connection.lock().map_err(|_| anyhow!("SQLite connection is poisoned"))
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl TreeBackend for SqliteTree {
+    fn name(&self) -> &[u8] {
+        &self.name
+    }
+
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        let connection = self.lock()?;
+        let value = connection.query_row("SELECT value FROM entries WHERE tree = ?1 AND key = ?2", params![self.name, key], |row| row.get(0))
+            .optional()?;
+        Ok(value)
+    }
+
+    fn insert(&self, key: &[u8], value: &[u8]) -> Result<()> {
+        let connection = self.lock()?;
+        connection.execute("INSERT OR REPLACE INTO entries (tree, key, value) VALUES (?1, ?2, ?3)", params![self.name, key, value])?;
+        Ok(())
+    }
+
+    fn remove(&self, key: &[u8]) -> Result<()> {
+        let connection = self.lock()?;
+        connection.execute("DELETE FROM entries WHERE tree = ?1 AND key = ?2", params![self.name, key])?;
+        Ok(())
+    }
+
+    fn contains_key(&self, key: &[u8]) -> Result<bool> {
+        Ok(self.get(key)?.is_some())
+    }
+
+    // Entries are loaded in memory as the connection can't be borrowed by the iterator
+    fn iter(&self) -> Iter {
+        let res = self.lock().and_then(|connection| {
+            let mut statement = connection.prepare("SELECT key, value FROM entries WHERE tree = ?1 ORDER BY key")?;
+            let rows = statement.query_map(params![self.name], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.map(|row| row.map_err(anyhow::Error::from)).collect::<Result<Vec<Entry>>>()
+        });
+
+        match res {
+            Ok(entries) => Iter(Box::new(entries.into_iter().map(Ok))),
+            Err(e) => Iter(Box::new(std::iter::once(Err(e))))
+        }
+    }
+
+    fn clear(&self) -> Result<()> {
+        let connection = self.lock()?;
+        connection.execute("DELETE FROM entries WHERE tree = ?1", params![self.name])?;
+        Ok(())
+    }
+
+    fn len(&self) -> Result<usize> {
+        let connection = self.lock()?;
+        let count: i64 = connection.query_row("SELECT COUNT(*) FROM entries WHERE tree = ?1", params![self.name], |row| row.get(0))?;
+        Ok(count as usize)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl StorageBackend for SqliteBackend {
+    fn default_tree(&self) -> Result<Tree> {
+        self.open_tree(SQLITE_DEFAULT_TREE)
+    }
+
+    fn open_tree(&self, name: &[u8]) -> Result<Tree> {
+        {
+            let connection = self.connection.lock().map_err(|_| anyhow!("SQLite connection is poisoned"))?;
+            connection.execute("INSERT OR IGNORE INTO trees (name) VALUES (?1)", params![name])?;
+        }
+
+        Ok(Tree(Arc::new(SqliteTree {
+            name: name.to_vec(),
+            connection: Arc::clone(&self.connection)
+        })))
+    }
+
+    fn tree_names(&self) -> Result<Vec<Vec<u8>>> {
+        let connection = self.connection.lock().map_err(|_| anyhow!("SQLite connection is poisoned"))?;
+        let mut statement = connection.prepare("SELECT name FROM trees")?;
+        let names = statement.query_map([], |row| row.get(0))?
+            .collect::<Result<Vec<Vec<u8>>, _>>()?;
+        Ok(names)
+    }
+
+    fn apply_batch(&self, batch: Batch) -> Result<()> {
+        let mut connection = self.connection.lock().map_err(|_| anyhow!("SQLite connection is poisoned"))?;
+        // Rolled back automatically if not committed
+        let transaction = connection.transaction()?;
+        for (name, operations) in &batch.operations {
+            for operation in operations {
+                match operation {
+                    BatchOperation::Insert(key, value) => {
+                        transaction.execute("INSERT OR REPLACE INTO entries (tree, key, value) VALUES (?1, ?2, ?3)", params![name, key, value])?;
+                    },
+                    BatchOperation::Remove(key) => {
+                        transaction.execute("DELETE FROM entries WHERE tree = ?1 AND key = ?2", params![name, key])?;
+                    }
+                }
+            }
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    // Each statement is committed and synced on disk
+    fn flush(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn compact(&self) -> Result<()> {
+        let connection = self.connection.lock().map_err(|_| anyhow!("SQLite connection is poisoned"))?;
+        connection.execute_batch("VACUUM; PRAGMA wal_checkpoint(TRUNCATE);")?;
+        Ok(())
+    }
+
+    fn size_on_disk(&self) -> Result<u64> {
+        let connection = self.connection.lock().map_err(|_| anyhow!("SQLite connection is poisoned"))?;
+        let page_count: i64 = connection.query_row("PRAGMA page_count", [], |row| row.get(0))?;
+        let page_size: i64 = connection.query_row("PRAGMA page_size", [], |row| row.get(0))?;
+        Ok((page_count * page_size) as u64)
+    }
+}
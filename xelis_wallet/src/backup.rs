@@ -0,0 +1,98 @@
+use anyhow::{anyhow, Context, Result};
+use chacha20poly1305::aead::OsRng;
+use rand::RngCore;
+use xelis_common::{
+    crypto::{PrivateKey, PublicKey},
+    network::Network,
+    serializer::{Reader, ReaderError, Serializer, Writer}
+};
+use crate::{
+    cipher::Cipher,
+    config::{PASSWORD_ALGORITHM_VERSION, SALT_SIZE},
+    wallet::hash_password
+};
+
+// Magic bytes at the start of a backup file
+const BACKUP_MAGIC: &[u8] = b"XELBAK";
+// Version of the backup format
+const BACKUP_VERSION: u8 = 0;
+
+// Portable content of a wallet: its keys and the data that can't be synced back from the chain
+// Balances and history are not included, they are recovered by a rescan
+pub struct WalletBackup {
+    pub network: Network,
+    // None for a watch-only wallet
+    pub private_key: Option<PrivateKey>,
+    // Public key of the main account
+    pub public_key: PublicKey,
+    // Accounts derived from the main one (index, name)
+    pub accounts: Vec<(u32, String)>,
+    // Address book (name, address)
+    pub contacts: Vec<(String, String)>,
+    pub daemon_address: Option<String>
+}
+
+impl WalletBackup {
+    // Encrypt the backup with a password
+    // Format: magic | version | password algorithm version | salt | encrypted content
+    pub fn to_encrypted_bytes(&self, password: String) -> Result<Vec<u8>> {
+        let mut salt = [0u8; SALT_SIZE];
+        OsRng.fill_bytes(&mut salt);
+
+        let hashed_password = hash_password(password, &salt, PASSWORD_ALGORITHM_VERSION)?;
+        let cipher = Cipher::new(&hashed_password, None)?;
+        let encrypted = cipher.encrypt_value(&self.to_bytes())?;
+
+        let mut bytes = Vec::with_capacity(BACKUP_MAGIC.len() + 2 + SALT_SIZE + encrypted.len());
+        bytes.extend_from_slice(BACKUP_MAGIC);
+        bytes.push(BACKUP_VERSION);
+        bytes.push(PASSWORD_ALGORITHM_VERSION);
+        bytes.extend_from_slice(&salt);
+        bytes.extend_from_slice(&encrypted);
+
+        Ok(bytes)
+    }
+
+    // Decrypt a backup created by `to_encrypted_bytes`
+    pub fn from_encrypted_bytes(bytes: &[u8], password: String) -> Result<Self> {
+        let header_size = BACKUP_MAGIC.len() + 2 + SALT_SIZE;
+        if bytes.len() <= header_size || !bytes.starts_with(BACKUP_MAGIC) {
+            return Err(anyhow!("Invalid backup file"))
+        }
+
+        let version = bytes[BACKUP_MAGIC.len()];
+        if version != BACKUP_VERSION {
+            return Err(anyhow!("Unsupported backup version {}", version))
+        }
+
+        let algorithm_version = bytes[BACKUP_MAGIC.len() + 1];
+        let salt = &bytes[BACKUP_MAGIC.len() + 2..header_size];
+        let hashed_password = hash_password(password, salt, algorithm_version)?;
+        let cipher = Cipher::new(&hashed_password, None)?;
+        let content = cipher.decrypt_value(&bytes[header_size..]).context("Invalid password provided for this backup")?;
+
+        Ok(Self::from_bytes(&content)?)
+    }
+}
+
+impl Serializer for WalletBackup {
+    fn read(reader: &mut Reader) -> Result<Self, ReaderError> {
+        Ok(Self {
+            network: Network::read(reader)?,
+            private_key: Option::read(reader)?,
+            public_key: PublicKey::read(reader)?,
+            accounts: Vec::read(reader)?,
+            contacts: Vec::read(reader)?,
+            daemon_address: Option::read(reader)?
+        })
+    }
+
+    fn write(&self, writer: &mut Writer) {
+        self.network.write(writer);
+        self.private_key.write(writer);
+        self.public_key.write(writer);
+        self.accounts.write(writer);
+        self.contacts.write(writer);
+        self.daemon_address.write(writer);
+    }
+}
@@ -0,0 +1,225 @@
+use std::{ops::ControlFlow, sync::Arc};
+use anyhow::Error;
+use log::{debug, info};
+use xelis_common::{
+    crypto::{ecdlp, Address, Hash, KeyPair, PrivateKey},
+    network::Network
+};
+use crate::{
+    backend::StorageBackend,
+    cipher::{hash_keyfile, mix_keyfile},
+    storage::Storage,
+    wallet::{PrecomputedTablesShared, Wallet, WalletError}
+};
+
+// Report the precomputed tables generation progress in the logs
+pub struct LogProgressTableGenerationReportFunction;
+
+impl ecdlp::ProgressTableGenerationReportFunction for LogProgressTableGenerationReportFunction {
+    fn report(&self, progress: f64, step: ecdlp::ReportStep) -> ControlFlow<()> {
+        info!("Progress: {:.2}% on step {:?}", progress * 100.0, step);
+        ControlFlow::Continue(())
+    }
+}
+
+// Keys used when creating a new wallet
+enum WalletKeys {
+    // Recover the keys from a BIP39 mnemonic or a legacy seed
+    Seed(String),
+    // Use an existing private key
+    PrivateKey(PrivateKey),
+    // Only monitor this address, no funds can be spent
+    WatchOnly(Address),
+    // Decrypt the balances using the private key, but refuse to sign
+    ViewOnly(PrivateKey)
+}
+
+// Options to open or create a wallet, made to embed it in another binary
+//
+// let wallet = Wallet::builder()
+//     .path("wallets/my_wallet")
+//     .password("password")
+//     .network(Network::Testnet)
+//     .daemon_address("http://127.0.0.1:8080")
+//     .open()
+//     .await?;
+pub struct WalletBuilder {
+    // Directory of the wallet, not used if a backend is set
+    path: Option<String>,
+    password: Option<String>,
+    network: Network,
+    // Daemons to connect to once the wallet is ready, by order of preference
+    // The wallet stays in offline mode if empty
+    daemon_addresses: Vec<String>,
+    // Custom storage backend, the default one for the path is used otherwise
+    backend: Option<Arc<dyn StorageBackend>>,
+    // Tables shared between wallets, read or generated if not set
+    precomputed_tables: Option<PrecomputedTablesShared>,
+    // Directory used to read or generate the precomputed tables
+    precomputed_tables_path: Option<String>,
+    // Keys of the wallet to create, derived from a new mnemonic if not set
+    keys: Option<WalletKeys>,
+    // Hash of the keyfile required with the password
+    keyfile: Option<Hash>
+}
+
+impl WalletBuilder {
+    pub fn new() -> Self {
+        Self {
+            path: None,
+            password: None,
+            network: Network::Mainnet,
+            daemon_addresses: Vec::new(),
+            backend: None,
+            precomputed_tables: None,
+            precomputed_tables_path: None,
+            keys: None,
+            keyfile: None
+        }
+    }
+
+    pub fn path<S: Into<String>>(mut self, path: S) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    pub fn password<S: Into<String>>(mut self, password: S) -> Self {
+        self.password = Some(password.into());
+        self
+    }
+
+    // File required in addition to the password to open the wallet
+    // When creating a wallet, it can't be opened without it anymore
+    pub fn keyfile<B: AsRef<[u8]>>(mut self, content: B) -> Self {
+        self.keyfile = Some(hash_keyfile(content.as_ref()));
+        self
+    }
+
+    pub fn network(mut self, network: Network) -> Self {
+        self.network = network;
+        self
+    }
+
+    // Can be called several times to add fallback daemons
+    pub fn daemon_address<S: Into<String>>(mut self, daemon_address: S) -> Self {
+        self.daemon_addresses.push(daemon_address.into());
+        self
+    }
+
+    pub fn daemon_addresses(mut self, daemon_addresses: Vec<String>) -> Self {
+        self.daemon_addresses = daemon_addresses;
+        self
+    }
+
+    pub fn backend(mut self, backend: Arc<dyn StorageBackend>) -> Self {
+        self.backend = Some(backend);
+        self
+    }
+
+    pub fn precomputed_tables(mut self, precomputed_tables: PrecomputedTablesShared) -> Self {
+        self.precomputed_tables = Some(precomputed_tables);
+        self
+    }
+
+    pub fn precomputed_tables_path<S: Into<String>>(mut self, path: S) -> Self {
+        self.precomputed_tables_path = Some(path.into());
+        self
+    }
+
+    // Only used when creating a wallet
+    pub fn seed<S: Into<String>>(mut self, seed: S) -> Self {
+        self.keys = Some(WalletKeys::Seed(seed.into()));
+        self
+    }
+
+    // Only used when creating a wallet
+    pub fn private_key(mut self, private_key: PrivateKey) -> Self {
+        self.keys = Some(WalletKeys::PrivateKey(private_key));
+        self
+    }
+
+    // Only used when creating a wallet
+    pub fn watch_only(mut self, address: Address) -> Self {
+        self.keys = Some(WalletKeys::WatchOnly(address));
+        self
+    }
+
+    // Only used when creating a wallet
+    pub fn view_only(mut self, private_key: PrivateKey) -> Self {
+        self.keys = Some(WalletKeys::ViewOnly(private_key));
+        self
+    }
+
+    // Open an existing wallet
+    pub async fn open(mut self) -> Result<Arc<Wallet>, Error> {
+        let (storage, password, precomputed_tables) = self.prepare()?;
+        if self.keyfile.is_none() && storage.is_keyfile_required()? {
+            return Err(WalletError::KeyfileRequired.into())
+        }
+
+        let wallet = Wallet::open_with_storage(storage, password, self.network, precomputed_tables)?;
+        wallet.set_keyfile_hash(self.keyfile.take()).await;
+        self.connect(wallet).await
+    }
+
+    // Create a new wallet, its storage must be empty
+    pub async fn create(mut self) -> Result<Arc<Wallet>, Error> {
+        let (mut storage, password, precomputed_tables) = self.prepare()?;
+        storage.set_keyfile_required(self.keyfile.is_some())?;
+        let network = self.network;
+        let wallet = match self.keys.take() {
+            Some(WalletKeys::WatchOnly(address)) => Wallet::create_watch_only_with_storage(storage, password, address, network, precomputed_tables)?,
+            Some(WalletKeys::ViewOnly(private_key)) => Wallet::create_view_only_with_storage(storage, password, private_key, network, precomputed_tables)?,
+            Some(WalletKeys::PrivateKey(private_key)) => Wallet::create_with_keypair(storage, password, KeyPair::from_private_key(private_key), network, precomputed_tables)?,
+            // A new BIP39 mnemonic is generated if no seed was set
+            Some(WalletKeys::Seed(seed)) => Wallet::create_with_seed(storage, password, Some(seed), network, precomputed_tables)?,
+            None => Wallet::create_with_seed(storage, password, None, network, precomputed_tables)?
+        };
+
+        wallet.set_keyfile_hash(self.keyfile.take()).await;
+        self.connect(wallet).await
+    }
+
+    // Open the storage and load the precomputed tables
+    // The password returned has the keyfile mixed in if any
+    fn prepare(&mut self) -> Result<(Storage, String, PrecomputedTablesShared), Error> {
+        let password = self.password.take().ok_or(WalletError::MissingPassword)?;
+        let password = mix_keyfile(password, self.keyfile.as_ref());
+
+        let storage = match (self.backend.take(), self.path.take()) {
+            (Some(backend), _) => Storage::with_backend(backend)?,
+            (None, Some(path)) => {
+                if path.is_empty() {
+                    return Err(WalletError::EmptyName.into())
+                }
+
+                debug!("Creating storage for {}", path);
+                Storage::new(path)?
+            },
+            (None, None) => return Err(WalletError::MissingStorage.into())
+        };
+
+        let precomputed_tables = match self.precomputed_tables.take() {
+            Some(precomputed_tables) => precomputed_tables,
+            None => Wallet::read_or_generate_precomputed_tables(self.precomputed_tables_path.take(), LogProgressTableGenerationReportFunction)?
+        };
+
+        Ok((storage, password, precomputed_tables))
+    }
+
+    // Switch the wallet to online mode if any daemon was set
+    async fn connect(self, wallet: Arc<Wallet>) -> Result<Arc<Wallet>, Error> {
+        if !self.daemon_addresses.is_empty() {
+            let daemon_address = wallet.set_online_mode_with_fallbacks(self.daemon_addresses).await?;
+            info!("Online mode enabled using daemon '{}'", daemon_address);
+        }
+
+        Ok(wallet)
+    }
+}
+
+impl Default for WalletBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
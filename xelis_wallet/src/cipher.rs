@@ -9,11 +9,12 @@ use chacha20poly1305::{
 };
 use xelis_common::crypto::{
     HASH_SIZE,
+    Hash,
     hash
 };
 use crate::{wallet::WalletError, config::SALT_SIZE};
 
-
+#[derive(Clone)]
 pub struct Cipher {
     cipher: XChaCha20Poly1305,
     // this salt is used for keys and values
@@ -30,6 +31,11 @@ impl Cipher {
         })
     }
 
+    // Create a new cipher using another key but the same salt
+    pub fn with_key(&self, key: &[u8]) -> Result<Self> {
+        Self::new(key, self.salt)
+    }
+
     // encrypt value passed in param and add plaintext nonce before encrypted value
     // a Nonce is generated randomly at each call
     pub fn encrypt_value(&self, value: &[u8]) -> Result<Vec<u8>, WalletError> {
@@ -86,4 +92,22 @@ impl Cipher {
         data.extend_from_slice(key.as_ref());
         hash(&data).to_bytes()
     }
-}
\ No newline at end of file
+}
+
+// Hash the content of a keyfile, any file can be used whatever its size
+pub fn hash_keyfile(content: &[u8]) -> Hash {
+    hash(content)
+}
+
+// Mix the keyfile hash into the password used as KDF input
+// Both are prefixed by their length so another password and keyfile pair can't give the same input
+// Opening the wallet then requires both the password and the file
+pub fn mix_keyfile(password: String, keyfile: Option<&Hash>) -> String {
+    match keyfile {
+        Some(keyfile) => {
+            let keyfile = keyfile.to_hex();
+            format!("xelis-keyfile:{}:{}:{}:{}", password.len(), password, keyfile.len(), keyfile)
+        },
+        None => password
+    }
+}
@@ -1,5 +1,4 @@
-use argon2::{Params, Argon2, Algorithm, Version};
-use lazy_static::lazy_static;
+use crate::password::PasswordParams;
 
 pub const DIR_PATH: &str = "wallets/";
 pub const XSWD_BIND_ADDRESS: &str = "0.0.0.0:44325";
@@ -10,10 +9,86 @@ pub const KEY_SIZE: usize = 32;
 // daemon address by default when no specified
 pub const DEFAULT_DAEMON_ADDRESS: &str = "http://127.0.0.1:8080";
 
-lazy_static! {
-    pub static ref PASSWORD_ALGORITHM: Argon2<'static> = {
-        // 15 MB, 16 iterations
-        let params = Params::new(15 * 1000, 16, 1,  Some(PASSWORD_HASH_SIZE)).unwrap();
-        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
-    };
-}
\ No newline at end of file
+// Delay in seconds without connection before switching to the next daemon
+pub const DAEMON_FAILOVER_DELAY: u64 = 30;
+
+// Interval in seconds between two background health checks of the daemon
+pub const DAEMON_HEALTH_CHECK_INTERVAL: u64 = 120;
+
+// Age in seconds of the daemon top block after which the daemon is considered lagging
+pub const DAEMON_MAX_TOP_BLOCK_AGE: u64 = 5 * 60;
+
+// Blocks requested per batch during a sync
+pub const DEFAULT_SYNC_BATCH_SIZE: usize = 32;
+
+// Block requests sent in parallel to the daemon during a sync
+pub const DEFAULT_SYNC_CONCURRENCY: usize = 4;
+
+// Minimum interval in milliseconds between two sync progress reports
+pub const SYNC_PROGRESS_REPORT_INTERVAL: u64 = 500;
+
+// Timeout in seconds of a webhook request
+pub const WEBHOOK_TIMEOUT: u64 = 10;
+
+// Attempts to deliver a webhook notification before giving up
+pub const WEBHOOK_MAX_RETRIES: u32 = 3;
+
+// Delay in seconds before retrying a failed webhook, doubled after each attempt
+pub const WEBHOOK_RETRY_DELAY: u64 = 5;
+
+// Transactions waiting in the daemon mempool above which the network is considered fully congested
+pub const MEMPOOL_CONGESTION_THRESHOLD: usize = 200;
+
+// Fee multipliers used at full congestion for the normal and high priorities
+// The low priority always pays the minimum fees
+pub const FEE_MULTIPLIER_NORMAL_MAX: f64 = 2.0;
+pub const FEE_MULTIPLIER_HIGH_MIN: f64 = 1.5;
+pub const FEE_MULTIPLIER_HIGH_MAX: f64 = 4.0;
+
+// Fee increase in percent of a replacement transaction compared to the one it replaces
+pub const REPLACEMENT_FEE_INCREASE_PERCENT: u64 = 25;
+
+// Events buffered for each subscriber before the slowest ones start lagging
+pub const EVENT_CHANNEL_CAPACITY: usize = 256;
+
+// Expected time between two blocks, used to estimate the age of a topoheight
+pub const BLOCK_TIME_SECONDS: u64 = 15;
+
+// Version of the password hashing parameters used for new passwords
+// Wallets using an older version are upgraded when changing their password
+pub const PASSWORD_ALGORITHM_VERSION: u8 = 1;
+
+// Version using the parameters stored in the wallet next to the password salt
+pub const PASSWORD_ALGORITHM_VERSION_CUSTOM: u8 = 2;
+
+// Version 0, used by wallets created before the versioning
+// 15 MB, 16 iterations
+pub const PASSWORD_PARAMS_V0: PasswordParams = PasswordParams::new(15 * 1000, 16, 1);
+
+// Version 1
+// 64 MB, 8 iterations
+pub const PASSWORD_PARAMS_V1: PasswordParams = PasswordParams::new(64 * 1024, 8, 1);
+
+// Bounds in KiB of the memory accepted for custom password parameters
+// The minimum keeps them at least as strong as the version 0
+pub const PASSWORD_MIN_MEMORY: u32 = 15 * 1000;
+pub const PASSWORD_MAX_MEMORY: u32 = 4 * 1024 * 1024;
+
+// Memory in KiB from which the calibration starts
+pub const PASSWORD_CALIBRATION_START_MEMORY: u32 = 64 * 1024;
+
+// Size in bytes of the keyfiles generated by the wallet
+pub const KEYFILE_SIZE: usize = 64;
+
+// Unlock time in milliseconds targeted by the calibration by default
+pub const DEFAULT_PASSWORD_CALIBRATION_TARGET: u64 = 1000;
+
+// Retrieve the password hashing parameters for the requested version
+// The custom version has no fixed parameters, they are read from the wallet
+pub fn get_password_params(version: u8) -> Option<PasswordParams> {
+    match version {
+        0 => Some(PASSWORD_PARAMS_V0),
+        1 => Some(PASSWORD_PARAMS_V1),
+        _ => None
+    }
+}
@@ -9,7 +9,10 @@ use xelis_common::{
         WebSocketJsonRPCClient,
         WebSocketJsonRPCClientImpl,
         JsonRPCResult,
-        EventReceiver
+        EventReceiver,
+        ProxyConfig,
+        DEFAULT_AUTO_RECONNECT,
+        TlsConfig
     },
     api::daemon::{
         GetBalanceResult,
@@ -19,21 +22,30 @@ use xelis_common::{
         SubmitTransactionParams,
         BlockResponse,
         GetBlockAtTopoHeightParams,
+        GetBlockByHashParams,
         GetTransactionParams,
         GetNonceParams,
         GetNonceResult,
+        GetMultiSigParams,
+        GetMultiSigResult,
         GetAssetsParams,
         IsTxExecutedInBlockParams,
         NotifyEvent,
         NewBlockEvent,
         BlockOrderedEvent,
         StableHeightChangedEvent,
+        ChainReorganizedEvent,
         TransactionAddedInMempoolEvent,
+        TransactionOrphanedEvent,
+        TransactionConflictedEvent,
+        TransactionExpiredEvent,
         GetAccountAssetsParams,
+        GetAccountTransactionsAtTopoHeightParams,
         GetAssetParams,
         GetMempoolCacheParams,
         GetMempoolCacheResult,
-        IsAccountRegisteredParams
+        IsAccountRegisteredParams,
+        TransactionResponse
     },
     account::VersionedBalance,
     crypto::{
@@ -60,19 +72,35 @@ impl DaemonAPI {
         })
     }
 
+    // Same as new but with custom TLS options for wss connections and an optional SOCKS5 proxy
+    pub async fn new_with_options(daemon_address: String, tls: Option<TlsConfig>, proxy: Option<ProxyConfig>) -> Result<Self> {
+        let client = WebSocketJsonRPCClientImpl::new_with_options(daemon_address, tls, proxy).await?;
+        Ok(Self {
+            client
+        })
+    }
+
     // is the websocket connection alive
     pub fn is_online(&self) -> bool {
         self.client.is_online()
     }
 
+    // Address of the daemon we are connected to
+    pub fn get_daemon_address(&self) -> &String {
+        self.client.get_target()
+    }
+
     // Disconnect by closing the connection with node RPC
     pub async fn disconnect(&self) -> Result<()> {
         self.client.disconnect().await
     }
 
     // Try to reconnect using the same client
+    // Auto reconnect is disabled by a disconnect, enable it again
     pub async fn reconnect(&self) -> Result<bool> {
-        self.client.reconnect().await
+        let reconnected = self.client.reconnect().await?;
+        self.client.set_auto_reconnect(Some(DEFAULT_AUTO_RECONNECT)).await;
+        Ok(reconnected)
     }
 
     // On connection event
@@ -104,11 +132,31 @@ impl DaemonAPI {
         Ok(receiver)
     }
 
+    pub async fn on_chain_reorganized_event(&self) -> Result<EventReceiver<ChainReorganizedEvent>> {
+        let receiver = self.client.subscribe_event(NotifyEvent::ChainReorganized).await?;
+        Ok(receiver)
+    }
+
     pub async fn on_transaction_added_in_mempool_event(&self) -> Result<EventReceiver<TransactionAddedInMempoolEvent>> {
         let receiver = self.client.subscribe_event(NotifyEvent::TransactionAddedInMempool).await?;
         Ok(receiver)
     }
 
+    pub async fn on_transaction_orphaned_event(&self) -> Result<EventReceiver<TransactionOrphanedEvent>> {
+        let receiver = self.client.subscribe_event(NotifyEvent::TransactionOrphaned).await?;
+        Ok(receiver)
+    }
+
+    pub async fn on_transaction_conflicted_event(&self) -> Result<EventReceiver<TransactionConflictedEvent>> {
+        let receiver = self.client.subscribe_event(NotifyEvent::TransactionConflicted).await?;
+        Ok(receiver)
+    }
+
+    pub async fn on_transaction_expired_event(&self) -> Result<EventReceiver<TransactionExpiredEvent>> {
+        let receiver = self.client.subscribe_event(NotifyEvent::TransactionExpired).await?;
+        Ok(receiver)
+    }
+
     pub async fn get_version(&self) -> Result<String> {
         let version = self.client.call("get_version").await.context("Error while retrieving version from daemon")?;
         Ok(version)
@@ -181,6 +229,15 @@ impl DaemonAPI {
         Ok(block)
     }
 
+    // Only the transactions involving the address are returned
+    pub async fn get_account_transactions_at_topoheight(&self, address: &Address, topoheight: u64) -> Result<Vec<TransactionResponse>> {
+        let txs = self.client.call_with("get_account_transactions_at_topoheight", &GetAccountTransactionsAtTopoHeightParams {
+            address: Cow::Borrowed(address),
+            topoheight
+        }).await.context(format!("Error while fetching account transactions at topoheight {}", topoheight))?;
+        Ok(txs)
+    }
+
     pub async fn get_transaction(&self, hash: &Hash) -> Result<Transaction> {
         let tx = self.client.call_with("get_transaction", &GetTransactionParams {
             hash: Cow::Borrowed(hash)
@@ -188,6 +245,22 @@ impl DaemonAPI {
         Ok(tx)
     }
 
+    // Same as get_transaction but also returns where the transaction is in the chain
+    pub async fn get_transaction_with_status(&self, hash: &Hash) -> Result<TransactionResponse<'static>> {
+        let tx = self.client.call_with("get_transaction", &GetTransactionParams {
+            hash: Cow::Borrowed(hash)
+        }).await.context(format!("Error while fetching transaction {}", hash))?;
+        Ok(tx)
+    }
+
+    pub async fn get_block_by_hash(&self, hash: &Hash) -> Result<BlockResponse> {
+        let block = self.client.call_with("get_block_by_hash", &GetBlockByHashParams {
+            hash: Cow::Borrowed(hash),
+            include_txs: false
+        }).await.context(format!("Error while fetching block {}", hash))?;
+        Ok(block)
+    }
+
     pub async fn submit_transaction(&self, transaction: &Transaction) -> Result<()> {
         let _: bool = self.client.call_with("submit_transaction", &SubmitTransactionParams {
             data: transaction.to_hex()
@@ -195,6 +268,14 @@ impl DaemonAPI {
         Ok(())
     }
 
+    // Verify the transaction without adding it to the mempool
+    pub async fn validate_transaction(&self, transaction: &Transaction) -> Result<()> {
+        let _: bool = self.client.call_with("validate_transaction", &SubmitTransactionParams {
+            data: transaction.to_hex()
+        }).await?;
+        Ok(())
+    }
+
     pub async fn get_nonce(&self, address: &Address) -> Result<GetNonceResult> {
         let nonce = self.client.call_with("get_nonce", &GetNonceParams {
             address: Cow::Borrowed(address)
@@ -202,6 +283,14 @@ impl DaemonAPI {
         Ok(nonce)
     }
 
+    // Returns None if the account never configured a multisig
+    pub async fn get_multisig(&self, address: &Address) -> Result<Option<GetMultiSigResult>> {
+        let multisig = self.client.call_with("get_multisig", &GetMultiSigParams {
+            address: Cow::Borrowed(address)
+        }).await.context(format!("Error while fetching multisig from address {}", address))?;
+        Ok(multisig)
+    }
+
     pub async fn is_tx_executed_in_block(&self, tx_hash: &Hash, block_hash: &Hash) -> Result<bool> {
         let is_executed = self.client.call_with("is_tx_executed_in_block", &IsTxExecutedInBlockParams {
             tx_hash: Cow::Borrowed(tx_hash),
@@ -0,0 +1,146 @@
+use std::str::FromStr;
+use xelis_common::{
+    api::DataElement,
+    crypto::Address,
+    serializer::{Reader, ReaderError, Serializer, Writer},
+    time::TimestampSeconds,
+    transaction::{
+        builder::{FeeBuilder, MultiSigBuilder, TransactionTypeBuilder, TransferBuilder},
+        BurnPayload,
+        MAX_TRANSFER_COUNT
+    }
+};
+
+// A transaction prepared by the user and saved under a name to be reviewed and sent later
+// Only its content is saved: the nonce and balances are read when it is sent,
+// so a draft stays valid even if other transactions were sent in the meantime
+#[derive(Debug, Clone)]
+pub struct DraftTransaction {
+    tx_type: TransactionTypeBuilder,
+    fee: FeeBuilder,
+    // Timestamp in seconds of its creation
+    created_at: TimestampSeconds
+}
+
+impl DraftTransaction {
+    pub fn new(tx_type: TransactionTypeBuilder, fee: FeeBuilder, created_at: TimestampSeconds) -> Self {
+        Self {
+            tx_type,
+            fee,
+            created_at
+        }
+    }
+
+    pub fn get_tx_type(&self) -> &TransactionTypeBuilder {
+        &self.tx_type
+    }
+
+    pub fn get_fee(&self) -> &FeeBuilder {
+        &self.fee
+    }
+
+    pub fn get_created_at(&self) -> TimestampSeconds {
+        self.created_at
+    }
+
+    pub fn consume(self) -> (TransactionTypeBuilder, FeeBuilder) {
+        (self.tx_type, self.fee)
+    }
+}
+
+impl Serializer for DraftTransaction {
+    fn read(reader: &mut Reader) -> Result<Self, ReaderError> {
+        let tx_type = match reader.read_u8()? {
+            0 => {
+                let count = reader.read_u8()? as usize;
+                if count == 0 || count > MAX_TRANSFER_COUNT {
+                    return Err(ReaderError::InvalidSize)
+                }
+
+                let mut transfers = Vec::with_capacity(count);
+                for _ in 0..count {
+                    let destination = Address::from_str(&String::read(reader)?)
+                        .map_err(|_| ReaderError::InvalidValue)?;
+                    let asset = reader.read_hash()?;
+                    let amount = reader.read_u64()?;
+                    let extra_data = Option::<DataElement>::read(reader)?;
+                    transfers.push(TransferBuilder {
+                        destination,
+                        asset,
+                        amount,
+                        extra_data
+                    });
+                }
+                TransactionTypeBuilder::Transfers(transfers)
+            },
+            1 => TransactionTypeBuilder::Burn(BurnPayload::read(reader)?),
+            2 => {
+                let threshold = reader.read_u8()?;
+                let count = reader.read_u8()? as usize;
+                let mut participants = Vec::with_capacity(count);
+                for _ in 0..count {
+                    let participant = Address::from_str(&String::read(reader)?)
+                        .map_err(|_| ReaderError::InvalidValue)?;
+                    participants.push(participant);
+                }
+                TransactionTypeBuilder::MultiSig(MultiSigBuilder {
+                    threshold,
+                    participants
+                })
+            },
+            _ => return Err(ReaderError::InvalidValue)
+        };
+
+        let fee = match reader.read_u8()? {
+            0 => FeeBuilder::Multiplier(f64::from_bits(reader.read_u64()?)),
+            1 => FeeBuilder::Value(reader.read_u64()?),
+            _ => return Err(ReaderError::InvalidValue)
+        };
+        let created_at = reader.read_u64()?;
+
+        Ok(Self {
+            tx_type,
+            fee,
+            created_at
+        })
+    }
+
+    fn write(&self, writer: &mut Writer) {
+        match &self.tx_type {
+            TransactionTypeBuilder::Transfers(transfers) => {
+                writer.write_u8(0);
+                writer.write_u8(transfers.len() as u8);
+                for transfer in transfers {
+                    transfer.destination.to_string().write(writer);
+                    writer.write_hash(&transfer.asset);
+                    writer.write_u64(&transfer.amount);
+                    transfer.extra_data.write(writer);
+                }
+            },
+            TransactionTypeBuilder::Burn(payload) => {
+                writer.write_u8(1);
+                payload.write(writer);
+            },
+            TransactionTypeBuilder::MultiSig(multisig) => {
+                writer.write_u8(2);
+                writer.write_u8(multisig.threshold);
+                writer.write_u8(multisig.participants.len() as u8);
+                for participant in &multisig.participants {
+                    participant.to_string().write(writer);
+                }
+            }
+        };
+
+        match &self.fee {
+            FeeBuilder::Multiplier(multiplier) => {
+                writer.write_u8(0);
+                writer.write_u64(&multiplier.to_bits());
+            },
+            FeeBuilder::Value(value) => {
+                writer.write_u8(1);
+                writer.write_u64(value);
+            }
+        };
+        writer.write_u64(&self.created_at);
+    }
+}
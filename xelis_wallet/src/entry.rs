@@ -13,18 +13,21 @@ use xelis_common::{
         Hash,
         PublicKey
     },
+    network::Network,
     serializer::{
         Reader,
         ReaderError,
         Serializer,
         Writer
     },
+    time::{TimestampMillis, TimestampSeconds},
     utils::{
         format_coin,
         format_xelis
     }
 };
 use anyhow::Result;
+use serde::Serialize;
 use crate::storage::EncryptedStorage;
 
 #[derive(Debug, Clone)]
@@ -46,7 +49,9 @@ pub struct TransferIn {
     // Amount spent
     amount: u64,
     // Extra data with good format
-    extra_data: Option<DataElement>
+    extra_data: Option<DataElement>,
+    // Index of the subaddress that received it, None for the main address
+    subaddress: Option<u32>
 }
 
 impl TransferOut {
@@ -78,11 +83,12 @@ impl TransferOut {
 
 
 impl TransferIn {
-    pub fn new(asset: Hash, amount: u64, extra_data: Option<DataElement>) -> Self {
+    pub fn new(asset: Hash, amount: u64, extra_data: Option<DataElement>, subaddress: Option<u32>) -> Self {
         Self {
             asset,
             amount,
-            extra_data
+            extra_data,
+            subaddress
         }
     }
 
@@ -97,6 +103,10 @@ impl TransferIn {
     pub fn get_extra_data(&self) -> &Option<DataElement> {
         &self.extra_data
     }
+
+    pub fn get_subaddress(&self) -> Option<u32> {
+        self.subaddress
+    }
 }
 
 impl Serializer for TransferOut {
@@ -135,10 +145,12 @@ impl Serializer for TransferIn {
 
         let extra_data = Option::read(reader)?;
 
+        // Only written in entries using subaddresses, see EntryData
         Ok(Self {
             asset,
             amount,
-            extra_data
+            extra_data,
+            subaddress: None
         })
     }
 
@@ -195,7 +207,7 @@ impl Serializer for EntryData {
                     transfers.push(transfer);
                 }
                 Self::Incoming { from: key, transfers }
-            }
+            },
             3 => {
                 let size = reader.read_u16()? as usize;
                 let mut transfers = Vec::new();
@@ -207,7 +219,20 @@ impl Serializer for EntryData {
                 let nonce = reader.read_u64()?;
 
                 Self::Outgoing { transfers, fee, nonce }
-            }
+            },
+            // Incoming entry with the subaddress of each transfer
+            // A new id is used to keep reading the entries saved before
+            4 => {
+                let key = PublicKey::read(reader)?;
+                let size = reader.read_u16()? as usize;
+                let mut transfers = Vec::new();
+                for _ in 0..size {
+                    let mut transfer = TransferIn::read(reader)?;
+                    transfer.subaddress = Option::read(reader)?;
+                    transfers.push(transfer);
+                }
+                Self::Incoming { from: key, transfers }
+            },
             _ => return Err(ReaderError::InvalidValue)
         }) 
     }
@@ -224,11 +249,15 @@ impl Serializer for EntryData {
                 writer.write_u64(amount);
             },
             Self::Incoming { from, transfers } => {
-                writer.write_u8(2);
+                let subaddresses = transfers.iter().any(|t| t.subaddress.is_some());
+                writer.write_u8(if subaddresses { 4 } else { 2 });
                 from.write(writer);
                 writer.write_u16(transfers.len() as u16);
                 for transfer in transfers {
                     transfer.write(writer);
+                    if subaddresses {
+                        transfer.subaddress.write(writer);
+                    }
                 }
             },
             Self::Outgoing { transfers, fee, nonce } => {
@@ -248,7 +277,8 @@ impl Serializer for EntryData {
             Self::Coinbase { reward } => reward.size(),
             Self::Burn { asset, amount } => asset.size() + amount.size(),
             Self::Incoming { from, transfers } => {
-                from.size() + 2 + transfers.iter().map(|t| t.size()).sum::<usize>()
+                let subaddresses = transfers.iter().any(|t| t.subaddress.is_some());
+                from.size() + 2 + transfers.iter().map(|t| t.size() + if subaddresses { t.subaddress.size() } else { 0 }).sum::<usize>()
             },
             Self::Outgoing { transfers, fee, nonce } => {
                 2 + transfers.iter().map(|t| t.size()).sum::<usize>() + fee.size() + nonce.size()
@@ -291,7 +321,7 @@ impl TransactionEntry {
 
     // Convert to RPC Transaction Entry
     // This is a necessary step to serialize correctly the public key into an address
-    pub fn serializable(self, mainnet: bool) -> RPCTransactionEntry {
+    pub fn serializable(self, network: Network) -> RPCTransactionEntry {
         RPCTransactionEntry {
             hash: self.hash,
             topoheight: self.topoheight,
@@ -302,13 +332,14 @@ impl TransactionEntry {
                     let transfers = transfers.into_iter().map(|t| RPCTransferIn {
                         asset: t.asset,
                         amount: t.amount,
-                        extra_data: t.extra_data
+                        extra_data: t.extra_data,
+                        subaddress: t.subaddress
                     }).collect();
-                    RPCEntryType::Incoming { from: from.to_address(mainnet), transfers }
+                    RPCEntryType::Incoming { from: from.to_address(network), transfers }
                 },
                 EntryData::Outgoing { transfers, fee, nonce } => {
                     let transfers = transfers.into_iter().map(|t| RPCTransferOut {
-                        destination: t.destination.to_address(mainnet),
+                        destination: t.destination.to_address(network),
                         asset: t.asset,
                         amount: t.amount,
                         extra_data: t.extra_data
@@ -319,7 +350,7 @@ impl TransactionEntry {
         }
     }
 
-    pub fn summary(&self, mainnet: bool, storage: &EncryptedStorage) -> Result<String> {
+    pub fn summary(&self, network: Network, storage: &EncryptedStorage) -> Result<String> {
         let entry_str = match self.get_entry() {
             EntryData::Coinbase { reward } => format!("Coinbase {} XELIS", format_xelis(*reward)),
             EntryData::Burn { asset, amount } => {
@@ -330,10 +361,23 @@ impl TransactionEntry {
                 let mut str = String::new();
                 for transfer in transfers {
                     if *transfer.get_asset() == XELIS_ASSET {
-                        str.push_str(&format!("Received {} XELIS from {}", format_xelis(transfer.get_amount()), from.as_address(mainnet)));
+                        str.push_str(&format!("Received {} XELIS from {}", format_xelis(transfer.get_amount()), from.as_address(network)));
                     } else {
                         let decimals = storage.get_asset_decimals(transfer.get_asset())?;
-                        str.push_str(&format!("Received {} {} from {}", format_coin(transfer.get_amount(), decimals), transfer.get_asset(), from.as_address(mainnet)));
+                        str.push_str(&format!("Received {} {} from {}", format_coin(transfer.get_amount(), decimals), transfer.get_asset(), from.as_address(network)));
+                    }
+
+                    if let Some(label) = storage.get_address_label(from)? {
+                        str.push_str(&format!(" ({})", label));
+                    }
+
+                    if let Some(subaddress) = transfer.get_subaddress() {
+                        str.push_str(&format!(" on subaddress #{}", subaddress));
+                    }
+
+                    // Show the data sent (from an integrated address for example) to match the payment
+                    if let Some(data) = transfer.get_extra_data() {
+                        str.push_str(&format!(" with data {}", format_extra_data(data)));
                     }
                 }
                 str
@@ -342,17 +386,115 @@ impl TransactionEntry {
                 let mut str = format!("Fee: {}, Nonce: {} ", format_xelis(*fee), nonce);
                 for transfer in transfers {
                     if *transfer.get_asset() == XELIS_ASSET {
-                        str.push_str(&format!("Sent {} XELIS to {}", format_xelis(transfer.get_amount()), transfer.get_destination().as_address(mainnet)));
+                        str.push_str(&format!("Sent {} XELIS to {}", format_xelis(transfer.get_amount()), transfer.get_destination().as_address(network)));
                     } else {
                         let decimals = storage.get_asset_decimals(transfer.get_asset())?;
-                        str.push_str(&format!("Sent {} {} to {}", format_coin(transfer.get_amount(), decimals), transfer.get_asset(), transfer.get_destination().as_address(mainnet)));
+                        str.push_str(&format!("Sent {} {} to {}", format_coin(transfer.get_amount(), decimals), transfer.get_asset(), transfer.get_destination().as_address(network)));
+                    }
+
+                    if let Some(label) = storage.get_address_label(transfer.get_destination())? {
+                        str.push_str(&format!(" ({})", label));
+                    }
+
+                    // Decrypted with our sender handle
+                    if let Some(data) = transfer.get_extra_data() {
+                        str.push_str(&format!(" with data {}", format_extra_data(data)));
                     }
                 }
                 str
             }
         };
 
-        Ok(format!("Hash {} at TopoHeight {}: {}", self.hash, self.topoheight, entry_str))
+        let summary = format!("Hash {} at TopoHeight {}: {}", self.hash, self.topoheight, entry_str);
+        match storage.get_transaction_label(&self.hash)? {
+            Some(label) => Ok(format!("{} [{}]", summary, label)),
+            None => Ok(summary)
+        }
+    }
+}
+
+// Format extra data in JSON to be human readable
+pub fn format_extra_data(data: &DataElement) -> String {
+    serde_json::to_string(data).unwrap_or_else(|_| "<invalid data>".to_owned())
+}
+
+// Flat representation of a transaction entry used to export the history
+// One record is created per transfer, amounts are in human readable units
+#[derive(Serialize)]
+pub struct ExportRecord {
+    pub hash: Hash,
+    pub topoheight: u64,
+    // Timestamp of the block in which the transaction was executed if known
+    pub timestamp: Option<TimestampMillis>,
+    pub entry_type: &'static str,
+    pub asset: Hash,
+    pub amount: String,
+    // Fee is only set on the first record of an outgoing transaction
+    pub fee: Option<String>,
+    // Sender for incoming and receiver for outgoing transfers
+    pub counterparty: Option<String>
+}
+
+impl ExportRecord {
+    pub const CSV_HEADER: &'static str = "hash,topoheight,timestamp,type,asset,amount,fee,counterparty";
+
+    // All fields are hex, numbers or addresses, so they never need to be escaped
+    pub fn to_csv_line(&self) -> String {
+        format!("{},{},{},{},{},{},{},{}",
+            self.hash,
+            self.topoheight,
+            self.timestamp.map(|v| v.to_string()).unwrap_or_default(),
+            self.entry_type,
+            self.asset,
+            self.amount,
+            self.fee.as_deref().unwrap_or_default(),
+            self.counterparty.as_deref().unwrap_or_default()
+        )
+    }
+}
+
+impl TransactionEntry {
+    // Convert this entry into records for export
+    pub fn to_export_records(&self, network: Network, timestamp: Option<TimestampMillis>, storage: &EncryptedStorage) -> Result<Vec<ExportRecord>> {
+        let record = |entry_type: &'static str, asset: &Hash, amount: String, fee: Option<String>, counterparty: Option<String>| ExportRecord {
+            hash: self.hash.clone(),
+            topoheight: self.topoheight,
+            timestamp,
+            entry_type,
+            asset: asset.clone(),
+            amount,
+            fee,
+            counterparty
+        };
+
+        let records = match self.get_entry() {
+            EntryData::Coinbase { reward } => vec![record("coinbase", &XELIS_ASSET, format_xelis(*reward), None, None)],
+            EntryData::Burn { asset, amount } => {
+                let decimals = storage.get_asset_decimals(asset)?;
+                vec![record("burn", asset, format_coin(*amount, decimals), None, None)]
+            },
+            EntryData::Incoming { from, transfers } => {
+                let from = from.as_address(network).to_string();
+                let mut records = Vec::with_capacity(transfers.len());
+                for transfer in transfers {
+                    let decimals = storage.get_asset_decimals(transfer.get_asset())?;
+                    records.push(record("incoming", transfer.get_asset(), format_coin(transfer.get_amount(), decimals), None, Some(from.clone())));
+                }
+                records
+            },
+            EntryData::Outgoing { transfers, fee, .. } => {
+                let mut records = Vec::with_capacity(transfers.len());
+                for (i, transfer) in transfers.iter().enumerate() {
+                    let decimals = storage.get_asset_decimals(transfer.get_asset())?;
+                    let fee = if i == 0 { Some(format_xelis(*fee)) } else { None };
+                    let destination = transfer.get_destination().as_address(network).to_string();
+                    records.push(record("outgoing", transfer.get_asset(), format_coin(transfer.get_amount(), decimals), fee, Some(destination)));
+                }
+                records
+            }
+        };
+
+        Ok(records)
     }
 }
 
@@ -380,6 +522,69 @@ impl Serializer for TransactionEntry {
     }
 }
 
+// Transaction broadcasted by the wallet but not yet executed in a block
+#[derive(Debug, Clone)]
+pub struct PendingTransaction {
+    hash: Hash,
+    nonce: u64,
+    fee: u64,
+    // Timestamp in seconds when it was submitted
+    timestamp: TimestampSeconds
+}
+
+impl PendingTransaction {
+    pub fn new(hash: Hash, nonce: u64, fee: u64, timestamp: TimestampSeconds) -> Self {
+        Self {
+            hash,
+            nonce,
+            fee,
+            timestamp
+        }
+    }
+
+    pub fn get_hash(&self) -> &Hash {
+        &self.hash
+    }
+
+    pub fn get_nonce(&self) -> u64 {
+        self.nonce
+    }
+
+    pub fn get_fee(&self) -> u64 {
+        self.fee
+    }
+
+    pub fn get_timestamp(&self) -> TimestampSeconds {
+        self.timestamp
+    }
+}
+
+impl Serializer for PendingTransaction {
+    fn read(reader: &mut Reader) -> Result<Self, ReaderError> {
+        let hash = reader.read_hash()?;
+        let nonce = reader.read_u64()?;
+        let fee = reader.read_u64()?;
+        let timestamp = reader.read_u64()?;
+
+        Ok(Self {
+            hash,
+            nonce,
+            fee,
+            timestamp
+        })
+    }
+
+    fn write(&self, writer: &mut Writer) {
+        writer.write_hash(&self.hash);
+        writer.write_u64(&self.nonce);
+        writer.write_u64(&self.fee);
+        writer.write_u64(&self.timestamp);
+    }
+
+    fn size(&self) -> usize {
+        self.hash.size() + self.nonce.size() + self.fee.size() + self.timestamp.size()
+    }
+}
 
 pub enum Transfer<'a> {
     In(&'a mut TransferIn),
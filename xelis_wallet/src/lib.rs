@@ -1,5 +1,8 @@
 pub mod storage;
+pub mod backend;
+pub mod migration;
 pub mod wallet;
+pub mod builder;
 pub mod config;
 pub mod cipher;
 pub mod daemon_api;
@@ -7,6 +10,16 @@ pub mod network_handler;
 pub mod entry;
 pub mod mnemonics;
 pub mod transaction_builder;
+pub mod scheduler;
+pub mod draft;
+pub mod template;
+pub mod price;
+pub mod backup;
+pub mod paper;
+pub mod webhook;
+pub mod api_token;
+pub mod signer;
+pub mod password;
 
 #[cfg(feature = "api_server")]
 pub mod api;
\ No newline at end of file
@@ -1,14 +1,28 @@
 use std::{
-    ops::ControlFlow,
+    collections::HashMap,
+    fs,
     path::Path,
+    str::FromStr,
     sync::Arc,
     time::Duration
 };
-use anyhow::{Result, Context};
+use tokio::sync::broadcast::{error::RecvError, Receiver as BroadcastReceiver};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::io::{stdin, AsyncBufReadExt, BufReader};
+use anyhow::{bail, Result, Context};
+use rand::{rngs::OsRng, RngCore};
+use image::Luma;
+use qrcode::{render::unicode, Color as QrColor, QrCode};
 use fern::colors::Color;
-use log::{error, info};
-use clap::Parser;
+use log::{error, info, warn};
+use clap::{parser::ValueSource, CommandFactory, FromArgMatches, Parser};
 use xelis_common::{
+    api::{
+        wallet::{EntryType, SyncProgress, TransactionEntry},
+        DataElement,
+        RPCTransactionType
+    },
     async_handler,
     config::{
         COIN_DECIMALS,
@@ -16,10 +30,15 @@ use xelis_common::{
         XELIS_ASSET
     },
     crypto::{
-        ecdlp,
         Address,
-        Hashable
+        Hash,
+        Hashable,
+        KeyPair,
+        PaymentURI,
+        PrivateKey,
+        Signature
     },
+    json_rpc::{ProxyConfig, TlsConfig},
     network::Network,
     prompt::{
         self,
@@ -39,26 +58,49 @@ use xelis_common::{
         PromptError
     },
     serializer::Serializer,
+    time::{get_current_time_in_seconds, TimestampMillis},
     transaction::{
-        builder::{FeeBuilder, TransactionTypeBuilder, TransferBuilder},
+        builder::{FeeBuilder, MultiSigBuilder, TransactionTypeBuilder, TransferBuilder},
+        multisig::SignatureId,
         BurnPayload,
+        EXTRA_DATA_LIMIT_SIZE,
+        MAX_TRANSFER_COUNT,
         Transaction
     },
     utils::{
         format_coin,
-        format_xelis
+        format_xelis,
+        from_coin
     }
 };
 use xelis_wallet::{
-    wallet::Wallet,
-    config::{DEFAULT_DAEMON_ADDRESS, DIR_PATH}
+    wallet::{Event, Wallet},
+    builder::{LogProgressTableGenerationReportFunction, WalletBuilder},
+    config::{DEFAULT_DAEMON_ADDRESS, DEFAULT_PASSWORD_CALIBRATION_TARGET, DEFAULT_SYNC_BATCH_SIZE, DEFAULT_SYNC_CONCURRENCY, DIR_PATH, KEYFILE_SIZE},
+    entry::{format_extra_data, EntryData, ExportRecord},
+    draft::DraftTransaction,
+    scheduler::parse_duration,
+    template::TransferTemplate,
+    price::{HttpPriceProvider, PriceFeed, DEFAULT_PRICE_CURRENCY, DEFAULT_PRICE_REFRESH_INTERVAL},
+    storage::{EncryptedStorage, Storage},
+    paper::{PaperWallet, PAPER_WALLET_PREFIX},
+    backend::{open_backend, set_default_backend, StorageBackendKind},
+    cipher::hash_keyfile,
+    backup::WalletBackup,
+    transaction_builder::{get_selection_strategy, FeePriority, MultiSigTransaction, UnsignedTransaction},
+    signer::{discover_signer, discover_signers, get_backends, HardwareKeySigner, KeySigner},
+    webhook::run_webhooks,
+    password::PasswordParams
 };
 
 #[cfg(feature = "api_server")]
 use {
     xelis_wallet::{
+        api_token::{ApiToken, TokenScope},
         api::{
+            APIServer,
             AuthConfig,
+            Permission,
             PermissionResult,
             AppStateShared
         },
@@ -83,7 +125,7 @@ use {
 #[derive(Debug, clap::Args)]
 pub struct RPCConfig {
     /// RPC Server bind address
-    #[clap(long)]
+    #[clap(long, alias = "rpc-bind")]
     rpc_bind_address: Option<String>,
     /// username for RPC authentication
     #[clap(long)]
@@ -98,11 +140,55 @@ pub struct RPCConfig {
 #[command(styles = xelis_common::get_cli_styles())]
 pub struct Config {
     /// Daemon address to use
-    #[clap(long, default_value_t = String::from(DEFAULT_DAEMON_ADDRESS))]
-    daemon_address: String,
-    /// Disable online mode
+    /// 
+    /// It can be set several times (or separated by commas) to have fallback daemons,
+    /// they are used in order if the current one is unreachable.
+    #[clap(long, default_value = DEFAULT_DAEMON_ADDRESS, value_delimiter = ',')]
+    daemon_address: Vec<String>,
+    /// PEM file with additional certificate authorities to trust for https/wss daemon connections
     #[clap(long)]
+    daemon_ca_file: Option<String>,
+    /// SHA-256 fingerprint of a daemon certificate to accept
+    /// 
+    /// It can be set several times (or separated by commas).
+    /// When set, only the pinned certificates are accepted, even if self-signed.
+    #[clap(long, value_delimiter = ',')]
+    daemon_pinned_cert: Vec<String>,
+    /// SOCKS5 proxy used for all the daemon connections (e.g. socks5://127.0.0.1:9050 for Tor)
+    /// 
+    /// Host names are resolved by the proxy, use an explicit ws:// or wss:// scheme in the daemon address.
+    #[clap(long)]
+    proxy: Option<String>,
+    /// Disable online mode
+    /// 
+    /// It can be changed at runtime using the `online_mode` and `offline_mode` commands.
+    #[clap(long, alias = "offline")]
     offline_mode: bool,
+    /// Light sync mode, trusting the daemon
+    /// 
+    /// Only the block headers and the wallet transactions are downloaded.
+    /// Each transaction is checked to be listed in its block header, but the daemon
+    /// can still hide transactions and the proof of work of the headers is not verified.
+    /// Only use it with a daemon you trust.
+    /// The daemon must support the `get_account_transactions_at_topoheight` method.
+    #[clap(long)]
+    light_mode: bool,
+    /// Blocks requested per batch during a sync
+    /// 
+    /// A bigger batch syncs faster but uses more memory.
+    #[clap(long, default_value_t = DEFAULT_SYNC_BATCH_SIZE)]
+    sync_batch_size: usize,
+    /// Block requests sent in parallel to the daemon during a sync
+    #[clap(long, default_value_t = DEFAULT_SYNC_CONCURRENCY)]
+    sync_concurrency: usize,
+    /// How the balances are consumed by new transactions
+    /// 
+    /// - minimize_fee: lowest fees, transactions can be chained before being confirmed
+    /// - consolidate: only the confirmed balances are spent
+    /// - privacy: destinations are never sent to the daemon before the transaction,
+    ///   the account creation fee is always paid
+    #[clap(long, default_value = "minimize_fee")]
+    selection_strategy: String,
     /// Set log level
     #[clap(long, value_enum, default_value_t = LogLevel::Info)]
     log_level: LogLevel,
@@ -131,14 +217,94 @@ pub struct Config {
     #[clap(long)]
     precomputed_tables_path: Option<String>,
     /// Password used to open wallet
-    #[clap(long)]
+    /// 
+    /// Using this option is not recommended as it will be visible in your shell history.
+    /// If no password is provided, it will be asked in a masked prompt.
+    #[clap(long, env = "XELIS_WALLET_PASSWORD", hide_env_values = true)]
     password: Option<String>,
+    /// File containing the password used to open wallet
+    /// 
+    /// Only the first line of the file is used.
+    /// It takes precedence over the XELIS_WALLET_PASSWORD env variable.
+    #[clap(long)]
+    password_file: Option<String>,
+    /// File required in addition to the password to open the wallet
+    /// 
+    /// When creating a wallet, it can't be opened without this file anymore.
+    #[clap(long)]
+    keyfile: Option<String>,
     /// Restore wallet using seed
+    /// 
+    /// It can be a 24 words BIP39 mnemonic or a 25 words legacy seed.
     #[clap(long)]
     seed: Option<String>,
+    /// Create a watch-only wallet from an address
+    /// 
+    /// It tracks the nonce, the encrypted balances and the outgoing transactions of the address but can't spend funds.
+    /// Amounts can't be decrypted without the private key, so received transfers are not listed.
+    /// The encrypted balances are exported with `build_unsigned_tx` to be signed by the offline wallet.
+    #[clap(long, conflicts_with = "seed")]
+    watch_only: Option<String>,
     /// Network selected for chain
     #[clap(long, value_enum, default_value_t = Network::Mainnet)]
     network: Network,
+    /// Lock the wallet after this many seconds of inactivity
+    /// 
+    /// Spending funds will then require to unlock it using the password.
+    #[clap(long)]
+    lock_timeout: Option<u64>,
+    /// Sign transactions using a hardware device of a registered signer backend
+    /// 
+    /// The private key never leaves the device, a new wallet is created as watch-only from its address.
+    #[clap(long, conflicts_with_all = ["seed", "watch_only"])]
+    hardware_signer: Option<String>,
+    /// Maximum fee in XELIS accepted for a transaction
+    /// 
+    /// Any transaction requiring a higher fee is refused before being built.
+    #[clap(long)]
+    max_fee: Option<String>,
+    /// Keep only the last N transactions in history
+    /// 
+    /// Older transactions are deleted from storage, balances are not affected.
+    #[clap(long)]
+    history_max_transactions: Option<u64>,
+    /// Keep only the transactions of the last N days in history
+    /// 
+    /// The age is estimated from the topoheight of the transactions.
+    #[clap(long)]
+    history_max_days: Option<u64>,
+    /// Price API used to display approximate fiat values
+    /// 
+    /// It must be compatible with CoinGecko `simple/price` endpoint (e.g. https://api.coingecko.com/api/v3/simple/price).
+    /// Fiat values are disabled if not set.
+    #[clap(long)]
+    price_provider: Option<String>,
+    /// Currency used to display fiat values
+    #[clap(long, default_value_t = String::from(DEFAULT_PRICE_CURRENCY))]
+    price_currency: String,
+    /// Delay in seconds before refreshing the price of an asset
+    #[clap(long, default_value_t = DEFAULT_PRICE_REFRESH_INTERVAL)]
+    price_refresh_interval: u64,
+    /// Storage backend used when creating a new wallet
+    /// 
+    /// Existing wallets are always opened with the backend they were created with.
+    /// SQLite requires the wallet to be built with the `sqlite` feature.
+    #[clap(long, value_enum, default_value_t = StorageBackendKind::Sled)]
+    storage_backend: StorageBackendKind,
+    /// Execute a command without the interactive prompt and exit, can be repeated
+    /// 
+    /// Commands are executed in order once the wallet is synced, each result is printed as a JSON line on stdout.
+    /// Logs are only written in the log file. It stops at the first command failing and exits with code 1.
+    /// The wallet path and its password are required, confirmations are accepted automatically.
+    #[clap(long)]
+    exec: Vec<String>,
+    /// Read commands as JSON lines from stdin and write the responses and events as JSON lines on stdout
+    /// 
+    /// Each line must be an object like {"id": 1, "command": "transfer", "args": ["--address", "xet:...", "--amount", "10"]}.
+    /// Logs are only written in the log file. It stops when stdin is closed or the exit command is sent.
+    /// The wallet path and its password are required, confirmations are accepted automatically.
+    #[clap(long, conflicts_with = "exec")]
+    json_stdin: bool,
     /// RPC Server configuration
     #[cfg(feature = "api_server")]
     #[structopt(flatten)]
@@ -146,23 +312,34 @@ pub struct Config {
     /// XSWD Server configuration
     #[cfg(feature = "api_server")]
     #[clap(long)]
-    enable_xswd: bool
-}
-
-/// This struct is used to log the progress of the table generation
-struct LogProgressTableGenerationReportFunction;
-
-impl ecdlp::ProgressTableGenerationReportFunction for LogProgressTableGenerationReportFunction {
-    fn report(&self, progress: f64, step: ecdlp::ReportStep) -> ControlFlow<()> {
-        info!("Progress: {:.2}% on step {:?}", progress * 100.0, step);
-        ControlFlow::Continue(())
-    }
+    enable_xswd: bool,
+    /// gRPC Server bind address
+    /// 
+    /// Clients must authenticate using an API token (see the api_token command).
+    #[cfg(feature = "grpc")]
+    #[clap(long)]
+    grpc_bind_address: Option<String>
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let config: Config = Config::parse();
-    let prompt = Prompt::new(config.log_level, &config.logs_path, &config.filename_log, config.disable_file_logging)?;
+    let matches = Config::command().get_matches();
+    // clap would also reject --password-file when the password comes from the env variable,
+    // so only the explicit --password option is considered as a conflict
+    if matches.value_source("password") == Some(ValueSource::CommandLine) && matches.contains_id("password_file") {
+        bail!("--password and --password-file can't be used together");
+    }
+    let config = Config::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    let headless = !config.exec.is_empty() || config.json_stdin;
+    if headless && config.wallet_path.is_none() {
+        bail!("A wallet path is required to execute commands");
+    }
+
+    let prompt = if headless {
+        Prompt::new_headless(config.log_level, &config.logs_path, &config.filename_log, config.disable_file_logging)?
+    } else {
+        Prompt::new(config.log_level, &config.logs_path, &config.filename_log, config.disable_file_logging)?
+    };
 
     #[cfg(feature = "api_server")]
     {
@@ -186,40 +363,175 @@ async fn main() -> Result<()> {
         }
     }
 
+    set_default_backend(config.storage_backend);
+
     let command_manager = CommandManager::new(prompt.clone());
     command_manager.store_in_context(config.network)?;
 
+    // Fiat values are only displayed if a price provider is set
+    if let Some(url) = config.price_provider.as_ref() {
+        info!("Using price provider {} for {}", url, config.price_currency.to_uppercase());
+        match HttpPriceProvider::new(url.clone()) {
+            Ok(provider) => command_manager.store_in_context(PriceFeed::new(Box::new(provider), config.price_currency.clone(), config.price_refresh_interval))?,
+            Err(e) => error!("Error while creating the price provider: {}", e)
+        }
+    }
+
     command_manager.register_default_commands()?;
+    // Any command resets the idle timer, unless the wallet got already locked
+    command_manager.set_before_command(|manager| {
+        if let Ok(context) = manager.get_context().lock() {
+            if let Some(wallet) = context.get_optional::<Arc<Wallet>>() {
+                if !wallet.is_locked() {
+                    wallet.refresh_activity();
+                }
+            }
+        }
+    })?;
+
+    // seed can only be used to create a wallet at a specific location
+    if config.seed.is_some() && config.wallet_path.is_none() {
+        error!("Invalid parameters configuration: seed can only be used with a wallet path");
+        return Ok(())
+    }
+
+    // watch-only wallet can only be created at a specific location
+    if config.watch_only.is_some() && config.wallet_path.is_none() {
+        error!("Invalid parameters configuration: watch-only can only be used with a wallet path");
+        return Ok(())
+    }
+
+    if let Some(seed) = config.seed.as_ref() {
+        if !is_valid_seed_words_count(seed) {
+            error!("Invalid seed: it must be a 24 words mnemonic or a 25 words legacy seed");
+            return Ok(())
+        }
+    }
 
     if let Some(path) = config.wallet_path {
-        // read password from option or ask him
-        let password = if let Some(password) = config.password {
-            password
+        let exists = Path::new(&path).is_dir();
+        if exists && config.seed.is_some() {
+            error!("A wallet already exists at {}, it cannot be recovered from seed", path);
+            return Ok(())
+        }
+
+        if exists && config.watch_only.is_some() {
+            error!("A wallet already exists at {}, it cannot be created as watch-only", path);
+            return Ok(())
+        }
+
+        let watch_only = match config.watch_only.as_ref().map(|address| Address::from_string(address)) {
+            Some(Ok(address)) => Some(address),
+            Some(Err(e)) => {
+                error!("Invalid watch-only address: {}", e);
+                return Ok(())
+            },
+            None => None
+        };
+
+        // The wallet only knows the address of the device
+        let watch_only = match config.hardware_signer.as_ref() {
+            Some(name) if !exists => {
+                let signer = discover_signer(name).context("Error while looking for the hardware signer")?;
+                info!("Please confirm the address on your {} device", signer.get_name());
+                signer.confirm_address(config.network.is_mainnet()).await
+                    .context("Address was not confirmed on the device")?;
+                let public_key = signer.get_public_key().await
+                    .context("Error while requesting the address of the device")?;
+                Some(public_key.to_address(config.network))
+            },
+            _ => watch_only
+        };
+
+        // read password from option, file, env or ask him
+        // If no password is provided for a new wallet, the creation wizard is used
+        let password = if let Some(password_file) = config.password_file {
+            let content = fs::read_to_string(&password_file)
+                .with_context(|| format!("Error while reading password file {}", password_file))?;
+            Some(content.lines().next().unwrap_or_default().to_owned())
+        } else if let Some(password) = config.password {
+            Some(password)
+        } else if exists || watch_only.is_some() {
+            Some(prompt.read_input(format!("Enter Password for '{}': ", path), true).await?)
         } else {
-            prompt.read_input(format!("Enter Password for '{}': ", path), true).await?
+            None
+        };
+
+        let mut builder = Wallet::builder()
+            .network(config.network);
+        if let Some(precomputed_tables_path) = config.precomputed_tables_path {
+            builder = builder.precomputed_tables_path(precomputed_tables_path);
+        }
+
+        if let Some(keyfile) = config.keyfile {
+            builder = builder.keyfile(read_keyfile(&keyfile)?);
+        }
+
+        let wallet = match password {
+            Some(password) if exists => {
+                info!("Opening wallet {}", path);
+                builder.path(path).password(password).open().await?
+            },
+            Some(password) => match watch_only {
+                Some(address) => {
+                    info!("Creating a new watch-only wallet at {}", path);
+                    builder.path(path).password(password).watch_only(address).create().await?
+                },
+                None => {
+                    info!("Creating a new wallet at {}", path);
+                    let new_seed = config.seed.is_none();
+                    if let Some(seed) = config.seed {
+                        builder = builder.seed(seed);
+                    }
+
+                    let wallet = builder.path(path).password(password).create().await?;
+                    if new_seed {
+                        confirm_seed(&command_manager, &wallet).await?;
+                    } else {
+                        // A new storage has no synced topoheight, so all the chain will be scanned
+                        info!("Wallet recovered from seed, it will be fully rescanned from genesis once online");
+                    }
+                    wallet
+                }
+            },
+            None => creation_wizard(&command_manager, path, config.seed, config.network, builder).await?
         };
 
-        let precomputed_tables = Wallet::read_or_generate_precomputed_tables(config.precomputed_tables_path, LogProgressTableGenerationReportFunction)?;
-        let wallet = if Path::new(&path).is_dir() {
-            info!("Opening wallet {}", path);
-            Wallet::open(path, password, config.network, precomputed_tables)?
+        // Subscribe before going online to not miss the end of the first sync
+        let events = if headless {
+            Some(wallet.subscribe_events().await)
         } else {
-            info!("Creating a new wallet at {}", path);
-            Wallet::create(path, password, config.seed, config.network, precomputed_tables)?
+            None
         };
 
         apply_config(&wallet, #[cfg(feature = "api_server")] &prompt).await;
+        if let Some(receiver) = events {
+            wait_for_sync(&wallet, receiver).await;
+        }
+
         setup_wallet_command_manager(wallet, &command_manager).await?;
     } else {
         command_manager.add_command(Command::new("open", "Open a wallet", CommandHandler::Async(async_handler!(open_wallet))))?;
         command_manager.add_command(Command::new("create", "Create a new wallet", CommandHandler::Async(async_handler!(create_wallet))))?;
         command_manager.add_command(Command::new("recover", "Recover a wallet using a seed", CommandHandler::Async(async_handler!(recover_wallet))))?;
+        command_manager.add_command(Command::new("restore", "Restore a wallet from a backup file", CommandHandler::Async(async_handler!(restore_wallet))))?;
+        command_manager.add_command(Command::new("import_key", "Create a wallet from a hex private key or a key file", CommandHandler::Async(async_handler!(import_key))))?;
+        command_manager.add_command(Command::new("import_private_key", "Create a view-only wallet from a private key, it shows the balances but refuses to sign", CommandHandler::Async(async_handler!(import_private_key))))?;
+        command_manager.add_command(Command::with_optional_arguments("import_paper", "Create a wallet from the text of a paper wallet exported by 'export_paper', or the path to its text file", vec![Arg::new("paper", ArgType::String)], CommandHandler::Async(async_handler!(import_paper))))?;
 
         // Display available commands
         command_manager.display_commands()?;
     }
 
-    if let Err(e) = prompt.start(Duration::from_millis(1000), Box::new(async_handler!(prompt_message_builder)), Some(&command_manager)).await {
+    let mut success = true;
+    if config.json_stdin {
+        if let Err(e) = run_json_stdin(&command_manager).await {
+            error!("Error while running JSON commands: {}", e);
+            success = false;
+        }
+    } else if headless {
+        success = execute_commands(&command_manager, config.exec).await;
+    } else if let Err(e) = prompt.start(Duration::from_millis(1000), Box::new(async_handler!(prompt_message_builder)), Some(&command_manager)).await {
         error!("Error while running prompt: {}", e);
     }
 
@@ -229,6 +541,155 @@ async fn main() -> Result<()> {
         }
     }
 
+    if !success {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+// Wait until the first sync is done, or the daemon connection is lost
+async fn wait_for_sync(wallet: &Arc<Wallet>, mut receiver: BroadcastReceiver<Event>) {
+    if !wallet.is_online().await {
+        return;
+    }
+
+    info!("Waiting for the wallet to be synced");
+    loop {
+        match receiver.recv().await {
+            Ok(Event::NewTopoHeight { .. }) | Ok(Event::Disconnected) | Ok(Event::Offline) => break,
+            Ok(_) | Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => break
+        }
+    }
+}
+
+// Execute a command and build its JSON result with the messages it sent
+// The arguments are given already split, so they can contain spaces
+async fn execute_command(manager: &CommandManager, name: &str, args: &[String]) -> (Value, Result<(), CommandError>) {
+    let command = std::iter::once(name).chain(args.iter().map(String::as_str)).collect::<Vec<_>>().join(" ");
+    info!("Executing command '{}'", command);
+    let res = manager.handle_command_with_args(name, args.iter().map(String::as_str)).await;
+    let output = manager.take_captured_output()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|message| json!({
+            "level": message.level.as_str().to_lowercase(),
+            "message": message.message
+        }))
+        .collect::<Vec<_>>();
+
+    let (success, error) = match &res {
+        Ok(()) | Err(CommandError::Exit) => (true, None),
+        Err(e) => (false, Some(format!("{:#}", e)))
+    };
+
+    let result = json!({
+        "command": command,
+        "success": success,
+        "output": output,
+        "error": error
+    });
+
+    (result, res)
+}
+
+// Execute the commands given with --exec
+// The result of each command is printed as a JSON line on stdout
+// Returns false if a command failed, the next ones are not executed
+async fn execute_commands(manager: &CommandManager, commands: Vec<String>) -> bool {
+    if let Err(e) = manager.set_capture_output(true) {
+        error!("Error while capturing commands output: {}", e);
+        return false;
+    }
+
+    for command in commands {
+        let mut split = command.split_whitespace().map(str::to_owned);
+        let name = split.next().unwrap_or_default();
+        let args: Vec<String> = split.collect();
+        let (result, res) = execute_command(manager, &name, &args).await;
+        println!("{}", result);
+
+        match res {
+            Ok(()) => {},
+            Err(CommandError::Exit) => break,
+            Err(_) => return false
+        }
+    }
+
+    true
+}
+
+// Command read from stdin in --json-stdin mode
+// Each arg is given as is to the command, it can contain spaces
+#[derive(Deserialize)]
+struct JsonCommand {
+    #[serde(default)]
+    id: Value,
+    command: String,
+    #[serde(default)]
+    args: Vec<String>
+}
+
+// Read the commands as JSON lines from stdin until it is closed or the exit command is sent
+// Each response is printed as a JSON line on stdout with the id of its request,
+// wallet events are printed on stdout too as soon as they happen
+async fn run_json_stdin(manager: &CommandManager) -> Result<(), CommandError> {
+    manager.set_capture_output(true)?;
+    let wallet = Arc::clone(manager.get_context().lock()?.get::<Arc<Wallet>>()?);
+
+    let mut receiver = wallet.subscribe_events().await;
+    let events = tokio::spawn(async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) => println!("{}", json!({
+                    "event": event.kind(),
+                    "data": event
+                })),
+                Err(RecvError::Lagged(skipped)) => warn!("{} wallet events were not sent on stdout", skipped),
+                Err(RecvError::Closed) => break
+            }
+        }
+    });
+
+    let mut lines = BufReader::new(stdin()).lines();
+    loop {
+        let line = match lines.next_line().await {
+            Ok(Some(line)) => line,
+            Ok(None) => break,
+            Err(e) => {
+                error!("Error while reading stdin: {}", e);
+                break;
+            }
+        };
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let request: JsonCommand = match serde_json::from_str(&line) {
+            Ok(request) => request,
+            Err(e) => {
+                println!("{}", json!({
+                    "id": Value::Null,
+                    "success": false,
+                    "output": [],
+                    "error": format!("Invalid request: {}", e)
+                }));
+                continue;
+            }
+        };
+
+        let (mut result, res) = execute_command(manager, &request.command, &request.args).await;
+        result["id"] = request.id;
+        println!("{}", result);
+
+        if let Err(CommandError::Exit) = res {
+            break;
+        }
+    }
+
+    events.abort();
     Ok(())
 }
 
@@ -301,17 +762,169 @@ async fn xswd_handle_request_permission(prompt: &ShareablePrompt, app_state: App
     })
 }
 
+// Listen to the wallet events to notify the user of the new transactions
+// The history found during a (re)scan is not notified
+async fn notify_transactions(wallet: Arc<Wallet>) {
+    let mut receiver = wallet.subscribe_events().await;
+    let mut synced = false;
+    loop {
+        match receiver.recv().await {
+            Ok(Event::NewTopoHeight { .. }) => synced = true,
+            Ok(Event::Rescan { .. }) => synced = false,
+            Ok(Event::NewTransaction(entry)) if synced => notify_transaction(&wallet, entry).await,
+            Ok(_) | Err(RecvError::Lagged(_)) => {},
+            Err(RecvError::Closed) => break
+        }
+    }
+}
+
+async fn notify_transaction(wallet: &Wallet, entry: TransactionEntry) {
+    match entry.entry {
+        EntryType::Coinbase { reward } => {
+            info!("Received {} XELIS as block reward in block {}", format_xelis(reward), entry.hash);
+        },
+        EntryType::Incoming { from, transfers } => {
+            let storage = wallet.get_storage().read().await;
+            for transfer in transfers {
+                let decimals = storage.get_asset_decimals(&transfer.asset).unwrap_or(COIN_DECIMALS);
+                let asset = if transfer.asset == XELIS_ASSET {
+                    "XELIS".to_owned()
+                } else {
+                    transfer.asset.to_string()
+                };
+                let subaddress = transfer.subaddress.map(|index| format!(" on subaddress #{}", index)).unwrap_or_default();
+                match transfer.extra_data.as_ref() {
+                    Some(data) => info!("Received {} {} from {}{} in tx {} with data {}", format_coin(transfer.amount, decimals), asset, from, subaddress, entry.hash, format_extra_data(data)),
+                    None => info!("Received {} {} from {}{} in tx {}", format_coin(transfer.amount, decimals), asset, from, subaddress, entry.hash)
+                };
+            }
+        },
+        EntryType::Outgoing { transfers, .. } => {
+            info!("Outgoing transaction {} with {} transfer(s) confirmed at topoheight {}", entry.hash, transfers.len(), entry.topoheight);
+        },
+        EntryType::Burn { .. } => {
+            info!("Burn transaction {} confirmed at topoheight {}", entry.hash, entry.topoheight);
+        }
+    }
+}
+
 // Apply the config passed in params
 async fn apply_config(wallet: &Arc<Wallet>, #[cfg(feature = "api_server")] prompt: &ShareablePrompt) {
     let config: Config = Config::parse();
 
+    if let Some(name) = config.hardware_signer.as_ref() {
+        match discover_signer(name) {
+            Ok(device) => {
+                let signer = HardwareKeySigner::new(device);
+                let device_name = signer.get_name().to_owned();
+                match wallet.set_signer(Some(Arc::new(signer))).await {
+                    Ok(()) => info!("Transactions will be signed using the {} device", device_name),
+                    Err(e) => error!("Error while using the {} device as signer: {}", device_name, e)
+                };
+            },
+            Err(e) => error!("Error while looking for the hardware signer '{}': {} (available: {})", name, e, get_backends().join(", "))
+        };
+    }
+
+    if wallet.is_watch_only() && wallet.get_signer().await.is_none() {
+        warn!("Wallet is in watch-only mode: funds can't be spent and amounts can't be decrypted");
+    } else if wallet.is_view_only() {
+        warn!("Wallet is in view-only mode: balances are decrypted but funds can't be spent");
+    }
+
+    if let Some(max_fee) = config.max_fee {
+        match from_coin(&max_fee, COIN_DECIMALS) {
+            Some(value) if value > 0 => {
+                info!("Transactions with a fee above {} XELIS will be refused", format_xelis(value));
+                wallet.set_max_fee(Some(value));
+            },
+            _ => error!("Invalid maximum fee '{}', no limit will be applied", max_fee)
+        }
+    }
+
+    if let Some(timeout) = config.lock_timeout {
+        info!("Wallet will be locked after {} seconds of inactivity", timeout);
+        wallet.set_lock_timeout(Some(timeout));
+    }
+
+    // Show a line in the console for each new transaction
+    tokio::spawn(notify_transactions(Arc::clone(wallet)));
+    tokio::spawn(run_webhooks(Arc::clone(wallet)));
+
+    // The retention is not applied at all if the maximum age is invalid
+    let history_max_age = match config.history_max_days {
+        Some(days) => match days.checked_mul(24 * 60 * 60).filter(|_| days > 0) {
+            Some(seconds) => Ok(Some(seconds)),
+            None => Err(days)
+        },
+        None => Ok(None)
+    };
+
+    match history_max_age {
+        Ok(max_age) if config.history_max_transactions.is_some() || max_age.is_some() => {
+            info!("History retention: {} transactions, {} days", config.history_max_transactions.map(|v| v.to_string()).unwrap_or("unlimited".to_owned()), config.history_max_days.map(|v| v.to_string()).unwrap_or("unlimited".to_owned()));
+            wallet.set_history_retention(config.history_max_transactions, max_age);
+            if let Err(e) = wallet.prune_history().await {
+                error!("Error while pruning history: {}", e);
+            }
+        },
+        Ok(_) => {},
+        Err(days) => error!("Invalid history retention of {} days, history will not be pruned", days)
+    }
+
+    wallet.set_sync_options(config.sync_batch_size, config.sync_concurrency);
+
+    match get_selection_strategy(&config.selection_strategy) {
+        Some(strategy) => wallet.set_selection_strategy(strategy).await,
+        None => error!("Unknown selection strategy '{}', using the default one", config.selection_strategy)
+    }
+
+    if config.light_mode {
+        warn!("Light sync mode enabled, the daemon is trusted to return all the wallet transactions");
+        wallet.set_light_mode(true);
+    }
+
+    let tls = TlsConfig {
+        ca_file: config.daemon_ca_file,
+        pinned_certificates: config.daemon_pinned_cert
+    };
+    if !tls.is_default() {
+        wallet.set_tls_config(Some(tls)).await;
+    }
+
+    if let Some(proxy) = config.proxy {
+        match ProxyConfig::parse(&proxy) {
+            Ok(proxy) => {
+                info!("Daemon connections will go through proxy {}", proxy.get_address());
+                wallet.set_proxy(Some(proxy)).await;
+            },
+            Err(e) => {
+                // Don't connect directly: it would leak the network traffic
+                error!("Invalid proxy: {:#}", e);
+                return;
+            }
+        }
+    }
+
     if !config.offline_mode {
-        info!("Trying to connect to daemon at '{}'", config.daemon_address);
-        if let Err(e) = wallet.set_online_mode(&config.daemon_address).await {
-            error!("Couldn't connect to daemon: {}", e);
-            info!("You can activate online mode using 'online_mode [daemon_address]'");
-        } else {
-            info!("Online mode enabled");
+        // Use the daemon saved in the wallet if none was provided
+        let mut daemon_addresses = config.daemon_address;
+        if daemon_addresses.len() == 1 && daemon_addresses[0] == DEFAULT_DAEMON_ADDRESS {
+            let storage = wallet.get_storage().read().await;
+            if storage.has_daemon_address().unwrap_or(false) {
+                if let Ok(address) = storage.get_daemon_address() {
+                    daemon_addresses = vec![address];
+                }
+            }
+        }
+
+        info!("Trying to connect to daemon at '{}'", daemon_addresses.join("', '"));
+        match wallet.set_online_mode_with_fallbacks(daemon_addresses).await {
+            Ok(address) => info!("Online mode enabled using daemon '{}'", address),
+            Err(e) => {
+                error!("Couldn't connect to daemon: {}", e);
+                info!("You can activate online mode using 'online_mode [daemon_address]'");
+            }
         }
     }
 
@@ -347,6 +960,18 @@ async fn apply_config(wallet: &Arc<Wallet>, #[cfg(feature = "api_server")] promp
             };
         }
     }
+
+    #[cfg(feature = "grpc")]
+    {
+        if let Some(address) = config.grpc_bind_address {
+            match address.parse() {
+                Ok(address) => if let Err(e) = wallet.enable_grpc_server(address).await {
+                    error!("Error while enabling gRPC Server: {}", e);
+                },
+                Err(e) => error!("Invalid gRPC Server bind address '{}': {}", address, e)
+            }
+        }
+    }
 }
 
 // Function to build the CommandManager when a wallet is open
@@ -355,19 +980,67 @@ async fn setup_wallet_command_manager(wallet: Arc<Wallet>, command_manager: &Com
     command_manager.remove_command("open")?;
     command_manager.remove_command("recover")?;
     command_manager.remove_command("create")?;
+    command_manager.remove_command("restore")?;
+    command_manager.remove_command("import_key")?;
+    command_manager.remove_command("import_private_key")?;
+    command_manager.remove_command("import_paper")?;
 
     // Add wallet commands
+    command_manager.add_command(Command::new("lock", "Lock the wallet, password will be required to spend funds", CommandHandler::Async(async_handler!(lock))))?;
+    command_manager.add_command(Command::new("unlock", "Unlock the wallet using your password", CommandHandler::Async(async_handler!(unlock))))?;
     command_manager.add_command(Command::new("change_password", "Set a new password to open your wallet", CommandHandler::Async(async_handler!(change_password))))?;
-    command_manager.add_command(Command::with_optional_arguments("transfer", "Send asset to a specified address", vec![Arg::new("asset", ArgType::Hash)], CommandHandler::Async(async_handler!(transfer))))?;
-    command_manager.add_command(Command::with_optional_arguments("transfer_all", "Send all your asset balance to a specified address", vec![Arg::new("asset", ArgType::Hash)], CommandHandler::Async(async_handler!(transfer_all))))?;
-    command_manager.add_command(Command::with_required_arguments("burn", "Burn amount of asset", vec![Arg::new("asset", ArgType::Hash), Arg::new("amount", ArgType::Number)], CommandHandler::Async(async_handler!(burn))))?;
-    command_manager.add_command(Command::new("display_address", "Show your wallet address", CommandHandler::Async(async_handler!(display_address))))?;
+    command_manager.add_command(Command::with_arguments("keyfile", "Require a file in addition to your password to open the wallet (add <path>, remove)", vec![Arg::new("action", ArgType::String)], vec![Arg::new("path", ArgType::String)], CommandHandler::Async(async_handler!(keyfile))))?;
+    command_manager.add_command(Command::with_arguments("password_params", "Show or change the parameters used to hash your password (show, calibrate [ms], set <memory KiB> <iterations> <parallelism>, reset)", vec![Arg::new("action", ArgType::String)], vec![Arg::new("value", ArgType::Number), Arg::new("iterations", ArgType::Number), Arg::new("parallelism", ArgType::Number)], CommandHandler::Async(async_handler!(password_params))))?;
+    command_manager.add_command(Command::with_optional_arguments("transfer", "Send asset to a specified address with a fee priority (low, normal, high), address and amount are asked if not set, a nonce can be set to replace a stuck transaction", vec![Arg::new("asset", ArgType::Hash)], CommandHandler::Async(async_handler!(transfer))).with_options(vec![Arg::new("address", ArgType::String), Arg::new("amount", ArgType::String), Arg::new("priority", ArgType::String), Arg::new("nonce", ArgType::Number)]).with_flags(vec!["dry-run"]))?;
+    command_manager.add_command(Command::with_arguments("transfer_multi", "Send asset to several addresses or contacts in one transaction (address:amount,address:amount)", vec![Arg::new("transfers", ArgType::Array(Box::new(ArgType::String)))], vec![Arg::new("asset", ArgType::Hash)], CommandHandler::Async(async_handler!(transfer_multi))))?;
+    command_manager.add_command(Command::with_required_arguments("transfer_assets", "Send several assets to one or more addresses or contacts in one transaction (address amount:asset amount:asset [address amount:asset...], XELIS can be used as asset)", vec![Arg::new("address", ArgType::String), Arg::new("transfers", ArgType::Array(Box::new(ArgType::String)))], CommandHandler::Async(async_handler!(transfer_assets))))?;
+    command_manager.add_command(Command::with_optional_arguments("max_fee", "Show or set the maximum fee in XELIS accepted for a transaction (none to disable it)", vec![Arg::new("amount", ArgType::String)], CommandHandler::Async(async_handler!(max_fee))))?;
+    command_manager.add_command(Command::with_optional_arguments("selection_strategy", "Show or set how balances are consumed by new transactions (minimize_fee, consolidate, privacy)", vec![Arg::new("strategy", ArgType::String)], CommandHandler::Async(async_handler!(selection_strategy))))?;
+    command_manager.add_command(Command::with_arguments("transfer_all", "Send all your asset balance to a specified address", vec![Arg::new("address", ArgType::String)], vec![Arg::new("asset", ArgType::Hash)], CommandHandler::Async(async_handler!(transfer_all))))?;
+    command_manager.add_command(Command::with_arguments("estimate_fee", "Estimate the fee for a transfer to an address or contact", vec![Arg::new("address", ArgType::String)], vec![Arg::new("asset", ArgType::Hash)], CommandHandler::Async(async_handler!(estimate_fee))))?;
+    command_manager.add_command(Command::with_required_arguments("burn", "Burn amount of asset", vec![Arg::new("asset", ArgType::Hash), Arg::new("amount", ArgType::String)], CommandHandler::Async(async_handler!(burn))))?;
+    command_manager.add_command(Command::with_required_arguments("pay_csv", "Send payments listed in a CSV file (address,amount[,asset])", vec![Arg::new("file", ArgType::String)], CommandHandler::Async(async_handler!(pay_csv))))?;
+    command_manager.add_command(Command::with_required_arguments("build_unsigned_tx", "Export a transfer to a file to sign it on an offline wallet", vec![Arg::new("output", ArgType::String)], CommandHandler::Async(async_handler!(build_unsigned_tx))))?;
+    command_manager.add_command(Command::with_required_arguments("sign_tx", "Sign a transaction exported by build_unsigned_tx", vec![Arg::new("input", ArgType::String), Arg::new("output", ArgType::String)], CommandHandler::Async(async_handler!(sign_tx))))?;
+    command_manager.add_command(Command::with_required_arguments("broadcast_tx", "Broadcast a transaction signed by sign_tx", vec![Arg::new("input", ArgType::String)], CommandHandler::Async(async_handler!(broadcast_tx_file))))?;
+    command_manager.add_command(Command::with_required_arguments("multisig", "Manage the multisig setup of your account (setup, show, delete)", vec![Arg::new("action", ArgType::String)], CommandHandler::Async(async_handler!(multisig))))?;
+    command_manager.add_command(Command::with_required_arguments("multisig_sign", "Sign a multisig transaction as a participant and show the partial signature to send back", vec![Arg::new("input", ArgType::String)], CommandHandler::Async(async_handler!(multisig_sign))))?;
+    command_manager.add_command(Command::with_required_arguments("multisig_finalize", "Import the partial signatures of a multisig transaction and broadcast it", vec![Arg::new("input", ArgType::String)], CommandHandler::Async(async_handler!(multisig_finalize))))?;
+    command_manager.add_command(Command::with_required_arguments("label_tx", "Set a local note on a transaction, empty to remove it", vec![Arg::new("hash", ArgType::Hash)], CommandHandler::Async(async_handler!(label_tx))))?;
+    command_manager.add_command(Command::with_required_arguments("label_address", "Set a local note on an address, empty to remove it", vec![Arg::new("address", ArgType::String)], CommandHandler::Async(async_handler!(label_address))))?;
+    command_manager.add_command(Command::with_required_arguments("backup", "Export an encrypted backup of your keys, accounts and address book to a file", vec![Arg::new("path", ArgType::String)], CommandHandler::Async(async_handler!(backup))))?;
+    command_manager.add_command(Command::with_arguments("account", "Manage the accounts derived from your seed (create <name>, switch <index>, list)", vec![Arg::new("action", ArgType::String)], vec![Arg::new("value", ArgType::String)], CommandHandler::Async(async_handler!(account))))?;
+    command_manager.add_command(Command::with_arguments("address", "Manage the receive subaddresses derived from your seed (new [label], list)", vec![Arg::new("action", ArgType::String)], vec![Arg::new("label", ArgType::String)], CommandHandler::Async(async_handler!(address))))?;
+    command_manager.add_command(Command::with_arguments("sweep_subaddress", "Send the whole balance of an asset received on a subaddress to your main address (XELIS by default)", vec![Arg::new("index", ArgType::Number)], vec![Arg::new("asset", ArgType::Hash)], CommandHandler::Async(async_handler!(sweep_subaddress))))?;
+    command_manager.add_command(Command::with_arguments("schedule", "Manage scheduled and recurring transfers (add, remove <id>, list)", vec![Arg::new("action", ArgType::String)], vec![Arg::new("id", ArgType::Number)], CommandHandler::Async(async_handler!(schedule))))?;
+    command_manager.add_command(Command::with_arguments("draft", "Save a transfer to review and send it later (save <name>, show <name>, send <name>, remove <name>, list)", vec![Arg::new("action", ArgType::String)], vec![Arg::new("name", ArgType::String)], CommandHandler::Async(async_handler!(draft))))?;
+    command_manager.add_command(Command::with_arguments("template", "Save a payment to send it again with one command (save <name>, apply <name>, remove <name>, list)", vec![Arg::new("action", ArgType::String)], vec![Arg::new("name", ArgType::String)], CommandHandler::Async(async_handler!(template))))?;
+    command_manager.add_command(Command::with_arguments("webhook", "Manage the URLs notified on confirmed transfers (add <url>, remove <url>, list)", vec![Arg::new("action", ArgType::String)], vec![Arg::new("url", ArgType::String)], CommandHandler::Async(async_handler!(webhook))))?;
+    command_manager.add_command(Command::with_arguments("address_book", "Manage your contacts (add <name> <address>, remove <name>, list)", vec![Arg::new("action", ArgType::String)], vec![Arg::new("name", ArgType::String), Arg::new("address", ArgType::String)], CommandHandler::Async(async_handler!(address_book))))?;
+    command_manager.add_command(Command::new("sign_message", "Sign a message with your wallet key to prove ownership of your address", CommandHandler::Async(async_handler!(sign_message))))?;
+    command_manager.add_command(Command::new("verify_message", "Verify the signature of a message for an address", CommandHandler::Async(async_handler!(verify_message))))?;
+    command_manager.add_command(Command::new("generate_integrated_address", "Generate an integrated address containing data to identify payments", CommandHandler::Async(async_handler!(generate_integrated_address))))?;
+    command_manager.add_command(Command::with_optional_arguments("payment_request", "Generate a payment URI to receive funds", vec![Arg::new("amount", ArgType::String), Arg::new("asset", ArgType::Hash)], CommandHandler::Async(async_handler!(payment_request))))?;
+    command_manager.add_command(Command::with_required_arguments("hardware_signer", "Manage hardware signers (list, confirm_address)", vec![Arg::new("action", ArgType::String)], CommandHandler::Async(async_handler!(hardware_signer))))?;
+    command_manager.add_command(Command::with_optional_arguments("display_address", "Show your wallet address, optionally as a QR code and exported to a PNG file", vec![Arg::new("qr", ArgType::Bool), Arg::new("png_path", ArgType::String)], CommandHandler::Async(async_handler!(display_address))))?;
     command_manager.add_command(Command::with_optional_arguments("balance", "List all non-zero balances or show the selected one", vec![Arg::new("asset", ArgType::Hash)], CommandHandler::Async(async_handler!(balance))))?;
+    command_manager.add_command(Command::new("pending", "Show transactions broadcasted but not yet confirmed in a block", CommandHandler::Async(async_handler!(pending))))?;
+    command_manager.add_command(Command::with_required_arguments("cancel_tx", "Cancel a pending transaction by replacing it with a higher fee", vec![Arg::new("hash", ArgType::Hash)], CommandHandler::Async(async_handler!(cancel_tx))))?;
+    command_manager.add_command(Command::with_required_arguments("tx_info", "Show the details of a transaction by its hash", vec![Arg::new("hash", ArgType::Hash)], CommandHandler::Async(async_handler!(tx_info))))?;
+    command_manager.add_command(Command::new("list_assets", "List all assets tracked by the wallet with their balances", CommandHandler::Async(async_handler!(list_assets))))?;
     command_manager.add_command(Command::with_optional_arguments("history", "Show all your transactions", vec![Arg::new("page", ArgType::Number)], CommandHandler::Async(async_handler!(history))))?;
+    command_manager.add_command(Command::with_required_arguments("export_history", "Export all your transactions to a file (format: csv or json)", vec![Arg::new("format", ArgType::String), Arg::new("output", ArgType::String)], CommandHandler::Async(async_handler!(export_history))))?;
+    command_manager.add_command(Command::new("status", "Show the wallet connection and synchronization status", CommandHandler::Async(async_handler!(status))))?;
+    command_manager.add_command(Command::new("ping_daemon", "Measure the latency of the daemon and check its chain state", CommandHandler::Async(async_handler!(ping_daemon))))?;
     command_manager.add_command(Command::with_optional_arguments("online_mode", "Set your wallet in online mode", vec![Arg::new("daemon_address", ArgType::String)], CommandHandler::Async(async_handler!(online_mode))))?;
     command_manager.add_command(Command::new("offline_mode", "Set your wallet in offline mode", CommandHandler::Async(async_handler!(offline_mode))))?;
+    command_manager.add_command(Command::with_required_arguments("sync", "Control the background syncing with the daemon (pause, resume, status)", vec![Arg::new("action", ArgType::String)], CommandHandler::Async(async_handler!(sync))))?;
     command_manager.add_command(Command::with_optional_arguments("rescan", "Rescan balance and transactions", vec![Arg::new("topoheight", ArgType::Number)], CommandHandler::Async(async_handler!(rescan))))?;
-    command_manager.add_command(Command::with_optional_arguments("seed", "Show seed of selected language", vec![Arg::new("language", ArgType::Number)], CommandHandler::Async(async_handler!(seed))))?;
+    command_manager.add_command(Command::new("verify_wallet", "Check the wallet storage, keys and balances for corruption", CommandHandler::Async(async_handler!(verify_wallet))))?;
+    command_manager.add_command(Command::new("maintenance", "Remove orphaned entries, then compact the SQLite storage or flush the sled one (it reclaims space in the background)", CommandHandler::Async(async_handler!(maintenance))))?;
+    command_manager.add_command(Command::with_required_arguments("export_paper", "Export your private key encrypted with a passphrase as a printable QR code (PNG or SVG file)", vec![Arg::new("path", ArgType::String)], CommandHandler::Async(async_handler!(export_paper))))?;
+    command_manager.add_command(Command::new("export_private_key", "Show the private key of the account, anyone holding it can spend your funds", CommandHandler::Async(async_handler!(export_private_key))))?;
+    command_manager.add_command(Command::with_optional_arguments("display_seed", "Show your mnemonic, or the legacy seed of selected language", vec![Arg::new("language", ArgType::Number)], CommandHandler::Async(async_handler!(display_seed))))?;
     command_manager.add_command(Command::new("nonce", "Show current nonce", CommandHandler::Async(async_handler!(nonce))))?;
     command_manager.add_command(Command::new("set_nonce", "Set new nonce", CommandHandler::Async(async_handler!(set_nonce))))?;
 
@@ -381,6 +1054,15 @@ async fn setup_wallet_command_manager(wallet: Arc<Wallet>, command_manager: &Com
         ], CommandHandler::Async(async_handler!(start_rpc_server))))?;
 
         command_manager.add_command(Command::new("start_xswd", "Start the XSWD Server",  CommandHandler::Async(async_handler!(start_xswd))))?;
+        command_manager.add_command(Command::with_arguments("api_token", "Manage the API tokens of the RPC Server (create <name> <read_only|transfer|admin> [spend limit], revoke <name>, list)", vec![Arg::new("action", ArgType::String)], vec![
+            Arg::new("name", ArgType::String),
+            Arg::new("scope", ArgType::String),
+            Arg::new("limit", ArgType::String)
+        ], CommandHandler::Async(async_handler!(api_token))))?;
+        command_manager.add_command(Command::with_arguments("xswd", "Manage the applications connected to XSWD (list, revoke, disconnect)", vec![Arg::new("action", ArgType::String)], vec![
+            Arg::new("app_id", ArgType::String),
+            Arg::new("method", ArgType::String)
+        ], CommandHandler::Async(async_handler!(xswd))))?;
 
         // Stop API Server (RPC or XSWD)
         command_manager.add_command(Command::new("stop_api_server", "Stop the API (XSWD/RPC) Server", CommandHandler::Async(async_handler!(stop_api_server))))?;
@@ -393,6 +1075,21 @@ async fn setup_wallet_command_manager(wallet: Arc<Wallet>, command_manager: &Com
 }
 
 // Function passed as param to prompt to build the prompt message shown
+// Render the sync progress as a progress bar
+fn format_sync_progress(progress: &SyncProgress) -> String {
+    const BAR_SIZE: u64 = 20;
+    let ratio = if progress.target == 0 { 1f64 } else { (progress.current.min(progress.target) as f64) / progress.target as f64 };
+    let filled = (ratio * BAR_SIZE as f64) as u64;
+    let bar: String = (0..BAR_SIZE).map(|i| if i < filled { '#' } else { '-' }).collect();
+
+    let eta = match progress.eta {
+        Some(eta) => format!(" ETA {}m{:02}s", eta / 60, eta % 60),
+        None => String::new()
+    };
+
+    format!("[{}] {:.0}% {:.1} blocks/s{}", bar, ratio * 100f64, progress.blocks_per_second, eta)
+}
+
 async fn prompt_message_builder(_: &Prompt, command_manager: Option<&CommandManager>) -> Result<String, PromptError> {
     if let Some(manager) = command_manager {
         let context = manager.get_context().lock()?;
@@ -404,19 +1101,36 @@ async fn prompt_message_builder(_: &Prompt, command_manager: Option<&CommandMana
                 prompt::colorize_str(Color::Yellow, addr)
             };
     
+            // Wallet is online only if the sync task is running and the connection is alive
+            let (network_topoheight, sync_progress) = {
+                let network_handler = wallet.get_network_handler().await.lock().await;
+                match network_handler.as_ref() {
+                    Some(handler) if handler.is_running().await && handler.get_api().is_online() => (Some(handler.get_network_topoheight()), handler.get_sync_progress().await),
+                    _ => (None, None)
+                }
+            };
+
             let storage = wallet.get_storage().read().await;
+            let synced_topoheight = storage.get_synced_topoheight().unwrap_or(0);
             let topoheight_str = format!(
                 "{}: {}",
                 prompt::colorize_str(Color::Yellow, "TopoHeight"),
-                prompt::colorize_string(Color::Green, &format!("{}", storage.get_synced_topoheight().unwrap_or(0)))
+                match (network_topoheight, sync_progress) {
+                    (Some(_), Some(progress)) => prompt::colorize_string(Color::Yellow, &format_sync_progress(&progress)),
+                    (Some(network_topoheight), None) if network_topoheight > synced_topoheight => prompt::colorize_string(Color::Red, &format!("{}/{}", synced_topoheight, network_topoheight)),
+                    (Some(network_topoheight), None) => prompt::colorize_string(Color::Green, &format!("{}/{}", synced_topoheight, network_topoheight)),
+                    (None, _) => prompt::colorize_string(Color::Green, &format!("{}", synced_topoheight))
+                }
             );
             let balance = format!(
                 "{}: {}",
                 prompt::colorize_str(Color::Yellow, "Balance"),
                 prompt::colorize_string(Color::Green, &format_xelis(storage.get_plaintext_balance_for(&XELIS_ASSET).await.unwrap_or(0))),
             );
-            let status = if wallet.is_online().await {
+            let status = if network_topoheight.is_some() {
                 prompt::colorize_str(Color::Green, "Online")
+            } else if wallet.is_sync_paused() {
+                prompt::colorize_str(Color::Yellow, "Paused")
             } else {
                 prompt::colorize_str(Color::Red, "Offline")
             };
@@ -471,12 +1185,20 @@ async fn open_wallet(manager: &CommandManager, _: ArgumentManager) -> Result<(),
     let password = prompt.read_input("Password: ".into(), true)
         .await.context("Error while reading wallet password")?;
 
-    let wallet = {
-        let context = manager.get_context().lock()?;
-        let network = context.get::<Network>()?;
-        let precomputed_tables = Wallet::read_or_generate_precomputed_tables(None, LogProgressTableGenerationReportFunction)?;
-        Wallet::open(dir, password, *network, precomputed_tables)?
-    };
+    let network = *manager.get_context().lock()?.get::<Network>()?;
+    let backend = open_backend(&dir).context("Error while opening wallet storage")?;
+    let mut builder = Wallet::builder()
+        .backend(Arc::clone(&backend))
+        .password(password)
+        .network(network);
+
+    if Storage::with_backend(backend)?.is_keyfile_required()? {
+        let path = prompt.read_input("Keyfile: ".into(), false)
+            .await.context("Error while reading keyfile path")?;
+        builder = builder.keyfile(read_keyfile(&path)?);
+    }
+
+    let wallet = builder.open().await?;
 
     manager.message("Wallet sucessfully opened");
     apply_config(&wallet, #[cfg(feature = "api_server")] prompt).await;
@@ -486,6 +1208,11 @@ async fn open_wallet(manager: &CommandManager, _: ArgumentManager) -> Result<(),
     Ok(())
 }
 
+// Read the content of a keyfile
+fn read_keyfile(path: &str) -> Result<Vec<u8>> {
+    fs::read(path).with_context(|| format!("Error while reading keyfile {}", path))
+}
+
 // Create a wallet by requesting name, password
 async fn create_wallet(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
     let prompt = manager.get_prompt();
@@ -516,469 +1243,3520 @@ async fn create_wallet(manager: &CommandManager, _: ArgumentManager) -> Result<(
         return Ok(())
     }
 
-    let wallet = {
-        let context = manager.get_context().lock()?;
-        let network = context.get::<Network>()?;
-        let precomputed_tables = Wallet::read_or_generate_precomputed_tables(None, LogProgressTableGenerationReportFunction)?;
-        Wallet::create(dir, password, None, *network, precomputed_tables)?
-    };
+    let network = *manager.get_context().lock()?.get::<Network>()?;
+    let wallet = Wallet::builder()
+        .path(dir)
+        .password(password)
+        .network(network)
+        .create()
+        .await?;
  
     manager.message("Wallet sucessfully created");
     apply_config(&wallet, #[cfg(feature = "api_server")] prompt).await;
 
-    // Display the seed in prompt
-    {
-        let seed = wallet.get_seed(0)?; // TODO language index
-        prompt.read_input(format!("Seed: {}\r\nPress ENTER to continue", seed), false)
-            .await.context("Error while displaying seed")?;
-    }
+    // Display the seed in prompt and wait until the user confirmed it
+    confirm_seed(manager, &wallet).await?;
 
     setup_wallet_command_manager(wallet, manager).await?;
 
     Ok(())
 }
 
-// Recover a wallet by requesting its seed, name and password
-async fn recover_wallet(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+// Number of words asked to confirm the seed at wallet creation
+const SEED_CONFIRMATION_WORDS: usize = 3;
+
+// Display the seed of a newly created wallet and ask the user to type back some of its words
+// This is done until the user gives the right words, so he can't miss to save it
+async fn confirm_seed(manager: &CommandManager, wallet: &Wallet) -> Result<(), CommandError> {
     let prompt = manager.get_prompt();
+    let seed = match wallet.get_mnemonic().await? {
+        Some(mnemonic) => mnemonic,
+        None => wallet.get_seed(0)? // TODO language index
+    };
+    let words: Vec<&str> = seed.split_whitespace().collect();
 
-    let seed = prompt.read_input("Seed: ".into(), false)
-        .await.context("Error while reading seed")?;
+    loop {
+        prompt.read_input(
+            prompt::colorize_string(Color::Green, &format!("Seed: {}\r\nWrite it down and keep it safe, press ENTER to continue", seed)),
+            false
+        ).await.context("Error while displaying seed")?;
 
-    let words_count = seed.split_whitespace().count();
-    if words_count != 25 && words_count != 24 {
-        manager.error("Seed must be 24 or 25 (checksum) words long");
-        return Ok(())
-    }
+        let mut indexes = rand::seq::index::sample(&mut rand::thread_rng(), words.len(), SEED_CONFIRMATION_WORDS).into_vec();
+        indexes.sort();
 
-    let name = prompt.read_input("Wallet name: ".into(), false)
-        .await.context("Error while reading wallet name")?;
+        let mut valid = true;
+        for index in indexes {
+            let word = prompt.read_input(format!("Word #{}: ", index + 1), false)
+                .await.context("Error while reading seed word")?;
 
-    if name.is_empty() {
-        manager.error("Wallet name cannot be empty");
-        return Ok(())
-    }
+            if word.trim() != words[index] {
+                valid = false;
+                break;
+            }
+        }
 
-    let dir = format!("{}{}", DIR_PATH, name);
-    // check if it doesn't exists yet
-    if Path::new(&dir).is_dir() {
-        manager.message("Wallet already exist with this name!");
-        return Ok(())
+        if valid {
+            manager.message("Seed has been confirmed");
+            return Ok(())
+        }
+
+        manager.error("Invalid word, please save your seed again");
     }
+}
 
-    // ask and verify password
-    let password = prompt.read_input("Password: ".into(), true)
-        .await.context("Error while reading password")?;
-    let confirm_password = prompt.read_input("Confirm Password: ".into(), true)
-        .await.context("Error while reading password")?;
+// Guided creation of a new wallet at the requested path
+// It asks the network, the password with its confirmation and the daemon to use
+// Then the seed must be confirmed before the wallet can be used
+async fn creation_wizard(manager: &CommandManager, path: String, seed: Option<String>, default_network: Network, builder: WalletBuilder) -> Result<Arc<Wallet>, CommandError> {
+    let prompt = manager.get_prompt();
+    manager.message(format!("No wallet found at '{}', starting the creation wizard", path));
 
-    if password != confirm_password {
-        manager.message("Confirm password doesn't match password");        
-        return Ok(())
-    }
+    let network = loop {
+        let value = prompt.read_input(format!("Network (mainnet, testnet, dev) [{}]: ", default_network), false)
+            .await.context("Error while reading network")?;
 
+        if value.is_empty() {
+            break default_network
+        }
 
-    let wallet = {
-        let context = manager.get_context().lock()?;
-        let network = context.get::<Network>()?;
-        let precomputed_tables = Wallet::read_or_generate_precomputed_tables(None, LogProgressTableGenerationReportFunction)?;
-        Wallet::create(dir, password, Some(seed), *network, precomputed_tables)?
+        match Network::from_str(&value.to_lowercase()) {
+            Ok(network) => break network,
+            Err(e) => manager.error(e)
+        };
     };
 
-    manager.message("Wallet sucessfully recovered");
-    apply_config(&wallet, #[cfg(feature = "api_server")] prompt).await;
+    let password = loop {
+        let password = prompt.read_input("Password: ".into(), true)
+            .await.context("Error while reading password")?;
+        let confirm_password = prompt.read_input("Confirm Password: ".into(), true)
+            .await.context("Error while reading password")?;
+
+        if password == confirm_password {
+            break password
+        }
+
+        manager.error("Confirm password doesn't match password");
+    };
+
+    let daemon_address = prompt.read_input(format!("Daemon address [{}]: ", DEFAULT_DAEMON_ADDRESS), false)
+        .await.context("Error while reading daemon address")?;
+
+    // network may be different than the one provided in config
+    manager.store_in_context(network)?;
+
+    let recovered = seed.is_some();
+    let mut builder = builder.path(path).password(password).network(network);
+    if let Some(seed) = seed {
+        builder = builder.seed(seed);
+    }
+
+    let wallet = builder.create().await?;
+    if !daemon_address.is_empty() {
+        let mut storage = wallet.get_storage().write().await;
+        storage.set_daemon_address(&daemon_address)?;
+    }
+
+    if recovered {
+        manager.message("Wallet recovered from seed, it will be fully rescanned from genesis once online");
+    } else {
+        confirm_seed(manager, &wallet).await?;
+    }
+
+    manager.message("Wallet sucessfully created");
+    Ok(wallet)
+}
+
+// Check that the seed has the words count of a BIP39 mnemonic or of a legacy seed
+fn is_valid_seed_words_count(seed: &str) -> bool {
+    let words_count = seed.split_whitespace().count();
+    words_count == 24 || words_count == 25
+}
+
+// Recover a wallet by requesting its seed, name and password
+async fn recover_wallet(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+    let prompt = manager.get_prompt();
+
+    let seed = prompt.read_input("Seed: ".into(), false)
+        .await.context("Error while reading seed")?;
+
+    if !is_valid_seed_words_count(&seed) {
+        manager.error("Seed must be a 24 words mnemonic or a 25 words legacy seed");
+        return Ok(())
+    }
+
+    let name = prompt.read_input("Wallet name: ".into(), false)
+        .await.context("Error while reading wallet name")?;
+
+    if name.is_empty() {
+        manager.error("Wallet name cannot be empty");
+        return Ok(())
+    }
+
+    let dir = format!("{}{}", DIR_PATH, name);
+    // check if it doesn't exists yet
+    if Path::new(&dir).is_dir() {
+        manager.message("Wallet already exist with this name!");
+        return Ok(())
+    }
+
+    // ask and verify password
+    let password = prompt.read_input("Password: ".into(), true)
+        .await.context("Error while reading password")?;
+    let confirm_password = prompt.read_input("Confirm Password: ".into(), true)
+        .await.context("Error while reading password")?;
+
+    if password != confirm_password {
+        manager.message("Confirm password doesn't match password");        
+        return Ok(())
+    }
+
+
+    let network = *manager.get_context().lock()?.get::<Network>()?;
+    let wallet = Wallet::builder()
+        .path(dir)
+        .password(password)
+        .network(network)
+        .seed(seed)
+        .create()
+        .await?;
+
+    manager.message("Wallet sucessfully recovered, it will be fully rescanned from genesis once online");
+    apply_config(&wallet, #[cfg(feature = "api_server")] prompt).await;
+
+    setup_wallet_command_manager(wallet, manager).await?;
+
+    Ok(())
+}
+
+// Restore a wallet from a backup created with the backup command
+async fn restore_wallet(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+    let prompt = manager.get_prompt();
+
+    let path = prompt.read_input("Backup file: ".into(), false)
+        .await.context("Error while reading backup path")?;
+    let content = fs::read(&path).with_context(|| format!("Error while reading backup file {}", path))?;
+
+    let backup_password = prompt.read_input("Backup password: ".into(), true)
+        .await.context("Error while reading backup password")?;
+    let backup = WalletBackup::from_encrypted_bytes(&content, backup_password)?;
+
+    {
+        let context = manager.get_context().lock()?;
+        let network = context.get::<Network>()?;
+        if backup.network != *network {
+            manager.error(format!("Backup is for {} network while {} is selected", backup.network, network));
+            return Ok(())
+        }
+    }
+
+    let name = prompt.read_input("Wallet name: ".into(), false)
+        .await.context("Error while reading wallet name")?;
+
+    if name.is_empty() {
+        manager.error("Wallet name cannot be empty");
+        return Ok(())
+    }
+
+    let dir = format!("{}{}", DIR_PATH, name);
+    // check if it doesn't exists yet
+    if Path::new(&dir).is_dir() {
+        manager.message("Wallet already exist with this name!");
+        return Ok(())
+    }
+
+    // ask and verify password
+    let password = prompt.read_input("Password: ".into(), true)
+        .await.context("Error while reading password")?;
+    let confirm_password = prompt.read_input("Confirm Password: ".into(), true)
+        .await.context("Error while reading password")?;
+
+    if password != confirm_password {
+        manager.message("Confirm password doesn't match password");
+        return Ok(())
+    }
+
+    let precomputed_tables = Wallet::read_or_generate_precomputed_tables(None, LogProgressTableGenerationReportFunction)?;
+    let wallet = Wallet::restore_backup(dir, password, backup, precomputed_tables)?;
+
+    manager.message("Wallet sucessfully restored, it will be fully rescanned from genesis once online");
+    apply_config(&wallet, #[cfg(feature = "api_server")] prompt).await;
+
+    setup_wallet_command_manager(wallet, manager).await?;
+
+    Ok(())
+}
+
+// Read a private key from its hex representation or from a key file
+// A key file contains either the hex key or a JSON object with a "private_key" field
+fn parse_private_key(value: &str) -> Result<PrivateKey, CommandError> {
+    let value = value.trim();
+    let hex = if Path::new(value).is_file() {
+        let content = fs::read_to_string(value).with_context(|| format!("Error while reading key file {}", value))?;
+        match serde_json::from_str::<serde_json::Value>(&content) {
+            Ok(json) => json.get("private_key")
+                .and_then(|v| v.as_str())
+                .map(str::to_owned)
+                .ok_or_else(|| CommandError::InvalidArgument("No private_key field found in key file".to_owned()))?,
+            Err(_) => content.lines().next().unwrap_or_default().trim().to_owned()
+        }
+    } else {
+        value.to_owned()
+    };
+
+    let bytes = hex::decode(&hex).map_err(|_| CommandError::InvalidArgument("Private key is not a valid hex string".to_owned()))?;
+    if bytes.len() != 32 || bytes.iter().all(|b| *b == 0) {
+        return Err(CommandError::InvalidArgument("Private key must be 32 non-zero bytes".to_owned()));
+    }
+
+    PrivateKey::from_bytes(&bytes).map_err(|_| CommandError::InvalidArgument("Invalid private key".to_owned()))
+}
+
+// Create a new wallet around an existing private key
+async fn import_key(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+    let prompt = manager.get_prompt();
+
+    let value = prompt.read_input("Private key (hex) or key file path: ".into(), true)
+        .await.context("Error while reading private key")?;
+    let private_key = parse_private_key(&value)?;
+
+    let name = prompt.read_input("Wallet name: ".into(), false)
+        .await.context("Error while reading wallet name")?;
+
+    if name.is_empty() {
+        manager.error("Wallet name cannot be empty");
+        return Ok(())
+    }
+
+    let dir = format!("{}{}", DIR_PATH, name);
+    // check if it doesn't exists yet
+    if Path::new(&dir).is_dir() {
+        manager.message("Wallet already exist with this name!");
+        return Ok(())
+    }
+
+    // ask and verify password
+    let password = prompt.read_input("Password: ".into(), true)
+        .await.context("Error while reading password")?;
+    let confirm_password = prompt.read_input("Confirm Password: ".into(), true)
+        .await.context("Error while reading password")?;
+
+    if password != confirm_password {
+        manager.message("Confirm password doesn't match password");
+        return Ok(())
+    }
+
+    let network = *manager.get_context().lock()?.get::<Network>()?;
+    let wallet = Wallet::builder()
+        .path(dir)
+        .password(password)
+        .network(network)
+        .private_key(private_key)
+        .create()
+        .await?;
+
+    manager.message(format!("Wallet sucessfully imported with address {}, it will be fully rescanned from genesis once online", wallet.get_address()));
+    apply_config(&wallet, #[cfg(feature = "api_server")] prompt).await;
+
+    setup_wallet_command_manager(wallet, manager).await?;
+
+    Ok(())
+}
+
+// Create a new wallet from a paper wallet exported by 'export_paper'
+// Images are not decoded: the text encoded in the QR code (see PAPER_WALLET_PREFIX) or its exported text file is expected
+async fn import_paper(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let prompt = manager.get_prompt();
+
+    let value = if arguments.has_argument("paper") {
+        arguments.get_value("paper")?.to_string_value()?
+    } else {
+        prompt.read_input(format!("Paper wallet text (starting with '{}') or path to its text file: ", PAPER_WALLET_PREFIX), false)
+            .await.context("Error while reading paper wallet")?
+    };
+
+    let value = value.trim();
+    let value = if value.starts_with(PAPER_WALLET_PREFIX) {
+        value.to_owned()
+    } else if Path::new(value).is_file() {
+        let lower = value.to_lowercase();
+        if lower.ends_with(".png") || lower.ends_with(".svg") {
+            manager.error(format!("QR code images can't be decoded, scan it and enter its text (starting with '{}') or use its .txt file", PAPER_WALLET_PREFIX));
+            return Ok(())
+        }
+        fs::read_to_string(value).with_context(|| format!("Error while reading paper wallet file {}", value))?
+    } else {
+        manager.error(format!("Expected the paper wallet text starting with '{}' or the path to its text file", PAPER_WALLET_PREFIX));
+        return Ok(())
+    };
+
+    let passphrase = prompt.read_input("Paper wallet passphrase: ".into(), true)
+        .await.context("Error while reading passphrase")?;
+    let paper = PaperWallet::from_encrypted_string(&value, passphrase)?;
+
+    let network = *manager.get_context().lock()?.get::<Network>()?;
+    if paper.network != network {
+        manager.error(format!("This paper wallet is for {}, but the wallet is running on {}", paper.network, network));
+        return Ok(())
+    }
+
+    manager.message(format!("Paper wallet address: {}", paper.get_address()));
+
+    let name = prompt.read_input("Wallet name: ".into(), false)
+        .await.context("Error while reading wallet name")?;
+
+    if name.is_empty() {
+        manager.error("Wallet name cannot be empty");
+        return Ok(())
+    }
+
+    let dir = format!("{}{}", DIR_PATH, name);
+    // check if it doesn't exists yet
+    if Path::new(&dir).is_dir() {
+        manager.message("Wallet already exist with this name!");
+        return Ok(())
+    }
+
+    // ask and verify password
+    let password = prompt.read_input("Password: ".into(), true)
+        .await.context("Error while reading password")?;
+    let confirm_password = prompt.read_input("Confirm Password: ".into(), true)
+        .await.context("Error while reading password")?;
+
+    if password != confirm_password {
+        manager.message("Confirm password doesn't match password");
+        return Ok(())
+    }
+
+    let wallet = Wallet::builder()
+        .path(dir)
+        .password(password)
+        .network(network)
+        .private_key(paper.private_key)
+        .create()
+        .await?;
+
+    manager.message(format!("Wallet sucessfully imported with address {}, it will be fully rescanned from genesis once online", wallet.get_address()));
+    apply_config(&wallet, #[cfg(feature = "api_server")] prompt).await;
+
+    setup_wallet_command_manager(wallet, manager).await?;
+
+    Ok(())
+}
+
+// Create a new view-only wallet around a private key exported by 'export_private_key'
+// The wallet refuses to sign, but the key itself can still spend the funds
+async fn import_private_key(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+    let prompt = manager.get_prompt();
+
+    let value = prompt.read_input("Private key (hex): ".into(), true)
+        .await.context("Error while reading private key")?;
+    let private_key = parse_private_key(&value)?;
+
+    let name = prompt.read_input("Wallet name: ".into(), false)
+        .await.context("Error while reading wallet name")?;
+
+    if name.is_empty() {
+        manager.error("Wallet name cannot be empty");
+        return Ok(())
+    }
+
+    let dir = format!("{}{}", DIR_PATH, name);
+    // check if it doesn't exists yet
+    if Path::new(&dir).is_dir() {
+        manager.message("Wallet already exist with this name!");
+        return Ok(())
+    }
+
+    // ask and verify password
+    let password = prompt.read_input("Password: ".into(), true)
+        .await.context("Error while reading password")?;
+    let confirm_password = prompt.read_input("Confirm Password: ".into(), true)
+        .await.context("Error while reading password")?;
+
+    if password != confirm_password {
+        manager.message("Confirm password doesn't match password");
+        return Ok(())
+    }
+
+    let network = *manager.get_context().lock()?.get::<Network>()?;
+    let wallet = Wallet::builder()
+        .path(dir)
+        .password(password)
+        .network(network)
+        .view_only(private_key)
+        .create()
+        .await?;
+
+    manager.message(format!("View-only wallet sucessfully created with address {}, it will be fully rescanned from genesis once online", wallet.get_address()));
+    apply_config(&wallet, #[cfg(feature = "api_server")] prompt).await;
 
     setup_wallet_command_manager(wallet, manager).await?;
 
     Ok(())
 }
 
-// Change wallet password
-async fn change_password(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+// Export the private key as an encrypted QR code to print it for a cold storage
+async fn export_paper(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let path = arguments.get_value("path")?.to_string_value()?;
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    let prompt = manager.get_prompt();
+
+    let password = prompt.read_input("Password: ".into(), true)
+        .await.context("Error while reading password")?;
+    wallet.is_valid_password(password).await?;
+
+    // The passphrase is different from the password so the paper doesn't depend on this wallet
+    let passphrase = prompt.read_input("Paper wallet passphrase: ".into(), true)
+        .await.context("Error while reading passphrase")?;
+    let confirm_passphrase = prompt.read_input("Confirm passphrase: ".into(), true)
+        .await.context("Error while reading passphrase")?;
+
+    if passphrase.is_empty() || passphrase != confirm_passphrase {
+        manager.error("Passphrase is empty or doesn't match its confirmation");
+        return Ok(())
+    }
+
+    let paper = wallet.export_paper_wallet().context("Error while exporting paper wallet")?;
+    let address = paper.get_address().to_string();
+    let encrypted = paper.to_encrypted_string(passphrase).context("Error while encrypting paper wallet")?;
+    let code = QrCode::new(encrypted.as_bytes()).context("Error while generating QR code")?;
+
+    if path.to_lowercase().ends_with(".svg") {
+        let svg = render_paper_svg(&code, &[address.clone(), "Import it using 'import_paper' with the text of this QR code and its passphrase".to_owned()]);
+        fs::write(&path, svg).with_context(|| format!("Error while writing paper wallet to {}", path))?;
+    } else {
+        code.render::<Luma<u8>>()
+            .min_dimensions(512, 512)
+            .build()
+            .save(&path)
+            .context("Error while saving paper wallet to PNG")?;
+    }
+
+    // The text is also saved as it is what 'import_paper' expects, images are not decoded
+    let text_path = format!("{}.txt", path);
+    fs::write(&text_path, &encrypted).with_context(|| format!("Error while writing paper wallet text to {}", text_path))?;
+
+    manager.message(format!("Paper wallet of {} has been exported to {} and its text to {}", address, path, text_path));
+    manager.message("Restore it with the 'import_paper' command using the text scanned from the QR code or the text file, the passphrase is required");
+    manager.warn("Anyone able to guess the passphrase can spend your funds, print it offline and delete the file afterwards");
+    Ok(())
+}
+
+// Render a QR code as a printable SVG page with some lines of text under it
+fn render_paper_svg(code: &QrCode, lines: &[String]) -> String {
+    // Size of a module in pixels and quiet zone around the code in modules
+    const MODULE_SIZE: usize = 8;
+    const QUIET_ZONE: usize = 4;
+    const LINE_HEIGHT: usize = 24;
+
+    let modules = code.width();
+    let size = (modules + 2 * QUIET_ZONE) * MODULE_SIZE;
+    let height = size + (lines.len() + 1) * LINE_HEIGHT;
+
+    let mut svg = format!("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{size}\" height=\"{height}\" viewBox=\"0 0 {size} {height}\" shape-rendering=\"crispEdges\">");
+    svg.push_str("<rect width=\"100%\" height=\"100%\" fill=\"#fff\"/>");
+    for (i, color) in code.to_colors().into_iter().enumerate() {
+        if color == QrColor::Dark {
+            let x = (i % modules + QUIET_ZONE) * MODULE_SIZE;
+            let y = (i / modules + QUIET_ZONE) * MODULE_SIZE;
+            svg.push_str(&format!("<rect x=\"{x}\" y=\"{y}\" width=\"{MODULE_SIZE}\" height=\"{MODULE_SIZE}\"/>"));
+        }
+    }
+
+    for (i, line) in lines.iter().enumerate() {
+        let y = size + (i + 1) * LINE_HEIGHT;
+        svg.push_str(&format!("<text x=\"{}\" y=\"{}\" font-family=\"monospace\" font-size=\"12\" text-anchor=\"middle\">{}</text>", size / 2, y, line));
+    }
+    svg.push_str("</svg>");
+
+    svg
+}
+
+// Show the private key of the wallet after confirming the password
+// It is always confirmed interactively, even when the confirmations are skipped
+async fn export_private_key(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+    let prompt = manager.get_prompt();
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+
+    let password = prompt.read_input("Password: ".into(), true)
+        .await.context("Error while reading password")?;
+    wallet.is_valid_password(password).await?;
+
+    manager.warn("This is the private key of your account, anyone holding it can spend your funds");
+    manager.warn("A wallet created with 'import_private_key' only refuses to sign, it doesn't make the key safe to share");
+    if !prompt.ask_interactive_confirmation().await.context("Error while confirming action")? {
+        return Ok(())
+    }
+
+    let private_key = wallet.export_private_key().context("Error while exporting private key")?;
+    manager.message(format!("Private key: {}", private_key));
+    Ok(())
+}
+
+// Lock the wallet
+async fn lock(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+
+    if wallet.is_locked() {
+        manager.message("Wallet is already locked");
+        return Ok(())
+    }
+
+    wallet.lock();
+    manager.message("Wallet has been locked");
+    Ok(())
+}
+
+// Unlock the wallet by asking its password
+async fn unlock(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+
+    if !wallet.is_locked() {
+        manager.message("Wallet is not locked");
+        return Ok(())
+    }
+
+    let password = manager.get_prompt().read_input(prompt::colorize_str(Color::BrightRed, "Password: "), true)
+        .await.context("Error while reading password")?;
+
+    wallet.unlock(password).await?;
+    manager.message("Wallet has been unlocked");
+    Ok(())
+}
+
+// Change wallet password
+async fn change_password(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+
+    let prompt = manager.get_prompt();
+
+    let old_password = prompt.read_input(prompt::colorize_str(Color::BrightRed, "Current Password: "), true)
+        .await
+        .context("Error while asking old password")?;
+
+    let new_password = prompt.read_input(prompt::colorize_str(Color::BrightRed, "New Password: "), true)
+        .await
+        .context("Error while asking new password")?;
+
+    manager.message("Changing password...");
+    wallet.set_password(old_password, new_password).await?;
+    manager.message("Your password has been changed!");
+    Ok(())
+}
+
+// Add or remove the keyfile mixed into the password to open the wallet
+async fn keyfile(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let action = arguments.get_value("action")?.to_string_value()?;
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    let prompt = manager.get_prompt();
+
+    let keyfile = match action.as_str() {
+        "add" => {
+            if !arguments.has_argument("path") {
+                return Err(CommandError::InvalidArgument("Expected add <path>".to_owned()))
+            }
+
+            let path = arguments.get_value("path")?.to_string_value()?;
+            // A random keyfile is generated if the file doesn't exist
+            if !Path::new(&path).exists() {
+                let mut content = [0u8; KEYFILE_SIZE];
+                OsRng.fill_bytes(&mut content);
+                fs::write(&path, content).with_context(|| format!("Error while writing keyfile {}", path))?;
+                manager.message(format!("A new keyfile has been generated at {}", path));
+            }
+
+            manager.warn("Keep a copy of this file: your wallet can't be opened without it, even with your password");
+            Some(hash_keyfile(&read_keyfile(&path)?))
+        },
+        "remove" => {
+            if wallet.get_keyfile_hash().await.is_none() {
+                manager.error("No keyfile is set on this wallet");
+                return Ok(())
+            }
+
+            manager.message("Only your password will be required to open the wallet");
+            None
+        },
+        _ => return Err(CommandError::InvalidArgument(format!("Unknown action '{}', expected add or remove", action)))
+    };
+
+    if !prompt.ask_confirmation().await.context("Error while confirming action")? {
+        return Ok(())
+    }
+
+    let password = prompt.read_input(prompt::colorize_str(Color::BrightRed, "Password: "), true)
+        .await
+        .context("Error while asking password")?;
+
+    manager.message("Re-encrypting wallet...");
+    wallet.set_keyfile(password, keyfile).await?;
+    manager.message("Keyfile has been updated!");
+    Ok(())
+}
+
+// Show the password hashing parameters or re-hash the password with new ones
+async fn password_params(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let action = arguments.get_value("action")?.to_string_value()?;
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    let prompt = manager.get_prompt();
+
+    let params = match action.as_str() {
+        "show" => {
+            let params = wallet.get_password_params().await.context("Error while retrieving password params")?;
+            manager.message(format!("Password params: {}", params));
+            return Ok(())
+        },
+        "calibrate" => {
+            let target = if arguments.has_argument("value") {
+                arguments.get_value("value")?.to_number()?
+            } else {
+                DEFAULT_PASSWORD_CALIBRATION_TARGET
+            };
+
+            manager.message(format!("Calibrating for an unlock time of {} ms, this may take a few seconds...", target));
+            let params = tokio::task::spawn_blocking(move || PasswordParams::calibrate(Duration::from_millis(target), 1))
+                .await
+                .context("Error while calibrating")?
+                .context("Error while calibrating")?;
+            Some(params)
+        },
+        "set" => {
+            if !arguments.has_argument("value") || !arguments.has_argument("iterations") || !arguments.has_argument("parallelism") {
+                return Err(CommandError::InvalidArgument("Expected set <memory KiB> <iterations> <parallelism>".to_owned()))
+            }
+
+            let memory = arguments.get_value("value")?.to_number()?;
+            let iterations = arguments.get_value("iterations")?.to_number()?;
+            let parallelism = arguments.get_value("parallelism")?.to_number()?;
+            let params = PasswordParams::new(
+                memory.try_into().context("Invalid memory")?,
+                iterations.try_into().context("Invalid iterations")?,
+                parallelism.try_into().context("Invalid parallelism")?
+            );
+            params.validate().context("Invalid password params")?;
+            Some(params)
+        },
+        // Back to the parameters of the latest algorithm version
+        "reset" => None,
+        _ => return Err(CommandError::InvalidArgument(format!("Unknown action '{}', expected show, calibrate, set or reset", action)))
+    };
+
+    match params.as_ref() {
+        Some(params) => manager.message(format!("New password params: {}", params)),
+        None => manager.message("Password params will be reset to the default ones")
+    };
+
+    if !prompt.ask_confirmation().await.context("Error while confirming action")? {
+        return Ok(())
+    }
+
+    let password = prompt.read_input(prompt::colorize_str(Color::BrightRed, "Password: "), true)
+        .await
+        .context("Error while asking password")?;
+
+    manager.message("Re-encrypting wallet...");
+    wallet.set_password_with_params(password.clone(), password, params).await?;
+    manager.message("Password params have been updated!");
+    Ok(())
+}
+
+// Parse an address or resolve it from the address book if it's a contact name
+async fn parse_address_or_contact(wallet: &Wallet, value: &str) -> Result<Address, CommandError> {
+    if let Ok(address) = Address::from_string(&value.to_owned()) {
+        return Ok(address)
+    }
+
+    let storage = wallet.get_storage().read().await;
+    if storage.has_contact(value)? {
+        return Ok(storage.get_contact(value)?)
+    }
+
+    Err(CommandError::InvalidArgument(format!("'{}' is neither a valid address nor a known contact", value)))
+}
+
+// Create a new transfer to a specified address
+async fn transfer(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let prompt = manager.get_prompt();
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    let dry_run = arguments.has_argument("dry-run");
+
+    let priority = if arguments.has_argument("priority") {
+        let value = arguments.get_value("priority")?.to_string_value()?;
+        Some(FeePriority::from_str(&value).ok_or_else(|| CommandError::InvalidArgument(format!("Unknown priority '{}', expected low, normal or high", value)))?)
+    } else {
+        None
+    };
+
+    let nonce = if arguments.has_argument("nonce") {
+        let nonce = arguments.get_value("nonce")?.to_number()?;
+        let current = wallet.get_nonce().await;
+        if nonce > current {
+            return Err(CommandError::InvalidArgument(format!("Nonce cannot be higher than the next nonce {}", current)));
+        }
+
+        if nonce < current {
+            manager.warn(format!("Nonce {} is already used, this transaction will replace the pending one using it", nonce));
+        }
+        Some(nonce)
+    } else {
+        None
+    };
+
+    // read address, a payment URI can also be used to fill the request
+    let str_address = if arguments.has_argument("address") {
+        arguments.get_value("address")?.to_string_value()?
+    } else {
+        prompt.read_input(
+            prompt::colorize_str(Color::Green, "Address, contact name or payment URI: "),
+            false
+        ).await.context("Error while reading address")?
+    };
+
+    let uri = if PaymentURI::is_uri(&str_address) {
+        let uri = PaymentURI::from_str(&str_address).context("Invalid payment URI")?;
+        if let Some(message) = uri.get_message() {
+            manager.message(format!("Payment request message: {}", message));
+        }
+        Some(uri)
+    } else {
+        None
+    };
+
+    let address = match uri.as_ref() {
+        Some(uri) => uri.get_address().clone(),
+        None => parse_address_or_contact(wallet, &str_address).await?
+    };
+
+    let asset = if arguments.has_argument("asset") {
+        Some(arguments.get_value("asset")?.to_hash()?)
+    } else if let Some(asset) = uri.as_ref().and_then(|uri| uri.get_asset()) {
+        Some(asset.clone())
+    } else {
+        prompt.read_hash(
+            prompt::colorize_str(Color::Green, "Asset (default XELIS): ")
+        ).await.ok()
+    };
+
+    let asset = asset.unwrap_or(XELIS_ASSET);
+
+    let (max_balance, decimals) = {
+        let storage = wallet.get_storage().read().await;
+        let balance = storage.get_plaintext_balance_for(&asset).await.unwrap_or(0);
+        let decimals = storage.get_asset_decimals(&asset).unwrap_or(COIN_DECIMALS);
+        (balance, decimals)
+    };
+
+    let requested_amount = if arguments.has_argument("amount") {
+        let value = arguments.get_value("amount")?.to_string_value()?;
+        Some(from_coin(&value, decimals).ok_or_else(|| CommandError::InvalidArgument(format!("Invalid amount '{}'", value)))?)
+    } else {
+        match uri.as_ref() {
+            Some(uri) => uri.get_atomic_amount(decimals).context("Invalid amount in payment URI")?,
+            None => None
+        }
+    };
+
+    let amount = match requested_amount {
+        Some(amount) => amount,
+        None => {
+            // read amount
+            let float_amount: f64 = prompt.read(
+                prompt::colorize_string(Color::Green, &format!("Amount (max: {}): ", format_coin(max_balance, decimals)))
+            ).await.context("Error while reading amount")?;
+
+            (float_amount * 10u32.pow(decimals as u32) as f64) as u64
+        }
+    };
+    manager.message(format!("Sending {} of {} to {}", format_coin(amount, decimals), asset, address.to_string()));
+
+    if !dry_run && !prompt.ask_confirmation().await.context("Error while confirming action")? {
+        manager.message("Transaction has been aborted");
+        return Ok(())
+    }
+
+    let transfer = TransferBuilder {
+        destination: address,
+        amount,
+        asset,
+        extra_data: None
+    };
+
+    let fee = match priority {
+        Some(priority) => {
+            let fee = wallet.get_fee_for_priority(priority).await.context("Error while computing fees for priority")?;
+            if let FeeBuilder::Multiplier(multiplier) = &fee {
+                manager.message(format!("Using {} priority: fees multiplied by {:.2}", priority.as_str(), multiplier));
+            }
+            fee
+        },
+        None => FeeBuilder::default()
+    };
+
+    if dry_run {
+        return simulate_tx(wallet, manager, TransactionTypeBuilder::Transfers(vec![transfer]), fee, nonce).await
+    }
+
+    broadcast_tx(wallet, manager, TransactionTypeBuilder::Transfers(vec![transfer]), fee, nonce).await
+}
+
+// Send an asset to several addresses using only one transaction
+// Each transfer is in the format address:amount
+async fn transfer_multi(manager: &CommandManager, mut args: ArgumentManager) -> Result<(), CommandError> {
+    let prompt = manager.get_prompt();
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+
+    let asset = if args.has_argument("asset") {
+        args.get_value("asset")?.to_hash()?
+    } else {
+        XELIS_ASSET
+    };
+
+    let (max_balance, decimals) = {
+        let storage = wallet.get_storage().read().await;
+        let balance = storage.get_plaintext_balance_for(&asset).await.unwrap_or(0);
+        let decimals = storage.get_asset_decimals(&asset).unwrap_or(COIN_DECIMALS);
+        (balance, decimals)
+    };
+
+    let values = args.get_value("transfers")?.to_vec()?;
+    if values.len() > MAX_TRANSFER_COUNT {
+        return Err(CommandError::InvalidArgument(format!("Too many transfers, maximum is {}", MAX_TRANSFER_COUNT)));
+    }
+
+    let mut transfers = Vec::with_capacity(values.len());
+    let mut total: u64 = 0;
+    for value in values {
+        let value = value.to_string_value()?;
+        let (str_address, str_amount) = value.split_once(':')
+            .ok_or_else(|| CommandError::InvalidArgument(format!("Invalid transfer '{}', expected address:amount", value)))?;
+
+        let address = parse_address_or_contact(wallet, str_address).await?;
+        let amount = from_coin(str_amount, decimals)
+            .ok_or_else(|| CommandError::InvalidArgument(format!("Invalid amount '{}'", str_amount)))?;
+
+        if amount == 0 {
+            return Err(CommandError::InvalidArgument(format!("Amount for {} cannot be zero", str_address)));
+        }
+
+        total = total.checked_add(amount)
+            .ok_or_else(|| CommandError::InvalidArgument("Total amount overflow".to_owned()))?;
+
+        manager.message(format!("Sending {} of {} to {}", format_coin(amount, decimals), asset, address));
+        transfers.push(TransferBuilder {
+            destination: address,
+            amount,
+            asset: asset.clone(),
+            extra_data: None
+        });
+    }
+
+    if total > max_balance {
+        return Err(CommandError::InvalidArgument(format!("Total amount {} is greater than your balance {}", format_coin(total, decimals), format_coin(max_balance, decimals))));
+    }
+
+    manager.message(format!("Total: {} of {} in {} transfers", format_coin(total, decimals), asset, transfers.len()));
+    if !prompt.ask_confirmation().await.context("Error while confirming action")? {
+        manager.message("Transaction has been aborted");
+        return Ok(())
+    }
+
+    broadcast_tx(wallet, manager, TransactionTypeBuilder::Transfers(transfers), FeeBuilder::default(), None).await
+}
+
+// Send different assets to one or more addresses using only one transaction
+// Each transfer is in the format amount:asset and is sent to the address before it
+async fn transfer_assets(manager: &CommandManager, mut args: ArgumentManager) -> Result<(), CommandError> {
+    let prompt = manager.get_prompt();
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+
+    let str_address = args.get_value("address")?.to_string_value()?;
+    let mut address = parse_address_or_contact(wallet, &str_address).await?;
+    let mut has_transfer = false;
+
+    // Resolve the recipients before locking the storage
+    let mut requested = Vec::new();
+    for value in args.get_value("transfers")?.to_vec()? {
+        let value = value.to_string_value()?;
+        // An address also contains a ':', only an amount is made of digits
+        match value.split_once(':') {
+            Some((str_amount, str_asset)) if !str_amount.is_empty() && str_amount.chars().all(|c| c.is_ascii_digit() || c == '.') => {
+                let asset = if str_asset.eq_ignore_ascii_case("XELIS") {
+                    XELIS_ASSET
+                } else {
+                    Hash::from_hex(str_asset.to_owned())
+                        .map_err(|_| CommandError::InvalidArgument(format!("Invalid asset '{}'", str_asset)))?
+                };
+                requested.push((address.clone(), str_amount.to_owned(), asset));
+                has_transfer = true;
+            },
+            _ => {
+                if !has_transfer {
+                    return Err(CommandError::InvalidArgument(format!("No transfer given for {}, expected amount:asset after the address", address)));
+                }
+                address = parse_address_or_contact(wallet, &value).await?;
+                has_transfer = false;
+            }
+        }
+    }
+
+    if !has_transfer {
+        return Err(CommandError::InvalidArgument(format!("No transfer given for {}, expected amount:asset after the address", address)));
+    }
+
+    if requested.len() > MAX_TRANSFER_COUNT {
+        return Err(CommandError::InvalidArgument(format!("Too many transfers, maximum is {}", MAX_TRANSFER_COUNT)));
+    }
+
+    let mut transfers = Vec::with_capacity(requested.len());
+    // Total amount sent per asset, to check it against the balance
+    let mut totals: HashMap<Hash, u64> = HashMap::new();
+    {
+        let storage = wallet.get_storage().read().await;
+        for (destination, str_amount, asset) in requested {
+            let decimals = storage.get_asset_decimals(&asset)
+                .map_err(|_| CommandError::InvalidArgument(format!("Asset {} is not tracked by the wallet", asset)))?;
+            let amount = from_coin(&str_amount, decimals)
+                .ok_or_else(|| CommandError::InvalidArgument(format!("Invalid amount '{}'", str_amount)))?;
+
+            if amount == 0 {
+                return Err(CommandError::InvalidArgument(format!("Amount of {} for {} cannot be zero", asset, destination)));
+            }
+
+            let total = totals.entry(asset.clone()).or_insert(0);
+            *total = total.checked_add(amount)
+                .ok_or_else(|| CommandError::InvalidArgument(format!("Total amount overflow for {}", asset)))?;
+
+            manager.message(format!("Sending {} of {} to {}", format_coin(amount, decimals), asset, destination));
+            transfers.push(TransferBuilder {
+                destination,
+                amount,
+                asset,
+                extra_data: None
+            });
+        }
+
+        for (asset, total) in totals.iter() {
+            let balance = storage.get_plaintext_balance_for(asset).await.unwrap_or(0);
+            if *total > balance {
+                let decimals = storage.get_asset_decimals(asset).unwrap_or(COIN_DECIMALS);
+                return Err(CommandError::InvalidArgument(format!("Total amount {} of {} is greater than your balance {}", format_coin(*total, decimals), asset, format_coin(balance, decimals))));
+            }
+        }
+    }
+
+    manager.message(format!("Total: {} assets in {} transfers", totals.len(), transfers.len()));
+    if !prompt.ask_confirmation().await.context("Error while confirming action")? {
+        manager.message("Transaction has been aborted");
+        return Ok(())
+    }
+
+    broadcast_tx(wallet, manager, TransactionTypeBuilder::Transfers(transfers), FeeBuilder::default(), None).await
+}
+
+// Show or change the maximum fee accepted for a transaction
+async fn max_fee(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+
+    if arguments.has_argument("amount") {
+        let value = arguments.get_value("amount")?.to_string_value()?;
+        if value == "none" {
+            wallet.set_max_fee(None);
+            manager.message("Maximum fee has been disabled");
+        } else {
+            let amount = from_coin(&value, COIN_DECIMALS)
+                .filter(|amount| *amount > 0)
+                .ok_or_else(|| CommandError::InvalidArgument(format!("Invalid amount '{}'", value)))?;
+            wallet.set_max_fee(Some(amount));
+            manager.message(format!("Maximum fee set to {} XELIS", format_xelis(amount)));
+        }
+    } else {
+        match wallet.get_max_fee() {
+            Some(max_fee) => manager.message(format!("Maximum fee: {} XELIS", format_xelis(max_fee))),
+            None => manager.message("No maximum fee set")
+        };
+    }
+
+    Ok(())
+}
+
+// Show or change the selection strategy used to fund the transactions
+async fn selection_strategy(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+
+    if arguments.has_argument("strategy") {
+        let name = arguments.get_value("strategy")?.to_string_value()?;
+        let strategy = get_selection_strategy(&name)
+            .ok_or_else(|| CommandError::InvalidArgument(format!("Unknown strategy '{}', expected minimize_fee, consolidate or privacy", name)))?;
+        wallet.set_selection_strategy(strategy).await;
+        manager.message(format!("Selection strategy set to {}", name));
+    } else {
+        manager.message(format!("Selection strategy: {}", wallet.get_selection_strategy().await.get_name()));
+    }
+
+    Ok(())
+}
+
+// Send the whole balance to a specified address
+// Fees are deducted from the amount when sending XELIS
+async fn transfer_all(manager: &CommandManager, mut args: ArgumentManager) -> Result<(), CommandError> {
+    let prompt = manager.get_prompt();
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+
+    let str_address = args.get_value("address")?.to_string_value()?;
+    let address = parse_address_or_contact(wallet, &str_address).await?;
+
+    let asset = if args.has_argument("asset") {
+        args.get_value("asset")?.to_hash()?
+    } else {
+        XELIS_ASSET
+    };
+
+    let decimals = {
+        let storage = wallet.get_storage().read().await;
+        storage.get_asset_decimals(&asset).unwrap_or(COIN_DECIMALS)
+    };
+
+    let (amount, estimated_fees) = wallet.estimate_transfer_all(&asset, address.clone()).await
+        .context("Error while estimating fees")?;
+
+    if amount == 0 {
+        manager.error("Nothing to send, your balance is not enough");
+        return Ok(())
+    }
+
+    manager.message(format!("Sending {} of {} to {} (fees: {})", format_coin(amount, decimals), asset, address.to_string(), format_xelis(estimated_fees)));
+
+    if !prompt.ask_confirmation().await.context("Error while confirming action")? {
+        manager.message("Transaction has been aborted");
+        return Ok(())
+    }
+
+    let transfer = TransferBuilder {
+        destination: address,
+        amount,
+        asset,
+        extra_data: None
+    };
+    let tx_type = TransactionTypeBuilder::Transfers(vec![transfer]);
+
+    broadcast_tx(wallet, manager, tx_type, FeeBuilder::default(), None).await
+}
+
+// Estimate the fee required for a transfer
+// Amount doesn't change the fee, only the size of the transaction and if the destination is registered
+async fn estimate_fee(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+
+    let str_address = arguments.get_value("address")?.to_string_value()?;
+    let address = parse_address_or_contact(wallet, &str_address).await?;
+
+    let asset = if arguments.has_argument("asset") {
+        arguments.get_value("asset")?.to_hash()?
+    } else {
+        XELIS_ASSET
+    };
+
+    if !wallet.is_online().await {
+        manager.warn("Wallet is offline, destination is considered as not registered on chain");
+    }
+
+    let transfer = TransferBuilder {
+        destination: address,
+        amount: 0,
+        asset,
+        extra_data: None
+    };
+    let fee = wallet.estimate_fees(TransactionTypeBuilder::Transfers(vec![transfer])).await
+        .context("Error while estimating fees")?;
+
+    manager.message(format!("Estimated fee: {} XELIS", format_xelis(fee)));
+    Ok(())
+}
+
+async fn burn(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let prompt = manager.get_prompt();
+    let str_amount = arguments.get_value("amount")?.to_string_value()?;
+    let asset = arguments.get_value("asset")?.to_hash()?;
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+
+    let (max_balance, decimals) = {
+        let storage = wallet.get_storage().read().await;
+        let balance = storage.get_plaintext_balance_for(&asset).await.unwrap_or(0);
+        let decimals = storage.get_asset_decimals(&asset).unwrap_or(COIN_DECIMALS);
+        (balance, decimals)
+    };
+
+    let amount = from_coin(str_amount.as_str(), decimals)
+        .ok_or_else(|| CommandError::InvalidArgument(format!("Invalid amount '{}'", str_amount)))?;
+
+    if amount == 0 {
+        return Err(CommandError::InvalidArgument("Amount to burn cannot be zero".to_owned()));
+    }
+
+    if amount > max_balance {
+        return Err(CommandError::InvalidArgument(format!("Amount {} is greater than your balance {}", format_coin(amount, decimals), format_coin(max_balance, decimals))));
+    }
+
+    let payload = BurnPayload {
+        amount,
+        asset: asset.clone()
+    };
+    let tx_type = TransactionTypeBuilder::Burn(payload);
+    let estimated_fees = wallet.estimate_fees(tx_type.clone()).await.context("Error while estimating fees")?;
+
+    manager.warn(format!("Burning {} of {} (fees: {})", format_coin(amount, decimals), asset, format_xelis(estimated_fees)));
+    manager.warn("Burned funds are destroyed forever, this action cannot be undone!");
+
+    if !prompt.ask_confirmation().await.context("Error while confirming action")? {
+        manager.message("Burn has been aborted");
+        return Ok(())
+    }
+
+    broadcast_tx(wallet, manager, tx_type, FeeBuilder::Multiplier(1f64), None).await
+}
+
+// Manage the address book: add, remove or list contacts
+async fn address_book(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    let action = arguments.get_value("action")?.to_string_value()?;
+
+    match action.as_str() {
+        "add" => {
+            let name = arguments.get_value("name")?.to_string_value()?;
+            let address = Address::from_string(&arguments.get_value("address")?.to_string_value()?).context("Invalid address")?;
+            if address.get_network() != *wallet.get_network() {
+                return Err(CommandError::InvalidArgument(format!("Address is for {} but this wallet is running on {}", address.get_network(), wallet.get_network())));
+            }
+
+            let mut storage = wallet.get_storage().write().await;
+            storage.add_contact(&name, &address)?;
+            manager.message(format!("Contact '{}' has been added", name));
+        },
+        "remove" => {
+            let name = arguments.get_value("name")?.to_string_value()?;
+            let mut storage = wallet.get_storage().write().await;
+            storage.remove_contact(&name)?;
+            manager.message(format!("Contact '{}' has been removed", name));
+        },
+        "list" => {
+            let storage = wallet.get_storage().read().await;
+            let contacts = storage.get_contacts()?;
+            if contacts.is_empty() {
+                manager.message("No contacts in address book");
+                return Ok(())
+            }
+
+            manager.message(format!("Contacts ({}):", contacts.len()));
+            for (name, address) in contacts {
+                manager.message(format!("- {}: {}", name, address));
+            }
+        },
+        _ => return Err(CommandError::InvalidArgument(format!("Unknown action '{}', expected add, remove or list", action)))
+    }
+
+    Ok(())
+}
+
+// Manage the webhooks notified with a signed JSON payload on confirmed transfers
+async fn webhook(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    let action = arguments.get_value("action")?.to_string_value()?;
+
+    match action.as_str() {
+        "add" => {
+            let url = arguments.get_value("url")?.to_string_value()?;
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                return Err(CommandError::InvalidArgument("Webhook URL must start with http:// or https://".to_owned()));
+            }
+
+            let mut storage = wallet.get_storage().write().await;
+            storage.add_webhook(&url, get_current_time_in_seconds())?;
+            manager.message(format!("Webhook '{}' has been added", url));
+        },
+        "remove" => {
+            let url = arguments.get_value("url")?.to_string_value()?;
+            let mut storage = wallet.get_storage().write().await;
+            storage.remove_webhook(&url)?;
+            manager.message(format!("Webhook '{}' has been removed", url));
+        },
+        "list" => {
+            let storage = wallet.get_storage().read().await;
+            let webhooks = storage.get_webhooks()?;
+            if webhooks.is_empty() {
+                manager.message("No webhooks registered");
+                return Ok(())
+            }
+
+            manager.message(format!("Webhooks ({}):", webhooks.len()));
+            for (url, _) in webhooks {
+                manager.message(format!("- {}", url));
+            }
+        },
+        _ => return Err(CommandError::InvalidArgument(format!("Unknown action '{}', expected add, remove or list", action)))
+    }
+
+    Ok(())
+}
+
+// Attach a label to a transaction, it is only stored encrypted in the wallet
+async fn label_tx(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let hash = arguments.get_value("hash")?.to_hash()?;
+    let prompt = manager.get_prompt();
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+
+    let label = prompt.read_input(prompt::colorize_str(Color::Green, "Label: "), false)
+        .await.context("Error while reading label")?;
+
+    let mut storage = wallet.get_storage().write().await;
+    if !storage.has_transaction(&hash)? {
+        manager.warn(format!("Transaction {} is not in your history yet", hash));
+    }
+
+    storage.set_transaction_label(&hash, label.trim())?;
+    if label.trim().is_empty() {
+        manager.message(format!("Label removed from transaction {}", hash));
+    } else {
+        manager.message(format!("Label set on transaction {}", hash));
+    }
+
+    Ok(())
+}
+
+// Attach a label to an address, it is shown in the history for all transfers with it
+async fn label_address(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let str_address = arguments.get_value("address")?.to_string_value()?;
+    let prompt = manager.get_prompt();
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    let address = parse_address_or_contact(wallet, &str_address).await?;
+
+    let label = prompt.read_input(prompt::colorize_str(Color::Green, "Label: "), false)
+        .await.context("Error while reading label")?;
+
+    let mut storage = wallet.get_storage().write().await;
+    storage.set_address_label(address.get_public_key(), label.trim())?;
+    if label.trim().is_empty() {
+        manager.message(format!("Label removed from address {}", address));
+    } else {
+        manager.message(format!("Label set on address {}", address));
+    }
+
+    Ok(())
+}
+
+// Export an encrypted backup of the wallet to a file
+async fn backup(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let path = arguments.get_value("path")?.to_string_value()?;
+    let prompt = manager.get_prompt();
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+
+    if Path::new(&path).exists() {
+        manager.error(format!("File {} already exists", path));
+        return Ok(())
+    }
+
+    let password = prompt.read_input(prompt::colorize_str(Color::BrightRed, "Wallet Password: "), true)
+        .await.context("Error while reading password")?;
+    wallet.is_valid_password(password).await?;
+
+    let backup_password = prompt.read_input(prompt::colorize_str(Color::BrightRed, "Backup Password: "), true)
+        .await.context("Error while reading backup password")?;
+    let confirm_password = prompt.read_input(prompt::colorize_str(Color::BrightRed, "Confirm Backup Password: "), true)
+        .await.context("Error while reading backup password")?;
+
+    if backup_password != confirm_password {
+        manager.error("Confirm password doesn't match password");
+        return Ok(())
+    }
+
+    let backup = wallet.export_backup().await?;
+    let content = backup.to_encrypted_bytes(backup_password)?;
+    fs::write(&path, content).with_context(|| format!("Error while writing backup to {}", path))?;
+
+    manager.message(format!("Backup has been written to {}, keep it safe with its password", path));
+    Ok(())
+}
+
+// Manage the accounts of the wallet, each one has its own address, balances and history
+async fn account(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let action = arguments.get_value("action")?.to_string_value()?;
+    let mut context = manager.get_context().lock()?;
+    let wallet: Arc<Wallet> = Arc::clone(context.get()?);
+
+    match action.as_str() {
+        "create" => {
+            let name = arguments.get_value("value")?.to_string_value()?;
+            let index = wallet.create_account(name.clone()).await.context("Error while creating account")?;
+            manager.message(format!("Account #{} '{}' has been created, use 'account switch {}' to use it", index, name, index));
+        },
+        "switch" => {
+            let value = arguments.get_value("value")?.to_string_value()?;
+            let index: u32 = value.parse().map_err(|_| CommandError::InvalidArgument(format!("Invalid account index '{}'", value)))?;
+            if index == wallet.get_account().await {
+                manager.error(format!("Account #{} is already selected", index));
+                return Ok(())
+            }
+
+            let new_wallet = wallet.open_account(index).await.context("Error while opening account")?;
+            new_wallet.set_lock_timeout(wallet.get_lock_timeout());
+            new_wallet.set_max_fee(wallet.get_max_fee());
+            let (max_transactions, max_age) = wallet.get_history_retention();
+            new_wallet.set_history_retention(max_transactions, max_age);
+            new_wallet.set_tls_config(wallet.get_tls_config().await).await;
+            new_wallet.set_proxy(wallet.get_proxy().await).await;
+            new_wallet.set_light_mode(wallet.is_light_mode());
+            new_wallet.set_sync_options(wallet.get_sync_batch_size(), wallet.get_sync_concurrency());
+            new_wallet.set_selection_strategy(wallet.get_selection_strategy().await).await;
+            new_wallet.set_keyfile_hash(wallet.get_keyfile_hash().await).await;
+
+            // Reuse the same daemon connection for the new account
+            let api = {
+                let network_handler = wallet.get_network_handler().await.lock().await;
+                network_handler.as_ref().map(|handler| handler.get_shared_api())
+            };
+
+            // Servers are closed with the previous account and started again for the new one
+            #[cfg(feature = "api_server")]
+            let api_server = match wallet.get_api_server().lock().await.as_ref() {
+                Some(APIServer::RPCServer(server)) => Some(Some((server.get_bind_address().to_owned(), server.get_auth_config().cloned()))),
+                Some(APIServer::XSWD(_)) => Some(None),
+                None => None
+            };
+            #[cfg(feature = "grpc")]
+            let grpc_address = wallet.get_grpc_bind_address().await;
+
+            wallet.close().await;
+            tokio::spawn(notify_transactions(Arc::clone(&new_wallet)));
+            tokio::spawn(run_webhooks(Arc::clone(&new_wallet)));
+            if let Some(api) = api {
+                if let Err(e) = new_wallet.set_online_mode_with_api(api).await {
+                    manager.error(format!("Couldn't enable online mode for account #{}: {}", index, e));
+                }
+            }
+
+            #[cfg(feature = "api_server")]
+            {
+                match api_server {
+                    Some(Some((bind_address, auth_config))) => if let Err(e) = new_wallet.enable_rpc_server(bind_address, auth_config).await {
+                        manager.error(format!("Error while enabling RPC Server for account #{}: {}", index, e));
+                    },
+                    Some(None) => match new_wallet.enable_xswd().await {
+                        Ok(receiver) => {
+                            tokio::spawn(xswd_handler(receiver, manager.get_prompt().clone()));
+                        },
+                        Err(e) => manager.error(format!("Error while enabling XSWD Server for account #{}: {}", index, e))
+                    },
+                    None => {}
+                }
+            }
+
+            #[cfg(feature = "grpc")]
+            {
+                if let Some(address) = grpc_address {
+                    if let Err(e) = new_wallet.enable_grpc_server(address).await {
+                        manager.error(format!("Error while enabling gRPC Server for account #{}: {}", index, e));
+                    }
+                }
+            }
+
+            manager.message(format!("Switched to account #{} with address {}", index, new_wallet.get_address()));
+            context.store(new_wallet);
+        },
+        "list" => {
+            let current = wallet.get_account().await;
+            let storage = wallet.get_storage().read().await;
+            let accounts = storage.get_accounts()?;
+
+            manager.message(format!("Accounts ({}):", accounts.len() + 1));
+            let network = *wallet.get_network();
+            for (index, name) in std::iter::once((0, "main".to_owned())).chain(accounts) {
+                let account_storage = storage.open_account(index)?;
+                let public_key = if account_storage.is_watch_only()? {
+                    account_storage.get_public_key()?
+                } else {
+                    KeyPair::from_private_key(account_storage.get_private_key()?).get_public_key().compress()
+                };
+
+                let selected = if index == current { " (selected)" } else { "" };
+                manager.message(format!("- #{} {}: {}{}", index, name, public_key.to_address(network), selected));
+            }
+        },
+        _ => return Err(CommandError::InvalidArgument(format!("Unknown action '{}', expected create, switch or list", action)))
+    }
+
+    Ok(())
+}
+
+// Manage the subaddresses of the account, each customer can be given a unique one to match its payments
+async fn address(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let action = arguments.get_value("action")?.to_string_value()?;
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+
+    match action.as_str() {
+        "new" => {
+            let label = if arguments.has_argument("label") {
+                arguments.get_value("label")?.to_string_value()?
+            } else {
+                String::new()
+            };
+
+            let (index, address) = wallet.create_subaddress(label).await.context("Error while creating subaddress")?;
+            manager.message(format!("Subaddress #{}: {}", index, address));
+            // It has its own balance on chain, it is not merged with the main address
+            manager.message("Funds received on it are shown in your history but kept on its own balance, use 'sweep_subaddress' to send them to your main address");
+        },
+        "list" => {
+            let subaddresses = wallet.get_subaddresses().await.context("Error while retrieving subaddresses")?;
+            if subaddresses.is_empty() {
+                manager.message("No subaddresses, use 'address new' to create one");
+                return Ok(())
+            }
+
+            manager.message(format!("Subaddresses ({}):", subaddresses.len()));
+            for (index, label, address) in subaddresses {
+                if label.is_empty() {
+                    manager.message(format!("- #{}: {}", index, address));
+                } else {
+                    manager.message(format!("- #{} {}: {}", index, label, address));
+                }
+            }
+        },
+        _ => return Err(CommandError::InvalidArgument(format!("Unknown action '{}', expected new or list", action)))
+    }
+
+    Ok(())
+}
+
+// Send the balance of a subaddress to the main address, the fees are paid by the subaddress
+async fn sweep_subaddress(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let prompt = manager.get_prompt();
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+
+    let index = u32::try_from(arguments.get_value("index")?.to_number()?)
+        .map_err(|_| CommandError::InvalidArgument("Invalid subaddress index".to_owned()))?;
+    let asset = if arguments.has_argument("asset") {
+        arguments.get_value("asset")?.to_hash()?
+    } else {
+        XELIS_ASSET
+    };
+
+    manager.message(format!("Sending the balance of {} on subaddress #{} to your main address", asset, index));
+    if !prompt.ask_confirmation().await.context("Error while confirming action")? {
+        manager.message("Transaction has been aborted");
+        return Ok(())
+    }
+
+    let tx = wallet.sweep_subaddress(index, asset).await.context("Error while sweeping subaddress")?;
+    manager.message(format!("Transaction {} has been accepted in mempool!", tx.hash()));
+
+    Ok(())
+}
+
+// Manage the transfers executed automatically by the wallet when online
+async fn schedule(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let prompt = manager.get_prompt();
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    let action = arguments.get_value("action")?.to_string_value()?;
+
+    match action.as_str() {
+        "add" => {
+            let str_address = prompt.read_input(
+                prompt::colorize_str(Color::Green, "Address or contact name: "),
+                false
+            ).await.context("Error while reading address")?;
+            let address = parse_address_or_contact(wallet, &str_address).await?;
+
+            let asset = prompt.read_hash(
+                prompt::colorize_str(Color::Green, "Asset (default XELIS): ")
+            ).await.ok().unwrap_or(XELIS_ASSET);
+
+            let decimals = {
+                let storage = wallet.get_storage().read().await;
+                if !storage.contains_asset(&asset).await? {
+                    return Err(CommandError::InvalidArgument(format!("Asset {} is not tracked by this wallet", asset)));
+                }
+                storage.get_asset_decimals(&asset).unwrap_or(COIN_DECIMALS)
+            };
+
+            let str_amount = prompt.read_input(prompt::colorize_str(Color::Green, "Amount: "), false)
+                .await.context("Error while reading amount")?;
+            let amount = from_coin(str_amount.as_str(), decimals)
+                .ok_or_else(|| CommandError::InvalidArgument(format!("Invalid amount '{}'", str_amount)))?;
+
+            let str_delay = prompt.read_input(prompt::colorize_str(Color::Green, "First execution in (e.g. 30m, 2h, 1d, default now): "), false)
+                .await.context("Error while reading delay")?;
+            let delay = if str_delay.is_empty() {
+                0
+            } else {
+                parse_duration(&str_delay).ok_or_else(|| CommandError::InvalidArgument(format!("Invalid duration '{}'", str_delay)))?
+            };
+
+            let str_interval = prompt.read_input(prompt::colorize_str(Color::Green, "Repeat every (e.g. 1d, 1w, empty for one-shot): "), false)
+                .await.context("Error while reading interval")?;
+            let interval = if str_interval.is_empty() {
+                None
+            } else {
+                match parse_duration(&str_interval) {
+                    Some(interval) if interval > 0 => Some(interval),
+                    _ => return Err(CommandError::InvalidArgument(format!("Invalid interval '{}'", str_interval)))
+                }
+            };
+
+            manager.message(format!("Scheduling {} of {} to {}{}", format_coin(amount, decimals), asset, address, match interval {
+                Some(interval) => format!(" every {} seconds", interval),
+                None => String::new()
+            }));
+
+            if !prompt.ask_confirmation().await.context("Error while confirming action")? {
+                manager.message("Scheduled transfer has been aborted");
+                return Ok(())
+            }
+
+            let mut storage = wallet.get_storage().write().await;
+            let id = storage.add_scheduled_payment(address, asset, amount, get_current_time_in_seconds() + delay, interval)?;
+            manager.message(format!("Scheduled transfer #{} has been added, it will be executed while the wallet is online", id));
+        },
+        "remove" => {
+            let id = arguments.get_value("id")?.to_number()?;
+            let mut storage = wallet.get_storage().write().await;
+            if storage.remove_scheduled_payment(id)? {
+                manager.message(format!("Scheduled transfer #{} has been removed", id));
+            } else {
+                manager.error(format!("No scheduled transfer found with id {}", id));
+            }
+        },
+        "list" => {
+            let storage = wallet.get_storage().read().await;
+            let payments = storage.get_scheduled_payments()?;
+            if payments.is_empty() {
+                manager.message("No scheduled transfers");
+                return Ok(())
+            }
+
+            let now = get_current_time_in_seconds();
+            manager.message(format!("Scheduled transfers ({}):", payments.len()));
+            for payment in payments {
+                let decimals = storage.get_asset_decimals(payment.get_asset()).unwrap_or(COIN_DECIMALS);
+                let next = payment.get_next_execution();
+                let when = if next <= now {
+                    "due now".to_owned()
+                } else {
+                    format!("in {} seconds", next - now)
+                };
+                let repeat = match payment.get_interval() {
+                    Some(interval) => format!(", every {} seconds", interval),
+                    None => String::new()
+                };
+
+                manager.message(format!("- #{}: {} of {} to {} ({}{})", payment.get_id(), format_coin(payment.get_amount(), decimals), payment.get_asset(), payment.get_destination(), when, repeat));
+            }
+        },
+        _ => return Err(CommandError::InvalidArgument(format!("Unknown action '{}', expected add, remove or list", action)))
+    }
+
+    Ok(())
+}
+
+// Sign a message with the wallet key
+async fn sign_message(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+    let prompt = manager.get_prompt();
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+
+    if wallet.is_locked() {
+        manager.error("Wallet is locked, use 'unlock' command first");
+        return Ok(())
+    }
+
+    let message = prompt.read_input(prompt::colorize_str(Color::Green, "Message: "), false)
+        .await.context("Error while reading message")?;
+
+    if message.is_empty() {
+        manager.error("Message cannot be empty");
+        return Ok(())
+    }
+
+    let signature = wallet.sign_data(message.as_bytes()).context("Error while signing message")?;
+    manager.message(format!("Address: {}", wallet.get_address()));
+    manager.message(format!("Signature: {}", signature.to_hex()));
+    Ok(())
+}
+
+// Verify a message signature against the address of its signer
+async fn verify_message(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+    let prompt = manager.get_prompt();
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+
+    let str_address = prompt.read_input(prompt::colorize_str(Color::Green, "Address: "), false)
+        .await.context("Error while reading address")?;
+    let address = Address::from_string(&str_address).context("Invalid address")?;
+
+    let message = prompt.read_input(prompt::colorize_str(Color::Green, "Message: "), false)
+        .await.context("Error while reading message")?;
+
+    let str_signature = prompt.read_input(prompt::colorize_str(Color::Green, "Signature: "), false)
+        .await.context("Error while reading signature")?;
+    let signature = Signature::from_hex(str_signature).context("Invalid signature format")?;
+
+    if wallet.verify_data(message.as_bytes(), &signature, address.get_public_key()) {
+        manager.message("Signature is valid for this address");
+    } else {
+        manager.error("Signature is NOT valid for this address");
+    }
+
+    Ok(())
+}
+
+// Generate an integrated address containing data in JSON format (an order ID for example)
+// Funds sent to it will have the data attached so the payment can be matched
+async fn generate_integrated_address(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+    let prompt = manager.get_prompt();
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+
+    let json = prompt.read_input(
+        prompt::colorize_str(Color::Green, "Data in JSON format (example: {\"order_id\": 42}): "),
+        false
+    ).await.context("Error while reading data")?;
+
+    let data: DataElement = serde_json::from_str(&json)
+        .map_err(|e| CommandError::InvalidArgument(format!("Invalid data: {}", e)))?;
+
+    if data.size() > EXTRA_DATA_LIMIT_SIZE {
+        return Err(CommandError::InvalidArgument(format!("Data is too big, maximum size is {} bytes", EXTRA_DATA_LIMIT_SIZE)));
+    }
+
+    let address = wallet.get_address_with(data);
+    manager.message(format!("Integrated address: {}", address));
+    Ok(())
+}
+
+// Generate a payment URI to request funds on this wallet
+async fn payment_request(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let prompt = manager.get_prompt();
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+
+    let mut uri = PaymentURI::new(wallet.get_address());
+    if arguments.has_argument("amount") {
+        let amount = arguments.get_value("amount")?.to_string_value()?;
+        uri = uri.with_amount(amount).context("Invalid amount")?;
+    }
+
+    if arguments.has_argument("asset") {
+        uri = uri.with_asset(arguments.get_value("asset")?.to_hash()?);
+    }
+
+    let message = prompt.read_input(
+        prompt::colorize_str(Color::Green, "Message (optional): "),
+        false
+    ).await.context("Error while reading message")?;
+    if !message.is_empty() {
+        uri = uri.with_message(message);
+    }
+
+    manager.message(format!("Payment URI: {}", uri));
+    Ok(())
+}
+
+// Show current wallet address
+async fn display_address(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    let address = wallet.get_address().to_string();
+    manager.message(format!("Wallet address: {}", address));
+
+    let qr = if arguments.has_argument("qr") {
+        arguments.get_value("qr")?.to_bool()?
+    } else {
+        false
+    };
+
+    let png_path = if arguments.has_argument("png_path") {
+        Some(arguments.get_value("png_path")?.to_string_value()?)
+    } else {
+        None
+    };
+
+    if !qr && png_path.is_none() {
+        return Ok(())
+    }
+
+    let code = QrCode::new(address.as_bytes()).context("Error while generating QR code")?;
+    if qr {
+        let rendered = code.render::<unicode::Dense1x2>()
+            .dark_color(unicode::Dense1x2::Light)
+            .light_color(unicode::Dense1x2::Dark)
+            .quiet_zone(true)
+            .build();
+        manager.message(format!("\n{}", rendered));
+    }
+
+    // PNG export doesn't depend on the terminal rendering
+    if let Some(path) = png_path {
+        code.render::<Luma<u8>>()
+            .min_dimensions(256, 256)
+            .build()
+            .save(&path)
+            .context("Error while saving QR code to PNG")?;
+        manager.message(format!("QR code has been exported to {}", path));
+    }
+
+    Ok(())
+}
+
+// Show current balance for specified asset or list all non-zero balances
+async fn balance(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    let price_feed = context.get_optional::<PriceFeed>();
+    // Read from the balances cache before locking the storage
+    let balances = wallet.get_balances().await.context("Error while retrieving balances")?;
+
+    if wallet.is_watch_only() {
+        manager.warn("Watch-only wallet: balances can't be decrypted without the private key");
+    }
+
+    let assets = {
+        let storage = wallet.get_storage().read().await;
+        if arguments.has_argument("asset") {
+            let asset = arguments.get_value("asset")?.to_hash()?;
+            let decimals = storage.get_asset_decimals(&asset).unwrap_or(0);
+            vec![(asset, decimals)]
+        } else {
+            storage.get_assets_with_decimals().await?
+        }
+    };
+
+    // Prices are fetched before locking the storage again
+    let prices = match price_feed {
+        Some(feed) => feed.get_prices(assets.iter().map(|(asset, _)| asset)).await,
+        None => HashMap::new()
+    };
+
+    let storage = wallet.get_storage().read().await;
+    // Only look for unconfirmed outgoing funds if we have broadcasted transactions
+    let has_pending = !storage.get_pending_transactions()?.is_empty();
+    let show_empty = arguments.has_argument("asset");
+    for (asset, decimals) in assets {
+        let balance = balances.get(&asset).copied().unwrap_or(0);
+        let outgoing = if has_pending {
+            let unconfirmed = storage.get_unconfirmed_balance_for(&asset).await.map(|b| b.amount).unwrap_or(balance);
+            balance.saturating_sub(unconfirmed)
+        } else {
+            0
+        };
+
+        if balance > 0 || show_empty {
+            let mut line = format!("Balance for asset {}: {}", asset, format_coin(balance, decimals));
+            if let Some(value) = fiat_value(price_feed, &prices, &asset, balance, decimals) {
+                line.push_str(&format!(" ({})", value));
+            }
+
+            if outgoing > 0 {
+                line.push_str(&format!(" (unconfirmed outgoing: {})", format_coin(outgoing, decimals)));
+            }
+            manager.message(line);
+        }
+    }
+
+    Ok(())
+}
+
+// Format the approximate fiat value of an amount if a price feed is configured
+// Prices must be fetched with `PriceFeed::get_prices` before
+fn fiat_value(price_feed: Option<&PriceFeed>, prices: &HashMap<Hash, f64>, asset: &Hash, amount: u64, decimals: u8) -> Option<String> {
+    let feed = price_feed?;
+    let price = prices.get(asset)?;
+    Some(feed.format_value(PriceFeed::value_of(*price, amount, decimals)))
+}
+
+// All amounts moved by a transaction with their asset decimals
+fn entry_amounts(entry: &EntryData, storage: &EncryptedStorage) -> Vec<(Hash, u64, u8)> {
+    let amounts: Vec<(&Hash, u64)> = match entry {
+        EntryData::Coinbase { reward } => vec![(&XELIS_ASSET, *reward)],
+        EntryData::Burn { asset, amount } => vec![(asset, *amount)],
+        EntryData::Incoming { transfers, .. } => transfers.iter().map(|t| (t.get_asset(), t.get_amount())).collect(),
+        EntryData::Outgoing { transfers, .. } => transfers.iter().map(|t| (t.get_asset(), t.get_amount())).collect()
+    };
+
+    amounts.into_iter()
+        .map(|(asset, amount)| (asset.clone(), amount, storage.get_asset_decimals(asset).unwrap_or(COIN_DECIMALS)))
+        .collect()
+}
+
+// Approximate fiat value of all amounts moved by a transaction, at the current price
+fn entry_fiat_value(price_feed: Option<&PriceFeed>, prices: &HashMap<Hash, f64>, amounts: &[(Hash, u64, u8)]) -> Option<String> {
+    let feed = price_feed?;
+    let mut total = None;
+    for (asset, amount, decimals) in amounts {
+        if let Some(price) = prices.get(asset) {
+            total = Some(total.unwrap_or(0f64) + PriceFeed::value_of(*price, *amount, *decimals));
+        }
+    }
+
+    total.map(|value| feed.format_value(value))
+}
+
+// Show all transactions broadcasted by the wallet that are not yet in a block
+async fn pending(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    let storage = wallet.get_storage().read().await;
+
+    let transactions = storage.get_pending_transactions()?;
+    if transactions.is_empty() {
+        manager.message("No pending transactions");
+        return Ok(())
+    }
+
+    manager.message(format!("Pending transactions ({}):", transactions.len()));
+    for tx in transactions {
+        let replaces = match storage.get_replaced_transaction(tx.get_hash())? {
+            Some(replaced) => format!(", replaces: {}", replaced),
+            None => String::new()
+        };
+        manager.message(format!("- {} (nonce: {}, fee: {}, submitted at: {}{})", tx.get_hash(), tx.get_nonce(), format_xelis(tx.get_fee()), tx.get_timestamp(), replaces));
+    }
+
+    Ok(())
+}
+
+// Replace a pending transaction by an empty burn using the same nonce
+// Its transfers are never executed if the replacement is included first
+async fn cancel_tx(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let hash = arguments.get_value("hash")?.to_hash()?;
+    let prompt = manager.get_prompt();
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+
+    {
+        let storage = wallet.get_storage().read().await;
+        let pending = storage.get_pending_transactions()?.into_iter()
+            .find(|tx| *tx.get_hash() == hash)
+            .ok_or_else(|| CommandError::InvalidArgument(format!("Transaction {} is not pending", hash)))?;
+        manager.message(format!("Cancelling transaction {} (nonce: {}, fee: {})", hash, pending.get_nonce(), format_xelis(pending.get_fee())));
+    }
+
+    if !prompt.ask_confirmation().await.context("Error while confirming action")? {
+        manager.message("Cancellation has been aborted");
+        return Ok(())
+    }
+
+    let tx = wallet.cancel_transaction(&hash).await
+        .context("Error while cancelling transaction")?;
+    manager.message(format!("Transaction {} replaced by {} (fee: {})", hash, tx.hash(), format_xelis(tx.get_fee())));
+
+    Ok(())
+}
+
+// Show the details of a transaction from the local history or from the daemon
+async fn tx_info(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let hash = arguments.get_value("hash")?.to_hash()?;
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    let network = *wallet.get_network();
+
+    let network_handler = wallet.get_network_handler().await.lock().await;
+    let api = network_handler.as_ref()
+        .filter(|handler| handler.get_api().is_online())
+        .map(|handler| handler.get_api());
+
+    // Current topoheight of the chain to compute the confirmations
+    let network_topoheight = match api {
+        Some(api) => api.get_info().await.ok().map(|info| info.topoheight),
+        None => None
+    };
+
+    let storage = wallet.get_storage().read().await;
+    // Replacement made by cancel_tx
+    let replacement = storage.get_transaction_replacement(&hash)?;
+    let replaced = storage.get_replaced_transaction(&hash)?;
+    let display_replacement = || {
+        if let Some(replacement) = replacement.as_ref() {
+            manager.message(format!("Replaced by: {}", replacement));
+        }
+        if let Some(replaced) = replaced.as_ref() {
+            manager.message(format!("Replaces: {}", replaced));
+        }
+    };
+
+    if storage.has_transaction(&hash)? {
+        let entry = storage.get_transaction(&hash)?;
+        let topoheight = entry.get_topoheight();
+        manager.message(format!("Transaction {}", hash));
+        display_replacement();
+        manager.message(format!("TopoHeight: {}", topoheight));
+        if let Some(api) = api {
+            if let Ok(block) = api.get_block_at_topoheight(topoheight).await {
+                manager.message(format!("Height: {}", block.height));
+            }
+        }
+        if let Some(network_topoheight) = network_topoheight {
+            manager.message(format!("Confirmations: {}", network_topoheight.saturating_sub(topoheight) + 1));
+        }
+
+        match entry.get_entry() {
+            EntryData::Coinbase { reward } => {
+                manager.message("Type: Coinbase");
+                manager.message(format!("Reward: {} XELIS", format_xelis(*reward)));
+            },
+            EntryData::Burn { asset, amount } => {
+                let decimals = storage.get_asset_decimals(asset).unwrap_or(0);
+                manager.message("Type: Burn");
+                manager.message(format!("Burned: {} of {}", format_coin(*amount, decimals), asset));
+            },
+            EntryData::Incoming { from, transfers } => {
+                manager.message("Type: Incoming");
+                manager.message(format!("From: {}", from.as_address(network)));
+                manager.message(format!("Transfers ({}):", transfers.len()));
+                for transfer in transfers {
+                    let decimals = storage.get_asset_decimals(transfer.get_asset()).unwrap_or(0);
+                    match transfer.get_extra_data() {
+                        Some(data) => manager.message(format!("- {} of {} with data {}", format_coin(transfer.get_amount(), decimals), transfer.get_asset(), format_extra_data(data))),
+                        None => manager.message(format!("- {} of {}", format_coin(transfer.get_amount(), decimals), transfer.get_asset()))
+                    };
+                    if let Some(subaddress) = transfer.get_subaddress() {
+                        manager.message(format!("  Received on subaddress #{}", subaddress));
+                    }
+                }
+            },
+            EntryData::Outgoing { transfers, fee, nonce } => {
+                manager.message("Type: Outgoing");
+                manager.message(format!("Fee: {} XELIS", format_xelis(*fee)));
+                manager.message(format!("Nonce: {}", nonce));
+                manager.message(format!("Transfers ({}):", transfers.len()));
+                for transfer in transfers {
+                    let decimals = storage.get_asset_decimals(transfer.get_asset()).unwrap_or(0);
+                    match transfer.get_extra_data() {
+                        Some(data) => manager.message(format!("- {} of {} to {} with data {}", format_coin(transfer.get_amount(), decimals), transfer.get_asset(), transfer.get_destination().as_address(network), format_extra_data(data))),
+                        None => manager.message(format!("- {} of {} to {}", format_coin(transfer.get_amount(), decimals), transfer.get_asset(), transfer.get_destination().as_address(network)))
+                    };
+                }
+            }
+        };
+
+        return Ok(())
+    }
+
+    let api = match api {
+        Some(api) => api,
+        None => return Err(CommandError::InvalidArgument("Transaction not found in history and wallet is offline".to_owned()))
+    };
+
+    let response = api.get_transaction_with_status(&hash).await
+        .context("Transaction not found in history nor in daemon")?;
+
+    manager.message(format!("Transaction {}", hash));
+    display_replacement();
+    if response.in_mempool {
+        manager.message("Status: in mempool");
+    } else if let Some(block_hash) = response.executed_in_block.as_ref() {
+        manager.message(format!("Status: executed in block {}", block_hash));
+        if let Ok(block) = api.get_block_by_hash(block_hash).await {
+            manager.message(format!("Height: {}", block.height));
+            if let Some(topoheight) = block.topoheight {
+                manager.message(format!("TopoHeight: {}", topoheight));
+                if let Some(network_topoheight) = network_topoheight {
+                    manager.message(format!("Confirmations: {}", network_topoheight.saturating_sub(topoheight) + 1));
+                }
+            }
+        }
+    } else {
+        manager.message("Status: included in blocks but not executed");
+    }
+
+    let data = response.data;
+    manager.message(format!("Source: {}", data.source));
+    manager.message(format!("Fee: {} XELIS", format_xelis(data.fee)));
+    manager.message(format!("Nonce: {}", data.nonce));
+    match data.data {
+        RPCTransactionType::Transfers(transfers) => {
+            // Amounts are encrypted and can't be shown
+            manager.message(format!("Transfers ({}):", transfers.len()));
+            for transfer in transfers {
+                manager.message(format!("- {} to {}", transfer.asset, transfer.destination));
+            }
+        },
+        RPCTransactionType::Burn(payload) => {
+            let decimals = storage.get_asset_decimals(&payload.asset).unwrap_or(COIN_DECIMALS);
+            manager.message(format!("Burned: {} of {}", format_coin(payload.amount, decimals), payload.asset));
+        },
+        RPCTransactionType::MultiSig(payload) => display_multisig_setup(manager, payload.threshold, &payload.participants)
+    };
+
+    Ok(())
+}
+
+// List all assets tracked by the wallet, even the ones with a zero balance
+async fn list_assets(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    let storage = wallet.get_storage().read().await;
+
+    let mut assets = storage.get_assets_with_decimals().await?;
+    if assets.is_empty() {
+        manager.message("No assets tracked");
+        return Ok(())
+    }
+
+    // XELIS first, then ordered by hash
+    assets.sort_by(|(a, _), (b, _)| (*b == XELIS_ASSET).cmp(&(*a == XELIS_ASSET)).then_with(|| a.cmp(b)));
+
+    manager.message(format!("Assets tracked ({}):", assets.len()));
+    for (asset, decimals) in assets {
+        let balance = storage.get_plaintext_balance_for(&asset).await.unwrap_or(0);
+        let name = if asset == XELIS_ASSET { " (XELIS)" } else { "" };
+        manager.message(format!("- {}{}: {} (decimals: {})", asset, name, format_coin(balance, decimals), decimals));
+    }
+
+    Ok(())
+}
+
+// Show all transactions
+const TXS_PER_PAGE: usize = 10;
+async fn history(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let page = if arguments.has_argument("page") {
+        arguments.get_value("page")?.to_number()? as usize
+    } else {
+        1
+    };
+
+    if page == 0 {
+        return Err(CommandError::InvalidArgument("Page must be greater than 0".to_string()));
+    }
+
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    let price_feed = context.get_optional::<PriceFeed>();
+    let storage = wallet.get_storage().read().await;
+    let count = storage.count_transactions();
+
+    // if we don't have any txs, no need proceed further
+    if count == 0 {
+        manager.message("No transactions available");
+        return Ok(())
+    }
+
+    let mut max_pages = count / TXS_PER_PAGE;
+    if count % TXS_PER_PAGE != 0 {
+        max_pages += 1;
+    }
+
+    if page > max_pages {
+        return Err(CommandError::InvalidArgument(format!("Page must be less than maximum pages ({})", max_pages)));
+    }
+
+    // desc ordered by topoheight thanks to the transactions index
+    let transactions = storage.get_transactions_page((page - 1) * TXS_PER_PAGE, TXS_PER_PAGE)?;
+    let mut lines = Vec::with_capacity(transactions.len());
+    for tx in transactions.iter() {
+        lines.push((tx.summary(*wallet.get_network(), &*storage)?, entry_amounts(tx.get_entry(), &*storage)));
+    }
+    // Prices are fetched once the storage is unlocked
+    drop(storage);
+
+    let prices = match price_feed {
+        Some(feed) => feed.get_prices(lines.iter().flat_map(|(_, amounts)| amounts.iter().map(|(asset, _, _)| asset))).await,
+        None => HashMap::new()
+    };
+
+    manager.message(format!("Transactions (total {}) page {}/{}:", count, page, max_pages));
+    for (summary, amounts) in lines {
+        match entry_fiat_value(price_feed, &prices, &amounts) {
+            Some(value) => manager.message(format!("- {} ({})", summary, value)),
+            None => manager.message(format!("- {}", summary))
+        }
+    }
+
+    Ok(())
+}
+
+// Export the whole history in CSV or JSON format to a file
+// Block timestamps are fetched from the daemon if the wallet is online
+async fn export_history(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let format = arguments.get_value("format")?.to_string_value()?;
+    if format != "csv" && format != "json" {
+        return Err(CommandError::InvalidArgument(format!("Unknown format '{}', expected csv or json", format)));
+    }
+    let output = arguments.get_value("output")?.to_string_value()?;
+
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    let network = *wallet.get_network();
+
+    let mut transactions = {
+        let storage = wallet.get_storage().read().await;
+        storage.get_transactions_page(0, storage.count_transactions())?
+    };
+    // oldest first
+    transactions.reverse();
+
+    let mut timestamps: HashMap<u64, TimestampMillis> = HashMap::new();
+    {
+        let network_handler = wallet.get_network_handler().await.lock().await;
+        if let Some(network_handler) = network_handler.as_ref() {
+            manager.message("Fetching blocks timestamps from daemon...");
+            for tx in transactions.iter() {
+                let topoheight = tx.get_topoheight();
+                if timestamps.contains_key(&topoheight) {
+                    continue;
+                }
+
+                match network_handler.get_api().get_block_at_topoheight(topoheight).await {
+                    Ok(block) => {
+                        timestamps.insert(topoheight, block.timestamp);
+                    },
+                    Err(e) => manager.warn(format!("Couldn't fetch block at topoheight {}: {}", topoheight, e))
+                };
+            }
+        } else {
+            manager.warn("Wallet is offline, timestamps will not be exported");
+        }
+    }
+
+    let mut records = Vec::new();
+    {
+        let storage = wallet.get_storage().read().await;
+        for tx in transactions.iter() {
+            let timestamp = timestamps.get(&tx.get_topoheight()).copied();
+            records.extend(tx.to_export_records(network, timestamp, &storage)?);
+        }
+    }
+
+    let content = if format == "csv" {
+        let mut content = String::from(ExportRecord::CSV_HEADER);
+        for record in records.iter() {
+            content.push('\n');
+            content.push_str(&record.to_csv_line());
+        }
+        content
+    } else {
+        serde_json::to_string_pretty(&records).context("Error while serializing history")?
+    };
+
+    fs::write(&output, content).context("Error while writing history file")?;
+    manager.message(format!("{} records from {} transactions exported to {}", records.len(), transactions.len(), output));
+
+    Ok(())
+}
+
+// Show the status of the wallet: daemon connection, sync progress, pending txs and assets
+async fn ping_daemon(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+
+    let network_handler = wallet.get_network_handler().await.lock().await;
+    let network_handler = network_handler.as_ref().ok_or(CommandError::InvalidArgument("Wallet is in offline mode".to_owned()))?;
+    let health = network_handler.check_health().await.context("Error while checking daemon health")?;
+
+    manager.message(format!("Daemon: {}", network_handler.get_api().get_daemon_address()));
+    manager.message(format!("Latency: {} ms", health.latency.as_millis()));
+    manager.message(format!("Version: {}", health.version));
+    manager.message(format!("Network: {}", health.network));
+    manager.message(format!("Height: {}", health.height));
+    manager.message(format!("TopoHeight: {} (wallet: {})", health.topoheight, health.synced_topoheight));
+    manager.message(format!("Last block: {} seconds ago", health.top_block_age));
+
+    let warnings = health.warnings();
+    if warnings.is_empty() {
+        manager.message(format!("Health: {}", prompt::colorize_str(Color::Green, "OK")));
+    } else {
+        for warning in warnings {
+            manager.warn(warning);
+        }
+    }
+
+    Ok(())
+}
+
+async fn status(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+
+    let (synced_topoheight, assets) = {
+        let storage = wallet.get_storage().read().await;
+        (storage.get_synced_topoheight().unwrap_or(0), storage.get_assets().await?.len())
+    };
+
+    manager.message(format!("Network: {}", wallet.get_network()));
+    {
+        let network_handler = wallet.get_network_handler().await.lock().await;
+        if let Some(network_handler) = network_handler.as_ref() {
+            let api = network_handler.get_api();
+            match wallet.get_active_daemon().await {
+                Some(daemon) => manager.message(format!("Daemon: {} ({})", daemon, api.get_daemon_address())),
+                None => manager.message(format!("Daemon: {}", api.get_daemon_address()))
+            };
+
+            let fallbacks = wallet.get_daemon_addresses().await;
+            if fallbacks.len() > 1 {
+                manager.message(format!("Daemons configured: {}", fallbacks.join(", ")));
+            }
+
+            let running = network_handler.is_running().await;
+            manager.message(format!("Connection: {}", if running && api.is_online() {
+                prompt::colorize_str(Color::Green, "Online")
+            } else {
+                prompt::colorize_str(Color::Red, "Offline")
+            }));
+
+            match api.get_info().await {
+                Ok(info) => {
+                    let behind = info.topoheight.saturating_sub(synced_topoheight);
+                    manager.message(format!("TopoHeight: {}/{} ({})", synced_topoheight, info.topoheight, if behind == 0 {
+                        "synced".to_owned()
+                    } else {
+                        format!("{} behind", behind)
+                    }));
+                },
+                Err(e) => manager.error(format!("Couldn't retrieve daemon info: {}", e))
+            };
+
+            // daemon returns an error if we don't have any tx in mempool
+            let pending = api.get_mempool_cache(&wallet.get_address()).await
+                .map(|cache| cache.get_txs().len())
+                .unwrap_or(0);
+            manager.message(format!("Pending transactions: {}", pending));
+        } else {
+            manager.message(format!("Connection: {}", prompt::colorize_str(Color::Red, "Offline")));
+            manager.message(format!("TopoHeight: {}", synced_topoheight));
+        }
+    }
+
+    manager.message(format!("Nonce: {}", wallet.get_nonce().await));
+    manager.message(format!("Tracked assets: {}", assets));
+    Ok(())
+}
+
+// Set your wallet in online mode
+async fn online_mode(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    if wallet.is_online().await {
+        manager.error("Wallet is already online");
+    } else {
+        let daemon_address = if arguments.has_argument("daemon_address") {
+            arguments.get_value("daemon_address")?.to_string_value()?
+        } else {
+            DEFAULT_DAEMON_ADDRESS.to_string()
+        };
+
+        wallet.set_online_mode_with_fallbacks(vec![daemon_address]).await.context("Couldn't enable online mode")?;
+        manager.message("Wallet is now online");
+    }
+    Ok(())
+}
+
+// Pause or resume the syncing without losing the daemon configuration
+async fn sync(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let action = arguments.get_value("action")?.to_string_value()?;
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+
+    match action.as_str() {
+        "pause" => {
+            wallet.pause_sync().await.context("Error while pausing sync")?;
+            manager.message("Syncing paused, the wallet doesn't communicate with the daemon anymore");
+        },
+        "resume" => {
+            wallet.resume_sync().await.context("Error while resuming sync")?;
+            manager.message("Syncing resumed");
+        },
+        "status" => {
+            let status = if wallet.is_sync_paused() {
+                "paused"
+            } else if wallet.is_online().await {
+                "running"
+            } else {
+                "offline"
+            };
+            manager.message(format!("Syncing is {}", status));
+        },
+        _ => return Err(CommandError::InvalidArgument(format!("Unknown action '{}', expected pause, resume or status", action)))
+    }
+
+    Ok(())
+}
+
+// Set your wallet in offline mode
+async fn offline_mode(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    if !wallet.is_online().await {
+        manager.error("Wallet is already offline");
+    } else {
+        wallet.set_offline_mode().await.context("Error on offline mode")?;
+        manager.message("Wallet is now offline");
+    }
+    Ok(())
+}
+
+// Show current wallet address
+// Clear balances and transactions above the requested topoheight and sync again from it
+async fn rescan(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    let topoheight = if arguments.has_argument("topoheight") {
+        arguments.get_value("topoheight")?.to_number()?
+    } else {
+        0
+    };
+
+    let synced_topoheight = {
+        let storage = wallet.get_storage().read().await;
+        storage.get_synced_topoheight().unwrap_or(0)
+    };
+
+    if topoheight > synced_topoheight {
+        return Err(CommandError::InvalidArgument(format!("Topoheight must be less than or equal to the synced topoheight ({})", synced_topoheight)));
+    }
+
+    manager.message(format!("Rescanning from topoheight {} (synced topoheight: {})", topoheight, synced_topoheight));
+    if !manager.get_prompt().ask_confirmation().await.context("Error while confirming action")? {
+        manager.message("Rescan has been aborted");
+        return Ok(())
+    }
+
+    wallet.rescan(topoheight).await.context("error while restarting network handler")?;
+    manager.message("Network handler has been restarted!");
+    Ok(())
+}
+
+// Clean up the storage, the wallet must be offline to not be synced at the same time
+async fn maintenance(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    if wallet.is_online().await {
+        return Err(CommandError::InvalidArgument("Wallet must be in offline mode to run maintenance".to_owned()));
+    }
+
+    manager.message("Running storage maintenance...");
+    let report = {
+        let mut storage = wallet.get_storage().write().await;
+        storage.maintenance().context("Error while running maintenance")?
+    };
+
+    manager.message(format!("Orphaned transactions removed: {}", report.orphaned_transactions));
+    manager.message(format!("Orphaned labels removed: {}", report.orphaned_labels));
+    manager.message(format!("Executed pending transactions removed: {}", report.executed_pending_transactions));
+    if report.rebuilt_indexes {
+        manager.message("Transactions indexes have been rebuilt");
+    }
+    manager.message(format!("Storage size: {} bytes -> {} bytes ({} bytes reclaimed)", report.size_before, report.size_after, report.reclaimed_space()));
+    Ok(())
+}
+
+// Verify the integrity of the wallet and report any issue found
+async fn verify_wallet(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+
+    manager.message("Verifying wallet...");
+    let report = wallet.verify().await.context("Error while verifying wallet")?;
+    manager.message(format!("{} storage entries checked", report.checked_entries));
+
+    for entry in report.corrupted_entries.iter() {
+        manager.error(format!("Corrupted entry in {}", entry));
+    }
+
+    if report.key_mismatch {
+        manager.error("The stored private key doesn't match the wallet public key");
+    }
+
+    for asset in report.cache_mismatches.iter() {
+        manager.error(format!("Cached balance of {} doesn't match the stored one", asset));
+    }
+
+    if wallet.is_watch_only() {
+        manager.warn("Watch-only wallet: keys and balances can't be verified without the private key");
+    } else if !report.balance_mismatches.is_empty() {
+        let storage = wallet.get_storage().read().await;
+        for (asset, stored, computed) in report.balance_mismatches.iter() {
+            let decimals = storage.get_asset_decimals(asset).unwrap_or(COIN_DECIMALS);
+            let computed = if *computed < 0 {
+                format!("-{}", format_coin(computed.unsigned_abs() as u64, decimals))
+            } else {
+                format_coin(*computed as u64, decimals)
+            };
+            manager.warn(format!("Balance of {} is {} but history gives {}", asset, format_coin(*stored, decimals), computed));
+        }
+        manager.warn("This is expected if the history is incomplete (synced from a recent topoheight or pruned), otherwise use rescan");
+    }
+
+    if report.is_valid() {
+        manager.message("No corruption found");
+    } else {
+        manager.error("Wallet storage is corrupted, restore it from your seed or a backup");
+    }
+
+    Ok(())
+}
+
+// Show the seed after asking the password again
+async fn display_seed(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    let prompt =  manager.get_prompt();
+
+    let password = prompt.read_input("Password: ".into(), true)
+        .await.context("Error while reading password")?;
+    // check if password is valid
+    wallet.is_valid_password(password).await?;
+
+    // The BIP39 mnemonic is shown by default, a language can be selected to show the legacy seed
+    let mnemonic = wallet.get_mnemonic().await?;
+    let seed = match (mnemonic, arguments.has_argument("language")) {
+        (Some(mnemonic), false) => mnemonic,
+        (_, has_language) => {
+            let language = if has_language {
+                arguments.get_value("language")?.to_number()?
+            } else {
+                0
+            };
+            wallet.get_seed(language as usize)?
+        }
+    };
+    prompt.read_input(
+        prompt::colorize_string(Color::Green, &format!("Seed: {}\r\nPress ENTER to continue", seed)),
+        false
+    ).await.context("Error while printing seed")?;
+    Ok(())
+}
+
+async fn nonce(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    let nonce = wallet.get_nonce().await;
+    manager.message(format!("Nonce: {}", nonce));
+
+    // Show the on-chain nonce to detect stuck transactions
+    let network_handler = wallet.get_network_handler().await.lock().await;
+    if let Some(handler) = network_handler.as_ref().filter(|handler| handler.get_api().is_online()) {
+        match handler.get_api().get_nonce(&wallet.get_address()).await {
+            Ok(result) => {
+                manager.message(format!("On-chain nonce: {}", result.version.get_nonce()));
+                if result.version.get_nonce() < nonce {
+                    manager.message(format!("{} transaction(s) waiting to be executed", nonce - result.version.get_nonce()));
+                }
+            },
+            Err(e) => manager.error(format!("Couldn't retrieve on-chain nonce: {}", e))
+        };
+    }
+    Ok(())
+}
+
+async fn set_nonce(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+    let value = manager.get_prompt().read("New Nonce: ".to_string()).await
+        .context("Error while reading new nonce to set")?;
+
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    let mut storage = wallet.get_storage().write().await;
+    storage.set_nonce(value)?;
+    manager.message(format!("New nonce is: {}", value));
+    Ok(())
+}
+
+#[cfg(feature = "api_server")]
+async fn stop_api_server(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    wallet.stop_api_server().await.context("Error while stopping API Server")?;
+    manager.message("API Server has been stopped");
+    Ok(())
+}
+
+#[cfg(feature = "api_server")]
+async fn start_rpc_server(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    let bind_address = arguments.get_value("bind_address")?.to_string_value()?;
+    let username = arguments.get_value("username")?.to_string_value()?;
+    let password = arguments.get_value("password")?.to_string_value()?;
+
+    let auth_config = Some(AuthConfig {
+        username,
+        password
+    });
+
+    wallet.enable_rpc_server(bind_address, auth_config).await.context("Error while enabling RPC Server")?;
+    manager.message("RPC Server has been enabled");
+    Ok(())
+}
+
+#[cfg(feature = "api_server")]
+async fn start_xswd(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    match wallet.enable_xswd().await {
+        Ok(receiver) => {
+            let prompt = manager.get_prompt().clone();
+            tokio::spawn(xswd_handler(receiver, prompt));
+            manager.message("XSWD Server has been enabled");
+        },
+        Err(e) => manager.error(format!("Error while enabling XSWD Server: {}", e))
+    };
+
+    Ok(())
+}
+
+// Manage the tokens giving a scoped access to the RPC Server
+#[cfg(feature = "api_server")]
+async fn api_token(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    let action = arguments.get_value("action")?.to_string_value()?;
+
+    match action.as_str() {
+        "create" => {
+            let name = arguments.get_value("name")?.to_string_value()?;
+            let scope = arguments.get_value("scope")?.to_string_value()?;
+            let scope = TokenScope::from_str(&scope)
+                .ok_or_else(|| CommandError::InvalidArgument(format!("Unknown scope '{}', expected read_only, transfer or admin", scope)))?;
+
+            let spend_limit = if arguments.has_argument("limit") {
+                if scope != TokenScope::Transfer {
+                    return Err(CommandError::InvalidArgument("A spend limit can only be set on a transfer token".to_owned()));
+                }
+                let limit = arguments.get_value("limit")?.to_string_value()?;
+                Some(from_coin(limit, COIN_DECIMALS).context("Invalid spend limit")?)
+            } else {
+                None
+            };
+
+            let (token, secret) = ApiToken::generate(scope, spend_limit);
+            let mut storage = wallet.get_storage().write().await;
+            storage.add_api_token(&name, &token)?;
+            manager.message(format!("API token '{}' has been created with scope {}", name, scope.as_str()));
+            manager.message(format!("Token: {}", secret));
+            manager.warn("Save it now, it can't be displayed again");
+        },
+        "revoke" => {
+            let name = arguments.get_value("name")?.to_string_value()?;
+            let mut storage = wallet.get_storage().write().await;
+            storage.remove_api_token(&name)?;
+            manager.message(format!("API token '{}' has been revoked", name));
+        },
+        "list" => {
+            let storage = wallet.get_storage().read().await;
+            let tokens = storage.get_api_tokens()?;
+            if tokens.is_empty() {
+                manager.message("No API tokens");
+                return Ok(())
+            }
+
+            manager.message(format!("API tokens ({}):", tokens.len()));
+            for (name, token) in tokens {
+                let limit = match token.get_spend_limit() {
+                    Some(limit) => format!(", spent {} / {} XELIS", format_xelis(token.get_spent()), format_xelis(limit)),
+                    None => String::new()
+                };
+                manager.message(format!("- {}: {}{}", name, token.get_scope().as_str(), limit));
+            }
+        },
+        _ => return Err(CommandError::InvalidArgument(format!("Unknown action '{}', expected create, revoke or list", action)))
+    }
+
+    Ok(())
+}
+
+// List the applications connected through XSWD and manage their permissions
+#[cfg(feature = "api_server")]
+async fn xswd(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let action = arguments.get_value("action")?.to_string_value()?;
     let context = manager.get_context().lock()?;
     let wallet: &Arc<Wallet> = context.get()?;
+    let lock = wallet.get_api_server().lock().await;
+    let handler = match lock.as_ref() {
+        Some(APIServer::XSWD(xswd)) => xswd.get_handler(),
+        _ => return Err(CommandError::InvalidArgument("XSWD Server is not running".to_owned()))
+    };
+
+    match action.as_str() {
+        "list" => {
+            let applications = handler.get_applications().read().await;
+            if applications.is_empty() {
+                manager.message("No application connected");
+                return Ok(())
+            }
+
+            manager.message(format!("{} application(s) connected:", applications.len()));
+            for app in applications.values() {
+                manager.message(format!("- {} ({}): {}", app.get_name(), app.get_id(), app.get_url().as_deref().unwrap_or("no URL")));
+                let permissions = app.get_permissions().lock().await;
+                for (method, permission) in permissions.iter() {
+                    let permission = match permission {
+                        Permission::Ask => "ask",
+                        Permission::AcceptAlways => "always allowed",
+                        Permission::DenyAlways => "always denied"
+                    };
+                    manager.message(format!("    {}: {}", method, permission));
+                }
+            }
+        },
+        "revoke" | "disconnect" => {
+            if !arguments.has_argument("app_id") {
+                return Err(CommandError::ExpectedRequiredArg("app_id".to_owned()))
+            }
+            let app_id = arguments.get_value("app_id")?.to_string_value()?;
+
+            if action == "disconnect" {
+                if !handler.disconnect_application(&app_id).await {
+                    return Err(CommandError::InvalidArgument(format!("No application connected with ID {}", app_id)))
+                }
+                manager.message(format!("Application {} has been disconnected", app_id));
+                return Ok(())
+            }
+
+            let app = handler.get_application_by_id(&app_id).await
+                .ok_or_else(|| CommandError::InvalidArgument(format!("No application connected with ID {}", app_id)))?;
+            let mut permissions = app.get_permissions().lock().await;
+            // Revoked methods go back to asking the user on the next request
+            if arguments.has_argument("method") {
+                let method = arguments.get_value("method")?.to_string_value()?;
+                if permissions.remove(&method).is_none() {
+                    return Err(CommandError::InvalidArgument(format!("No permission set for method {}", method)))
+                }
+                manager.message(format!("Permission for {} has been revoked from {}", method, app.get_name()));
+            } else {
+                permissions.clear();
+                manager.message(format!("All permissions have been revoked from {}", app.get_name()));
+            }
+        },
+        _ => return Err(CommandError::InvalidArgument(format!("Unknown action '{}', expected list, revoke or disconnect", action)))
+    }
+
+    Ok(())
+}
 
+// Pay all the rows of a CSV file (address,amount[,asset])
+// Every row is validated before sending anything, then rows are grouped in transactions of up to MAX_TRANSFER_COUNT transfers
+async fn pay_csv(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let file = arguments.get_value("file")?.to_string_value()?;
     let prompt = manager.get_prompt();
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
 
-    let old_password = prompt.read_input(prompt::colorize_str(Color::BrightRed, "Current Password: "), true)
-        .await
-        .context("Error while asking old password")?;
+    if !wallet.is_online().await {
+        return Err(CommandError::InvalidArgument("Wallet must be online to pay a CSV file".to_owned()));
+    }
 
-    let new_password = prompt.read_input(prompt::colorize_str(Color::BrightRed, "New Password: "), true)
-        .await
-        .context("Error while asking new password")?;
+    let content = fs::read_to_string(&file).with_context(|| format!("Error while reading {}", file))?;
+
+    // Parse and validate all the rows
+    let mut rows = Vec::new();
+    let mut errors = Vec::new();
+    let mut totals: HashMap<Hash, u64> = HashMap::new();
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim();
+        let row = i + 1;
+        // Skip empty lines and the optional header
+        if line.is_empty() || (i == 0 && line.to_lowercase().starts_with("address")) {
+            continue;
+        }
 
-    manager.message("Changing password...");
-    wallet.set_password(old_password, new_password).await?;
-    manager.message("Your password has been changed!");
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        if fields.len() < 2 || fields.len() > 3 {
+            errors.push(format!("Row {}: expected address,amount[,asset]", row));
+            continue;
+        }
+
+        let address = match parse_address_or_contact(wallet, fields[0]).await {
+            Ok(address) => address,
+            Err(e) => {
+                errors.push(format!("Row {}: {}", row, e));
+                continue;
+            }
+        };
+
+        if address.get_network() != *wallet.get_network() {
+            errors.push(format!("Row {}: address is for {} but this wallet is running on {}", row, address.get_network(), wallet.get_network()));
+            continue;
+        }
+
+        let asset = match fields.get(2).filter(|v| !v.is_empty()) {
+            Some(value) => match Hash::from_hex(value.to_string()) {
+                Ok(asset) => asset,
+                Err(_) => {
+                    errors.push(format!("Row {}: invalid asset '{}'", row, value));
+                    continue;
+                }
+            },
+            None => XELIS_ASSET
+        };
+
+        let decimals = {
+            let storage = wallet.get_storage().read().await;
+            match storage.get_asset_decimals(&asset) {
+                Ok(decimals) => decimals,
+                Err(_) => {
+                    errors.push(format!("Row {}: asset {} is not tracked by this wallet", row, asset));
+                    continue;
+                }
+            }
+        };
+
+        let amount = match from_coin(fields[1], decimals) {
+            Some(amount) if amount > 0 => amount,
+            _ => {
+                errors.push(format!("Row {}: invalid amount '{}'", row, fields[1]));
+                continue;
+            }
+        };
+
+        let total = totals.entry(asset.clone()).or_insert(0);
+        *total = total.saturating_add(amount);
+
+        rows.push((row, TransferBuilder {
+            destination: address,
+            amount,
+            asset,
+            extra_data: None
+        }));
+    }
+
+    if !errors.is_empty() {
+        for error in errors {
+            manager.error(error);
+        }
+        return Err(CommandError::InvalidArgument("Invalid CSV file, nothing has been sent".to_owned()));
+    }
+
+    if rows.is_empty() {
+        manager.message("No payments found in file");
+        return Ok(())
+    }
+
+    // Verify that we have enough funds for all the payments
+    {
+        let storage = wallet.get_storage().read().await;
+        for (asset, total) in totals.iter() {
+            let balance = storage.get_unconfirmed_balance_for(asset).await.map(|b| b.amount).unwrap_or(0);
+            let decimals = storage.get_asset_decimals(asset).unwrap_or(COIN_DECIMALS);
+            if *total > balance {
+                return Err(CommandError::InvalidArgument(format!("Not enough funds for asset {}: need {} but have {}", asset, format_coin(*total, decimals), format_coin(balance, decimals))));
+            }
+            manager.message(format!("Total for asset {}: {}", asset, format_coin(*total, decimals)));
+        }
+    }
+
+    let chunks = rows.len().div_ceil(MAX_TRANSFER_COUNT);
+    manager.message(format!("{} payments will be sent in {} transaction(s)", rows.len(), chunks));
+    if !prompt.ask_confirmation().await.context("Error while confirming action")? {
+        manager.message("Payments have been aborted");
+        return Ok(())
+    }
+
+    let (mut succeeded, mut failed) = (0, 0);
+    for chunk in rows.chunks(MAX_TRANSFER_COUNT) {
+        let transfers = chunk.iter().map(|(_, transfer)| transfer.clone()).collect();
+        let result = {
+            let mut storage = wallet.get_storage().write().await;
+            match wallet.create_transaction_with_storage(&mut storage, TransactionTypeBuilder::Transfers(transfers), FeeBuilder::default()).await {
+                Ok((state, tx)) => wallet.submit_transaction_with_state(&mut storage, state, &tx).await.map(|_| tx.hash()),
+                Err(e) => Err(e)
+            }
+        };
+
+        match result {
+            Ok(hash) => {
+                succeeded += chunk.len();
+                for (row, transfer) in chunk {
+                    manager.message(format!("Row {}: sent to {} in transaction {}", row, transfer.destination, hash));
+                }
+            },
+            Err(e) => {
+                failed += chunk.len();
+                for (row, transfer) in chunk {
+                    manager.error(format!("Row {}: payment to {} failed: {}", row, transfer.destination, e));
+                }
+            }
+        }
+    }
+
+    manager.message(format!("Payments done: {} succeeded, {} failed", succeeded, failed));
     Ok(())
 }
 
-// Create a new transfer to a specified address
-async fn transfer(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+// Export a transfer to a file so it can be signed by an offline wallet
+// Can be used from a watch-only wallet
+async fn build_unsigned_tx(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let output = arguments.get_value("output")?.to_string_value()?;
     let prompt = manager.get_prompt();
     let context = manager.get_context().lock()?;
     let wallet: &Arc<Wallet> = context.get()?;
 
-    // read address
     let str_address = prompt.read_input(
-        prompt::colorize_str(Color::Green, "Address: "),
+        prompt::colorize_str(Color::Green, "Address or contact name: "),
         false
     ).await.context("Error while reading address")?;
-    let address = Address::from_string(&str_address).context("Invalid address")?;
+    let address = parse_address_or_contact(wallet, &str_address).await?;
 
     let asset = prompt.read_hash(
         prompt::colorize_str(Color::Green, "Asset (default XELIS): ")
-    ).await.ok();
-
-    let asset = asset.unwrap_or(XELIS_ASSET);
+    ).await.unwrap_or(XELIS_ASSET);
 
-    let (max_balance, decimals) = {
+    let decimals = {
         let storage = wallet.get_storage().read().await;
-        let balance = storage.get_plaintext_balance_for(&asset).await.unwrap_or(0);
-        let decimals = storage.get_asset_decimals(&asset).unwrap_or(COIN_DECIMALS);
-        (balance, decimals)
+        storage.get_asset_decimals(&asset).unwrap_or(COIN_DECIMALS)
     };
 
-    // read amount
-    let float_amount: f64 = prompt.read(
-        prompt::colorize_string(Color::Green, &format!("Amount (max: {}): ", format_coin(max_balance, decimals)))
+    let str_amount = prompt.read_input(
+        prompt::colorize_str(Color::Green, "Amount: "),
+        false
     ).await.context("Error while reading amount")?;
+    let amount = from_coin(str_amount.as_str(), decimals)
+        .ok_or_else(|| CommandError::InvalidArgument(format!("Invalid amount '{}'", str_amount)))?;
 
-    let amount = (float_amount * 10u32.pow(decimals as u32) as f64) as u64;
-    manager.message(format!("Sending {} of {} to {}", format_coin(amount, decimals), asset, address.to_string()));
+    if amount == 0 {
+        return Err(CommandError::InvalidArgument("Amount cannot be zero".to_owned()));
+    }
 
-    if !prompt.ask_confirmation().await.context("Error while confirming action")? {
-        manager.message("Transaction has been aborted");
-        return Ok(())
+    let transfer = TransferBuilder {
+        destination: address,
+        amount,
+        asset,
+        extra_data: None
+    };
+
+    let unsigned = wallet.build_unsigned_transaction(TransactionTypeBuilder::Transfers(vec![transfer]), FeeBuilder::default()).await
+        .context("Error while building unsigned transaction")?;
+
+    let content = serde_json::to_string_pretty(&unsigned).context("Error while serializing unsigned transaction")?;
+    fs::write(&output, content).with_context(|| format!("Error while writing unsigned transaction to {}", output))?;
+
+    manager.message(format!("Unsigned transaction with nonce {} written to {}", unsigned.nonce, output));
+    manager.message("Sign it on your offline wallet using 'sign_tx' then broadcast it using 'broadcast_tx'");
+    Ok(())
+}
+
+// Show the transfers or burn of a transaction not yet built
+async fn display_tx_type(manager: &CommandManager, wallet: &Wallet, tx_type: &TransactionTypeBuilder) {
+    let storage = wallet.get_storage().read().await;
+    match tx_type {
+        TransactionTypeBuilder::Transfers(transfers) => {
+            for transfer in transfers {
+                let decimals = storage.get_asset_decimals(&transfer.asset).unwrap_or(COIN_DECIMALS);
+                manager.message(format!("Transfer {} of {} to {}", format_coin(transfer.amount, decimals), transfer.asset, transfer.destination));
+            }
+        },
+        TransactionTypeBuilder::Burn(payload) => {
+            let decimals = storage.get_asset_decimals(&payload.asset).unwrap_or(COIN_DECIMALS);
+            manager.message(format!("Burn {} of {}", format_coin(payload.amount, decimals), payload.asset));
+        },
+        TransactionTypeBuilder::MultiSig(multisig) => display_multisig_setup(manager, multisig.threshold, &multisig.participants)
+    };
+}
+
+// Manage the transactions saved to be reviewed and sent later
+// This allows one person to prepare a transaction and another one to approve it
+async fn draft(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let prompt = manager.get_prompt();
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    let action = arguments.get_value("action")?.to_string_value()?;
+
+    match action.as_str() {
+        "save" => {
+            let name = arguments.get_value("name")?.to_string_value()?;
+            if wallet.get_storage().read().await.has_draft(&name)? {
+                return Err(CommandError::InvalidArgument(format!("Draft '{}' already exists", name)));
+            }
+
+            let str_address = prompt.read_input(
+                prompt::colorize_str(Color::Green, "Address or contact name: "),
+                false
+            ).await.context("Error while reading address")?;
+            let address = parse_address_or_contact(wallet, &str_address).await?;
+
+            let asset = prompt.read_hash(
+                prompt::colorize_str(Color::Green, "Asset (default XELIS): ")
+            ).await.unwrap_or(XELIS_ASSET);
+
+            let decimals = {
+                let storage = wallet.get_storage().read().await;
+                storage.get_asset_decimals(&asset).unwrap_or(COIN_DECIMALS)
+            };
+
+            let str_amount = prompt.read_input(
+                prompt::colorize_str(Color::Green, "Amount: "),
+                false
+            ).await.context("Error while reading amount")?;
+            let amount = from_coin(str_amount.as_str(), decimals)
+                .ok_or_else(|| CommandError::InvalidArgument(format!("Invalid amount '{}'", str_amount)))?;
+
+            if amount == 0 {
+                return Err(CommandError::InvalidArgument("Amount cannot be zero".to_owned()));
+            }
+
+            let tx_type = TransactionTypeBuilder::Transfers(vec![TransferBuilder {
+                destination: address,
+                amount,
+                asset,
+                extra_data: None
+            }]);
+            let draft = DraftTransaction::new(tx_type, FeeBuilder::default(), get_current_time_in_seconds());
+
+            let mut storage = wallet.get_storage().write().await;
+            storage.add_draft(&name, &draft)?;
+            manager.message(format!("Draft '{}' has been saved, use 'draft send {}' to send it", name, name));
+        },
+        "show" => {
+            let name = arguments.get_value("name")?.to_string_value()?;
+            let draft = wallet.get_storage().read().await.get_draft(&name)?;
+
+            manager.message(format!("Draft '{}' created at {}", name, draft.get_created_at()));
+            display_tx_type(manager, wallet, draft.get_tx_type()).await;
+            match wallet.estimate_fees(draft.get_tx_type().clone()).await {
+                Ok(fee) => manager.message(format!("Estimated fees: {} XELIS", format_xelis(fee))),
+                Err(e) => manager.warn(format!("Couldn't estimate fees: {}", e))
+            };
+        },
+        "send" => {
+            let name = arguments.get_value("name")?.to_string_value()?;
+            let draft = wallet.get_storage().read().await.get_draft(&name)?;
+
+            manager.message(format!("Sending draft '{}':", name));
+            display_tx_type(manager, wallet, draft.get_tx_type()).await;
+            if !prompt.ask_confirmation().await.context("Error while confirming action")? {
+                manager.message("Transaction has been aborted");
+                return Ok(())
+            }
+
+            // The draft is kept if the daemon rejects the transaction
+            let (tx_type, fee) = draft.consume();
+            let mut storage = wallet.get_storage().write().await;
+            let (mut state, tx) = wallet.create_transaction_with_nonce(&mut storage, tx_type, fee, None).await
+                .context("Error while creating transaction")?;
+            manager.message(format!("Transaction hash: {}", tx.hash()));
+
+            if wallet.is_online().await {
+                wallet.submit_transaction_with_state(&mut storage, state, &tx).await
+                    .context("Couldn't submit transaction")?;
+                manager.message("Transaction has been accepted in mempool!");
+            } else {
+                state.apply_changes(&mut storage).await
+                    .context("Error while applying changes to storage")?;
+
+                manager.warn("You are currently offline, transaction cannot be send automatically. Please send it manually to the network.");
+                manager.message(format!("Transaction in hex format: {}", tx.to_hex()));
+            }
+
+            storage.remove_draft(&name)?;
+            manager.message(format!("Draft '{}' has been sent and removed", name));
+        },
+        "remove" => {
+            let name = arguments.get_value("name")?.to_string_value()?;
+            let mut storage = wallet.get_storage().write().await;
+            storage.remove_draft(&name)?;
+            manager.message(format!("Draft '{}' has been removed", name));
+        },
+        "list" => {
+            let storage = wallet.get_storage().read().await;
+            let drafts = storage.get_drafts()?;
+            if drafts.is_empty() {
+                manager.message("No drafts saved");
+                return Ok(())
+            }
+
+            manager.message(format!("Drafts ({}):", drafts.len()));
+            for (name, draft) in drafts {
+                let count = match draft.get_tx_type() {
+                    TransactionTypeBuilder::Transfers(transfers) => format!("{} transfer(s)", transfers.len()),
+                    TransactionTypeBuilder::Burn(_) => "burn".to_owned(),
+                    TransactionTypeBuilder::MultiSig(_) => "multisig setup".to_owned()
+                };
+                manager.message(format!("- {}: {} created at {}", name, count, draft.get_created_at()));
+            }
+        },
+        _ => return Err(CommandError::InvalidArgument(format!("Unknown action '{}', expected save, show, send, remove or list", action)))
     }
 
-    manager.message("Building transaction...");
+    Ok(())
+}
+
+// Manage the payments saved to be sent again
+async fn template(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let prompt = manager.get_prompt();
+    let context = manager.get_context().lock()?;
+    let wallet: &Arc<Wallet> = context.get()?;
+    let action = arguments.get_value("action")?.to_string_value()?;
+
+    match action.as_str() {
+        "save" => {
+            let name = arguments.get_value("name")?.to_string_value()?;
+            if wallet.get_storage().read().await.has_template(&name)? {
+                manager.warn(format!("Template '{}' already exists, it will be replaced", name));
+            }
+
+            let str_address = prompt.read_input(
+                prompt::colorize_str(Color::Green, "Address or contact name: "),
+                false
+            ).await.context("Error while reading address")?;
+            let address = parse_address_or_contact(wallet, &str_address).await?;
+
+            let asset = prompt.read_hash(
+                prompt::colorize_str(Color::Green, "Asset (default XELIS): ")
+            ).await.unwrap_or(XELIS_ASSET);
+
+            let decimals = {
+                let storage = wallet.get_storage().read().await;
+                storage.get_asset_decimals(&asset).unwrap_or(COIN_DECIMALS)
+            };
+
+            let str_amount = prompt.read_input(
+                prompt::colorize_str(Color::Green, "Amount: "),
+                false
+            ).await.context("Error while reading amount")?;
+            let amount = from_coin(str_amount.as_str(), decimals)
+                .ok_or_else(|| CommandError::InvalidArgument(format!("Invalid amount '{}'", str_amount)))?;
+
+            if amount == 0 {
+                return Err(CommandError::InvalidArgument("Amount cannot be zero".to_owned()));
+            }
+
+            let memo = prompt.read_input(
+                prompt::colorize_str(Color::Green, "Memo (optional): "),
+                false
+            ).await.context("Error while reading memo")?;
+            if memo.len() > u8::MAX as usize {
+                return Err(CommandError::InvalidArgument(format!("Memo is too long, maximum is {} bytes", u8::MAX)));
+            }
+            let memo = if memo.is_empty() { None } else { Some(memo) };
+
+            let template = TransferTemplate::new(address, asset, amount, memo);
+            // Verify that the memo fits in a transfer before saving it
+            if let Some(extra_data) = template.to_transfer().extra_data {
+                if extra_data.size() > EXTRA_DATA_LIMIT_SIZE {
+                    return Err(CommandError::InvalidArgument(format!("Memo is too big, maximum is {} bytes", EXTRA_DATA_LIMIT_SIZE)));
+                }
+            }
+
+            let mut storage = wallet.get_storage().write().await;
+            storage.set_template(&name, &template)?;
+            manager.message(format!("Template '{}' has been saved, use 'template apply {}' to send it", name, name));
+        },
+        "apply" => {
+            let name = arguments.get_value("name")?.to_string_value()?;
+            let template = wallet.get_storage().read().await.get_template(&name)?;
+
+            let decimals = {
+                let storage = wallet.get_storage().read().await;
+                storage.get_asset_decimals(template.get_asset()).unwrap_or(COIN_DECIMALS)
+            };
+            manager.message(format!("Sending {} of {} to {}", format_coin(template.get_amount(), decimals), template.get_asset(), template.get_destination()));
+            if let Some(memo) = template.get_memo() {
+                manager.message(format!("Memo: {}", memo));
+            }
+
+            if !prompt.ask_confirmation().await.context("Error while confirming action")? {
+                manager.message("Transaction has been aborted");
+                return Ok(())
+            }
 
-    let transfer = TransferBuilder {
-        destination: address,
-        amount,
-        asset,
-        extra_data: None
-    };
-    let tx = wallet.create_transaction(TransactionTypeBuilder::Transfers(vec![transfer]), FeeBuilder::default()).await
-        .context("Error while creating transaction")?;
+            broadcast_tx(wallet, manager, TransactionTypeBuilder::Transfers(vec![template.to_transfer()]), FeeBuilder::default(), None).await?;
+        },
+        "remove" => {
+            let name = arguments.get_value("name")?.to_string_value()?;
+            let mut storage = wallet.get_storage().write().await;
+            storage.remove_template(&name)?;
+            manager.message(format!("Template '{}' has been removed", name));
+        },
+        "list" => {
+            let storage = wallet.get_storage().read().await;
+            let templates = storage.get_templates()?;
+            if templates.is_empty() {
+                manager.message("No templates saved");
+                return Ok(())
+            }
+
+            manager.message(format!("Templates ({}):", templates.len()));
+            for (name, template) in templates {
+                let decimals = storage.get_asset_decimals(template.get_asset()).unwrap_or(COIN_DECIMALS);
+                manager.message(format!("- {}: {} of {} to {}", name, format_coin(template.get_amount(), decimals), template.get_asset(), template.get_destination()));
+            }
+        },
+        _ => return Err(CommandError::InvalidArgument(format!("Unknown action '{}', expected save, apply, remove or list", action)))
+    }
 
-    broadcast_tx(wallet, manager, tx).await;
     Ok(())
 }
 
-
-// Send the whole balance to a specified address
-async fn transfer_all(manager: &CommandManager, mut args: ArgumentManager) -> Result<(), CommandError> {
+// Sign a transaction exported by 'build_unsigned_tx' and write it in hex format
+async fn sign_tx(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let input = arguments.get_value("input")?.to_string_value()?;
+    let output = arguments.get_value("output")?.to_string_value()?;
     let prompt = manager.get_prompt();
     let context = manager.get_context().lock()?;
     let wallet: &Arc<Wallet> = context.get()?;
 
-    // read address
-    let str_address = prompt.read_input(
-        prompt::colorize_str(Color::Green, "Address: "),
-        false
-    ).await.context("Error while reading address")?;
-    let address = Address::from_string(&str_address).context("Invalid address")?;
-
-    let mut asset = args.get_value("asset").and_then(|v| v.to_hash()).ok();
-    if asset.is_none() {
-        asset = prompt.read_hash(
-           prompt::colorize_str(Color::Green, "Asset (default XELIS): ")
-       ).await.ok();
-    }
-
-    let asset = asset.unwrap_or(XELIS_ASSET);
-    let (mut amount, decimals) = {
-        let storage = wallet.get_storage().read().await;
-        let amount = storage.get_plaintext_balance_for(&asset).await.unwrap_or(0);
-        let decimals = storage.get_asset_decimals(&asset).unwrap_or(COIN_DECIMALS);
-        (amount, decimals)
-    };
-
-    let transfer = TransferBuilder {
-        destination: address.clone(),
-        amount,
-        asset: asset.clone(),
-        extra_data: None
-    };
-    let tx_type = TransactionTypeBuilder::Transfers(vec![transfer]);
-    let estimated_fees = wallet.estimate_fees(tx_type.clone()).await.context("Error while estimating fees")?;
+    let content = fs::read_to_string(&input).with_context(|| format!("Error while reading {}", input))?;
+    let unsigned: UnsignedTransaction = serde_json::from_str(&content)
+        .map_err(|e| CommandError::InvalidArgument(format!("Invalid unsigned transaction: {}", e)))?;
 
-    if asset == XELIS_ASSET {
-        amount -= estimated_fees;
-    }
+    // Show what is going to be signed
+    manager.message(format!("Nonce: {}", unsigned.nonce));
+    display_tx_type(manager, wallet, &unsigned.tx_type).await;
 
-    manager.message(format!("Sending {} of {} to {} (fees: {})", format_coin(amount, decimals), asset, address.to_string(), format_xelis(estimated_fees)));
-    
     if !prompt.ask_confirmation().await.context("Error while confirming action")? {
-        manager.message("Transaction has been aborted");
+        manager.message("Signing has been aborted");
         return Ok(())
     }
 
-    let transfer = TransferBuilder {
-        destination: address,
-        amount,
-        asset,
-        extra_data: None
-    };
-    let tx_type = TransactionTypeBuilder::Transfers(vec![transfer]);
-
-    manager.message("Building transaction...");
-
-    let tx = wallet.create_transaction(tx_type, FeeBuilder::default()).await
-        .context("Error while creating transaction")?;
+    let tx = wallet.sign_unsigned_transaction(unsigned).await
+        .context("Error while signing transaction")?;
 
-    broadcast_tx(wallet, manager, tx).await;
+    fs::write(&output, tx.to_hex()).with_context(|| format!("Error while writing signed transaction to {}", output))?;
+    manager.message(format!("Transaction {} signed and written to {}", tx.hash(), output));
     Ok(())
 }
 
-async fn burn(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
-    let amount = arguments.get_value("amount")?.to_number()?;
-    let asset = arguments.get_value("asset")?.to_hash()?;
+// Broadcast a transaction signed by 'sign_tx'
+async fn broadcast_tx_file(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let input = arguments.get_value("input")?.to_string_value()?;
     let context = manager.get_context().lock()?;
     let wallet: &Arc<Wallet> = context.get()?;
-    {
-        let storage = wallet.get_storage().read().await;
-        let decimals = storage.get_asset_decimals(&asset).unwrap_or(COIN_DECIMALS);
 
-        manager.message(format!("Burning {} of {}", format_coin(amount, decimals), asset));
-    }
-    let payload = BurnPayload {
-        amount,
-        asset
-    };
-    let tx = wallet.create_transaction(TransactionTypeBuilder::Burn(payload), FeeBuilder::Multiplier(1f64)).await
-        .context("Error while creating transaction")?;
+    let content = fs::read_to_string(&input).with_context(|| format!("Error while reading {}", input))?;
+    let tx = Transaction::from_hex(content.trim().to_owned())
+        .map_err(|e| CommandError::InvalidArgument(format!("Invalid transaction: {}", e)))?;
 
-    broadcast_tx(wallet, manager, tx).await;
-    Ok(())
-}
+    manager.message(format!("Transaction hash: {}", tx.hash()));
+    wallet.submit_signed_transaction(&tx).await
+        .context("Couldn't submit transaction")?;
 
-// Show current wallet address
-async fn display_address(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
-    let context = manager.get_context().lock()?;
-    let wallet: &Arc<Wallet> = context.get()?;
-    manager.message(format!("Wallet address: {}", wallet.get_address()));
+    manager.message("Transaction has been accepted in mempool!");
     Ok(())
 }
 
-// Show current balance for specified asset or list all non-zero balances
-async fn balance(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
-    let context = manager.get_context().lock()?;
-    let wallet: &Arc<Wallet> = context.get()?;
-    let storage = wallet.get_storage().read().await;
+// Build the transaction and let the daemon verify it without broadcasting it
+// Nothing is changed in the wallet
+async fn simulate_tx(wallet: &Wallet, manager: &CommandManager, tx_type: TransactionTypeBuilder, fee: FeeBuilder, nonce: Option<u64>) -> Result<(), CommandError> {
+    manager.message("Building transaction for a dry run...");
 
-    if arguments.has_argument("asset") {
-        let asset = arguments.get_value("asset")?.to_hash()?;
-        let balance = storage.get_plaintext_balance_for(&asset).await.unwrap_or(0);
-        let decimals = storage.get_asset_decimals(&asset).unwrap_or(0);
-        manager.message(format!("Balance for asset {}: {}", asset, format_coin(balance, decimals)));
-    } else {
-        for (asset, decimals) in storage.get_assets_with_decimals().await? {
-            let balance = storage.get_plaintext_balance_for(&asset).await.unwrap_or(0);
-            if balance > 0 {
-                manager.message(format!("Balance for asset {}: {}", asset, format_coin(balance, decimals)));
-            }
-        }
-    }
+    let tx = {
+        let mut storage = wallet.get_storage().write().await;
+        let (_, tx) = wallet.create_transaction_with_nonce(&mut storage, tx_type, fee, nonce).await
+            .context("Error while creating transaction")?;
+        tx
+    };
 
+    manager.message(format!("Transaction hash: {}", tx.hash()));
+    manager.message(format!("Size: {} bytes, fees: {} XELIS, nonce: {}", tx.size(), format_xelis(tx.get_fee()), tx.get_nonce()));
+
+    wallet.simulate_transaction(&tx).await
+        .context("Transaction would be rejected")?;
+
+    manager.message("Transaction would be accepted by the daemon, it has not been sent");
     Ok(())
 }
 
-// Show all transactions
-const TXS_PER_PAGE: usize = 10;
-async fn history(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
-    let page = if arguments.has_argument("page") {
-        arguments.get_value("page")?.to_number()? as usize
-    } else {
-        1
-    };
+// Build the transaction and broadcast it if possible
+// Nonce and balances are updated locally only if the daemon accepted the transaction in its mempool
+// A nonce can be set to replace a previous transaction that got stuck
+async fn broadcast_tx(wallet: &Wallet, manager: &CommandManager, tx_type: TransactionTypeBuilder, fee: FeeBuilder, nonce: Option<u64>) -> Result<(), CommandError> {
+    if let Some(signer) = wallet.get_signer().await {
+        return broadcast_tx_with_signer(wallet, manager, signer.get_name(), tx_type, fee, nonce).await
+    }
 
-    if page == 0 {
-        return Err(CommandError::InvalidArgument("Page must be greater than 0".to_string()));
+    // Transactions of a multisig account must be signed by its participants first
+    if wallet.is_online().await {
+        if let Some(setup) = wallet.get_multisig_setup().await.context("Error while fetching multisig setup")? {
+            if nonce.is_some() {
+                manager.warn("Nonce can't be set for a multisig transaction, the next one is used");
+            }
+            manager.message(format!("Your account is a {}-of-{} multisig, the transaction must be signed by its participants", setup.threshold, setup.participants.len()));
+            return export_multisig_tx(wallet, manager, tx_type, fee).await
+        }
     }
 
-    let context = manager.get_context().lock()?;
-    let wallet: &Arc<Wallet> = context.get()?;
-    let storage = wallet.get_storage().read().await;
-    let mut transactions = storage.get_transactions()?;
+    manager.message("Building transaction...");
 
-    // if we don't have any txs, no need proceed further
-    if transactions.is_empty() {
-        manager.message("No transactions available");
-        return Ok(())
-    }
+    let mut storage = wallet.get_storage().write().await;
+    let (mut state, tx) = wallet.create_transaction_with_nonce(&mut storage, tx_type, fee, nonce).await
+        .context("Error while creating transaction")?;
 
-    // desc ordered
-    transactions.sort_by(|a, b| b.get_topoheight().cmp(&a.get_topoheight()));
-    let mut max_pages = transactions.len() / TXS_PER_PAGE;
-    if transactions.len() % TXS_PER_PAGE != 0 {
-        max_pages += 1;
-    }
+    let tx_hash = tx.hash();
+    manager.message(format!("Transaction hash: {}", tx_hash));
 
-    if page > max_pages {
-        return Err(CommandError::InvalidArgument(format!("Page must be less than maximum pages ({})", max_pages - 1)));
-    }
+    if wallet.is_online().await {
+        manager.message("Submitting transaction to the daemon...");
+        match wallet.submit_transaction_with_state(&mut storage, state, &tx).await {
+            Ok(()) => manager.message("Transaction has been accepted in mempool!"),
+            Err(e) => {
+                manager.error(format!("Couldn't submit transaction: {}", e));
+                manager.error("You can try to rescan your balance with the command 'rescan'");
+            }
+        }
+    } else {
+        // Apply the changes as the user will broadcast it himself
+        state.apply_changes(&mut storage).await
+            .context("Error while applying changes to storage")?;
 
-    manager.message(format!("Transactions (total {}) page {}/{}:", transactions.len(), page, max_pages));
-    for tx in transactions.iter().skip((page - 1) * TXS_PER_PAGE).take(TXS_PER_PAGE) {
-        manager.message(format!("- {}", tx.summary(wallet.get_network().is_mainnet(), &*storage)?));
+        manager.warn("You are currently offline, transaction cannot be send automatically. Please send it manually to the network.");
+        manager.message(format!("Transaction in hex format: {}", tx.to_hex()));
     }
 
     Ok(())
 }
 
-// Set your wallet in online mode
-async fn online_mode(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
-    let context = manager.get_context().lock()?;
-    let wallet: &Arc<Wallet> = context.get()?;
-    if wallet.is_online().await {
-        manager.error("Wallet is already online");
-    } else {
-        let daemon_address = if arguments.has_argument("daemon_address") {
-            arguments.get_value("daemon_address")?.to_string_value()?
-        } else {
-            DEFAULT_DAEMON_ADDRESS.to_string()
-        };
+// Build a transaction of a multisig account and write it to a file for its participants
+async fn export_multisig_tx(wallet: &Wallet, manager: &CommandManager, tx_type: TransactionTypeBuilder, fee: FeeBuilder) -> Result<(), CommandError> {
+    let prompt = manager.get_prompt();
+    let output = prompt.read_input(
+        prompt::colorize_str(Color::Green, "File to write the transaction to sign: "),
+        false
+    ).await.context("Error while reading output file")?;
 
-        wallet.set_online_mode(&daemon_address).await.context("Couldn't enable online mode")?;
-        manager.message("Wallet is now online");
-    }
+    manager.message("Building transaction...");
+    let multisig = wallet.create_multisig_transaction(tx_type, fee).await
+        .context("Error while creating multisig transaction")?;
+
+    let content = serde_json::to_string_pretty(&multisig).context("Error while serializing multisig transaction")?;
+    fs::write(&output, content).with_context(|| format!("Error while writing multisig transaction to {}", output))?;
+
+    manager.message(format!("Multisig transaction with nonce {} written to {}", multisig.transaction.get_nonce(), output));
+    manager.message(format!("Send it to the participants to sign it using 'multisig_sign', {} signature(s) are required", multisig.setup.threshold));
+    manager.message("Then finalize it using 'multisig_finalize'");
     Ok(())
 }
 
-// Set your wallet in offline mode
-async fn offline_mode(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
-    let context = manager.get_context().lock()?;
-    let wallet: &Arc<Wallet> = context.get()?;
-    if !wallet.is_online().await {
-        manager.error("Wallet is already offline");
-    } else {
-        wallet.set_offline_mode().await.context("Error on offline mode")?;
-        manager.message("Wallet is now offline");
+// Show the threshold and participants of a multisig setup
+fn display_multisig_setup(manager: &CommandManager, threshold: u8, participants: &[Address]) {
+    if threshold == 0 && participants.is_empty() {
+        manager.message("Multisig removal");
+        return
+    }
+
+    manager.message(format!("Multisig {}-of-{}:", threshold, participants.len()));
+    for (id, participant) in participants.iter().enumerate() {
+        manager.message(format!("- #{}: {}", id, participant));
     }
-    Ok(())
 }
 
-// Show current wallet address
-async fn rescan(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+// Configure, show or remove the multisig setup of the account
+// Once configured, each transaction requires the signatures of the participants
+async fn multisig(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let prompt = manager.get_prompt();
     let context = manager.get_context().lock()?;
     let wallet: &Arc<Wallet> = context.get()?;
-    let topoheight = if arguments.has_argument("topoheight") {
-        arguments.get_value("topoheight")?.to_number()?
-    } else {
-        0
-    };
+    let action = arguments.get_value("action")?.to_string_value()?;
+
+    let setup = wallet.get_multisig_setup().await
+        .context("Error while fetching multisig setup")?;
+    let network = *wallet.get_network();
+
+    let tx_type = match action.as_str() {
+        "show" => {
+            match setup {
+                Some(setup) => {
+                    let participants: Vec<Address> = setup.participants.iter().map(|key| key.as_address(network)).collect();
+                    display_multisig_setup(manager, setup.threshold, &participants);
+                },
+                None => manager.message("Your account is not a multisig")
+            };
+            return Ok(())
+        },
+        "setup" => {
+            let str_participants = prompt.read_input(
+                prompt::colorize_str(Color::Green, "Participants addresses or contact names (comma separated): "),
+                false
+            ).await.context("Error while reading participants")?;
+
+            let mut participants = Vec::new();
+            for value in str_participants.split(',').map(str::trim).filter(|v| !v.is_empty()) {
+                participants.push(parse_address_or_contact(wallet, value).await?);
+            }
 
-    wallet.rescan(topoheight).await.context("error while restarting network handler")?;
-    manager.message("Network handler has been restarted!");
-    Ok(())
-}
+            let str_threshold = prompt.read_input(
+                prompt::colorize_str(Color::Green, "Signatures required: "),
+                false
+            ).await.context("Error while reading threshold")?;
+            let threshold: u8 = str_threshold.trim().parse()
+                .map_err(|_| CommandError::InvalidArgument(format!("Invalid threshold '{}'", str_threshold)))?;
 
-async fn seed(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
-    let context = manager.get_context().lock()?;
-    let wallet: &Arc<Wallet> = context.get()?;
-    let prompt =  manager.get_prompt();
+            if threshold == 0 || threshold as usize > participants.len() {
+                return Err(CommandError::InvalidArgument(format!("Threshold must be between 1 and {}", participants.len())));
+            }
 
-    let password = prompt.read_input("Password: ".into(), true)
-        .await.context("Error while reading password")?;
-    // check if password is valid
-    wallet.is_valid_password(password).await?;
+            TransactionTypeBuilder::MultiSig(MultiSigBuilder {
+                threshold,
+                participants
+            })
+        },
+        "delete" => {
+            if setup.is_none() {
+                return Err(CommandError::InvalidArgument("Your account is not a multisig".to_owned()));
+            }
 
-    let language = if arguments.has_argument("language") {
-        arguments.get_value("language")?.to_number()?
-    } else {
-        0
+            TransactionTypeBuilder::MultiSig(MultiSigBuilder {
+                threshold: 0,
+                participants: Vec::new()
+            })
+        },
+        _ => return Err(CommandError::InvalidArgument(format!("Unknown action '{}', expected setup, show or delete", action)))
     };
 
-    let seed = wallet.get_seed(language as usize)?;
-    prompt.read_input(
-        prompt::colorize_string(Color::Green, &format!("Seed: {}\r\nPress ENTER to continue", seed)),
-        false
-    ).await.context("Error while printing seed")?;
-    Ok(())
+    display_tx_type(manager, wallet, &tx_type).await;
+    let estimated_fees = wallet.estimate_fees(tx_type.clone()).await.context("Error while estimating fees")?;
+    manager.message(format!("Estimated fees: {} XELIS", format_xelis(estimated_fees)));
+
+    if !prompt.ask_confirmation().await.context("Error while confirming action")? {
+        manager.message("Multisig setup has been aborted");
+        return Ok(())
+    }
+
+    broadcast_tx(wallet, manager, tx_type, FeeBuilder::Multiplier(1f64), None).await
 }
 
-async fn nonce(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+// Sign a multisig transaction exported by its owner
+// The partial signature is shown in hex to be sent back to the owner
+async fn multisig_sign(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let input = arguments.get_value("input")?.to_string_value()?;
+    let prompt = manager.get_prompt();
     let context = manager.get_context().lock()?;
     let wallet: &Arc<Wallet> = context.get()?;
-    let nonce = wallet.get_nonce().await;
-    manager.message(format!("Nonce: {}", nonce));
-    Ok(())
-}
 
-async fn set_nonce(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
-    let value = manager.get_prompt().read("New Nonce: ".to_string()).await
-        .context("Error while reading new nonce to set")?;
+    let content = fs::read_to_string(&input).with_context(|| format!("Error while reading {}", input))?;
+    let multisig: MultiSigTransaction = serde_json::from_str(&content)
+        .map_err(|e| CommandError::InvalidArgument(format!("Invalid multisig transaction: {}", e)))?;
 
-    let context = manager.get_context().lock()?;
-    let wallet: &Arc<Wallet> = context.get()?;
-    let mut storage = wallet.get_storage().write().await;
-    storage.set_nonce(value)?;
-    manager.message(format!("New nonce is: {}", value));
-    Ok(())
-}
+    // Show what is going to be signed
+    let network = *wallet.get_network();
+    manager.message(format!("Source: {}", multisig.transaction.get_source().as_address(network)));
+    manager.message(format!("Fee: {} XELIS, nonce: {}", format_xelis(multisig.transaction.get_fee()), multisig.transaction.get_nonce()));
+    display_tx_type(manager, wallet, &multisig.tx_type).await;
 
-#[cfg(feature = "api_server")]
-async fn stop_api_server(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
-    let context = manager.get_context().lock()?;
-    let wallet: &Arc<Wallet> = context.get()?;
-    wallet.stop_api_server().await.context("Error while stopping API Server")?;
-    manager.message("API Server has been stopped");
+    if !prompt.ask_confirmation().await.context("Error while confirming action")? {
+        manager.message("Signing has been aborted");
+        return Ok(())
+    }
+
+    let signature = wallet.sign_multisig_transaction(&multisig)
+        .context("Error while signing multisig transaction")?;
+
+    manager.message(format!("Partial signature: {}", signature.to_hex()));
+    manager.message("Send it to the owner of the account to finalize the transaction");
     Ok(())
 }
 
-#[cfg(feature = "api_server")]
-async fn start_rpc_server(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+// Import the partial signatures of the participants, sign the multisig transaction and broadcast it
+async fn multisig_finalize(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let input = arguments.get_value("input")?.to_string_value()?;
+    let prompt = manager.get_prompt();
     let context = manager.get_context().lock()?;
     let wallet: &Arc<Wallet> = context.get()?;
-    let bind_address = arguments.get_value("bind_address")?.to_string_value()?;
-    let username = arguments.get_value("username")?.to_string_value()?;
-    let password = arguments.get_value("password")?.to_string_value()?;
 
-    let auth_config = Some(AuthConfig {
-        username,
-        password
-    });
+    let content = fs::read_to_string(&input).with_context(|| format!("Error while reading {}", input))?;
+    let multisig: MultiSigTransaction = serde_json::from_str(&content)
+        .map_err(|e| CommandError::InvalidArgument(format!("Invalid multisig transaction: {}", e)))?;
+
+    let threshold = multisig.setup.threshold as usize;
+    let mut signatures = Vec::with_capacity(threshold);
+    while signatures.len() < threshold {
+        let hex = prompt.read_input(
+            prompt::colorize_str(Color::Green, &format!("Partial signature {}/{}: ", signatures.len() + 1, threshold)),
+            false
+        ).await.context("Error while reading partial signature")?;
+
+        match SignatureId::from_hex(hex.trim().to_owned()) {
+            Ok(signature) => signatures.push(signature),
+            Err(e) => manager.error(format!("Invalid partial signature: {}", e))
+        };
+    }
 
-    wallet.enable_rpc_server(bind_address, auth_config).await.context("Error while enabling RPC Server")?;
-    manager.message("RPC Server has been enabled");
+    let tx = wallet.finalize_multisig_transaction(multisig, signatures)
+        .context("Error while finalizing multisig transaction")?;
+
+    manager.message(format!("Transaction hash: {}", tx.hash()));
+    wallet.submit_signed_transaction(&tx).await
+        .context("Couldn't submit transaction")?;
+
+    manager.message("Transaction has been accepted in mempool!");
     Ok(())
 }
 
-#[cfg(feature = "api_server")]
-async fn start_xswd(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+// List the hardware devices connected or verify the wallet address on the signer in use
+async fn hardware_signer(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let action = arguments.get_value("action")?.to_string_value()?;
     let context = manager.get_context().lock()?;
     let wallet: &Arc<Wallet> = context.get()?;
-    match wallet.enable_xswd().await {
-        Ok(receiver) => {
-            let prompt = manager.get_prompt().clone();
-            tokio::spawn(xswd_handler(receiver, prompt));
-            manager.message("XSWD Server has been enabled");
+    let network = *wallet.get_network();
+
+    match action.as_str() {
+        "list" => {
+            let signers = discover_signers();
+            if signers.is_empty() {
+                manager.message(format!("No hardware signer found (backends: {})", get_backends().join(", ")));
+                return Ok(())
+            }
+
+            manager.message(format!("Hardware signers ({}):", signers.len()));
+            for signer in signers {
+                match signer.get_public_key().await {
+                    Ok(public_key) => manager.message(format!("- {}: {}", signer.get_name(), public_key.to_address(network))),
+                    Err(e) => manager.message(format!("- {}: {}", signer.get_name(), e))
+                };
+            }
         },
-        Err(e) => manager.error(format!("Error while enabling XSWD Server: {}", e))
+        "confirm_address" => {
+            let signer = wallet.get_signer().await
+                .ok_or_else(|| CommandError::InvalidArgument("No hardware signer is used by this wallet".to_owned()))?;
+            manager.message(format!("Please verify that your {} device shows the address {}", signer.get_name(), wallet.get_address()));
+            signer.confirm_address(network.is_mainnet()).await.context("Address was not confirmed on the device")?;
+            manager.message("Address has been confirmed");
+        },
+        _ => return Err(CommandError::InvalidArgument(format!("Unknown action '{}', expected list or confirm_address", action)))
     };
 
     Ok(())
 }
 
-// broadcast tx if possible
-// submit_transaction increase the local nonce in storage in case of success
-async fn broadcast_tx(wallet: &Wallet, manager: &CommandManager, tx: Transaction) {
-    let tx_hash = tx.hash();
-    manager.message(format!("Transaction hash: {}", tx_hash));
+// Let the signer build and sign the transaction, it shows its content on the device to be confirmed
+async fn broadcast_tx_with_signer(wallet: &Wallet, manager: &CommandManager, signer: &str, tx_type: TransactionTypeBuilder, fee: FeeBuilder, nonce: Option<u64>) -> Result<(), CommandError> {
+    if nonce.is_some() {
+        return Err(CommandError::InvalidArgument("Nonce can't be overridden when signing with a device".to_owned()))
+    }
+
+    manager.message(format!("Please review and confirm the transaction on your {} device...", signer));
+    let tx = wallet.create_transaction_with_signer(tx_type, fee).await
+        .context("Error while signing transaction")?;
 
+    manager.message(format!("Transaction hash: {}", tx.hash()));
     if wallet.is_online().await {
-        if let Err(e) = wallet.submit_transaction(&tx).await {
-            manager.error(format!("Couldn't submit transaction: {}", e));
-            manager.error("You can try to rescan your balance with the command 'rescan'");
-        } else {
-            manager.message("Transaction submitted successfully!");
-        }
+        manager.message("Submitting transaction to the daemon...");
+        match wallet.submit_signed_transaction(&tx).await {
+            Ok(()) => manager.message("Transaction has been accepted in mempool!"),
+            Err(e) => manager.error(format!("Couldn't submit transaction: {}", e))
+        };
     } else {
         manager.warn("You are currently offline, transaction cannot be send automatically. Please send it manually to the network.");
         manager.message(format!("Transaction in hex format: {}", tx.to_hex()));
     }
-}
\ No newline at end of file
+
+    Ok(())
+}
@@ -0,0 +1,43 @@
+use anyhow::{anyhow, Context, Result};
+use log::{debug, info};
+use crate::storage::EncryptedStorage;
+
+// A migration upgrades the storage from the previous schema version to the next one
+type Migration = fn(&mut EncryptedStorage) -> Result<()>;
+
+// Ordered list of migrations, the one at index N upgrades version N to N + 1
+// To change the layout of the storage, add a migration at the end of this list
+const MIGRATIONS: &[(&str, Migration)] = &[
+    ("build transactions indexes", migrate_transactions_indexes)
+];
+
+// Schema version of a storage created by this wallet version
+pub const CURRENT_SCHEMA_VERSION: u32 = MIGRATIONS.len() as u32;
+
+// Apply every migration needed to reach the current schema version
+// The version is saved after each migration so an interrupted upgrade restarts from the last one done
+pub fn migrate(storage: &mut EncryptedStorage) -> Result<()> {
+    let mut version = storage.get_schema_version()?;
+    if version > CURRENT_SCHEMA_VERSION {
+        return Err(anyhow!("Wallet storage schema version {} is not supported by this wallet version (max: {})", version, CURRENT_SCHEMA_VERSION))
+    }
+
+    if version < CURRENT_SCHEMA_VERSION {
+        info!("Upgrading wallet storage from schema version {} to {}", version, CURRENT_SCHEMA_VERSION);
+    }
+
+    while version < CURRENT_SCHEMA_VERSION {
+        let (name, migration) = MIGRATIONS[version as usize];
+        debug!("Applying migration {}: {}", version + 1, name);
+        migration(storage).with_context(|| format!("Error while applying migration '{}'", name))?;
+        version += 1;
+        storage.set_schema_version(version)?;
+    }
+
+    Ok(())
+}
+
+// Version 1: transactions are indexed by topoheight to paginate the history
+fn migrate_transactions_indexes(storage: &mut EncryptedStorage) -> Result<()> {
+    storage.rebuild_transactions_indexes()
+}
@@ -1,5 +1,9 @@
 use std::{
-    sync::Arc,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc
+    },
+    time::{Duration, Instant},
     collections::{
         HashMap,
         HashSet
@@ -8,29 +12,38 @@ use std::{
 };
 use thiserror::Error;
 use anyhow::Error;
-use log::{debug, error, trace, warn};
-use tokio::{task::JoinHandle, sync::Mutex, net::lookup_host};
+use futures_util::{stream, StreamExt};
+use log::{debug, error, info, trace, warn};
+use tokio::{task::JoinHandle, sync::Mutex, net::lookup_host, time::sleep};
 use xelis_common::{
     account::CiphertextCache,
     api::{
         daemon::{
             BlockResponse,
+            ChainReorganizedEvent,
             NewBlockEvent
         },
-        wallet::BalanceChanged,
+        wallet::{BalanceChanged, BlockScanned, EntryType, IncomingTransfer, SyncProgress, TransactionFailed, TransactionFailedReason},
         RPCTransactionType
     },
     asset::AssetWithData,
+    block::BlockHeader,
     config::XELIS_ASSET,
     crypto::{
         elgamal::Ciphertext,
         Address,
-        Hash
+        Hash,
+        Hashable
     },
+    json_rpc::{ProxyConfig, TlsConfig},
+    network::Network,
     serializer::Serializer,
+    time::get_current_time_in_millis,
+    transaction::Transaction,
     utils::sanitize_daemon_address
 };
 use crate::{
+    config::{DAEMON_HEALTH_CHECK_INTERVAL, DAEMON_MAX_TOP_BLOCK_AGE, SYNC_PROGRESS_REPORT_INTERVAL},
     daemon_api::DaemonAPI,
     entry::{
         EntryData,
@@ -58,36 +71,165 @@ pub enum NetworkError {
     #[error(transparent)]
     DaemonAPIError(#[from] Error),
     #[error("Network mismatch")]
-    NetworkMismatch
+    NetworkMismatch,
+    #[error("Block header at topoheight {} doesn't match its hash", _0)]
+    InvalidBlockHeader(u64),
+    #[error("Transaction {} is not included in block {}", _0, _1)]
+    TransactionNotInBlock(Hash, Hash),
+    #[error("Transaction {} doesn't match its hash", _0)]
+    InvalidTransactionHash(Hash)
+}
+
+// Result of a daemon health check
+#[derive(Debug, Clone)]
+pub struct DaemonHealth {
+    // Round-trip time of the get_info request
+    pub latency: Duration,
+    pub version: String,
+    pub network: Network,
+    pub height: u64,
+    pub topoheight: u64,
+    // Topoheight synced by the wallet
+    pub synced_topoheight: u64,
+    // Seconds elapsed since the daemon top block
+    pub top_block_age: u64,
+    // Daemon is not running on the same network as the wallet
+    pub network_mismatch: bool,
+    // Daemon doesn't have the last block synced by the wallet
+    pub chain_mismatch: bool
+}
+
+impl DaemonHealth {
+    // Daemon didn't receive any block for a long time or is behind the wallet
+    pub fn is_lagging(&self) -> bool {
+        self.top_block_age > DAEMON_MAX_TOP_BLOCK_AGE || self.topoheight < self.synced_topoheight
+    }
+
+    // Human readable issues detected
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if self.network_mismatch {
+            warnings.push(format!("Daemon is running on {} network", self.network));
+        }
+
+        if self.chain_mismatch {
+            warnings.push(format!("Daemon doesn't have the block synced by the wallet at topoheight {}, it may be on a different chain", self.synced_topoheight));
+        }
+
+        if self.topoheight < self.synced_topoheight {
+            warnings.push(format!("Daemon is behind the wallet (topoheight {} < {})", self.topoheight, self.synced_topoheight));
+        } else if self.top_block_age > DAEMON_MAX_TOP_BLOCK_AGE {
+            warnings.push(format!("Daemon didn't receive any block for {} seconds, it may be lagging behind the network", self.top_block_age));
+        }
+
+        warnings
+    }
+}
+
+// Track the progress of a blocks scan
+// Each asset is scanned from the daemon topoheight down to the wallet topoheight
+struct SyncProgressTracker {
+    start: Instant,
+    last_report: Option<Instant>,
+    // Highest topoheight to scan
+    max_topoheight: u64,
+    // Topoheights to scan per asset
+    span: u64,
+    // Topoheights to scan for all the assets
+    target: u64,
+    // Topoheights scanned by the previous assets
+    offset: u64,
+    current: u64,
+    // Blocks fetched until now
+    blocks: u64
+}
+
+impl SyncProgressTracker {
+    fn new(min_topoheight: u64, max_topoheight: u64, assets: usize) -> Self {
+        let span = max_topoheight.saturating_sub(min_topoheight);
+        Self {
+            start: Instant::now(),
+            last_report: None,
+            max_topoheight,
+            span,
+            target: span * assets as u64,
+            offset: 0,
+            current: 0,
+            blocks: 0
+        }
+    }
+
+    fn on_block(&mut self, topoheight: u64) {
+        self.blocks += 1;
+        self.current = self.offset + self.max_topoheight.saturating_sub(topoheight).min(self.span);
+    }
+
+    // Current asset is fully scanned
+    fn next_asset(&mut self) {
+        self.offset += self.span;
+        self.current = self.offset;
+    }
+
+    fn progress(&self) -> SyncProgress {
+        let elapsed = self.start.elapsed().as_secs_f64();
+        let blocks_per_second = if elapsed > 0f64 { self.blocks as f64 / elapsed } else { 0f64 };
+        // Wait at least one second to have a meaningful estimation
+        let eta = if self.current > 0 && elapsed >= 1f64 {
+            let remaining = self.target.saturating_sub(self.current) as f64;
+            Some((remaining * elapsed / self.current as f64) as u64)
+        } else {
+            None
+        };
+
+        SyncProgress {
+            current: self.current,
+            target: self.target,
+            blocks_per_second,
+            eta
+        }
+    }
 }
 
 pub struct NetworkHandler {
     // tokio task
     task: Mutex<Option<JoinHandle<Result<(), Error>>>>,
+    // task checking periodically the daemon health
+    health_task: Mutex<Option<JoinHandle<()>>>,
     // wallet where we can save every data from chain
     wallet: Arc<Wallet>,
     // api to communicate with daemon
     // It is behind a Arc to be shared across several wallets
     // in case someone make a custom service and don't want to create a new connection
-    api: Arc<DaemonAPI>
+    api: Arc<DaemonAPI>,
+    // Last topoheight known of the daemon
+    network_topoheight: AtomicU64,
+    // Progress of the current blocks scan, None if not scanning
+    sync_progress: Mutex<Option<SyncProgress>>
 }
 
 impl NetworkHandler {
     // Create a new network handler with a wallet and a daemon address
     // This will create itself a DaemonAPI and verify if connection is possible
-    pub async fn new<S: ToString>(wallet: Arc<Wallet>, daemon_address: S) -> Result<SharedNetworkHandler, Error> {
+    pub async fn new<S: ToString>(wallet: Arc<Wallet>, daemon_address: S, tls: Option<TlsConfig>, proxy: Option<ProxyConfig>) -> Result<SharedNetworkHandler, Error> {
         let s = daemon_address.to_string();
-        let mut resolved_addr: String;
-        resolved_addr = "".to_string();
-        let mut resolved_addrs = lookup_host(s).await?;
-         while let Some(addr) = resolved_addrs.next() {
-            match addr {
-                SocketAddr::V4(addr) => resolved_addr = addr.to_string(),
-                SocketAddr::V6(addr) => resolved_addr = addr.to_string(),
+        // An explicit scheme is kept as is: resolving the host would break the TLS hostname verification
+        // With a proxy, the host must never be resolved locally to not leak DNS requests
+        let has_scheme = ["http://", "https://", "ws://", "wss://"].iter().any(|scheme| s.to_lowercase().starts_with(scheme));
+        let resolved_addr = if has_scheme || proxy.is_some() {
+            s
+        } else {
+            let mut resolved_addr = "".to_string();
+            let mut resolved_addrs = lookup_host(s).await?;
+            while let Some(addr) = resolved_addrs.next() {
+                match addr {
+                    SocketAddr::V4(addr) => resolved_addr = addr.to_string(),
+                    SocketAddr::V6(addr) => resolved_addr = addr.to_string(),
+                }
             }
-        }
+            resolved_addr
+        };
 
-        let api = DaemonAPI::new(format!("{}/json_rpc", sanitize_daemon_address(resolved_addr.as_str()))).await?;
+        let api = DaemonAPI::new_with_options(format!("{}/json_rpc", sanitize_daemon_address(resolved_addr.as_str())), tls, proxy).await?;
         Self::with_api(wallet, Arc::new(api)).await
     }
 
@@ -99,8 +241,11 @@ impl NetworkHandler {
 
         Ok(Arc::new(Self {
             task: Mutex::new(None),
+            health_task: Mutex::new(None),
             wallet,
-            api
+            api,
+            network_topoheight: AtomicU64::new(0),
+            sync_progress: Mutex::new(None)
         }))
     }
 
@@ -125,6 +270,7 @@ impl NetworkHandler {
             let res =  zelf.start_syncing().await;
             if let Err(e) = res.as_ref() {
                 error!("Error while syncing: {}", e);
+                zelf.wallet.propagate_event(Event::Disconnected).await;
             }
 
             // Turn off the websocket connection
@@ -139,6 +285,11 @@ impl NetworkHandler {
         }));
 
 
+        let zelf = Arc::clone(&self);
+        *self.health_task.lock().await = Some(tokio::spawn(async move {
+            zelf.run_health_checks().await;
+        }));
+
         // Notify that we are online
         self.wallet.propagate_event(Event::Online).await;
 
@@ -148,6 +299,10 @@ impl NetworkHandler {
     // Stop the internal loop to stop syncing
     pub async fn stop(&self) -> Result<(), NetworkError> {
         trace!("Stopping network handler");
+        if let Some(handle) = self.health_task.lock().await.take() {
+            handle.abort();
+        }
+
         if let Some(handle) = self.task.lock().await.take() {
             if handle.is_finished() {
                 // We are already finished, which mean the event got triggered
@@ -170,11 +325,22 @@ impl NetworkHandler {
         }
     }
 
+    // Get the last topoheight known of the daemon
+    // It is updated at each sync
+    pub fn get_network_topoheight(&self) -> u64 {
+        self.network_topoheight.load(Ordering::SeqCst)
+    }
+
     // Retrieve the daemon API used
     pub fn get_api(&self) -> &DaemonAPI {
         &self.api
     }
 
+    // Retrieve the daemon API to share it with another wallet
+    pub fn get_shared_api(&self) -> Arc<DaemonAPI> {
+        Arc::clone(&self.api)
+    }
+
     // check if the network handler is running (that we have a task and its not finished)
     pub async fn is_running(&self) -> bool {
         let task = self.task.lock().await;
@@ -185,6 +351,80 @@ impl NetworkHandler {
         }
     }
 
+    // Measure the latency of the daemon and check that it is on the same chain as the wallet
+    pub async fn check_health(&self) -> Result<DaemonHealth, Error> {
+        let start = Instant::now();
+        let info = self.api.get_info().await?;
+        let latency = start.elapsed();
+
+        let top_block = self.api.get_block_by_hash(&info.top_block_hash).await?;
+        let top_block_age = get_current_time_in_millis().saturating_sub(top_block.timestamp) / 1000;
+
+        let (synced_topoheight, top_block_hash) = {
+            let storage = self.wallet.get_storage().read().await;
+            let top_block_hash = if storage.has_top_block_hash()? {
+                Some(storage.get_top_block_hash()?)
+            } else {
+                None
+            };
+            (storage.get_synced_topoheight().unwrap_or(0), top_block_hash)
+        };
+
+        // Compare the last block synced by the wallet with the one of the daemon at the same topoheight
+        let in_daemon_range = synced_topoheight <= info.topoheight && info.pruned_topoheight.map_or(true, |pruned| synced_topoheight >= pruned);
+        let chain_mismatch = match top_block_hash {
+            Some(hash) if in_daemon_range => {
+                let block = self.api.get_block_at_topoheight(synced_topoheight).await?;
+                *block.hash != hash
+            },
+            _ => false
+        };
+
+        Ok(DaemonHealth {
+            latency,
+            network_mismatch: info.network != *self.wallet.get_network(),
+            version: info.version,
+            network: info.network,
+            height: info.height,
+            topoheight: info.topoheight,
+            synced_topoheight,
+            top_block_age,
+            chain_mismatch
+        })
+    }
+
+    // Check the daemon health periodically and warn the user about the issues found
+    // Stops once the syncing task is done
+    async fn run_health_checks(&self) {
+        loop {
+            sleep(Duration::from_secs(DAEMON_HEALTH_CHECK_INTERVAL)).await;
+            if !self.is_running().await {
+                break;
+            }
+
+            match self.check_health().await {
+                Ok(health) => {
+                    trace!("Daemon latency: {} ms", health.latency.as_millis());
+                    for warning in health.warnings() {
+                        warn!("{}", warning);
+                    }
+                },
+                Err(e) => debug!("Error while checking daemon health: {}", e)
+            }
+        }
+    }
+
+    // Decrypt an amount using the wallet private key
+    // A watch-only wallet can't decrypt it, so only its ciphertext can be stored
+    async fn decrypt_amount(&self, ciphertext: Ciphertext) -> Result<Option<u64>, Error> {
+        if self.wallet.is_watch_only() {
+            trace!("watch-only wallet, skipping amount decryption");
+            return Ok(None)
+        }
+
+        Ok(Some(Arc::clone(&self.wallet).decrypt_ciphertext(ciphertext).await?))
+    }
+
     // Process a block by checking if it contains any transaction for us
     // Or that we mined it
     // Returns assets that changed and returns the highest nonce if we send a transaction
@@ -192,7 +432,7 @@ impl NetworkHandler {
         let block_hash = block.hash.into_owned();
         debug!("Processing block {} at topoheight {}", block_hash, topoheight);
 
-        if block.miner.is_mainnet() != self.wallet.get_network().is_mainnet() {
+        if block.miner.get_network() != *self.wallet.get_network() {
             debug!("Block {} at topoheight {} is not on the same network as the wallet", block_hash, topoheight);
             return Err(NetworkError::NetworkMismatch.into())
         }
@@ -225,7 +465,7 @@ impl NetworkHandler {
                 }
 
                 // Propagate the event to the wallet
-                self.wallet.propagate_event(Event::NewTransaction(entry.serializable(self.wallet.get_network().is_mainnet()))).await;
+                self.wallet.propagate_event(Event::NewTransaction(entry.serializable(*self.wallet.get_network()))).await;
             } else {
                 warn!("No reward for block {} at topoheight {}", block_hash, topoheight);
             }
@@ -234,6 +474,12 @@ impl NetworkHandler {
         // Highest nonce we found in this block
         let mut our_highest_nonce = None;
 
+        // Subaddresses derived to recognize the transfers sent to them
+        let subaddresses: Vec<_> = self.wallet.get_subaddress_keys().await?
+            .into_iter()
+            .map(|(index, keypair)| (index, keypair.get_public_key().compress(), keypair))
+            .collect();
+
         // Verify all TXs one by one to find one for us
         for tx in block.transactions.into_iter() {
             trace!("Checking transaction {}", tx.hash);
@@ -247,12 +493,21 @@ impl NetworkHandler {
                         None
                     }
                 },
+                RPCTransactionType::MultiSig(_) => {
+                    // Only the fee is paid, no transfer is involved
+                    if is_owner {
+                        Some(EntryData::Outgoing { transfers: Vec::new(), fee: tx.fee, nonce: tx.nonce })
+                    } else {
+                        None
+                    }
+                },
                 RPCTransactionType::Transfers(txs) => {
                     let mut transfers_in: Vec<TransferIn> = Vec::new();
                     let mut transfers_out: Vec<TransferOut> = Vec::new();
                     for transfer in txs {
                         let destination = transfer.destination.to_public_key();
-                        if is_owner || destination == *address.get_public_key() {
+                        let subaddress = subaddresses.iter().find(|(_, key, _)| *key == destination);
+                        if is_owner || destination == *address.get_public_key() || subaddress.is_some() {
                             // Get the right handle
                             let handle = if is_owner {
                                 transfer.sender_handle
@@ -278,24 +533,41 @@ impl NetworkHandler {
                                 }
                             };
 
+                            // A transfer received on a subaddress is decrypted using its own key
+                            let subaddress = subaddress.filter(|_| !is_owner);
                             let extra_data = if let Some(cipher) = transfer.extra_data.into_owned() {
-                                self.wallet.decrypt_extra_data(cipher, &handle).ok()
+                                match subaddress {
+                                    Some((_, _, keypair)) => self.wallet.decrypt_extra_data_with(keypair.get_private_key(), cipher, &handle).ok(),
+                                    None => self.wallet.decrypt_extra_data(cipher, &handle).ok()
+                                }
                             } else {
                                 None
                             };
 
                             debug!("Decrypting amount from TX {}", tx.hash);
                             let ciphertext = Ciphertext::new(commitment, handle);
-                            let amount = Arc::clone(&self.wallet).decrypt_ciphertext(ciphertext).await?;
+                            let amount = match subaddress {
+                                Some((_, _, keypair)) => Arc::clone(&self.wallet).decrypt_ciphertext_with(keypair.get_private_key().clone(), ciphertext).await?,
+                                None => match self.decrypt_amount(ciphertext).await? {
+                                    Some(amount) => amount,
+                                    None => {
+                                        debug!("Amount of TX {} is unknown, transfer is not stored", tx.hash);
+                                        continue;
+                                    }
+                                }
+                            };
 
                             let asset = transfer.asset.into_owned();
-                            assets_changed.insert(asset.clone());
+                            // Funds received on a subaddress don't change the balance of the main address
+                            if subaddress.is_none() {
+                                assets_changed.insert(asset.clone());
+                            }
 
                             if is_owner {
                                 let transfer = TransferOut::new(destination, asset, amount, extra_data);
                                 transfers_out.push(transfer);
                             } else {
-                                let transfer = TransferIn::new(asset, amount, extra_data);
+                                let transfer = TransferIn::new(asset, amount, extra_data, subaddress.map(|(index, _, _)| *index));
                                 transfers_in.push(transfer);
                             }
                         }
@@ -327,6 +599,18 @@ impl NetworkHandler {
                 let propagate = {
                     let mut storage = self.wallet.get_storage().write().await;
                     let found = storage.has_transaction(entry.get_hash())?;
+                    // It got executed in a block, it's not pending anymore
+                    if storage.remove_pending_transaction(entry.get_hash())? {
+                        debug!("Pending transaction {} has been executed at topoheight {}", entry.get_hash(), topoheight);
+                    }
+
+                    // Other pending transactions using the same nonce can't be executed anymore
+                    if is_owner {
+                        for conflicting in storage.delete_pending_transactions_with_nonce(tx.nonce)? {
+                            debug!("Pending transaction {} conflicts with {} executed at topoheight {}", conflicting.get_hash(), entry.get_hash(), topoheight);
+                        }
+                    }
+
                     // Even if we probably scanned it before and a DAG reorg happened,
                     // It shouldn't be found because it got deleted from storage
                     if !found {
@@ -342,11 +626,33 @@ impl NetworkHandler {
 
                 if propagate {
                     // Propagate the event to the wallet
-                    self.wallet.propagate_event(Event::NewTransaction(entry.serializable(self.wallet.get_network().is_mainnet()))).await;
+                    let entry = entry.serializable(*self.wallet.get_network());
+                    let incoming = match &entry.entry {
+                        EntryType::Incoming { from, transfers } => transfers.iter().map(|transfer| IncomingTransfer {
+                            hash: entry.hash.clone(),
+                            topoheight,
+                            from: from.clone(),
+                            asset: transfer.asset.clone(),
+                            amount: transfer.amount,
+                            extra_data: transfer.extra_data.clone(),
+                            subaddress: transfer.subaddress
+                        }).collect(),
+                        _ => Vec::new()
+                    };
+
+                    self.wallet.propagate_event(Event::NewTransaction(entry)).await;
+                    for transfer in incoming {
+                        self.wallet.propagate_event(Event::IncomingTransfer(transfer)).await;
+                    }
                 }
             }
         }
 
+        self.wallet.propagate_event(Event::NewBlockScanned(BlockScanned {
+            block_hash,
+            topoheight
+        })).await;
+
         if !changes_stored || assets_changed.is_empty() {
             Ok(None)
         } else {
@@ -355,29 +661,95 @@ impl NetworkHandler {
         }
     }
 
+    // Retrieve the block header and only the transactions involving us
+    // The header hash is recomputed to prove the transactions hashes list wasn't altered,
+    // then each transaction must hash to an entry of this list
+    // Note that the proof of work of the header is not verified and that the daemon
+    // can omit some of our transactions, this mode trusts the daemon
+    async fn get_light_block_at_topoheight(&self, address: &Address, topoheight: u64) -> Result<BlockResponse, Error> {
+        let mut block = self.api.get_block_at_topoheight(topoheight).await?;
+
+        let mut header = BlockHeader::new(block.version, block.height, block.timestamp, block.tips.clone().into_owned(), *block.extra_nonce, block.miner.get_public_key().clone(), block.txs_hashes.clone().into_owned());
+        header.set_nonce(block.nonce);
+        if header.hash() != *block.hash {
+            return Err(NetworkError::InvalidBlockHeader(topoheight).into())
+        }
+
+        let transactions = self.api.get_account_transactions_at_topoheight(address, topoheight).await?;
+        for tx in transactions.iter() {
+            if !block.txs_hashes.contains(tx.hash.as_ref()) {
+                return Err(NetworkError::TransactionNotInBlock(tx.hash.clone().into_owned(), block.hash.clone().into_owned()).into())
+            }
+
+            if Transaction::from(tx.clone()).hash() != *tx.hash {
+                return Err(NetworkError::InvalidTransactionHash(tx.hash.clone().into_owned()).into())
+            }
+        }
+        trace!("Verified {} transactions over {} in block {}", transactions.len(), block.txs_hashes.len(), block.hash);
+
+        block.transactions = transactions;
+        Ok(block)
+    }
+
     // Scan the chain using a specific balance asset, this helps us to get a list of version to only requests blocks where changes happened
     // When the block is requested, we don't limit the syncing to asset in parameter
-    async fn get_balance_and_transactions(&self, topoheight_processed: &mut HashSet<u64>, address: &Address, asset: &Hash, min_topoheight: u64, balances: bool, highest_nonce: &mut Option<u64>) -> Result<(), Error> {
+    // The versions walked are the ones of `scanned`, which is either our address or one of our subaddresses
+    async fn get_balance_and_transactions(&self, topoheight_processed: &mut HashSet<u64>, address: &Address, scanned: &Address, asset: &Hash, min_topoheight: u64, balances: bool, highest_nonce: &mut Option<u64>, progress: &mut SyncProgressTracker) -> Result<(), Error> {
         // Retrieve the highest version
-        let (mut topoheight, mut version) = self.api.get_balance(address, asset).await.map(|res| (res.topoheight, res.version))?;
+        let (topoheight, version) = self.api.get_balance(scanned, asset).await.map(|res| (res.topoheight, res.version))?;
         // don't sync already synced blocks
         if min_topoheight >= topoheight {
             return Ok(())
         }
 
+        let batch_size = self.wallet.get_sync_batch_size();
+        let concurrency = self.wallet.get_sync_concurrency();
+
+        // Next version to process
+        let mut next = Some((topoheight, version));
         // Determine if its the highest version of balance or not
         // This is used to save the latest balance
         let mut highest_version = true;
-        loop {
-            let (mut balance, _, _, previous_topoheight) = version.consume();
-            // add this topoheight in cache to not re-process it (blocks are independant of asset to have faster sync)
-            // if its not already processed, do it
-            if topoheight_processed.insert(topoheight) {
-                let response = self.api.get_block_with_txs_at_topoheight(topoheight).await?;
+        while next.is_some() {
+            // Walk through the versions until we have a full batch of blocks to fetch
+            let mut batch = Vec::with_capacity(batch_size);
+            while let Some((topoheight, version)) = next.take() {
+                let (balance, _, _, previous_topoheight) = version.consume();
+                // add this topoheight in cache to not re-process it (blocks are independant of asset to have faster sync)
+                // if its not already processed, do it
+                if topoheight_processed.insert(topoheight) {
+                    batch.push((topoheight, balance, highest_version));
+                }
+                // Only first iteration is the highest one
+                highest_version = false;
+
+                // don't sync already synced blocks
+                if let Some(previous) = previous_topoheight.filter(|previous| *previous > min_topoheight) {
+                    let version = self.api.get_balance_at_topoheight(scanned, asset, previous).await?;
+                    next = Some((previous, version));
+                }
+
+                if batch.len() >= batch_size {
+                    break;
+                }
+            }
+
+            // Fetch the blocks of the batch in parallel, they are still processed in order
+            let topoheights: Vec<u64> = batch.iter().map(|(topoheight, _, _)| *topoheight).collect();
+            let mut blocks = stream::iter(topoheights.into_iter().map(|topoheight| self.fetch_block_for_sync(scanned, topoheight)))
+                .buffered(concurrency);
+
+            for (topoheight, mut balance, highest) in batch {
+                let response = match blocks.next().await {
+                    Some(response) => response?,
+                    None => break
+                };
                 let changes = self.process_block(address, response, topoheight).await?;
+                progress.on_block(topoheight);
+                self.report_sync_progress(progress, false).await;
 
                 // Check if a change occured, we are the highest version and update balances is requested
-                if let Some((_, nonce)) = changes.filter(|_| balances && highest_version) {
+                if let Some((_, nonce)) = changes.filter(|_| balances && highest) {
                     let mut storage = self.wallet.get_storage().write().await;
 
                     if highest_nonce.is_none() {
@@ -398,40 +770,41 @@ impl NetworkHandler {
                     if store {
                         debug!("Storing balance for asset {}", asset);
                         let plaintext_balance = if let Some(plaintext_balance) = storage.get_unconfirmed_balance_decoded_for(&asset, &balance.compressed()).await? {
-                            plaintext_balance
+                            Some(plaintext_balance)
                         } else {
                             trace!("Decrypting balance for asset {}", asset);
                             let ciphertext = balance.decompressed()?;
-                            Arc::clone(&self.wallet).decrypt_ciphertext(ciphertext.clone()).await?
+                            self.decrypt_amount(ciphertext.clone()).await?
                         };
 
-                        // Store the new balance
-                        storage.set_balance_for(asset, Balance::new(plaintext_balance, balance)).await?;
+                        if let Some(plaintext_balance) = plaintext_balance {
+                            // Store the new balance
+                            storage.set_balance_for(asset, Balance::new(plaintext_balance, balance)).await?;
+                            self.wallet.update_balances_cache([(asset.clone(), plaintext_balance)]).await;
 
-                        // Propagate the event
-                        self.wallet.propagate_event(Event::BalanceChanged(BalanceChanged {
-                            asset: asset.clone(),
-                            balance: plaintext_balance
-                        })).await;
+                            // Propagate the event
+                            self.wallet.propagate_event(Event::BalanceChanged(BalanceChanged {
+                                asset: asset.clone(),
+                                balance: plaintext_balance
+                            })).await;
+                        } else {
+                            // Watch-only, the signing wallet will decrypt it
+                            storage.set_encrypted_balance_for(asset, balance.compressed())?;
+                        }
                     }
                 }
             }
+        }
 
-            // Prepare a new iteration
-            if let Some(previous) = previous_topoheight {
-                // don't sync already synced blocks
-                if min_topoheight >= previous {
-                    return Ok(())
-                }
-
-                topoheight = previous;
-                version = self.api.get_balance_at_topoheight(address, asset, previous).await?;
-            } else {
-                return Ok(())
-            }
+        Ok(())
+    }
 
-            // Only first iteration is the highest one
-            highest_version = false;
+    // Retrieve the block to process at a topoheight depending on the sync mode
+    async fn fetch_block_for_sync(&self, address: &Address, topoheight: u64) -> Result<BlockResponse, Error> {
+        if self.wallet.is_light_mode() {
+            self.get_light_block_at_topoheight(address, topoheight).await
+        } else {
+            self.api.get_block_with_txs_at_topoheight(topoheight).await
         }
     }
 
@@ -543,6 +916,8 @@ impl NetworkHandler {
             warn!("Cleaning transactions above topoheight {}", maximum);
             // Changes were deleted, we should also delete transactions
             storage.delete_transactions_above_topoheight(maximum)?;
+            // Unconfirmed balances were built on top of the orphaned changes
+            storage.delete_unconfirmed_balances().await?;
         }
 
         // Save the new values
@@ -581,6 +956,7 @@ impl NetworkHandler {
                             warn!("We have balances but we couldn't fetch the nonce, deleting all balances");
                             storage.delete_balances().await?;
                             storage.delete_assets().await?;
+                            self.wallet.clear_balances_cache().await;
                         }
                     }
                     // Account is not registered, we can return safely here
@@ -633,6 +1009,11 @@ impl NetworkHandler {
         {
             if let Some(new_nonce) = new_nonce {
                 let mut storage = self.wallet.get_storage().write().await;
+                // Pending transactions using an already used nonce were either executed or rejected
+                for tx in storage.delete_pending_transactions_below_nonce(new_nonce)? {
+                    debug!("Pending transaction {} with nonce {} is not pending anymore", tx.get_hash(), tx.get_nonce());
+                }
+
                 if storage.get_nonce().map(|n| n != new_nonce).unwrap_or(true) {
                     // Store the new nonce
                     storage.set_nonce(new_nonce)?;
@@ -640,6 +1021,8 @@ impl NetworkHandler {
                 }
             }
 
+            // Balances changed, saved all at once
+            let mut updates = Vec::new();
             for (asset, mut ciphertext) in balances {
                 let (must_update, balance_cache) = {
                     let storage = self.wallet.get_storage().read().await;
@@ -662,24 +1045,49 @@ impl NetworkHandler {
                 if must_update {
                     trace!("must update balance for asset: {}, ct: {:?}", asset, ciphertext.to_bytes());
                     let value = if let Some(cache) = balance_cache {
-                        cache
+                        Some(cache)
                     } else {
                         trace!("Decrypting balance for asset {}", asset);
-                        Arc::clone(&self.wallet).decrypt_ciphertext(ciphertext.decompressed()?.clone()).await?
+                        self.decrypt_amount(ciphertext.decompressed()?.clone()).await?
                     };
 
-                    // Inform the change of the balance
-                    self.wallet.propagate_event(Event::BalanceChanged(BalanceChanged {
-                        asset: asset.clone(),
-                        balance: value
-                    })).await;
+                    match value {
+                        Some(value) => updates.push((asset, value, ciphertext)),
+                        // Watch-only, only the ciphertext is stored to build the unsigned transactions
+                        None => {
+                            let mut storage = self.wallet.get_storage().write().await;
+                            let changed = storage.get_encrypted_balance_for(&asset)
+                                .map(|previous| previous != *ciphertext.compressed())
+                                .unwrap_or(true);
+                            if changed {
+                                storage.set_encrypted_balance_for(&asset, ciphertext.compressed())?;
+                                should_sync_blocks = true;
+                            }
+                        }
+                    }
+                }
+            }
+
+            if !updates.is_empty() {
+                // We should sync new blocks to get the TXs
+                should_sync_blocks = true;
 
-                    // Update the balance
+                let mut changes = Vec::with_capacity(updates.len());
+                {
                     let mut storage = self.wallet.get_storage().write().await;
-                    storage.set_balance_for(asset, Balance::new(value, ciphertext)).await?;
+                    for (asset, value, ciphertext) in updates {
+                        storage.set_balance_for(asset, Balance::new(value, ciphertext)).await?;
+                        changes.push((asset.clone(), value));
+                    }
+                    self.wallet.update_balances_cache(changes.clone()).await;
+                }
 
-                    // We should sync new blocks to get the TXs
-                    should_sync_blocks = true;
+                // Inform the change of the balances
+                for (asset, balance) in changes {
+                    self.wallet.propagate_event(Event::BalanceChanged(BalanceChanged {
+                        asset,
+                        balance
+                    })).await;
                 }
             }
         }
@@ -687,12 +1095,37 @@ impl NetworkHandler {
         Ok(should_sync_blocks)
     }
 
+    // Check if the balance of a subaddress changed after the topoheight for one of our assets
+    async fn has_subaddress_changes_since(&self, topoheight: u64) -> Result<bool, Error> {
+        let subaddresses = self.wallet.get_subaddress_keys().await?;
+        if subaddresses.is_empty() {
+            return Ok(false)
+        }
+
+        let network = *self.wallet.get_network();
+        let assets = self.wallet.get_storage().read().await.get_assets().await?;
+        for (_, keypair) in subaddresses {
+            let address = keypair.get_public_key().to_address(network);
+            for asset in assets.iter() {
+                // No balance means it never received this asset
+                if let Ok(result) = self.api.get_balance(&address, asset).await {
+                    if result.topoheight > topoheight {
+                        return Ok(true)
+                    }
+                }
+            }
+        }
+
+        Ok(false)
+    }
+
     // Locate the highest valid topoheight we synced to, clean wallet storage
     // then sync again the head state
     async fn sync(&self, address: &Address, event: Option<NewBlockEvent>) -> Result<(), Error> {
         trace!("sync");
         // First, locate the last topoheight valid for syncing
         let (daemon_topoheight, daemon_block_hash, wallet_topoheight, sync_back) = self.locate_sync_topoheight_and_clean().await?;
+        self.network_topoheight.store(daemon_topoheight, Ordering::SeqCst);
 
         // Sync back is requested, sync the head state again
         if sync_back {
@@ -700,7 +1133,8 @@ impl NetworkHandler {
             // Now sync head state, this will helps us to determinate if we should sync blocks or not
             let should_sync_blocks = self.sync_head_state(&address, None, None, true).await?;
             // we have something that changed, sync transactions
-            if should_sync_blocks {
+            // Subaddresses have their own balances, their TXs are only found by scanning the blocks
+            if should_sync_blocks || self.has_subaddress_changes_since(wallet_topoheight).await? {
                 self.sync_new_blocks(&address, wallet_topoheight, false).await?;
             }
         } else if daemon_topoheight > wallet_topoheight {
@@ -759,11 +1193,18 @@ impl NetworkHandler {
         // Thanks to websocket, we can be notified when a new block is added in chain
         // this allows us to have a instant sync of each new block instead of polling periodically
         let mut receiver = self.api.on_new_block_event().await?;
+        // Blocks executed again by a reorg must be invalidated
+        let mut on_reorg = self.api.on_chain_reorganized_event().await?;
+        // Pending transactions deleted from the mempool will never be executed
+        let mut on_conflicted = self.api.on_transaction_conflicted_event().await?;
+        let mut on_orphaned = self.api.on_transaction_orphaned_event().await?;
 
         // Network events to detect if we are online or offline
         let mut on_connection = self.api.on_connection().await;
         let mut on_connection_lost = self.api.on_connection_lost().await;
 
+        // Blocks were skipped, the next sync must not rely only on the block event
+        let mut skipped = false;
         loop {
             tokio::select! {
                 // Wait on a new block, we don't parse the block directly as it may
@@ -771,26 +1212,151 @@ impl NetworkHandler {
                 res = receiver.next() => {
                     trace!("on_new_block_event");
                     let event = res?;
-                    self.sync(&address, Some(event)).await?;
+                    // The private key is dropped while locked, amounts can't be decrypted
+                    if self.wallet.is_locked() && !self.wallet.is_watch_only() {
+                        debug!("Wallet is locked, block {} will be synced once unlocked", event.hash);
+                        skipped = true;
+                        continue;
+                    }
+
+                    let event = if std::mem::take(&mut skipped) { None } else { Some(event) };
+                    // The connection may be lost during the sync
+                    // Don't stop the handler, we will sync again once reconnected
+                    if let Err(e) = self.sync(&address, event).await {
+                        error!("Error while syncing new block: {}", e);
+                        skipped = true;
+                        continue;
+                    }
+
+                    // Now that we are synced, send the scheduled payments
+                    if let Err(e) = self.wallet.execute_scheduled_payments().await {
+                        error!("Error while executing scheduled payments: {}", e);
+                    }
+
+                    if let Err(e) = self.wallet.prune_history().await {
+                        error!("Error while pruning history: {}", e);
+                    }
+                },
+                res = on_reorg.next() => {
+                    trace!("on_chain_reorganized_event");
+                    let event = res?;
+                    if let Err(e) = self.clean_reorganized_changes(&event).await {
+                        error!("Error while cleaning the changes reverted by the reorg: {}", e);
+                    }
+
+                    if self.wallet.is_locked() && !self.wallet.is_watch_only() {
+                        debug!("Wallet is locked, blocks reorganized by {} will be synced once unlocked", event.block_hash);
+                        skipped = true;
+                        continue;
+                    }
+
+                    // Sync again the blocks executed by the new chain
+                    if let Err(e) = self.sync(&address, None).await {
+                        error!("Error while syncing after reorg: {}", e);
+                        skipped = true;
+                    }
+                },
+                res = on_conflicted.next() => {
+                    trace!("on_transaction_conflicted_event");
+                    let event = res?;
+                    let tx = &event.transaction.data;
+                    if let Err(e) = self.mark_transaction_failed(&tx.hash, tx.nonce, &tx.source, event.reason.into()).await {
+                        error!("Error while marking the conflicted transaction {} as failed: {}", tx.hash, e);
+                    }
+                },
+                res = on_orphaned.next() => {
+                    trace!("on_transaction_orphaned_event");
+                    let event = res?;
+                    let tx = &event.data;
+                    if let Err(e) = self.mark_transaction_failed(&tx.hash, tx.nonce, &tx.source, TransactionFailedReason::Orphaned).await {
+                        error!("Error while marking the orphaned transaction {} as failed: {}", tx.hash, e);
+                    }
                 },
                 // Detect network events
                 res = on_connection.recv() => {
                     trace!("on_connection");
                     res?;
-                    // We are connected again, make sure we are still up-to-date with node 
-                    self.sync(&address, None).await?;
-
+                    info!("Connection with daemon restored, syncing from last known topoheight");
                     self.wallet.propagate_event(Event::Online).await;
+
+                    // We are connected again, make sure we are still up-to-date with node 
+                    if let Err(e) = self.sync(&address, None).await {
+                        error!("Error while syncing after reconnection: {}", e);
+                        skipped = true;
+                    }
                 },
                 res = on_connection_lost.recv() => {
                     trace!("on_connection_lost");
                     res?;
+                    warn!("Connection with daemon lost, trying to reconnect");
+                    self.wallet.propagate_event(Event::Disconnected).await;
                     self.wallet.propagate_event(Event::Offline).await;
                 }
             }
         }
     }
 
+    // A transaction of the wallet got deleted from the daemon mempool and will never be executed
+    // It is not pending anymore, and its nonce can be used again by a new transaction
+    async fn mark_transaction_failed(&self, hash: &Hash, nonce: u64, source: &Address, reason: TransactionFailedReason) -> Result<(), Error> {
+        // The daemon notifies the transactions of all the accounts
+        if source.get_public_key() != self.wallet.get_public_key() {
+            return Ok(())
+        }
+
+        {
+            let mut storage = self.wallet.get_storage().write().await;
+            // An orphaned transaction is not pending anymore as it was executed before the reorg
+            if !storage.remove_pending_transaction(hash)? && reason != TransactionFailedReason::Orphaned {
+                trace!("Conflicted transaction {} is not a pending transaction of the wallet", hash);
+                return Ok(())
+            }
+        }
+
+        warn!("Transaction {} with nonce {} failed and will never be executed: {:?}", hash, nonce, reason);
+        self.wallet.propagate_event(Event::TransactionFailed(TransactionFailed {
+            hash: hash.clone(),
+            nonce,
+            reason
+        })).await;
+
+        Ok(())
+    }
+
+    // Delete the transactions and changes synced from the first topoheight reverted by a reorg
+    // Nothing is deleted if the reorg happened above our synced topoheight
+    async fn clean_reorganized_changes(&self, event: &ChainReorganizedEvent<'_>) -> Result<(), Error> {
+        let Some(topoheight) = event.from_topoheight.checked_sub(1) else {
+            return Ok(())
+        };
+
+        {
+            let storage = self.wallet.get_storage().read().await;
+            if !storage.has_top_block_hash()? || storage.get_synced_topoheight()? < event.from_topoheight {
+                debug!("Reorg from topoheight {} is above our synced topoheight", event.from_topoheight);
+                return Ok(())
+            }
+        }
+
+        let block_hash = self.api.get_block_at_topoheight(topoheight).await?.hash.into_owned();
+        {
+            let mut storage = self.wallet.get_storage().write().await;
+            warn!("Chain reorganized from topoheight {}, cleaning transactions above topoheight {}", event.from_topoheight, topoheight);
+            if storage.delete_changes_above_topoheight(topoheight)? {
+                storage.delete_transactions_above_topoheight(topoheight)?;
+            }
+
+            storage.set_synced_topoheight(topoheight)?;
+            storage.set_top_block_hash(&block_hash)?;
+            if !storage.has_topoheight_in_changes(topoheight)? {
+                storage.add_topoheight_to_changes(topoheight, &block_hash)?;
+            }
+        }
+
+        self.wallet.propagate_event(Event::Rescan { start_topoheight: topoheight }).await;
+        Ok(())
+    }
+
     // Sync all new blocks until the current topoheight
     async fn sync_new_blocks(&self, address: &Address, current_topoheight: u64, balances: bool) -> Result<(), Error> {
         let assets = {
@@ -802,14 +1368,61 @@ impl NetworkHandler {
         // this will prevent us to request more than one time the same topoheight
         let mut topoheight_processed = HashSet::new();
 
+        // Funds sent to a subaddress only change its own balance, so its versions are walked too
+        let network = *self.wallet.get_network();
+        let subaddresses: Vec<(u32, Address)> = self.wallet.get_subaddress_keys().await?
+            .into_iter()
+            .map(|(index, keypair)| (index, keypair.get_public_key().to_address(network)))
+            .collect();
+
+        let mut progress = SyncProgressTracker::new(current_topoheight, self.get_network_topoheight(), assets.len() * (subaddresses.len() + 1));
+
         // get balance and transactions for each asset
         let mut highest_nonce = None;
-        for asset in assets {
+        for asset in assets.iter() {
             debug!("calling get balances and transactions {}", current_topoheight);
-            if let Err(e) = self.get_balance_and_transactions(&mut topoheight_processed, &address, &asset, current_topoheight, balances, &mut highest_nonce).await {
+            if let Err(e) = self.get_balance_and_transactions(&mut topoheight_processed, &address, &address, asset, current_topoheight, balances, &mut highest_nonce, &mut progress).await {
                 error!("Error while syncing balance for asset {}: {}", asset, e);
             }
+            progress.next_asset();
         }
+
+        for (index, subaddress) in subaddresses {
+            // In light mode, a block fetched for our address only contains our transactions
+            // so the blocks are processed again for each subaddress
+            let mut subaddress_processed = HashSet::new();
+            let processed = if self.wallet.is_light_mode() { &mut subaddress_processed } else { &mut topoheight_processed };
+            for asset in assets.iter() {
+                // A subaddress that never received this asset has no balance
+                if let Err(e) = self.get_balance_and_transactions(processed, &address, &subaddress, asset, current_topoheight, false, &mut highest_nonce, &mut progress).await {
+                    debug!("No transactions synced for asset {} on subaddress #{}: {}", asset, index, e);
+                }
+                progress.next_asset();
+            }
+        }
+
+        // Always report the end of the scan
+        self.report_sync_progress(&mut progress, true).await;
+        *self.sync_progress.lock().await = None;
+
         Ok(())
     }
+
+    // Save and broadcast the sync progress, at most every SYNC_PROGRESS_REPORT_INTERVAL unless forced
+    async fn report_sync_progress(&self, tracker: &mut SyncProgressTracker, force: bool) {
+        if !force && tracker.last_report.is_some_and(|last| last.elapsed() < Duration::from_millis(SYNC_PROGRESS_REPORT_INTERVAL)) {
+            return;
+        }
+        tracker.last_report = Some(Instant::now());
+
+        let progress = tracker.progress();
+        trace!("Sync progress: {}/{} ({:.2} blocks/s)", progress.current, progress.target, progress.blocks_per_second);
+        *self.sync_progress.lock().await = Some(progress.clone());
+        self.wallet.propagate_event(Event::SyncProgress(progress)).await;
+    }
+
+    // Progress of the blocks scan in progress, None if the wallet isn't scanning blocks
+    pub async fn get_sync_progress(&self) -> Option<SyncProgress> {
+        self.sync_progress.lock().await.clone()
+    }
 }
\ No newline at end of file
@@ -0,0 +1,149 @@
+use anyhow::{anyhow, Context, Result};
+use chacha20poly1305::aead::OsRng;
+use rand::RngCore;
+use xelis_common::{
+    crypto::{Address, KeyPair, PrivateKey},
+    network::Network,
+    serializer::{Reader, ReaderError, Serializer, Writer}
+};
+use crate::{
+    cipher::Cipher,
+    config::{PASSWORD_ALGORITHM_VERSION, SALT_SIZE},
+    wallet::hash_password
+};
+
+// Prefix of the text encoded in the QR code of a paper wallet
+pub const PAPER_WALLET_PREFIX: &str = "xelispaper:";
+// Version of the paper wallet format
+const PAPER_WALLET_VERSION: u8 = 0;
+
+// Private key of a wallet encrypted with a passphrase, to be printed for a cold storage
+pub struct PaperWallet {
+    pub network: Network,
+    pub private_key: PrivateKey
+}
+
+impl PaperWallet {
+    pub fn new(network: Network, private_key: PrivateKey) -> Self {
+        Self {
+            network,
+            private_key
+        }
+    }
+
+    // Address of the wallet, printed next to the QR code to receive funds on it
+    pub fn get_address(&self) -> Address {
+        KeyPair::from_private_key(self.private_key.clone())
+            .get_public_key()
+            .to_address(self.network)
+    }
+
+    // Encrypt it with a passphrase and encode it as text for the QR code
+    // Format: prefix | hex(version | password algorithm version | salt | encrypted content)
+    pub fn to_encrypted_string(&self, passphrase: String) -> Result<String> {
+        let mut salt = [0u8; SALT_SIZE];
+        OsRng.fill_bytes(&mut salt);
+
+        let hashed_passphrase = hash_password(passphrase, &salt, PASSWORD_ALGORITHM_VERSION)?;
+        let cipher = Cipher::new(&hashed_passphrase, None)?;
+        let encrypted = cipher.encrypt_value(&self.to_bytes())?;
+
+        let mut bytes = Vec::with_capacity(2 + SALT_SIZE + encrypted.len());
+        bytes.push(PAPER_WALLET_VERSION);
+        bytes.push(PASSWORD_ALGORITHM_VERSION);
+        bytes.extend_from_slice(&salt);
+        bytes.extend_from_slice(&encrypted);
+
+        Ok(format!("{}{}", PAPER_WALLET_PREFIX, hex::encode(bytes)))
+    }
+
+    // Decrypt a paper wallet created by `to_encrypted_string`
+    pub fn from_encrypted_string(value: &str, passphrase: String) -> Result<Self> {
+        let encoded = value.trim().strip_prefix(PAPER_WALLET_PREFIX).ok_or_else(|| anyhow!("Invalid paper wallet"))?;
+        let bytes = hex::decode(encoded).context("Invalid paper wallet encoding")?;
+        let header_size = 2 + SALT_SIZE;
+        if bytes.len() <= header_size {
+            return Err(anyhow!("Invalid paper wallet"))
+        }
+
+        let version = bytes[0];
+        if version != PAPER_WALLET_VERSION {
+            return Err(anyhow!("Unsupported paper wallet version {}", version))
+        }
+
+        let algorithm_version = bytes[1];
+        let salt = &bytes[2..header_size];
+        let hashed_passphrase = hash_password(passphrase, salt, algorithm_version)?;
+        let cipher = Cipher::new(&hashed_passphrase, None)?;
+        let content = cipher.decrypt_value(&bytes[header_size..]).context("Invalid passphrase provided for this paper wallet")?;
+
+        Ok(Self::from_bytes(&content)?)
+    }
+}
+
+impl Serializer for PaperWallet {
+    fn read(reader: &mut Reader) -> Result<Self, ReaderError> {
+        Ok(Self {
+            network: Network::read(reader)?,
+            private_key: PrivateKey::read(reader)?
+        })
+    }
+
+    fn write(&self, writer: &mut Writer) {
+        self.network.write(writer);
+        self.private_key.write(writer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_paper(network: Network) -> PaperWallet {
+        PaperWallet::new(network, KeyPair::new().get_private_key().clone())
+    }
+
+    #[test]
+    fn test_paper_wallet_round_trip() {
+        let paper = new_paper(Network::Testnet);
+        let encrypted = paper.to_encrypted_string("passphrase".to_owned()).unwrap();
+        assert!(encrypted.starts_with(PAPER_WALLET_PREFIX));
+
+        // Surrounding whitespaces from a scanner or a text file are ignored
+        let decrypted = PaperWallet::from_encrypted_string(&format!(" {}\n", encrypted), "passphrase".to_owned()).unwrap();
+        assert_eq!(decrypted.network, Network::Testnet);
+        assert_eq!(decrypted.private_key.as_scalar(), paper.private_key.as_scalar());
+        assert_eq!(decrypted.get_address(), paper.get_address());
+    }
+
+    #[test]
+    fn test_paper_wallet_salted() {
+        // The same key is never encoded twice the same way
+        let paper = new_paper(Network::Mainnet);
+        let first = paper.to_encrypted_string("passphrase".to_owned()).unwrap();
+        let second = paper.to_encrypted_string("passphrase".to_owned()).unwrap();
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn test_paper_wallet_wrong_passphrase() {
+        let paper = new_paper(Network::Mainnet);
+        let encrypted = paper.to_encrypted_string("passphrase".to_owned()).unwrap();
+        assert!(PaperWallet::from_encrypted_string(&encrypted, "wrong".to_owned()).is_err());
+    }
+
+    #[test]
+    fn test_paper_wallet_invalid_text() {
+        let paper = new_paper(Network::Mainnet);
+        let encrypted = paper.to_encrypted_string("passphrase".to_owned()).unwrap();
+
+        // Missing prefix
+        let without_prefix = encrypted.strip_prefix(PAPER_WALLET_PREFIX).unwrap();
+        assert!(PaperWallet::from_encrypted_string(without_prefix, "passphrase".to_owned()).is_err());
+        // Truncated content
+        assert!(PaperWallet::from_encrypted_string(&encrypted[..encrypted.len() - 2], "passphrase".to_owned()).is_err());
+        // Only the header
+        let header = format!("{}{}", PAPER_WALLET_PREFIX, hex::encode([PAPER_WALLET_VERSION; 2 + SALT_SIZE]));
+        assert!(PaperWallet::from_encrypted_string(&header, "passphrase".to_owned()).is_err());
+    }
+}
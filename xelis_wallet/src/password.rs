@@ -0,0 +1,118 @@
+use std::{fmt, time::{Duration, Instant}};
+use argon2::{Algorithm, Argon2, Params, Version};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use xelis_common::serializer::{Reader, ReaderError, Serializer, Writer};
+use crate::{
+    config::{
+        PASSWORD_CALIBRATION_START_MEMORY,
+        PASSWORD_HASH_SIZE,
+        PASSWORD_MAX_MEMORY,
+        PASSWORD_MIN_MEMORY,
+        SALT_SIZE
+    },
+    wallet::WalletError
+};
+
+// Argon2id parameters used to derive the key protecting the master key from the password
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PasswordParams {
+    // Memory used in KiB
+    pub memory: u32,
+    pub iterations: u32,
+    // Lanes of the memory
+    pub parallelism: u32
+}
+
+impl PasswordParams {
+    pub const fn new(memory: u32, iterations: u32, parallelism: u32) -> Self {
+        Self {
+            memory,
+            iterations,
+            parallelism
+        }
+    }
+
+    // Verify that the parameters can be used and aren't weaker than the version 0 ones
+    pub fn validate(&self) -> Result<(), WalletError> {
+        if self.memory < PASSWORD_MIN_MEMORY || self.memory > PASSWORD_MAX_MEMORY {
+            return Err(WalletError::InvalidPasswordParams(format!("memory must be between {} and {} KiB", PASSWORD_MIN_MEMORY, PASSWORD_MAX_MEMORY)))
+        }
+
+        if self.iterations == 0 || self.parallelism == 0 {
+            return Err(WalletError::InvalidPasswordParams("iterations and parallelism can't be zero".to_owned()))
+        }
+
+        self.to_algorithm().map(|_| ())
+    }
+
+    fn to_algorithm(&self) -> Result<Argon2<'static>, WalletError> {
+        let params = Params::new(self.memory, self.iterations, self.parallelism, Some(PASSWORD_HASH_SIZE))
+            .map_err(|e| WalletError::InvalidPasswordParams(e.to_string()))?;
+        Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+    }
+
+    // Hash the password into the key used to encrypt the master key
+    pub fn hash(&self, password: &[u8], salt: &[u8]) -> Result<[u8; PASSWORD_HASH_SIZE], WalletError> {
+        let mut output = [0; PASSWORD_HASH_SIZE];
+        self.to_algorithm()?
+            .hash_password_into(password, salt, &mut output)
+            .map_err(|e| WalletError::AlgorithmHashingError(e.to_string()))?;
+        Ok(output)
+    }
+
+    // Time needed to hash a password with these parameters on this hardware
+    fn measure(&self) -> Result<Duration, WalletError> {
+        let start = Instant::now();
+        self.hash(b"calibration", &[0; SALT_SIZE])?;
+        Ok(start.elapsed())
+    }
+
+    // Find the parameters giving an unlock time close to the target on this hardware
+    // Memory is increased first as it is the most costly for an attacker,
+    // then the iterations are added to fill the remaining time
+    // This is blocking and takes a few times the target to complete
+    pub fn calibrate(target: Duration, parallelism: u32) -> Result<Self, WalletError> {
+        let mut params = Self::new(PASSWORD_CALIBRATION_START_MEMORY, 1, parallelism);
+        let mut elapsed = params.measure()?;
+        // Keep at least a quarter of the target for the iterations
+        while elapsed * 4 < target && params.memory * 2 <= PASSWORD_MAX_MEMORY {
+            params.memory *= 2;
+            elapsed = params.measure()?;
+        }
+
+        // The time grows linearly with the iterations
+        let per_iteration = elapsed.as_secs_f64().max(f64::EPSILON);
+        params.iterations = ((target.as_secs_f64() / per_iteration) as u32).max(1);
+        debug!("Calibrated password params: {} ({:?} per iteration)", params, elapsed);
+
+        params.validate()?;
+        Ok(params)
+    }
+}
+
+impl fmt::Display for PasswordParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} KiB, {} iterations, {} lanes", self.memory, self.iterations, self.parallelism)
+    }
+}
+
+impl Serializer for PasswordParams {
+    fn read(reader: &mut Reader) -> Result<Self, ReaderError> {
+        Ok(Self {
+            memory: reader.read_u32()?,
+            iterations: reader.read_u32()?,
+            parallelism: reader.read_u32()?
+        })
+    }
+
+    fn write(&self, writer: &mut Writer) {
+        writer.write_u32(&self.memory);
+        writer.write_u32(&self.iterations);
+        writer.write_u32(&self.parallelism);
+    }
+
+    fn size(&self) -> usize {
+        self.memory.size() + self.iterations.size() + self.parallelism.size()
+    }
+}
@@ -0,0 +1,170 @@
+use std::{collections::HashMap, time::Duration};
+use async_trait::async_trait;
+use log::{debug, trace, warn};
+use reqwest::Client as HttpClient;
+use serde_json::Value;
+use thiserror::Error;
+use tokio::sync::Mutex;
+use xelis_common::{
+    config::XELIS_ASSET,
+    crypto::Hash,
+    time::{get_current_time_in_seconds, TimestampSeconds}
+};
+
+// Currency used when none is configured
+pub const DEFAULT_PRICE_CURRENCY: &str = "usd";
+// Delay in seconds before requesting again the price of an asset
+pub const DEFAULT_PRICE_REFRESH_INTERVAL: u64 = 300;
+// Maximum time in seconds to wait for the price provider
+pub const PRICE_REQUEST_TIMEOUT: u64 = 5;
+
+#[derive(Debug, Error)]
+pub enum PriceError {
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+    #[error("Invalid response from price provider")]
+    InvalidResponse
+}
+
+// Source of the price of an asset in a fiat currency
+// Implement it to plug another price API
+#[async_trait]
+pub trait PriceProvider: Send + Sync {
+    // Price of one unit of the asset, None if the asset is not supported
+    async fn get_price(&self, asset: &Hash, currency: &str) -> Result<Option<f64>, PriceError>;
+}
+
+// Provider using an HTTP API compatible with CoinGecko `simple/price` endpoint
+// Request: <url>?ids=<id>&vs_currencies=<currency>
+// Response: { "<id>": { "<currency>": <price> } }
+pub struct HttpPriceProvider {
+    client: HttpClient,
+    url: String,
+    // API id for each asset supported
+    ids: HashMap<Hash, String>
+}
+
+impl HttpPriceProvider {
+    pub fn new(url: String) -> Result<Self, PriceError> {
+        let mut ids = HashMap::new();
+        ids.insert(XELIS_ASSET, "xelis".to_owned());
+
+        // A slow provider must not block the commands displaying the prices
+        let client = HttpClient::builder()
+            .timeout(Duration::from_secs(PRICE_REQUEST_TIMEOUT))
+            .build()?;
+
+        Ok(Self {
+            client,
+            url,
+            ids
+        })
+    }
+
+    // Register the API id for an asset
+    pub fn with_asset(mut self, asset: Hash, id: String) -> Self {
+        self.ids.insert(asset, id);
+        self
+    }
+}
+
+#[async_trait]
+impl PriceProvider for HttpPriceProvider {
+    async fn get_price(&self, asset: &Hash, currency: &str) -> Result<Option<f64>, PriceError> {
+        let id = match self.ids.get(asset) {
+            Some(id) => id,
+            None => return Ok(None)
+        };
+
+        trace!("requesting price of {} in {}", id, currency);
+        let response: Value = self.client.get(&self.url)
+            .query(&[("ids", id.as_str()), ("vs_currencies", currency)])
+            .send().await?
+            .error_for_status()?
+            .json().await?;
+
+        response.get(id)
+            .and_then(|v| v.get(currency))
+            .and_then(Value::as_f64)
+            .map(Some)
+            .ok_or(PriceError::InvalidResponse)
+    }
+}
+
+// Cache the prices returned by a provider to not request it for each display
+pub struct PriceFeed {
+    provider: Box<dyn PriceProvider>,
+    currency: String,
+    // in seconds
+    refresh_interval: u64,
+    cache: Mutex<HashMap<Hash, (f64, TimestampSeconds)>>
+}
+
+impl PriceFeed {
+    pub fn new(provider: Box<dyn PriceProvider>, currency: String, refresh_interval: u64) -> Self {
+        Self {
+            provider,
+            currency: currency.to_lowercase(),
+            refresh_interval,
+            cache: Mutex::new(HashMap::new())
+        }
+    }
+
+    pub fn get_currency(&self) -> &str {
+        &self.currency
+    }
+
+    // Retrieve the price of an asset, from cache if it's recent enough
+    // If the provider is unreachable, the last known price is returned
+    pub async fn get_price(&self, asset: &Hash) -> Option<f64> {
+        let now = get_current_time_in_seconds();
+        {
+            let cache = self.cache.lock().await;
+            if let Some((price, timestamp)) = cache.get(asset) {
+                if timestamp + self.refresh_interval > now {
+                    return Some(*price)
+                }
+            }
+        }
+
+        // The cache is not locked during the request
+        match self.provider.get_price(asset, &self.currency).await {
+            Ok(Some(price)) => {
+                debug!("Price of {} is {} {}", asset, price, self.currency);
+                self.cache.lock().await.insert(asset.clone(), (price, now));
+                Some(price)
+            },
+            Ok(None) => None,
+            Err(e) => {
+                warn!("Error while fetching price of {}: {}", asset, e);
+                self.cache.lock().await.get(asset).map(|(price, _)| *price)
+            }
+        }
+    }
+
+    // Retrieve the prices of several assets, the unsupported ones are not returned
+    // Must be called before locking the wallet as the provider may be slow
+    pub async fn get_prices<'a>(&self, assets: impl IntoIterator<Item = &'a Hash>) -> HashMap<Hash, f64> {
+        let mut prices = HashMap::new();
+        for asset in assets {
+            if prices.contains_key(asset) {
+                continue;
+            }
+
+            if let Some(price) = self.get_price(asset).await {
+                prices.insert(asset.clone(), price);
+            }
+        }
+        prices
+    }
+
+    // Approximate value of an atomic amount
+    pub fn value_of(price: f64, amount: u64, decimals: u8) -> f64 {
+        amount as f64 / 10f64.powi(decimals as i32) * price
+    }
+
+    // Format a value in the configured currency
+    pub fn format_value(&self, value: f64) -> String {
+        format!("~{:.2} {}", value, self.currency.to_uppercase())
+    }
+}
@@ -0,0 +1,192 @@
+use std::str::FromStr;
+use xelis_common::{
+    crypto::{Address, Hash},
+    serializer::{Reader, ReaderError, Serializer, Writer},
+    time::TimestampSeconds
+};
+
+// A transfer registered by the user to be executed later by the wallet
+// If an interval is set, it is executed again after each interval
+#[derive(Debug, Clone)]
+pub struct ScheduledPayment {
+    id: u64,
+    destination: Address,
+    asset: Hash,
+    amount: u64,
+    // Timestamp in seconds of the next execution
+    next_execution: TimestampSeconds,
+    // Interval in seconds between two executions, None for a one-shot payment
+    interval: Option<u64>
+}
+
+impl ScheduledPayment {
+    pub fn new(id: u64, destination: Address, asset: Hash, amount: u64, next_execution: TimestampSeconds, interval: Option<u64>) -> Self {
+        Self {
+            id,
+            destination,
+            asset,
+            amount,
+            next_execution,
+            interval
+        }
+    }
+
+    pub fn get_id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn get_destination(&self) -> &Address {
+        &self.destination
+    }
+
+    pub fn get_asset(&self) -> &Hash {
+        &self.asset
+    }
+
+    pub fn get_amount(&self) -> u64 {
+        self.amount
+    }
+
+    pub fn get_next_execution(&self) -> TimestampSeconds {
+        self.next_execution
+    }
+
+    pub fn get_interval(&self) -> Option<u64> {
+        self.interval
+    }
+
+    // Check if the payment should be executed now
+    pub fn is_due(&self, now: TimestampSeconds) -> bool {
+        self.next_execution <= now
+    }
+
+    // Schedule the next execution after the current one
+    // Returns false if it was a one-shot payment
+    // Missed executions (wallet offline) are skipped to not send them all at once
+    pub fn reschedule(&mut self, now: TimestampSeconds) -> bool {
+        match self.interval {
+            Some(interval) if interval > 0 => {
+                while self.next_execution <= now {
+                    self.next_execution += interval;
+                }
+                true
+            },
+            _ => false
+        }
+    }
+}
+
+impl Serializer for ScheduledPayment {
+    fn read(reader: &mut Reader) -> Result<Self, ReaderError> {
+        let id = reader.read_u64()?;
+        let destination = Address::from_str(&String::read(reader)?)
+            .map_err(|_| ReaderError::InvalidValue)?;
+        let asset = reader.read_hash()?;
+        let amount = reader.read_u64()?;
+        let next_execution = reader.read_u64()?;
+        let interval = Option::read(reader)?;
+
+        Ok(Self {
+            id,
+            destination,
+            asset,
+            amount,
+            next_execution,
+            interval
+        })
+    }
+
+    fn write(&self, writer: &mut Writer) {
+        writer.write_u64(&self.id);
+        self.destination.to_string().write(writer);
+        writer.write_hash(&self.asset);
+        writer.write_u64(&self.amount);
+        writer.write_u64(&self.next_execution);
+        self.interval.write(writer);
+    }
+}
+
+// Parse a duration such as 30s, 10m, 2h, 1d or 1w into seconds
+// A number without unit is in seconds
+pub fn parse_duration(value: &str) -> Option<u64> {
+    let value = value.trim();
+    let (number, multiplier) = match value.chars().last()? {
+        's' => (&value[..value.len() - 1], 1),
+        'm' => (&value[..value.len() - 1], 60),
+        'h' => (&value[..value.len() - 1], 60 * 60),
+        'd' => (&value[..value.len() - 1], 24 * 60 * 60),
+        'w' => (&value[..value.len() - 1], 7 * 24 * 60 * 60),
+        _ => (value, 1)
+    };
+
+    number.parse::<u64>().ok()?.checked_mul(multiplier)
+}
+
+#[cfg(test)]
+mod tests {
+    use xelis_common::{
+        config::XELIS_ASSET,
+        crypto::KeyPair,
+        network::Network
+    };
+    use super::*;
+
+    fn new_payment(next_execution: TimestampSeconds, interval: Option<u64>) -> ScheduledPayment {
+        let destination = KeyPair::new().get_public_key().to_address(Network::Mainnet);
+        ScheduledPayment::new(0, destination, XELIS_ASSET, 100, next_execution, interval)
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("30"), Some(30));
+        assert_eq!(parse_duration("30s"), Some(30));
+        assert_eq!(parse_duration("10m"), Some(10 * 60));
+        assert_eq!(parse_duration("2h"), Some(2 * 60 * 60));
+        assert_eq!(parse_duration("1d"), Some(24 * 60 * 60));
+        assert_eq!(parse_duration(" 1w "), Some(7 * 24 * 60 * 60));
+        assert_eq!(parse_duration("0"), Some(0));
+    }
+
+    #[test]
+    fn test_parse_invalid_duration() {
+        assert_eq!(parse_duration(""), None);
+        assert_eq!(parse_duration("s"), None);
+        assert_eq!(parse_duration("10y"), None);
+        assert_eq!(parse_duration("-5m"), None);
+        assert_eq!(parse_duration("1.5h"), None);
+        assert_eq!(parse_duration("m10"), None);
+        // Overflow when converted in seconds
+        assert_eq!(parse_duration(&format!("{}w", u64::MAX)), None);
+    }
+
+    #[test]
+    fn test_reschedule_one_shot() {
+        let mut payment = new_payment(100, None);
+        assert!(payment.is_due(100));
+        assert!(!payment.reschedule(100));
+        assert_eq!(payment.get_next_execution(), 100);
+
+        // A zero interval is handled as a one-shot payment
+        let mut payment = new_payment(100, Some(0));
+        assert!(!payment.reschedule(100));
+        assert_eq!(payment.get_next_execution(), 100);
+    }
+
+    #[test]
+    fn test_reschedule_interval() {
+        let mut payment = new_payment(100, Some(60));
+        assert!(!payment.is_due(99));
+        assert!(payment.reschedule(100));
+        assert_eq!(payment.get_next_execution(), 160);
+        assert!(!payment.is_due(159));
+    }
+
+    #[test]
+    fn test_reschedule_skips_missed_executions() {
+        let mut payment = new_payment(100, Some(60));
+        // Wallet was offline during several intervals
+        assert!(payment.reschedule(350));
+        assert_eq!(payment.get_next_execution(), 400);
+        assert!(!payment.is_due(350));
+    }
+}
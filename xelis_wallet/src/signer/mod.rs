@@ -0,0 +1,289 @@
+use std::sync::{Arc, RwLock};
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use log::{debug, error};
+use thiserror::Error;
+use xelis_common::{
+    crypto::PublicKey,
+    transaction::Transaction
+};
+use crate::transaction_builder::UnsignedTransaction;
+
+#[derive(Debug, Error)]
+pub enum SignerError {
+    #[error("No signing device found")]
+    DeviceNotFound,
+    #[error("Unknown hardware signer backend '{}'", _0)]
+    UnknownBackend(String),
+    #[error("This operation is not supported by the signer")]
+    Unsupported,
+    #[error("Error while communicating with the device: {}", _0)]
+    Communication(String),
+    #[error("Invalid response from the device")]
+    InvalidResponse,
+    #[error("Request was rejected on the device")]
+    Rejected,
+    #[error("Device returned the status word {:#06x}", _0)]
+    Status(u16)
+}
+
+// Holds the private key of a wallet and signs its transactions
+// The wallet storage only knows the public key, so the key never leaves the signer
+#[async_trait]
+pub trait KeySigner: Send + Sync {
+    // Name displayed to the user
+    fn get_name(&self) -> &str;
+
+    // Public key of the account used to sign
+    async fn get_public_key(&self) -> Result<PublicKey, SignerError>;
+
+    // Build the proofs and sign the transaction
+    // The signer is responsible to show its content to the user before signing it
+    async fn sign_transaction(&self, unsigned: &UnsignedTransaction) -> Result<Transaction, SignerError>;
+
+    // Show the address on the signer so the user can verify it
+    async fn confirm_address(&self, _mainnet: bool) -> Result<(), SignerError> {
+        Err(SignerError::Unsupported)
+    }
+}
+
+// A device holding the private key: Ledger, Trezor-like wallets or an HSM
+// The device builds the proofs itself, nothing in the transaction builder depends on it
+#[async_trait]
+pub trait HardwareSigner: Send + Sync {
+    // Model of the device
+    fn get_name(&self) -> &str;
+
+    async fn get_public_key(&self) -> Result<PublicKey, SignerError>;
+
+    // The device must show the transfers and the fee to be confirmed by the user
+    async fn sign_tx(&self, unsigned: &UnsignedTransaction) -> Result<Transaction, SignerError>;
+
+    // Display the address on the device, an error is returned if the user rejects it
+    async fn confirm_address(&self, mainnet: bool) -> Result<(), SignerError>;
+}
+
+// Look for the devices of one kind connected to this machine
+// Implement it and register it using `register_backend` to support a new device
+pub trait HardwareSignerBackend: Send + Sync {
+    // Name used to select the backend, in lowercase
+    fn get_name(&self) -> &str;
+
+    // Devices found, none is not an error
+    fn discover(&self) -> Result<Vec<Arc<dyn HardwareSigner>>, SignerError>;
+}
+
+// No backend is compiled with the wallet, they are registered by the application supporting the devices
+lazy_static! {
+    static ref BACKENDS: RwLock<Vec<Arc<dyn HardwareSignerBackend>>> = RwLock::new(Vec::new());
+}
+
+// Register a backend, it replaces the one using the same name
+pub fn register_backend(backend: Arc<dyn HardwareSignerBackend>) {
+    let mut backends = BACKENDS.write().unwrap_or_else(|e| e.into_inner());
+    backends.retain(|b| b.get_name() != backend.get_name());
+    backends.push(backend);
+}
+
+// Names of all the backends available
+pub fn get_backends() -> Vec<String> {
+    BACKENDS.read().unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .map(|backend| backend.get_name().to_owned())
+        .collect()
+}
+
+// Find the devices of all backends, a failing backend is skipped
+pub fn discover_signers() -> Vec<Arc<dyn HardwareSigner>> {
+    let backends = BACKENDS.read().unwrap_or_else(|e| e.into_inner()).clone();
+    let mut signers = Vec::new();
+    for backend in backends {
+        match backend.discover() {
+            Ok(devices) => {
+                debug!("{} device(s) found for backend {}", devices.len(), backend.get_name());
+                signers.extend(devices);
+            },
+            Err(e) => error!("Error while looking for {} devices: {}", backend.get_name(), e)
+        }
+    }
+
+    signers
+}
+
+// First device found for the requested backend
+pub fn discover_signer(name: &str) -> Result<Arc<dyn HardwareSigner>, SignerError> {
+    let backend = BACKENDS.read().unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .find(|backend| backend.get_name() == name)
+        .cloned()
+        .ok_or_else(|| SignerError::UnknownBackend(name.to_owned()))?;
+
+    backend.discover()?.into_iter().next().ok_or(SignerError::DeviceNotFound)
+}
+
+// Use a hardware device as signer of a wallet
+pub struct HardwareKeySigner {
+    device: Arc<dyn HardwareSigner>
+}
+
+impl HardwareKeySigner {
+    pub fn new(device: Arc<dyn HardwareSigner>) -> Self {
+        Self {
+            device
+        }
+    }
+}
+
+#[async_trait]
+impl KeySigner for HardwareKeySigner {
+    fn get_name(&self) -> &str {
+        self.device.get_name()
+    }
+
+    async fn get_public_key(&self) -> Result<PublicKey, SignerError> {
+        self.device.get_public_key().await
+    }
+
+    async fn sign_transaction(&self, unsigned: &UnsignedTransaction) -> Result<Transaction, SignerError> {
+        self.device.sign_tx(unsigned).await
+    }
+
+    async fn confirm_address(&self, mainnet: bool) -> Result<(), SignerError> {
+        self.device.confirm_address(mainnet).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+    use xelis_common::{
+        account::CiphertextCache,
+        config::{COIN_VALUE, XELIS_ASSET},
+        crypto::{Hash, KeyPair},
+        network::Network,
+        transaction::{
+            builder::{FeeBuilder, TransactionBuilder, TransactionTypeBuilder, TransferBuilder},
+            Reference
+        }
+    };
+    use crate::{storage::Balance, transaction_builder::TransactionBuilderState};
+    use super::*;
+
+    // Device keeping its key in memory, it knows the plaintext of its balances
+    struct MockDevice {
+        keypair: KeyPair,
+        balances: HashMap<Hash, u64>,
+        approve: bool
+    }
+
+    #[async_trait]
+    impl HardwareSigner for MockDevice {
+        fn get_name(&self) -> &str {
+            "Mock"
+        }
+
+        async fn get_public_key(&self) -> Result<PublicKey, SignerError> {
+            Ok(self.keypair.get_public_key().compress())
+        }
+
+        async fn sign_tx(&self, unsigned: &UnsignedTransaction) -> Result<Transaction, SignerError> {
+            if !self.approve {
+                return Err(SignerError::Rejected)
+            }
+
+            let mut state = TransactionBuilderState::new(unsigned.network, unsigned.reference.clone(), unsigned.nonce);
+            for (asset, ciphertext) in unsigned.balances.iter() {
+                let amount = *self.balances.get(asset).ok_or(SignerError::InvalidResponse)?;
+                state.add_balance(asset.clone(), Balance::new(amount, CiphertextCache::Compressed(ciphertext.clone())));
+            }
+            state.set_registered_keys(unsigned.registered_keys.clone());
+
+            let builder = TransactionBuilder::new(0, unsigned.source.clone(), unsigned.tx_type.clone(), unsigned.fee.clone());
+            builder.build(&mut state, &self.keypair).map_err(|e| SignerError::Communication(e.to_string()))
+        }
+
+        async fn confirm_address(&self, _: bool) -> Result<(), SignerError> {
+            Ok(())
+        }
+    }
+
+    struct MockBackend {
+        keypair: KeyPair,
+        approve: bool
+    }
+
+    impl HardwareSignerBackend for MockBackend {
+        fn get_name(&self) -> &str {
+            if self.approve { "mock" } else { "mock-reject" }
+        }
+
+        fn discover(&self) -> Result<Vec<Arc<dyn HardwareSigner>>, SignerError> {
+            Ok(vec![Arc::new(MockDevice {
+                keypair: self.keypair.clone(),
+                balances: HashMap::from([(XELIS_ASSET, 100 * COIN_VALUE)]),
+                approve: self.approve
+            })])
+        }
+    }
+
+    // Same content as the one exported by `build_unsigned_transaction`
+    fn create_unsigned(keypair: &KeyPair) -> UnsignedTransaction {
+        let destination = KeyPair::new().get_public_key().to_address(Network::Testnet);
+        UnsignedTransaction {
+            network: Network::Testnet,
+            source: keypair.get_public_key().compress(),
+            tx_type: TransactionTypeBuilder::Transfers(vec![TransferBuilder {
+                asset: XELIS_ASSET,
+                amount: COIN_VALUE,
+                destination,
+                extra_data: None
+            }]),
+            fee: FeeBuilder::Multiplier(1f64),
+            nonce: 5,
+            reference: Reference {
+                topoheight: 0,
+                hash: Hash::zero()
+            },
+            balances: HashMap::from([(XELIS_ASSET, keypair.get_public_key().encrypt(100 * COIN_VALUE).compress())]),
+            registered_keys: HashSet::new()
+        }
+    }
+
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(future)
+    }
+
+    #[test]
+    fn test_sign_with_registered_backend() {
+        let keypair = KeyPair::new();
+        register_backend(Arc::new(MockBackend { keypair: keypair.clone(), approve: true }));
+        assert!(get_backends().contains(&"mock".to_owned()));
+
+        let signer = HardwareKeySigner::new(discover_signer("mock").unwrap());
+        block_on(async {
+            assert_eq!(signer.get_public_key().await.unwrap(), keypair.get_public_key().compress());
+            signer.confirm_address(false).await.unwrap();
+
+            let unsigned = create_unsigned(&keypair);
+            let tx = signer.sign_transaction(&unsigned).await.unwrap();
+            assert_eq!(*tx.get_source(), unsigned.source);
+            assert_eq!(tx.get_nonce(), unsigned.nonce);
+            assert!(tx.verify_stateless::<()>().is_ok());
+        });
+    }
+
+    #[test]
+    fn test_sign_rejected_on_device() {
+        let keypair = KeyPair::new();
+        register_backend(Arc::new(MockBackend { keypair: keypair.clone(), approve: false }));
+
+        let signer = HardwareKeySigner::new(discover_signer("mock-reject").unwrap());
+        let unsigned = create_unsigned(&keypair);
+        assert!(matches!(block_on(signer.sign_transaction(&unsigned)), Err(SignerError::Rejected)));
+    }
+
+    #[test]
+    fn test_unknown_backend() {
+        assert!(matches!(discover_signer("unknown"), Err(SignerError::UnknownBackend(_))));
+    }
+}
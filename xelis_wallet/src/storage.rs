@@ -1,14 +1,11 @@
 use std::{
     collections::{HashMap, HashSet, VecDeque},
-    num::NonZeroUsize
+    num::NonZeroUsize,
+    sync::Arc
 };
 use indexmap::IndexMap;
-use log::trace;
+use log::{debug, trace, warn};
 use lru::LruCache;
-use sled::{
-    Tree,
-    Db
-};
 use tokio::sync::Mutex;
 use xelis_common::{
     account::CiphertextCache,
@@ -22,9 +19,11 @@ use xelis_common::{
     },
     crypto::{
         elgamal::CompressedCiphertext,
+        Address,
         Hash,
         PrivateKey,
-        PublicKey
+        PublicKey,
+        HASH_SIZE
     },
     network::Network,
     serializer::{
@@ -32,7 +31,8 @@ use xelis_common::{
         ReaderError,
         Serializer,
         Writer
-    }
+    },
+    time::TimestampSeconds
 };
 use anyhow::{
     Context,
@@ -40,13 +40,21 @@ use anyhow::{
     anyhow
 };
 use crate::{
+    api_token::ApiToken,
+    backend::{open_backend, Batch, StorageBackend, Tree},
     cipher::Cipher,
-    config::SALT_SIZE,
+    config::{get_password_params, PASSWORD_ALGORITHM_VERSION_CUSTOM, SALT_SIZE},
+    migration::{migrate, CURRENT_SCHEMA_VERSION},
     entry::{
         EntryData,
+        PendingTransaction,
         TransactionEntry,
         Transfer
     },
+    draft::DraftTransaction,
+    scheduler::ScheduledPayment,
+    password::PasswordParams,
+    template::TransferTemplate,
     wallet::WalletError
 };
 use log::error;
@@ -58,7 +66,19 @@ const SALT_KEY: &[u8] = b"SALT";
 const PASSWORD_SALT_KEY: &[u8] = b"PSALT";
 // Master key to encrypt/decrypt while interacting with the storage 
 const MASTER_KEY: &[u8] = b"MKEY";
+// Version of the algorithm used to hash the password
+const PASSWORD_ALGORITHM_VERSION_KEY: &[u8] = b"PALGV";
+// Parameters of the password hashing, only set for the custom algorithm version
+const PASSWORD_PARAMS_KEY: &[u8] = b"PALGP";
+// Only set if a keyfile is mixed into the password, it's a hint to ask it before opening
+const KEYFILE_KEY: &[u8] = b"KEYF";
 const PRIVATE_KEY: &[u8] = b"PKEY";
+// BIP39 mnemonic the private key was derived from
+const MNEMONIC_KEY: &[u8] = b"MNEMO";
+// Only set for watch-only wallets
+const PUBLIC_KEY: &[u8] = b"PUBK";
+// Only set for view-only wallets created from a private key
+const VIEW_ONLY_KEY: &[u8] = b"VIEWO";
 
 // const used for online mode
 // represent the daemon topoheight
@@ -66,10 +86,54 @@ const TOPOHEIGHT_KEY: &[u8] = b"TOPH";
 // represent the daemon top block hash
 const TOP_BLOCK_HASH_KEY: &[u8] = b"TOPBH";
 const NETWORK: &[u8] = b"NET";
+// Daemon address selected by the user for this wallet
+const DAEMON_ADDRESS_KEY: &[u8] = b"DAEMON";
+// Next id to use for a scheduled payment
+const SCHEDULE_ID_KEY: &[u8] = b"SCHID";
+// Version of the storage layout, used to apply migrations
+const SCHEMA_VERSION_KEY: &[u8] = b"SCHEMA";
+// New master key encrypted with the current one, only set while re-encrypting the storage
+const REENCRYPT_KEY: &[u8] = b"REENC";
+
+// Maximum number of entries written at once while re-encrypting the storage
+const REENCRYPT_BATCH_SIZE: usize = 1024;
 
 // Default cache size
 const DEFAULT_CACHE_SIZE: usize = 100;
 
+// How the keys of a tree are written
+#[derive(Clone, Copy)]
+enum KeyFormat {
+    // Hashed with the storage salt, they don't depend on the master key
+    Hashed,
+    // Encrypted to be read back, see `EncryptedStorage::create_encrypted_key`
+    Encrypted
+}
+
+// Trees opened for each account with the format of their keys
+// Custom trees always use encrypted keys
+const ACCOUNT_TREES: [(&str, KeyFormat); 19] = [
+    ("transactions", KeyFormat::Hashed),
+    ("transactions_indexes", KeyFormat::Encrypted),
+    ("balances", KeyFormat::Hashed),
+    ("extra", KeyFormat::Hashed),
+    ("assets", KeyFormat::Encrypted),
+    ("changes_topoheight", KeyFormat::Encrypted),
+    ("address_book", KeyFormat::Encrypted),
+    ("pending_transactions", KeyFormat::Hashed),
+    ("replaced_transactions", KeyFormat::Hashed),
+    ("scheduled_payments", KeyFormat::Hashed),
+    ("transactions_labels", KeyFormat::Hashed),
+    ("addresses_labels", KeyFormat::Hashed),
+    ("webhooks", KeyFormat::Encrypted),
+    ("api_tokens", KeyFormat::Encrypted),
+    ("drafts", KeyFormat::Encrypted),
+    ("templates", KeyFormat::Encrypted),
+    ("subaddresses", KeyFormat::Hashed),
+    ("encrypted_balances", KeyFormat::Hashed),
+    ("notified_transactions", KeyFormat::Hashed)
+];
+
 #[derive(Debug, Clone)]
 pub struct Balance {
     pub amount: u64,
@@ -102,8 +166,33 @@ impl Serializer for Balance {
 }
 
 // Use this struct to get access to non-encrypted keys (such as salt for KDF and encrypted master key)
+#[derive(Clone)]
 pub struct Storage {
-    db: Db
+    backend: Arc<dyn StorageBackend>,
+    // tree containing the public keys
+    db: Tree
+}
+
+// Result of a storage maintenance
+#[derive(Debug, Default)]
+pub struct MaintenanceReport {
+    // Transactions above the synced topoheight (orphaned by a reorg)
+    pub orphaned_transactions: usize,
+    // Labels of the orphaned transactions removed
+    pub orphaned_labels: usize,
+    // Pending transactions already in history
+    pub executed_pending_transactions: usize,
+    // Transactions index had to be rebuilt
+    pub rebuilt_indexes: bool,
+    pub size_before: u64,
+    pub size_after: u64
+}
+
+impl MaintenanceReport {
+    // Bytes reclaimed on disk, 0 if the storage grew
+    pub fn reclaimed_space(&self) -> u64 {
+        self.size_before.saturating_sub(self.size_after)
+    }
 }
 
 // Implement an encrypted storage system 
@@ -112,6 +201,9 @@ pub struct EncryptedStorage {
     cipher: Cipher,
     // All transactions where this wallet is part of
     transactions: Tree,
+    // Transactions hashes indexed by their topoheight
+    // This allows to paginate the history without decrypting every transaction
+    transactions_indexes: Tree,
     // balances for each asset
     balances: Tree,
     // extra data (network, topoheight, etc)
@@ -120,6 +212,36 @@ pub struct EncryptedStorage {
     assets: Tree,
     // This tree is used to store all topoheight where a change in the wallet occured
     changes_topoheight: Tree,
+    // Contacts saved by the user (name -> address)
+    address_book: Tree,
+    // Transactions broadcasted but not yet executed in a block
+    pending_transactions: Tree,
+    // Pending transactions replaced by another one using the same nonce (replaced hash -> replacement hash)
+    replaced_transactions: Tree,
+    // Transfers scheduled by the user (id -> payment)
+    scheduled_payments: Tree,
+    // Notes set by the user on transactions (hash -> label)
+    transactions_labels: Tree,
+    // Notes set by the user on addresses (public key -> label)
+    addresses_labels: Tree,
+    // Webhooks notified on confirmed transfers (url -> timestamp added)
+    webhooks: Tree,
+    // Tokens giving a scoped access to the wallet API (name -> token)
+    api_tokens: Tree,
+    // Transactions saved by the user to be sent later (name -> draft)
+    drafts: Tree,
+    // Payments saved by the user to be sent again (name -> template)
+    templates: Tree,
+    // Receive subaddresses derived for this account (index -> label)
+    subaddresses: Tree,
+    // Balances of a watch-only wallet, they can't be decrypted without the private key
+    encrypted_balances: Tree,
+    // Transactions sent to the webhooks (hash -> topoheight)
+    notified_transactions: Tree,
+    // Accounts created in this wallet (index -> name)
+    accounts: Tree,
+    // Index of the account used by this storage
+    account: u32,
     // The inner storage
     inner: Storage,
     // Caches
@@ -137,12 +259,37 @@ pub struct EncryptedStorage {
 impl EncryptedStorage {
     pub fn new(inner: Storage, key: &[u8], salt: [u8; SALT_SIZE], network: Network) -> Result<Self> {
         let cipher = Cipher::new(key, Some(salt))?;
+        Self::new_for_account(inner, cipher, network, 0)
+    }
+
+    // Open the storage of an account
+    // Account 0 use the original trees names to stay compatible with existing wallets
+    fn new_for_account(inner: Storage, cipher: Cipher, network: Network, account: u32) -> Result<Self> {
+        let tree_name = |name: &str| Self::account_tree_name(&cipher, account, name);
+
         let mut storage = Self {
-            transactions: inner.db.open_tree(&cipher.hash_key("transactions"))?,
-            balances: inner.db.open_tree(&cipher.hash_key("balances"))?,
-            extra: inner.db.open_tree(&cipher.hash_key("extra"))?,
-            assets: inner.db.open_tree(&cipher.hash_key("assets"))?,
-            changes_topoheight: inner.db.open_tree(&cipher.hash_key("changes_topoheight"))?,
+            transactions: inner.open_tree(&tree_name("transactions"))?,
+            transactions_indexes: inner.open_tree(&tree_name("transactions_indexes"))?,
+            balances: inner.open_tree(&tree_name("balances"))?,
+            extra: inner.open_tree(&tree_name("extra"))?,
+            assets: inner.open_tree(&tree_name("assets"))?,
+            changes_topoheight: inner.open_tree(&tree_name("changes_topoheight"))?,
+            address_book: inner.open_tree(&tree_name("address_book"))?,
+            pending_transactions: inner.open_tree(&tree_name("pending_transactions"))?,
+            replaced_transactions: inner.open_tree(&tree_name("replaced_transactions"))?,
+            scheduled_payments: inner.open_tree(&tree_name("scheduled_payments"))?,
+            transactions_labels: inner.open_tree(&tree_name("transactions_labels"))?,
+            addresses_labels: inner.open_tree(&tree_name("addresses_labels"))?,
+            webhooks: inner.open_tree(&tree_name("webhooks"))?,
+            api_tokens: inner.open_tree(&tree_name("api_tokens"))?,
+            drafts: inner.open_tree(&tree_name("drafts"))?,
+            templates: inner.open_tree(&tree_name("templates"))?,
+            subaddresses: inner.open_tree(&tree_name("subaddresses"))?,
+            encrypted_balances: inner.open_tree(&tree_name("encrypted_balances"))?,
+            notified_transactions: inner.open_tree(&tree_name("notified_transactions"))?,
+            // Shared by all accounts
+            accounts: inner.open_tree(&cipher.hash_key("accounts"))?,
+            account,
             cipher,
             inner,
             balances_cache: Mutex::new(LruCache::new(NonZeroUsize::new(DEFAULT_CACHE_SIZE).unwrap())),
@@ -151,29 +298,389 @@ impl EncryptedStorage {
             synced_topoheight: None,
         };
 
+        // Trees of all accounts are restored when opening the main one
+        if account == 0 {
+            storage.rollback_reencryption()?;
+        }
+
         if storage.has_network()? {
             let storage_network = storage.get_network()?;
             if storage_network != network {
                 return Err(anyhow!("Network mismatch for this wallet storage (stored: {})!", storage_network));
             }
         } else {
+            // New storage, it's already using the latest layout
             storage.set_network(&network)?;
+            storage.set_schema_version(CURRENT_SCHEMA_VERSION)?;
         }
 
+        migrate(&mut storage)?;
+
         Ok(storage)
     }
 
+    // Hashed name of a tree of an account
+    // Account 0 use the original trees names to stay compatible with existing wallets
+    fn account_tree_name(cipher: &Cipher, account: u32, name: &str) -> [u8; HASH_SIZE] {
+        if account == 0 {
+            cipher.hash_key(name)
+        } else {
+            cipher.hash_key(format!("account_{}_{}", account, name))
+        }
+    }
+
+    // Re-encrypt all the entries of the wallet (every account) with a new master key
+    // Hashed keys only depend on the storage salt and are kept as is
+    // Entries are written in bounded batches, the new master key encrypted with the current one is saved first
+    // So if it fails or is interrupted, it is rolled back when opening the wallet with the previous password
+    // Custom password parameters are stored with the new version, or removed if not used anymore
+    pub fn reencrypt(&mut self, master_key: &[u8], encrypted_master_key: &[u8], password_salt: &[u8], encrypted_storage_salt: &[u8], algorithm_version: u8, password_params: Option<&PasswordParams>) -> Result<()> {
+        trace!("reencrypt storage");
+        let new_cipher = self.cipher.with_key(master_key)?;
+
+        let default_tree = self.inner.db.clone();
+        default_tree.insert(REENCRYPT_KEY, self.cipher.encrypt_value(master_key)?)?;
+        self.inner.backend.flush()?;
+
+        let count = self.reencrypt_trees(&self.cipher, &new_cipher).context("Error while re-encrypting storage")?;
+        debug!("re-encrypted {} entries", count);
+
+        // Switch to the new keys at once
+        let mut batch = Batch::new();
+        batch.insert(&default_tree, MASTER_KEY, encrypted_master_key);
+        batch.insert(&default_tree, PASSWORD_SALT_KEY, password_salt);
+        batch.insert(&default_tree, SALT_KEY, encrypted_storage_salt);
+        batch.insert(&default_tree, PASSWORD_ALGORITHM_VERSION_KEY, [algorithm_version]);
+        match password_params {
+            Some(params) => batch.insert(&default_tree, PASSWORD_PARAMS_KEY, params.to_bytes()),
+            None => batch.remove(&default_tree, PASSWORD_PARAMS_KEY)
+        };
+        batch.remove(&default_tree, REENCRYPT_KEY);
+        self.inner.backend.apply_batch(batch).context("Error while saving the new master key")?;
+
+        self.cipher = new_cipher;
+        Ok(())
+    }
+
+    // Restore the entries already re-encrypted by an interrupted `reencrypt`
+    // The previous master key is still the one saved, so they are encrypted back with it
+    fn rollback_reencryption(&self) -> Result<()> {
+        let default_tree = self.inner.db.clone();
+        let encrypted_key = match default_tree.get(REENCRYPT_KEY)? {
+            Some(key) => key,
+            None => return Ok(())
+        };
+
+        warn!("Re-encryption of the storage was interrupted, restoring the previous password");
+        let master_key = self.cipher.decrypt_value(&encrypted_key).context("Error while decrypting the interrupted re-encryption key")?;
+        let cipher = self.cipher.with_key(&master_key)?;
+        let count = self.reencrypt_trees(&cipher, &self.cipher).context("Error while restoring storage")?;
+        debug!("restored {} entries", count);
+
+        default_tree.remove(REENCRYPT_KEY)?;
+        self.inner.backend.flush()?;
+        Ok(())
+    }
+
+    // Re-encrypt the entries of every tree from a cipher to another
+    // Entries already readable with the new cipher are skipped, so it can be applied again after an interruption
+    fn reencrypt_trees(&self, from: &Cipher, to: &Cipher) -> Result<usize> {
+        let mut batch = Batch::new();
+        let mut pending = 0;
+        let mut count = 0;
+        for (tree, format) in self.get_trees_with_key_format(from, to)? {
+            for res in tree.iter() {
+                let (key, value) = res?;
+                // Key and value are written together, so the value is enough to know if it was done
+                if to.decrypt_value(&value).is_ok() {
+                    continue;
+                }
+
+                let new_value = to.encrypt_value(&from.decrypt_value(&value)?)?;
+                match format {
+                    KeyFormat::Hashed => batch.insert(&tree, &key, new_value),
+                    KeyFormat::Encrypted => {
+                        // Encrypted keys use the start of their hashed key as nonce
+                        if key.len() < Cipher::NONCE_SIZE {
+                            return Err(WalletError::InvalidEncryptedValue.into())
+                        }
+                        let mut nonce = [0u8; Cipher::NONCE_SIZE];
+                        nonce.copy_from_slice(&key[0..Cipher::NONCE_SIZE]);
+                        let new_key = to.encrypt_value_with_nonce(&from.decrypt_value(&key)?, &nonce)?;
+                        batch.remove(&tree, &key);
+                        batch.insert(&tree, new_key, new_value);
+                    }
+                }
+
+                count += 1;
+                pending += 1;
+                if pending >= REENCRYPT_BATCH_SIZE {
+                    self.inner.backend.apply_batch(std::mem::take(&mut batch))?;
+                    pending = 0;
+                }
+            }
+        }
+
+        if pending > 0 {
+            self.inner.backend.apply_batch(batch)?;
+        }
+
+        Ok(count)
+    }
+
+    // All the trees of the wallet with the format of their keys
+    // Accounts are read with both ciphers as the re-encryption may have been interrupted
+    fn get_trees_with_key_format(&self, from: &Cipher, to: &Cipher) -> Result<Vec<(Tree, KeyFormat)>> {
+        let accounts_name = self.cipher.hash_key("accounts");
+        let mut accounts = vec![0];
+        for res in self.accounts.iter().values() {
+            let value = res?;
+            let bytes = from.decrypt_value(&value).or_else(|_| to.decrypt_value(&value))?;
+            accounts.push(Reader::new(&bytes).read_u32()?);
+        }
+
+        // Shared by all accounts
+        let mut known = vec![(accounts_name.to_vec(), KeyFormat::Hashed)];
+        for account in accounts {
+            for (name, format) in ACCOUNT_TREES {
+                known.push((Self::account_tree_name(&self.cipher, account, name).to_vec(), format));
+            }
+        }
+
+        let default_name = self.inner.db.name();
+        let mut trees = Vec::new();
+        for name in self.inner.backend.tree_names()? {
+            if name.as_slice() == default_name {
+                continue;
+            }
+
+            // Any other tree was created through the custom data API
+            let format = known.iter()
+                .find(|(known, _)| *known == name)
+                .map(|(_, format)| *format)
+                .unwrap_or(KeyFormat::Encrypted);
+            trees.push((self.inner.open_tree(&name)?, format));
+        }
+
+        Ok(trees)
+    }
+
+    // Open the storage of another account from the same wallet
+    pub fn open_account(&self, account: u32) -> Result<Self> {
+        trace!("open account {}", account);
+        Self::new_for_account(self.inner.clone(), self.cipher.clone(), self.get_network()?, account)
+    }
+
+    // Index of the account used by this storage
+    pub fn get_account(&self) -> u32 {
+        self.account
+    }
+
+    // Register a new account and returns its index
+    pub fn add_account(&mut self, name: &str) -> Result<u32> {
+        let index = self.get_accounts()?.last().map(|(index, _)| index + 1).unwrap_or(1);
+        trace!("add account {} ({})", index, name);
+        // Value is prefixed by the index as the key is hashed
+        let mut writer = Writer::new();
+        writer.write_u32(&index);
+        writer.write_string(&name.to_owned());
+        self.save_to_disk(&self.accounts, &index.to_be_bytes(), writer.as_bytes())?;
+
+        Ok(index)
+    }
+
+    // Check if an account is registered
+    pub fn has_account(&self, account: u32) -> Result<bool> {
+        trace!("has account {}", account);
+        if account == 0 {
+            return Ok(true)
+        }
+
+        self.contains_data(&self.accounts, &account.to_be_bytes())
+    }
+
+    // Retrieve all accounts created ordered by index
+    // The main account (index 0) is not included
+    pub fn get_accounts(&self) -> Result<Vec<(u32, String)>> {
+        trace!("get accounts");
+        let mut accounts = Vec::new();
+        for res in self.accounts.iter().values() {
+            let value = res?;
+            let bytes = self.cipher.decrypt_value(&value)?;
+            let mut reader = Reader::new(&bytes);
+            let index = reader.read_u32()?;
+            let name = reader.read_string()?;
+            accounts.push((index, name));
+        }
+        accounts.sort_by_key(|(index, _)| *index);
+
+        Ok(accounts)
+    }
+
+    // Register a new subaddress and returns its index
+    // Index 0 is the main address, so subaddresses start at 1
+    pub fn add_subaddress(&mut self, label: &str) -> Result<u32> {
+        let index = self.get_subaddresses()?.last().map(|(index, _)| index + 1).unwrap_or(1);
+        trace!("add subaddress {} ({})", index, label);
+        // Value is prefixed by the index as the key is hashed
+        let mut writer = Writer::new();
+        writer.write_u32(&index);
+        writer.write_string(&label.to_owned());
+        self.save_to_disk(&self.subaddresses, &index.to_be_bytes(), writer.as_bytes())?;
+
+        Ok(index)
+    }
+
+    // Retrieve all subaddresses derived ordered by index
+    pub fn get_subaddresses(&self) -> Result<Vec<(u32, String)>> {
+        trace!("get subaddresses");
+        let mut subaddresses = Vec::new();
+        for res in self.subaddresses.iter().values() {
+            let value = res?;
+            let bytes = self.cipher.decrypt_value(&value)?;
+            let mut reader = Reader::new(&bytes);
+            let index = reader.read_u32()?;
+            let label = reader.read_string()?;
+            subaddresses.push((index, label));
+        }
+        subaddresses.sort_by_key(|(index, _)| *index);
+
+        Ok(subaddresses)
+    }
+
     // Flush on disk to make sure it is saved
     pub fn flush(&mut self) -> Result<()> {
         trace!("Flushing storage");
-        self.inner.db.flush()?;
+        self.inner.backend.flush()?;
         Ok(())
     }
 
+    // Drop the entries left behind by reorgs or interrupted syncs, then ask the backend to reclaim space
+    // The wallet must not be syncing while this is running
+    pub fn maintenance(&mut self) -> Result<MaintenanceReport> {
+        trace!("maintenance");
+        let mut report = MaintenanceReport {
+            size_before: self.inner.backend.size_on_disk()?,
+            ..Default::default()
+        };
+
+        // Hashes of the transactions deleted from history
+        let mut orphaned = Vec::new();
+        if self.contains_data(&self.extra, TOPOHEIGHT_KEY)? {
+            let synced_topoheight = self.get_synced_topoheight()?;
+            for topoheight in self.get_transactions_topoheights()? {
+                if topoheight <= synced_topoheight {
+                    break;
+                }
+                orphaned.extend(self.get_transactions_hashes_at_topoheight(topoheight)?);
+            }
+            report.orphaned_transactions = orphaned.len();
+
+            if report.orphaned_transactions > 0 {
+                debug!("deleting {} orphaned transactions", report.orphaned_transactions);
+                self.delete_transactions_above_topoheight(synced_topoheight)?;
+            }
+            self.delete_changes_above_topoheight(synced_topoheight)?;
+        }
+
+        // Every indexed transaction must exist and every transaction must be indexed
+        let mut indexed = 0;
+        let mut missing = false;
+        for topoheight in self.get_transactions_topoheights()? {
+            for hash in self.get_transactions_hashes_at_topoheight(topoheight)? {
+                indexed += 1;
+                if !self.has_transaction(&hash)? {
+                    missing = true;
+                }
+            }
+        }
+
+        if missing || indexed != self.count_transactions() {
+            debug!("transactions indexes are inconsistent, rebuilding them");
+            self.rebuild_transactions_indexes()?;
+            report.rebuilt_indexes = true;
+        }
+
+        // A label can be set before the transaction is synced or while it is pending
+        // so only the labels of the transactions deleted above are known to be useless
+        let pending = self.get_pending_transactions()?;
+        for hash in orphaned {
+            if pending.iter().any(|tx| *tx.get_hash() == hash) {
+                continue;
+            }
+
+            if self.contains_data(&self.transactions_labels, hash.as_bytes())? {
+                self.delete_from_disk(&self.transactions_labels, hash.as_bytes())?;
+                report.orphaned_labels += 1;
+            }
+        }
+
+        for tx in self.get_pending_transactions()? {
+            if self.has_transaction(tx.get_hash())? {
+                self.delete_from_disk(&self.pending_transactions, tx.get_hash().as_bytes())?;
+                report.executed_pending_transactions += 1;
+            }
+        }
+
+        self.inner.backend.compact()?;
+        report.size_after = self.inner.backend.size_on_disk()?;
+
+        Ok(report)
+    }
+
+    // Decrypt every entry of this account to verify it was not altered
+    // Each value is authenticated by the cipher, so a corrupted byte fails its decryption
+    // Returns the number of entries checked and a description of each corrupted one
+    pub fn verify_entries(&self) -> Result<(usize, Vec<String>)> {
+        trace!("verify entries");
+        let trees = [
+            ("transactions", &self.transactions),
+            ("transactions_indexes", &self.transactions_indexes),
+            ("balances", &self.balances),
+            ("extra", &self.extra),
+            ("assets", &self.assets),
+            ("changes_topoheight", &self.changes_topoheight),
+            ("address_book", &self.address_book),
+            ("pending_transactions", &self.pending_transactions),
+            ("replaced_transactions", &self.replaced_transactions),
+            ("scheduled_payments", &self.scheduled_payments),
+            ("transactions_labels", &self.transactions_labels),
+            ("addresses_labels", &self.addresses_labels),
+            ("webhooks", &self.webhooks),
+            ("api_tokens", &self.api_tokens),
+            ("drafts", &self.drafts),
+            ("templates", &self.templates),
+            ("subaddresses", &self.subaddresses),
+            ("encrypted_balances", &self.encrypted_balances),
+            ("notified_transactions", &self.notified_transactions),
+            ("accounts", &self.accounts)
+        ];
+
+        let mut checked = 0;
+        let mut corrupted = Vec::new();
+        for (name, tree) in trees {
+            for res in tree.iter() {
+                let (key, value) = res?;
+                checked += 1;
+                // Hashed keys can't be verified, encrypted ones are
+                if key.len() != HASH_SIZE && self.cipher.decrypt_value(&key).is_err() {
+                    corrupted.push(format!("{}: invalid key {}", name, hex::encode(&key)));
+                    continue;
+                }
+
+                if self.cipher.decrypt_value(&value).is_err() {
+                    corrupted.push(format!("{}: invalid value for key {}", name, hex::encode(&key)));
+                }
+            }
+        }
+
+        Ok((checked, corrupted))
+    }
+
     // Await for the storage to be flushed
     pub async fn stop(&mut self) {
         trace!("Stopping storage");
-        if let Err(e) = self.inner.db.flush_async().await {
+        if let Err(e) = self.inner.backend.flush() {
             error!("Error while flushing the database: {}", e);
         }
     }
@@ -255,8 +762,12 @@ impl EncryptedStorage {
 
     // Open the named tree
     fn get_custom_tree(&self, name: impl Into<String>) -> Result<Tree> {
-        let hash = self.cipher.hash_key(format!("custom_{}", name.into()));
-        let tree = self.inner.db.open_tree(&hash)?;
+        let hash = if self.account == 0 {
+            self.cipher.hash_key(format!("custom_{}", name.into()))
+        } else {
+            self.cipher.hash_key(format!("account_{}_custom_{}", self.account, name.into()))
+        };
+        let tree = self.inner.open_tree(&hash)?;
         Ok(tree)
     }
 
@@ -516,6 +1027,12 @@ impl EncryptedStorage {
         Ok(())
     }
 
+    // Determine if transactions spending this asset are waiting to be confirmed
+    pub async fn has_unconfirmed_balance_for(&self, asset: &Hash) -> bool {
+        let cache = self.unconfirmed_balances_cache.lock().await;
+        cache.get(asset).map_or(false, |balances| !balances.is_empty())
+    }
+
     // Determine if we have any balance stored
     pub async fn has_any_balance(&self) -> Result<bool> {
         let cache = self.balances_cache.lock().await;
@@ -571,13 +1088,19 @@ impl EncryptedStorage {
 
     // delete all transactions above the specified topoheight
     // This will go through each transaction, deserialize it, check topoheight, and delete it if required
+    // Only the index is read to find them, no need to decrypt every transaction
     pub fn delete_transactions_above_topoheight(&mut self, topoheight: u64) -> Result<()> {
-        for el in self.transactions.iter().values() {
-            let value = el?;
-            let entry = TransactionEntry::from_bytes(&self.cipher.decrypt_value(&value)?)?;
-            if entry.get_topoheight() > topoheight {
-                self.delete_transaction(entry.get_hash())?;
+        trace!("delete transactions above topoheight {}", topoheight);
+        for topo in self.get_transactions_topoheights()? {
+            // topoheights are ordered from highest to lowest
+            if topo <= topoheight {
+                break;
+            }
+
+            for hash in self.get_transactions_hashes_at_topoheight(topo)? {
+                self.transactions.remove(self.cipher.hash_key(hash.as_bytes()))?;
             }
+            self.delete_from_disk_with_encrypted_key(&self.transactions_indexes, &topo.to_be_bytes())?;
         }
 
         Ok(())
@@ -655,6 +1178,10 @@ impl EncryptedStorage {
 
     // Delete a transaction saved in wallet using its hash
     pub fn delete_transaction(&mut self, hash: &Hash) -> Result<()> {
+        if let Ok(entry) = self.get_transaction(hash) {
+            self.remove_transaction_from_index(entry.get_topoheight(), hash)?;
+        }
+
         self.transactions.remove(self.cipher.hash_key(hash.as_bytes()))?;
         Ok(())
     }
@@ -662,17 +1189,159 @@ impl EncryptedStorage {
     // Delete all transactions from this wallet
     pub fn delete_transactions(&mut self) -> Result<()> {
         self.transactions.clear()?;
+        self.transactions_indexes.clear()?;
+        Ok(())
+    }
+
+    // Retrieve all transactions hashes stored at the requested topoheight
+    fn get_transactions_hashes_at_topoheight(&self, topoheight: u64) -> Result<Vec<Hash>> {
+        if !self.contains_encrypted_data(&self.transactions_indexes, &topoheight.to_be_bytes())? {
+            return Ok(Vec::new())
+        }
+
+        self.load_from_disk_with_encrypted_key(&self.transactions_indexes, &topoheight.to_be_bytes())
+    }
+
+    // Register the transaction hash in the index of its topoheight
+    fn add_transaction_to_index(&self, topoheight: u64, hash: &Hash) -> Result<()> {
+        trace!("add transaction {} to index at topoheight {}", hash, topoheight);
+        let mut hashes = self.get_transactions_hashes_at_topoheight(topoheight)?;
+        if !hashes.contains(hash) {
+            hashes.push(hash.clone());
+            self.save_to_disk_with_encrypted_key(&self.transactions_indexes, &topoheight.to_be_bytes(), &hashes.to_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    // Remove the transaction hash from the index of its topoheight
+    fn remove_transaction_from_index(&self, topoheight: u64, hash: &Hash) -> Result<()> {
+        trace!("remove transaction {} from index at topoheight {}", hash, topoheight);
+        let mut hashes = self.get_transactions_hashes_at_topoheight(topoheight)?;
+        hashes.retain(|h| h != hash);
+        if hashes.is_empty() {
+            self.delete_from_disk_with_encrypted_key(&self.transactions_indexes, &topoheight.to_be_bytes())
+        } else {
+            self.save_to_disk_with_encrypted_key(&self.transactions_indexes, &topoheight.to_be_bytes(), &hashes.to_bytes())
+        }
+    }
+
+    // Delete the oldest transactions to keep at most `max_transactions` and none below `min_topoheight`
+    // Transactions of a same topoheight are kept or deleted together
+    // Balances are not affected, returns the number of transactions deleted
+    pub fn prune_transactions(&mut self, max_transactions: Option<usize>, min_topoheight: Option<u64>) -> Result<usize> {
+        trace!("prune transactions");
+        let mut kept = 0;
+        let mut pruned = 0;
+        // topoheights are ordered from highest to lowest
+        for topoheight in self.get_transactions_topoheights()? {
+            let hashes = self.get_transactions_hashes_at_topoheight(topoheight)?;
+            let too_old = min_topoheight.map(|min| topoheight < min).unwrap_or(false);
+            let too_many = max_transactions.map(|max| kept >= max).unwrap_or(false);
+            if !too_old && !too_many {
+                kept += hashes.len();
+                continue;
+            }
+
+            for hash in hashes {
+                let key = self.cipher.hash_key(hash.as_bytes());
+                self.transactions.remove(&key)?;
+                self.transactions_labels.remove(&key)?;
+                pruned += 1;
+            }
+            self.delete_from_disk_with_encrypted_key(&self.transactions_indexes, &topoheight.to_be_bytes())?;
+        }
+
+        Ok(pruned)
+    }
+
+    // Re-create the whole transactions index from the stored transactions
+    pub fn rebuild_transactions_indexes(&mut self) -> Result<()> {
+        trace!("rebuild transactions indexes");
+        self.transactions_indexes.clear()?;
+        for el in self.transactions.iter().values() {
+            let value = el?;
+            let entry = TransactionEntry::from_bytes(&self.cipher.decrypt_value(&value)?)?;
+            self.add_transaction_to_index(entry.get_topoheight(), entry.get_hash())?;
+        }
+
         Ok(())
     }
 
+    // Retrieve all topoheights containing at least one transaction, in descending order
+    pub fn get_transactions_topoheights(&self) -> Result<Vec<u64>> {
+        let mut topoheights = Vec::new();
+        for res in self.transactions_indexes.iter().keys() {
+            let key = res?;
+            let raw = self.cipher.decrypt_value(&key).context("Error while decrypting key from disk")?;
+            topoheights.push(u64::from_bytes(&raw)?);
+        }
+
+        topoheights.sort_by(|a, b| b.cmp(a));
+        Ok(topoheights)
+    }
+
+    // Count how many transactions are stored in wallet
+    pub fn count_transactions(&self) -> usize {
+        self.transactions.len()
+    }
+
+    // Retrieve transactions ordered by topoheight (highest first)
+    // Only the transactions requested are decrypted from disk
+    pub fn get_transactions_page(&self, skip: usize, maximum: usize) -> Result<Vec<TransactionEntry>> {
+        let mut transactions = Vec::with_capacity(maximum);
+        let mut skipped = 0;
+        for topoheight in self.get_transactions_topoheights()? {
+            let hashes = self.get_transactions_hashes_at_topoheight(topoheight)?;
+            if skipped + hashes.len() <= skip {
+                skipped += hashes.len();
+                continue;
+            }
+
+            for hash in hashes {
+                if skipped < skip {
+                    skipped += 1;
+                    continue;
+                }
+
+                transactions.push(self.get_transaction(&hash)?);
+                if transactions.len() >= maximum {
+                    return Ok(transactions)
+                }
+            }
+        }
+
+        Ok(transactions)
+    }
+
     // Delete all balances from this wallet
     pub async fn delete_balances(&mut self) -> Result<()> {
         self.balances.clear()?;
+        self.encrypted_balances.clear()?;
         self.delete_unconfirmed_balances().await?;
         self.balances_cache.lock().await.clear();
         Ok(())
     }
 
+    // Set the balance of a watch-only wallet for this asset
+    // Only its ciphertext is known, the amount is decrypted by the wallet signing its transactions
+    pub fn set_encrypted_balance_for(&mut self, asset: &Hash, ciphertext: &CompressedCiphertext) -> Result<()> {
+        trace!("set encrypted balance for {}", asset);
+        self.save_to_disk(&self.encrypted_balances, asset.as_bytes(), &ciphertext.to_bytes())
+    }
+
+    // Retrieve the balance of a watch-only wallet for this asset
+    pub fn get_encrypted_balance_for(&self, asset: &Hash) -> Result<CompressedCiphertext> {
+        trace!("get encrypted balance for {}", asset);
+        self.load_from_disk(&self.encrypted_balances, asset.as_bytes())
+    }
+
+    // Check if the watch-only wallet has a balance for this asset
+    pub fn has_encrypted_balance_for(&self, asset: &Hash) -> Result<bool> {
+        trace!("has encrypted balance for {}", asset);
+        self.contains_data(&self.encrypted_balances, asset.as_bytes())
+    }
+
     // Delete all unconfirmed balances from this wallet
     pub async fn delete_unconfirmed_balances(&mut self) -> Result<()> {
         self.unconfirmed_balances_cache.lock().await.clear();
@@ -691,6 +1360,15 @@ impl EncryptedStorage {
     // with no access to the decrypted master key
     pub fn save_transaction(&mut self, hash: &Hash, transaction: &TransactionEntry) -> Result<()> {
         trace!("save transaction {}", hash);
+        // A transaction saved again (after a reorg) may have moved to another topoheight
+        if self.has_transaction(hash)? {
+            let previous = self.get_transaction(hash)?;
+            if previous.get_topoheight() != transaction.get_topoheight() {
+                self.remove_transaction_from_index(previous.get_topoheight(), hash)?;
+            }
+        }
+
+        self.add_transaction_to_index(transaction.get_topoheight(), hash)?;
         self.save_to_disk(&self.transactions, hash.as_bytes(), &transaction.to_bytes())
     }
 
@@ -724,6 +1402,52 @@ impl EncryptedStorage {
         self.load_from_disk(&self.extra, PRIVATE_KEY)
     }
 
+    // Store the BIP39 mnemonic of this wallet to be able to display it again
+    pub fn set_mnemonic(&mut self, mnemonic: &String) -> Result<()> {
+        trace!("set mnemonic");
+        self.save_to_disk(&self.extra, MNEMONIC_KEY, &mnemonic.to_bytes())
+    }
+
+    // Retrieve the BIP39 mnemonic, wallets created from a legacy seed or a private key don't have one
+    pub fn get_mnemonic(&self) -> Result<Option<String>> {
+        trace!("get mnemonic");
+        if !self.contains_data(&self.extra, MNEMONIC_KEY)? {
+            return Ok(None)
+        }
+
+        self.load_from_disk(&self.extra, MNEMONIC_KEY).map(Some)
+    }
+
+    // Store the public key for a watch-only wallet
+    pub fn set_public_key(&mut self, public_key: &PublicKey) -> Result<()> {
+        trace!("set public key");
+        self.save_to_disk(&self.extra, PUBLIC_KEY, &public_key.to_bytes())
+    }
+
+    // Retrieve the public key of a watch-only wallet
+    pub fn get_public_key(&self) -> Result<PublicKey> {
+        trace!("get public key");
+        self.load_from_disk(&self.extra, PUBLIC_KEY)
+    }
+
+    // A watch-only wallet has no private key stored
+    pub fn is_watch_only(&self) -> Result<bool> {
+        trace!("is watch only");
+        Ok(!self.contains_data(&self.extra, PRIVATE_KEY)?)
+    }
+
+    // Mark the wallet as view-only, its private key is only used to decrypt
+    pub fn set_view_only(&mut self) -> Result<()> {
+        trace!("set view only");
+        self.save_to_disk(&self.extra, VIEW_ONLY_KEY, &[1])
+    }
+
+    // A view-only wallet has a private key stored but refuses to sign
+    pub fn is_view_only(&self) -> Result<bool> {
+        trace!("is view only");
+        self.contains_data(&self.extra, VIEW_ONLY_KEY)
+    }
+
     // Set the topoheight until which the wallet is synchronized
     pub fn set_synced_topoheight(&mut self, topoheight: u64) -> Result<()> {
         trace!("set synced topoheight to {}", topoheight);
@@ -796,6 +1520,487 @@ impl EncryptedStorage {
         self.contains_data(&self.extra, NETWORK)
     }
 
+    // Retrieve the schema version of the storage
+    // Wallets created before the versioning use the version 0
+    pub fn get_schema_version(&self) -> Result<u32> {
+        trace!("get schema version");
+        if !self.contains_data(&self.extra, SCHEMA_VERSION_KEY)? {
+            return Ok(0)
+        }
+
+        self.load_from_disk(&self.extra, SCHEMA_VERSION_KEY)
+    }
+
+    // Save the schema version of the storage
+    pub fn set_schema_version(&mut self, version: u32) -> Result<()> {
+        trace!("set schema version to {}", version);
+        self.save_to_disk(&self.extra, SCHEMA_VERSION_KEY, &version.to_bytes())
+    }
+
+    // Save a transaction broadcasted by the wallet until it is executed in a block
+    pub fn add_pending_transaction(&mut self, transaction: &PendingTransaction) -> Result<()> {
+        trace!("add pending transaction {}", transaction.get_hash());
+        self.save_to_disk(&self.pending_transactions, transaction.get_hash().as_bytes(), &transaction.to_bytes())
+    }
+
+    // Remove a pending transaction, returns true if it was present
+    pub fn remove_pending_transaction(&mut self, hash: &Hash) -> Result<bool> {
+        trace!("remove pending transaction {}", hash);
+        if !self.contains_data(&self.pending_transactions, hash.as_bytes())? {
+            return Ok(false)
+        }
+
+        self.delete_from_disk(&self.pending_transactions, hash.as_bytes())?;
+        Ok(true)
+    }
+
+    // Retrieve all pending transactions ordered by nonce
+    pub fn get_pending_transactions(&self) -> Result<Vec<PendingTransaction>> {
+        trace!("get pending transactions");
+        let mut transactions = Vec::new();
+        for res in self.pending_transactions.iter().values() {
+            let value = res?;
+            transactions.push(PendingTransaction::from_bytes(&self.cipher.decrypt_value(&value)?)?);
+        }
+        transactions.sort_by_key(|tx| tx.get_nonce());
+
+        Ok(transactions)
+    }
+
+    // Save that a pending transaction was replaced by another one
+    // Both hashes are stored as value to be able to search by replacement too
+    pub fn set_transaction_replacement(&mut self, replaced: &Hash, replacement: &Hash) -> Result<()> {
+        trace!("set transaction replacement {} -> {}", replaced, replacement);
+        let value = (replaced.clone(), replacement.clone());
+        self.save_to_disk(&self.replaced_transactions, replaced.as_bytes(), &value.to_bytes())
+    }
+
+    // Retrieve the transaction that replaced this one, if any
+    pub fn get_transaction_replacement(&self, replaced: &Hash) -> Result<Option<Hash>> {
+        trace!("get transaction replacement {}", replaced);
+        if !self.contains_data(&self.replaced_transactions, replaced.as_bytes())? {
+            return Ok(None)
+        }
+
+        let (_, replacement): (Hash, Hash) = self.load_from_disk(&self.replaced_transactions, replaced.as_bytes())?;
+        Ok(Some(replacement))
+    }
+
+    // Retrieve the transaction replaced by this one, if any
+    pub fn get_replaced_transaction(&self, replacement: &Hash) -> Result<Option<Hash>> {
+        trace!("get replaced transaction {}", replacement);
+        for res in self.replaced_transactions.iter().values() {
+            let value = res?;
+            let (replaced, hash) = <(Hash, Hash)>::from_bytes(&self.cipher.decrypt_value(&value)?)?;
+            if hash == *replacement {
+                return Ok(Some(replaced))
+            }
+        }
+
+        Ok(None)
+    }
+
+    // Delete all pending transactions using a nonce already used on chain
+    // They are either executed or were replaced/rejected
+    // Returns the deleted transactions
+    pub fn delete_pending_transactions_below_nonce(&mut self, nonce: u64) -> Result<Vec<PendingTransaction>> {
+        trace!("delete pending transactions below nonce {}", nonce);
+        let mut deleted = Vec::new();
+        for tx in self.get_pending_transactions()? {
+            if tx.get_nonce() >= nonce {
+                break;
+            }
+
+            self.delete_from_disk(&self.pending_transactions, tx.get_hash().as_bytes())?;
+            deleted.push(tx);
+        }
+
+        Ok(deleted)
+    }
+
+    // Delete all pending transactions using this nonce
+    // Once a transaction with this nonce is executed, the others conflict with it
+    pub fn delete_pending_transactions_with_nonce(&mut self, nonce: u64) -> Result<Vec<PendingTransaction>> {
+        trace!("delete pending transactions with nonce {}", nonce);
+        let mut deleted = Vec::new();
+        for tx in self.get_pending_transactions()? {
+            if tx.get_nonce() == nonce {
+                self.delete_from_disk(&self.pending_transactions, tx.get_hash().as_bytes())?;
+                deleted.push(tx);
+            }
+        }
+
+        Ok(deleted)
+    }
+
+    // Register a new scheduled payment and returns its id
+    pub fn add_scheduled_payment(&mut self, destination: Address, asset: Hash, amount: u64, next_execution: u64, interval: Option<u64>) -> Result<u64> {
+        let id = if self.contains_data(&self.extra, SCHEDULE_ID_KEY)? {
+            self.load_from_disk(&self.extra, SCHEDULE_ID_KEY)?
+        } else {
+            0
+        };
+        trace!("add scheduled payment {}", id);
+
+        let payment = ScheduledPayment::new(id, destination, asset, amount, next_execution, interval);
+        self.set_scheduled_payment(&payment)?;
+        self.save_to_disk(&self.extra, SCHEDULE_ID_KEY, &(id + 1).to_be_bytes())?;
+
+        Ok(id)
+    }
+
+    // Save or update a scheduled payment
+    pub fn set_scheduled_payment(&mut self, payment: &ScheduledPayment) -> Result<()> {
+        trace!("set scheduled payment {}", payment.get_id());
+        self.save_to_disk(&self.scheduled_payments, &payment.get_id().to_be_bytes(), &payment.to_bytes())
+    }
+
+    // Remove a scheduled payment, returns true if it was present
+    pub fn remove_scheduled_payment(&mut self, id: u64) -> Result<bool> {
+        trace!("remove scheduled payment {}", id);
+        let key = id.to_be_bytes();
+        if !self.contains_data(&self.scheduled_payments, &key)? {
+            return Ok(false)
+        }
+
+        self.delete_from_disk(&self.scheduled_payments, &key)?;
+        Ok(true)
+    }
+
+    // Retrieve all scheduled payments ordered by next execution
+    pub fn get_scheduled_payments(&self) -> Result<Vec<ScheduledPayment>> {
+        trace!("get scheduled payments");
+        let mut payments = Vec::new();
+        for res in self.scheduled_payments.iter().values() {
+            let value = res?;
+            payments.push(ScheduledPayment::from_bytes(&self.cipher.decrypt_value(&value)?)?);
+        }
+        payments.sort_by_key(|p| (p.get_next_execution(), p.get_id()));
+
+        Ok(payments)
+    }
+
+    // Set a label on a transaction, an empty label deletes it
+    pub fn set_transaction_label(&mut self, hash: &Hash, label: &str) -> Result<()> {
+        trace!("set transaction label {}", hash);
+        if label.is_empty() {
+            self.delete_from_disk(&self.transactions_labels, hash.as_bytes())
+        } else {
+            self.save_to_disk(&self.transactions_labels, hash.as_bytes(), &label.to_owned().to_bytes())
+        }
+    }
+
+    // Retrieve the label set on a transaction
+    pub fn get_transaction_label(&self, hash: &Hash) -> Result<Option<String>> {
+        trace!("get transaction label {}", hash);
+        if !self.contains_data(&self.transactions_labels, hash.as_bytes())? {
+            return Ok(None)
+        }
+
+        self.load_from_disk(&self.transactions_labels, hash.as_bytes()).map(Some)
+    }
+
+    // Set a label on an address, an empty label deletes it
+    // It is linked to the public key so it is shared by its integrated addresses
+    pub fn set_address_label(&mut self, key: &PublicKey, label: &str) -> Result<()> {
+        trace!("set address label");
+        if label.is_empty() {
+            self.delete_from_disk(&self.addresses_labels, key.as_bytes())
+        } else {
+            self.save_to_disk(&self.addresses_labels, key.as_bytes(), &label.to_owned().to_bytes())
+        }
+    }
+
+    // Retrieve the label set on an address
+    pub fn get_address_label(&self, key: &PublicKey) -> Result<Option<String>> {
+        trace!("get address label");
+        if !self.contains_data(&self.addresses_labels, key.as_bytes())? {
+            return Ok(None)
+        }
+
+        self.load_from_disk(&self.addresses_labels, key.as_bytes()).map(Some)
+    }
+
+    // Save a contact in the address book
+    // Name is stored as an encrypted key to be able to list all contacts
+    pub fn add_contact(&mut self, name: &str, address: &Address) -> Result<()> {
+        trace!("add contact {}", name);
+        if self.has_contact(name)? {
+            return Err(WalletError::ContactAlreadyExists(name.to_owned()).into());
+        }
+
+        self.save_to_disk_with_encrypted_key(&self.address_book, name.as_bytes(), &address.to_string().to_bytes())
+    }
+
+    // Check if a contact exists with this name
+    pub fn has_contact(&self, name: &str) -> Result<bool> {
+        trace!("has contact {}", name);
+        self.contains_encrypted_data(&self.address_book, name.as_bytes())
+    }
+
+    // Retrieve the address of a contact
+    pub fn get_contact(&self, name: &str) -> Result<Address> {
+        trace!("get contact {}", name);
+        let address: String = self.load_from_disk_with_encrypted_key(&self.address_book, name.as_bytes())?;
+        Ok(Address::from_string(&address)?)
+    }
+
+    // Delete a contact from the address book
+    pub fn remove_contact(&mut self, name: &str) -> Result<()> {
+        trace!("remove contact {}", name);
+        if !self.has_contact(name)? {
+            return Err(WalletError::ContactNotFound(name.to_owned()).into());
+        }
+
+        self.delete_from_disk_with_encrypted_key(&self.address_book, name.as_bytes())
+    }
+
+    // Retrieve all contacts sorted by name
+    pub fn get_contacts(&self) -> Result<Vec<(String, Address)>> {
+        trace!("get contacts");
+        let mut contacts = Vec::new();
+        for res in self.address_book.iter() {
+            let (key, value) = res?;
+            let name = String::from_utf8(self.cipher.decrypt_value(&key)?)?;
+            let address = String::from_bytes(&self.cipher.decrypt_value(&value)?)?;
+            contacts.push((name, Address::from_string(&address)?));
+        }
+        contacts.sort_by(|a, b| a.0.cmp(&b.0));
+
+        Ok(contacts)
+    }
+
+    // Register a webhook URL to notify on confirmed transfers
+    // URL is stored as an encrypted key to be able to list all webhooks
+    pub fn add_webhook(&mut self, url: &str, added_at: TimestampSeconds) -> Result<()> {
+        trace!("add webhook {}", url);
+        if self.has_webhook(url)? {
+            return Err(WalletError::WebhookAlreadyExists(url.to_owned()).into());
+        }
+
+        self.save_to_disk_with_encrypted_key(&self.webhooks, url.as_bytes(), &added_at.to_bytes())
+    }
+
+    // Check if a webhook is registered with this URL
+    pub fn has_webhook(&self, url: &str) -> Result<bool> {
+        trace!("has webhook {}", url);
+        self.contains_encrypted_data(&self.webhooks, url.as_bytes())
+    }
+
+    // Delete a registered webhook
+    pub fn remove_webhook(&mut self, url: &str) -> Result<()> {
+        trace!("remove webhook {}", url);
+        if !self.has_webhook(url)? {
+            return Err(WalletError::WebhookNotFound(url.to_owned()).into());
+        }
+
+        self.delete_from_disk_with_encrypted_key(&self.webhooks, url.as_bytes())
+    }
+
+    // Check if the webhooks were already notified of this transaction at this topoheight
+    // A transaction moved to another topoheight by a reorg is notified again
+    pub fn is_transaction_notified(&self, hash: &Hash, topoheight: u64) -> Result<bool> {
+        trace!("is transaction notified {}", hash);
+        if !self.contains_data(&self.notified_transactions, hash.as_bytes())? {
+            return Ok(false)
+        }
+
+        let notified: u64 = self.load_from_disk(&self.notified_transactions, hash.as_bytes())?;
+        Ok(notified == topoheight)
+    }
+
+    // Mark the transaction as sent to the webhooks at this topoheight
+    pub fn set_transaction_notified(&mut self, hash: &Hash, topoheight: u64) -> Result<()> {
+        trace!("set transaction notified {}", hash);
+        self.save_to_disk(&self.notified_transactions, hash.as_bytes(), &topoheight.to_bytes())
+    }
+
+    // Retrieve all registered webhooks with the time they were added
+    pub fn get_webhooks(&self) -> Result<Vec<(String, TimestampSeconds)>> {
+        trace!("get webhooks");
+        let mut webhooks = Vec::new();
+        for res in self.webhooks.iter() {
+            let (key, value) = res?;
+            let url = String::from_utf8(self.cipher.decrypt_value(&key)?)?;
+            let added_at = u64::from_bytes(&self.cipher.decrypt_value(&value)?)?;
+            webhooks.push((url, added_at));
+        }
+        webhooks.sort_by(|a, b| a.1.cmp(&b.1));
+
+        Ok(webhooks)
+    }
+
+    // Save a new API token
+    // Name is stored as an encrypted key to be able to list all tokens
+    pub fn add_api_token(&mut self, name: &str, token: &ApiToken) -> Result<()> {
+        trace!("add api token {}", name);
+        if self.has_api_token(name)? {
+            return Err(WalletError::ApiTokenAlreadyExists(name.to_owned()).into());
+        }
+
+        self.set_api_token(name, token)
+    }
+
+    // Update an existing API token, used to save its spending
+    pub fn set_api_token(&mut self, name: &str, token: &ApiToken) -> Result<()> {
+        trace!("set api token {}", name);
+        self.save_to_disk_with_encrypted_key(&self.api_tokens, name.as_bytes(), &token.to_bytes())
+    }
+
+    // Check if an API token exists with this name
+    pub fn has_api_token(&self, name: &str) -> Result<bool> {
+        trace!("has api token {}", name);
+        self.contains_encrypted_data(&self.api_tokens, name.as_bytes())
+    }
+
+    // Retrieve an API token using its name
+    pub fn get_api_token(&self, name: &str) -> Result<ApiToken> {
+        trace!("get api token {}", name);
+        self.load_from_disk_with_encrypted_key(&self.api_tokens, name.as_bytes())
+            .context(WalletError::ApiTokenNotFound(name.to_owned()))
+    }
+
+    // Revoke an API token, clients using it are rejected on their next request
+    pub fn remove_api_token(&mut self, name: &str) -> Result<()> {
+        trace!("remove api token {}", name);
+        if !self.has_api_token(name)? {
+            return Err(WalletError::ApiTokenNotFound(name.to_owned()).into());
+        }
+
+        self.delete_from_disk_with_encrypted_key(&self.api_tokens, name.as_bytes())
+    }
+
+    // Retrieve all API tokens sorted by name
+    pub fn get_api_tokens(&self) -> Result<Vec<(String, ApiToken)>> {
+        trace!("get api tokens");
+        let mut tokens = Vec::new();
+        for res in self.api_tokens.iter() {
+            let (key, value) = res?;
+            let name = String::from_utf8(self.cipher.decrypt_value(&key)?)?;
+            let token = ApiToken::from_bytes(&self.cipher.decrypt_value(&value)?)?;
+            tokens.push((name, token));
+        }
+        tokens.sort_by(|a, b| a.0.cmp(&b.0));
+
+        Ok(tokens)
+    }
+
+    // Find the API token matching the secret sent by a client
+    pub fn find_api_token(&self, secret: &str) -> Result<Option<(String, ApiToken)>> {
+        trace!("find api token");
+        Ok(self.get_api_tokens()?.into_iter().find(|(_, token)| token.matches(secret)))
+    }
+
+    // Save a draft transaction under a name
+    pub fn add_draft(&mut self, name: &str, draft: &DraftTransaction) -> Result<()> {
+        trace!("add draft {}", name);
+        if self.has_draft(name)? {
+            return Err(WalletError::DraftAlreadyExists(name.to_owned()).into());
+        }
+
+        self.save_to_disk_with_encrypted_key(&self.drafts, name.as_bytes(), &draft.to_bytes())
+    }
+
+    // Check if a draft exists with this name
+    pub fn has_draft(&self, name: &str) -> Result<bool> {
+        trace!("has draft {}", name);
+        self.contains_encrypted_data(&self.drafts, name.as_bytes())
+    }
+
+    // Retrieve a draft transaction using its name
+    pub fn get_draft(&self, name: &str) -> Result<DraftTransaction> {
+        trace!("get draft {}", name);
+        self.load_from_disk_with_encrypted_key(&self.drafts, name.as_bytes())
+            .context(WalletError::DraftNotFound(name.to_owned()))
+    }
+
+    // Delete a draft, once sent or rejected
+    pub fn remove_draft(&mut self, name: &str) -> Result<()> {
+        trace!("remove draft {}", name);
+        if !self.has_draft(name)? {
+            return Err(WalletError::DraftNotFound(name.to_owned()).into());
+        }
+
+        self.delete_from_disk_with_encrypted_key(&self.drafts, name.as_bytes())
+    }
+
+    // Retrieve all drafts sorted by name
+    pub fn get_drafts(&self) -> Result<Vec<(String, DraftTransaction)>> {
+        trace!("get drafts");
+        let mut drafts = Vec::new();
+        for res in self.drafts.iter() {
+            let (key, value) = res?;
+            let name = String::from_utf8(self.cipher.decrypt_value(&key)?)?;
+            let draft = DraftTransaction::from_bytes(&self.cipher.decrypt_value(&value)?)?;
+            drafts.push((name, draft));
+        }
+        drafts.sort_by(|a, b| a.0.cmp(&b.0));
+
+        Ok(drafts)
+    }
+
+    // Save a transfer template under a name, an existing one is replaced
+    pub fn set_template(&mut self, name: &str, template: &TransferTemplate) -> Result<()> {
+        trace!("set template {}", name);
+        self.save_to_disk_with_encrypted_key(&self.templates, name.as_bytes(), &template.to_bytes())
+    }
+
+    // Check if a template exists with this name
+    pub fn has_template(&self, name: &str) -> Result<bool> {
+        trace!("has template {}", name);
+        self.contains_encrypted_data(&self.templates, name.as_bytes())
+    }
+
+    // Retrieve a transfer template using its name
+    pub fn get_template(&self, name: &str) -> Result<TransferTemplate> {
+        trace!("get template {}", name);
+        self.load_from_disk_with_encrypted_key(&self.templates, name.as_bytes())
+            .context(WalletError::TemplateNotFound(name.to_owned()))
+    }
+
+    // Delete a transfer template
+    pub fn remove_template(&mut self, name: &str) -> Result<()> {
+        trace!("remove template {}", name);
+        if !self.has_template(name)? {
+            return Err(WalletError::TemplateNotFound(name.to_owned()).into());
+        }
+
+        self.delete_from_disk_with_encrypted_key(&self.templates, name.as_bytes())
+    }
+
+    // Retrieve all transfer templates sorted by name
+    pub fn get_templates(&self) -> Result<Vec<(String, TransferTemplate)>> {
+        trace!("get templates");
+        let mut templates = Vec::new();
+        for res in self.templates.iter() {
+            let (key, value) = res?;
+            let name = String::from_utf8(self.cipher.decrypt_value(&key)?)?;
+            let template = TransferTemplate::from_bytes(&self.cipher.decrypt_value(&value)?)?;
+            templates.push((name, template));
+        }
+        templates.sort_by(|a, b| a.0.cmp(&b.0));
+
+        Ok(templates)
+    }
+
+    // Save the daemon address to use by default for this wallet
+    pub fn set_daemon_address(&mut self, daemon_address: &String) -> Result<()> {
+        trace!("set daemon address to {}", daemon_address);
+        self.save_to_disk(&self.extra, DAEMON_ADDRESS_KEY, &daemon_address.to_bytes())
+    }
+
+    // Check if a default daemon address is set
+    pub fn has_daemon_address(&self) -> Result<bool> {
+        trace!("has daemon address");
+        self.contains_data(&self.extra, DAEMON_ADDRESS_KEY)
+    }
+
+    // Get the daemon address to use by default for this wallet
+    pub fn get_daemon_address(&self) -> Result<String> {
+        trace!("get daemon address");
+        self.load_from_disk(&self.extra, DAEMON_ADDRESS_KEY)
+    }
+
     // Add a topoheight where a change occured
     pub fn add_topoheight_to_changes(&mut self, topoheight: u64, block_hash: &Hash) -> Result<()> {
         trace!("add topoheight to changes: {} at {}", topoheight, block_hash);
@@ -863,13 +2068,22 @@ impl EncryptedStorage {
 
 impl Storage {
     pub fn new(name: String) -> Result<Self> {
-        let db = sled::open(name)?;
+        Self::with_backend(open_backend(&name)?)
+    }
 
+    // Use an already opened backend, this allows to store the wallet elsewhere than on disk
+    pub fn with_backend(backend: Arc<dyn StorageBackend>) -> Result<Self> {
         Ok(Self {
-            db
+            db: backend.default_tree()?,
+            backend
         })
     }
 
+    // Open (or create) a tree in the backend
+    fn open_tree(&self, name: &[u8]) -> Result<Tree> {
+        self.backend.open_tree(name)
+    }
+
     // save the encrypted form of the master key
     // it can only be decrypted using the password-based key
     pub fn set_encrypted_master_key(&mut self, encrypted_key: &[u8]) -> Result<()> {
@@ -883,7 +2097,7 @@ impl Storage {
         trace!("get encrypted master key");
         match self.db.get(MASTER_KEY)? {
             Some(key) => {
-                Ok(key.to_vec())
+                Ok(key)
             }
             None => {
                 Err(WalletError::NoMasterKeyFound.into())
@@ -918,13 +2132,64 @@ impl Storage {
         Ok(salt)
     }
 
+    // set the version of the algorithm used to hash the password
+    pub fn set_password_algorithm_version(&mut self, version: u8) -> Result<()> {
+        trace!("set password algorithm version {}", version);
+        self.db.insert(PASSWORD_ALGORITHM_VERSION_KEY, [version])?;
+        Ok(())
+    }
+
+    // retrieve the version of the algorithm used to hash the password
+    // Wallets created before the versioning use the version 0
+    pub fn get_password_algorithm_version(&self) -> Result<u8> {
+        trace!("get password algorithm version");
+        match self.db.get(PASSWORD_ALGORITHM_VERSION_KEY)? {
+            Some(value) if value.len() == 1 => Ok(value[0]),
+            Some(_) => Err(anyhow!("Invalid password algorithm version")),
+            None => Ok(0)
+        }
+    }
+
+    // set the custom parameters used to hash the password
+    pub fn set_password_params(&mut self, params: &PasswordParams) -> Result<()> {
+        trace!("set password params {}", params);
+        self.db.insert(PASSWORD_PARAMS_KEY, params.to_bytes())?;
+        Ok(())
+    }
+
+    // retrieve the parameters used to hash the password, resolved from its algorithm version
+    pub fn get_password_params(&self) -> Result<PasswordParams> {
+        trace!("get password params");
+        let version = self.get_password_algorithm_version()?;
+        if version == PASSWORD_ALGORITHM_VERSION_CUSTOM {
+            let value = self.db.get(PASSWORD_PARAMS_KEY)?.context("password params were not found")?;
+            return Ok(PasswordParams::from_bytes(&value)?)
+        }
+
+        get_password_params(version).ok_or_else(|| WalletError::UnknownPasswordAlgorithmVersion(version).into())
+    }
+
+    // set if a keyfile is required in addition to the password
+    pub fn set_keyfile_required(&mut self, required: bool) -> Result<()> {
+        trace!("set keyfile required {}", required);
+        if required {
+            self.db.insert(KEYFILE_KEY, [1])?;
+        } else {
+            self.db.remove(KEYFILE_KEY)?;
+        }
+        Ok(())
+    }
+
+    // check if a keyfile is required in addition to the password
+    pub fn is_keyfile_required(&self) -> Result<bool> {
+        trace!("is keyfile required");
+        Ok(self.db.get(KEYFILE_KEY)?.is_some())
+    }
+
     // get the salt used for encrypted storage
     pub fn get_encrypted_storage_salt(&self) -> Result<Vec<u8>> {
         trace!("get encrypted storage salt");
-        let values = self.db.get(SALT_KEY)?.context("encrypted salt for storage was not found")?;
-        let mut encrypted_salt = Vec::with_capacity(values.len());
-        encrypted_salt.extend_from_slice(&values);
-
+        let encrypted_salt = self.db.get(SALT_KEY)?.context("encrypted salt for storage was not found")?;
         Ok(encrypted_salt)
     }
 
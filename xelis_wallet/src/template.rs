@@ -0,0 +1,78 @@
+use std::str::FromStr;
+use xelis_common::{
+    api::{DataElement, DataValue},
+    crypto::{Address, Hash},
+    serializer::{Reader, ReaderError, Serializer, Writer},
+    transaction::builder::TransferBuilder
+};
+
+// A payment used frequently, saved under a name to be sent again with one command
+#[derive(Debug, Clone)]
+pub struct TransferTemplate {
+    destination: Address,
+    asset: Hash,
+    amount: u64,
+    // Sent as extra data of the transfer
+    memo: Option<String>
+}
+
+impl TransferTemplate {
+    pub fn new(destination: Address, asset: Hash, amount: u64, memo: Option<String>) -> Self {
+        Self {
+            destination,
+            asset,
+            amount,
+            memo
+        }
+    }
+
+    pub fn get_destination(&self) -> &Address {
+        &self.destination
+    }
+
+    pub fn get_asset(&self) -> &Hash {
+        &self.asset
+    }
+
+    pub fn get_amount(&self) -> u64 {
+        self.amount
+    }
+
+    pub fn get_memo(&self) -> Option<&String> {
+        self.memo.as_ref()
+    }
+
+    // Create the transfer to send
+    pub fn to_transfer(&self) -> TransferBuilder {
+        TransferBuilder {
+            destination: self.destination.clone(),
+            asset: self.asset.clone(),
+            amount: self.amount,
+            extra_data: self.memo.as_ref().map(|memo| DataElement::Value(DataValue::String(memo.clone())))
+        }
+    }
+}
+
+impl Serializer for TransferTemplate {
+    fn read(reader: &mut Reader) -> Result<Self, ReaderError> {
+        let destination = Address::from_str(&String::read(reader)?)
+            .map_err(|_| ReaderError::InvalidValue)?;
+        let asset = reader.read_hash()?;
+        let amount = reader.read_u64()?;
+        let memo = Option::read(reader)?;
+
+        Ok(Self {
+            destination,
+            asset,
+            amount,
+            memo
+        })
+    }
+
+    fn write(&self, writer: &mut Writer) {
+        self.destination.to_string().write(writer);
+        writer.write_hash(&self.asset);
+        writer.write_u64(&self.amount);
+        self.memo.write(writer);
+    }
+}
@@ -1,10 +1,137 @@
-use std::collections::{HashMap, HashSet};
+use std::{collections::{HashMap, HashSet}, sync::Arc};
+use serde::{Deserialize, Serialize};
 use xelis_common::{
     account::CiphertextCache,
-    crypto::{elgamal::Ciphertext, Hash, PublicKey},
-    transaction::{builder::{AccountState, FeeHelper}, Reference}
+    crypto::{elgamal::{Ciphertext, CompressedCiphertext}, Hash, PublicKey},
+    network::Network,
+    transaction::{
+        builder::{AccountState, FeeBuilder, FeeHelper, TransactionSigner, TransactionTypeBuilder},
+        multisig::MultiSigPayload,
+        Reference
+    }
+};
+use crate::{
+    config::{
+        FEE_MULTIPLIER_HIGH_MAX,
+        FEE_MULTIPLIER_HIGH_MIN,
+        FEE_MULTIPLIER_NORMAL_MAX,
+        MEMPOOL_CONGESTION_THRESHOLD
+    },
+    storage::{Balance, EncryptedStorage},
+    wallet::WalletError
 };
-use crate::{storage::{Balance, EncryptedStorage}, wallet::WalletError};
+
+// Priority chosen by the user to trade the fees paid against the confirmation speed
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FeePriority {
+    // Minimum fees, may wait several blocks when the mempool is congested
+    Low,
+    Normal,
+    High
+}
+
+impl FeePriority {
+    pub fn from_str(value: &str) -> Option<Self> {
+        Some(match value {
+            "low" => Self::Low,
+            "normal" => Self::Normal,
+            "high" => Self::High,
+            _ => return None
+        })
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Low => "low",
+            Self::Normal => "normal",
+            Self::High => "high"
+        }
+    }
+
+    // Multiplier applied to the minimum fees
+    // It grows with the transactions waiting in the mempool until the congestion threshold
+    pub fn get_multiplier(&self, mempool_size: usize) -> f64 {
+        let congestion = (mempool_size as f64 / MEMPOOL_CONGESTION_THRESHOLD as f64).min(1f64);
+        match self {
+            Self::Low => 1f64,
+            Self::Normal => 1f64 + congestion * (FEE_MULTIPLIER_NORMAL_MAX - 1f64),
+            Self::High => FEE_MULTIPLIER_HIGH_MIN + congestion * (FEE_MULTIPLIER_HIGH_MAX - FEE_MULTIPLIER_HIGH_MIN)
+        }
+    }
+
+    pub fn to_fee_builder(&self, mempool_size: usize) -> FeeBuilder {
+        FeeBuilder::Multiplier(self.get_multiplier(mempool_size))
+    }
+}
+
+// Decide how the account balances and nonce are consumed when funding a new transaction
+// Integrators can implement it to control the wallet behavior
+pub trait SelectionStrategy: Send + Sync {
+    fn get_name(&self) -> &str;
+
+    // Allow to spend a balance on top of transactions not yet included in a block,
+    // using the next nonce available
+    // If false, the transaction is rejected until the pending ones are confirmed
+    fn allow_pending_balance(&self, _asset: &Hash) -> bool {
+        true
+    }
+
+    // Ask the daemon which destinations are already registered,
+    // to only pay the account creation fee for the new ones
+    // This reveals the destinations to the daemon before the transaction is sent
+    fn check_registered_destinations(&self) -> bool {
+        true
+    }
+}
+
+// Pay the lowest fees and chain the transactions with the pending ones
+// This is the default strategy
+pub struct MinimizeFeeStrategy;
+
+impl SelectionStrategy for MinimizeFeeStrategy {
+    fn get_name(&self) -> &str {
+        "minimize_fee"
+    }
+}
+
+// Only spend the confirmed balances, one transaction per block
+// This prevents a chain of pending transactions to be invalidated at once
+pub struct ConsolidateStrategy;
+
+impl SelectionStrategy for ConsolidateStrategy {
+    fn get_name(&self) -> &str {
+        "consolidate"
+    }
+
+    fn allow_pending_balance(&self, _: &Hash) -> bool {
+        false
+    }
+}
+
+// Never query the daemon about the destinations
+// The account creation fee is paid for each destination
+pub struct PrivacyStrategy;
+
+impl SelectionStrategy for PrivacyStrategy {
+    fn get_name(&self) -> &str {
+        "privacy"
+    }
+
+    fn check_registered_destinations(&self) -> bool {
+        false
+    }
+}
+
+// Find a built-in strategy by its name
+pub fn get_selection_strategy(name: &str) -> Option<Arc<dyn SelectionStrategy>> {
+    Some(match name {
+        "minimize_fee" => Arc::new(MinimizeFeeStrategy),
+        "consolidate" => Arc::new(ConsolidateStrategy),
+        "privacy" => Arc::new(PrivacyStrategy),
+        _ => return None
+    })
+}
 
 // State used to estimate fees for a transaction
 // Because fees can be higher if a destination account is not registered
@@ -28,6 +155,10 @@ impl EstimateFeesState {
     pub fn add_registered_key(&mut self, key: PublicKey) {
         self.registered_keys.insert(key);
     }
+
+    pub fn get_registered_keys(&self) -> &HashSet<PublicKey> {
+        &self.registered_keys
+    }
 }
 
 impl FeeHelper for EstimateFeesState {
@@ -42,19 +173,19 @@ impl FeeHelper for EstimateFeesState {
 // It contains the balances of the wallet and the registered keys
 pub struct TransactionBuilderState {
     inner: EstimateFeesState,
-    mainnet: bool,
+    network: Network,
     balances: HashMap<Hash, Balance>,
     reference: Reference,
     nonce: u64,
 }
 
 impl TransactionBuilderState {
-    pub fn new(mainnet: bool, reference: Reference, nonce: u64) -> Self {
+    pub fn new(network: Network, reference: Reference, nonce: u64) -> Self {
         Self {
             inner: EstimateFeesState {
                 registered_keys: HashSet::new(),
             },
-            mainnet,
+            network,
             balances: HashMap::new(),
             reference,
             nonce
@@ -81,7 +212,10 @@ impl TransactionBuilderState {
         for (asset, balance) in self.balances.drain() {
             storage.set_unconfirmed_balance_for(asset, balance).await?;
         }
-        storage.set_nonce(self.nonce)?;
+        // A replacement reuses an older nonce, the stored one must never go back
+        if self.nonce > storage.get_nonce().unwrap_or(0) {
+            storage.set_nonce(self.nonce)?;
+        }
 
         Ok(())
     }
@@ -96,8 +230,8 @@ impl FeeHelper for TransactionBuilderState {
 }
 
 impl AccountState for TransactionBuilderState {
-    fn is_mainnet(&self) -> bool {
-        self.mainnet
+    fn get_network(&self) -> Network {
+        self.network
     }
 
     fn get_reference(&self) -> Reference {
@@ -134,4 +268,35 @@ impl AsMut<EstimateFeesState> for TransactionBuilderState {
     fn as_mut(&mut self) -> &mut EstimateFeesState {
         &mut self.inner
     }
-}
\ No newline at end of file
+}
+
+// Everything needed by an offline wallet to build and sign a transaction
+// It is created by an online (or watch-only) wallet which knows the current state of the account
+#[derive(Serialize, Deserialize)]
+pub struct UnsignedTransaction {
+    pub network: Network,
+    // Account that must sign the transaction
+    pub source: PublicKey,
+    pub tx_type: TransactionTypeBuilder,
+    pub fee: FeeBuilder,
+    pub nonce: u64,
+    pub reference: Reference,
+    // Encrypted balances of each asset used
+    pub balances: HashMap<Hash, CompressedCiphertext>,
+    // Destination keys already registered on chain, used to compute the fees
+    pub registered_keys: HashSet<PublicKey>
+}
+
+// A transaction of a multisig account waiting for the signatures of its participants
+// It is shared with each participant, then given back to the owner to finalize it
+#[derive(Serialize, Deserialize)]
+pub struct MultiSigTransaction {
+    pub network: Network,
+    // Setup of the account when the transaction was built
+    // The participants signatures are referenced by their index in it
+    pub setup: MultiSigPayload,
+    // Content requested by the owner, shown to the participants before signing
+    // Amounts are encrypted in the transaction and can't be verified against it
+    pub tx_type: TransactionTypeBuilder,
+    pub transaction: TransactionSigner
+}
@@ -1,13 +1,23 @@
 use std::{
+    collections::HashMap,
     fs::{create_dir_all, File},
     io::{Read, Write},
     path::Path,
-    sync::Arc
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc,
+        RwLock as StdRwLock
+    }
 };
 use anyhow::{Error, Context};
 use serde::Serialize;
+use tokio::{
+    task::JoinHandle,
+    time::{sleep, Duration}
+};
 use tokio::sync::{
     broadcast::{
+        error::RecvError,
         Sender as BroadcastSender,
         Receiver as BroadcastReceiver
     },
@@ -15,35 +25,50 @@ use tokio::sync::{
     RwLock
 };
 use xelis_common::{
+    account::CiphertextCache,
     api::{
         wallet::{
             BalanceChanged,
+            BlockScanned,
+            IncomingTransfer,
             NotifyEvent,
-            TransactionEntry
+            SyncProgress,
+            TransactionEntry,
+            TransactionFailed
         },
         DataElement
     },
     asset::AssetWithData,
+    config::{MAX_TRANSACTION_SIZE, XELIS_ASSET},
     crypto::{
         ecdlp::{self, ECDLPTablesFileView},
         elgamal::{Ciphertext, DecryptHandle, PublicKey as DecompressedPublicKey},
         Address,
         Hash,
+        mnemonic::{self, MNEMONIC_WORDS_COUNT},
+        Hashable,
         KeyPair,
+        PrivateKey,
         PublicKey,
         Signature
     },
+    json_rpc::{ProxyConfig, TlsConfig},
     network::Network,
     serializer::Serializer,
+    time::get_current_time_in_seconds,
     transaction::{
         aead::{self, AEADCipher},
         builder::{
             FeeBuilder,
             TransactionBuilder,
-            TransactionTypeBuilder
+            TransactionTypeBuilder,
+            TransferBuilder
         },
+        multisig::{MultiSigPayload, SignatureId},
+        BurnPayload,
         Reference,
-        Transaction
+        Transaction,
+        MULTISIG_TX_VERSION
     },
     utils::{
         format_coin,
@@ -51,24 +76,47 @@ use xelis_common::{
     }
 };
 use crate::{
-    cipher::Cipher,
+    backup::WalletBackup,
+    builder::WalletBuilder,
+    cipher::{mix_keyfile, Cipher},
     config::{
-        PASSWORD_ALGORITHM,
+        get_password_params,
+        BLOCK_TIME_SECONDS,
+        DAEMON_FAILOVER_DELAY,
+        DEFAULT_SYNC_BATCH_SIZE,
+        DEFAULT_SYNC_CONCURRENCY,
+        EVENT_CHANNEL_CAPACITY,
+        PASSWORD_ALGORITHM_VERSION,
+        PASSWORD_ALGORITHM_VERSION_CUSTOM,
         PASSWORD_HASH_SIZE,
+        REPLACEMENT_FEE_INCREASE_PERCENT,
         SALT_SIZE
     },
     daemon_api::DaemonAPI,
+    entry::{EntryData, PendingTransaction},
     mnemonics,
+    paper::PaperWallet,
+    password::PasswordParams,
     network_handler::{
         NetworkError,
         NetworkHandler,
         SharedNetworkHandler
     },
+    signer::{KeySigner, SignerError},
     storage::{
+        Balance,
         EncryptedStorage,
         Storage
     },
-    transaction_builder::{EstimateFeesState, TransactionBuilderState}
+    transaction_builder::{
+        EstimateFeesState,
+        FeePriority,
+        MinimizeFeeStrategy,
+        MultiSigTransaction,
+        SelectionStrategy,
+        TransactionBuilderState,
+        UnsignedTransaction
+    }
 };
 use chacha20poly1305::{
     aead::OsRng,
@@ -80,7 +128,14 @@ use log::{
     trace,
     debug,
     error,
-    info
+    info,
+    warn
+};
+
+#[cfg(feature = "grpc")]
+use {
+    std::net::SocketAddr,
+    crate::api::grpc
 };
 
 #[cfg(feature = "api_server")]
@@ -97,8 +152,10 @@ use {
         AppStateShared,
         PermissionResult,
         PermissionRequest,
+        RPCTokenHandler,
         XSWDPermissionHandler
     },
+    crate::api_token::TokenScope,
     xelis_common::rpc_server::{
         RPCHandler,
         RpcRequest,
@@ -116,6 +173,10 @@ use {
 pub enum WalletError {
     #[error("Transaction too big: {} bytes, max is {} bytes", _0, _1)]
     TransactionTooBig(usize, usize),
+    #[error("Transaction fee {} is above the maximum fee {} allowed", format_xelis(*_0), format_xelis(*_1))]
+    FeeTooHigh(u64, u64),
+    #[error("Asset {} has transactions waiting to be confirmed, not allowed by the '{}' selection strategy", _0, _1)]
+    PendingBalance(Hash, String),
     #[error("Invalid key pair")]
     InvalidKeyPair,
     #[error("Invalid signature")]
@@ -150,12 +211,20 @@ pub enum WalletError {
     NotEnoughFundsForFee(u64, u64),
     #[error("Invalid address params")]
     InvalidAddressParams,
+    #[error("Address is for {} but the wallet is running on {}", _0, _1)]
+    InvalidAddressNetwork(Network, Network),
     #[error("Invalid extra data in this transaction, expected maximum {} bytes but got {} bytes", _0, _1)]
     ExtraDataTooBig(usize, usize),
     #[error("Wallet is not in online mode")]
     NotOnlineMode,
     #[error("Wallet is already in online mode")]
     AlreadyOnlineMode,
+    #[error("No daemon available")]
+    NoDaemonAvailable,
+    #[error("Syncing is already paused")]
+    SyncAlreadyPaused,
+    #[error("Syncing is not paused")]
+    SyncNotPaused,
     #[error("Asset is already present on disk")]
     AssetAlreadyRegistered,
     #[error("Topoheight is too high to rescan")]
@@ -168,10 +237,16 @@ pub enum WalletError {
     RPCServerNotRunning,
     #[error("RPC Server is already running")]
     RPCServerAlreadyRunning,
+    #[error("gRPC Server is already running")]
+    GrpcServerAlreadyRunning,
     #[error("Invalid fees provided, minimum fees calculated: {}, provided: {}", format_xelis(*_0), format_xelis(*_1))]
     InvalidFeeProvided(u64, u64),
     #[error("Wallet name cannot be empty")]
     EmptyName,
+    #[error("Wallet builder requires a storage path or a storage backend")]
+    MissingStorage,
+    #[error("Wallet builder requires a password")]
+    MissingPassword,
     #[error("No handler available for this request")]
     NoHandlerAvailable,
     #[error(transparent)]
@@ -182,6 +257,72 @@ pub enum WalletError {
     CiphertextDecode,
     #[error(transparent)]
     AEADCipherFormatError(#[from] aead::CipherFormatError),
+    #[error("Transaction was rejected by the daemon: {}", _0)]
+    TransactionRejected(String),
+    #[error("Wallet is locked, unlock it using your password")]
+    Locked,
+    #[error("Wallet is in watch-only mode, private key is not available")]
+    WatchOnly,
+    #[error("Wallet is in view-only mode, it can't sign")]
+    ViewOnly,
+    #[error("Unknown password algorithm version {}", _0)]
+    UnknownPasswordAlgorithmVersion(u8),
+    #[error("Invalid password params: {}", _0)]
+    InvalidPasswordParams(String),
+    #[error("A keyfile is required to open this wallet")]
+    KeyfileRequired,
+    #[error("Account {} not found", _0)]
+    AccountNotFound(u32),
+    #[error("Contact '{}' already exists in address book", _0)]
+    ContactAlreadyExists(String),
+    #[error("Contact '{}' was not found in address book", _0)]
+    ContactNotFound(String),
+    #[error(transparent)]
+    Signer(#[from] SignerError),
+    #[error("No signer is set for this wallet")]
+    NoSigner,
+    #[error("Signer uses another key than the wallet")]
+    SignerKeyMismatch,
+    #[error("Transaction {} is not pending", _0)]
+    PendingTransactionNotFound(Hash),
+    #[error("Transaction {} can't be replaced, it is not the only pending transaction", _0)]
+    PendingTransactionNotOnly(Hash),
+    #[error("Invalid nonce {}, the next nonce is {}", _0, _1)]
+    NonceTooHigh(u64, u64),
+    #[error("Nonce {} can only be reused to replace the only pending transaction", _0)]
+    NonceReplacementNotAllowed(u64),
+    #[error("Draft '{}' already exists", _0)]
+    DraftAlreadyExists(String),
+    #[error("Draft '{}' was not found", _0)]
+    DraftNotFound(String),
+    #[error("Template '{}' was not found", _0)]
+    TemplateNotFound(String),
+    #[error("Webhook '{}' is already registered", _0)]
+    WebhookAlreadyExists(String),
+    #[error("Webhook '{}' was not found", _0)]
+    WebhookNotFound(String),
+    #[error("API token '{}' already exists", _0)]
+    ApiTokenAlreadyExists(String),
+    #[error("API token '{}' was not found", _0)]
+    ApiTokenNotFound(String),
+    #[error("Spend limit of API token '{}' would be exceeded", _0)]
+    ApiTokenSpendLimitExceeded(String),
+    #[error("API token '{}' with a spend limit can only send XELIS", _0)]
+    ApiTokenAssetNotAllowed(String),
+    #[error("API token '{}' can't change the multisig setup, an admin token is required", _0)]
+    ApiTokenMultiSigNotAllowed(String),
+    #[error("Account is not a multisig")]
+    MultiSigNotConfigured,
+    #[error("This wallet is not a participant of the multisig")]
+    NotMultiSigParticipant,
+    #[error("Invalid signature from multisig participant {}", _0)]
+    InvalidMultiSigSignature(u8),
+    #[error("Not enough multisig signatures: {} provided, {} required", _0, _1)]
+    MultiSigThreshold(usize, u8),
+    #[error("Subaddress #{} was not found", _0)]
+    SubaddressNotFound(u32),
+    #[error("Subaddress #{} has no balance for asset {}", _0, _1)]
+    SubaddressEmpty(u32, Hash),
 }
 
 #[derive(Serialize, Clone)]
@@ -207,10 +348,20 @@ pub enum Event {
     Rescan {
         start_topoheight: u64   
     },
+    // Progress of the blocks scan during a sync
+    SyncProgress(SyncProgress),
+    // When a block has been scanned during a sync
+    NewBlockScanned(BlockScanned),
+    // When a transfer is received, sent in addition to the NewTransaction event
+    IncomingTransfer(IncomingTransfer),
+    // When a pending transaction got deleted from the daemon mempool without being executed
+    TransactionFailed(TransactionFailed),
     // Wallet is now in online mode
     Online,
     // Wallet is now in offline mode
-    Offline
+    Offline,
+    // Connection with the daemon was lost, Offline is sent too
+    Disconnected
 }
 
 impl Event {
@@ -221,8 +372,13 @@ impl Event {
             Event::BalanceChanged(_) => NotifyEvent::BalanceChanged,
             Event::NewAsset(_) => NotifyEvent::NewAsset,
             Event::Rescan { .. } => NotifyEvent::Rescan,
+            Event::SyncProgress(_) => NotifyEvent::SyncProgress,
+            Event::NewBlockScanned(_) => NotifyEvent::NewBlockScanned,
+            Event::IncomingTransfer(_) => NotifyEvent::IncomingTransfer,
+            Event::TransactionFailed(_) => NotifyEvent::TransactionFailed,
             Event::Online => NotifyEvent::Online,
-            Event::Offline => NotifyEvent::Offline
+            Event::Offline => NotifyEvent::Offline,
+            Event::Disconnected => NotifyEvent::Disconnected
         }
     }
 
@@ -280,15 +436,55 @@ impl PrecomputedTables {
 
 pub const PRECOMPUTED_TABLES_L1: usize = 26;
 
+// Result of the wallet integrity verification
+#[derive(Debug, Default)]
+pub struct VerificationReport {
+    // Number of storage entries decrypted
+    pub checked_entries: usize,
+    // Entries that couldn't be decrypted
+    pub corrupted_entries: Vec<String>,
+    // Public key derived from the stored private key doesn't match the wallet one
+    pub key_mismatch: bool,
+    // Assets whose stored balance differs from the one computed from history (asset, stored, computed)
+    pub balance_mismatches: Vec<(Hash, u64, i128)>,
+    // Assets whose cached balance differs from the stored one
+    pub cache_mismatches: Vec<Hash>
+}
+
+impl VerificationReport {
+    // No corruption found, balances mismatches from history are not counted
+    // as they are expected when the history is incomplete
+    pub fn is_valid(&self) -> bool {
+        self.corrupted_entries.is_empty() && !self.key_mismatch && self.cache_mismatches.is_empty()
+    }
+}
+
 pub struct Wallet {
     // Encrypted Wallet Storage
     storage: RwLock<EncryptedStorage>,
     // Private & Public key linked for this wallet
-    keypair: KeyPair,
+    // None if the wallet is in watch-only mode or locked
+    keypair: StdRwLock<Option<Arc<KeyPair>>>,
+    // Only the public key is known, no keypair is available
+    watch_only: bool,
+    // Keypair is only used to decrypt the balances and transfers
+    view_only: bool,
+    // Public key of the wallet
+    decompressed_public_key: DecompressedPublicKey,
     // Compressed public key
     public_key: PublicKey,
     // network handler for online mode to keep wallet synced
     network_handler: Mutex<Option<SharedNetworkHandler>>,
+    // Daemons to use by order of preference, the next one is used if the current one is unreachable
+    daemon_addresses: Mutex<Vec<String>>,
+    // Daemon currently used
+    active_daemon: Mutex<Option<String>>,
+    // Task switching to another daemon after a connection loss
+    failover_task: Mutex<Option<JoinHandle<()>>>,
+    // TLS options used for the secure daemon connections
+    tls_config: Mutex<Option<TlsConfig>>,
+    // SOCKS5 proxy used for all the daemon connections
+    proxy: Mutex<Option<ProxyConfig>>,
     // network on which we are connected
     network: Network,
     // RPC Server
@@ -297,19 +493,66 @@ pub struct Wallet {
     // All XSWD requests are routed through this channel
     #[cfg(feature = "api_server")]
     xswd_channel: RwLock<Option<UnboundedSender<XSWDEvent>>>,
+    // gRPC Server task with its bind address
+    #[cfg(feature = "grpc")]
+    grpc_server: Mutex<Option<(SocketAddr, JoinHandle<()>)>>,
     // Event broadcaster
     event_broadcaster: Mutex<Option<BroadcastSender<Event>>>,
     // Precomputed tables byte array
-    precomputed_tables: PrecomputedTablesShared
+    precomputed_tables: PrecomputedTablesShared,
+    // Is the wallet locked, spending funds requires to unlock it using the password
+    locked: AtomicBool,
+    // Last time (in seconds) the wallet was unlocked or a command was executed
+    last_activity: AtomicU64,
+    // Idle timeout in seconds before locking automatically the wallet, 0 means disabled
+    lock_timeout: AtomicU64,
+    // History retention policy, 0 means no limit
+    // Maximum number of transactions kept in history
+    history_max_transactions: AtomicU64,
+    // Maximum age in seconds of the transactions kept in history
+    history_max_age: AtomicU64,
+    // Light mode: only the block headers and our own transactions are downloaded during sync
+    light_mode: AtomicBool,
+    // Syncing was paused by the user, the daemon connection is closed until resumed
+    sync_paused: AtomicBool,
+    // Blocks requested per batch during a sync
+    sync_batch_size: AtomicUsize,
+    // Block requests sent in parallel during a sync
+    sync_concurrency: AtomicUsize,
+    // Plaintext balances per asset, kept in sync with the storage by the network handler
+    // None until loaded from storage on first access
+    balances_cache: RwLock<Option<HashMap<Hash, u64>>>,
+    // How the balances and nonce are consumed by new transactions
+    selection_strategy: Mutex<Arc<dyn SelectionStrategy>>,
+    // Maximum fee in atomic units accepted for a transaction, 0 means no limit
+    max_fee: AtomicU64,
+    // Device holding the private key of a watch-only wallet
+    signer: Mutex<Option<Arc<dyn KeySigner>>>,
+    // Hash of the keyfile mixed into the password, kept to verify the password when unlocking
+    keyfile: Mutex<Option<Hash>>
 }
 
-pub fn hash_password(password: String, salt: &[u8]) -> Result<[u8; PASSWORD_HASH_SIZE], WalletError> {
-    let mut output = [0; PASSWORD_HASH_SIZE];
-    PASSWORD_ALGORITHM.hash_password_into(password.as_bytes(), salt, &mut output).map_err(|e| WalletError::AlgorithmHashingError(e.to_string()))?;
-    Ok(output)
+// Version of the transaction to build for this type
+// Setting up a multisig requires the version introducing it
+fn get_tx_version(tx_type: &TransactionTypeBuilder) -> u8 {
+    match tx_type {
+        TransactionTypeBuilder::MultiSig(_) => MULTISIG_TX_VERSION,
+        _ => 0
+    }
+}
+
+// Hash the password using the algorithm parameters of the requested version
+pub fn hash_password(password: String, salt: &[u8], version: u8) -> Result<[u8; PASSWORD_HASH_SIZE], WalletError> {
+    let params = get_password_params(version).ok_or(WalletError::UnknownPasswordAlgorithmVersion(version))?;
+    params.hash(password.as_bytes(), salt)
 }
 
 impl Wallet {
+    // Configure a wallet to open or create, see WalletBuilder
+    pub fn builder() -> WalletBuilder {
+        WalletBuilder::new()
+    }
+
     // This will read from file if exists, or generate and store it in file
     // This must be call only one time, and can be cloned to be shared through differents wallets
     pub fn read_or_generate_precomputed_tables<P: ecdlp::ProgressTableGenerationReportFunction>(path: Option<String>, progress_report: P) -> Result<PrecomputedTablesShared, Error> {
@@ -340,50 +583,207 @@ impl Wallet {
 
     // Create a new wallet with the specificed storage, keypair and its network
     fn new(storage: EncryptedStorage, keypair: KeyPair, network: Network, precomputed_tables: PrecomputedTablesShared) -> Arc<Self> {
+        let public_key = keypair.get_public_key().clone();
+        Self::new_with(storage, Some(keypair), false, public_key, network, precomputed_tables)
+    }
+
+    // Create a new wallet with only its public key, it can't spend any funds
+    fn new_watch_only(storage: EncryptedStorage, public_key: DecompressedPublicKey, network: Network, precomputed_tables: PrecomputedTablesShared) -> Arc<Self> {
+        Self::new_with(storage, None, false, public_key, network, precomputed_tables)
+    }
+
+    // Create a new wallet which decrypts its balances but refuses to sign
+    fn new_view_only(storage: EncryptedStorage, keypair: KeyPair, network: Network, precomputed_tables: PrecomputedTablesShared) -> Arc<Self> {
+        let public_key = keypair.get_public_key().clone();
+        Self::new_with(storage, Some(keypair), true, public_key, network, precomputed_tables)
+    }
+
+    fn new_with(storage: EncryptedStorage, keypair: Option<KeyPair>, view_only: bool, decompressed_public_key: DecompressedPublicKey, network: Network, precomputed_tables: PrecomputedTablesShared) -> Arc<Self> {
         let zelf = Self {
             storage: RwLock::new(storage),
-            public_key: keypair.get_public_key().compress(),
-            keypair,
+            public_key: decompressed_public_key.compress(),
+            decompressed_public_key,
+            watch_only: keypair.is_none(),
+            keypair: StdRwLock::new(keypair.map(Arc::new)),
+            view_only,
             network_handler: Mutex::new(None),
+            daemon_addresses: Mutex::new(Vec::new()),
+            active_daemon: Mutex::new(None),
+            failover_task: Mutex::new(None),
+            tls_config: Mutex::new(None),
+            proxy: Mutex::new(None),
             network,
             #[cfg(feature = "api_server")]
             api_server: Mutex::new(None),
             #[cfg(feature = "api_server")]
             xswd_channel: RwLock::new(None),
+            #[cfg(feature = "grpc")]
+            grpc_server: Mutex::new(None),
             event_broadcaster: Mutex::new(None),
-            precomputed_tables
+            precomputed_tables,
+            locked: AtomicBool::new(false),
+            last_activity: AtomicU64::new(get_current_time_in_seconds()),
+            lock_timeout: AtomicU64::new(0),
+            history_max_transactions: AtomicU64::new(0),
+            history_max_age: AtomicU64::new(0),
+            light_mode: AtomicBool::new(false),
+            sync_paused: AtomicBool::new(false),
+            sync_batch_size: AtomicUsize::new(DEFAULT_SYNC_BATCH_SIZE),
+            sync_concurrency: AtomicUsize::new(DEFAULT_SYNC_CONCURRENCY),
+            balances_cache: RwLock::new(None),
+            selection_strategy: Mutex::new(Arc::new(MinimizeFeeStrategy)),
+            max_fee: AtomicU64::new(0),
+            signer: Mutex::new(None),
+            keyfile: Mutex::new(None)
         };
 
         Arc::new(zelf)
     }
 
     // Create a new wallet on disk
+    // A new BIP39 mnemonic is generated if no seed is given
     pub fn create(name: String, password: String, seed: Option<String>, network: Network, precomputed_tables: PrecomputedTablesShared) -> Result<Arc<Self>, Error> {
         if name.is_empty() {
             return Err(WalletError::EmptyName.into())
         }
 
-        // generate random keypair or recover it from seed
-        let keypair = if let Some(seed) = seed {
+        debug!("Creating storage for {}", name);
+        Self::create_with_seed(Storage::new(name)?, password, seed, network, precomputed_tables)
+    }
+
+    // Create a new wallet in the given storage from a seed or a new BIP39 mnemonic
+    pub(crate) fn create_with_seed(inner: Storage, password: String, seed: Option<String>, network: Network, precomputed_tables: PrecomputedTablesShared) -> Result<Arc<Self>, Error> {
+        let seed = match seed {
+            Some(seed) => seed,
+            None => {
+                debug!("Generating a new mnemonic...");
+                mnemonic::generate_mnemonic()
+            }
+        };
+
+        let keypair = Self::keypair_from_seed(&seed)?;
+        // Legacy seeds can be rebuilt from the private key, only BIP39 mnemonics are saved
+        let words: Vec<&str> = seed.split_whitespace().collect();
+        let mnemonic = if words.len() == MNEMONIC_WORDS_COUNT {
+            Some(words.join(" ").to_lowercase())
+        } else {
+            None
+        };
+
+        Self::create_with_keys(inner, password, keypair, mnemonic, network, precomputed_tables)
+    }
+
+    // Recover the keypair from its mnemonic seed
+    // A BIP39 mnemonic has 24 words, the legacy seed has 25 words with its checksum
+    pub(crate) fn keypair_from_seed(seed: &str) -> Result<KeyPair, Error> {
         debug!("Retrieving keypair from seed...");
         let words: Vec<String> = seed.split_whitespace().map(str::to_string).collect();
-        let key = mnemonics::words_to_key(&words)?;
-            KeyPair::from_private_key(key)
+        let key = if words.len() == MNEMONIC_WORDS_COUNT {
+            mnemonic::mnemonic_to_private_key(seed, "")?
         } else {
-            debug!("Generating a new keypair...");
-            KeyPair::new()
+            mnemonics::words_to_key(&words)?
         };
+        Ok(KeyPair::from_private_key(key))
+    }
+
+    // Create a new wallet on disk around an existing private key
+    // This is used to import keys from other tools or older releases
+    pub fn create_with_private_key(name: String, password: String, private_key: PrivateKey, network: Network, precomputed_tables: PrecomputedTablesShared) -> Result<Arc<Self>, Error> {
+        if name.is_empty() {
+            return Err(WalletError::EmptyName.into())
+        }
+
+        debug!("Creating storage for {}", name);
+        Self::create_with_keypair(Storage::new(name)?, password, KeyPair::from_private_key(private_key), network, precomputed_tables)
+    }
+
+    // Create a new wallet in the given storage, which must be empty
+    pub(crate) fn create_with_keypair(inner: Storage, password: String, keypair: KeyPair, network: Network, precomputed_tables: PrecomputedTablesShared) -> Result<Arc<Self>, Error> {
+        Self::create_with_keys(inner, password, keypair, None, network, precomputed_tables)
+    }
+
+    fn create_with_keys(inner: Storage, password: String, keypair: KeyPair, mnemonic: Option<String>, network: Network, precomputed_tables: PrecomputedTablesShared) -> Result<Arc<Self>, Error> {
+        let mut storage = Self::create_storage(inner, password, network)?;
+
+        // Store the private key
+        storage.set_private_key(&keypair.get_private_key())?;
+        if let Some(mnemonic) = mnemonic {
+            storage.set_mnemonic(&mnemonic)?;
+        }
+
+        // Flush the storage to be sure its written on disk
+        storage.flush()?;
+
+        Ok(Self::new(storage, keypair, network, precomputed_tables))
+    }
+
+    // Create a new watch-only wallet on disk using only the address to monitor
+    // It can't build any transaction and amounts can't be decrypted without the private key
+    pub fn create_watch_only(name: String, password: String, address: Address, network: Network, precomputed_tables: PrecomputedTablesShared) -> Result<Arc<Self>, Error> {
+        if name.is_empty() {
+            return Err(WalletError::EmptyName.into())
+        }
+
+        debug!("Creating storage for {}", name);
+        Self::create_watch_only_with_storage(Storage::new(name)?, password, address, network, precomputed_tables)
+    }
+
+    // Create a new watch-only wallet in the given storage, which must be empty
+    pub(crate) fn create_watch_only_with_storage(inner: Storage, password: String, address: Address, network: Network, precomputed_tables: PrecomputedTablesShared) -> Result<Arc<Self>, Error> {
+        if address.get_network() != network {
+            return Err(WalletError::InvalidAddressNetwork(address.get_network(), network).into())
+        }
+
+        let public_key = address.to_public_key();
+        let decompressed = public_key.decompress().map_err(|_| WalletError::InvalidKeyPair)?;
+
+        let mut storage = Self::create_storage(inner, password, network)?;
+
+        // Store only the public key
+        storage.set_public_key(&public_key)?;
+
+        // Flush the storage to be sure its written on disk
+        storage.flush()?;
+
+        Ok(Self::new_watch_only(storage, decompressed, network, precomputed_tables))
+    }
+
+    // Create a new view-only wallet on disk from a private key exported by `export_private_key`
+    // It scans and decrypts the balances and history of the account but refuses to sign
+    pub fn create_view_only(name: String, password: String, private_key: PrivateKey, network: Network, precomputed_tables: PrecomputedTablesShared) -> Result<Arc<Self>, Error> {
+        if name.is_empty() {
+            return Err(WalletError::EmptyName.into())
+        }
+
+        debug!("Creating storage for {}", name);
+        Self::create_view_only_with_storage(Storage::new(name)?, password, private_key, network, precomputed_tables)
+    }
 
+    // Create a new view-only wallet in the given storage, which must be empty
+    pub(crate) fn create_view_only_with_storage(inner: Storage, password: String, private_key: PrivateKey, network: Network, precomputed_tables: PrecomputedTablesShared) -> Result<Arc<Self>, Error> {
+        let keypair = KeyPair::from_private_key(private_key);
+        let mut storage = Self::create_storage(inner, password, network)?;
+
+        storage.set_private_key(&keypair.get_private_key())?;
+        storage.set_view_only()?;
+
+        // Flush the storage to be sure its written on disk
+        storage.flush()?;
+
+        Ok(Self::new_view_only(storage, keypair, network, precomputed_tables))
+    }
+
+    // Create the encrypted storage of a new wallet
+    fn create_storage(mut inner: Storage, password: String, network: Network) -> Result<EncryptedStorage, Error> {
         // generate random salt for hashed password
         let mut salt: [u8; SALT_SIZE] = [0; SALT_SIZE];
         OsRng.fill_bytes(&mut salt);
 
         // generate hashed password which will be used as key to encrypt master_key
         debug!("hashing provided password");
-        let hashed_password = hash_password(password, &salt)?;
+        let hashed_password = hash_password(password, &salt, PASSWORD_ALGORITHM_VERSION)?;
 
-        debug!("Creating storage for {}", name);
-        let mut inner = Storage::new(name)?;
+        inner.set_password_algorithm_version(PASSWORD_ALGORITHM_VERSION)?;
 
         // generate the Cipher
         let cipher = Cipher::new(&hashed_password, None)?;
@@ -406,15 +806,9 @@ impl Wallet {
         inner.set_encrypted_storage_salt(&encrypted_storage_salt)?;
 
         debug!("Creating encrypted storage");
-        let mut storage = EncryptedStorage::new(inner, &master_key, storage_salt, network)?;
-
-        // Store the private key
-        storage.set_private_key(&keypair.get_private_key())?;
-
-        // Flush the storage to be sure its written on disk
-        storage.flush()?;
+        let storage = EncryptedStorage::new(inner, &master_key, storage_salt, network)?;
 
-        Ok(Self::new(storage, keypair, network, precomputed_tables))
+        Ok(storage)
     }
 
     // Open an existing wallet on disk
@@ -424,8 +818,11 @@ impl Wallet {
         }
 
         debug!("Creating storage for {}", name);
-        let storage = Storage::new(name)?;
-        
+        Self::open_with_storage(Storage::new(name)?, password, network, precomputed_tables)
+    }
+
+    // Open an existing wallet from the given storage
+    pub(crate) fn open_with_storage(storage: Storage, password: String, network: Network, precomputed_tables: PrecomputedTablesShared) -> Result<Arc<Self>, Error> {
         // get password salt for KDF
         debug!("Retrieving password salt from public storage");
         let salt = storage.get_password_salt()?;
@@ -434,7 +831,7 @@ impl Wallet {
         debug!("Retrieving encrypted master key from public storage");
         let encrypted_master_key = storage.get_encrypted_master_key()?;
 
-        let hashed_password = hash_password(password, &salt)?;
+        let hashed_password = storage.get_password_params()?.hash(password.as_bytes(), &salt)?;
 
         // decrypt the encrypted master key using the hashed password (used as key)
         let cipher = Cipher::new(&hashed_password, None)?;
@@ -453,13 +850,152 @@ impl Wallet {
 
         debug!("Creating encrypted storage");
         let storage = EncryptedStorage::new(storage, &master_key, salt, network)?;
+        if storage.is_watch_only()? {
+            debug!("Retrieving public key from encrypted storage");
+            let public_key = storage.get_public_key()?;
+            let decompressed = public_key.decompress().map_err(|_| WalletError::InvalidKeyPair)?;
+
+            return Ok(Self::new_watch_only(storage, decompressed, network, precomputed_tables))
+        }
+
         debug!("Retrieving private key from encrypted storage");
         let private_key =  storage.get_private_key()?;
         let keypair = KeyPair::from_private_key(private_key);
 
+        if storage.is_view_only()? {
+            return Ok(Self::new_view_only(storage, keypair, network, precomputed_tables))
+        }
+
         Ok(Self::new(storage, keypair, network, precomputed_tables))
     }
 
+    // Restore a wallet on disk from a backup
+    // Balances and history are synced again from the chain once online
+    pub fn restore_backup(name: String, password: String, backup: WalletBackup, precomputed_tables: PrecomputedTablesShared) -> Result<Arc<Self>, Error> {
+        if name.is_empty() {
+            return Err(WalletError::EmptyName.into())
+        }
+
+        let network = backup.network;
+        debug!("Creating storage for {}", name);
+        let mut storage = Self::create_storage(Storage::new(name)?, password, network)?;
+        for (contact, address) in &backup.contacts {
+            storage.add_contact(contact, &Address::from_string(address)?)?;
+        }
+
+        if let Some(daemon_address) = &backup.daemon_address {
+            storage.set_daemon_address(daemon_address)?;
+        }
+
+        let wallet = match backup.private_key {
+            Some(private_key) => {
+                for (index, name) in &backup.accounts {
+                    if storage.add_account(name)? != *index {
+                        return Err(WalletError::AccountNotFound(*index).into())
+                    }
+
+                    let mut account_storage = storage.open_account(*index)?;
+                    account_storage.set_private_key(&private_key.derive_child(*index))?;
+                    account_storage.flush()?;
+                }
+
+                storage.set_private_key(&private_key)?;
+                storage.flush()?;
+                Self::new(storage, KeyPair::from_private_key(private_key), network, precomputed_tables)
+            },
+            None => {
+                let decompressed = backup.public_key.decompress().map_err(|_| WalletError::InvalidKeyPair)?;
+                storage.set_public_key(&backup.public_key)?;
+                storage.flush()?;
+                Self::new_watch_only(storage, decompressed, network, precomputed_tables)
+            }
+        };
+
+        Ok(wallet)
+    }
+
+    // Export the keys and local data of the wallet (all accounts) to be restored elsewhere
+    pub async fn export_backup(&self) -> Result<WalletBackup, Error> {
+        trace!("export backup");
+        let storage = self.storage.read().await;
+        let main = storage.open_account(0)?;
+        // It would be restored as a full wallet, the private key must be imported again instead
+        if main.is_view_only()? {
+            return Err(WalletError::ViewOnly.into())
+        }
+
+        let (private_key, public_key) = if main.is_watch_only()? {
+            (None, main.get_public_key()?)
+        } else {
+            let keypair = KeyPair::from_private_key(main.get_private_key()?);
+            let public_key = keypair.get_public_key().compress();
+            (Some(keypair.get_private_key().clone()), public_key)
+        };
+
+        let daemon_address = if main.has_daemon_address()? {
+            Some(main.get_daemon_address()?)
+        } else {
+            None
+        };
+
+        Ok(WalletBackup {
+            network: self.network,
+            private_key,
+            public_key,
+            accounts: storage.get_accounts()?,
+            contacts: main.get_contacts()?.into_iter().map(|(name, address)| (name, address.to_string())).collect(),
+            daemon_address
+        })
+    }
+
+    // Create a new account derived from the main account private key
+    // Its index is used for the derivation so it can be recovered from the same seed
+    pub async fn create_account(&self, name: String) -> Result<u32, Error> {
+        trace!("create account {}", name);
+        if self.is_watch_only() {
+            return Err(WalletError::WatchOnly.into())
+        }
+
+        if self.view_only {
+            return Err(WalletError::ViewOnly.into())
+        }
+
+        let mut storage = self.storage.write().await;
+        let main_key = storage.open_account(0)?.get_private_key()?;
+        let index = storage.add_account(&name)?;
+
+        let mut account_storage = storage.open_account(index)?;
+        account_storage.set_private_key(&main_key.derive_child(index))?;
+        account_storage.flush()?;
+
+        Ok(index)
+    }
+
+    // Open an account of this wallet
+    // It has its own keys, balances and history but share the same storage file and password
+    // The current wallet should be closed before using the new instance
+    pub async fn open_account(&self, account: u32) -> Result<Arc<Self>, Error> {
+        trace!("open account {}", account);
+        let storage = self.storage.read().await;
+        if !storage.has_account(account)? {
+            return Err(WalletError::AccountNotFound(account).into())
+        }
+
+        let account_storage = storage.open_account(account)?;
+        if account_storage.is_watch_only()? {
+            let decompressed = account_storage.get_public_key()?.decompress().map_err(|_| WalletError::InvalidKeyPair)?;
+            return Ok(Self::new_watch_only(account_storage, decompressed, self.network, self.precomputed_tables.clone()))
+        }
+
+        let keypair = KeyPair::from_private_key(account_storage.get_private_key()?);
+        Ok(Self::new(account_storage, keypair, self.network, self.precomputed_tables.clone()))
+    }
+
+    // Index of the account opened, 0 being the main account
+    pub async fn get_account(&self) -> u32 {
+        self.storage.read().await.get_account()
+    }
+
     // Close the wallet
     // this will stop the network handler and the API Server if it's running
     // Because wallet is behind Arc, we need to close differents modules that has a copy of it
@@ -485,6 +1021,14 @@ impl Wallet {
             }
         }
 
+        // Close gRPC server
+        #[cfg(feature = "grpc")]
+        {
+            if let Some((_, task)) = self.grpc_server.lock().await.take() {
+                task.abort();
+            }
+        }
+
         // Stop gracefully the network handler
         {
             let mut lock = self.network_handler.lock().await;
@@ -530,13 +1074,16 @@ impl Wallet {
         }
     }
 
-    // Subscribe to events
+    // Subscribe to the typed wallet events (new blocks scanned, incoming transfers,
+    // balance changes, disconnections...)
+    // Each receiver gets every event propagated after its subscription,
+    // a receiver too slow to consume them will get a Lagged error
     pub async fn subscribe_events(&self) -> BroadcastReceiver<Event> {
         let mut broadcaster = self.event_broadcaster.lock().await;
         match broadcaster.as_ref() {
             Some(broadcaster) => broadcaster.subscribe(),
             None => {
-                let (sender, receiver) = tokio::sync::broadcast::channel(10);
+                let (sender, receiver) = tokio::sync::broadcast::channel(EVENT_CHANNEL_CAPACITY);
                 *broadcaster = Some(sender);
                 receiver
             }
@@ -566,6 +1113,29 @@ impl Wallet {
         Ok(())
     }
 
+    // Enable the gRPC Server, clients must use an API token
+    #[cfg(feature = "grpc")]
+    pub async fn enable_grpc_server(self: &Arc<Self>, bind_address: SocketAddr) -> Result<(), Error> {
+        let mut lock = self.grpc_server.lock().await;
+        if lock.is_some() {
+            return Err(WalletError::GrpcServerAlreadyRunning.into())
+        }
+
+        let zelf = Arc::clone(self);
+        *lock = Some((bind_address, tokio::spawn(async move {
+            if let Err(e) = grpc::serve(zelf, bind_address).await {
+                error!("Error while running gRPC Server: {}", e);
+            }
+        })));
+        Ok(())
+    }
+
+    // Bind address of the gRPC Server if it is running
+    #[cfg(feature = "grpc")]
+    pub async fn get_grpc_bind_address(&self) -> Option<SocketAddr> {
+        self.grpc_server.lock().await.as_ref().map(|(address, _)| *address)
+    }
+
     // Enable XSWD Protocol
     #[cfg(feature = "api_server")]
     pub async fn enable_xswd(self: &Arc<Self>) -> Result<UnboundedReceiver<XSWDEvent>, Error> {
@@ -601,150 +1171,816 @@ impl Wallet {
     }
 
     // Verify if a password is valid or not
+    // The keyfile used to open the wallet is mixed automatically
     pub async fn is_valid_password(&self, password: String) -> Result<(), Error> {
+        let password = mix_keyfile(password, self.keyfile.lock().await.as_ref());
         let mut encrypted_storage = self.storage.write().await;
         let storage = encrypted_storage.get_mutable_public_storage();
         let salt = storage.get_password_salt()?;
-        let hashed_password = hash_password(password, &salt)?;
+        let hashed_password = storage.get_password_params()?.hash(password.as_bytes(), &salt)?;
         let cipher = Cipher::new(&hashed_password, None)?;
         let encrypted_master_key = storage.get_encrypted_master_key()?;
         let _ = cipher.decrypt_value(&encrypted_master_key).context("Invalid password provided")?;
         Ok(())
     }
 
-    // change the current password wallet to a new one
-    pub async fn set_password(&self, old_password: String, password: String) -> Result<(), Error> {
-        let mut encrypted_storage = self.storage.write().await;
-        let storage = encrypted_storage.get_mutable_public_storage();
-        let (master_key, storage_salt) = {
-            // retrieve old salt to build key from current password
-            let salt = storage.get_password_salt()?;
-            let hashed_password = hash_password(old_password, &salt)?;
+    // Lock the wallet, it must be unlocked using the password to spend funds again
+    // Private key is dropped from memory, so the syncing waits until the wallet is unlocked
+    pub fn lock(&self) {
+        trace!("lock wallet");
+        self.locked.store(true, Ordering::SeqCst);
+        self.keypair.write().unwrap_or_else(|e| e.into_inner()).take();
+    }
 
-            let encrypted_master_key = storage.get_encrypted_master_key()?;
-            let encrypted_storage_salt = storage.get_encrypted_storage_salt()?;
+    // Unlock the wallet if the password is valid
+    // Private key is loaded again from the encrypted storage
+    pub async fn unlock(&self, password: String) -> Result<(), Error> {
+        trace!("unlock wallet");
+        self.is_valid_password(password).await?;
+        if !self.watch_only {
+            let private_key = self.storage.read().await.get_private_key()?;
+            *self.keypair.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(KeyPair::from_private_key(private_key)));
+        }
+        self.locked.store(false, Ordering::SeqCst);
+        self.refresh_activity();
+        Ok(())
+    }
 
-            // decrypt the encrypted master key using the provided password
-            let cipher = Cipher::new(&hashed_password, None)?;
-            let master_key = cipher.decrypt_value(&encrypted_master_key).context("Invalid password provided")?;
-            let storage_salt = cipher.decrypt_value(&encrypted_storage_salt)?;
-            (master_key, storage_salt)
-        };
+    // Check if the wallet is locked
+    // If the idle timeout is reached, the wallet is locked automatically
+    pub fn is_locked(&self) -> bool {
+        if self.locked.load(Ordering::SeqCst) {
+            return true
+        }
 
-        // generate a new salt for password
-        let mut salt: [u8; SALT_SIZE] = [0; SALT_SIZE];
-        OsRng.fill_bytes(&mut salt);
+        let timeout = self.lock_timeout.load(Ordering::SeqCst);
+        if timeout != 0 && get_current_time_in_seconds().saturating_sub(self.last_activity.load(Ordering::SeqCst)) >= timeout {
+            debug!("Idle timeout reached, locking wallet");
+            self.lock();
+            return true
+        }
 
-        // generate the password-based derivated key to encrypt the master key
-        let hashed_password = hash_password(password, &salt)?;
-        let cipher = Cipher::new(&hashed_password, None)?;
+        false
+    }
 
-        // encrypt the master key using the new password
-        let encrypted_key = cipher.encrypt_value(&master_key)?;
+    // Set the maximum fee accepted for a transaction, None to disable it
+    pub fn set_max_fee(&self, max_fee: Option<u64>) {
+        self.max_fee.store(max_fee.unwrap_or(0), Ordering::SeqCst);
+    }
 
-        // encrypt the salt with the new password
-        let encrypted_storage_salt = cipher.encrypt_value(&storage_salt)?;
+    pub fn get_max_fee(&self) -> Option<u64> {
+        match self.max_fee.load(Ordering::SeqCst) {
+            0 => None,
+            max_fee => Some(max_fee)
+        }
+    }
 
-        // save on disk
-        storage.set_password_salt(&salt)?;
-        storage.set_encrypted_master_key(&encrypted_key)?;
-        storage.set_encrypted_storage_salt(&encrypted_storage_salt)?;
+    // Set the signer used to sign the transactions of a watch-only wallet
+    // Its public key must be the one of the wallet
+    pub async fn set_signer(&self, signer: Option<Arc<dyn KeySigner>>) -> Result<(), WalletError> {
+        if let Some(signer) = signer.as_ref() {
+            if signer.get_public_key().await? != self.public_key {
+                return Err(WalletError::SignerKeyMismatch)
+            }
+        }
 
+        *self.signer.lock().await = signer;
         Ok(())
     }
 
-    // Wallet has to be under a Arc to be shared to the spawn_blocking function
-    pub async fn decrypt_ciphertext(self: Arc<Self>, ciphertext: Ciphertext) -> Result<u64, WalletError> {
-        trace!("decrypt ciphertext");
-        tokio::task::spawn_blocking(move || {
-            let view = ECDLPTablesFileView::<PRECOMPUTED_TABLES_L1>::from_bytes(self.precomputed_tables.get());
-            self.keypair.get_private_key()
-                .decrypt(&view, &ciphertext)
-                .ok_or(WalletError::CiphertextDecode)
-        }).await.context("Error while decrypting ciphertext")?
-    }
-
-    // Decrypt the extra data from a transfer
-    pub fn decrypt_extra_data(&self, cipher: AEADCipher, handle: &DecryptHandle) -> Result<DataElement, WalletError> {
-        trace!("decrypt extra data");
-        let key = aead::derive_aead_key_from_handle(&self.keypair.get_private_key(), handle);
-        let plaintext = cipher.decrypt_in_place(&key)?;
-        DataElement::from_bytes(&plaintext.0).map_err(|_| WalletError::CiphertextDecode)
+    pub async fn get_signer(&self) -> Option<Arc<dyn KeySigner>> {
+        self.signer.lock().await.clone()
     }
 
-    // Create a transaction with the given transaction type and fee
-    // this will apply the changes to the storage if the transaction
-    pub async fn create_transaction(&self, transaction_type: TransactionTypeBuilder, fee: FeeBuilder) -> Result<Transaction, WalletError> {
-        trace!("create transaction");
-        let mut storage = self.storage.write().await;
-        let (mut state, transaction) = self.create_transaction_with_storage(&mut storage, transaction_type, fee).await?;
+    // Build a transaction and let the signer build its proofs and sign it
+    // The storage is not modified, the transaction must be submitted using `submit_signed_transaction`
+    pub async fn create_transaction_with_signer(&self, transaction_type: TransactionTypeBuilder, fee: FeeBuilder) -> Result<Transaction, WalletError> {
+        trace!("create transaction with signer");
+        let signer = self.get_signer().await.ok_or(WalletError::NoSigner)?;
+        let unsigned = self.build_unsigned_transaction(transaction_type, fee).await?;
+
+        debug!("Waiting for the transaction to be signed by {}", signer.get_name());
+        let transaction = signer.sign_transaction(&unsigned).await?;
+        if *transaction.get_source() != self.public_key || transaction.get_nonce() != unsigned.nonce {
+            return Err(WalletError::InvalidSignature)
+        }
 
-        state.apply_changes(&mut storage).await?;
+        // The fee is computed by the signer
+        if let Some(max_fee) = self.get_max_fee() {
+            if transaction.get_fee() > max_fee {
+                return Err(WalletError::FeeTooHigh(transaction.get_fee(), max_fee))
+            }
+        }
 
         Ok(transaction)
     }
 
-    // create the final transaction with calculated fees and signature
-    // also check that we have enough funds for the transaction
-    // This will returns the transaction builder state along the transaction
-    // You must handle "apply changes" to the storage
-    pub async fn create_transaction_with_storage(&self, storage: &mut EncryptedStorage, transaction_type: TransactionTypeBuilder, fee: FeeBuilder) -> Result<(TransactionBuilderState, Transaction), WalletError> {
-        trace!("create transaction with storage");
-        let nonce = storage.get_nonce().unwrap_or(0);
-
-        // Build the state for the builder
-        let used_assets = transaction_type.used_assets();
+    // Refuse to build a transaction paying more fees than allowed by the user
+    // This protects against a wrong estimation or a malicious daemon
+    fn verify_fee_cap(&self, builder: &TransactionBuilder, state: &mut TransactionBuilderState) -> Result<(), WalletError> {
+        if let Some(max_fee) = self.get_max_fee() {
+            let fee = builder.estimate_fees(state).map_err(|e| WalletError::Any(e.into()))?;
+            if fee > max_fee {
+                return Err(WalletError::FeeTooHigh(fee, max_fee))
+            }
+        }
 
-        // state used to build the transaction
-        let mut state = TransactionBuilderState::new(
-            self.network.is_mainnet(),
-            Reference {
-                topoheight: storage.get_synced_topoheight()?,
-                hash: storage.get_top_block_hash()?
-            },
-            nonce
-        );
+        Ok(())
+    }
 
-        // Get all balances used
-        for asset in used_assets {
-            trace!("Checking balance for asset {}", asset);
-            if !storage.has_balance_for(&asset).await? {
-                return Err(WalletError::BalanceNotFound(asset));
-            }
+    // Set the idle timeout before locking the wallet, None to disable it
+    pub fn set_lock_timeout(&self, timeout: Option<u64>) {
+        self.lock_timeout.store(timeout.unwrap_or(0), Ordering::SeqCst);
+        self.refresh_activity();
+    }
 
-            let balance = storage.get_unconfirmed_balance_for(&asset).await?;
-            state.add_balance(asset, balance);
+    // Idle timeout before locking the wallet
+    pub fn get_lock_timeout(&self) -> Option<u64> {
+        match self.lock_timeout.load(Ordering::SeqCst) {
+            0 => None,
+            timeout => Some(timeout)
         }
+    }
 
-        self.add_registered_keys_for_fees_estimation(state.as_mut(), &fee, &transaction_type).await?;
+    // Set the history retention policy, None to keep everything
+    // The age is in seconds and is estimated from the topoheight of the transactions
+    pub fn set_history_retention(&self, max_transactions: Option<u64>, max_age: Option<u64>) {
+        self.history_max_transactions.store(max_transactions.unwrap_or(0), Ordering::SeqCst);
+        self.history_max_age.store(max_age.unwrap_or(0), Ordering::SeqCst);
+    }
 
-        // Create the transaction builder
-        let builder = TransactionBuilder::new(0, self.public_key.clone(), transaction_type, fee);
+    // History retention policy (max transactions, max age in seconds)
+    pub fn get_history_retention(&self) -> (Option<u64>, Option<u64>) {
+        let filter = |value: u64| if value == 0 { None } else { Some(value) };
+        (filter(self.history_max_transactions.load(Ordering::SeqCst)), filter(self.history_max_age.load(Ordering::SeqCst)))
+    }
 
-        // Build the final transaction
-        let transaction = builder.build(&mut state, &self.keypair)
+    // Enable or disable the light sync mode
+    // In light mode, the wallet only downloads its transactions instead of scanning full blocks
+    // It trusts the daemon to not hide any of them, only their presence in the block header is verified
+    pub fn set_light_mode(&self, enabled: bool) {
+        self.light_mode.store(enabled, Ordering::SeqCst);
+    }
+
+    pub fn is_light_mode(&self) -> bool {
+        self.light_mode.load(Ordering::SeqCst)
+    }
+
+    // Set how many blocks are requested per batch and how many requests are sent in parallel during a sync
+    // A bigger batch uses more memory, both are at least 1
+    pub fn set_sync_options(&self, batch_size: usize, concurrency: usize) {
+        self.sync_batch_size.store(batch_size.max(1), Ordering::SeqCst);
+        self.sync_concurrency.store(concurrency.max(1), Ordering::SeqCst);
+    }
+
+    pub fn get_sync_batch_size(&self) -> usize {
+        self.sync_batch_size.load(Ordering::SeqCst)
+    }
+
+    pub fn get_sync_concurrency(&self) -> usize {
+        self.sync_concurrency.load(Ordering::SeqCst)
+    }
+
+    // Delete the transactions outside of the history retention policy
+    // Returns the number of transactions deleted
+    pub async fn prune_history(&self) -> Result<usize, Error> {
+        let (max_transactions, max_age) = self.get_history_retention();
+        if max_transactions.is_none() && max_age.is_none() {
+            return Ok(0)
+        }
+
+        let mut storage = self.storage.write().await;
+        let min_topoheight = match max_age {
+            Some(age) => Some(storage.get_synced_topoheight().unwrap_or(0).saturating_sub(age / BLOCK_TIME_SECONDS)),
+            None => None
+        };
+
+        let pruned = storage.prune_transactions(max_transactions.map(|v| v as usize), min_topoheight)?;
+        if pruned > 0 {
+            debug!("{} transactions pruned from history", pruned);
+        }
+
+        Ok(pruned)
+    }
+
+    // Mark the wallet as used to reset the idle timer
+    pub fn refresh_activity(&self) {
+        self.last_activity.store(get_current_time_in_seconds(), Ordering::SeqCst);
+    }
+
+    // change the current password wallet to a new one
+    // A new master key is generated and all the storage is re-encrypted with it
+    // The password is hashed using the latest algorithm version, upgrading older wallets
+    // Custom parameters set on the wallet are kept
+    pub async fn set_password(&self, old_password: String, password: String) -> Result<(), Error> {
+        let params = self.get_custom_password_params().await?;
+        self.set_password_with_params(old_password, password, params).await
+    }
+
+    // Parameters set on the wallet, None if it uses the ones of an algorithm version
+    async fn get_custom_password_params(&self) -> Result<Option<PasswordParams>, Error> {
+        let encrypted_storage = self.storage.read().await;
+        let storage = encrypted_storage.get_public_storage();
+        if storage.get_password_algorithm_version()? == PASSWORD_ALGORITHM_VERSION_CUSTOM {
+            Ok(Some(storage.get_password_params()?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    // Parameters used to hash the password of this wallet
+    pub async fn get_password_params(&self) -> Result<PasswordParams, Error> {
+        let encrypted_storage = self.storage.read().await;
+        encrypted_storage.get_public_storage().get_password_params()
+    }
+
+    // change the current password and the parameters used to hash it
+    // None uses the parameters of the latest algorithm version
+    // The keyfile used to open the wallet stays required
+    pub async fn set_password_with_params(&self, old_password: String, password: String, params: Option<PasswordParams>) -> Result<(), Error> {
+        let (old_password, password) = {
+            let keyfile = self.keyfile.lock().await;
+            (mix_keyfile(old_password, keyfile.as_ref()), mix_keyfile(password, keyfile.as_ref()))
+        };
+
+        self.rekey(old_password, password, params).await
+    }
+
+    // Set the hash of the keyfile used to open the wallet, see `cipher::hash_keyfile`
+    pub async fn set_keyfile_hash(&self, keyfile: Option<Hash>) {
+        *self.keyfile.lock().await = keyfile;
+    }
+
+    pub async fn get_keyfile_hash(&self) -> Option<Hash> {
+        self.keyfile.lock().await.clone()
+    }
+
+    // Add, replace or remove the keyfile required with the password to open the wallet
+    // The storage hint is only updated once the master key is re-encrypted,
+    // so a failed rekey keeps the wallet as it was
+    pub async fn set_keyfile(&self, password: String, keyfile: Option<Hash>) -> Result<(), Error> {
+        let mut current = self.keyfile.lock().await;
+        let old_password = mix_keyfile(password.clone(), current.as_ref());
+        let new_password = mix_keyfile(password, keyfile.as_ref());
+        let params = self.get_custom_password_params().await?;
+
+        self.rekey(old_password, new_password, params).await?;
+
+        {
+            let mut storage = self.storage.write().await;
+            storage.get_mutable_public_storage().set_keyfile_required(keyfile.is_some())?;
+            storage.flush()?;
+        }
+
+        *current = keyfile;
+        Ok(())
+    }
+
+    // Re-encrypt the master key using a new KDF input
+    async fn rekey(&self, old_password: String, password: String, params: Option<PasswordParams>) -> Result<(), Error> {
+        if let Some(params) = params.as_ref() {
+            params.validate()?;
+        }
+
+        let mut encrypted_storage = self.storage.write().await;
+        let storage_salt = {
+            let storage = encrypted_storage.get_public_storage();
+            // retrieve old salt to build key from current password
+            let salt = storage.get_password_salt()?;
+            let hashed_password = storage.get_password_params()?.hash(old_password.as_bytes(), &salt)?;
+
+            let encrypted_master_key = storage.get_encrypted_master_key()?;
+            let encrypted_storage_salt = storage.get_encrypted_storage_salt()?;
+
+            // decrypt the encrypted master key using the provided password
+            let cipher = Cipher::new(&hashed_password, None)?;
+            cipher.decrypt_value(&encrypted_master_key).context("Invalid password provided")?;
+            cipher.decrypt_value(&encrypted_storage_salt)?
+        };
+
+        // generate a new salt for password
+        let mut salt: [u8; SALT_SIZE] = [0; SALT_SIZE];
+        OsRng.fill_bytes(&mut salt);
+
+        // generate the password-based derivated key to encrypt the master key
+        let (version, hashed_password) = match params.as_ref() {
+            Some(params) => {
+                debug!("hashing new password with custom params {}", params);
+                (PASSWORD_ALGORITHM_VERSION_CUSTOM, params.hash(password.as_bytes(), &salt)?)
+            },
+            None => {
+                debug!("hashing new password with algorithm version {}", PASSWORD_ALGORITHM_VERSION);
+                (PASSWORD_ALGORITHM_VERSION, hash_password(password, &salt, PASSWORD_ALGORITHM_VERSION)?)
+            }
+        };
+        let cipher = Cipher::new(&hashed_password, None)?;
+
+        // generate a new master key and encrypt it using the new password
+        let mut master_key: [u8; 32] = [0; 32];
+        OsRng.fill_bytes(&mut master_key);
+        let encrypted_key = cipher.encrypt_value(&master_key)?;
+
+        // encrypt the salt with the new password
+        let encrypted_storage_salt = cipher.encrypt_value(&storage_salt)?;
+
+        // re-encrypt everything then save the new keys
+        // an interruption is rolled back, so the wallet stays usable with the old password
+        info!("Re-encrypting wallet storage");
+        encrypted_storage.reencrypt(&master_key, &encrypted_key, &salt, &encrypted_storage_salt, version, params.as_ref())?;
+        encrypted_storage.flush()?;
+
+        Ok(())
+    }
+
+    // Wallet has to be under a Arc to be shared to the spawn_blocking function
+    pub async fn decrypt_ciphertext(self: Arc<Self>, ciphertext: Ciphertext) -> Result<u64, WalletError> {
+        let private_key = self.get_view_keypair()?.get_private_key().clone();
+        self.decrypt_ciphertext_with(private_key, ciphertext).await
+    }
+
+    // Decrypt a ciphertext sent to another key of this wallet, such as a subaddress
+    pub async fn decrypt_ciphertext_with(self: Arc<Self>, private_key: PrivateKey, ciphertext: Ciphertext) -> Result<u64, WalletError> {
+        trace!("decrypt ciphertext");
+        tokio::task::spawn_blocking(move || {
+            let view = ECDLPTablesFileView::<PRECOMPUTED_TABLES_L1>::from_bytes(self.precomputed_tables.get());
+            private_key.decrypt(&view, &ciphertext)
+                .ok_or(WalletError::CiphertextDecode)
+        }).await.context("Error while decrypting ciphertext")?
+    }
+
+    // Decrypt the extra data from a transfer
+    pub fn decrypt_extra_data(&self, cipher: AEADCipher, handle: &DecryptHandle) -> Result<DataElement, WalletError> {
+        self.decrypt_extra_data_with(self.get_view_keypair()?.get_private_key(), cipher, handle)
+    }
+
+    // Decrypt the extra data from a transfer sent to another key of this wallet
+    pub fn decrypt_extra_data_with(&self, private_key: &PrivateKey, cipher: AEADCipher, handle: &DecryptHandle) -> Result<DataElement, WalletError> {
+        trace!("decrypt extra data");
+        let key = aead::derive_aead_key_from_handle(private_key, handle);
+        let plaintext = cipher.decrypt_in_place(&key)?;
+        DataElement::from_bytes(&plaintext.0).map_err(|_| WalletError::CiphertextDecode)
+    }
+
+    // Create a transaction with the given transaction type and fee
+    // this will apply the changes to the storage if the transaction
+    pub async fn create_transaction(&self, transaction_type: TransactionTypeBuilder, fee: FeeBuilder) -> Result<Transaction, WalletError> {
+        trace!("create transaction");
+        let mut storage = self.storage.write().await;
+        let (mut state, transaction) = self.create_transaction_with_storage(&mut storage, transaction_type, fee).await?;
+
+        state.apply_changes(&mut storage).await?;
+
+        Ok(transaction)
+    }
+
+    // create the final transaction with calculated fees and signature
+    // also check that we have enough funds for the transaction
+    // This will returns the transaction builder state along the transaction
+    // You must handle "apply changes" to the storage
+    pub async fn create_transaction_with_storage(&self, storage: &mut EncryptedStorage, transaction_type: TransactionTypeBuilder, fee: FeeBuilder) -> Result<(TransactionBuilderState, Transaction), WalletError> {
+        self.create_transaction_with_nonce(storage, transaction_type, fee, None).await
+    }
+
+    // Same as `create_transaction_with_storage` but allow to override the nonce used
+    // This is useful to re-issue a transaction that got stuck and is blocking the next nonces
+    // When the nonce is lower than the stored one, confirmed balances are used
+    // as the unconfirmed ones depend on the transactions we are replacing
+    pub async fn create_transaction_with_nonce(&self, storage: &mut EncryptedStorage, transaction_type: TransactionTypeBuilder, fee: FeeBuilder, nonce: Option<u64>) -> Result<(TransactionBuilderState, Transaction), WalletError> {
+        trace!("create transaction with nonce {:?}", nonce);
+        let keypair = self.get_keypair()?;
+        if self.is_locked() {
+            return Err(WalletError::Locked)
+        }
+        self.refresh_activity();
+
+        let mut state = self.create_builder_state(storage, &transaction_type, &fee, nonce).await?;
+
+        // Create the transaction builder
+        let builder = TransactionBuilder::new(get_tx_version(&transaction_type), self.public_key.clone(), transaction_type, fee);
+
+        // Reject it before generating the proofs if it can't be accepted by the network
+        let size = builder.estimate_size();
+        if size > MAX_TRANSACTION_SIZE {
+            return Err(WalletError::TransactionTooBig(size, MAX_TRANSACTION_SIZE))
+        }
+        self.verify_fee_cap(&builder, &mut state)?;
+
+        // Build the final transaction
+        let transaction = builder.build(&mut state, &keypair)
             .map_err(|e| WalletError::Any(e.into()))?;
 
         Ok((state, transaction))
     }
 
+    // Prepare the state used to build a transaction: nonce, reference and balances used
+    async fn create_builder_state(&self, storage: &mut EncryptedStorage, transaction_type: &TransactionTypeBuilder, fee: &FeeBuilder, nonce: Option<u64>) -> Result<TransactionBuilderState, WalletError> {
+        let stored_nonce = storage.get_nonce().unwrap_or(0);
+        let nonce = nonce.unwrap_or(stored_nonce);
+        if nonce > stored_nonce {
+            return Err(WalletError::NonceTooHigh(nonce, stored_nonce))
+        }
+
+        let replace = nonce < stored_nonce;
+        if replace {
+            // The daemon only accepts a replacement when it is the only transaction of the account in mempool
+            let pending = storage.get_pending_transactions()?;
+            if nonce + 1 != stored_nonce || pending.len() != 1 || pending[0].get_nonce() != nonce {
+                return Err(WalletError::NonceReplacementNotAllowed(nonce))
+            }
+            warn!("Building a transaction with nonce {} while the next nonce is {}, it will replace a previous transaction", nonce, stored_nonce);
+        }
+
+        // Build the state for the builder
+        let used_assets = transaction_type.used_assets();
+
+        // state used to build the transaction
+        let mut state = TransactionBuilderState::new(
+            self.network,
+            Reference {
+                topoheight: storage.get_synced_topoheight()?,
+                hash: storage.get_top_block_hash()?
+            },
+            nonce
+        );
+
+        // Get all balances used
+        let strategy = self.get_selection_strategy().await;
+        for asset in used_assets {
+            trace!("Checking balance for asset {}", asset);
+            if !storage.has_balance_for(&asset).await? {
+                return Err(WalletError::BalanceNotFound(asset));
+            }
+
+            if !replace && !strategy.allow_pending_balance(&asset) && storage.has_unconfirmed_balance_for(&asset).await {
+                return Err(WalletError::PendingBalance(asset, strategy.get_name().to_owned()));
+            }
+
+            let balance = if replace {
+                storage.get_balance_for(&asset).await?
+            } else {
+                storage.get_unconfirmed_balance_for(&asset).await?
+            };
+            state.add_balance(asset, balance);
+        }
+
+        self.add_registered_keys_for_fees_estimation(state.as_mut(), fee, transaction_type).await?;
+
+        Ok(state)
+    }
+
+    // Get the multisig setup of the wallet account from the daemon
+    // Returns None if the account is not a multisig
+    pub async fn get_multisig_setup(&self) -> Result<Option<MultiSigPayload>, WalletError> {
+        trace!("get multisig setup");
+        let network_handler = self.network_handler.lock().await;
+        let network_handler = network_handler.as_ref().ok_or(WalletError::NotOnlineMode)?;
+        let multisig = network_handler.get_api().get_multisig(&self.get_address()).await?;
+
+        Ok(multisig.and_then(|multisig| multisig.state).map(MultiSigPayload::from))
+    }
+
+    // Build a transaction of a multisig account without its final signature
+    // It must be signed by enough participants using `sign_multisig_transaction` before being finalized
+    // Nothing is changed in the storage, the nonce is updated once it is submitted
+    pub async fn create_multisig_transaction(&self, transaction_type: TransactionTypeBuilder, fee: FeeBuilder) -> Result<MultiSigTransaction, WalletError> {
+        trace!("create multisig transaction");
+        let keypair = self.get_keypair()?;
+        if self.is_locked() {
+            return Err(WalletError::Locked)
+        }
+        self.refresh_activity();
+
+        let setup = self.get_multisig_setup().await?
+            .ok_or(WalletError::MultiSigNotConfigured)?;
+
+        let mut storage = self.storage.write().await;
+        let mut state = self.create_builder_state(&mut storage, &transaction_type, &fee, None).await?;
+
+        let builder = TransactionBuilder::new(MULTISIG_TX_VERSION, self.public_key.clone(), transaction_type.clone(), fee)
+            .with_required_signatures(setup.threshold);
+
+        let size = builder.estimate_size();
+        if size > MAX_TRANSACTION_SIZE {
+            return Err(WalletError::TransactionTooBig(size, MAX_TRANSACTION_SIZE))
+        }
+        self.verify_fee_cap(&builder, &mut state)?;
+
+        let transaction = builder.build_unsigned(&mut state, &keypair)
+            .map_err(|e| WalletError::Any(e.into()))?;
+
+        Ok(MultiSigTransaction {
+            network: self.network,
+            setup,
+            tx_type: transaction_type,
+            transaction
+        })
+    }
+
+    // Sign a multisig transaction as one of its participants
+    // The returned signature must be sent back to the owner of the account
+    pub fn sign_multisig_transaction(&self, multisig: &MultiSigTransaction) -> Result<SignatureId, WalletError> {
+        trace!("sign multisig transaction");
+        let keypair = self.get_keypair()?;
+        if self.is_locked() {
+            return Err(WalletError::Locked)
+        }
+        self.refresh_activity();
+
+        if multisig.network != self.network {
+            return Err(WalletError::InvalidAddressNetwork(multisig.network, self.network))
+        }
+
+        let id = multisig.setup.participants.get_index_of(&self.public_key)
+            .ok_or(WalletError::NotMultiSigParticipant)?;
+
+        Ok(multisig.transaction.sign_multisig(&keypair, id as u8))
+    }
+
+    // Add the participants signatures to a multisig transaction and sign it
+    // Each signature is verified so an invalid one is detected before submitting it
+    pub fn finalize_multisig_transaction(&self, mut multisig: MultiSigTransaction, signatures: Vec<SignatureId>) -> Result<Transaction, WalletError> {
+        trace!("finalize multisig transaction");
+        let keypair = self.get_keypair()?;
+        if self.is_locked() {
+            return Err(WalletError::Locked)
+        }
+        self.refresh_activity();
+
+        if multisig.network != self.network {
+            return Err(WalletError::InvalidAddressNetwork(multisig.network, self.network))
+        }
+
+        if *multisig.transaction.get_source() != self.public_key {
+            return Err(WalletError::InvalidAddressParams)
+        }
+
+        let hash = multisig.transaction.get_multisig_hash();
+        for signature in signatures {
+            let participant = multisig.setup.participants.get_index(signature.id as usize)
+                .ok_or(WalletError::InvalidMultiSigSignature(signature.id))?
+                .decompress()
+                .map_err(|e| WalletError::Any(e.into()))?;
+
+            if !signature.signature.verify(hash.as_bytes(), &participant) {
+                return Err(WalletError::InvalidMultiSigSignature(signature.id))
+            }
+            multisig.transaction.add_multisig_signature(signature);
+        }
+
+        let count = multisig.transaction.get_multisig().as_ref().map(|m| m.len()).unwrap_or(0);
+        if count < multisig.setup.threshold as usize {
+            return Err(WalletError::MultiSigThreshold(count, multisig.setup.threshold))
+        }
+
+        Ok(multisig.transaction.sign(&keypair))
+    }
+
+    // Export the current state of the account to build the transaction on an offline wallet
+    // This doesn't require the private key and can be used by a watch-only wallet
+    pub async fn build_unsigned_transaction(&self, transaction_type: TransactionTypeBuilder, fee: FeeBuilder) -> Result<UnsignedTransaction, WalletError> {
+        trace!("build unsigned transaction");
+        let storage = self.storage.read().await;
+
+        let strategy = self.get_selection_strategy().await;
+        let mut balances = HashMap::new();
+        for asset in transaction_type.used_assets() {
+            // A watch-only wallet only knows the ciphertext of its balances
+            if self.watch_only {
+                if !storage.has_encrypted_balance_for(&asset)? {
+                    return Err(WalletError::BalanceNotFound(asset));
+                }
+
+                let ciphertext = storage.get_encrypted_balance_for(&asset)?;
+                balances.insert(asset, ciphertext);
+                continue;
+            }
+
+            if !storage.has_balance_for(&asset).await? {
+                return Err(WalletError::BalanceNotFound(asset));
+            }
+
+            if !strategy.allow_pending_balance(&asset) && storage.has_unconfirmed_balance_for(&asset).await {
+                return Err(WalletError::PendingBalance(asset, strategy.get_name().to_owned()));
+            }
+
+            let balance = storage.get_unconfirmed_balance_for(&asset).await?;
+            balances.insert(asset, balance.ciphertext.compress().into_owned());
+        }
+
+        let mut state = EstimateFeesState::new();
+        self.add_registered_keys_for_fees_estimation(&mut state, &fee, &transaction_type).await?;
+
+        Ok(UnsignedTransaction {
+            network: self.network,
+            source: self.public_key.clone(),
+            tx_type: transaction_type,
+            fee,
+            nonce: storage.get_nonce().unwrap_or(0),
+            reference: Reference {
+                topoheight: storage.get_synced_topoheight()?,
+                hash: storage.get_top_block_hash()?
+            },
+            balances,
+            registered_keys: state.get_registered_keys().clone()
+        })
+    }
+
+    // Build and sign a transaction exported by `build_unsigned_transaction`
+    // Storage is not used, so it can be done on a wallet that is never connected
+    pub async fn sign_unsigned_transaction(self: &Arc<Self>, unsigned: UnsignedTransaction) -> Result<Transaction, WalletError> {
+        trace!("sign unsigned transaction");
+        let keypair = self.get_keypair()?;
+        if self.is_locked() {
+            return Err(WalletError::Locked)
+        }
+        self.refresh_activity();
+
+        if unsigned.network != self.network {
+            return Err(WalletError::InvalidAddressNetwork(unsigned.network, self.network))
+        }
+
+        if unsigned.source != self.public_key {
+            return Err(WalletError::InvalidAddressParams)
+        }
+
+        let mut state = TransactionBuilderState::new(unsigned.network, unsigned.reference, unsigned.nonce);
+        for (asset, ciphertext) in unsigned.balances {
+            let decompressed = ciphertext.decompress().map_err(|e| WalletError::Any(e.into()))?;
+            let amount = Arc::clone(self).decrypt_ciphertext(decompressed).await?;
+            state.add_balance(asset, Balance::new(amount, CiphertextCache::Compressed(ciphertext)));
+        }
+        state.set_registered_keys(unsigned.registered_keys);
+
+        let builder = TransactionBuilder::new(get_tx_version(&unsigned.tx_type), self.public_key.clone(), unsigned.tx_type, unsigned.fee);
+        // The fee may be computed from data given by another wallet
+        self.verify_fee_cap(&builder, &mut state)?;
+        builder.build(&mut state, &keypair)
+            .map_err(|e| WalletError::Any(e.into()))
+    }
+
     // submit a transaction to the network through the connection to daemon
-    // It will increase the local nonce by 1 if the TX is accepted by the daemon
-    // returns error if the wallet is in offline mode or if the TX is rejected
+    // returns error if the wallet is in offline mode or if the TX is rejected by the daemon mempool
+    // This doesn't apply any change to the storage, see `submit_transaction_with_state`
     pub async fn submit_transaction(&self, transaction: &Transaction) -> Result<(), WalletError> {
         trace!("submit transaction");
         let network_handler = self.network_handler.lock().await;
         if let Some(network_handler) = network_handler.as_ref() {
-            network_handler.get_api().submit_transaction(transaction).await?;
+            network_handler.get_api().submit_transaction(transaction).await
+                .map_err(|e| WalletError::TransactionRejected(e.to_string()))?;
             Ok(())
         } else {
             Err(WalletError::NotOnlineMode)
         }
     }
 
+    // Ask the daemon to verify a transaction without broadcasting it
+    // The error returned is the one the daemon would give on submission
+    pub async fn simulate_transaction(&self, transaction: &Transaction) -> Result<(), WalletError> {
+        trace!("simulate transaction");
+        let network_handler = self.network_handler.lock().await;
+        if let Some(network_handler) = network_handler.as_ref() {
+            network_handler.get_api().validate_transaction(transaction).await
+                .map_err(|e| WalletError::TransactionRejected(e.to_string()))?;
+            Ok(())
+        } else {
+            Err(WalletError::NotOnlineMode)
+        }
+    }
+
+    // submit a transaction signed by another wallet (offline signing)
+    // Only the nonce is updated, balances will be synced once the transaction is executed
+    pub async fn submit_signed_transaction(&self, transaction: &Transaction) -> Result<(), WalletError> {
+        trace!("submit signed transaction");
+        if let Err(e) = self.submit_transaction(transaction).await {
+            // It may have been tracked by a previous submission
+            self.storage.write().await.remove_pending_transaction(&transaction.hash())?;
+            return Err(e)
+        }
+
+        if *transaction.get_source() == self.public_key {
+            let mut storage = self.storage.write().await;
+            if transaction.get_nonce() >= storage.get_nonce().unwrap_or(0) {
+                storage.set_nonce(transaction.get_nonce() + 1)?;
+            }
+
+            let pending = PendingTransaction::new(transaction.hash(), transaction.get_nonce(), transaction.get_fee(), get_current_time_in_seconds());
+            storage.add_pending_transaction(&pending)?;
+        }
+
+        Ok(())
+    }
+
+    // submit a transaction built with `create_transaction_with_storage`
+    // The builder state (nonce and unconfirmed balances) is applied to the storage only once the daemon accepted it
+    // So a rejected transaction doesn't leave the wallet with a wrong nonce
+    pub async fn submit_transaction_with_state(&self, storage: &mut EncryptedStorage, mut state: TransactionBuilderState, transaction: &Transaction) -> Result<(), WalletError> {
+        trace!("submit transaction with state");
+        if let Err(e) = self.submit_transaction(transaction).await {
+            // It may have been tracked by a previous submission
+            storage.remove_pending_transaction(&transaction.hash())?;
+            return Err(e)
+        }
+
+        // A replaced transaction won't be executed, drop its unconfirmed balances and pending entry
+        if transaction.get_nonce() < storage.get_nonce().unwrap_or(0) {
+            storage.delete_unconfirmed_balances().await?;
+            storage.delete_pending_transactions_with_nonce(transaction.get_nonce())?;
+        }
+        state.apply_changes(storage).await?;
+
+        // Track it until it is executed in a block
+        let pending = PendingTransaction::new(transaction.hash(), transaction.get_nonce(), transaction.get_fee(), get_current_time_in_seconds());
+        storage.add_pending_transaction(&pending)?;
+
+        Ok(())
+    }
+
+    // Cancel a pending transaction by replacing it with an empty burn using the same nonce and a higher fee
+    // It must be the only pending transaction, the daemon applies the same rule in its mempool
+    // Returns the replacement transaction, the link between both is saved in storage
+    pub async fn cancel_transaction(&self, hash: &Hash) -> Result<Transaction, WalletError> {
+        trace!("cancel transaction {}", hash);
+        let mut storage = self.storage.write().await;
+        let pending_transactions = storage.get_pending_transactions()?;
+        let pending = pending_transactions.iter()
+            .find(|tx| tx.get_hash() == hash)
+            .ok_or_else(|| WalletError::PendingTransactionNotFound(hash.clone()))?;
+
+        if pending_transactions.len() != 1 {
+            return Err(WalletError::PendingTransactionNotOnly(hash.clone()))
+        }
+
+        // The daemon only accepts the replacement with a strictly higher fee
+        let increase = (pending.get_fee().saturating_mul(REPLACEMENT_FEE_INCREASE_PERCENT) / 100).max(1);
+        let fee = pending.get_fee().saturating_add(increase);
+
+        let tx_type = TransactionTypeBuilder::Burn(BurnPayload {
+            asset: XELIS_ASSET,
+            amount: 0
+        });
+        let (state, transaction) = self.create_transaction_with_nonce(&mut storage, tx_type, FeeBuilder::Value(fee), Some(pending.get_nonce())).await?;
+        self.submit_transaction_with_state(&mut storage, state, &transaction).await?;
+
+        let replacement = transaction.hash();
+        storage.remove_pending_transaction(hash)?;
+        storage.set_transaction_replacement(hash, &replacement)?;
+        info!("Transaction {} replaced by {} with a fee of {}", hash, replacement, format_xelis(fee));
+
+        Ok(transaction)
+    }
+
+    // Execute all scheduled payments that are due
+    // Recurring payments are rescheduled, one-shot payments are removed once sent
+    // A payment that failed (locked wallet, not enough funds...) stays due and is retried later
+    pub async fn execute_scheduled_payments(&self) -> Result<(), WalletError> {
+        trace!("execute scheduled payments");
+        let mut storage = self.storage.write().await;
+        let now = get_current_time_in_seconds();
+        for mut payment in storage.get_scheduled_payments()? {
+            if !payment.is_due(now) {
+                // Payments are ordered by next execution
+                break;
+            }
+
+            let transfer = TransferBuilder {
+                destination: payment.get_destination().clone(),
+                amount: payment.get_amount(),
+                asset: payment.get_asset().clone(),
+                extra_data: None
+            };
+
+            let decimals = storage.get_asset_decimals(payment.get_asset()).unwrap_or(0);
+            let res = match self.create_transaction_with_storage(&mut storage, TransactionTypeBuilder::Transfers(vec![transfer]), FeeBuilder::default()).await {
+                Ok((state, tx)) => self.submit_transaction_with_state(&mut storage, state, &tx).await.map(|_| tx.hash()),
+                Err(e) => Err(e)
+            };
+
+            match res {
+                Ok(hash) => {
+                    info!("Scheduled payment #{} of {} to {} sent in transaction {}", payment.get_id(), format_coin(payment.get_amount(), decimals), payment.get_destination(), hash);
+                    if payment.reschedule(now) {
+                        storage.set_scheduled_payment(&payment)?;
+                    } else {
+                        storage.remove_scheduled_payment(payment.get_id())?;
+                    }
+                },
+                Err(e) => {
+                    warn!("Error while executing scheduled payment #{}: {}", payment.get_id(), e);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     // Search if possible all registered keys for the transaction type
     pub async fn add_registered_keys_for_fees_estimation(&self, state: &mut EstimateFeesState, fee: &FeeBuilder, transaction_type: &TransactionTypeBuilder) -> Result<(), WalletError> {
         trace!("add registered keys for fees estimation");
+        if !self.get_selection_strategy().await.check_registered_destinations() {
+            debug!("Selection strategy doesn't allow to check the destinations, all of them are considered as new accounts");
+            return Ok(())
+        }
+
         if let FeeBuilder::Multiplier(_) = fee {
             // To pay exact fees needed, we must verify that we don't have to pay more than needed
             let used_keys = transaction_type.used_keys();
@@ -754,7 +1990,7 @@ impl Wallet {
                     if network_handler.is_running().await {
                         trace!("Network handler is running, checking if keys are registered");
                         for key in used_keys {
-                            let addr = key.to_address(self.network.is_mainnet());
+                            let addr = key.to_address(self.network);
                             trace!("Checking if {} is registered in stable height", addr);
                             if network_handler.get_api().is_account_registered(&addr, true).await? {
                                 state.add_registered_key(addr.to_public_key());
@@ -768,6 +2004,24 @@ impl Wallet {
         Ok(())
     }
 
+    // Fees to use for the requested priority, based on the congestion of the daemon mempool
+    // The mempool is considered empty when offline
+    pub async fn get_fee_for_priority(&self, priority: FeePriority) -> Result<FeeBuilder, WalletError> {
+        trace!("get fee for priority {}", priority.as_str());
+        let mut mempool_size = 0;
+        if priority != FeePriority::Low {
+            let network_handler = self.network_handler.lock().await;
+            if let Some(network_handler) = network_handler.as_ref() {
+                if network_handler.is_running().await {
+                    mempool_size = network_handler.get_api().get_info().await?.mempool_size;
+                }
+            }
+        }
+
+        debug!("Using {} priority with {} transactions in mempool", priority.as_str(), mempool_size);
+        Ok(priority.to_fee_builder(mempool_size))
+    }
+
     // Estimate fees for a given transaction type
     // Estimated fees returned are the minimum required to be valid on chain
     pub async fn estimate_fees(&self, tx_type: TransactionTypeBuilder) -> Result<u64, WalletError> {
@@ -776,13 +2030,51 @@ impl Wallet {
 
         self.add_registered_keys_for_fees_estimation(&mut state, &FeeBuilder::default(), &tx_type).await?;
 
-        let builder = TransactionBuilder::new(0, self.public_key.clone(), tx_type, FeeBuilder::default());
+        let builder = TransactionBuilder::new(get_tx_version(&tx_type), self.public_key.clone(), tx_type, FeeBuilder::default());
         let estimated_fees = builder.estimate_fees(&mut state)
             .map_err(|e| WalletError::Any(e.into()))?;
 
         Ok(estimated_fees)
     }
 
+    // Compute the maximum amount that can be sent for an asset to a destination
+    // Fees are deducted from the amount if the asset is XELIS
+    // Returns the amount to send and the estimated fees
+    pub async fn estimate_transfer_all(&self, asset: &Hash, destination: Address) -> Result<(u64, u64), WalletError> {
+        trace!("estimate transfer all");
+        let (balance, native_balance) = {
+            let storage = self.storage.read().await;
+            let balance = storage.get_plaintext_balance_for(asset).await.unwrap_or(0);
+            let native_balance = if *asset == XELIS_ASSET {
+                balance
+            } else {
+                storage.get_plaintext_balance_for(&XELIS_ASSET).await.unwrap_or(0)
+            };
+            (balance, native_balance)
+        };
+
+        // Amount doesn't change the size of the transaction, so fees are the same
+        let transfer = TransferBuilder {
+            destination,
+            amount: balance,
+            asset: asset.clone(),
+            extra_data: None
+        };
+        let fees = self.estimate_fees(TransactionTypeBuilder::Transfers(vec![transfer])).await?;
+
+        if native_balance < fees {
+            return Err(WalletError::NotEnoughFundsForFee(fees, native_balance))
+        }
+
+        let amount = if *asset == XELIS_ASSET {
+            balance - fees
+        } else {
+            balance
+        };
+
+        Ok((amount, fees))
+    }
+
     // set wallet in online mode: start a communication task which will keep the wallet synced
     pub async fn set_online_mode(self: &Arc<Self>, daemon_address: &String) -> Result<(), WalletError> {
         trace!("Set online mode");
@@ -792,14 +2084,142 @@ impl Wallet {
         }
 
         // create the network handler
-        let network_handler = NetworkHandler::new(Arc::clone(&self), daemon_address).await?;
+        let tls = self.tls_config.lock().await.clone();
+        let proxy = self.proxy.lock().await.clone();
+        let network_handler = NetworkHandler::new(Arc::clone(&self), daemon_address, tls, proxy).await?;
         // start the task
         network_handler.start().await?;
         *self.network_handler.lock().await = Some(network_handler);
+        *self.active_daemon.lock().await = Some(daemon_address.clone());
+        self.sync_paused.store(false, Ordering::SeqCst);
 
         Ok(())
     }
 
+    // set wallet in online mode using the first reachable daemon of the list
+    // The others are kept as fallbacks if the connection is lost
+    // Returns the address of the daemon used
+    pub async fn set_online_mode_with_fallbacks(self: &Arc<Self>, daemon_addresses: Vec<String>) -> Result<String, WalletError> {
+        trace!("Set online mode with fallbacks");
+        if self.is_online().await {
+            return Err(WalletError::AlreadyOnlineMode)
+        }
+
+        *self.daemon_addresses.lock().await = daemon_addresses.clone();
+        let address = self.connect_to_first_available(&daemon_addresses).await?;
+        if daemon_addresses.len() > 1 {
+            self.start_failover_task().await;
+        }
+
+        Ok(address)
+    }
+
+    // Try each daemon in order until one works
+    async fn connect_to_first_available(self: &Arc<Self>, daemon_addresses: &[String]) -> Result<String, WalletError> {
+        let mut last_error = None;
+        for daemon_address in daemon_addresses {
+            debug!("Trying to connect to daemon {}", daemon_address);
+            match self.set_online_mode(daemon_address).await {
+                Ok(()) => return Ok(daemon_address.clone()),
+                Err(e) => {
+                    warn!("Couldn't connect to daemon {}: {}", daemon_address, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or(WalletError::NoDaemonAvailable))
+    }
+
+    // Watch the connection with the daemon to switch to the next one of the list
+    // when the connection is lost and not restored in time
+    async fn start_failover_task(self: &Arc<Self>) {
+        let mut task = self.failover_task.lock().await;
+        if let Some(task) = task.take() {
+            task.abort();
+        }
+
+        let mut receiver = self.subscribe_events().await;
+        let zelf = Arc::clone(self);
+        *task = Some(tokio::spawn(async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(Event::Offline) => {},
+                    Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return
+                }
+
+                debug!("Connection with daemon lost, waiting {} seconds before failover", DAEMON_FAILOVER_DELAY);
+                loop {
+                    sleep(Duration::from_secs(DAEMON_FAILOVER_DELAY)).await;
+                    // Connection may have been restored meanwhile
+                    // or closed on purpose by pausing the syncing
+                    if zelf.is_online().await || zelf.is_sync_paused() {
+                        break
+                    }
+
+                    if let Some(network_handler) = zelf.network_handler.lock().await.take() {
+                        if let Err(e) = network_handler.stop().await {
+                            debug!("Error while stopping network handler: {}", e);
+                        }
+                    }
+
+                    // Start by the daemons after the current one
+                    let mut addresses = zelf.daemon_addresses.lock().await.clone();
+                    let current = zelf.active_daemon.lock().await.clone();
+                    if let Some(index) = current.and_then(|current| addresses.iter().position(|a| *a == current)) {
+                        addresses.rotate_left(index + 1);
+                    }
+
+                    match zelf.connect_to_first_available(&addresses).await {
+                        Ok(address) => {
+                            info!("Switched to daemon {}", address);
+                            break
+                        },
+                        Err(e) => warn!("No daemon available ({}), retrying in {} seconds", e, DAEMON_FAILOVER_DELAY)
+                    }
+                }
+            }
+        }));
+    }
+
+    // Daemons configured by order of preference
+    pub async fn get_daemon_addresses(&self) -> Vec<String> {
+        self.daemon_addresses.lock().await.clone()
+    }
+
+    // Address of the daemon used, None if offline or using a shared API
+    pub async fn get_active_daemon(&self) -> Option<String> {
+        self.active_daemon.lock().await.clone()
+    }
+
+    // Set the TLS options (custom CA, pinned certificates) used by the next daemon connections
+    pub async fn set_tls_config(&self, tls: Option<TlsConfig>) {
+        *self.tls_config.lock().await = tls;
+    }
+
+    pub async fn get_tls_config(&self) -> Option<TlsConfig> {
+        self.tls_config.lock().await.clone()
+    }
+
+    // Set how the balances and nonce are consumed by the next transactions
+    pub async fn set_selection_strategy(&self, strategy: Arc<dyn SelectionStrategy>) {
+        *self.selection_strategy.lock().await = strategy;
+    }
+
+    pub async fn get_selection_strategy(&self) -> Arc<dyn SelectionStrategy> {
+        self.selection_strategy.lock().await.clone()
+    }
+
+    // Set the SOCKS5 proxy used by the next daemon connections
+    pub async fn set_proxy(&self, proxy: Option<ProxyConfig>) {
+        *self.proxy.lock().await = proxy;
+    }
+
+    pub async fn get_proxy(&self) -> Option<ProxyConfig> {
+        self.proxy.lock().await.clone()
+    }
+
     // set the wallet in online mode using a shared daemon API
     // this allows to share the same connection/Daemon API across several wallets to save resources
     pub async fn set_online_mode_with_api(self: &Arc<Self>, daemon_api: Arc<DaemonAPI>) -> Result<(), WalletError> {
@@ -814,6 +2234,7 @@ impl Wallet {
         // start the task
         network_handler.start().await?;
         *self.network_handler.lock().await = Some(network_handler);
+        *self.active_daemon.lock().await = None;
 
         Ok(())
     }
@@ -821,9 +2242,19 @@ impl Wallet {
     // set wallet in offline mode: stop communication task if exists
     pub async fn set_offline_mode(&self) -> Result<(), WalletError> {
         trace!("Set offline mode");
+        // Don't switch to another daemon anymore
+        if let Some(task) = self.failover_task.lock().await.take() {
+            task.abort();
+        }
+        *self.active_daemon.lock().await = None;
+
         let mut handler = self.network_handler.lock().await;
         if let Some(network_handler) = handler.take() {
-            network_handler.stop().await?;
+            // Already stopped if syncing was paused
+            let paused = self.sync_paused.swap(false, Ordering::SeqCst);
+            if !paused {
+                network_handler.stop().await?;
+            }
         } else {
             return Err(WalletError::NotOnlineMode)
         }
@@ -831,6 +2262,43 @@ impl Wallet {
         Ok(())
     }
 
+    // Pause the syncing: the connection with the daemon is closed but kept configured
+    // so it can be resumed later without reconfiguring the daemon
+    pub async fn pause_sync(&self) -> Result<(), WalletError> {
+        trace!("Pause sync");
+        let handler = self.network_handler.lock().await;
+        let network_handler = handler.as_ref().ok_or(WalletError::NotOnlineMode)?;
+        if self.sync_paused.swap(true, Ordering::SeqCst) {
+            return Err(WalletError::SyncAlreadyPaused)
+        }
+
+        if let Err(e) = network_handler.stop().await {
+            self.sync_paused.store(false, Ordering::SeqCst);
+            return Err(e.into())
+        }
+
+        Ok(())
+    }
+
+    // Reconnect to the daemon and sync again from the last synced topoheight
+    pub async fn resume_sync(&self) -> Result<(), WalletError> {
+        trace!("Resume sync");
+        let handler = self.network_handler.lock().await;
+        let network_handler = handler.as_ref().ok_or(WalletError::NotOnlineMode)?;
+        if !self.is_sync_paused() {
+            return Err(WalletError::SyncNotPaused)
+        }
+
+        network_handler.start().await?;
+        self.sync_paused.store(false, Ordering::SeqCst);
+
+        Ok(())
+    }
+
+    pub fn is_sync_paused(&self) -> bool {
+        self.sync_paused.load(Ordering::SeqCst)
+    }
+
     // rescan the wallet from the given topoheight
     // that will delete all transactions above the given topoheight and all balances
     // then it will re-fetch all transactions and balances from daemon
@@ -857,6 +2325,7 @@ impl Wallet {
                 // balances will be re-fetched from daemon
                 storage.delete_balances().await?;
                 storage.delete_assets().await?;
+                self.clear_balances_cache().await;
 
                 debug!("Retrieve current wallet nonce");
                 let nonce_result = network_handler.get_api()
@@ -865,6 +2334,8 @@ impl Wallet {
                     .map(|v| v.version.get_nonce()).unwrap_or(0);
 
                 storage.set_nonce(nonce_result)?;
+                // pending transactions below the on-chain nonce were executed or dropped
+                storage.delete_pending_transactions_below_nonce(nonce_result)?;
 
                 if topoheight == 0 {
                     debug!("Deleting all transactions for full rescan");
@@ -873,6 +2344,9 @@ impl Wallet {
                     debug!("Deleting transactions above {} for partial rescan", topoheight);
                     storage.delete_transactions_above_topoheight(topoheight)?;
                 }
+
+                // changes above are not valid anymore and will be found again during sync
+                storage.delete_changes_above_topoheight(topoheight)?;
             }
             debug!("Starting again network handler");
             network_handler.start().await.context("Error while restarting network handler")?;
@@ -899,8 +2373,74 @@ impl Wallet {
     }
 
     // Create a signature of the given data
-    pub fn sign_data(&self, data: &[u8]) -> Signature {
-        self.keypair.sign(data)
+    pub fn sign_data(&self, data: &[u8]) -> Result<Signature, WalletError> {
+        Ok(self.get_keypair()?.sign(data))
+    }
+
+    // Get the keypair of the wallet to sign, not available in watch-only and view-only modes
+    fn get_keypair(&self) -> Result<Arc<KeyPair>, WalletError> {
+        if self.view_only {
+            return Err(WalletError::ViewOnly)
+        }
+
+        self.get_view_keypair()
+    }
+
+    // Get the keypair of the wallet to decrypt, also available in view-only mode
+    fn get_view_keypair(&self) -> Result<Arc<KeyPair>, WalletError> {
+        if self.watch_only {
+            return Err(WalletError::WatchOnly)
+        }
+
+        self.keypair.read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+            .ok_or(WalletError::Locked)
+    }
+
+    // Check if the wallet only has the public key
+    // Such wallet can't spend funds nor decrypt amounts
+    pub fn is_watch_only(&self) -> bool {
+        self.watch_only
+    }
+
+    // Check if the wallet was created as view-only from a private key
+    // Such wallet decrypts its balances but refuses to sign
+    pub fn is_view_only(&self) -> bool {
+        self.view_only
+    }
+
+    // Export the private key of the account, in hex
+    // Balances are encrypted to it, so a view-only wallet needs it even if it refuses to sign
+    // Anyone holding this key can spend the funds
+    pub fn export_private_key(&self) -> Result<String, WalletError> {
+        trace!("export private key");
+        if self.is_locked() {
+            return Err(WalletError::Locked)
+        }
+        self.refresh_activity();
+
+        Ok(hex::encode(self.get_view_keypair()?.get_private_key().to_bytes()))
+    }
+
+    // Export the private key of this account to be encrypted on a paper wallet
+    pub fn export_paper_wallet(&self) -> Result<PaperWallet, WalletError> {
+        trace!("export paper wallet");
+        if self.is_locked() {
+            return Err(WalletError::Locked)
+        }
+        self.refresh_activity();
+
+        Ok(PaperWallet::new(self.network, self.get_keypair()?.get_private_key().clone()))
+    }
+
+    // Verify a signature of the given data against the public key of the signer
+    // An invalid public key can't produce a valid signature
+    pub fn verify_data(&self, data: &[u8], signature: &Signature, key: &PublicKey) -> bool {
+        match key.decompress() {
+            Ok(key) => signature.verify(data, &key),
+            Err(_) => false
+        }
     }
 
     // Get the public key of the wallet
@@ -910,17 +2450,168 @@ impl Wallet {
 
     // Get the address of the wallet using its network used
     pub fn get_address(&self) -> Address {
-        self.keypair.get_public_key().to_address(self.get_network().is_mainnet())
+        self.decompressed_public_key.to_address(*self.get_network())
     }
 
     // Get the address with integrated data and using its network used
     pub fn get_address_with(&self, data: DataElement) -> Address {
-        self.keypair.get_public_key().to_address_with(self.get_network().is_mainnet(), data)
+        self.decompressed_public_key.to_address_with(*self.get_network(), data)
+    }
+
+    // Derive a new receive subaddress from the private key of the account
+    // Its index is used for the derivation so it can be recovered from the same seed
+    // Each subaddress is a distinct key on chain: funds sent to it are kept on its own balance
+    // until they are sent to the main address using `sweep_subaddress`
+    pub async fn create_subaddress(&self, label: String) -> Result<(u32, Address), Error> {
+        trace!("create subaddress {}", label);
+        // Make sure we can derive it before registering it
+        self.get_view_keypair()?;
+        let index = {
+            let mut storage = self.storage.write().await;
+            storage.add_subaddress(&label)?
+        };
+
+        let keypair = self.derive_subaddress_keypair(index)?;
+        Ok((index, keypair.get_public_key().to_address(*self.get_network())))
+    }
+
+    // Retrieve all the subaddresses derived with their index and label
+    pub async fn get_subaddresses(&self) -> Result<Vec<(u32, String, Address)>, Error> {
+        trace!("get subaddresses");
+        let network = *self.get_network();
+        let storage = self.storage.read().await;
+        let mut subaddresses = Vec::new();
+        for (index, label) in storage.get_subaddresses()? {
+            let keypair = self.derive_subaddress_keypair(index)?;
+            subaddresses.push((index, label, keypair.get_public_key().to_address(network)));
+        }
+
+        Ok(subaddresses)
+    }
+
+    // Derive the keys of all the subaddresses to recognize the funds sent to them
+    // A watch-only wallet can't derive them and has none
+    pub(crate) async fn get_subaddress_keys(&self) -> Result<Vec<(u32, KeyPair)>, Error> {
+        if self.is_watch_only() {
+            return Ok(Vec::new())
+        }
+
+        let storage = self.storage.read().await;
+        let mut keys = Vec::new();
+        for (index, _) in storage.get_subaddresses()? {
+            keys.push((index, self.derive_subaddress_keypair(index)?));
+        }
+
+        Ok(keys)
+    }
+
+    fn derive_subaddress_keypair(&self, index: u32) -> Result<KeyPair, WalletError> {
+        let private_key = self.get_view_keypair()?.get_private_key().derive_subaddress(index);
+        Ok(KeyPair::from_private_key(private_key))
+    }
+
+    // Decrypt the balance of a subaddress for an asset using the daemon state
+    // Returns None if the subaddress never received this asset
+    async fn get_subaddress_balance(self: &Arc<Self>, api: &DaemonAPI, keypair: &KeyPair, asset: &Hash) -> Result<Option<Balance>, WalletError> {
+        let address = keypair.get_public_key().to_address(self.network);
+        let Ok(result) = api.get_balance(&address, asset).await else {
+            return Ok(None)
+        };
+
+        let mut ciphertext = result.version.take_balance();
+        let decompressed = ciphertext.decompressed().map_err(|e| WalletError::Any(e.into()))?.clone();
+        let amount = Arc::clone(self).decrypt_ciphertext_with(keypair.get_private_key().clone(), decompressed).await?;
+        Ok(Some(Balance::new(amount, ciphertext)))
+    }
+
+    // Send the whole balance of an asset received on a subaddress to the main address
+    // The transaction is signed using the subaddress key, its fees are paid from the XELIS balance of the subaddress
+    // Returns the transaction once accepted by the daemon, the main address sees it as an incoming transfer
+    pub async fn sweep_subaddress(self: &Arc<Self>, index: u32, asset: Hash) -> Result<Transaction, WalletError> {
+        trace!("sweep subaddress #{} for asset {}", index, asset);
+        // A view-only wallet refuses to sign for its subaddresses too
+        self.get_keypair()?;
+        if self.is_locked() {
+            return Err(WalletError::Locked)
+        }
+        self.refresh_activity();
+
+        if !self.storage.read().await.get_subaddresses()?.iter().any(|(i, _)| *i == index) {
+            return Err(WalletError::SubaddressNotFound(index))
+        }
+
+        let keypair = self.derive_subaddress_keypair(index)?;
+        let address = keypair.get_public_key().to_address(self.network);
+        // Don't keep the lock while requesting the daemon, the submission needs it
+        let network_handler = self.network_handler.lock().await.clone()
+            .ok_or(WalletError::NotOnlineMode)?;
+        let api = network_handler.get_api();
+
+        let balance = self.get_subaddress_balance(api, &keypair, &asset).await?
+            .filter(|balance| balance.amount > 0)
+            .ok_or_else(|| WalletError::SubaddressEmpty(index, asset.clone()))?;
+        let nonce = api.get_nonce(&address).await
+            .map_err(|e| WalletError::Any(e.into()))?
+            .version.get_nonce();
+        let info = api.get_info().await.map_err(|e| WalletError::Any(e.into()))?;
+
+        let mut state = TransactionBuilderState::new(self.network, Reference { topoheight: info.topoheight, hash: info.top_block_hash }, nonce);
+        if api.is_account_registered(&self.get_address(), false).await.map_err(|e| WalletError::Any(e.into()))? {
+            state.add_registered_key(self.public_key.clone());
+        }
+
+        let source = keypair.get_public_key().compress();
+        let transfer = |amount| TransactionTypeBuilder::Transfers(vec![TransferBuilder {
+            destination: self.get_address(),
+            amount,
+            asset: asset.clone(),
+            extra_data: None
+        }]);
+
+        // The fee doesn't depend on the amount sent, estimate it with the full balance
+        let fee = TransactionBuilder::new(0, source.clone(), transfer(balance.amount), FeeBuilder::default())
+            .estimate_fees(&mut state)
+            .map_err(|e| WalletError::Any(e.into()))?;
+
+        let amount = if asset == XELIS_ASSET {
+            if balance.amount <= fee {
+                return Err(WalletError::NotEnoughFundsForFee(fee, balance.amount))
+            }
+            balance.amount - fee
+        } else {
+            let fee_balance = self.get_subaddress_balance(api, &keypair, &XELIS_ASSET).await?;
+            let available = fee_balance.as_ref().map_or(0, |balance| balance.amount);
+            if available < fee {
+                return Err(WalletError::NotEnoughFundsForFee(fee, available))
+            }
+            if let Some(fee_balance) = fee_balance {
+                state.add_balance(XELIS_ASSET, fee_balance);
+            }
+            balance.amount
+        };
+        state.add_balance(asset.clone(), balance);
+
+        let builder = TransactionBuilder::new(0, source, transfer(amount), FeeBuilder::Value(fee));
+        self.verify_fee_cap(&builder, &mut state)?;
+        let transaction = builder.build(&mut state, &keypair)
+            .map_err(|e| WalletError::Any(e.into()))?;
+
+        self.submit_transaction(&transaction).await?;
+        info!("Asset {} of subaddress #{} swept to the main address in transaction {}", asset, index, transaction.hash());
+
+        Ok(transaction)
     }
 
     // Returns the seed using the language index provided
+    // BIP39 mnemonic of the wallet, only available if it was created from one
+    pub async fn get_mnemonic(&self) -> Result<Option<String>, Error> {
+        self.get_keypair()?;
+        let storage = self.storage.read().await;
+        storage.get_mnemonic()
+    }
+
     pub fn get_seed(&self, language_index: usize) -> Result<String, Error> {
-        let words = mnemonics::key_to_words(self.keypair.get_private_key(), language_index)?;
+        let words = mnemonics::key_to_words(self.get_keypair()?.get_private_key(), language_index)?;
         Ok(words.join(" "))
     }
 
@@ -931,6 +2622,124 @@ impl Wallet {
         storage.get_nonce().unwrap_or(0)
     }
 
+    // Get the plaintext balance of an asset, 0 if the wallet has none
+    // It doesn't wait on the storage lock once the cache is loaded
+    pub async fn get_balance(&self, asset: &Hash) -> Result<u64, Error> {
+        Ok(self.get_balances().await?.get(asset).copied().unwrap_or(0))
+    }
+
+    // Get the plaintext balances of all assets tracked
+    pub async fn get_balances(&self) -> Result<HashMap<Hash, u64>, Error> {
+        {
+            let cache = self.balances_cache.read().await;
+            if let Some(balances) = cache.as_ref() {
+                return Ok(balances.clone())
+            }
+        }
+
+        // Storage lock is always taken before the cache one to not deadlock with the network handler
+        let storage = self.storage.read().await;
+        let mut cache = self.balances_cache.write().await;
+        // Another task may have loaded it while we were waiting for the lock
+        if let Some(balances) = cache.as_ref() {
+            return Ok(balances.clone())
+        }
+
+        trace!("loading balances cache");
+        let mut balances = HashMap::new();
+        for asset in storage.get_assets().await? {
+            if storage.has_balance_for(&asset).await? {
+                let balance = storage.get_plaintext_balance_for(&asset).await?;
+                balances.insert(asset, balance);
+            }
+        }
+        *cache = Some(balances.clone());
+
+        Ok(balances)
+    }
+
+    // Apply all the balance changes at once, they must be saved in storage first
+    // The storage write lock must still be held to keep both in sync
+    // Nothing is done if the cache is not loaded yet, it will be read from storage
+    pub(crate) async fn update_balances_cache(&self, changes: impl IntoIterator<Item = (Hash, u64)>) {
+        let mut cache = self.balances_cache.write().await;
+        if let Some(balances) = cache.as_mut() {
+            balances.extend(changes);
+        }
+    }
+
+    // Must be called when balances are deleted from storage, while holding its lock
+    pub(crate) async fn clear_balances_cache(&self) {
+        trace!("clear balances cache");
+        *self.balances_cache.write().await = None;
+    }
+
+    // Verify the storage entries, keys and balances of the wallet
+    pub async fn verify(&self) -> Result<VerificationReport, Error> {
+        trace!("verify wallet");
+        let storage = self.storage.read().await;
+        let (checked_entries, corrupted_entries) = storage.verify_entries()?;
+        let mut report = VerificationReport {
+            checked_entries,
+            corrupted_entries,
+            ..Default::default()
+        };
+
+        if !storage.is_watch_only()? {
+            let public_key = KeyPair::from_private_key(storage.get_private_key()?).get_public_key().compress();
+            report.key_mismatch = public_key != self.public_key
+                || storage.get_public_key().map(|key| key != public_key).unwrap_or(false);
+
+            // Replay the history to compute the expected balances
+            let mut computed: HashMap<Hash, i128> = HashMap::new();
+            for entry in storage.get_transactions()? {
+                match entry.get_entry() {
+                    EntryData::Coinbase { reward } => {
+                        *computed.entry(XELIS_ASSET).or_default() += *reward as i128;
+                    },
+                    EntryData::Burn { asset, amount } => {
+                        *computed.entry(asset.clone()).or_default() -= *amount as i128;
+                    },
+                    EntryData::Incoming { transfers, .. } => {
+                        for transfer in transfers {
+                            *computed.entry(transfer.get_asset().clone()).or_default() += transfer.get_amount() as i128;
+                        }
+                    },
+                    EntryData::Outgoing { transfers, fee, .. } => {
+                        for transfer in transfers {
+                            *computed.entry(transfer.get_asset().clone()).or_default() -= transfer.get_amount() as i128;
+                        }
+                        *computed.entry(XELIS_ASSET).or_default() -= *fee as i128;
+                    }
+                }
+            }
+
+            for asset in storage.get_assets().await? {
+                let stored = if storage.has_balance_for(&asset).await? {
+                    storage.get_plaintext_balance_for(&asset).await?
+                } else {
+                    0
+                };
+                let expected = computed.get(&asset).copied().unwrap_or(0);
+                if stored as i128 != expected {
+                    report.balance_mismatches.push((asset, stored, expected));
+                }
+            }
+        }
+
+        // Storage lock is held, so the cache can't be updated meanwhile
+        if let Some(balances) = self.balances_cache.read().await.as_ref() {
+            for (asset, balance) in balances {
+                let stored = storage.get_plaintext_balance_for(asset).await.unwrap_or(0);
+                if stored != *balance {
+                    report.cache_mismatches.push(asset.clone());
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
     // Encrypted storage of the wallet
     pub fn get_storage(&self) -> &RwLock<EncryptedStorage> {
         &self.storage
@@ -990,7 +2799,17 @@ impl XSWDPermissionHandler for Arc<Wallet> {
     }
 
     async fn get_public_key(&self) -> Result<&DecompressedPublicKey, Error> {
-        Ok(self.keypair.get_public_key())
+        Ok(&self.decompressed_public_key)
+    }
+}
+
+#[cfg(feature = "api_server")]
+#[async_trait]
+impl RPCTokenHandler for Arc<Wallet> {
+    async fn get_token_scope(&self, secret: &str) -> Result<Option<(String, TokenScope)>, Error> {
+        let storage = self.storage.read().await;
+        let token = storage.find_api_token(secret)?;
+        Ok(token.map(|(name, token)| (name, token.get_scope())))
     }
 }
 
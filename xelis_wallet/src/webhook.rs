@@ -0,0 +1,148 @@
+use std::{sync::Arc, time::Duration};
+use log::{debug, error, trace, warn};
+use reqwest::Client as HttpClient;
+use serde_json::json;
+use tokio::{sync::broadcast::error::RecvError, time::sleep};
+use xelis_common::{
+    api::wallet::{EntryType, TransactionEntry},
+    serializer::Serializer
+};
+use crate::{
+    config::{WEBHOOK_MAX_RETRIES, WEBHOOK_RETRY_DELAY, WEBHOOK_TIMEOUT},
+    wallet::{Event, Wallet}
+};
+
+// Header containing the address of the wallet sending the notification
+pub const WEBHOOK_ADDRESS_HEADER: &str = "X-Xelis-Address";
+// Header containing the hex signature of the body, made with the wallet key
+pub const WEBHOOK_SIGNATURE_HEADER: &str = "X-Xelis-Signature";
+
+// POST a signed JSON payload to every webhook registered in the wallet
+// for each confirmed incoming or outgoing transfer
+// Transactions found during a sync are sent once it is done,
+// and each transaction is sent only once per topoheight
+// It stops when the wallet is closed
+pub async fn run_webhooks(wallet: Arc<Wallet>) {
+    let client = match HttpClient::builder().timeout(Duration::from_secs(WEBHOOK_TIMEOUT)).build() {
+        Ok(client) => client,
+        Err(e) => {
+            error!("Error while creating webhooks HTTP client: {}", e);
+            return;
+        }
+    };
+
+    let mut receiver = wallet.subscribe_events().await;
+    // Transactions waiting for the end of the sync
+    let mut queue = Vec::new();
+    loop {
+        match receiver.recv().await {
+            Ok(Event::NewTransaction(entry)) => queue.push(entry),
+            // Sent once the wallet is synced with the daemon
+            Ok(Event::NewTopoHeight { .. }) => {
+                for entry in queue.drain(..) {
+                    notify_webhooks(&wallet, &client, entry).await;
+                }
+            },
+            Ok(_) => {},
+            Err(RecvError::Lagged(skipped)) => warn!("Webhooks missed {} wallet events", skipped),
+            Err(RecvError::Closed) => break
+        }
+    }
+}
+
+async fn notify_webhooks(wallet: &Wallet, client: &HttpClient, entry: TransactionEntry) {
+    let event = match entry.entry {
+        EntryType::Incoming { .. } => "incoming",
+        EntryType::Outgoing { .. } => "outgoing",
+        _ => return
+    };
+
+    let urls = {
+        let mut storage = wallet.get_storage().write().await;
+        match storage.is_transaction_notified(&entry.hash, entry.topoheight) {
+            Ok(false) => {},
+            Ok(true) => {
+                trace!("webhooks already notified of {} at topoheight {}", entry.hash, entry.topoheight);
+                return;
+            },
+            Err(e) => {
+                error!("Error while checking if {} was notified: {}", entry.hash, e);
+                return;
+            }
+        }
+
+        let urls = match storage.get_webhooks() {
+            Ok(webhooks) => webhooks.into_iter().map(|(url, _)| url).collect::<Vec<_>>(),
+            Err(e) => {
+                error!("Error while retrieving webhooks: {}", e);
+                return;
+            }
+        };
+
+        if urls.is_empty() {
+            return;
+        }
+
+        if let Err(e) = storage.set_transaction_notified(&entry.hash, entry.topoheight) {
+            error!("Error while marking {} as notified: {}", entry.hash, e);
+        }
+        urls
+    };
+
+    let address = wallet.get_address().to_string();
+    let body = json!({
+        "event": event,
+        "address": address,
+        "transaction": entry
+    }).to_string();
+
+    // Watch-only wallets can't sign, the payload is sent without signature
+    let signature = match wallet.sign_data(body.as_bytes()) {
+        Ok(signature) => Some(signature.to_hex()),
+        Err(e) => {
+            debug!("Webhook payload for {} is not signed: {}", entry.hash, e);
+            None
+        }
+    };
+
+    for url in urls {
+        let client = client.clone();
+        let address = address.clone();
+        let body = body.clone();
+        let signature = signature.clone();
+        tokio::spawn(async move {
+            deliver(&client, &url, &address, body, signature.as_deref()).await;
+        });
+    }
+}
+
+// Send the payload to the webhook, retrying with an increasing delay on failure
+async fn deliver(client: &HttpClient, url: &str, address: &str, body: String, signature: Option<&str>) {
+    let mut delay = WEBHOOK_RETRY_DELAY;
+    for attempt in 1..=WEBHOOK_MAX_RETRIES {
+        trace!("sending webhook to {} (attempt {})", url, attempt);
+        let mut request = client.post(url)
+            .header("Content-Type", "application/json")
+            .header(WEBHOOK_ADDRESS_HEADER, address)
+            .body(body.clone());
+
+        if let Some(signature) = signature {
+            request = request.header(WEBHOOK_SIGNATURE_HEADER, signature);
+        }
+
+        match request.send().await.and_then(|response| response.error_for_status()) {
+            Ok(_) => {
+                debug!("Webhook {} notified", url);
+                return;
+            },
+            Err(e) => warn!("Error while notifying webhook {} (attempt {}/{}): {}", url, attempt, WEBHOOK_MAX_RETRIES, e)
+        }
+
+        if attempt < WEBHOOK_MAX_RETRIES {
+            sleep(Duration::from_secs(delay)).await;
+            delay *= 2;
+        }
+    }
+
+    error!("Webhook {} couldn't be notified after {} attempts", url, WEBHOOK_MAX_RETRIES);
+}